@@ -1,8 +1,9 @@
-mod parameters;
+pub mod parameters;
 
 use std::borrow::{BorrowMut, Borrow};
 use std::cell::RefCell;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use std::{io::Cursor, collections::HashMap};
 
 use futures_channel::mpsc::{UnboundedSender};
@@ -68,6 +69,66 @@ impl Client {
     pub async fn execute_cypher_request(&mut self, query: &str) -> Result<Document, Canceled> {
         self.execute_cypher_request_with_parameters(query, Parameters::new()).await
     }
+
+    pub async fn execute_cypher_request_page(&mut self, query: &str, page_size: i64, cursor: Option<i64>) -> Result<Document, Canceled> {
+        let uuid =  Uuid::new_v4();
+        let (tx, rx) = futures_channel::oneshot::channel::<Document>();
+        self.map_rx_channels.lock().unwrap().insert(uuid.to_string(), tx);
+        let mut doc = doc!{
+            "request_id": uuid.to_string(),
+            "query" : query,
+            "page_size": page_size,
+        };
+        if let Some(c) = cursor {
+            doc.insert("cursor", c);
+        }
+        tokio::spawn(send_document(self.request_tx.clone(), doc));
+        rx.await
+    }
+
+    /// The query fails with a timeout error once it runs longer than the timeout.
+    pub async fn execute_cypher_request_with_timeout(&mut self, query: &str, timeout: Duration) -> Result<Document, Canceled> {
+        let uuid =  Uuid::new_v4();
+        let (tx, rx) = futures_channel::oneshot::channel::<Document>();
+        self.map_rx_channels.lock().unwrap().insert(uuid.to_string(), tx);
+        let doc = doc!{
+            "request_id": uuid.to_string(),
+            "query" : query,
+            "timeout": timeout.as_millis() as i64,
+        };
+        tokio::spawn(send_document(self.request_tx.clone(), doc));
+        rx.await
+    }
+
+    /// Timeout of the following requests of this connection sent without their own one, a zero timeout removes it.
+    pub async fn set_session_timeout(&mut self, timeout: Duration) -> Result<Document, Canceled> {
+        let uuid =  Uuid::new_v4();
+        let (tx, rx) = futures_channel::oneshot::channel::<Document>();
+        self.map_rx_channels.lock().unwrap().insert(uuid.to_string(), tx);
+        let doc = doc!{
+            "request_id": uuid.to_string(),
+            "session_timeout": timeout.as_millis() as i64,
+        };
+        tokio::spawn(send_document(self.request_tx.clone(), doc));
+        rx.await
+    }
+
+    /// Parameters used by all the following requests of this connection unless they override them.
+    pub async fn set_session_parameters(&mut self, params: Parameters) -> Result<Document, Canceled> {
+        let uuid =  Uuid::new_v4();
+        let (tx, rx) = futures_channel::oneshot::channel::<Document>();
+        self.map_rx_channels.lock().unwrap().insert(uuid.to_string(), tx);
+        let mut session_parameters = Document::new();
+        for (name, value) in params {
+            session_parameters.extend(extract_value(name, value));
+        }
+        let doc = doc!{
+            "request_id": uuid.to_string(),
+            "session_parameters": session_parameters,
+        };
+        tokio::spawn(send_document(self.request_tx.clone(), doc));
+        rx.await
+    }
 }
 
 fn extract_value(name: String, value: PropertyValue) -> Document {
@@ -99,12 +160,16 @@ fn build_parameters(params: Parameters) -> Vec<Document> {
 }
 
 async fn send_request(tx: futures_channel::mpsc::UnboundedSender<Message>, id: String, query: String, params: Parameters) -> Option<()> {
-    let mut msg = "!application/openCypher".as_bytes().to_vec();
     let doc = doc!{
         "request_id": String::from(id),
         "query" : query,
         "parameters": build_parameters(params),
     };
+    send_document(tx, doc).await
+}
+
+async fn send_document(tx: futures_channel::mpsc::UnboundedSender<Message>, doc: Document) -> Option<()> {
+    let mut msg = "!application/openCypher".as_bytes().to_vec();
     doc.to_writer(&mut msg).ok()?;
     tx.unbounded_send(Message::binary(msg)).unwrap();
     Some(())
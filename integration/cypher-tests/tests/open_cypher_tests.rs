@@ -23,7 +23,9 @@ use zawgl_core::{model::init::InitContext, test_utils::build_dir_path_and_rm_old
 use simple_logger::SimpleLogger;
 use log::*;
 use zawgl_client::Client;
+use zawgl_client::parameters::{Parameters, PropertyValue};
 use std::future::Future;
+use std::time::Duration;
 
 #[tokio::test]
 async fn test_cypher_0() {
@@ -35,6 +37,35 @@ async fn test_cypher_0() {
     run_test("test_mutliple_match", 8187, test_mutliple_match).await;
     run_test("test_cypher_self_relationship", 8189, test_cypher_self_relationship).await;
     run_test("test_cypher_self_relationship_2", 8190, test_cypher_self_relationship_2).await;
+    run_test("test_optional_match", 8191, test_optional_match).await;
+    run_test("test_aggregation", 8192, test_aggregation).await;
+    run_test("test_where_comparisons", 8193, test_where_comparisons).await;
+    run_test("test_unique_constraint", 8194, test_unique_constraint).await;
+    run_test("test_paged_results", 8195, test_paged_results).await;
+    run_test("test_explain_profile", 8196, test_explain_profile).await;
+    run_test("test_temporal_values", 8197, test_temporal_values).await;
+    run_test("test_tabular_rows", 8198, test_tabular_rows).await;
+    run_test("test_return_expressions", 8199, test_return_expressions).await;
+    run_test("test_return_distinct", 8200, test_return_distinct).await;
+    run_test("test_unwind", 8201, test_unwind).await;
+    run_test("test_call_procedure", 8202, test_call_procedure).await;
+    run_test("test_schema_procedures", 8203, test_schema_procedures).await;
+    run_test("test_session_parameters", 8204, test_session_parameters).await;
+    run_test("test_count_statistics", 8205, test_count_statistics).await;
+    run_test("test_relationship_type_union", 8206, test_relationship_type_union).await;
+    run_test("test_query_timeout", 8207, test_query_timeout).await;
+    run_test("test_element_functions", 8208, test_element_functions).await;
+    run_test("test_string_functions", 8209, test_string_functions).await;
+    run_test("test_relationship_id", 8210, test_relationship_id).await;
+    run_test("test_element_id", 8211, test_element_id).await;
+    run_test("test_set_remove_labels", 8212, test_set_remove_labels).await;
+    run_test("test_remove_properties", 8213, test_remove_properties).await;
+    run_test("test_union", 8214, test_union).await;
+    run_test("test_foreach", 8215, test_foreach).await;
+    run_test("test_pattern_predicates", 8216, test_pattern_predicates).await;
+    run_test("test_exists", 8217, test_exists).await;
+    run_test("test_property_distribution", 8218, test_property_distribution).await;
+    run_test("test_syntax_error", 8219, test_syntax_error).await;
 }
 
 async fn run_test<F, T>(db_name: &str, port: i32, lambda: F) where F : FnOnce(Client) -> T, T : Future<Output = ()> + Send {
@@ -267,4 +298,799 @@ async fn test_mutliple_match(mut client: Client) {
     } else {
         assert!(false, "no response")
     }
-}
\ No newline at end of file
+}
+
+async fn test_optional_match(mut client: Client) {
+    let r = client.execute_cypher_request("create (p:Person)-[o:OWNS]->(c:Car) return p, o, c").await;
+    if let Ok(d) = r {
+        debug!("{}", d.to_string());
+    } else {
+        assert!(false, "no response")
+    }
+    let r = client.execute_cypher_request("create (p:Person) return p").await;
+    if let Ok(d) = r {
+        debug!("{}", d.to_string());
+    } else {
+        assert!(false, "no response")
+    }
+    let r = client.execute_cypher_request("match (a:Person) optional match (a)-[r:OWNS]->(c:Car) return a, r, c").await;
+    if let Ok(d) = r {
+        debug!("{}", d.to_string());
+        let res = d.get_document("result").expect("result");
+        let graphs = res.get_array("graphs").expect("graphs");
+        assert_eq!(graphs.len(), 2);
+        let mut nb_owners = 0;
+        for g in graphs {
+            let graph = g.as_document().expect("a graph");
+            let nodes = graph.get_array("nodes").expect("nodes");
+            let relationships = graph.get_array("relationships").expect("relationships");
+            if relationships.len() == 1 {
+                assert_eq!(nodes.len(), 2);
+                nb_owners += 1;
+            } else {
+                assert_eq!(nodes.len(), 1);
+            }
+        }
+        assert_eq!(nb_owners, 1);
+    } else {
+        assert!(false, "no response")
+    }
+}
+
+async fn test_aggregation(mut client: Client) {
+    for (city, age) in [("Paris", 30), ("Lyon", 40), ("Paris", 50)] {
+        let r = client.execute_cypher_request(&format!("create (p:Person {{city: '{}', age: {}}}) return p", city, age)).await;
+        if let Ok(d) = r {
+            debug!("{}", d.to_string());
+        } else {
+            assert!(false, "no response")
+        }
+    }
+    let r = client.execute_cypher_request("match (p:Person) return p.city, count(p), sum(p.age)").await;
+    if let Ok(d) = r {
+        debug!("{}", d.to_string());
+        let res = d.get_document("result").expect("result");
+        let columns = res.get_array("columns").expect("columns");
+        assert_eq!(columns.len(), 3);
+        let rows = res.get_array("rows").expect("rows");
+        assert_eq!(rows.len(), 2);
+        for row in rows {
+            let values = row.as_array().expect("a row");
+            match values[0].as_str() {
+                Some("Paris") => {
+                    assert_eq!(values[1].as_i64(), Some(2));
+                    assert_eq!(values[2].as_i64(), Some(80));
+                },
+                Some("Lyon") => {
+                    assert_eq!(values[1].as_i64(), Some(1));
+                    assert_eq!(values[2].as_i64(), Some(40));
+                },
+                _ => assert!(false, "unexpected city"),
+            }
+        }
+    } else {
+        assert!(false, "no response")
+    }
+}
+
+async fn test_where_comparisons(mut client: Client) {
+    for (name, age) in [("Alice", 12), ("Bob", 30), ("Carol", 45), ("Dave", 70)] {
+        let r = client.execute_cypher_request(&format!("create (p:Person {{name: '{}', age: {}}}) return p", name, age)).await;
+        if let Ok(d) = r {
+            debug!("{}", d.to_string());
+        } else {
+            assert!(false, "no response")
+        }
+    }
+    let r = client.execute_cypher_request("match (p:Person) where p.age >= 18 and not (p.age > 65 or p.name = 'Bob') return p").await;
+    if let Ok(d) = r {
+        debug!("{}", d.to_string());
+        let res = d.get_document("result").expect("result");
+        let graphs = res.get_array("graphs").expect("graphs");
+        assert_eq!(graphs.len(), 1);
+        let graph = graphs[0].as_document().expect("a graph");
+        let nodes = graph.get_array("nodes").expect("nodes");
+        let props = nodes[0].as_document().expect("a node").get_array("properties").expect("properties");
+        assert_eq!(props[0].as_document().expect("name").get_str("name").ok(), Some("Carol"));
+    } else {
+        assert!(false, "no response")
+    }
+    let r = client.execute_cypher_request("match (p:Person) where p.age < 18 or p.age <> 30 and p.age <= 45 return p").await;
+    if let Ok(d) = r {
+        let res = d.get_document("result").expect("result");
+        let graphs = res.get_array("graphs").expect("graphs");
+        assert_eq!(graphs.len(), 2);
+    } else {
+        assert!(false, "no response")
+    }
+}
+
+async fn test_unique_constraint(mut client: Client) {
+    let r = client.execute_cypher_request("create constraint on (p:Person) assert p.email is unique").await;
+    assert!(r.is_ok(), "no response");
+    let get_error_code = |d: &bson::Document| d.get_document("error").and_then(|error| error.get_str("code")).map(String::from).ok();
+    for (email, error_code) in [("alice", None), ("bob", None), ("alice", Some(String::from("Neo.ClientError.Schema.ConstraintValidationFailed")))] {
+        let r = client.execute_cypher_request(&format!("create (p:Person {{email: '{}'}}) return p", email)).await;
+        if let Ok(d) = r {
+            debug!("{}", d.to_string());
+            assert_eq!(error_code, get_error_code(&d));
+        } else {
+            assert!(false, "no response")
+        }
+    }
+    let r = client.execute_cypher_request("match (p:Person) return p").await;
+    if let Ok(d) = r {
+        let res = d.get_document("result").expect("result");
+        let graphs = res.get_array("graphs").expect("graphs");
+        assert_eq!(graphs.len(), 2);
+    } else {
+        assert!(false, "no response")
+    }
+    for _ in 0..2 {
+        let r = client.execute_cypher_request("create (m:Member {email: 'alice'}) return m").await;
+        assert!(r.is_ok_and(|d| d.get_document("result").is_ok()), "no response");
+    }
+    let r = client.execute_cypher_request("create constraint on (m:Member) assert m.email is unique").await;
+    if let Ok(d) = r {
+        debug!("{}", d.to_string());
+        assert_eq!(Some(String::from("Neo.ClientError.Schema.ConstraintValidationFailed")), get_error_code(&d));
+    } else {
+        assert!(false, "no response")
+    }
+    let r = client.execute_cypher_request("create (m:Member {email: 'alice'}) return m").await;
+    assert!(r.is_ok_and(|d| d.get_document("result").is_ok()), "the constraint was not created");
+}
+
+async fn test_paged_results(mut client: Client) {
+    for _ in 0..5 {
+        let r = client.execute_cypher_request("create (p:Person) return p").await;
+        assert!(r.is_ok(), "no response");
+    }
+    let mut cursor = None;
+    let mut pages = Vec::new();
+    loop {
+        let r = client.execute_cypher_request_page("match (p:Person) return p", 2, cursor).await;
+        if let Ok(d) = r {
+            debug!("{}", d.to_string());
+            let res = d.get_document("result").expect("result");
+            pages.push(res.get_array("graphs").expect("graphs").len());
+            if !res.get_bool("has_more").expect("has_more") {
+                break;
+            }
+            cursor = Some(res.get_i64("cursor").expect("cursor"));
+        } else {
+            assert!(false, "no response")
+        }
+    }
+    assert_eq!(pages, vec![2, 2, 1]);
+}
+
+async fn test_explain_profile(mut client: Client) {
+    for label in ["Person", "Person", "Person", "City"] {
+        let r = client.execute_cypher_request(&format!("create (n:{}) return n", label)).await;
+        assert!(r.is_ok(), "no response");
+    }
+    let r = client.execute_cypher_request("explain match (p:Person)-[:LIVES_IN]->(c:City) return p, c").await;
+    if let Ok(d) = r {
+        debug!("{}", d.to_string());
+        let res = d.get_document("result").expect("result");
+        assert_eq!(0, res.get_array("graphs").expect("graphs").len());
+        let plan = res.get_array("plan").expect("plan");
+        assert_eq!(1, plan.len());
+        let step = plan[0].as_document().expect("step");
+        assert_eq!("MATCH", step.get_str("step").expect("step name"));
+        let pattern = step.get_array("patterns").expect("patterns")[0].as_document().expect("pattern");
+        assert_eq!("c", pattern.get_str("start_variable").expect("start variable"));
+        assert_eq!("LabelScan", pattern.get_str("scan").expect("scan"));
+    } else {
+        assert!(false, "no response")
+    }
+    let r = client.execute_cypher_request("profile match (p:Person) return p").await;
+    if let Ok(d) = r {
+        debug!("{}", d.to_string());
+        let res = d.get_document("result").expect("result");
+        assert_eq!(3, res.get_array("graphs").expect("graphs").len());
+        let step = res.get_array("plan").expect("plan")[0].as_document().expect("step").clone();
+        assert_eq!(3, step.get_i64("rows").expect("rows"));
+        assert!(step.get_i64("time_us").is_ok());
+    } else {
+        assert!(false, "no response")
+    }
+}
+
+async fn test_temporal_values(mut client: Client) {
+    for (name, at) in [("launch", "2015-07-21T21:40:32.142+01:00"), ("release", "2021-03-01T08:00:00Z")] {
+        let r = client.execute_cypher_request(&format!("create (e:Event {{name: '{}', at: datetime('{}'), span: duration('P1DT2H')}}) return e", name, at)).await;
+        assert!(r.is_ok(), "no response");
+    }
+    let r = client.execute_cypher_request("match (e:Event) where e.at < datetime({year: 2016, month: 1, day: 1}) return e").await;
+    if let Ok(d) = r {
+        debug!("{}", d.to_string());
+        let res = d.get_document("result").expect("result");
+        let graphs = res.get_array("graphs").expect("graphs");
+        assert_eq!(graphs.len(), 1);
+        let graph = graphs[0].as_document().expect("a graph");
+        let nodes = graph.get_array("nodes").expect("nodes");
+        let props = nodes[0].as_document().expect("a node").get_array("properties").expect("properties");
+        let values: Vec<String> = props.iter().map(|p| p.as_document().expect("property").values().next().expect("value").to_string()).collect();
+        assert!(values.contains(&String::from("\"2015-07-21T21:40:32.142+01:00\"")));
+        assert!(values.contains(&String::from("\"P1DT2H\"")));
+    } else {
+        assert!(false, "no response")
+    }
+}
+
+async fn test_tabular_rows(mut client: Client) {
+    for query in ["create (p:Person {name: 'ada'}) return p", "create (c:City {name: 'London'}) return c", "match (p:Person), (c:City) create (p)-[r:LIVES_IN]->(c) return r"] {
+        let r = client.execute_cypher_request(query).await;
+        assert!(r.is_ok(), "no response");
+    }
+    let r = client.execute_cypher_request("match (p:Person)-[r:LIVES_IN]->(c:City) return p.name, r, c").await;
+    if let Ok(d) = r {
+        debug!("{}", d.to_string());
+        let res = d.get_document("result").expect("result");
+        let columns: Vec<&str> = res.get_array("columns").expect("columns").iter().filter_map(|c| c.as_str()).collect();
+        assert_eq!(vec!["p.name", "r", "c"], columns);
+        let rows = res.get_array("rows").expect("rows");
+        assert_eq!(rows.len(), 1);
+        let values = rows[0].as_array().expect("a row");
+        assert_eq!(Some("ada"), values[0].as_str());
+        let rel = values[1].as_document().expect("a relationship");
+        let node = values[2].as_document().expect("a node");
+        assert_eq!(rel.get_str("target_id").expect("target id"), node.get_str("id").expect("node id"));
+        assert_eq!("LIVES_IN", rel.get_array("labels").expect("labels")[0].as_str().expect("type"));
+    } else {
+        assert!(false, "no response")
+    }
+}
+
+async fn test_return_expressions(mut client: Client) {
+    for query in ["create (p:Person {name: 'ada', age: 36}) return p", "create (p:Person {name: 'alan', age: 41}) return p"] {
+        let r = client.execute_cypher_request(query).await;
+        assert!(r.is_ok(), "no response");
+    }
+    let r = client.execute_cypher_request("match (p:Person) where p.age + 1 > 40 return p.name AS name, p.age + 1, p.age * 2 AS twice").await;
+    if let Ok(d) = r {
+        debug!("{}", d.to_string());
+        let res = d.get_document("result").expect("result");
+        let columns: Vec<&str> = res.get_array("columns").expect("columns").iter().filter_map(|c| c.as_str()).collect();
+        assert_eq!(vec!["name", "p.age + 1", "twice"], columns);
+        let rows = res.get_array("rows").expect("rows");
+        assert_eq!(rows.len(), 1);
+        let values = rows[0].as_array().expect("a row");
+        assert_eq!(Some("alan"), values[0].as_str());
+        assert_eq!(Some(42), values[1].as_i64());
+        assert_eq!(Some(82), values[2].as_i64());
+    } else {
+        assert!(false, "no response")
+    }
+}
+
+async fn test_return_distinct(mut client: Client) {
+    for query in ["create (p:Person {city: 'Paris'}) return p", "create (p:Person {city: 'Paris'}) return p", "create (p:Person {city: 'Lyon'}) return p"] {
+        let r = client.execute_cypher_request(query).await;
+        assert!(r.is_ok(), "no response");
+    }
+    let r = client.execute_cypher_request("match (p:Person) return distinct p.city").await;
+    if let Ok(d) = r {
+        debug!("{}", d.to_string());
+        let res = d.get_document("result").expect("result");
+        let mut cities: Vec<&str> = res.get_array("rows").expect("rows").iter().filter_map(|row| row.as_array().and_then(|values| values[0].as_str())).collect();
+        cities.sort();
+        assert_eq!(vec!["Lyon", "Paris"], cities);
+    } else {
+        assert!(false, "no response")
+    }
+}
+
+async fn test_unwind(mut client: Client) {
+    let get_rows = |d: &bson::Document| d.get_document("result").expect("result").get_array("rows").expect("rows").clone();
+    let r = client.execute_cypher_request("unwind [{name: 'ada'}, {name: 'alan'}, {name: 'grace'}] as item create (n:Thing {name: item.name}) return n.name").await;
+    if let Ok(d) = r {
+        debug!("{}", d.to_string());
+        assert_eq!(3, get_rows(&d).len());
+    } else {
+        assert!(false, "no response")
+    }
+    let r = client.execute_cypher_request("match (n:Thing) return n.name").await;
+    if let Ok(d) = r {
+        let mut names: Vec<String> = get_rows(&d).iter().filter_map(|row| row.as_array().and_then(|values| values[0].as_str()).map(String::from)).collect();
+        names.sort();
+        assert_eq!(vec!["ada", "alan", "grace"], names);
+    } else {
+        assert!(false, "no response")
+    }
+    let r = client.execute_cypher_request("unwind ['ada', 'grace'] as name match (n:Thing) where n.name = name return n.name").await;
+    if let Ok(d) = r {
+        assert_eq!(2, get_rows(&d).len());
+    } else {
+        assert!(false, "no response")
+    }
+}
+
+async fn test_call_procedure(mut client: Client) {
+    let r = client.execute_cypher_request("create constraint on (p:Person) assert p.email is unique").await;
+    assert!(r.is_ok(), "no response");
+    let r = client.execute_cypher_request("call db.constraints()").await;
+    if let Ok(d) = r {
+        debug!("{}", d.to_string());
+        let result = d.get_document("result").expect("result");
+        assert_eq!(&vec![bson::Bson::from("label"), bson::Bson::from("property")], result.get_array("columns").expect("columns"));
+        assert_eq!(1, result.get_array("rows").expect("rows").len());
+    } else {
+        assert!(false, "no response")
+    }
+    let r = client.execute_cypher_request("call db.constraints() yield property return property + '!' as name").await;
+    if let Ok(d) = r {
+        let result = d.get_document("result").expect("result");
+        let rows = result.get_array("rows").expect("rows");
+        assert_eq!(Some("email!"), rows[0].as_array().and_then(|values| values[0].as_str()));
+    } else {
+        assert!(false, "no response")
+    }
+}
+
+async fn test_schema_procedures(mut client: Client) {
+    let get_names = |d: &bson::Document| -> Vec<String> {
+        let rows = d.get_document("result").expect("result").get_array("rows").expect("rows").clone();
+        rows.iter().filter_map(|row| row.as_array().and_then(|values| values[0].as_str()).map(String::from)).collect()
+    };
+    let r = client.execute_cypher_request("create (p:Person {name: 'ada'})").await;
+    assert!(r.is_ok(), "no response");
+    let r = client.execute_cypher_request("create (c:City {name: 'London'})").await;
+    assert!(r.is_ok(), "no response");
+    let r = client.execute_cypher_request("match (p:Person), (c:City) create (p)-[r:LIVES_IN]->(c)").await;
+    assert!(r.is_ok(), "no response");
+    let r = client.execute_cypher_request("call db.labels()").await;
+    if let Ok(d) = r {
+        debug!("{}", d.to_string());
+        assert_eq!(vec!["City", "Person"], get_names(&d));
+    } else {
+        assert!(false, "no response")
+    }
+    let r = client.execute_cypher_request("call db.relationshipTypes()").await;
+    if let Ok(d) = r {
+        assert_eq!(vec!["LIVES_IN"], get_names(&d));
+    } else {
+        assert!(false, "no response")
+    }
+    let r = client.execute_cypher_request("call db.propertyKeys() yield propertyKey return propertyKey").await;
+    if let Ok(d) = r {
+        assert_eq!(vec!["name"], get_names(&d));
+    } else {
+        assert!(false, "no response")
+    }
+}
+
+async fn test_session_parameters(mut client: Client) {
+    let get_rows = |d: &bson::Document| d.get_document("result").expect("result").get_array("rows").expect("rows").clone();
+    let mut params = Parameters::new();
+    params.insert(String::from("name"), PropertyValue::String(String::from("ada")));
+    let r = client.set_session_parameters(params).await;
+    assert!(r.is_ok(), "no response");
+    for _ in 0..2 {
+        let r = client.execute_cypher_request("create (p:Person {name: $name}) return p.name").await;
+        if let Ok(d) = r {
+            debug!("{}", d.to_string());
+            let rows = get_rows(&d);
+            assert_eq!(Some("ada"), rows[0].as_array().and_then(|values| values[0].as_str()));
+        } else {
+            assert!(false, "no response")
+        }
+    }
+    let r = client.execute_cypher_request("match (p:Person) where p.name = $name return p.name").await;
+    if let Ok(d) = r {
+        assert_eq!(2, get_rows(&d).len());
+    } else {
+        assert!(false, "no response")
+    }
+}
+
+async fn test_count_statistics(mut client: Client) {
+    let get_count = |d: &bson::Document| -> (Option<String>, Option<i64>) {
+        let result = d.get_document("result").expect("result");
+        let column = result.get_array("columns").expect("columns").first().and_then(|c| c.as_str()).map(String::from);
+        let rows = result.get_array("rows").expect("rows");
+        (column, rows.first().and_then(|row| row.as_array()).and_then(|values| values[0].as_i64()))
+    };
+    for name in ["ada", "alan", "grace"] {
+        let r = client.execute_cypher_request(&format!("create (p:Person {{name: '{}'}})", name)).await;
+        assert!(r.is_ok(), "no response");
+    }
+    let r = client.execute_cypher_request("create (c:City {name: 'London'})").await;
+    assert!(r.is_ok(), "no response");
+    let r = client.execute_cypher_request("match (p:Person), (c:City) create (p)-[r:LIVES_IN]->(c)").await;
+    assert!(r.is_ok(), "no response");
+    let r = client.execute_cypher_request("match (p:Person) return count(p)").await;
+    if let Ok(d) = r {
+        debug!("{}", d.to_string());
+        assert_eq!((Some(String::from("count(p)")), Some(3)), get_count(&d));
+    } else {
+        assert!(false, "no response")
+    }
+    let r = client.execute_cypher_request("match (n) return count(n) as total").await;
+    if let Ok(d) = r {
+        assert_eq!((Some(String::from("total")), Some(4)), get_count(&d));
+    } else {
+        assert!(false, "no response")
+    }
+    let r = client.execute_cypher_request("match (a)-[r:LIVES_IN]->(b) return count(r)").await;
+    if let Ok(d) = r {
+        assert_eq!((Some(String::from("count(r)")), Some(3)), get_count(&d));
+    } else {
+        assert!(false, "no response")
+    }
+    let r = client.execute_cypher_request("match (p:Person) where p.name = 'ada' return count(p)").await;
+    if let Ok(d) = r {
+        assert_eq!((Some(String::from("count(p)")), Some(1)), get_count(&d));
+    } else {
+        assert!(false, "no response")
+    }
+}
+
+async fn test_relationship_type_union(mut client: Client) {
+    for label in ["Ada", "Alan", "Grace"] {
+        let r = client.execute_cypher_request(&format!("create (p:{})", label)).await;
+        assert!(r.is_ok(), "no response");
+    }
+    for (source, rel_type, target) in [("Ada", "KNOWS", "Alan"), ("Alan", "LIKES", "Grace"), ("Grace", "HATES", "Ada")] {
+        let r = client.execute_cypher_request(&format!("match (a:{}), (b:{}) create (a)-[r:{}]->(b)", source, target, rel_type)).await;
+        assert!(r.is_ok(), "no response");
+    }
+    let count_rows = |d: &bson::Document| d.get_document("result").and_then(|result| result.get_array("rows")).map(|rows| rows.len()).unwrap_or(0);
+    for (query, count) in [("match (a)-[r:KNOWS|LIKES]->(b) return r", 2), ("match (a)-[r:LIKES|HATES]->(b) return r", 2), ("match (a)-[r:KNOWS|:HATES]->(b:Ada) return r", 1)] {
+        let r = client.execute_cypher_request(query).await;
+        if let Ok(d) = r {
+            debug!("{}", d.to_string());
+            assert_eq!(count, count_rows(&d), "{}", query);
+        } else {
+            assert!(false, "no response")
+        }
+    }
+}
+
+async fn test_query_timeout(mut client: Client) {
+    let get_error_code = |d: &bson::Document| d.get_document("error").and_then(|error| error.get_str("code")).map(String::from).ok();
+    let names = (0..200).map(|i| format!("{{name: 'thing {}'}}", i)).collect::<Vec<String>>().join(", ");
+    let r = client.execute_cypher_request(&format!("unwind [{}] as item create (n:Thing {{name: item.name}}) return n.name", names)).await;
+    assert!(r.is_ok(), "no response");
+    let query = "match (a:Thing), (b:Thing) return a.name, b.name";
+    let r = client.execute_cypher_request_with_timeout(query, Duration::from_millis(1)).await;
+    if let Ok(d) = r {
+        assert_eq!(Some(String::from("Neo.ClientError.Transaction.TransactionTimedOut")), get_error_code(&d));
+    } else {
+        assert!(false, "no response")
+    }
+    let r = client.set_session_timeout(Duration::from_millis(1)).await;
+    assert!(r.is_ok(), "no response");
+    let r = client.execute_cypher_request(query).await;
+    if let Ok(d) = r {
+        assert_eq!(Some(String::from("Neo.ClientError.Transaction.TransactionTimedOut")), get_error_code(&d));
+    } else {
+        assert!(false, "no response")
+    }
+    let r = client.execute_cypher_request_with_timeout(query, Duration::ZERO).await;
+    if let Ok(d) = r {
+        assert_eq!(None, get_error_code(&d));
+        assert!(!d.get_document("result").expect("result").get_array("rows").expect("rows").is_empty());
+    } else {
+        assert!(false, "no response")
+    }
+    let r = client.set_session_timeout(Duration::ZERO).await;
+    assert!(r.is_ok(), "no response");
+    let r = client.execute_cypher_request("match (n:Thing) return n.name").await;
+    if let Ok(d) = r {
+        assert_eq!(200, d.get_document("result").expect("result").get_array("rows").expect("rows").len());
+    } else {
+        assert!(false, "no response")
+    }
+}
+
+async fn test_element_functions(mut client: Client) {
+    for query in ["create (a:Ada {name: 'ada', born: 1815})", "create (b:Alan {name: 'alan'})", "match (a:Ada), (b:Alan) create (a)-[r:KNOWS]->(b)"] {
+        let r = client.execute_cypher_request(query).await;
+        assert!(r.is_ok(), "no response");
+    }
+    let r = client.execute_cypher_request("match (a:Ada)-[r:KNOWS]->(b) return labels(a), type(r), keys(a), properties(b)").await;
+    if let Ok(d) = r {
+        debug!("{}", d.to_string());
+        let rows = d.get_document("result").expect("result").get_array("rows").expect("rows").clone();
+        assert_eq!(1, rows.len());
+        let values = rows[0].as_array().expect("values");
+        let strings = |value: &bson::Bson| value.as_array().expect("list").iter().filter_map(|v| v.as_str()).map(String::from).collect::<Vec<String>>();
+        assert_eq!(vec!["Ada"], strings(&values[0]));
+        assert_eq!(Some("KNOWS"), values[1].as_str());
+        assert_eq!(vec!["born", "name"], strings(&values[2]));
+        assert_eq!(Ok("alan"), values[3].as_document().expect("properties").get_str("name"));
+    } else {
+        assert!(false, "no response")
+    }
+}
+
+async fn test_string_functions(mut client: Client) {
+    for name in ["Ada", "Alan", "Grace"] {
+        let r = client.execute_cypher_request(&format!("create (p:Person {{name: '{}'}})", name)).await;
+        assert!(r.is_ok(), "no response");
+    }
+    let get_names = |d: &bson::Document| {
+        let rows = d.get_document("result").expect("result").get_array("rows").expect("rows").clone();
+        let mut names = rows.iter().filter_map(|row| row.as_array().and_then(|values| values[0].as_str()).map(String::from)).collect::<Vec<String>>();
+        names.sort();
+        names
+    };
+    for (query, expected) in [("match (p:Person) where p.name starts with 'A' return p.name", vec!["Ada", "Alan"]),
+        ("match (p:Person) where p.name ends with 'e' or p.name contains 'la' return p.name", vec!["Alan", "Grace"]),
+        ("match (p:Person) where not p.name contains 'a' return toUpper(p.name)", vec![]),
+        ("match (p:Person) where p.name starts with 'G' return toLower(p.name)", vec!["grace"])] {
+        let r = client.execute_cypher_request(query).await;
+        if let Ok(d) = r {
+            debug!("{}", d.to_string());
+            assert_eq!(expected, get_names(&d), "{}", query);
+        } else {
+            assert!(false, "no response")
+        }
+    }
+}
+
+async fn test_relationship_id(mut client: Client) {
+    for query in ["create (a:Ada)", "create (b:Alan)", "create (c:Grace)", "match (a:Ada), (b:Alan) create (a)-[r:KNOWS]->(b)", "match (a:Ada), (c:Grace) create (a)-[r:KNOWS]->(c)"] {
+        let r = client.execute_cypher_request(query).await;
+        assert!(r.is_ok(), "no response");
+    }
+    let get_rows = |d: &bson::Document| d.get_document("result").expect("result").get_array("rows").expect("rows").clone();
+    let r = client.execute_cypher_request("match (a:Ada)-[r:KNOWS]->(c:Grace) return id(r)").await;
+    let rel_id = match r {
+        Ok(d) => get_rows(&d)[0].as_array().and_then(|values| values[0].as_i64()).expect("relationship id"),
+        Err(_) => panic!("no response"),
+    };
+    let r = client.execute_cypher_request(&format!("match (a)-[r:KNOWS]->(b) where id(r) = {} return labels(b)", rel_id)).await;
+    if let Ok(d) = r {
+        debug!("{}", d.to_string());
+        let rows = get_rows(&d);
+        assert_eq!(1, rows.len());
+        let labels = rows[0].as_array().and_then(|values| values[0].as_array()).expect("labels");
+        assert_eq!(Some("Grace"), labels[0].as_str());
+    } else {
+        assert!(false, "no response")
+    }
+}
+
+async fn test_element_id(mut client: Client) {
+    for query in ["create (a:Ada {uuid: 'a1'})", "create (b:Alan {uuid: 'b2'})", "create (c:Grace)"] {
+        let r = client.execute_cypher_request(query).await;
+        assert!(r.is_ok(), "no response");
+    }
+    let get_rows = |d: &bson::Document| d.get_document("result").expect("result").get_array("rows").expect("rows").clone();
+    let r = client.execute_cypher_request("match (n) where elementId(n) = 'b2' return labels(n), elementId(n)").await;
+    if let Ok(d) = r {
+        debug!("{}", d.to_string());
+        let rows = get_rows(&d);
+        assert_eq!(1, rows.len());
+        let values = rows[0].as_array().expect("values");
+        assert_eq!(Some("Alan"), values[0].as_array().and_then(|labels| labels[0].as_str()));
+        assert_eq!(Some("b2"), values[1].as_str());
+    } else {
+        assert!(false, "no response")
+    }
+    let r = client.execute_cypher_request("match (n:Grace) return elementId(n)").await;
+    if let Ok(d) = r {
+        let rows = get_rows(&d);
+        assert_eq!(1, rows.len());
+        assert!(rows[0].as_array().map_or(false, |values| values[0].as_null().is_some()));
+    } else {
+        assert!(false, "no response")
+    }
+}
+
+async fn test_set_remove_labels(mut client: Client) {
+    for query in ["create (a:Ada {name: 'Ada'})", "create (b:Alan {name: 'Alan'})"] {
+        let r = client.execute_cypher_request(query).await;
+        assert!(r.is_ok(), "no response");
+    }
+    let get_rows = |d: &bson::Document| d.get_document("result").expect("result").get_array("rows").expect("rows").clone();
+    let get_labels = |row: &bson::Bson| -> Vec<String> {
+        row.as_array().and_then(|values| values[0].as_array()).expect("labels").iter().filter_map(|label| label.as_str().map(String::from)).collect()
+    };
+    let r = client.execute_cypher_request("match (n:Ada) set n:Person:Mathematician return labels(n)").await;
+    if let Ok(d) = r {
+        debug!("{}", d.to_string());
+        let rows = get_rows(&d);
+        assert_eq!(1, rows.len());
+        assert_eq!(vec!["Ada", "Person", "Mathematician"], get_labels(&rows[0]));
+    } else {
+        assert!(false, "no response")
+    }
+    let r = client.execute_cypher_request("match (n:Person) remove n:Ada return labels(n), n.name").await;
+    if let Ok(d) = r {
+        debug!("{}", d.to_string());
+        let rows = get_rows(&d);
+        assert_eq!(1, rows.len());
+        assert_eq!(vec!["Person", "Mathematician"], get_labels(&rows[0]));
+        assert_eq!(Some("Ada"), rows[0].as_array().and_then(|values| values[1].as_str()));
+    } else {
+        assert!(false, "no response")
+    }
+    let r = client.execute_cypher_request("match (n:Ada) return n").await;
+    if let Ok(d) = r {
+        assert!(get_rows(&d).is_empty());
+    } else {
+        assert!(false, "no response")
+    }
+    for query in ["create constraint on (p:Person) assert p.name is unique", "create (c:Clone {name: 'Ada'})"] {
+        let r = client.execute_cypher_request(query).await;
+        assert!(r.is_ok_and(|d| d.get_document("result").is_ok()), "no response");
+    }
+    let r = client.execute_cypher_request("match (n:Clone) set n:Person return labels(n)").await;
+    if let Ok(d) = r {
+        debug!("{}", d.to_string());
+        assert_eq!(Ok("Neo.ClientError.Schema.ConstraintValidationFailed"), d.get_document("error").and_then(|error| error.get_str("code")));
+    } else {
+        assert!(false, "no response")
+    }
+    let r = client.execute_cypher_request("match (n:Clone) return labels(n)").await;
+    if let Ok(d) = r {
+        let rows = get_rows(&d);
+        assert_eq!(1, rows.len());
+        assert_eq!(vec!["Clone"], get_labels(&rows[0]));
+    } else {
+        assert!(false, "no response")
+    }
+}
+
+async fn test_remove_properties(mut client: Client) {
+    for query in ["create (a:Ada {name: 'Ada', age: 36})", "create (b:Charles {name: 'Charles', age: 79})", "match (a:Ada), (b:Charles) create (a)-[r:KNOWS]->(b)"] {
+        let r = client.execute_cypher_request(query).await;
+        assert!(r.is_ok(), "no response");
+    }
+    let get_rows = |d: &bson::Document| d.get_document("result").expect("result").get_array("rows").expect("rows").clone();
+    let r = client.execute_cypher_request("match (a:Ada)-[r:KNOWS]->(b:Charles) remove a.age return a.name, a.age, b.age").await;
+    if let Ok(d) = r {
+        debug!("{}", d.to_string());
+        let rows = get_rows(&d);
+        assert_eq!(1, rows.len());
+        let values = rows[0].as_array().expect("values");
+        assert_eq!(Some("Ada"), values[0].as_str());
+        assert!(values[1].as_null().is_some());
+        assert_eq!(Some(79), values[2].as_i64());
+    } else {
+        assert!(false, "no response")
+    }
+    let r = client.execute_cypher_request("match (n) where n.age is null return n.name").await;
+    if let Ok(d) = r {
+        debug!("{}", d.to_string());
+        let rows = get_rows(&d);
+        assert_eq!(1, rows.len());
+        assert_eq!(Some("Ada"), rows[0].as_array().and_then(|values| values[0].as_str()));
+    } else {
+        assert!(false, "no response")
+    }
+}
+
+async fn test_union(mut client: Client) {
+    for query in ["create (a:Person {name: 'Ada'})", "create (b:Person {name: 'Charles'})", "create (c:City {name: 'Ada'})"] {
+        let r = client.execute_cypher_request(query).await;
+        assert!(r.is_ok(), "no response");
+    }
+    let get_names = |d: &bson::Document| {
+        let result = d.get_document("result").expect("result");
+        assert_eq!(&vec![bson::Bson::from("name")], result.get_array("columns").expect("columns"));
+        let rows = result.get_array("rows").expect("rows");
+        rows.iter().map(|row| row.as_array().and_then(|values| values[0].as_str()).unwrap_or_default().to_string()).collect::<Vec<String>>()
+    };
+    let r = client.execute_cypher_request("match (p:Person) return p.name as name union all match (c:City) return c.name as name").await;
+    if let Ok(d) = r {
+        debug!("{}", d.to_string());
+        let mut names = get_names(&d);
+        names.sort();
+        assert_eq!(vec!["Ada", "Ada", "Charles"], names);
+    } else {
+        assert!(false, "no response")
+    }
+    let r = client.execute_cypher_request("match (p:Person) return p.name as name union match (c:City) return c.name as name").await;
+    if let Ok(d) = r {
+        debug!("{}", d.to_string());
+        let mut names = get_names(&d);
+        names.sort();
+        assert_eq!(vec!["Ada", "Charles"], names);
+    } else {
+        assert!(false, "no response")
+    }
+}
+
+async fn test_foreach(mut client: Client) {
+    let r = client.execute_cypher_request("foreach (x in [1, 2, 3] | create (i:Item {v: x}))").await;
+    assert!(r.is_ok(), "no response");
+    let r = client.execute_cypher_request("create (p:Person {name: 'Ada'})").await;
+    assert!(r.is_ok(), "no response");
+    let r = client.execute_cypher_request("match (p:Person) foreach (tag in ['math', 'poetry'] | create (p)-[r:TAGGED]->(t:Tag {name: tag})) return p.name").await;
+    if let Ok(d) = r {
+        debug!("{}", d.to_string());
+        let rows = d.get_document("result").expect("result").get_array("rows").expect("rows").clone();
+        assert_eq!(1, rows.len());
+    } else {
+        assert!(false, "no response")
+    }
+    let get_count = |d: &bson::Document| d.get_document("result").expect("result").get_array("rows").expect("rows")[0].as_array().and_then(|values| values[0].as_i64());
+    for (query, count) in [("match (i:Item) return count(i)", 3), ("match (i:Item) where i.v = 2 return count(i)", 1), ("match (p:Person)-[r:TAGGED]->(t:Tag) return count(t)", 2)] {
+        let r = client.execute_cypher_request(query).await;
+        if let Ok(d) = r {
+            debug!("{}", d.to_string());
+            assert_eq!(Some(count), get_count(&d));
+        } else {
+            assert!(false, "no response")
+        }
+    }
+}
+
+async fn test_pattern_predicates(mut client: Client) {
+    for query in ["create (a:Person:Ada {name: 'Ada'})", "create (b:Person:Bob {name: 'Bob'})", "create (c:Person:Cy {name: 'Cy'})",
+        "match (a:Ada), (b:Bob) create (a)-[r:KNOWS]->(b)", "match (a:Ada), (c:Cy) create (a)-[r:KNOWS]->(c)", "match (b:Bob), (c:Cy) create (b)-[r:KNOWS]->(c)"] {
+        let r = client.execute_cypher_request(query).await;
+        assert!(r.is_ok(), "no response");
+    }
+    let get_rows = |d: &bson::Document| d.get_document("result").expect("result").get_array("rows").expect("rows").clone();
+    for (query, count) in [("match (p:Person) where (p)-[:KNOWS]->(:Person) return p.name", 2),
+        ("match (p:Person) where not (p)-[:KNOWS]->(:Person) return p.name", 1),
+        ("match (a:Person), (b:Person) where (a)-[:KNOWS]->(b) return a.name, b.name", 3),
+        ("match (p:Person) where (p)-[:KNOWS]->(:Person) and (p)<-[:KNOWS]-(:Person) return p.name", 1)] {
+        let r = client.execute_cypher_request(query).await;
+        if let Ok(d) = r {
+            debug!("{}", d.to_string());
+            assert_eq!(count, get_rows(&d).len(), "{}", query);
+        } else {
+            assert!(false, "no response")
+        }
+    }
+}
+
+async fn test_exists(mut client: Client) {
+    for query in ["create (a:Person:Ada {name: 'Ada', email: 'ada@example.com'})", "create (b:Person:Bob {name: 'Bob'})", "match (a:Ada), (b:Bob) create (a)-[r:KNOWS]->(b)"] {
+        let r = client.execute_cypher_request(query).await;
+        assert!(r.is_ok(), "no response");
+    }
+    let get_rows = |d: &bson::Document| d.get_document("result").expect("result").get_array("rows").expect("rows").clone();
+    for (query, name) in [("match (p:Person) where exists(p.email) return p.name", "Ada"),
+        ("match (p:Person) where not exists(p.email) return p.name", "Bob"),
+        ("match (p:Person) where exists((p)-[:KNOWS]->()) return p.name", "Ada"),
+        ("match (p:Person) where exists((p)<-[:KNOWS]-()) and not exists(p.email) return p.name", "Bob")] {
+        let r = client.execute_cypher_request(query).await;
+        if let Ok(d) = r {
+            debug!("{}", d.to_string());
+            let rows = get_rows(&d);
+            assert_eq!(1, rows.len(), "{}", query);
+            assert_eq!(Some(name), rows[0].as_array().and_then(|values| values[0].as_str()), "{}", query);
+        } else {
+            assert!(false, "no response")
+        }
+    }
+}
+
+async fn test_property_distribution(mut client: Client) {
+    for query in ["foreach (x in [1, 2, 2, 3] | create (i:Item {v: x}))", "create (i:Item {name: 'no value'})"] {
+        let r = client.execute_cypher_request(query).await;
+        assert!(r.is_ok(), "no response");
+    }
+    let r = client.execute_cypher_request("call db.stats.propertyDistribution('Item', 'v') yield lower, count return lower, count").await;
+    if let Ok(d) = r {
+        debug!("{}", d.to_string());
+        let rows = d.get_document("result").expect("result").get_array("rows").expect("rows").clone();
+        let buckets = rows.iter().filter_map(|row| row.as_array().and_then(|values| Some((values[0].as_i64()?, values[1].as_i64()?)))).collect::<Vec<(i64, i64)>>();
+        assert_eq!(vec![(1, 1), (2, 2), (3, 1)], buckets);
+    } else {
+        assert!(false, "no response")
+    }
+}
+
+async fn test_syntax_error(mut client: Client) {
+    let r = client.execute_cypher_request("match (p:Person)\nretrun p").await;
+    if let Ok(d) = r {
+        debug!("{}", d.to_string());
+        let error = d.get_document("error").expect("error");
+        assert_eq!(Ok("Neo.ClientError.Statement.SyntaxError"), error.get_str("code"));
+        assert!(error.get_str("message").expect("message").starts_with("unexpected 'retrun' at line 2, column 1"));
+    } else {
+        assert!(false, "no response")
+    }
+    let r = client.execute_cypher_request("create (p:Person) return p").await;
+    assert!(r.is_ok_and(|d| d.get_document("result").is_ok()), "no response");
+}
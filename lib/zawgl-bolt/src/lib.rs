@@ -0,0 +1,185 @@
+// MIT License
+//
+// Copyright (c) 2022 Alexandre RICCIARDI
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use log::*;
+use thiserror::Error;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use zawgl_tx_handler::databases::Databases;
+
+use self::message::{BOLT_MAGIC, MAX_MESSAGE_SIZE, BoltRequest, chunk_message, negotiate_version};
+use self::session::BoltSession;
+
+pub mod packstream;
+pub mod message;
+pub mod session;
+
+pub const DEFAULT_BOLT_PORT: u16 = 7687;
+
+static CONNECTION_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Debug, Error)]
+pub enum BoltError {
+    #[error("IO error: {0}")]
+    IoError(#[source] std::io::Error),
+    #[error("invalid bolt handshake")]
+    HandshakeError,
+    #[error("none of the proposed bolt versions is supported")]
+    UnsupportedVersion,
+    #[error("packstream error: {0}")]
+    PackStreamError(String),
+    #[error("invalid message: {0}")]
+    MessageError(String),
+    #[error("request task error: {0}")]
+    TaskError(#[source] tokio::task::JoinError),
+}
+
+/// Reads the chunks of a message up to its end marker, a message longer than `max_size` bytes fails.
+async fn read_message<S>(stream: &mut S, max_size: usize) -> Result<Option<Vec<u8>>, BoltError> where S: AsyncRead + Unpin {
+    let mut message = Vec::new();
+    loop {
+        let mut size = [0u8; 2];
+        if let Err(err) = stream.read_exact(&mut size).await {
+            if err.kind() == std::io::ErrorKind::UnexpectedEof && message.is_empty() {
+                return Ok(None);
+            }
+            return Err(BoltError::IoError(err));
+        }
+        let size = u16::from_be_bytes(size) as usize;
+        if size == 0 {
+            if !message.is_empty() {
+                return Ok(Some(message));
+            }
+            continue;
+        }
+        let start = message.len();
+        if start + size > max_size {
+            return Err(BoltError::MessageError(format!("message larger than {} bytes", max_size)));
+        }
+        message.resize(start + size, 0);
+        stream.read_exact(&mut message[start..]).await.map_err(BoltError::IoError)?;
+    }
+}
+
+pub async fn handle_bolt_connection<S>(peer: SocketAddr, databases: Databases<'static>, mut stream: S) -> Result<(), BoltError> where S: AsyncRead + AsyncWrite + Unpin {
+    let mut magic = [0u8; 4];
+    stream.read_exact(&mut magic).await.map_err(BoltError::IoError)?;
+    if magic != BOLT_MAGIC {
+        return Err(BoltError::HandshakeError);
+    }
+    let mut proposals = [0u8; 16];
+    stream.read_exact(&mut proposals).await.map_err(BoltError::IoError)?;
+    let (major, minor) = match negotiate_version(&proposals) {
+        Some(version) => version,
+        None => {
+            stream.write_all(&[0, 0, 0, 0]).await.map_err(BoltError::IoError)?;
+            return Err(BoltError::UnsupportedVersion);
+        }
+    };
+    stream.write_all(&[0, 0, minor, major]).await.map_err(BoltError::IoError)?;
+    info!("New Bolt {}.{} connection: {}", major, minor, peer);
+
+    let connection_id = format!("bolt-{}", CONNECTION_COUNT.fetch_add(1, Ordering::SeqCst));
+    let mut session = BoltSession::new(&connection_id, databases);
+    while let Some(message) = read_message(&mut stream, MAX_MESSAGE_SIZE).await? {
+        let request = BoltRequest::decode(&message)?;
+        debug!("bolt request: {:?}", request);
+        // the requests wait for the transaction locks and the store IO on the blocking threads pool
+        let (responses, running_session) = tokio::task::spawn_blocking(move || {
+            let responses = session.handle_request(request);
+            (responses, session)
+        }).await.map_err(BoltError::TaskError)?;
+        session = running_session;
+        let mut response_data = Vec::new();
+        for response in responses {
+            response_data.extend(chunk_message(&response.encode()));
+        }
+        stream.write_all(&response_data).await.map_err(BoltError::IoError)?;
+        if session.is_closed() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test_bolt {
+    use super::*;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use zawgl_core::model::init::InitContext;
+    use zawgl_core::test_utils::build_dir_path_and_rm_old;
+    use zawgl_tx_handler::databases::DatabaseManager;
+    use self::packstream::{PackValue, pack};
+
+    fn request(signature: u8, fields: Vec<PackValue>) -> Vec<u8> {
+        let mut buf = Vec::new();
+        pack(&PackValue::Structure(signature, fields), &mut buf);
+        chunk_message(&buf)
+    }
+
+    #[tokio::test]
+    async fn test_bolt_connection() {
+        let main_dir = build_dir_path_and_rm_old("test_bolt_connection").expect("db path");
+        let conf = InitContext::new(&main_dir).expect("can't create context");
+        let databases = Arc::new(DatabaseManager::new(conf, Duration::from_secs(10)));
+        let (mut client, server) = tokio::io::duplex(4096);
+        let peer: SocketAddr = "127.0.0.1:7687".parse().expect("peer");
+        let handle = tokio::spawn(handle_bolt_connection(peer, databases, server));
+
+        let mut handshake = BOLT_MAGIC.to_vec();
+        handshake.extend_from_slice(&[0, 0, 4, 4, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0]);
+        client.write_all(&handshake).await.expect("handshake");
+        let mut version = [0u8; 4];
+        client.read_exact(&mut version).await.expect("version");
+        assert_eq!([0, 0, 4, 4], version);
+
+        client.write_all(&request(0x01, vec![PackValue::Map(vec![(String::from("user_agent"), PackValue::String(String::from("test")))])])).await.expect("hello");
+        let hello = read_message(&mut client, MAX_MESSAGE_SIZE).await.expect("hello response").expect("message");
+        assert_eq!(0x70, hello[1]);
+
+        client.write_all(&request(0x10, vec![PackValue::String(String::from("CREATE (n:Person {name: 'ada'}) RETURN n")), PackValue::Map(vec![]), PackValue::Map(vec![])])).await.expect("run");
+        client.write_all(&request(0x3F, vec![PackValue::Map(vec![(String::from("n"), PackValue::Integer(-1))])])).await.expect("pull");
+        let run = read_message(&mut client, MAX_MESSAGE_SIZE).await.expect("run response").expect("message");
+        assert_eq!(0x70, run[1]);
+        let record = read_message(&mut client, MAX_MESSAGE_SIZE).await.expect("record").expect("message");
+        assert_eq!(0x71, record[1]);
+        let pull = read_message(&mut client, MAX_MESSAGE_SIZE).await.expect("pull response").expect("message");
+        assert_eq!(0x70, pull[1]);
+
+        client.write_all(&request(0x02, vec![])).await.expect("goodbye");
+        assert!(handle.await.expect("connection task").is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_message_size_limit() {
+        let data = chunk_message(&[1u8; 10]);
+        assert_eq!(10, read_message(&mut &data[..], 10).await.expect("message").expect("data").len());
+        assert!(matches!(read_message(&mut &data[..], 8).await, Err(BoltError::MessageError(_))));
+        let mut chunks = (10u16).to_be_bytes().to_vec();
+        chunks.extend_from_slice(&[1u8; 10]);
+        chunks.extend_from_slice(&(10u16).to_be_bytes());
+        assert!(matches!(read_message(&mut &chunks[..], 16).await, Err(BoltError::MessageError(_))));
+    }
+}
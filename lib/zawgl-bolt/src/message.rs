@@ -0,0 +1,193 @@
+// MIT License
+//
+// Copyright (c) 2022 Alexandre RICCIARDI
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use crate::BoltError;
+use crate::packstream::{PackValue, pack, unpack};
+
+pub const BOLT_MAGIC: [u8; 4] = [0x60, 0x60, 0xB0, 0x17];
+pub const MAX_CHUNK_SIZE: usize = u16::MAX as usize;
+/// Size above which a message received from a client is rejected, as its chunks are buffered until its end marker.
+pub const MAX_MESSAGE_SIZE: usize = 16 * 1024 * 1024;
+
+const HELLO: u8 = 0x01;
+const GOODBYE: u8 = 0x02;
+const RESET: u8 = 0x0F;
+const RUN: u8 = 0x10;
+const BEGIN: u8 = 0x11;
+const COMMIT: u8 = 0x12;
+const ROLLBACK: u8 = 0x13;
+const DISCARD: u8 = 0x2F;
+const PULL: u8 = 0x3F;
+
+const SUCCESS: u8 = 0x70;
+const RECORD: u8 = 0x71;
+const IGNORED: u8 = 0x7E;
+const FAILURE: u8 = 0x7F;
+
+pub const NODE: u8 = 0x4E;
+pub const RELATIONSHIP: u8 = 0x52;
+pub const UNBOUND_RELATIONSHIP: u8 = 0x72;
+pub const PATH: u8 = 0x50;
+
+const SUPPORTED_MAJOR_VERSION: u8 = 4;
+const MAX_SUPPORTED_MINOR_VERSION: u8 = 4;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BoltRequest {
+    Hello(PackValue),
+    Goodbye,
+    Reset,
+    Run(String, PackValue, PackValue),
+    Begin(PackValue),
+    Commit,
+    Rollback,
+    Discard(PackValue),
+    Pull(PackValue),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BoltResponse {
+    Success(Vec<(String, PackValue)>),
+    Record(Vec<PackValue>),
+    Ignored,
+    Failure(String, String),
+}
+
+fn field(fields: &mut std::vec::IntoIter<PackValue>) -> PackValue {
+    fields.next().unwrap_or(PackValue::Map(Vec::new()))
+}
+
+impl BoltRequest {
+    pub fn decode(data: &[u8]) -> Result<Self, BoltError> {
+        let mut data = data;
+        match unpack(&mut data)? {
+            PackValue::Structure(signature, fields) => {
+                let mut fields = fields.into_iter();
+                match signature {
+                    HELLO => Ok(BoltRequest::Hello(field(&mut fields))),
+                    GOODBYE => Ok(BoltRequest::Goodbye),
+                    RESET => Ok(BoltRequest::Reset),
+                    RUN => {
+                        let query = match fields.next() {
+                            Some(PackValue::String(query)) => query,
+                            _ => return Err(BoltError::MessageError(String::from("RUN without query"))),
+                        };
+                        Ok(BoltRequest::Run(query, field(&mut fields), field(&mut fields)))
+                    }
+                    BEGIN => Ok(BoltRequest::Begin(field(&mut fields))),
+                    COMMIT => Ok(BoltRequest::Commit),
+                    ROLLBACK => Ok(BoltRequest::Rollback),
+                    DISCARD => Ok(BoltRequest::Discard(field(&mut fields))),
+                    PULL => Ok(BoltRequest::Pull(field(&mut fields))),
+                    _ => Err(BoltError::MessageError(format!("unsupported message {:#04x}", signature))),
+                }
+            }
+            other => Err(BoltError::MessageError(format!("expected a message structure, got {:?}", other))),
+        }
+    }
+}
+
+impl BoltResponse {
+    pub fn encode(&self) -> Vec<u8> {
+        let value = match self {
+            BoltResponse::Success(metadata) => PackValue::Structure(SUCCESS, vec![PackValue::Map(metadata.clone())]),
+            BoltResponse::Record(values) => PackValue::Structure(RECORD, vec![PackValue::List(values.clone())]),
+            BoltResponse::Ignored => PackValue::Structure(IGNORED, Vec::new()),
+            BoltResponse::Failure(code, message) => PackValue::Structure(FAILURE, vec![PackValue::Map(vec![
+                (String::from("code"), PackValue::String(code.clone())),
+                (String::from("message"), PackValue::String(message.clone())),
+            ])]),
+        };
+        let mut buf = Vec::new();
+        pack(&value, &mut buf);
+        buf
+    }
+}
+
+/// Splits a message in chunks prefixed by their size and appends the end of message marker.
+pub fn chunk_message(data: &[u8]) -> Vec<u8> {
+    let mut chunked = Vec::with_capacity(data.len() + 4);
+    for chunk in data.chunks(MAX_CHUNK_SIZE) {
+        chunked.extend_from_slice(&(chunk.len() as u16).to_be_bytes());
+        chunked.extend_from_slice(chunk);
+    }
+    chunked.extend_from_slice(&[0, 0]);
+    chunked
+}
+
+/// Picks the highest supported version among the four proposals following the magic preamble,
+/// each proposal being encoded as `[0, range, minor, major]`.
+pub fn negotiate_version(proposals: &[u8; 16]) -> Option<(u8, u8)> {
+    for proposal in proposals.chunks(4) {
+        let (range, minor, major) = (proposal[1], proposal[2], proposal[3]);
+        if major != SUPPORTED_MAJOR_VERSION {
+            continue;
+        }
+        let lowest = minor.saturating_sub(range);
+        let selected = minor.min(MAX_SUPPORTED_MINOR_VERSION);
+        if selected >= lowest {
+            return Some((major, selected));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod test_message {
+    use super::*;
+
+    #[test]
+    fn test_negotiate_version() {
+        let mut proposals = [0u8; 16];
+        proposals[..4].copy_from_slice(&[0, 0, 0, 5]);
+        proposals[4..8].copy_from_slice(&[0, 3, 5, 4]);
+        assert_eq!(Some((4, 4)), negotiate_version(&proposals));
+        proposals[4..8].copy_from_slice(&[0, 0, 6, 4]);
+        assert_eq!(None, negotiate_version(&proposals));
+        proposals[8..12].copy_from_slice(&[0, 0, 1, 4]);
+        assert_eq!(Some((4, 1)), negotiate_version(&proposals));
+    }
+
+    #[test]
+    fn test_decode_run() {
+        let mut buf = Vec::new();
+        pack(&PackValue::Structure(RUN, vec![PackValue::String(String::from("MATCH (n) RETURN n")), PackValue::Map(vec![(String::from("id"), PackValue::Integer(1))])]), &mut buf);
+        match BoltRequest::decode(&buf).expect("run") {
+            BoltRequest::Run(query, params, extra) => {
+                assert_eq!("MATCH (n) RETURN n", query);
+                assert_eq!(Some(1), params.get("id").and_then(|v| v.as_integer()));
+                assert_eq!(PackValue::Map(Vec::new()), extra);
+            }
+            other => panic!("unexpected request {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_chunk_message() {
+        let data = vec![7u8; MAX_CHUNK_SIZE + 10];
+        let chunked = chunk_message(&data);
+        assert_eq!(data.len() + 6, chunked.len());
+        assert_eq!(&[0xFF, 0xFF], &chunked[..2]);
+        assert_eq!(&[0, 10], &chunked[MAX_CHUNK_SIZE + 2..MAX_CHUNK_SIZE + 4]);
+        assert_eq!(&[0, 0], &chunked[chunked.len() - 2..]);
+    }
+}
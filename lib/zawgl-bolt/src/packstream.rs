@@ -0,0 +1,341 @@
+// MIT License
+//
+// Copyright (c) 2022 Alexandre RICCIARDI
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use crate::BoltError;
+
+const NULL: u8 = 0xC0;
+const FLOAT_64: u8 = 0xC1;
+const FALSE: u8 = 0xC2;
+const TRUE: u8 = 0xC3;
+const INT_8: u8 = 0xC8;
+const INT_16: u8 = 0xC9;
+const INT_32: u8 = 0xCA;
+const INT_64: u8 = 0xCB;
+const BYTES_8: u8 = 0xCC;
+const BYTES_16: u8 = 0xCD;
+const BYTES_32: u8 = 0xCE;
+const TINY_STRING: u8 = 0x80;
+const STRING_8: u8 = 0xD0;
+const STRING_16: u8 = 0xD1;
+const STRING_32: u8 = 0xD2;
+const TINY_LIST: u8 = 0x90;
+const LIST_8: u8 = 0xD4;
+const LIST_16: u8 = 0xD5;
+const LIST_32: u8 = 0xD6;
+const TINY_MAP: u8 = 0xA0;
+const MAP_8: u8 = 0xD8;
+const MAP_16: u8 = 0xD9;
+const MAP_32: u8 = 0xDA;
+const TINY_STRUCT: u8 = 0xB0;
+/// Lists, maps and structures nested deeper are rejected before they overflow the stack of the decoder.
+const MAX_DEPTH: usize = 64;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PackValue {
+    Null,
+    Boolean(bool),
+    Integer(i64),
+    Float(f64),
+    Bytes(Vec<u8>),
+    String(String),
+    List(Vec<PackValue>),
+    Map(Vec<(String, PackValue)>),
+    Structure(u8, Vec<PackValue>),
+}
+
+impl PackValue {
+    pub fn get(&self, key: &str) -> Option<&PackValue> {
+        match self {
+            PackValue::Map(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            PackValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_integer(&self) -> Option<i64> {
+        match self {
+            PackValue::Integer(i) => Some(*i),
+            _ => None,
+        }
+    }
+}
+
+fn pack_header(buf: &mut Vec<u8>, size: usize, tiny: u8, marker_8: u8, marker_16: u8, marker_32: u8) {
+    if size < 16 {
+        buf.push(tiny | size as u8);
+    } else if size <= u8::MAX as usize {
+        buf.push(marker_8);
+        buf.push(size as u8);
+    } else if size <= u16::MAX as usize {
+        buf.push(marker_16);
+        buf.extend_from_slice(&(size as u16).to_be_bytes());
+    } else {
+        buf.push(marker_32);
+        buf.extend_from_slice(&(size as u32).to_be_bytes());
+    }
+}
+
+pub fn pack(value: &PackValue, buf: &mut Vec<u8>) {
+    match value {
+        PackValue::Null => buf.push(NULL),
+        PackValue::Boolean(b) => buf.push(if *b { TRUE } else { FALSE }),
+        PackValue::Integer(i) => {
+            let i = *i;
+            if (-16..128).contains(&i) {
+                buf.push(i as u8);
+            } else if (i8::MIN as i64..=i8::MAX as i64).contains(&i) {
+                buf.push(INT_8);
+                buf.push(i as i8 as u8);
+            } else if (i16::MIN as i64..=i16::MAX as i64).contains(&i) {
+                buf.push(INT_16);
+                buf.extend_from_slice(&(i as i16).to_be_bytes());
+            } else if (i32::MIN as i64..=i32::MAX as i64).contains(&i) {
+                buf.push(INT_32);
+                buf.extend_from_slice(&(i as i32).to_be_bytes());
+            } else {
+                buf.push(INT_64);
+                buf.extend_from_slice(&i.to_be_bytes());
+            }
+        }
+        PackValue::Float(f) => {
+            buf.push(FLOAT_64);
+            buf.extend_from_slice(&f.to_be_bytes());
+        }
+        PackValue::Bytes(bytes) => {
+            if bytes.len() <= u8::MAX as usize {
+                buf.push(BYTES_8);
+                buf.push(bytes.len() as u8);
+            } else if bytes.len() <= u16::MAX as usize {
+                buf.push(BYTES_16);
+                buf.extend_from_slice(&(bytes.len() as u16).to_be_bytes());
+            } else {
+                buf.push(BYTES_32);
+                buf.extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+            }
+            buf.extend_from_slice(bytes);
+        }
+        PackValue::String(s) => {
+            pack_header(buf, s.len(), TINY_STRING, STRING_8, STRING_16, STRING_32);
+            buf.extend_from_slice(s.as_bytes());
+        }
+        PackValue::List(values) => {
+            pack_header(buf, values.len(), TINY_LIST, LIST_8, LIST_16, LIST_32);
+            for v in values {
+                pack(v, buf);
+            }
+        }
+        PackValue::Map(entries) => {
+            pack_header(buf, entries.len(), TINY_MAP, MAP_8, MAP_16, MAP_32);
+            for (k, v) in entries {
+                pack(&PackValue::String(k.clone()), buf);
+                pack(v, buf);
+            }
+        }
+        PackValue::Structure(signature, fields) => {
+            buf.push(TINY_STRUCT | fields.len() as u8);
+            buf.push(*signature);
+            for f in fields {
+                pack(f, buf);
+            }
+        }
+    }
+}
+
+fn read_bytes<'a>(data: &mut &'a [u8], len: usize) -> Result<&'a [u8], BoltError> {
+    if data.len() < len {
+        return Err(BoltError::PackStreamError(String::from("unexpected end of data")));
+    }
+    let (bytes, rest) = data.split_at(len);
+    *data = rest;
+    Ok(bytes)
+}
+
+fn read_u8(data: &mut &[u8]) -> Result<u8, BoltError> {
+    Ok(read_bytes(data, 1)?[0])
+}
+
+fn read_size(data: &mut &[u8], marker_size: usize) -> Result<usize, BoltError> {
+    let bytes = read_bytes(data, marker_size)?;
+    Ok(bytes.iter().fold(0usize, |size, b| (size << 8) | *b as usize))
+}
+
+fn unpack_string(data: &mut &[u8], len: usize) -> Result<PackValue, BoltError> {
+    let bytes = read_bytes(data, len)?;
+    let s = std::str::from_utf8(bytes).map_err(|err| BoltError::PackStreamError(err.to_string()))?;
+    Ok(PackValue::String(String::from(s)))
+}
+
+/// Each value takes at least one byte, the declared length can't preallocate more values than the bytes left.
+fn unpack_list(data: &mut &[u8], len: usize, depth: usize) -> Result<PackValue, BoltError> {
+    let mut values = Vec::with_capacity(len.min(data.len()));
+    for _ in 0..len {
+        values.push(unpack_value(data, depth)?);
+    }
+    Ok(PackValue::List(values))
+}
+
+fn unpack_map(data: &mut &[u8], len: usize, depth: usize) -> Result<PackValue, BoltError> {
+    let mut entries = Vec::with_capacity(len.min(data.len() / 2));
+    for _ in 0..len {
+        let key = match unpack_value(data, depth)? {
+            PackValue::String(k) => k,
+            other => return Err(BoltError::PackStreamError(format!("invalid map key {:?}", other))),
+        };
+        entries.push((key, unpack_value(data, depth)?));
+    }
+    Ok(PackValue::Map(entries))
+}
+
+pub fn unpack(data: &mut &[u8]) -> Result<PackValue, BoltError> {
+    unpack_value(data, 0)
+}
+
+/// Unpacks a value nested in `depth` lists, maps or structures, deeper values are rejected.
+fn unpack_value(data: &mut &[u8], depth: usize) -> Result<PackValue, BoltError> {
+    if depth > MAX_DEPTH {
+        return Err(BoltError::PackStreamError(format!("values nested deeper than {} levels", MAX_DEPTH)));
+    }
+    let marker = read_u8(data)?;
+    match marker {
+        0x00..=0x7F => Ok(PackValue::Integer(marker as i64)),
+        0xF0..=0xFF => Ok(PackValue::Integer(marker as i8 as i64)),
+        NULL => Ok(PackValue::Null),
+        FALSE => Ok(PackValue::Boolean(false)),
+        TRUE => Ok(PackValue::Boolean(true)),
+        FLOAT_64 => {
+            let mut bytes = [0u8; 8];
+            bytes.copy_from_slice(read_bytes(data, 8)?);
+            Ok(PackValue::Float(f64::from_be_bytes(bytes)))
+        }
+        INT_8 => Ok(PackValue::Integer(read_u8(data)? as i8 as i64)),
+        INT_16 => {
+            let mut bytes = [0u8; 2];
+            bytes.copy_from_slice(read_bytes(data, 2)?);
+            Ok(PackValue::Integer(i16::from_be_bytes(bytes) as i64))
+        }
+        INT_32 => {
+            let mut bytes = [0u8; 4];
+            bytes.copy_from_slice(read_bytes(data, 4)?);
+            Ok(PackValue::Integer(i32::from_be_bytes(bytes) as i64))
+        }
+        INT_64 => {
+            let mut bytes = [0u8; 8];
+            bytes.copy_from_slice(read_bytes(data, 8)?);
+            Ok(PackValue::Integer(i64::from_be_bytes(bytes)))
+        }
+        BYTES_8 | BYTES_16 | BYTES_32 => {
+            let len = read_size(data, 1 << (marker - BYTES_8))?;
+            Ok(PackValue::Bytes(read_bytes(data, len)?.to_vec()))
+        }
+        0x80..=0x8F => unpack_string(data, (marker & 0x0F) as usize),
+        STRING_8 | STRING_16 | STRING_32 => {
+            let len = read_size(data, 1 << (marker - STRING_8))?;
+            unpack_string(data, len)
+        }
+        0x90..=0x9F => unpack_list(data, (marker & 0x0F) as usize, depth + 1),
+        LIST_8 | LIST_16 | LIST_32 => {
+            let len = read_size(data, 1 << (marker - LIST_8))?;
+            unpack_list(data, len, depth + 1)
+        }
+        0xA0..=0xAF => unpack_map(data, (marker & 0x0F) as usize, depth + 1),
+        MAP_8 | MAP_16 | MAP_32 => {
+            let len = read_size(data, 1 << (marker - MAP_8))?;
+            unpack_map(data, len, depth + 1)
+        }
+        0xB0..=0xBF => {
+            let signature = read_u8(data)?;
+            let mut fields = Vec::new();
+            for _ in 0..(marker & 0x0F) {
+                fields.push(unpack_value(data, depth + 1)?);
+            }
+            Ok(PackValue::Structure(signature, fields))
+        }
+        _ => Err(BoltError::PackStreamError(format!("unknown marker {:#04x}", marker))),
+    }
+}
+
+#[cfg(test)]
+mod test_packstream {
+    use super::*;
+
+    fn round_trip(value: PackValue) -> Vec<u8> {
+        let mut buf = Vec::new();
+        pack(&value, &mut buf);
+        let mut data = &buf[..];
+        assert_eq!(value, unpack(&mut data).expect("unpack"));
+        assert!(data.is_empty());
+        buf
+    }
+
+    #[test]
+    fn test_integers() {
+        assert_eq!(vec![0x01], round_trip(PackValue::Integer(1)));
+        assert_eq!(vec![0xF0], round_trip(PackValue::Integer(-16)));
+        assert_eq!(vec![INT_8, 0xEF], round_trip(PackValue::Integer(-17)));
+        assert_eq!(vec![INT_16, 0x00, 0x80], round_trip(PackValue::Integer(128)));
+        assert_eq!(vec![INT_32, 0x00, 0x01, 0x00, 0x00], round_trip(PackValue::Integer(65536)));
+        round_trip(PackValue::Integer(i64::MIN));
+    }
+
+    #[test]
+    fn test_strings_and_collections() {
+        assert_eq!(vec![0x81, b'a'], round_trip(PackValue::String(String::from("a"))));
+        round_trip(PackValue::String("x".repeat(300)));
+        round_trip(PackValue::List((0..20).map(PackValue::Integer).collect()));
+        let map = PackValue::Map(vec![(String::from("name"), PackValue::String(String::from("zawgl"))), (String::from("score"), PackValue::Float(1.5)), (String::from("none"), PackValue::Null)]);
+        assert_eq!(Some("zawgl"), map.get("name").and_then(|v| v.as_str()));
+        round_trip(map);
+        round_trip(PackValue::Structure(0x4E, vec![PackValue::Integer(1), PackValue::List(vec![]), PackValue::Map(vec![])]));
+        round_trip(PackValue::Bytes(vec![1, 2, 3]));
+    }
+
+    #[test]
+    fn test_truncated_data() {
+        let mut data: &[u8] = &[STRING_8, 5, b'a'];
+        assert!(unpack(&mut data).is_err());
+    }
+
+    #[test]
+    fn test_declared_length_larger_than_data() {
+        let mut data: &[u8] = &[LIST_32, 0xFF, 0xFF, 0xFF, 0xFF];
+        assert!(unpack(&mut data).is_err());
+        let mut data: &[u8] = &[MAP_32, 0xFF, 0xFF, 0xFF, 0xFF];
+        assert!(unpack(&mut data).is_err());
+    }
+
+    #[test]
+    fn test_nesting_depth() {
+        let nested = vec![0x91; 1_000_000];
+        assert!(unpack(&mut &nested[..]).is_err());
+        let mut value = PackValue::Null;
+        for _ in 0..MAX_DEPTH {
+            value = PackValue::List(vec![value]);
+        }
+        round_trip(value);
+    }
+}
@@ -0,0 +1,554 @@
+// MIT License
+//
+// Copyright (c) 2022 Alexandre RICCIARDI
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use std::collections::VecDeque;
+
+use bson::{Bson, Document, doc};
+use zawgl_core::error::GraphError;
+use zawgl_core::graph_engine::query_context::QueryAbort;
+use zawgl_cypher::{CypherError, handle_open_cypher_query_request};
+use zawgl_cypher::cypher::parser::error::ParserError;
+use zawgl_cypher::session::CypherSession;
+use zawgl_tx_handler::DatabaseError;
+use zawgl_tx_handler::databases::Databases;
+
+use crate::message::{BoltRequest, BoltResponse, NODE, PATH, RELATIONSHIP, UNBOUND_RELATIONSHIP};
+use crate::packstream::PackValue;
+
+const SERVER_AGENT: &str = concat!("zawgl/", env!("CARGO_PKG_VERSION"));
+
+const SYNTAX_ERROR: &str = "Neo.ClientError.Statement.SyntaxError";
+const SEMANTIC_ERROR: &str = "Neo.ClientError.Statement.SemanticError";
+const PARAMETER_ERROR: &str = "Neo.ClientError.Statement.ArgumentError";
+const TX_ERROR: &str = "Neo.ClientError.Transaction.TransactionNotFound";
+const TX_FAILED_ERROR: &str = "Neo.ClientError.Transaction.TransactionMarkedAsFailed";
+const DATABASE_ERROR: &str = "Neo.DatabaseError.General.UnknownError";
+const PROCEDURE_ERROR: &str = "Neo.ClientError.Procedure.ProcedureCallFailed";
+const ACCESS_MODE_ERROR: &str = "Neo.ClientError.Statement.AccessMode";
+const DEADLOCK_ERROR: &str = "Neo.TransientError.Transaction.DeadlockDetected";
+const OUTDATED_ERROR: &str = "Neo.TransientError.Transaction.Outdated";
+const LOCK_TIMEOUT_ERROR: &str = "Neo.TransientError.Transaction.LockAcquisitionTimeout";
+const DATABASE_NOT_FOUND_ERROR: &str = "Neo.ClientError.Database.DatabaseNotFound";
+const TRIGGER_ERROR: &str = "Neo.ClientError.Transaction.TransactionHookFailed";
+const BUSY_ERROR: &str = "Neo.TransientError.Request.NoThreadsAvailable";
+const TERMINATED_ERROR: &str = "Neo.TransientError.Transaction.Terminated";
+const MEMORY_LIMIT_ERROR: &str = "Neo.TransientError.General.MemoryPoolOutOfMemoryError";
+const TIMEOUT_ERROR: &str = "Neo.ClientError.Transaction.TransactionTimedOut";
+const CONSTRAINT_ERROR: &str = "Neo.ClientError.Schema.ConstraintValidationFailed";
+
+struct BoltFailure {
+    code: &'static str,
+    message: String,
+}
+
+impl BoltFailure {
+    fn new(code: &'static str, message: &str) -> Self {
+        BoltFailure{code: code, message: String::from(message)}
+    }
+}
+
+struct ResultStream {
+    fields: Vec<String>,
+    records: VecDeque<Vec<PackValue>>,
+}
+
+/// Bolt connection state, RUN statements are translated into open cypher requests
+/// and explicit transactions are mapped on tx handler sessions.
+pub struct BoltSession<'a> {
+    connection_id: String,
+    databases: Databases<'a>,
+    cypher_session: CypherSession,
+    tx_session_id: Option<String>,
+    tx_database: Option<String>,
+    tx_read_only: bool,
+    tx_timeout: Option<i64>,
+    tx_count: usize,
+    request_count: usize,
+    result: Option<ResultStream>,
+    failed: bool,
+    closed: bool,
+}
+
+impl <'a> BoltSession<'a> {
+    pub fn new(connection_id: &str, databases: Databases<'a>) -> Self {
+        BoltSession{connection_id: String::from(connection_id), databases,
+            cypher_session: CypherSession::new(), tx_session_id: None, tx_database: None, tx_read_only: false, tx_timeout: None, tx_count: 0, request_count: 0, result: None, failed: false, closed: false}
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    pub fn handle_request(&mut self, request: BoltRequest) -> Vec<BoltResponse> {
+        if self.failed && !matches!(request, BoltRequest::Reset | BoltRequest::Goodbye) {
+            return vec![BoltResponse::Ignored];
+        }
+        let res = match request {
+            BoltRequest::Hello(_) => Ok(vec![BoltResponse::Success(vec![
+                (String::from("server"), PackValue::String(String::from(SERVER_AGENT))),
+                (String::from("connection_id"), PackValue::String(self.connection_id.clone())),
+            ])]),
+            BoltRequest::Goodbye => {
+                self.closed = true;
+                self.reset();
+                Ok(Vec::new())
+            }
+            BoltRequest::Reset => {
+                self.reset();
+                Ok(vec![BoltResponse::Success(Vec::new())])
+            }
+            BoltRequest::Run(query, parameters, extra) => self.run(&query, &parameters, &extra),
+            BoltRequest::Pull(extra) => Ok(self.pull(&extra, true)),
+            BoltRequest::Discard(extra) => Ok(self.pull(&extra, false)),
+            BoltRequest::Begin(extra) => self.begin(&extra),
+            BoltRequest::Commit => self.end_tx("commit"),
+            BoltRequest::Rollback => self.end_tx("rollback"),
+        };
+        res.unwrap_or_else(|failure| {
+            self.failed = true;
+            self.result = None;
+            vec![BoltResponse::Failure(String::from(failure.code), failure.message)]
+        })
+    }
+
+    fn reset(&mut self) {
+        if self.tx_session_id.is_some() {
+            let _ = self.end_tx("rollback");
+        }
+        self.result = None;
+        self.failed = false;
+    }
+
+    fn next_request(&mut self) -> Document {
+        self.request_count += 1;
+        let mut request = doc!{"request_id": format!("{}-{}", self.connection_id, self.request_count)};
+        if let Some(session_id) = &self.tx_session_id {
+            request.insert("session_id", session_id);
+            request.insert("read_only", self.tx_read_only);
+            if let Some(timeout) = self.tx_timeout {
+                request.insert("timeout", timeout);
+            }
+        }
+        request
+    }
+
+    /// Sends the request to the database named `database`, or to the default one.
+    fn send_request(&mut self, request: &Document, database: Option<&str>) -> Result<Document, BoltFailure> {
+        let db = self.databases.get_database(database).map_err(build_database_failure)?;
+        let query = self.databases.start_query(request.get_str("request_id").unwrap_or_default());
+        let reply = handle_open_cypher_query_request(db.tx_handler, db.graph_request_handler, &mut self.cypher_session, request, query.get_query_context()).map_err(|err| match err {
+            CypherError::RequestError => BoltFailure::new(SYNTAX_ERROR, "invalid cypher request"),
+            CypherError::ResponseError => BoltFailure::new(DATABASE_ERROR, "invalid cypher response"),
+            CypherError::QueryError(err @ ParserError::SemanticError) => BoltFailure::new(SEMANTIC_ERROR, &err.to_string()),
+            CypherError::QueryError(err) => BoltFailure::new(SYNTAX_ERROR, &err.to_string()),
+            CypherError::TxError(err) => build_database_failure(err),
+        })?;
+        reply.get_document("result").map(|result| result.clone()).map_err(|err| BoltFailure::new(DATABASE_ERROR, &err.to_string()))
+    }
+
+    fn run(&mut self, query: &str, parameters: &PackValue, extra: &PackValue) -> Result<Vec<BoltResponse>, BoltFailure> {
+        let mut request = self.next_request();
+        request.insert("query", query);
+        if let PackValue::Map(entries) = parameters {
+            if !entries.is_empty() {
+                request.insert("parameters", build_parameters(entries)?);
+            }
+        }
+        if self.tx_session_id.is_none() {
+            if let Some(timeout) = get_tx_timeout(extra) {
+                request.insert("timeout", timeout);
+            }
+        }
+        let database = if self.tx_session_id.is_some() { self.tx_database.clone() } else { get_database_name(extra) };
+        let databases = self.databases.clone();
+        let _permit = databases.admit().map_err(build_database_failure)?;
+        let result = self.send_request(&request, database.as_deref())?;
+        let stream = build_result_stream(&result)?;
+        let fields = stream.fields.iter().map(|f| PackValue::String(f.clone())).collect();
+        self.result = Some(stream);
+        Ok(vec![BoltResponse::Success(vec![
+            (String::from("fields"), PackValue::List(fields)),
+            (String::from("t_first"), PackValue::Integer(0)),
+        ])])
+    }
+
+    fn pull(&mut self, extra: &PackValue, send_records: bool) -> Vec<BoltResponse> {
+        let mut responses = Vec::new();
+        let n = extra.get("n").and_then(|n| n.as_integer()).unwrap_or(-1);
+        let mut has_more = false;
+        if let Some(stream) = &mut self.result {
+            let count = if n < 0 { stream.records.len() } else { (n as usize).min(stream.records.len()) };
+            for record in stream.records.drain(..count) {
+                if send_records {
+                    responses.push(BoltResponse::Record(record));
+                }
+            }
+            has_more = !stream.records.is_empty();
+        }
+        if has_more {
+            responses.push(BoltResponse::Success(vec![(String::from("has_more"), PackValue::Boolean(true))]));
+        } else {
+            self.result = None;
+            responses.push(BoltResponse::Success(vec![(String::from("t_last"), PackValue::Integer(0))]));
+        }
+        responses
+    }
+
+    fn begin(&mut self, extra: &PackValue) -> Result<Vec<BoltResponse>, BoltFailure> {
+        if self.tx_session_id.is_some() {
+            return Err(BoltFailure::new(TX_ERROR, "a transaction is already open"));
+        }
+        let database = get_database_name(extra);
+        self.databases.get_database(database.as_deref()).map_err(build_database_failure)?;
+        self.tx_database = database;
+        self.tx_count += 1;
+        self.tx_session_id = Some(format!("{}-tx-{}", self.connection_id, self.tx_count));
+        self.tx_read_only = extra.get("mode").and_then(|mode| mode.as_str()) == Some("r");
+        self.tx_timeout = get_tx_timeout(extra);
+        Ok(vec![BoltResponse::Success(Vec::new())])
+    }
+
+    fn end_tx(&mut self, tx: &str) -> Result<Vec<BoltResponse>, BoltFailure> {
+        if self.tx_session_id.is_none() {
+            return Err(BoltFailure::new(TX_ERROR, "no open transaction"));
+        }
+        let mut request = self.next_request();
+        request.insert("tx", tx);
+        let bookmark = self.tx_session_id.take().unwrap_or_default();
+        let database = self.tx_database.take();
+        self.tx_timeout = None;
+        self.send_request(&request, database.as_deref())?;
+        Ok(vec![BoltResponse::Success(vec![(String::from("bookmark"), PackValue::String(bookmark))])])
+    }
+}
+
+fn get_database_name(extra: &PackValue) -> Option<String> {
+    extra.get("db").and_then(|db| db.as_str()).map(String::from)
+}
+
+/// Timeout in milliseconds of the statements of a transaction, or of an auto commit statement.
+fn get_tx_timeout(extra: &PackValue) -> Option<i64> {
+    extra.get("tx_timeout").and_then(|timeout| timeout.as_integer()).filter(|timeout| *timeout >= 0)
+}
+
+fn build_database_failure(err: DatabaseError) -> BoltFailure {
+    match err {
+        DatabaseError::ProcedureError(err) => BoltFailure::new(PROCEDURE_ERROR, &err.to_string()),
+        DatabaseError::WriteInReadOnlyTx => BoltFailure::new(ACCESS_MODE_ERROR, "write in a read only transaction"),
+        err @ DatabaseError::TxRollbackOnly(_) => BoltFailure::new(TX_FAILED_ERROR, &err.to_string()),
+        err @ DatabaseError::TxOutdated(_) => BoltFailure::new(OUTDATED_ERROR, &err.to_string()),
+        DatabaseError::Deadlock => BoltFailure::new(DEADLOCK_ERROR, "the transaction was rolled back to break a deadlock"),
+        DatabaseError::LockTimeout => BoltFailure::new(LOCK_TIMEOUT_ERROR, "timed out waiting for a lock"),
+        DatabaseError::UnknownDatabase(name) => BoltFailure::new(DATABASE_NOT_FOUND_ERROR, &format!("unknown database {}", name)),
+        DatabaseError::TriggerVeto(trigger, message) => BoltFailure::new(TRIGGER_ERROR, &format!("trigger {} rejected the transaction: {}", trigger, message)),
+        DatabaseError::QueryAborted(QueryAbort::Cancelled) => BoltFailure::new(TERMINATED_ERROR, "the query was cancelled"),
+        DatabaseError::QueryAborted(QueryAbort::MemoryLimitExceeded) => BoltFailure::new(MEMORY_LIMIT_ERROR, "the query exceeded its memory limit"),
+        DatabaseError::QueryAborted(QueryAbort::Timeout) => BoltFailure::new(TIMEOUT_ERROR, "the query timed out"),
+        DatabaseError::Busy => BoltFailure::new(BUSY_ERROR, "too many concurrent queries, retry later"),
+        DatabaseError::EngineError(err @ GraphError::UniqueConstraintViolation(_)) => BoltFailure::new(CONSTRAINT_ERROR, &err.to_string()),
+        DatabaseError::EngineError(err @ GraphError::NodeHasRelationships(_)) => BoltFailure::new(CONSTRAINT_ERROR, &err.to_string()),
+        err => BoltFailure::new(DATABASE_ERROR, &err.to_string()),
+    }
+}
+
+fn build_parameters(entries: &Vec<(String, PackValue)>) -> Result<Document, BoltFailure> {
+    let mut params = Document::new();
+    for (name, value) in entries {
+        let param = match value {
+            PackValue::Boolean(b) => Bson::Boolean(*b),
+            PackValue::Integer(i) => Bson::Int64(*i),
+            PackValue::Float(f) => Bson::Double(*f),
+            PackValue::String(s) => Bson::String(s.clone()),
+            PackValue::Map(nested) => Bson::Document(build_parameters(nested)?),
+            _ => return Err(BoltFailure::new(PARAMETER_ERROR, &format!("unsupported parameter type for {}", name))),
+        };
+        params.insert(name, param);
+    }
+    Ok(params)
+}
+
+fn build_pack_value(value: &Bson) -> PackValue {
+    match value {
+        Bson::Null => PackValue::Null,
+        Bson::Boolean(b) => PackValue::Boolean(*b),
+        Bson::Int32(i) => PackValue::Integer(*i as i64),
+        Bson::Int64(i) => PackValue::Integer(*i),
+        Bson::Double(f) => PackValue::Float(*f),
+        Bson::String(s) => PackValue::String(s.clone()),
+        Bson::Array(values) => PackValue::List(values.iter().map(build_pack_value).collect()),
+        Bson::Document(d) => PackValue::Map(d.iter().map(|(k, v)| (k.clone(), build_pack_value(v))).collect()),
+        other => PackValue::String(other.to_string()),
+    }
+}
+
+fn get_id(item: &Document, key: &str) -> Result<PackValue, BoltFailure> {
+    let id = item.get_str(key).ok().and_then(|id| id.parse::<i64>().ok()).ok_or_else(|| BoltFailure::new(DATABASE_ERROR, &format!("missing {}", key)))?;
+    Ok(PackValue::Integer(id))
+}
+
+fn get_properties(item: &Document) -> PackValue {
+    let mut properties = Vec::new();
+    if let Ok(props) = item.get_array("properties") {
+        for prop in props {
+            if let Bson::Document(p) = prop {
+                properties.extend(p.iter().map(|(k, v)| (k.clone(), build_pack_value(v))));
+            }
+        }
+    }
+    PackValue::Map(properties)
+}
+
+fn get_labels(item: &Document) -> Vec<PackValue> {
+    item.get_array("labels").map(|labels| labels.iter().map(build_pack_value).collect()).unwrap_or_default()
+}
+
+fn build_node(node: &Document) -> Result<PackValue, BoltFailure> {
+    Ok(PackValue::Structure(NODE, vec![get_id(node, "id")?, PackValue::List(get_labels(node)), get_properties(node)]))
+}
+
+fn build_relationship(rel: &Document) -> Result<PackValue, BoltFailure> {
+    let rel_type = get_labels(rel).into_iter().next().unwrap_or(PackValue::String(String::new()));
+    Ok(PackValue::Structure(RELATIONSHIP, vec![get_id(rel, "id")?, get_id(rel, "source_id")?, get_id(rel, "target_id")?, rel_type, get_properties(rel)]))
+}
+
+/// Nodes and relationships are listed once, each step of the path is the 1-based index of its relationship,
+/// negative when it is traversed backwards, followed by the index of the node it leads to.
+fn build_path(path: &Document) -> Result<PackValue, BoltFailure> {
+    let mut node_ids = Vec::new();
+    let mut nodes = Vec::new();
+    let mut path_node_ids = Vec::new();
+    for node in get_documents(path, "nodes") {
+        let id = get_id(node, "id")?;
+        if !node_ids.contains(&id) {
+            node_ids.push(id.clone());
+            nodes.push(build_node(node)?);
+        }
+        path_node_ids.push(id);
+    }
+    let mut rel_ids = Vec::new();
+    let mut relationships = Vec::new();
+    let mut indices = Vec::new();
+    for (step, rel) in get_documents(path, "relationships").into_iter().enumerate() {
+        let id = get_id(rel, "id")?;
+        if !rel_ids.contains(&id) {
+            rel_ids.push(id.clone());
+            let rel_type = get_labels(rel).into_iter().next().unwrap_or(PackValue::String(String::new()));
+            relationships.push(PackValue::Structure(UNBOUND_RELATIONSHIP, vec![id.clone(), rel_type, get_properties(rel)]));
+        }
+        let rel_index = rel_ids.iter().position(|rel_id| *rel_id == id).unwrap_or_default() as i64 + 1;
+        let forward = path_node_ids.get(step).is_some_and(|node_id| get_id(rel, "source_id").ok().as_ref() == Some(node_id));
+        indices.push(PackValue::Integer(if forward { rel_index } else { -rel_index }));
+        let node_index = path_node_ids.get(step + 1).and_then(|node_id| node_ids.iter().position(|id| id == node_id)).unwrap_or_default();
+        indices.push(PackValue::Integer(node_index as i64));
+    }
+    Ok(PackValue::Structure(PATH, vec![PackValue::List(nodes), PackValue::List(relationships), PackValue::List(indices)]))
+}
+
+fn get_documents<'a>(item: &'a Document, key: &str) -> Vec<&'a Document> {
+    item.get_array(key).map(|items| items.iter().filter_map(|item| item.as_document()).collect()).unwrap_or_default()
+}
+
+fn is_path(item: &Document) -> bool {
+    item.len() == 2 && item.get_array("nodes").is_ok() && item.get_array("relationships").is_ok()
+}
+
+fn build_items(graph: &Document, key: &str, build_item: fn(&Document) -> Result<PackValue, BoltFailure>) -> Result<PackValue, BoltFailure> {
+    let mut items = Vec::new();
+    for item in graph.get_array(key).map_err(|err| BoltFailure::new(DATABASE_ERROR, &err.to_string()))? {
+        if let Bson::Document(d) = item {
+            items.push(build_item(d)?);
+        }
+    }
+    Ok(PackValue::List(items))
+}
+
+fn is_entity(item: &Document, keys: &[&str]) -> bool {
+    item.len() == keys.len() && keys.iter().all(|key| item.contains_key(key)) && item.get_str("id").is_ok() && item.get_array("properties").is_ok()
+}
+
+fn build_row_value(value: &Bson) -> Result<PackValue, BoltFailure> {
+    match value {
+        Bson::Document(d) if is_entity(d, &["id", "labels", "properties"]) => build_node(d),
+        Bson::Document(d) if is_entity(d, &["id", "source_id", "target_id", "labels", "properties"]) => build_relationship(d),
+        Bson::Document(d) if is_path(d) => build_path(d),
+        Bson::Array(values) => Ok(PackValue::List(values.iter().map(build_row_value).collect::<Result<Vec<PackValue>, BoltFailure>>()?)),
+        _ => Ok(build_pack_value(value)),
+    }
+}
+
+/// Results of queries with a RETURN clause are streamed as rows, otherwise each matched graph
+/// is a record made of its nodes and relationships.
+fn build_result_stream(result: &Document) -> Result<ResultStream, BoltFailure> {
+    if let (Ok(columns), Ok(rows)) = (result.get_array("columns"), result.get_array("rows")) {
+        let fields = columns.iter().filter_map(|c| c.as_str().map(String::from)).collect();
+        let mut records = VecDeque::new();
+        for row in rows {
+            records.push_back(match build_row_value(row)? {
+                PackValue::List(values) => values,
+                value => vec![value],
+            });
+        }
+        return Ok(ResultStream{fields: fields, records: records});
+    }
+    let mut records = VecDeque::new();
+    if let Ok(graphs) = result.get_array("graphs") {
+        for graph in graphs {
+            if let Bson::Document(g) = graph {
+                records.push_back(vec![build_items(g, "nodes", build_node)?, build_items(g, "relationships", build_relationship)?]);
+            }
+        }
+    }
+    Ok(ResultStream{fields: vec![String::from("nodes"), String::from("relationships")], records: records})
+}
+
+#[cfg(test)]
+mod test_session {
+    use super::*;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use zawgl_core::model::init::InitContext;
+    use zawgl_core::test_utils::build_dir_path_and_rm_old;
+    use zawgl_tx_handler::databases::DatabaseManager;
+
+    fn run(session: &mut BoltSession, query: &str) -> Vec<BoltResponse> {
+        let mut responses = session.handle_request(BoltRequest::Run(String::from(query), PackValue::Map(Vec::new()), PackValue::Map(Vec::new())));
+        responses.extend(session.handle_request(BoltRequest::Pull(PackValue::Map(Vec::new()))));
+        responses
+    }
+
+    #[test]
+    fn test_bolt_session() {
+        let main_dir = build_dir_path_and_rm_old("test_bolt_session").expect("db path");
+        let conf = InitContext::new(&main_dir).expect("can't create context");
+        let databases = Arc::new(DatabaseManager::new(conf, Duration::from_secs(10)));
+        let mut session = BoltSession::new("bolt-test", databases.clone());
+
+        let hello = session.handle_request(BoltRequest::Hello(PackValue::Map(Vec::new())));
+        assert!(matches!(&hello[..], [BoltResponse::Success(_)]));
+
+        let created = run(&mut session, "CREATE (n:Person {name: 'ada'}) RETURN n");
+        assert!(matches!(&created[..], [BoltResponse::Success(_), BoltResponse::Record(_), BoltResponse::Success(_)]));
+
+        let matched = run(&mut session, "MATCH (n:Person) RETURN n, n.name");
+        match &matched[1] {
+            BoltResponse::Record(values) => {
+                match &values[0] {
+                    PackValue::Structure(NODE, fields) => assert_eq!(Some("ada"), fields[2].get("name").and_then(|v| v.as_str())),
+                    other => panic!("unexpected node {:?}", other),
+                }
+                assert_eq!(Some("ada"), values[1].as_str());
+            },
+            other => panic!("unexpected response {:?}", other),
+        }
+
+        let failed = run(&mut session, "MATCH (n:Person RETURN n");
+        assert!(matches!(&failed[..], [BoltResponse::Failure(_, _), BoltResponse::Ignored]));
+        let reset = session.handle_request(BoltRequest::Reset);
+        assert!(matches!(&reset[..], [BoltResponse::Success(_)]));
+
+        let commit = session.handle_request(BoltRequest::Commit);
+        assert!(matches!(&commit[..], [BoltResponse::Failure(_, _)]));
+        session.handle_request(BoltRequest::Reset);
+
+        session.handle_request(BoltRequest::Begin(PackValue::Map(Vec::new())));
+        let created = run(&mut session, "CREATE (n:Person {name: 'alan'}) RETURN n");
+        assert!(matches!(&created[..], [BoltResponse::Success(_), BoltResponse::Record(_), BoltResponse::Success(_)]));
+        let commit = session.handle_request(BoltRequest::Commit);
+        assert!(matches!(&commit[..], [BoltResponse::Success(_)]));
+
+        let matched = run(&mut session, "MATCH (n:Person) RETURN n");
+        assert_eq!(4, matched.len());
+
+        let db = |name: &str| (String::from("db"), PackValue::String(String::from(name)));
+        let failed = session.handle_request(BoltRequest::Run(String::from("MATCH (n:Person) RETURN n"), PackValue::Map(Vec::new()), PackValue::Map(vec![db("analytics")])));
+        assert!(matches!(&failed[..], [BoltResponse::Failure(code, _)] if code == DATABASE_NOT_FOUND_ERROR));
+        session.handle_request(BoltRequest::Reset);
+        databases.create_database("analytics").expect("create database");
+        session.handle_request(BoltRequest::Begin(PackValue::Map(vec![db("analytics")])));
+        run(&mut session, "CREATE (n:Person {name: 'edsger'}) RETURN n");
+        let commit = session.handle_request(BoltRequest::Commit);
+        assert!(matches!(&commit[..], [BoltResponse::Success(_)]));
+        let mut responses = session.handle_request(BoltRequest::Run(String::from("MATCH (n:Person) RETURN n"), PackValue::Map(Vec::new()), PackValue::Map(vec![db("analytics")])));
+        responses.extend(session.handle_request(BoltRequest::Pull(PackValue::Map(Vec::new()))));
+        assert_eq!(3, responses.len());
+
+        session.handle_request(BoltRequest::Begin(PackValue::Map(vec![(String::from("mode"), PackValue::String(String::from("r")))])));
+        let matched = run(&mut session, "MATCH (n:Person) RETURN n");
+        assert_eq!(4, matched.len());
+        let failed = run(&mut session, "CREATE (n:Person {name: 'grace'}) RETURN n");
+        assert!(matches!(&failed[..], [BoltResponse::Failure(code, _), BoltResponse::Ignored] if code == ACCESS_MODE_ERROR));
+        session.handle_request(BoltRequest::Reset);
+
+        assert!(session.handle_request(BoltRequest::Goodbye).is_empty());
+        assert!(session.is_closed());
+    }
+
+    #[test]
+    fn test_bolt_tx_timeout() {
+        let main_dir = build_dir_path_and_rm_old("test_bolt_tx_timeout").expect("db path");
+        let conf = InitContext::new(&main_dir).expect("can't create context");
+        let databases = Arc::new(DatabaseManager::new(conf, Duration::from_secs(10)));
+        let mut writer = BoltSession::new("bolt-writer", databases.clone());
+        let mut session = BoltSession::new("bolt-test", databases);
+        writer.handle_request(BoltRequest::Begin(PackValue::Map(Vec::new())));
+        run(&mut writer, "CREATE (n:Person {name: 'ada'}) RETURN n");
+
+        let tx_timeout = || PackValue::Map(vec![(String::from("tx_timeout"), PackValue::Integer(50))]);
+        let failed = session.handle_request(BoltRequest::Run(String::from("CREATE (n:Person {name: 'alan'}) RETURN n"), PackValue::Map(Vec::new()), tx_timeout()));
+        assert!(matches!(&failed[..], [BoltResponse::Failure(code, _)] if code == TIMEOUT_ERROR));
+        session.handle_request(BoltRequest::Reset);
+        session.handle_request(BoltRequest::Begin(tx_timeout()));
+        let failed = run(&mut session, "CREATE (n:Person {name: 'alan'}) RETURN n");
+        assert!(matches!(&failed[..], [BoltResponse::Failure(code, _), BoltResponse::Ignored] if code == TIMEOUT_ERROR));
+        session.handle_request(BoltRequest::Reset);
+
+        let commit = writer.handle_request(BoltRequest::Commit);
+        assert!(matches!(&commit[..], [BoltResponse::Success(_)]));
+        let created = run(&mut session, "CREATE (n:Person {name: 'alan'}) RETURN n");
+        assert!(matches!(&created[..], [BoltResponse::Success(_), BoltResponse::Record(_), BoltResponse::Success(_)]));
+    }
+
+    #[test]
+    fn test_bolt_path() {
+        let main_dir = build_dir_path_and_rm_old("test_bolt_path").expect("db path");
+        let conf = InitContext::new(&main_dir).expect("can't create context");
+        let databases = Arc::new(DatabaseManager::new(conf, Duration::from_secs(10)));
+        let mut session = BoltSession::new("bolt-test", databases);
+        session.handle_request(BoltRequest::Hello(PackValue::Map(Vec::new())));
+
+        run(&mut session, "CREATE (a:Person {name: 'ada'})-[r:KNOWS]->(b:Person {name: 'alan'}) RETURN a, r, b");
+        let matched = run(&mut session, "MATCH p = (b:Person)<-[r:KNOWS]-(a:Person) RETURN p");
+        match &matched[1] {
+            BoltResponse::Record(values) => {
+                match &values[0] {
+                    PackValue::Structure(PATH, fields) => {
+                        assert!(matches!(&fields[0], PackValue::List(nodes) if nodes.len() == 2));
+                        assert!(matches!(&fields[1], PackValue::List(rels) if matches!(&rels[..], [PackValue::Structure(UNBOUND_RELATIONSHIP, _)])));
+                        assert_eq!(PackValue::List(vec![PackValue::Integer(-1), PackValue::Integer(1)]), fields[2]);
+                    },
+                    other => panic!("unexpected path {:?}", other),
+                }
+            },
+            other => panic!("unexpected response {:?}", other),
+        }
+    }
+}
@@ -67,6 +67,13 @@ const DYN_ID_SIZE: usize = 8;
 const DYN_PAYLOAD_SIZE: usize = 120;
 const DYN_RECORD_SIZE: usize = DYN_HEADER_SIZE + DYN_ID_SIZE + DYN_PAYLOAD_SIZE;
 
+//CONSTRAINTS STORE
+const CONSTRAINT_RECORD_SIZE: usize = 2 * DYN_ID_SIZE;
+
+//STATISTICS STORE
+const STATISTIC_COUNT_SIZE: usize = 8;
+const STATISTIC_RECORD_SIZE: usize = DYN_ID_SIZE + STATISTIC_COUNT_SIZE;
+
 
 const fn max_nb_records(record_size: usize) -> usize {
     (PAGE_SIZE - HEADER_SIZE) / record_size
@@ -203,6 +210,25 @@ fn generate_config() -> Result<()> {
     writeln!(config, "pub const DYN_RECORD_SIZE: usize = {};", DYN_RECORD_SIZE)?;
     writeln!(config, "pub const DYN_NB_RECORDS_PER_PAGE: usize = {};", nb_dyn_records_per_page)?;
     writeln!(config, "pub const DYN_NB_PAGES_PER_RECORD: usize = {};", nb_pages_per_dyn_record)?;
+
+    let nb_constraint_records_per_page = compute_nb_records_per_page(CONSTRAINT_RECORD_SIZE);
+    let nb_pages_per_constraint_record = compute_nb_pages_per_record(CONSTRAINT_RECORD_SIZE);
+    writeln!(config, "//CONSTRAINTS")?;
+    writeln!(config, "//PAGE PAYLOAD SIZE {} BYTES", compute_page_payload_size(nb_constraint_records_per_page))?;
+    writeln!(config, "//UNUSED SPACE {} BYTES", compute_page_free_space_size(CONSTRAINT_RECORD_SIZE, nb_constraint_records_per_page, nb_pages_per_constraint_record))?;
+    writeln!(config, "pub const CONSTRAINT_RECORD_SIZE: usize = {};", CONSTRAINT_RECORD_SIZE)?;
+    writeln!(config, "pub const CONSTRAINT_NB_RECORDS_PER_PAGE: usize = {};", nb_constraint_records_per_page)?;
+    writeln!(config, "pub const CONSTRAINT_NB_PAGES_PER_RECORD: usize = {};", nb_pages_per_constraint_record)?;
+
+    let nb_statistic_records_per_page = compute_nb_records_per_page(STATISTIC_RECORD_SIZE);
+    let nb_pages_per_statistic_record = compute_nb_pages_per_record(STATISTIC_RECORD_SIZE);
+    writeln!(config, "//STATISTICS")?;
+    writeln!(config, "//PAGE PAYLOAD SIZE {} BYTES", compute_page_payload_size(nb_statistic_records_per_page))?;
+    writeln!(config, "//UNUSED SPACE {} BYTES", compute_page_free_space_size(STATISTIC_RECORD_SIZE, nb_statistic_records_per_page, nb_pages_per_statistic_record))?;
+    writeln!(config, "pub const STATISTIC_COUNT_SIZE: usize = {};", STATISTIC_COUNT_SIZE)?;
+    writeln!(config, "pub const STATISTIC_RECORD_SIZE: usize = {};", STATISTIC_RECORD_SIZE)?;
+    writeln!(config, "pub const STATISTIC_NB_RECORDS_PER_PAGE: usize = {};", nb_statistic_records_per_page)?;
+    writeln!(config, "pub const STATISTIC_NB_PAGES_PER_RECORD: usize = {};", nb_pages_per_statistic_record)?;
     Ok(())
 }
 
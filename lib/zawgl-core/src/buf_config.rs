@@ -60,3 +60,16 @@ pub const DYN_PAYLOAD_SIZE: usize = 120;
 pub const DYN_RECORD_SIZE: usize = 129;
 pub const DYN_NB_RECORDS_PER_PAGE: usize = 30;
 pub const DYN_NB_PAGES_PER_RECORD: usize = 0;
+//CONSTRAINTS
+//PAGE PAYLOAD SIZE 3263 BYTES
+//UNUSED SPACE 15 BYTES
+pub const CONSTRAINT_RECORD_SIZE: usize = 16;
+pub const CONSTRAINT_NB_RECORDS_PER_PAGE: usize = 203;
+pub const CONSTRAINT_NB_PAGES_PER_RECORD: usize = 0;
+//STATISTICS
+//PAGE PAYLOAD SIZE 3263 BYTES
+//UNUSED SPACE 15 BYTES
+pub const STATISTIC_COUNT_SIZE: usize = 8;
+pub const STATISTIC_RECORD_SIZE: usize = 16;
+pub const STATISTIC_NB_RECORDS_PER_PAGE: usize = 203;
+pub const STATISTIC_NB_PAGES_PER_RECORD: usize = 0;
@@ -24,4 +24,15 @@ pub const PROPERTIES_FILE_NAME: &str = "properties.db";
 pub const DYN_FILE_NAME: &str = "dyn.db";
 pub const NODES_LABELS_INDEX_FILE_NAME: &str = "nodes-index.db";
 pub const RELATIONSHIPS_TYPES_INDEX_FILE_NAME: &str = "relationships-index.db";
-pub const LABELS_FILE_NAME: &str = "labels.db";
\ No newline at end of file
+pub const RELATIONSHIPS_PROPERTIES_INDEX_FILE_NAME: &str = "relationships-properties-index.db";
+pub const LABELS_FILE_NAME: &str = "labels.db";
+pub const NODES_PROPERTIES_INDEX_FILE_NAME: &str = "nodes-properties-index.db";
+pub const NODES_PROPERTY_KEYS_INDEX_FILE_NAME: &str = "nodes-property-keys-index.db";
+pub const CONSTRAINTS_FILE_NAME: &str = "constraints.db";
+pub const STATISTICS_FILE_NAME: &str = "statistics.db";
+pub const DATABASES_DIR_NAME: &str = "databases";
+pub const PARTITIONS_DIR_NAME: &str = "partitions";
+pub const CHANGE_LOG_FILE_NAME: &str = "changes.log";
+
+pub const DEFAULT_PAGE_CACHE_SIZE: usize = 64 * 1024 * 1024;
+pub const DEFAULT_SEGMENT_SIZE: u64 = 1024 * 1024 * 1024;
@@ -0,0 +1,81 @@
+// MIT License
+//
+// Copyright (c) 2022 Alexandre RICCIARDI
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use std::fmt;
+
+use thiserror::Error;
+
+use super::model::constraints::UniqueConstraint;
+
+pub const NODES_STORE: &str = "nodes";
+pub const RELATIONSHIPS_STORE: &str = "relationships";
+pub const PROPERTIES_STORE: &str = "properties";
+pub const LABELS_STORE: &str = "labels";
+pub const CONSTRAINTS_STORE: &str = "constraints";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StoreOperation {
+    Load,
+    Save,
+    Create,
+    Delete,
+}
+
+impl fmt::Display for StoreOperation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            StoreOperation::Load => "load",
+            StoreOperation::Save => "save",
+            StoreOperation::Create => "create",
+            StoreOperation::Delete => "delete",
+        })
+    }
+}
+
+/// Failures of the writes of the graph engine, with the store, the record and the operation involved.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum GraphError {
+    #[error("cannot {operation} record {record_id} of the {store} store")]
+    Record { store: &'static str, record_id: u64, operation: StoreOperation },
+    #[error("cannot create a record in the {store} store")]
+    StoreFull { store: &'static str },
+    #[error("node {0} does not exist")]
+    NodeNotFound(u64),
+    #[error("relationship {0} does not exist")]
+    RelationshipNotFound(u64),
+    #[error("node {0} still has relationships")]
+    NodeHasRelationships(u64),
+    #[error("a node with label {} already has this value of property {}", .0.label, .0.property_name)]
+    UniqueConstraintViolation(UniqueConstraint),
+    #[error("uuid {0} is already used")]
+    DuplicateUuid(String),
+    #[error("cannot load the nodes matching the pattern")]
+    PatternLoad,
+}
+
+impl GraphError {
+    pub fn record(store: &'static str, record_id: u64, operation: StoreOperation) -> Self {
+        GraphError::Record{store, record_id, operation}
+    }
+}
+
+pub type GraphResult<T> = Result<T, GraphError>;
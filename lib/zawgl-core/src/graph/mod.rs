@@ -20,6 +20,8 @@
 
 pub mod traits;
 pub mod container;
+#[cfg(feature = "serde")]
+mod serialization;
 
 use self::traits::*;
 
@@ -157,19 +159,19 @@ impl <'a, R: Clone> Iterator for InEdges<'a, R> {
 }
 
 impl <N: Clone, R: Clone> Graph<N, R> {
-    fn out_edges(&self, source: &NodeIndex) -> OutEdges<'_, R> {
+    pub fn out_edges(&self, source: &NodeIndex) -> OutEdges<'_, R> {
         let first_outbound_edge = self.vertices[source.get_index()].first_outbound_edge;
         OutEdges{ edges: &self.edges, current_edge_index: first_outbound_edge }
     }
 
-    fn in_edges(&self, target: &NodeIndex) -> InEdges<'_, R> {
+    pub fn in_edges(&self, target: &NodeIndex) -> InEdges<'_, R> {
         let first_inbound_edge = self.vertices[target.get_index()].first_inbound_edge;
         InEdges{ edges: &self.edges, current_edge_index: first_inbound_edge }
     }
-    fn in_degree(&self, node: &NodeIndex) -> usize {
+    pub fn in_degree(&self, node: &NodeIndex) -> usize {
         self.in_edges(node).count()
     }
-    fn out_degree(&self, node: &NodeIndex) -> usize {
+    pub fn out_degree(&self, node: &NodeIndex) -> usize {
         self.out_edges(node).count()
     }
 }
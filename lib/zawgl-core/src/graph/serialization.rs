@@ -0,0 +1,113 @@
+// MIT License
+//
+// Copyright (c) 2022 Alexandre RICCIARDI
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde::de::Error;
+
+use super::container::GraphContainer;
+use super::traits::MemGraphId;
+
+/// Graphs are serialized as their nodes and their relationships with the positions of their source and
+/// target nodes, the adjacency lists are rebuilt when they are deserialized.
+#[derive(Serialize)]
+struct GraphRef<'a, NODE, RELATIONSHIP> {
+    nodes: Vec<&'a NODE>,
+    relationships: Vec<EdgeRef<'a, RELATIONSHIP>>,
+}
+
+#[derive(Serialize)]
+struct EdgeRef<'a, RELATIONSHIP> {
+    source: usize,
+    target: usize,
+    relationship: &'a RELATIONSHIP,
+}
+
+#[derive(Deserialize)]
+struct GraphData<NODE, RELATIONSHIP> {
+    nodes: Vec<NODE>,
+    relationships: Vec<EdgeData<RELATIONSHIP>>,
+}
+
+#[derive(Deserialize)]
+struct EdgeData<RELATIONSHIP> {
+    source: usize,
+    target: usize,
+    relationship: RELATIONSHIP,
+}
+
+impl <NODE: Clone + Serialize, RELATIONSHIP: Clone + Serialize> Serialize for GraphContainer<NODE, RELATIONSHIP> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let relationships = self.get_relationships_and_edges().iter().map(|edge| EdgeRef{source: edge.source.get_index(), target: edge.target.get_index(), relationship: &edge.relationship}).collect();
+        GraphRef{nodes: self.get_nodes(), relationships}.serialize(serializer)
+    }
+}
+
+impl <'de, NODE: Clone + Deserialize<'de>, RELATIONSHIP: Clone + Deserialize<'de>> Deserialize<'de> for GraphContainer<NODE, RELATIONSHIP> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let data = GraphData::<NODE, RELATIONSHIP>::deserialize(deserializer)?;
+        let mut graph = GraphContainer::new();
+        let nodes = data.nodes.into_iter().map(|node| graph.add_node(node)).collect::<Vec<_>>();
+        for edge in data.relationships {
+            let source = *nodes.get(edge.source).ok_or_else(|| D::Error::custom(format!("unknown source node {}", edge.source)))?;
+            let target = *nodes.get(edge.target).ok_or_else(|| D::Error::custom(format!("unknown target node {}", edge.target)))?;
+            graph.add_relationship(edge.relationship, source, target);
+        }
+        Ok(graph)
+    }
+}
+
+#[cfg(test)]
+mod test_serialization {
+    use super::super::super::model::*;
+    use super::super::super::model::temporal::Date;
+    extern crate serde_json;
+
+    #[test]
+    fn test_property_graph_round_trip() {
+        let mut graph = PropertyGraph::new();
+        let mut ada = Node::new();
+        ada.set_id(Some(1));
+        ada.set_labels(vec![String::from("Person")]);
+        ada.set_properties(vec![Property::new(String::from("name"), PropertyValue::from("ada")), Property::new(String::from("born"), PropertyValue::PDate(Date{epoch_days: -56_000}))]);
+        let mut alan = Node::new();
+        alan.set_id(Some(2));
+        alan.set_properties(vec![Property::new(String::from("tags"), PropertyValue::from(vec![1i64, 2]))]);
+        let mut knows = Relationship::new();
+        knows.set_id(Some(3));
+        knows.set_labels(vec![String::from("KNOWS")]);
+        let ada_index = graph.add_node(ada);
+        let alan_index = graph.add_node(alan);
+        graph.add_relationship(knows, ada_index, alan_index);
+
+        let json = serde_json::to_string(&graph).expect("serialize");
+        let copy: PropertyGraph = serde_json::from_str(&json).expect("deserialize");
+        assert_eq!(json, serde_json::to_string(&copy).expect("serialize copy"));
+        assert_eq!(2, copy.nodes_len());
+        let edge = &copy.get_relationships_and_edges()[0];
+        assert_eq!(Some(1), copy.get_node_ref(&edge.source).get_id());
+        assert_eq!(Some(2), copy.get_node_ref(&edge.target).get_id());
+        assert_eq!(&vec![String::from("KNOWS")], edge.relationship.get_labels_ref());
+        assert_eq!(PropertyValue::from("ada"), *copy.get_node_ref(&edge.source).get_properties_ref()[0].get_value());
+
+        assert!(serde_json::from_str::<PropertyGraph>(r#"{"nodes": [], "relationships": [{"source": 0, "target": 1, "relationship": {"id": null, "var": null, "properties": [], "labels": [], "status": "Match"}}]}"#).is_err());
+    }
+}
@@ -0,0 +1,262 @@
+// MIT License
+//
+// Copyright (c) 2022 Alexandre RICCIARDI
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+
+use std::collections::{HashMap, VecDeque};
+
+use super::GraphEngine;
+use super::snapshot::GraphSnapshot;
+use super::subgraph::SubgraphFilter;
+use super::super::graph::{EdgeIndex, NodeIndex};
+use super::super::graph::traits::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Outbound,
+    Inbound,
+    Both,
+}
+
+/// Edges of one direction in compressed sparse row layout, the edges of the node at position `p`
+/// are at `offsets[p]..offsets[p + 1]`.
+struct CsrEdges {
+    offsets: Vec<usize>,
+    neighbors: Vec<usize>,
+    relationships: Vec<u64>,
+}
+
+impl CsrEdges {
+    /// Sorts the (node, neighbor, relationship) edges by node with a counting sort.
+    fn new(nodes_len: usize, edges: &[(usize, usize, u64)]) -> Self {
+        let mut offsets = vec![0; nodes_len + 1];
+        for (node, _, _) in edges {
+            offsets[node + 1] += 1;
+        }
+        for position in 0..nodes_len {
+            offsets[position + 1] += offsets[position];
+        }
+        let mut next = offsets.clone();
+        let mut neighbors = vec![0; edges.len()];
+        let mut relationships = vec![0; edges.len()];
+        for (node, neighbor, relationship) in edges {
+            neighbors[next[*node]] = *neighbor;
+            relationships[next[*node]] = *relationship;
+            next[*node] += 1;
+        }
+        CsrEdges{offsets, neighbors, relationships}
+    }
+
+    fn range(&self, position: usize) -> std::ops::Range<usize> {
+        self.offsets[position]..self.offsets[position + 1]
+    }
+}
+
+/// Read-only compressed sparse row copy of a graph for traversals, nodes are identified by their position,
+/// from 0 to the number of nodes.
+pub struct CsrGraph {
+    nodes_ids: Vec<u64>,
+    positions: HashMap<u64, usize>,
+    outbound: CsrEdges,
+    inbound: CsrEdges,
+}
+
+impl CsrGraph {
+    pub fn from_snapshot(snapshot: &GraphSnapshot) -> Self {
+        let graph = snapshot.get_graph();
+        let nodes_ids = (0..graph.nodes_len()).map(|index| snapshot.get_node_id(NodeIndex::new(index))).collect::<Vec<u64>>();
+        let positions = nodes_ids.iter().enumerate().map(|(position, id)| (*id, position)).collect();
+        let mut outbound_edges = Vec::with_capacity(graph.edges_len());
+        let mut inbound_edges = Vec::with_capacity(graph.edges_len());
+        for index in 0..graph.edges_len() {
+            let edge = graph.get_edge_data(EdgeIndex::new(index));
+            let (source, target) = (edge.get_source().get_index(), edge.get_target().get_index());
+            outbound_edges.push((source, target, edge.relationship));
+            inbound_edges.push((target, source, edge.relationship));
+        }
+        let outbound = CsrEdges::new(nodes_ids.len(), &outbound_edges);
+        let inbound = CsrEdges::new(nodes_ids.len(), &inbound_edges);
+        CsrGraph{nodes_ids, positions, outbound, inbound}
+    }
+
+    pub fn nodes_len(&self) -> usize {
+        self.nodes_ids.len()
+    }
+
+    pub fn edges_len(&self) -> usize {
+        self.outbound.neighbors.len()
+    }
+
+    pub fn get_node_id(&self, position: usize) -> u64 {
+        self.nodes_ids[position]
+    }
+
+    pub fn get_position(&self, node_id: u64) -> Option<usize> {
+        self.positions.get(&node_id).copied()
+    }
+
+    pub fn out_neighbors(&self, position: usize) -> &[usize] {
+        &self.outbound.neighbors[self.outbound.range(position)]
+    }
+
+    pub fn in_neighbors(&self, position: usize) -> &[usize] {
+        &self.inbound.neighbors[self.inbound.range(position)]
+    }
+
+    /// Ids of the outbound relationships, in the order of the outbound neighbors.
+    pub fn out_relationships(&self, position: usize) -> &[u64] {
+        &self.outbound.relationships[self.outbound.range(position)]
+    }
+
+    /// Ids of the inbound relationships, in the order of the inbound neighbors.
+    pub fn in_relationships(&self, position: usize) -> &[u64] {
+        &self.inbound.relationships[self.inbound.range(position)]
+    }
+
+    pub fn degree(&self, position: usize, direction: Direction) -> usize {
+        match direction {
+            Direction::Outbound => self.outbound.range(position).len(),
+            Direction::Inbound => self.inbound.range(position).len(),
+            Direction::Both => self.outbound.range(position).len() + self.inbound.range(position).len(),
+        }
+    }
+
+    pub fn degrees(&self, direction: Direction) -> Vec<usize> {
+        (0..self.nodes_len()).map(|position| self.degree(position, direction)).collect()
+    }
+
+    fn neighbors(&self, position: usize, direction: Direction) -> impl Iterator<Item = usize> + '_ {
+        let outbound = if direction == Direction::Inbound { &[][..] } else { self.out_neighbors(position) };
+        let inbound = if direction == Direction::Outbound { &[][..] } else { self.in_neighbors(position) };
+        outbound.iter().chain(inbound.iter()).copied()
+    }
+
+    /// Positions of the nodes reachable from the start node in breadth first order, with their distance to it.
+    pub fn bfs(&self, start: usize, direction: Direction) -> Vec<(usize, usize)> {
+        let mut distances = vec![None; self.nodes_len()];
+        let mut visited = Vec::new();
+        let mut queue = VecDeque::new();
+        distances[start] = Some(0);
+        queue.push_back(start);
+        while let Some(position) = queue.pop_front() {
+            let distance = distances[position].unwrap_or_default();
+            visited.push((position, distance));
+            for neighbor in self.neighbors(position, direction) {
+                if distances[neighbor].is_none() {
+                    distances[neighbor] = Some(distance + 1);
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+        visited
+    }
+
+    /// Positions of the nodes reachable from the start node in depth first preorder.
+    pub fn dfs(&self, start: usize, direction: Direction) -> Vec<usize> {
+        let mut visited = vec![false; self.nodes_len()];
+        let mut order = Vec::new();
+        let mut stack = vec![start];
+        while let Some(position) = stack.pop() {
+            if visited[position] {
+                continue;
+            }
+            visited[position] = true;
+            order.push(position);
+            let neighbors = self.neighbors(position, direction).collect::<Vec<usize>>();
+            stack.extend(neighbors.into_iter().rev().filter(|neighbor| !visited[*neighbor]));
+        }
+        order
+    }
+}
+
+impl GraphEngine {
+    /// Builds the CSR graph of the nodes having one of the labels, or of the whole graph without labels,
+    /// and of the relationships between them.
+    pub fn build_csr_graph(&mut self, labels: &[String]) -> Option<CsrGraph> {
+        let mut proxy = if labels.is_empty() {
+            self.retrieve_graph()?
+        } else {
+            self.retrieve_subgraph(&SubgraphFilter::new().with_labels(labels))?
+        };
+        GraphSnapshot::from_proxy(&mut proxy).map(|snapshot| CsrGraph::from_snapshot(&snapshot))
+    }
+}
+
+#[cfg(test)]
+mod test_analytics {
+    use super::*;
+    use super::super::super::model::*;
+    use super::super::super::model::init::InitContext;
+    use super::super::super::test_utils::build_dir_path_and_rm_old;
+
+    fn make_node(label: &str) -> Node {
+        let mut node = Node::new();
+        node.set_labels(vec![String::from(label)]);
+        node
+    }
+
+    #[test]
+    fn test_csr_graph() {
+        let main_dir = build_dir_path_and_rm_old("test_csr_graph").expect("db path");
+        let conf = InitContext::new(&main_dir).expect("can't create context");
+        let mut ge = GraphEngine::new(&conf);
+        let mut graph = PropertyGraph::new();
+        let a = graph.add_node(make_node("Person"));
+        let b = graph.add_node(make_node("Person"));
+        let c = graph.add_node(make_node("Person"));
+        let d = graph.add_node(make_node("City"));
+        graph.add_relationship(Relationship::new(), a, b);
+        graph.add_relationship(Relationship::new(), b, c);
+        graph.add_relationship(Relationship::new(), a, c);
+        graph.add_relationship(Relationship::new(), c, d);
+        let created = ge.create_graph(&graph).expect("graph");
+        ge.sync();
+        let ids = created.get_nodes().iter().map(|node| node.get_id().expect("id")).collect::<Vec<u64>>();
+
+        let csr = ge.build_csr_graph(&[]).expect("csr");
+        assert_eq!(4, csr.nodes_len());
+        assert_eq!(4, csr.edges_len());
+        let positions = ids.iter().map(|id| csr.get_position(*id).expect("position")).collect::<Vec<usize>>();
+        assert_eq!(ids[2], csr.get_node_id(positions[2]));
+        assert_eq!(2, csr.degree(positions[0], Direction::Outbound));
+        assert_eq!(2, csr.degree(positions[2], Direction::Inbound));
+        assert_eq!(3, csr.degree(positions[2], Direction::Both));
+        let degrees = csr.degrees(Direction::Outbound);
+        assert_eq!(vec![2, 1, 1, 0], positions.iter().map(|p| degrees[*p]).collect::<Vec<usize>>());
+        assert_eq!(csr.out_neighbors(positions[0]).len(), csr.out_relationships(positions[0]).len());
+        assert_eq!(&[positions[2]], csr.in_neighbors(positions[3]));
+
+        let bfs = csr.bfs(positions[0], Direction::Outbound);
+        assert_eq!(4, bfs.len());
+        assert_eq!((positions[0], 0), bfs[0]);
+        assert!(bfs.contains(&(positions[2], 1)));
+        assert!(bfs.contains(&(positions[3], 2)));
+        assert_eq!(vec![(positions[3], 0), (positions[2], 1)], csr.bfs(positions[3], Direction::Inbound)[..2].to_vec());
+        let dfs = csr.dfs(positions[1], Direction::Outbound);
+        assert_eq!(vec![positions[1], positions[2], positions[3]], dfs);
+        assert_eq!(4, csr.dfs(positions[3], Direction::Both).len());
+
+        let persons = ge.build_csr_graph(&[String::from("Person")]).expect("csr");
+        assert_eq!(3, persons.nodes_len());
+        assert_eq!(3, persons.edges_len());
+        assert!(persons.get_position(ids[3]).is_none());
+    }
+}
@@ -0,0 +1,285 @@
+// MIT License
+//
+// Copyright (c) 2022 Alexandre RICCIARDI
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufReader, Read, Seek, SeekFrom, Write};
+use std::sync::Mutex;
+
+use chrono::Utc;
+
+use super::super::model::*;
+use super::super::model::changes::{ChangeEvent, ChangeKind, ChangeSet};
+use super::dump::*;
+
+const RECORD_MAGIC: &[u8; 4] = b"ZCDC";
+
+const NODE_TAG: u8 = 1;
+const RELATIONSHIP_TAG: u8 = 2;
+
+/// Serializes the appends of the engines of the process, the offset of a record is the log size before it.
+static APPEND_LOCK: Mutex<()> = Mutex::new(());
+
+fn write_kind<W: Write>(writer: &mut W, kind: ChangeKind) -> io::Result<()> {
+    let code = match kind {
+        ChangeKind::Created => 1,
+        ChangeKind::Updated => 2,
+        ChangeKind::Deleted => 3,
+    };
+    writer.write_all(&[code])
+}
+
+fn read_kind<R: Read>(reader: &mut R) -> io::Result<ChangeKind> {
+    match read_u8(reader)? {
+        1 => Ok(ChangeKind::Created),
+        2 => Ok(ChangeKind::Updated),
+        3 => Ok(ChangeKind::Deleted),
+        code => Err(invalid_data(&format!("unknown change kind {}", code))),
+    }
+}
+
+fn write_event<W: Write>(writer: &mut W, event: &ChangeEvent) -> io::Result<()> {
+    match event {
+        ChangeEvent::Node{kind, node} => {
+            writer.write_all(&[NODE_TAG])?;
+            write_kind(writer, *kind)?;
+            write_u64(writer, node.get_id().unwrap_or_default())?;
+            write_labels(writer, node.get_labels_ref())?;
+            write_properties(writer, node.get_properties_ref())
+        },
+        ChangeEvent::Relationship{kind, relationship, source_id, target_id} => {
+            writer.write_all(&[RELATIONSHIP_TAG])?;
+            write_kind(writer, *kind)?;
+            write_u64(writer, relationship.get_id().unwrap_or_default())?;
+            write_u64(writer, *source_id)?;
+            write_u64(writer, *target_id)?;
+            write_labels(writer, relationship.get_labels_ref())?;
+            write_properties(writer, relationship.get_properties_ref())
+        },
+    }
+}
+
+fn read_event<R: Read>(reader: &mut R) -> io::Result<ChangeEvent> {
+    match read_u8(reader)? {
+        NODE_TAG => {
+            let kind = read_kind(reader)?;
+            let mut node = Node::new();
+            node.set_id(Some(read_u64(reader)?));
+            node.set_labels(read_labels(reader)?);
+            node.set_properties(read_properties(reader)?);
+            Ok(ChangeEvent::Node{kind, node})
+        },
+        RELATIONSHIP_TAG => {
+            let kind = read_kind(reader)?;
+            let mut relationship = Relationship::new();
+            relationship.set_id(Some(read_u64(reader)?));
+            let source_id = read_u64(reader)?;
+            let target_id = read_u64(reader)?;
+            relationship.set_labels(read_labels(reader)?);
+            relationship.set_properties(read_properties(reader)?);
+            Ok(ChangeEvent::Relationship{kind, relationship, source_id, target_id})
+        },
+        tag => Err(invalid_data(&format!("unknown change tag {}", tag))),
+    }
+}
+
+/// Append only log of the committed changes of a database, each record holds the changes of a transaction.
+pub struct ChangeLog {
+    path: String,
+    fsync: bool,
+}
+
+impl ChangeLog {
+    /// The log of the database if change data capture is enabled.
+    pub fn new(ctx: &init::InitContext) -> Option<Self> {
+        if !ctx.get_change_data_capture() {
+            return None;
+        }
+        let fsync = !matches!(ctx.get_durability(), init::Durability::Periodic(_));
+        Some(ChangeLog{path: ctx.get_change_log_path()?, fsync})
+    }
+
+    /// Appends the changes as a single record and returns its offset.
+    pub fn append(&self, events: &[ChangeEvent]) -> io::Result<u64> {
+        let mut payload = Vec::new();
+        write_u64(&mut payload, Utc::now().timestamp_millis() as u64)?;
+        write_u32(&mut payload, events.len() as u32)?;
+        for event in events {
+            write_event(&mut payload, event)?;
+        }
+        let mut record = Vec::with_capacity(payload.len() + 8);
+        record.extend_from_slice(RECORD_MAGIC);
+        write_bytes(&mut record, &payload)?;
+
+        let _guard = APPEND_LOCK.lock().unwrap();
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        let offset = file.metadata()?.len();
+        file.write_all(&record)?;
+        if self.fsync {
+            file.sync_data()?;
+        }
+        Ok(offset)
+    }
+}
+
+/// Iterates over the change sets of a change log from an offset, it stops at the end of the log and
+/// can be created again from `get_offset` to tail the log.
+pub struct ChangeCursor {
+    reader: Option<BufReader<File>>,
+    offset: u64,
+}
+
+impl ChangeCursor {
+    /// Offset of the next change set to read.
+    pub fn get_offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Reads the next record, none if the log ends before it is complete.
+    fn read_record(&mut self) -> io::Result<Option<Vec<u8>>> {
+        let reader = match self.reader.as_mut() {
+            Some(reader) => reader,
+            None => return Ok(None),
+        };
+        let mut header = [0u8; 8];
+        match reader.read_exact(&mut header) {
+            Ok(()) => {},
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(err) => return Err(err),
+        }
+        if &header[..4] != RECORD_MAGIC {
+            return Err(invalid_data(&format!("no change set at offset {}", self.offset)));
+        }
+        let mut payload = Vec::new();
+        let size = read_u32(&mut &header[4..])? as u64;
+        reader.take(size).read_to_end(&mut payload)?;
+        Ok(if payload.len() as u64 == size { Some(payload) } else { None })
+    }
+
+    fn read_change_set(&mut self) -> io::Result<Option<ChangeSet>> {
+        let payload = match self.read_record()? {
+            Some(payload) => payload,
+            None => return Ok(None),
+        };
+        let mut reader = payload.as_slice();
+        let timestamp_millis = read_u64(&mut reader)? as i64;
+        let count = read_u32(&mut reader)?;
+        let events = (0..count).map(|_| read_event(&mut reader)).collect::<io::Result<Vec<ChangeEvent>>>()?;
+        let offset = self.offset;
+        self.offset += payload.len() as u64 + 8;
+        Ok(Some(ChangeSet{offset, next_offset: self.offset, timestamp_millis, events}))
+    }
+}
+
+impl Iterator for ChangeCursor {
+    type Item = io::Result<ChangeSet>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let res = self.read_change_set().transpose();
+        if !matches!(res, Some(Ok(_))) {
+            self.reader = None;
+        }
+        res
+    }
+}
+
+/// Reads the change log of a database from the offset of a change set, 0 being the first one.
+pub fn read_changes(ctx: &init::InitContext, offset: u64) -> io::Result<ChangeCursor> {
+    let path = ctx.get_change_log_path().ok_or_else(|| invalid_data("invalid database directory"))?;
+    let reader = match File::open(path) {
+        Ok(mut file) => {
+            file.seek(SeekFrom::Start(offset))?;
+            Some(BufReader::new(file))
+        },
+        Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+        Err(err) => return Err(err),
+    };
+    Ok(ChangeCursor{reader, offset})
+}
+
+#[cfg(test)]
+mod test_cdc {
+    use super::*;
+    use super::super::GraphEngine;
+    use super::super::super::test_utils::*;
+
+    fn make_node(label: &str, name: &str) -> Node {
+        let mut node = Node::new();
+        node.set_labels(vec![String::from(label)]);
+        node.set_properties(vec![Property::new(String::from("name"), PropertyValue::PString(String::from(name)))]);
+        node
+    }
+
+    fn describe_events(change_set: &ChangeSet) -> Vec<String> {
+        change_set.events.iter().map(|event| match event {
+            ChangeEvent::Node{kind, node} => format!("{} {:?}{:?}", kind.get_name(), node.get_labels_ref(), node.get_properties_ref()[0].get_value()),
+            ChangeEvent::Relationship{kind, relationship, ..} => format!("{} {:?}", kind.get_name(), relationship.get_labels_ref()),
+        }).collect()
+    }
+
+    #[test]
+    fn test_change_log() {
+        let main_dir = build_dir_path_and_rm_old("test_change_log").unwrap();
+        let ctx = init::InitContext::new(&main_dir).unwrap().with_change_data_capture(true);
+        let mut ge = GraphEngine::new(&ctx);
+        let alice = ge.create_node(&make_node("Person", "alice")).unwrap();
+        let bob = ge.create_node(&make_node("Person", "bob")).unwrap();
+        let mut rel = Relationship::new();
+        rel.set_labels(vec![String::from("KNOWS")]);
+        let knows = ge.create_relationship(&rel, alice.get_id().unwrap(), bob.get_id().unwrap()).unwrap();
+        ge.sync();
+
+        let mut rolled_back = GraphEngine::new(&ctx);
+        rolled_back.create_node(&make_node("Person", "carol")).unwrap();
+        drop(rolled_back);
+
+        let mut cursor = read_changes(&ctx, 0).unwrap();
+        let created = cursor.next().unwrap().unwrap();
+        assert_eq!(created.offset, 0);
+        assert_eq!(describe_events(&created), vec!["created [\"Person\"]PString(\"alice\")", "created [\"Person\"]PString(\"bob\")", "created [\"KNOWS\"]"]);
+        assert!(matches!(&created.events[2], ChangeEvent::Relationship{source_id, target_id, ..} if Some(*source_id) == alice.get_id() && Some(*target_id) == bob.get_id()));
+        assert!(cursor.next().is_none());
+        assert_eq!(cursor.get_offset(), created.next_offset);
+
+        let mut ge = GraphEngine::new(&ctx);
+        ge.detach_delete_node(alice.get_id().unwrap()).unwrap();
+        ge.sync();
+        let deleted = read_changes(&ctx, created.next_offset).unwrap().collect::<io::Result<Vec<ChangeSet>>>().unwrap();
+        assert_eq!(deleted.len(), 1);
+        assert_eq!(describe_events(&deleted[0]), vec!["deleted [\"KNOWS\"]", "deleted [\"Person\"]PString(\"alice\")"]);
+        assert_eq!(deleted[0].events[0].get_kind(), ChangeKind::Deleted);
+        assert!(matches!(&deleted[0].events[0], ChangeEvent::Relationship{relationship, ..} if relationship.get_id() == knows.get_id()));
+        assert!(deleted[0].timestamp_millis >= created.timestamp_millis);
+
+        assert_eq!(read_changes(&ctx, 0).unwrap().count(), 2);
+        assert!(read_changes(&ctx, 1).unwrap().next().unwrap().is_err());
+    }
+
+    #[test]
+    fn test_change_log_disabled() {
+        let main_dir = build_dir_path_and_rm_old("test_change_log_disabled").unwrap();
+        let ctx = init::InitContext::new(&main_dir).unwrap();
+        let mut ge = GraphEngine::new(&ctx);
+        ge.create_node(&make_node("Person", "alice")).unwrap();
+        ge.sync();
+        assert!(read_changes(&ctx, 0).unwrap().next().is_none());
+    }
+}
@@ -0,0 +1,241 @@
+// MIT License
+//
+// Copyright (c) 2022 Alexandre RICCIARDI
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use super::GraphEngine;
+use super::dump::invalid_data;
+use super::super::model::*;
+use super::super::repository::io::segmented_file_access::{rename_segmented_file, segmented_file_len};
+
+const COMPACTION_DIR_NAME: &str = "compaction";
+const BACKUP_DIR_NAME: &str = "compaction-backup";
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct CompactionReport {
+    pub nodes: usize,
+    pub relationships: usize,
+    pub bytes_before: u64,
+    pub bytes_after: u64,
+    /// New ids of the nodes, by their id before the compaction.
+    pub nodes_relocations: HashMap<u64, u64>,
+    /// New ids of the relationships, by their id before the compaction.
+    pub relationships_relocations: HashMap<u64, u64>,
+}
+
+fn copy_properties(properties: &[Property]) -> Vec<Property> {
+    properties.iter().map(|prop| Property::new(String::from(prop.get_name()), prop.get_value().clone())).collect()
+}
+
+fn stores_len(paths: &[String]) -> u64 {
+    paths.iter().map(|path| segmented_file_len(path)).sum()
+}
+
+/// Copies the graph into an empty graph, the relationships of a node are created from the tail of its outbound chain
+/// so that the chain keeps its order. Indexes are filled by the creations.
+pub fn compact_graph(source: &GraphEngine, target: &mut GraphEngine) -> io::Result<CompactionReport> {
+    let mut report = CompactionReport::default();
+    {
+        let mut source_repository = source.repository.lock().unwrap();
+        let mut target_repository = target.repository.lock().unwrap();
+        if !target_repository.retrieve_all_nodes_ids().unwrap_or_default().is_empty() {
+            return Err(io::Error::new(io::ErrorKind::AlreadyExists, "target database is not empty"));
+        }
+        for constraint in source_repository.get_constraints().clone() {
+            target_repository.create_unique_constraint(&constraint).map_err(|err| invalid_data(&err.to_string()))?;
+        }
+        let nodes_ids = source_repository.retrieve_all_nodes_ids().ok_or_else(|| invalid_data("can't read nodes store"))?;
+        for &node_id in &nodes_ids {
+            let (node, _) = source_repository.retrieve_node_by_id(node_id).ok_or_else(|| invalid_data("can't read node"))?;
+            let mut copy = Node::new();
+            copy.set_labels(node.get_labels_ref().clone());
+            copy.set_properties(copy_properties(node.get_properties_ref()));
+            let created = target_repository.create_node(&copy).map_err(|err| invalid_data(&err.to_string()))?;
+            report.nodes_relocations.insert(node_id, created.get_id().ok_or_else(|| invalid_data("missing node id"))?);
+        }
+        for node_id in nodes_ids {
+            let mut outbound = Vec::new();
+            let mut rel_id = source_repository.retrieve_vertex_data_by_id(node_id).and_then(|vertex| vertex.first_outbound_edge);
+            while let Some(rid) = rel_id {
+                let (rel, edge) = source_repository.retrieve_relationship_by_id(rid).ok_or_else(|| invalid_data("can't read relationship"))?;
+                rel_id = edge.next_outbound_edge;
+                outbound.push((rid, rel, edge));
+            }
+            for (rid, rel, edge) in outbound.into_iter().rev() {
+                let (source_id, target_id) = match (report.nodes_relocations.get(&edge.source), report.nodes_relocations.get(&edge.target)) {
+                    (Some(source_id), Some(target_id)) => (*source_id, *target_id),
+                    _ => return Err(invalid_data(&format!("relationship {} references an unknown node", rid))),
+                };
+                let mut copy = Relationship::new();
+                copy.set_labels(rel.get_labels_ref().clone());
+                copy.set_properties(copy_properties(rel.get_properties_ref()));
+                let created = target_repository.create_relationship(&copy, source_id, target_id).map_err(|err| invalid_data(&err.to_string()))?;
+                report.relationships_relocations.insert(rid, created.get_id().ok_or_else(|| invalid_data("missing relationship id"))?);
+            }
+        }
+    }
+    target.sync();
+    report.nodes = report.nodes_relocations.len();
+    report.relationships = report.relationships_relocations.len();
+    Ok(report)
+}
+
+/// Rewrites the stores of a database densely then replaces its files, the database must not be opened by another engine.
+/// Node and relationship ids are reassigned, the report maps the previous ids to the new ones.
+/// The previous files are moved to a backup directory which is removed once all the files are replaced.
+pub fn compact_database(ctx: &init::InitContext) -> io::Result<CompactionReport> {
+    let invalid_dir = || io::Error::new(io::ErrorKind::InvalidInput, "invalid database directory");
+    let db_dir = Path::new(ctx.get_db_dir());
+    let backup_dir = db_dir.join(BACKUP_DIR_NAME);
+    if backup_dir.exists() {
+        return Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("a previous compaction was interrupted, its backup is in {}", backup_dir.display())));
+    }
+    let compaction_dir = db_dir.join(COMPACTION_DIR_NAME);
+    if compaction_dir.exists() {
+        fs::remove_dir_all(&compaction_dir)?;
+    }
+    let compaction_ctx = compaction_dir.to_str().and_then(|dir| ctx.for_directory(dir)).ok_or_else(invalid_dir)?;
+    let mut report = {
+        let source = GraphEngine::new(ctx);
+        let mut target = GraphEngine::new(&compaction_ctx);
+        compact_graph(&source, &mut target)?
+    };
+
+    let stores_paths = ctx.get_stores_paths().ok_or_else(invalid_dir)?;
+    let compacted_paths = compaction_ctx.get_stores_paths().ok_or_else(invalid_dir)?;
+    let backup_paths = backup_dir.to_str().and_then(|dir| ctx.for_directory(dir)).and_then(|backup_ctx| backup_ctx.get_stores_paths()).ok_or_else(invalid_dir)?;
+    report.bytes_before = stores_len(&stores_paths);
+    for ((path, compacted_path), backup_path) in stores_paths.iter().zip(&compacted_paths).zip(&backup_paths) {
+        rename_segmented_file(path, backup_path)?;
+        rename_segmented_file(compacted_path, path)?;
+    }
+    report.bytes_after = stores_len(&stores_paths);
+    fs::remove_dir_all(&compaction_dir)?;
+    fs::remove_dir_all(&backup_dir)?;
+    Ok(report)
+}
+
+#[cfg(test)]
+mod test_compact {
+    use super::*;
+    use super::super::super::model::constraints::UniqueConstraint;
+    use super::super::super::test_utils::*;
+
+    fn make_node(index: i64) -> Node {
+        let mut node = Node::new();
+        node.set_labels(vec![String::from("Person")]);
+        node.set_properties(vec![
+            Property::new(String::from("index"), PropertyValue::PInteger(index)),
+            Property::new(String::from("bio"), PropertyValue::PString("a long biography ".repeat(20))),
+        ]);
+        node
+    }
+
+    #[test]
+    fn test_compact_database() {
+        let main_dir = build_dir_path_and_rm_old("test_compact_database").unwrap();
+        let ctx = init::InitContext::new(&main_dir).unwrap();
+        let mut kept = Vec::new();
+        {
+            let mut ge = GraphEngine::new(&ctx);
+            ge.create_unique_constraint(&UniqueConstraint::new("Person", "index")).unwrap();
+            let mut ids = Vec::new();
+            for i in 0..300 {
+                ids.push(ge.create_node(&make_node(i)).unwrap().get_id().unwrap());
+            }
+            for (i, pair) in ids.windows(2).enumerate().filter(|(i, _)| i % 10 == 0) {
+                let mut rel = Relationship::new();
+                rel.set_labels(vec![String::from("KNOWS")]);
+                rel.set_properties(vec![Property::new(String::from("since"), PropertyValue::PInteger(i as i64))]);
+                ge.create_relationship(&rel, pair[0], pair[1]).unwrap();
+            }
+            for (i, id) in ids.iter().enumerate() {
+                if i % 10 < 7 {
+                    ge.detach_delete_node(*id).unwrap();
+                } else {
+                    kept.push(*id);
+                }
+            }
+            ge.sync();
+        }
+
+        let report = compact_database(&ctx).unwrap();
+        assert_eq!(90, report.nodes);
+        assert_eq!(0, report.relationships);
+        assert!(report.bytes_after < report.bytes_before, "{} >= {}", report.bytes_after, report.bytes_before);
+        let new_ids = kept.iter().map(|id| report.nodes_relocations[id]).collect::<Vec<u64>>();
+        assert_eq!(89, new_ids.iter().max().unwrap() - new_ids.iter().min().unwrap());
+        assert!(!Path::new(&main_dir).join(COMPACTION_DIR_NAME).exists());
+        assert!(!Path::new(&main_dir).join(BACKUP_DIR_NAME).exists());
+
+        let mut ge = GraphEngine::new(&ctx);
+        assert!(ge.check_stores().is_consistent());
+        assert!(ge.check_indexes().iter().all(|report| report.is_consistent()));
+        assert_eq!(vec![UniqueConstraint::new("Person", "index")], ge.get_constraints());
+        assert!(ge.create_node(&make_node(7)).is_err());
+        let old_id = kept[0];
+        let (node, _) = ge.repository.lock().unwrap().retrieve_node_by_id(report.nodes_relocations[&old_id]).unwrap();
+        assert_eq!(&PropertyValue::PInteger(7), node.get_properties_ref()[0].get_value());
+    }
+
+    #[test]
+    fn test_compact_graph_relationships() {
+        let source_dir = build_dir_path_and_rm_old("test_compact_graph_source").unwrap();
+        let target_dir = build_dir_path_and_rm_old("test_compact_graph_target").unwrap();
+        let mut source = GraphEngine::new(&init::InitContext::new(&source_dir).unwrap());
+        let ids = (0..4).map(|i| source.create_node(&make_node(i)).unwrap().get_id().unwrap()).collect::<Vec<u64>>();
+        let mut rels_ids = Vec::new();
+        for target in &ids[1..] {
+            let mut rel = Relationship::new();
+            rel.set_labels(vec![format!("TO_{}", target)]);
+            rels_ids.push(source.create_relationship(&rel, ids[0], *target).unwrap().get_id().unwrap());
+        }
+        source.create_relationship(&Relationship::new(), ids[3], ids[3]).unwrap();
+        source.delete_relationship(rels_ids[0]).unwrap();
+        source.detach_delete_node(ids[1]).unwrap();
+        source.sync();
+
+        let mut target = GraphEngine::new(&init::InitContext::new(&target_dir).unwrap());
+        let report = compact_graph(&source, &mut target).unwrap();
+        assert_eq!(3, report.nodes);
+        assert_eq!(3, report.relationships);
+        assert!(compact_graph(&source, &mut target).is_err());
+
+        let outbound_types = |ge: &GraphEngine, node_id: u64| {
+            let mut repository = ge.repository.lock().unwrap();
+            let mut types = Vec::new();
+            let mut rel_id = repository.retrieve_vertex_data_by_id(node_id).unwrap().first_outbound_edge;
+            while let Some(rid) = rel_id {
+                let (rel, edge) = repository.retrieve_relationship_by_id(rid).unwrap();
+                types.push(rel.get_labels_ref().join(":"));
+                rel_id = edge.next_outbound_edge;
+            }
+            types
+        };
+        assert_eq!(outbound_types(&source, ids[0]), outbound_types(&target, report.nodes_relocations[&ids[0]]));
+        assert_eq!(1, outbound_types(&target, report.nodes_relocations[&ids[3]]).len());
+        assert!(target.check_stores().is_consistent());
+    }
+}
@@ -0,0 +1,167 @@
+// MIT License
+//
+// Copyright (c) 2022 Alexandre RICCIARDI
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::rc::Rc;
+
+use super::model::*;
+use super::compare_relationships;
+use super::query_context::{QueryContext, estimate_graph_size};
+use super::super::model::*;
+use super::super::graph::*;
+use super::super::graph::traits::*;
+use super::super::matcher::vf2::Matcher;
+
+type NodeComparator = fn(&Node, &Node) -> bool;
+type RelationshipComparator = fn(&Relationship, &Relationship) -> bool;
+type MatchCallback = Box<dyn FnMut(&HashMap<NodeIndex, ProxyNodeId>, &HashMap<ProxyNodeId, NodeIndex>, &PropertyGraph, &mut GraphProxy) -> Option<bool>>;
+
+fn match_node(n0: &Node, n1: &Node) -> bool {
+    if n0.get_id() == None && n0.get_labels_ref().is_empty() {
+        return true;
+    }
+    
+    if n0.get_id() != None && n0.get_id() != n1.get_id() {
+        return false;
+    }
+
+    let mut match_labels = true;
+    for label in n0.get_labels_ref() {
+        if !n1.get_labels_ref().contains(label) {
+            match_labels = false;
+            break;
+        }
+    }
+    let mut match_properties = true;
+    for pred in n0.get_predicates_ref() {
+        if match_properties {
+            for p1 in n1.get_properties_ref() {
+                if p1.get_name() == &pred.name {
+                    match_properties = pred.predicate.eval(p1.get_value());
+                    if !match_properties {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+    match_labels && match_properties
+}
+
+fn match_relationship(e0: &Relationship, e1: &Relationship) -> bool {
+    if !compare_relationships(e0, e1) {
+        return false;
+    }
+    if e0.get_id() == None && e0.get_labels_ref().is_empty() {
+        return true;
+    }
+    
+    if e0.get_id() != None && e0.get_id() != e1.get_id() {
+        return false;
+    }
+
+    // the labels of a pattern relationship are alternative types
+    let match_labels = e0.get_labels_ref().is_empty() || e0.get_labels_ref().iter().any(|label| e1.get_labels_ref().contains(label));
+    let mut match_properties = true;
+    for pred in e0.get_predicates_ref() {
+        if match_properties {
+            for p1 in e1.get_properties_ref() {
+                if p1.get_name() == &pred.name {
+                    match_properties = pred.predicate.eval(p1.get_value());
+                    if !match_properties {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+    match_labels && match_properties
+}
+
+fn build_match(map0: &HashMap<NodeIndex, ProxyNodeId>, gpattern: &PropertyGraph, proxy: &mut GraphProxy) -> Option<PropertyGraph> {
+    let mut res_match = PropertyGraph::new();
+    proxy.prefetch_nodes(&map0.values().copied().collect::<Vec<ProxyNodeId>>());
+    for index in gpattern.get_nodes_ids() {
+        let pattern_node = gpattern.get_node_ref(&index);
+        let proxy_index = map0[&index];
+        let mut proxy_node = proxy.get_node_ref(&proxy_index)?.clone();
+        proxy_node.set_option_var(pattern_node.get_var());
+        res_match.add_node(proxy_node);
+    }
+    for prel in gpattern.get_relationships_and_edges() {
+        let psource_id = &prel.source;
+        let ptarget_id = &prel.target;
+        let proxy_source_id = map0[psource_id];
+        let proxy_target_id = map0[ptarget_id];
+        for rel_id in proxy.out_edges(&proxy_source_id) {
+            let target_id = proxy.get_target_index(&rel_id);
+            if target_id == proxy_target_id {
+                let rel = proxy.get_relationship_ref(&rel_id)?;
+                if compare_relationships(&prel.relationship, rel) {
+                    let mut rel_clone = rel.clone();
+                    rel_clone.set_option_var(&prel.relationship.get_var());
+                    res_match.add_relationship(rel_clone, *psource_id, *ptarget_id);
+                }
+            }
+        }
+    }
+    Some(res_match)
+}
+
+/// Lazily yields the graphs matching a pattern, running the matcher only as far as needed.
+pub struct MatchCursor {
+    matcher: Matcher<NodeComparator, RelationshipComparator, MatchCallback>,
+    matches: Rc<RefCell<VecDeque<PropertyGraph>>>,
+    failed: bool,
+}
+
+impl MatchCursor {
+    /// The matching stops once the query is aborted, the memory of the matched graphs is accounted in the query context.
+    pub fn new(pattern: &PropertyGraph, graph_proxy: GraphProxy, nodes_order: Vec<NodeIndex>, query_context: QueryContext) -> Self {
+        let matches = Rc::new(RefCell::new(VecDeque::new()));
+        let found = matches.clone();
+        let matcher_context = query_context.clone();
+        let callback: MatchCallback = Box::new(move |map0, _map1, gpattern, proxy| {
+            let graph = build_match(map0, gpattern, proxy)?;
+            query_context.allocate(estimate_graph_size(&graph)).ok()?;
+            found.borrow_mut().push_back(graph);
+            Some(false)
+        });
+        let matcher = Matcher::new_with_order(Rc::new(pattern.clone()), graph_proxy, nodes_order, match_node as NodeComparator, match_relationship as RelationshipComparator, callback)
+            .with_query_context(matcher_context);
+        MatchCursor{matcher: matcher, matches: matches, failed: false}
+    }
+}
+
+impl Iterator for MatchCursor {
+    type Item = PropertyGraph;
+
+    fn next(&mut self) -> Option<PropertyGraph> {
+        if self.matches.borrow().is_empty() && !self.failed && !self.matcher.is_exhausted() {
+            if self.matcher.process().is_none() {
+                self.failed = true;
+            }
+        }
+        self.matches.borrow_mut().pop_front()
+    }
+}
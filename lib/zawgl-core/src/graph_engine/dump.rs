@@ -0,0 +1,307 @@
+// MIT License
+//
+// Copyright (c) 2022 Alexandre RICCIARDI
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+
+use super::GraphEngine;
+use super::super::model::*;
+use super::super::model::constraints::UniqueConstraint;
+use super::super::repository::properties_repository::{encode_value, decode_element};
+
+const MAGIC: &[u8; 8] = b"ZAWGLDMP";
+const VERSION: u8 = 1;
+
+const END_TAG: u8 = 0;
+const CONSTRAINT_TAG: u8 = 1;
+const NODE_TAG: u8 = 2;
+const RELATIONSHIP_TAG: u8 = 3;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct DumpStats {
+    pub constraints: usize,
+    pub nodes: usize,
+    pub relationships: usize,
+}
+
+pub(super) fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+pub(super) fn write_u32<W: Write>(writer: &mut W, value: u32) -> io::Result<()> {
+    writer.write_all(&value.to_be_bytes())
+}
+
+pub(super) fn write_u64<W: Write>(writer: &mut W, value: u64) -> io::Result<()> {
+    writer.write_all(&value.to_be_bytes())
+}
+
+pub(super) fn write_bytes<W: Write>(writer: &mut W, bytes: &[u8]) -> io::Result<()> {
+    write_u32(writer, bytes.len() as u32)?;
+    writer.write_all(bytes)
+}
+
+pub(super) fn write_labels<W: Write>(writer: &mut W, labels: &Vec<String>) -> io::Result<()> {
+    write_u32(writer, labels.len() as u32)?;
+    for label in labels {
+        write_bytes(writer, label.as_bytes())?;
+    }
+    Ok(())
+}
+
+pub(super) fn write_properties<W: Write>(writer: &mut W, properties: &Vec<Property>) -> io::Result<()> {
+    write_u32(writer, properties.len() as u32)?;
+    for prop in properties {
+        write_bytes(writer, prop.get_name().as_bytes())?;
+        let (value_type, value_bytes) = encode_value(prop.get_value());
+        writer.write_all(&[value_type])?;
+        write_bytes(writer, &value_bytes)?;
+    }
+    Ok(())
+}
+
+pub(super) fn read_u8<R: Read>(reader: &mut R) -> io::Result<u8> {
+    let mut buf = [0u8; 1];
+    reader.read_exact(&mut buf)?;
+    Ok(buf[0])
+}
+
+pub(super) fn read_u32<R: Read>(reader: &mut R) -> io::Result<u32> {
+    let mut buf = [0u8; 4];
+    reader.read_exact(&mut buf)?;
+    Ok(u32::from_be_bytes(buf))
+}
+
+pub(super) fn read_u64<R: Read>(reader: &mut R) -> io::Result<u64> {
+    let mut buf = [0u8; 8];
+    reader.read_exact(&mut buf)?;
+    Ok(u64::from_be_bytes(buf))
+}
+
+fn read_bytes<R: Read>(reader: &mut R) -> io::Result<Vec<u8>> {
+    let size = read_u32(reader)? as usize;
+    let mut buf = vec![0u8; size];
+    reader.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+fn read_string<R: Read>(reader: &mut R) -> io::Result<String> {
+    String::from_utf8(read_bytes(reader)?).map_err(|_| invalid_data("invalid utf-8 string"))
+}
+
+pub(super) fn read_labels<R: Read>(reader: &mut R) -> io::Result<Vec<String>> {
+    let count = read_u32(reader)?;
+    (0..count).map(|_| read_string(reader)).collect()
+}
+
+pub(super) fn read_properties<R: Read>(reader: &mut R) -> io::Result<Vec<Property>> {
+    let count = read_u32(reader)?;
+    let mut properties = Vec::new();
+    for _ in 0..count {
+        let name = read_string(reader)?;
+        let value_type = read_u8(reader)?;
+        let value = decode_element(value_type, &read_bytes(reader)?).ok_or_else(|| invalid_data("invalid property value"))?;
+        properties.push(Property::new(name, value));
+    }
+    Ok(properties)
+}
+
+/// Writes the whole graph: unique constraints first, then every node and its outbound relationships.
+/// Labels and properties indexes are not written, they are rebuilt by the restore.
+pub fn dump_graph<W: Write>(graph_engine: &GraphEngine, writer: &mut W) -> io::Result<DumpStats> {
+    let mut stats = DumpStats::default();
+    let mut repository = graph_engine.repository.lock().unwrap();
+    writer.write_all(MAGIC)?;
+    writer.write_all(&[VERSION])?;
+    for constraint in repository.get_constraints() {
+        writer.write_all(&[CONSTRAINT_TAG])?;
+        write_bytes(writer, constraint.label.as_bytes())?;
+        write_bytes(writer, constraint.property_name.as_bytes())?;
+        stats.constraints += 1;
+    }
+    let nodes_ids = repository.retrieve_all_nodes_ids().ok_or_else(|| invalid_data("can't read nodes store"))?;
+    for node_id in &nodes_ids {
+        let (node, _) = repository.retrieve_node_by_id(*node_id).ok_or_else(|| invalid_data("can't read node"))?;
+        writer.write_all(&[NODE_TAG])?;
+        write_u64(writer, *node_id)?;
+        write_labels(writer, node.get_labels_ref())?;
+        write_properties(writer, node.get_properties_ref())?;
+        stats.nodes += 1;
+    }
+    for node_id in &nodes_ids {
+        let mut rel_id = repository.retrieve_vertex_data_by_id(*node_id).and_then(|vertex| vertex.first_outbound_edge);
+        while let Some(rid) = rel_id {
+            let (rel, edge) = repository.retrieve_relationship_by_id(rid).ok_or_else(|| invalid_data("can't read relationship"))?;
+            writer.write_all(&[RELATIONSHIP_TAG])?;
+            write_u64(writer, edge.source)?;
+            write_u64(writer, edge.target)?;
+            write_labels(writer, rel.get_labels_ref())?;
+            write_properties(writer, rel.get_properties_ref())?;
+            stats.relationships += 1;
+            rel_id = edge.next_outbound_edge;
+        }
+    }
+    writer.write_all(&[END_TAG])?;
+    writer.flush()?;
+    Ok(stats)
+}
+
+/// Restores a dump into an empty graph, node ids are reassigned by the stores.
+pub fn restore_graph<R: Read>(graph_engine: &mut GraphEngine, reader: &mut R) -> io::Result<DumpStats> {
+    let mut stats = DumpStats::default();
+    let mut magic = [0u8; 8];
+    reader.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(invalid_data("not a zawgl dump"));
+    }
+    let version = read_u8(reader)?;
+    if version != VERSION {
+        return Err(invalid_data(&format!("unsupported dump version {}", version)));
+    }
+    let repository_lock = graph_engine.repository.clone();
+    let mut repository = repository_lock.lock().unwrap();
+    if !repository.retrieve_all_nodes_ids().unwrap_or_default().is_empty() {
+        return Err(io::Error::new(io::ErrorKind::AlreadyExists, "target database is not empty"));
+    }
+    let mut nodes_ids = HashMap::new();
+    loop {
+        match read_u8(reader)? {
+            END_TAG => break,
+            CONSTRAINT_TAG => {
+                let label = read_string(reader)?;
+                let property_name = read_string(reader)?;
+                repository.create_unique_constraint(&UniqueConstraint::new(&label, &property_name)).map_err(|err| invalid_data(&err.to_string()))?;
+                stats.constraints += 1;
+            },
+            NODE_TAG => {
+                let dump_id = read_u64(reader)?;
+                let mut node = Node::new();
+                node.set_labels(read_labels(reader)?);
+                node.set_properties(read_properties(reader)?);
+                let created = repository.create_node(&node).map_err(|err| invalid_data(&err.to_string()))?;
+                nodes_ids.insert(dump_id, created.get_id().ok_or_else(|| invalid_data("missing node id"))?);
+                stats.nodes += 1;
+            },
+            RELATIONSHIP_TAG => {
+                let source = read_u64(reader)?;
+                let target = read_u64(reader)?;
+                let mut rel = Relationship::new();
+                rel.set_labels(read_labels(reader)?);
+                rel.set_properties(read_properties(reader)?);
+                match (nodes_ids.get(&source), nodes_ids.get(&target)) {
+                    (Some(source_id), Some(target_id)) => {
+                        repository.create_relationship(&rel, *source_id, *target_id).map_err(|err| invalid_data(&err.to_string()))?;
+                    },
+                    _ => return Err(invalid_data("relationship references an unknown node")),
+                }
+                stats.relationships += 1;
+            },
+            tag => return Err(invalid_data(&format!("unknown record tag {}", tag))),
+        }
+    }
+    drop(repository);
+    graph_engine.sync();
+    Ok(stats)
+}
+
+pub fn dump_database(ctx: &init::InitContext, file_path: &str) -> io::Result<DumpStats> {
+    let graph_engine = GraphEngine::new(ctx);
+    let mut writer = BufWriter::new(File::create(file_path)?);
+    dump_graph(&graph_engine, &mut writer)
+}
+
+pub fn restore_database(ctx: &init::InitContext, file_path: &str) -> io::Result<DumpStats> {
+    let mut graph_engine = GraphEngine::new(ctx);
+    let mut reader = BufReader::new(File::open(file_path)?);
+    restore_graph(&mut graph_engine, &mut reader)
+}
+
+#[cfg(test)]
+mod test_dump {
+    use super::*;
+    use std::collections::BTreeMap;
+    use super::super::super::test_utils::*;
+    use super::super::super::model::temporal::Date;
+
+    fn make_node(label: &str, props: Vec<(&str, PropertyValue)>) -> Node {
+        let mut node = Node::new();
+        node.set_labels(vec![String::from(label)]);
+        node.set_properties(props.into_iter().map(|(name, value)| Property::new(String::from(name), value)).collect());
+        node
+    }
+
+    fn describe_properties(props: &Vec<Property>) -> Vec<(String, PropertyValue)> {
+        props.iter().map(|p| (String::from(p.get_name()), p.get_value().clone())).collect()
+    }
+
+    fn describe_graph(ge: &GraphEngine) -> Vec<String> {
+        let mut repository = ge.repository.lock().unwrap();
+        let mut res = Vec::new();
+        for node_id in repository.retrieve_all_nodes_ids().unwrap() {
+            let (node, vertex) = repository.retrieve_node_by_id(node_id).unwrap();
+            let mut rel_id = vertex.first_outbound_edge;
+            while let Some(rid) = rel_id {
+                let (rel, edge) = repository.retrieve_relationship_by_id(rid).unwrap();
+                let target = repository.retrieve_node_by_id(edge.target).unwrap().0;
+                res.push(format!("{:?}{:?}-{:?}{:?}->{:?}", node.get_labels_ref(), node.get_properties_ref()[0].get_value(), rel.get_labels_ref(), describe_properties(rel.get_properties_ref()), target.get_properties_ref()[0].get_value()));
+                rel_id = edge.next_outbound_edge;
+            }
+            res.push(format!("{:?}{:?}", node.get_labels_ref(), describe_properties(node.get_properties_ref())));
+        }
+        res.sort();
+        res
+    }
+
+    #[test]
+    fn test_dump_restore() {
+        let main_dir = build_dir_path_and_rm_old("test_dump_restore").unwrap();
+        let ctx = init::InitContext::new(&main_dir).unwrap();
+        let mut ge = GraphEngine::new(&ctx);
+        ge.create_unique_constraint(&UniqueConstraint::new("Person", "email")).unwrap();
+        let mut address = BTreeMap::new();
+        address.insert(String::from("city"), PropertyValue::PString(String::from("Paris")));
+        let alice = ge.create_node(&make_node("Person", vec![("email", PropertyValue::PString(String::from("alice@zawgl.org"))), ("address", PropertyValue::PMap(address))])).unwrap();
+        let bob = ge.create_node(&make_node("Person", vec![("email", PropertyValue::PString(String::from("bob@zawgl.org"))), ("born", PropertyValue::PDate(Date::from_ymd(1990, 4, 2).unwrap()))])).unwrap();
+        let mut rel = Relationship::new();
+        rel.set_labels(vec![String::from("KNOWS")]);
+        rel.set_properties(vec![Property::new(String::from("since"), PropertyValue::PInteger(2010))]);
+        ge.create_relationship(&rel, alice.get_id().unwrap(), bob.get_id().unwrap()).unwrap();
+        ge.create_relationship(&Relationship::new(), bob.get_id().unwrap(), bob.get_id().unwrap()).unwrap();
+        ge.sync();
+
+        let mut dump = Vec::new();
+        let stats = dump_graph(&ge, &mut dump).unwrap();
+        assert_eq!(stats, DumpStats{constraints: 1, nodes: 2, relationships: 2});
+
+        let restore_dir = build_dir_path_and_rm_old("test_dump_restore_target").unwrap();
+        let restore_ctx = init::InitContext::new(&restore_dir).unwrap();
+        let mut restored = GraphEngine::new(&restore_ctx);
+        assert_eq!(restore_graph(&mut restored, &mut dump.as_slice()).unwrap(), stats);
+        assert_eq!(restored.get_constraints(), vec![UniqueConstraint::new("Person", "email")]);
+        assert!(restored.create_node(&make_node("Person", vec![("email", PropertyValue::PString(String::from("bob@zawgl.org")))])).is_err());
+
+        assert_eq!(describe_graph(&ge), describe_graph(&restored));
+        assert!(restore_graph(&mut restored, &mut dump.as_slice()).is_err());
+        assert!(restore_graph(&mut ge, &mut &b"garbage!"[..]).is_err());
+    }
+}
@@ -0,0 +1,268 @@
+// MIT License
+//
+// Copyright (c) 2022 Alexandre RICCIARDI
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use std::collections::{BTreeMap, HashSet};
+use std::io::{self, Write};
+
+use super::GraphEngine;
+use super::super::graph::traits::*;
+use super::super::model::*;
+
+/// Property used by the cypher script to reconnect relationships to their nodes.
+pub const EXPORT_ID_PROPERTY: &str = "_export_id";
+
+struct ExportedRelationship {
+    relationship: Relationship,
+    source: u64,
+    target: u64,
+}
+
+fn collect_graph(graph_engine: &mut GraphEngine) -> io::Result<(Vec<Node>, Vec<ExportedRelationship>)> {
+    let not_found = || io::Error::new(io::ErrorKind::NotFound, "can't retrieve graph");
+    let mut graph = graph_engine.retrieve_graph().ok_or_else(not_found)?;
+    let ids = graph.get_nodes_ids();
+    let mut nodes = Vec::new();
+    for id in &ids {
+        nodes.push(graph.get_node_ref(id).ok_or_else(not_found)?.clone());
+    }
+    let mut relationships = Vec::new();
+    for id in &ids {
+        for rel_id in graph.out_edges(id).collect::<Vec<_>>() {
+            let relationship = graph.get_relationship_ref(&rel_id).ok_or_else(not_found)?.clone();
+            let target = graph.get_target_index(&rel_id).store_id;
+            relationships.push(ExportedRelationship{relationship, source: id.store_id, target});
+        }
+    }
+    Ok((nodes, relationships))
+}
+
+fn escape_xml(value: &str) -> String {
+    value.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;").replace('\'', "&apos;")
+}
+
+fn graphml_type(value: &PropertyValue) -> &'static str {
+    match value {
+        PropertyValue::PInteger(_) => "long",
+        PropertyValue::PFloat(_) => "double",
+        PropertyValue::PBool(_) => "boolean",
+        _ => "string",
+    }
+}
+
+fn graphml_value(value: &PropertyValue) -> String {
+    match value {
+        PropertyValue::PString(sval) => sval.clone(),
+        PropertyValue::PInteger(ival) => ival.to_string(),
+        PropertyValue::PFloat(fval) => fval.to_string(),
+        PropertyValue::PBool(bval) => bval.to_string(),
+        PropertyValue::PDateTime(dt) => dt.to_string(),
+        PropertyValue::PDate(date) => date.to_string(),
+        PropertyValue::PDuration(duration) => duration.to_string(),
+        _ => format_cypher_value(value),
+    }
+}
+
+/// Declares a GraphML key per property name, properties whose values have different types are declared as strings.
+fn collect_keys<'a, I: Iterator<Item = &'a Property>>(properties: I) -> BTreeMap<String, &'static str> {
+    let mut keys = BTreeMap::new();
+    for prop in properties {
+        if *prop.get_value() == PropertyValue::PNull {
+            continue;
+        }
+        let value_type = graphml_type(prop.get_value());
+        let key_type = keys.entry(String::from(prop.get_name())).or_insert(value_type);
+        if *key_type != value_type {
+            *key_type = "string";
+        }
+    }
+    keys
+}
+
+fn write_graphml_keys<W: Write>(writer: &mut W, domain: &str, keys: &BTreeMap<String, &'static str>) -> io::Result<()> {
+    for (name, value_type) in keys {
+        writeln!(writer, "  <key id=\"{}_{}\" for=\"{}\" attr.name=\"{}\" attr.type=\"{}\"/>", domain, escape_xml(name), domain, escape_xml(name), value_type)?;
+    }
+    Ok(())
+}
+
+fn write_graphml_data<W: Write>(writer: &mut W, domain: &str, properties: &[Property]) -> io::Result<()> {
+    for prop in properties {
+        if *prop.get_value() != PropertyValue::PNull {
+            write!(writer, "<data key=\"{}_{}\">{}</data>", domain, escape_xml(prop.get_name()), escape_xml(&graphml_value(prop.get_value())))?;
+        }
+    }
+    Ok(())
+}
+
+/// Writes the whole graph as GraphML, node labels and relationship types are exported as `labels` and `label` attributes.
+pub fn export_graphml<W: Write>(graph_engine: &mut GraphEngine, writer: &mut W) -> io::Result<()> {
+    let (nodes, relationships) = collect_graph(graph_engine)?;
+    writeln!(writer, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+    writeln!(writer, "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">")?;
+    writeln!(writer, "  <key id=\"labels\" for=\"node\" attr.name=\"labels\" attr.type=\"string\"/>")?;
+    writeln!(writer, "  <key id=\"label\" for=\"edge\" attr.name=\"label\" attr.type=\"string\"/>")?;
+    write_graphml_keys(writer, "node", &collect_keys(nodes.iter().flat_map(|n| n.get_properties_ref())))?;
+    write_graphml_keys(writer, "edge", &collect_keys(relationships.iter().flat_map(|r| r.relationship.get_properties_ref())))?;
+    writeln!(writer, "  <graph id=\"G\" edgedefault=\"directed\">")?;
+    for node in &nodes {
+        let labels = node.get_labels_ref().iter().map(|l| format!(":{}", l)).collect::<String>();
+        write!(writer, "    <node id=\"n{}\" labels=\"{}\">", node.get_id().unwrap_or_default(), escape_xml(&labels))?;
+        write!(writer, "<data key=\"labels\">{}</data>", escape_xml(&labels))?;
+        write_graphml_data(writer, "node", node.get_properties_ref())?;
+        writeln!(writer, "</node>")?;
+    }
+    for rel in &relationships {
+        let label = rel.relationship.get_labels_ref().join(":");
+        write!(writer, "    <edge id=\"e{}\" source=\"n{}\" target=\"n{}\" label=\"{}\">", rel.relationship.get_id().unwrap_or_default(), rel.source, rel.target, escape_xml(&label))?;
+        write!(writer, "<data key=\"label\">{}</data>", escape_xml(&label))?;
+        write_graphml_data(writer, "edge", rel.relationship.get_properties_ref())?;
+        writeln!(writer, "</edge>")?;
+    }
+    writeln!(writer, "  </graph>")?;
+    writeln!(writer, "</graphml>")?;
+    writer.flush()
+}
+
+fn format_cypher_name(name: &str) -> String {
+    let plain = name.chars().next().is_some_and(|c| c.is_alphabetic() || c == '_') && name.chars().all(|c| c.is_alphanumeric() || c == '_');
+    if plain {
+        String::from(name)
+    } else {
+        format!("`{}`", name.replace('`', "``"))
+    }
+}
+
+fn format_cypher_string(value: &str) -> String {
+    format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+/// Formats a property value as a cypher literal.
+pub fn format_cypher_value(value: &PropertyValue) -> String {
+    match value {
+        PropertyValue::PString(sval) => format_cypher_string(sval),
+        PropertyValue::PInteger(ival) => ival.to_string(),
+        PropertyValue::PFloat(fval) => format!("{:?}", fval),
+        PropertyValue::PBool(bval) => bval.to_string(),
+        PropertyValue::PList(values) => format!("[{}]", values.iter().map(format_cypher_value).collect::<Vec<String>>().join(", ")),
+        PropertyValue::PMap(entries) => format!("{{{}}}", entries.iter().map(|(k, v)| format!("{}: {}", format_cypher_name(k), format_cypher_value(v))).collect::<Vec<String>>().join(", ")),
+        PropertyValue::PDateTime(dt) => format!("datetime({})", format_cypher_string(&dt.to_string())),
+        PropertyValue::PDate(date) => format!("date({})", format_cypher_string(&date.to_string())),
+        PropertyValue::PDuration(duration) => format!("duration({})", format_cypher_string(&duration.to_string())),
+        PropertyValue::PNull => String::from("null"),
+    }
+}
+
+fn format_cypher_properties(properties: &[Property], export_id: Option<u64>) -> String {
+    let mut entries = properties.iter()
+        .filter(|p| *p.get_value() != PropertyValue::PNull)
+        .map(|p| format!("{}: {}", format_cypher_name(p.get_name()), format_cypher_value(p.get_value())))
+        .collect::<Vec<String>>();
+    if let Some(id) = export_id {
+        entries.push(format!("{}: {}", EXPORT_ID_PROPERTY, id));
+    }
+    if entries.is_empty() {
+        String::new()
+    } else {
+        format!(" {{{}}}", entries.join(", "))
+    }
+}
+
+fn format_cypher_labels(labels: &[String]) -> String {
+    labels.iter().map(|l| format!(":{}", format_cypher_name(l))).collect()
+}
+
+/// Writes a script of CREATE statements rebuilding the graph.
+/// Nodes having relationships get an `_export_id` property so that each relationship statement can match its nodes.
+pub fn export_cypher<W: Write>(graph_engine: &mut GraphEngine, writer: &mut W) -> io::Result<()> {
+    let (nodes, relationships) = collect_graph(graph_engine)?;
+    let connected = relationships.iter().flat_map(|r| vec![r.source, r.target]).collect::<HashSet<u64>>();
+    for node in &nodes {
+        let export_id = node.get_id().filter(|id| connected.contains(id));
+        writeln!(writer, "CREATE (n{}{});", format_cypher_labels(node.get_labels_ref()), format_cypher_properties(node.get_properties_ref(), export_id))?;
+    }
+    for rel in &relationships {
+        let rel_pattern = format!("[r{}{}]", format_cypher_labels(rel.relationship.get_labels_ref()), format_cypher_properties(rel.relationship.get_properties_ref(), None));
+        if rel.source == rel.target {
+            writeln!(writer, "MATCH (a {{{}: {}}}) CREATE (a)-{}->(a);", EXPORT_ID_PROPERTY, rel.source, rel_pattern)?;
+        } else {
+            writeln!(writer, "MATCH (a {{{}: {}}}), (b {{{}: {}}}) CREATE (a)-{}->(b);", EXPORT_ID_PROPERTY, rel.source, EXPORT_ID_PROPERTY, rel.target, rel_pattern)?;
+        }
+    }
+    writer.flush()
+}
+
+#[cfg(test)]
+mod test_export {
+    use super::*;
+    use super::super::super::test_utils::*;
+    use super::super::super::model::temporal::Date;
+
+    fn make_graph(name: &str) -> (GraphEngine, u64, u64) {
+        let main_dir = build_dir_path_and_rm_old(name).unwrap();
+        let ctx = init::InitContext::new(&main_dir).unwrap();
+        let mut ge = GraphEngine::new(&ctx);
+        let mut alice = Node::new();
+        alice.set_labels(vec![String::from("Person")]);
+        alice.set_properties(vec![Property::new(String::from("name"), PropertyValue::PString(String::from("O'Hara <Alice>"))),
+            Property::new(String::from("age"), PropertyValue::PInteger(32))]);
+        let alice = ge.create_node(&alice).unwrap().get_id().unwrap();
+        let mut city = Node::new();
+        city.set_labels(vec![String::from("City")]);
+        city.set_properties(vec![Property::new(String::from("founded"), PropertyValue::PDate(Date::from_ymd(1610, 1, 1).unwrap())),
+            Property::new(String::from("tags"), PropertyValue::PList(vec![PropertyValue::PFloat(1.0), PropertyValue::PBool(true)]))]);
+        let city = ge.create_node(&city).unwrap().get_id().unwrap();
+        ge.create_node(&Node::new()).unwrap();
+        let mut rel = Relationship::new();
+        rel.set_labels(vec![String::from("LIVES_IN")]);
+        rel.set_properties(vec![Property::new(String::from("since"), PropertyValue::PInteger(2010))]);
+        ge.create_relationship(&rel, alice, city).unwrap();
+        (ge, alice, city)
+    }
+
+    #[test]
+    fn test_export_graphml() {
+        let (mut ge, alice, city) = make_graph("test_export_graphml");
+        let mut out = Vec::new();
+        export_graphml(&mut ge, &mut out).unwrap();
+        let xml = String::from_utf8(out).unwrap();
+        assert!(xml.contains("<key id=\"node_age\" for=\"node\" attr.name=\"age\" attr.type=\"long\"/>"));
+        assert!(xml.contains("<key id=\"edge_since\" for=\"edge\" attr.name=\"since\" attr.type=\"long\"/>"));
+        assert!(xml.contains(&format!("<node id=\"n{}\" labels=\":Person\"><data key=\"labels\">:Person</data><data key=\"node_name\">O&apos;Hara &lt;Alice&gt;</data><data key=\"node_age\">32</data></node>", alice)));
+        assert!(xml.contains("<data key=\"node_founded\">1610-01-01</data><data key=\"node_tags\">[1.0, true]</data>"));
+        assert!(xml.contains(&format!("source=\"n{}\" target=\"n{}\" label=\"LIVES_IN\"><data key=\"label\">LIVES_IN</data><data key=\"edge_since\">2010</data></edge>", alice, city)));
+        assert_eq!(xml.matches("<node ").count(), 3);
+    }
+
+    #[test]
+    fn test_export_cypher() {
+        let (mut ge, alice, city) = make_graph("test_export_cypher");
+        let mut out = Vec::new();
+        export_cypher(&mut ge, &mut out).unwrap();
+        let script = String::from_utf8(out).unwrap();
+        let lines = script.lines().collect::<HashSet<&str>>();
+        assert_eq!(lines.len(), 4);
+        assert!(lines.contains(format!("CREATE (n:Person {{name: 'O\\'Hara <Alice>', age: 32, _export_id: {}}});", alice).as_str()));
+        assert!(lines.contains(format!("CREATE (n:City {{founded: date('1610-01-01'), tags: [1.0, true], _export_id: {}}});", city).as_str()));
+        assert!(lines.contains("CREATE (n);"));
+        assert!(lines.contains(format!("MATCH (a {{_export_id: {}}}), (b {{_export_id: {}}}) CREATE (a)-[r:LIVES_IN {{since: 2010}}]->(b);", alice, city).as_str()));
+    }
+}
@@ -19,26 +19,44 @@
 // SOFTWARE.
 
 pub mod model;
+pub mod cursor;
+pub mod planner;
+pub mod dump;
+pub mod export;
+pub mod cdc;
+pub mod compact;
+pub mod query_context;
+pub mod snapshot;
+pub mod analytics;
+pub mod subgraph;
+pub mod paging;
 
 use std::collections::{HashMap, HashSet};
+use super::repository::io::segmented_file_access::remove_segmented_file;
+use std::io;
 use std::sync::{Arc, Mutex};
+use log::error;
 
 use super::model::*;
+use super::error::{GraphError, GraphResult};
 use super::repository::graph_repository::GraphRepository;
+pub use super::repository::{BufferPool, SharedBufferPool};
 use self::model::*;
-use super::matcher::vf2::sub_graph_isomorphism;
-use super::graph::traits::*;
+use self::cursor::MatchCursor;
+use self::query_context::QueryContext;
 
 pub type MutableGraphRepository = Arc<Mutex<GraphRepository>>;
 
 pub struct GraphEngine {
     repository: MutableGraphRepository,
+    change_log: Option<cdc::ChangeLog>,
+    query_context: QueryContext,
 }
 
 fn compare_relationships(r0: &Relationship, r1: &Relationship) -> bool {
     let mut res = true;
     for p0 in r0.get_properties_ref() {
-        if !r1.get_properties_ref().contains(p0) {
+        if !r1.get_properties_ref().iter().any(|p1| p1.get_name() == p0.get_name() && p1.get_value() == p0.get_value()) {
             res = false;
             break;
         }
@@ -48,120 +66,131 @@ fn compare_relationships(r0: &Relationship, r1: &Relationship) -> bool {
 
 impl GraphEngine {
     pub fn new(ctx: &init::InitContext) -> Self {
-        GraphEngine{repository: Arc::new(Mutex::new(GraphRepository::new(ctx)))}
+        Self::open(ctx, &BufferPool::from_context(ctx))
     }
 
-    pub fn create_graph(&mut self, graph: &PropertyGraph) -> Option<PropertyGraph> {
+    /// Engine opening the database, see [`GraphRepository::open`]: it rebuilds the missing secondary indexes,
+    /// which should be done once before the other engines of the database are created.
+    pub fn open(ctx: &init::InitContext, pool: &SharedBufferPool) -> Self {
+        GraphEngine{repository: Arc::new(Mutex::new(GraphRepository::open(ctx, pool))), change_log: cdc::ChangeLog::new(ctx), query_context: QueryContext::new()}
+    }
+
+    /// Engine sharing the pages cached in the buffer pool of its database with the other engines of the database,
+    /// its writes stay private until it syncs.
+    pub fn with_pool(ctx: &init::InitContext, pool: &SharedBufferPool) -> Self {
+        GraphEngine{repository: Arc::new(Mutex::new(GraphRepository::with_pool(ctx, pool))), change_log: cdc::ChangeLog::new(ctx), query_context: QueryContext::new()}
+    }
+
+    pub fn create_graph(&mut self, graph: &PropertyGraph) -> GraphResult<PropertyGraph> {
         self.repository.lock().unwrap().create_graph(graph)
     }
 
-    pub fn create_node(&mut self, node: &Node) -> Option<Node> {
+    pub fn create_node(&mut self, node: &Node) -> GraphResult<Node> {
         self.repository.lock().unwrap().create_node(node)
     }
     
-    pub fn create_relationship(&mut self, rel: &Relationship, source_id: u64, target_id: u64) -> Option<Relationship> {
+    pub fn create_relationship(&mut self, rel: &Relationship, source_id: u64, target_id: u64) -> GraphResult<Relationship> {
         self.repository.lock().unwrap().create_relationship(rel, source_id, target_id)
     }
 
+    pub fn delete_node(&mut self, node_id: u64) -> GraphResult<()> {
+        self.repository.lock().unwrap().delete_node(node_id)
+    }
+
+    pub fn detach_delete_node(&mut self, node_id: u64) -> GraphResult<()> {
+        self.repository.lock().unwrap().detach_delete_node(node_id)
+    }
+
+    pub fn delete_relationship(&mut self, rel_id: u64) -> GraphResult<()> {
+        self.repository.lock().unwrap().delete_relationship(rel_id)
+    }
+
+    pub fn add_node_labels(&mut self, node_id: u64, labels: &[String]) -> GraphResult<Node> {
+        self.repository.lock().unwrap().add_node_labels(node_id, labels)
+    }
+
+    pub fn remove_node_labels(&mut self, node_id: u64, labels: &[String]) -> GraphResult<Node> {
+        self.repository.lock().unwrap().remove_node_labels(node_id, labels)
+    }
+
+    pub fn remove_node_property(&mut self, node_id: u64, name: &str) -> GraphResult<Node> {
+        self.repository.lock().unwrap().remove_node_property(node_id, name)
+    }
+
+    pub fn remove_relationship_property(&mut self, rel_id: u64, name: &str) -> GraphResult<Relationship> {
+        self.repository.lock().unwrap().remove_relationship_property(rel_id, name)
+    }
+
+    pub fn find_node_by_uuid(&mut self, uuid: &str) -> Option<u64> {
+        self.repository.lock().unwrap().find_node_by_uuid(uuid)
+    }
+
+    pub fn find_relationship_by_uuid(&mut self, uuid: &str) -> Option<u64> {
+        self.repository.lock().unwrap().find_relationship_by_uuid(uuid)
+    }
+
+    pub fn create_unique_constraint(&mut self, constraint: &constraints::UniqueConstraint) -> GraphResult<()> {
+        self.repository.lock().unwrap().create_unique_constraint(constraint)
+    }
+
+    pub fn get_constraints(&self) -> Vec<constraints::UniqueConstraint> {
+        self.repository.lock().unwrap().get_constraints().clone()
+    }
+
     pub fn match_pattern(&mut self, pattern: &PropertyGraph) -> Option<Vec<PropertyGraph>> {
-        let mut graph_proxy = GraphProxy::new(self.repository.clone(), pattern)?;
-        let mut res = Vec::new();
-        sub_graph_isomorphism(pattern, &mut graph_proxy, 
-        |n0, n1| {
-            if n0.get_id() == None && n0.get_labels_ref().is_empty() {
-                return true;
-            }
-            
-            if n0.get_id() != None && n0.get_id() != n1.get_id() {
-                return false;
-            }
+        Some(self.match_pattern_cursor(pattern)?.collect())
+    }
 
-            let mut match_labels = true;
-            for label in n0.get_labels_ref() {
-                if !n1.get_labels_ref().contains(label) {
-                    match_labels = false;
-                    break;
-                }
-            }
-            let mut match_properties = true;
-            for pred in n0.get_predicates_ref() {
-                if match_properties {
-                    for p1 in n1.get_properties_ref() {
-                        if p1.get_name() == &pred.name {
-                            match_properties = pred.predicate.eval(p1.get_value());
-                            if !match_properties {
-                                break;
-                            }
-                        }
-                    }
-                }
-            }
-            match_labels && match_properties
-        },
-        |e0, e1| {
-            if e0.get_id() == None && e0.get_labels_ref().is_empty() {
-                return true;
-            }
-            
-            if e0.get_id() != None && e0.get_id() != e1.get_id() {
-                return false;
-            }
+    pub fn match_pattern_cursor(&mut self, pattern: &PropertyGraph) -> Option<MatchCursor> {
+        let nodes_order = {
+            let repository = self.repository.lock().unwrap();
+            planner::plan_nodes_order(pattern, repository.get_statistics(), repository.get_constraints())
+        };
+        let graph_proxy = GraphProxy::new(self.repository.clone(), pattern)?;
+        Some(MatchCursor::new(pattern, graph_proxy, nodes_order, self.query_context.clone()))
+    }
 
-            let mut match_labels = true;
-            for label in e0.get_labels_ref() {
-                if !e1.get_labels_ref().contains(label) {
-                    match_labels = false;
-                    break;
-                }
-            }
-            let mut match_properties = true;
-            for pred in e0.get_predicates_ref() {
-                if match_properties {
-                    for p1 in e1.get_properties_ref() {
-                        if p1.get_name() == &pred.name {
-                            match_properties = pred.predicate.eval(p1.get_value());
-                            if !match_properties {
-                                break;
-                            }
-                        }
-                    }
-                }
-            }
-            match_labels && match_properties
-        },
-        |map0, _map1, gpattern, proxy| {
-            let mut res_match = PropertyGraph::new();
-            for index in gpattern.get_nodes_ids() {
-                let pattern_node = gpattern.get_node_ref(&index);
-                let proxy_index = map0[&index];
-                let mut proxy_node = proxy.get_node_ref(&proxy_index)?.clone();
-                proxy_node.set_option_var(pattern_node.get_var());
-                res_match.add_node(proxy_node);
-            }
-            for prel in gpattern.get_relationships_and_edges() {
-                let psource_id = &prel.source;
-                let ptarget_id = &prel.target;
-                let proxy_source_id = map0[psource_id];
-                let proxy_target_id = map0[ptarget_id];
-                for rel_id in proxy.out_edges(&proxy_source_id) {
-                    let target_id = proxy.get_target_index(&rel_id);
-                    if target_id == proxy_target_id {
-                        let rel = proxy.get_relationship_ref(&rel_id)?;
-                        if compare_relationships(&prel.relationship, rel) {
-                            let mut rel_clone = rel.clone();
-                            rel_clone.set_option_var(&prel.relationship.get_var());
-                            res_match.add_relationship(rel_clone, *psource_id, *ptarget_id);
-                        }
-                    }
-                }
-            }
-            res.push(res_match);
-            Some(true)
-        });
-        Some(res)
+    /// Context of the query run by the engine, the matches stop once it is aborted.
+    pub fn set_query_context(&mut self, query_context: QueryContext) {
+        self.query_context = query_context;
+    }
+
+    pub fn get_query_context(&self) -> &QueryContext {
+        &self.query_context
+    }
+
+    pub fn get_statistics(&self) -> statistics::GraphStatistics {
+        self.repository.lock().unwrap().get_statistics().clone()
+    }
+
+    pub fn get_page_cache_statistics(&self) -> statistics::PageCacheStatistics {
+        self.repository.lock().unwrap().get_page_cache_statistics()
     }
 
-    pub fn match_patterns_and_create(&mut self, patterns: &Vec<PropertyGraph>) -> Option<Vec<Vec<PropertyGraph>>> {
+    /// Preloads the stores into the page cache, so that the first queries after startup read from memory.
+    pub fn warm_up(&self, indexes: &[&str]) -> Option<statistics::WarmUpReport> {
+        self.repository.lock().unwrap().warm_up(indexes)
+    }
+
+    pub fn check_indexes(&self) -> Vec<check::IndexCheckReport> {
+        self.repository.lock().unwrap().check_indexes()
+    }
+
+    pub fn check_stores(&self) -> check::StoreCheckReport {
+        self.repository.lock().unwrap().check_stores()
+    }
+
+    pub fn plan_pattern(&self, pattern: &PropertyGraph) -> Vec<planner::NodePlan> {
+        let repository = self.repository.lock().unwrap();
+        planner::plan_pattern(pattern, repository.get_statistics(), repository.get_constraints())
+    }
+
+    pub fn estimate_node_cardinality(&self, node: &Node) -> f64 {
+        let repository = self.repository.lock().unwrap();
+        planner::estimate_node_cardinality(node, repository.get_statistics(), repository.get_constraints())
+    }
+
+    pub fn match_patterns_and_create(&mut self, patterns: &Vec<PropertyGraph>) -> GraphResult<Vec<Vec<PropertyGraph>>> {
         let mut matched_patterns = Vec::new();
 
         for pattern in patterns {
@@ -206,7 +235,7 @@ impl GraphEngine {
                 }
             }
 
-            let res = self.match_pattern(&match_pattern)?;
+            let res = self.match_pattern(&match_pattern).ok_or(GraphError::PatternLoad)?;
 
             matched_patterns.push((map_nodes_ids, res, pattern));
         }
@@ -226,8 +255,8 @@ impl GraphEngine {
                     if *re.relationship.get_status() == Status::Create {
                         let source_index = matched.0[&re.source];
                         let target_index = matched.0[&re.target];
-                        let source = matched_graph.get_node_ref(&source_index).get_id()?;
-                        let target = matched_graph.get_node_ref(&target_index).get_id()?;
+                        let source = matched_graph.get_node_ref(&source_index).get_id().ok_or(GraphError::PatternLoad)?;
+                        let target = matched_graph.get_node_ref(&target_index).get_id().ok_or(GraphError::PatternLoad)?;
                         let res = self.create_relationship(&re.relationship, source, target)?;
                         matched_graph.add_relationship(res, source_index, target_index);
                     }
@@ -237,26 +266,125 @@ impl GraphEngine {
             results.push(matched_graphs);
         }
         
-        Some(results)
+        Ok(results)
     }
 
+    /// Matches the patterns then deletes the elements flagged with the delete status,
+    /// deleted nodes are detached from their relationships first.
+    pub fn match_patterns_and_delete(&mut self, patterns: &Vec<PropertyGraph>) -> GraphResult<Vec<Vec<PropertyGraph>>> {
+        let mut results = Vec::new();
+        let mut nodes_to_delete = HashSet::new();
+        let mut relationships_to_delete = HashSet::new();
+        for pattern in patterns {
+            let matched_graphs = self.match_pattern(pattern).ok_or(GraphError::PatternLoad)?;
+            for matched_graph in &matched_graphs {
+                for re in pattern.get_relationships_and_edges() {
+                    if *re.relationship.get_status() == Status::Delete {
+                        for matched_re in matched_graph.get_relationships_and_edges() {
+                            let same_labels = re.relationship.get_labels_ref().is_empty() || matched_re.relationship.get_labels_ref().iter().any(|l| re.relationship.get_labels_ref().contains(l));
+                            if matched_re.source == re.source && matched_re.target == re.target && same_labels {
+                                relationships_to_delete.insert(matched_re.relationship.get_id().ok_or(GraphError::PatternLoad)?);
+                            }
+                        }
+                    }
+                }
+                for nid in pattern.get_nodes_ids() {
+                    if *pattern.get_node_ref(&nid).get_status() == Status::Delete {
+                        nodes_to_delete.insert(matched_graph.get_node_ref(&nid).get_id().ok_or(GraphError::PatternLoad)?);
+                    }
+                }
+            }
+            results.push(matched_graphs);
+        }
+        for rel_id in relationships_to_delete {
+            self.delete_relationship(rel_id)?;
+        }
+        for node_id in nodes_to_delete {
+            self.detach_delete_node(node_id)?;
+        }
+        Ok(results)
+    }
 
     pub fn retrieve_graph(&mut self) -> Option<GraphProxy> {
         GraphProxy::new_full(self.repository.clone())
     }
 
+    /// Proxy of the nodes selected by the filter and of the relationships between them, the other nodes
+    /// are not loaded.
+    pub fn retrieve_subgraph(&mut self, filter: &subgraph::SubgraphFilter) -> Option<GraphProxy> {
+        let nodes_ids = filter.fetch_nodes_ids(&self.repository)?;
+        Some(GraphProxy::new_subgraph(self.repository.clone(), nodes_ids))
+    }
+
+    /// Page of at most `limit` nodes with ids greater than the cursor and of their outbound relationships,
+    /// the first page is retrieved without cursor.
+    pub fn retrieve_graph_page(&mut self, after: Option<u64>, limit: usize) -> Option<paging::GraphPage> {
+        paging::GraphPage::load(&self.repository, after.unwrap_or(0), limit)
+    }
+
+    /// Copies the whole graph into a dense in-memory graph.
+    pub fn retrieve_snapshot(&mut self) -> Option<snapshot::GraphSnapshot> {
+        snapshot::GraphSnapshot::from_proxy(&mut self.retrieve_graph()?)
+    }
+
+    /// Records the changes made by the engine even if change data capture is disabled.
+    pub fn enable_change_capture(&mut self) {
+        self.repository.lock().unwrap().set_capture_changes(true);
+    }
+
+    /// Changes made since the last sync, if they are recorded.
+    pub fn get_changes(&self) -> Vec<changes::ChangeEvent> {
+        self.repository.lock().unwrap().get_changes().clone()
+    }
+
+    /// True once another engine of the database synced since this engine was created or synced: the engine keeps
+    /// reading the stores as they were, its writes can't be synced over the newer commits.
+    pub fn is_outdated(&self) -> bool {
+        self.repository.lock().unwrap().is_outdated()
+    }
+
+    /// Syncs the stores then appends the changes since the last sync to the change log.
     pub fn sync(&mut self) {
-        self.repository.lock().unwrap().sync();
+        let changes = {
+            let mut repository = self.repository.lock().unwrap();
+            repository.sync();
+            repository.take_changes()
+        };
+        if let Some(change_log) = self.change_log.as_ref().filter(|_| !changes.is_empty()) {
+            if let Err(err) = change_log.append(&changes) {
+                error!("appending to the change log: {}", err);
+            }
+        }
+    }
+}
+
+/// Removes the secondary indexes of a database, they are rebuilt from the stores when it is opened.
+pub fn rebuild_indexes(ctx: &init::InitContext) -> io::Result<GraphEngine> {
+    let indexes_paths = ctx.get_indexes_paths().ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "invalid database directory"))?;
+    for index_path in indexes_paths {
+        if let Err(err) = remove_segmented_file(&index_path) {
+            if err.kind() != io::ErrorKind::NotFound {
+                return Err(err);
+            }
+        }
     }
+    Ok(GraphEngine::new(ctx))
 }
 
 
 
 #[cfg(test)]
 mod test_graph_engine_match {
-    use crate::{model::{PropertyGraph, Node, Relationship, init::InitContext}, test_utils::{get_tmp_dir_path, build_dir_path_and_rm_old}};
+    use crate::{model::{PropertyGraph, Node, Relationship, Property, PropertyValue, Status, ELEMENT_UUID_PROPERTY, get_element_uuid, init::{InitContext, Durability, IoMode, PropertyCompression, IndexLayout}}, test_utils::build_dir_path_and_rm_old};
+    use std::time::Duration;
 
-    use super::GraphEngine;
+    use super::{BufferPool, GraphEngine, rebuild_indexes};
+    use crate::model::check::INDEXES_NAMES;
+    use super::query_context::{QueryAbort, QueryContext, estimate_graph_size};
+    use super::model::GraphProxy;
+    use crate::graph::traits::GrowableGraphTrait;
+    use crate::model::constraints::UniqueConstraint;
+    use crate::error::GraphError;
 
     #[test]
     fn test_match() {
@@ -280,7 +408,7 @@ mod test_graph_engine_match {
             graph.add_relationship(r32, id3, id2);
             let conf = InitContext::new(&main_dir).expect("can't create context");
             let mut ge = GraphEngine::new(&conf);
-            ge.create_graph(&graph);
+            ge.create_graph(&graph).expect("create graph");
             ge.sync();
 
         }
@@ -330,7 +458,7 @@ mod test_graph_engine_match {
             graph.add_relationship(r33, id3, id3);
             let conf = InitContext::new(&main_dir).expect("can't create context");
             let mut ge = GraphEngine::new(&conf);
-            ge.create_graph(&graph);
+            ge.create_graph(&graph).expect("create graph");
             ge.sync();
 
         }
@@ -377,7 +505,7 @@ mod test_graph_engine_match {
             graph.add_relationship(r33, id3, id3);
             let conf = InitContext::new(&main_dir).expect("can't create context");
             let mut ge = GraphEngine::new(&conf);
-            ge.create_graph(&graph);
+            ge.create_graph(&graph).expect("create graph");
             ge.sync();
 
         }
@@ -397,4 +525,975 @@ mod test_graph_engine_match {
 
         assert_eq!(1, res.len())
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_match_relationship_property() {
+        let main_dir = build_dir_path_and_rm_old("test_match_graph_engine_rel_prop").expect("db path");
+        {
+            let mut graph = PropertyGraph::new();
+            let id1 = graph.add_node(Node::new());
+            let id2 = graph.add_node(Node::new());
+            let id3 = graph.add_node(Node::new());
+            let mut r12 = Relationship::new();
+            r12.set_labels(vec!["KNOWS".to_string()]);
+            r12.get_properties_mut().push(Property::new("since".to_string(), PropertyValue::PInteger(2010)));
+            graph.add_relationship(r12, id1, id2);
+            let mut r32 = Relationship::new();
+            r32.set_labels(vec!["KNOWS".to_string()]);
+            r32.get_properties_mut().push(Property::new("since".to_string(), PropertyValue::PInteger(2020)));
+            graph.add_relationship(r32, id3, id2);
+            let conf = InitContext::new(&main_dir).expect("can't create context");
+            let mut ge = GraphEngine::new(&conf);
+            ge.create_graph(&graph).expect("create graph");
+            ge.sync();
+        }
+
+        let conf = InitContext::new(&main_dir).expect("can't create context");
+        let mut ge_load = GraphEngine::new(&conf);
+
+        let mut pattern = PropertyGraph::new();
+        let id1 = pattern.add_node(Node::new());
+        let id2 = pattern.add_node(Node::new());
+        let mut r = Relationship::new();
+        r.get_properties_mut().push(Property::new("since".to_string(), PropertyValue::PInteger(2020)));
+        pattern.add_relationship(r, id1, id2);
+
+        let res = ge_load.match_pattern(&pattern).expect("graphs");
+
+        assert_eq!(1, res.len());
+        let rel = &res[0].get_relationships()[0];
+        assert_eq!(Some(&PropertyValue::PInteger(2020)), rel.get_properties_ref().first().map(|p| p.get_value()));
+    }
+
+    #[test]
+    fn test_segmented_stores() {
+        let main_dir = build_dir_path_and_rm_old("test_segmented_stores").expect("db path");
+        let nb_nodes = 300;
+        {
+            let mut graph = PropertyGraph::new();
+            for value in 0..nb_nodes {
+                let mut node = Node::new();
+                node.set_labels(vec!["Person".to_string()]);
+                node.get_properties_mut().push(Property::new("id".to_string(), PropertyValue::PInteger(value)));
+                graph.add_node(node);
+            }
+            let conf = InitContext::new(&main_dir).expect("can't create context").with_segment_size(2 * 4096);
+            let mut ge = GraphEngine::new(&conf);
+            ge.create_graph(&graph).expect("create graph");
+            ge.sync();
+        }
+        let conf = InitContext::new(&main_dir).expect("can't create context").with_io_mode(IoMode::Mmap);
+        assert!(std::path::Path::new(&format!("{}.1", conf.get_nodes_store_path().unwrap())).exists());
+        let mut ge_load = GraphEngine::new(&conf);
+        let mut pattern = PropertyGraph::new();
+        let mut node = Node::new();
+        node.set_labels(vec!["Person".to_string()]);
+        pattern.add_node(node);
+        let mut values = ge_load.match_pattern(&pattern).expect("graphs").iter().filter_map(|g| match g.get_nodes()[0].get_properties_ref().first().map(|p| p.get_value()) {
+            Some(PropertyValue::PInteger(value)) => Some(*value),
+            _ => None,
+        }).collect::<Vec<i64>>();
+        values.sort();
+        assert_eq!((0..nb_nodes).collect::<Vec<i64>>(), values);
+    }
+
+    #[test]
+    fn test_compressed_properties() {
+        let main_dir = build_dir_path_and_rm_old("test_compressed_properties").expect("db path");
+        let description = "a long description compressed in the dynamic store ".repeat(50);
+        {
+            let mut graph = PropertyGraph::new();
+            let mut node = Node::new();
+            node.set_labels(vec!["Document".to_string()]);
+            node.get_properties_mut().push(Property::new("description".to_string(), PropertyValue::PString(description.clone())));
+            graph.add_node(node);
+            let conf = InitContext::new(&main_dir).expect("can't create context").with_property_compression(PropertyCompression::Zstd{threshold: 256});
+            let mut ge = GraphEngine::new(&conf);
+            ge.create_graph(&graph).expect("create graph");
+            ge.sync();
+        }
+        let conf = InitContext::new(&main_dir).expect("can't create context");
+        let mut ge_load = GraphEngine::new(&conf);
+        let mut pattern = PropertyGraph::new();
+        let mut node = Node::new();
+        node.set_labels(vec!["Document".to_string()]);
+        pattern.add_node(node);
+        let res = ge_load.match_pattern(&pattern).expect("graphs");
+        assert_eq!(Some(&PropertyValue::PString(description)), res[0].get_nodes()[0].get_properties_ref().first().map(|p| p.get_value()));
+    }
+
+    #[test]
+    fn test_durability_policies() {
+        for (name, durability) in [("test_durability_on_commit", Durability::OnCommit), ("test_durability_periodic", Durability::Periodic(Duration::from_secs(3600)))] {
+            let main_dir = build_dir_path_and_rm_old(name).expect("db path");
+            {
+                let mut graph = PropertyGraph::new();
+                let id1 = graph.add_node(Node::new());
+                let id2 = graph.add_node(Node::new());
+                let mut r = Relationship::new();
+                r.set_labels(vec!["KNOWS".to_string()]);
+                graph.add_relationship(r, id1, id2);
+                let conf = InitContext::new(&main_dir).expect("can't create context").with_durability(durability);
+                assert_eq!(durability, conf.get_durability());
+                let mut ge = GraphEngine::new(&conf);
+                ge.create_graph(&graph).expect("create graph");
+                ge.sync();
+            }
+            let conf = InitContext::new(&main_dir).expect("can't create context").with_durability(durability);
+            let mut ge_load = GraphEngine::new(&conf);
+            let mut pattern = PropertyGraph::new();
+            let id1 = pattern.add_node(Node::new());
+            let id2 = pattern.add_node(Node::new());
+            let mut r = Relationship::new();
+            r.set_labels(vec!["KNOWS".to_string()]);
+            pattern.add_relationship(r, id1, id2);
+            assert_eq!(1, ge_load.match_pattern(&pattern).expect("graphs").len());
+        }
+    }
+
+    #[test]
+    fn test_match_relationship_type() {
+        let main_dir = build_dir_path_and_rm_old("test_match_graph_engine_rel_type").expect("db path");
+        {
+            let mut graph = PropertyGraph::new();
+            let id1 = graph.add_node(Node::new());
+            let id2 = graph.add_node(Node::new());
+            let id3 = graph.add_node(Node::new());
+            let mut r12 = Relationship::new();
+            r12.set_labels(vec!["KNOWS".to_string()]);
+            graph.add_relationship(r12, id1, id2);
+            let mut r32 = Relationship::new();
+            r32.set_labels(vec!["LIKES".to_string()]);
+            graph.add_relationship(r32, id3, id2);
+            let conf = InitContext::new(&main_dir).expect("can't create context");
+            let mut ge = GraphEngine::new(&conf);
+            ge.create_graph(&graph).expect("create graph");
+            ge.sync();
+        }
+
+        let conf = InitContext::new(&main_dir).expect("can't create context");
+        let mut ge_load = GraphEngine::new(&conf);
+        let likes = ge_load.repository.lock().unwrap().fetch_relationships_ids_with_labels(&vec!["LIKES".to_string()]);
+        assert_eq!(1, likes.len());
+
+        let mut pattern = PropertyGraph::new();
+        let id1 = pattern.add_node(Node::new());
+        let id2 = pattern.add_node(Node::new());
+        let mut r = Relationship::new();
+        r.set_labels(vec!["LIKES".to_string()]);
+        pattern.add_relationship(r, id1, id2);
+
+        let res = ge_load.match_pattern(&pattern).expect("graphs");
+
+        assert_eq!(1, res.len());
+        assert_eq!(likes.iter().next().copied(), res[0].get_relationships()[0].get_id());
+
+        let mut union_pattern = PropertyGraph::new();
+        let id1 = union_pattern.add_node(Node::new());
+        let id2 = union_pattern.add_node(Node::new());
+        let mut r = Relationship::new();
+        r.set_labels(vec!["KNOWS".to_string(), "LIKES".to_string()]);
+        union_pattern.add_relationship(r, id1, id2);
+        assert_eq!(2, ge_load.match_pattern(&union_pattern).expect("graphs").len());
+    }
+
+    #[test]
+    fn test_match_anchored_expansion() {
+        let main_dir = build_dir_path_and_rm_old("test_match_anchored_expansion").expect("db path");
+        let conf = InitContext::new(&main_dir).expect("can't create context");
+        let mut ge = GraphEngine::new(&conf);
+        let mut graph = PropertyGraph::new();
+        let mut person = Node::new();
+        person.set_labels(vec!["Person".to_string()]);
+        let person = graph.add_node(person);
+        for _ in 0..2 {
+            let friend = graph.add_node(Node::new());
+            graph.add_relationship(Relationship::new(), person, friend);
+        }
+        for _ in 0..5 {
+            graph.add_node(Node::new());
+        }
+        ge.create_graph(&graph).expect("graph");
+
+        let mut pattern = PropertyGraph::new();
+        let mut anchor = Node::new();
+        anchor.set_labels(vec!["Person".to_string()]);
+        let anchor = pattern.add_node(anchor);
+        let friend = pattern.add_node(Node::new());
+        pattern.add_relationship(Relationship::new(), anchor, friend);
+
+        let proxy = GraphProxy::new(ge.repository.clone(), &pattern).expect("proxy");
+        assert_eq!(1, proxy.get_nodes_ids().len());
+        assert_eq!(2, ge.match_pattern(&pattern).expect("graphs").len());
+    }
+
+    #[test]
+    fn test_match_relationship_id() {
+        let main_dir = build_dir_path_and_rm_old("test_match_relationship_id").expect("db path");
+        let conf = InitContext::new(&main_dir).expect("can't create context");
+        let mut ge = GraphEngine::new(&conf);
+        let mut graph = PropertyGraph::new();
+        let ids = (0..6).map(|_| graph.add_node(Node::new())).collect::<Vec<_>>();
+        graph.add_relationship(Relationship::new(), ids[0], ids[1]);
+        graph.add_relationship(Relationship::new(), ids[2], ids[3]);
+        let created = ge.create_graph(&graph).expect("graph");
+        let rel_id = created.get_relationships()[1].get_id();
+
+        let mut pattern = PropertyGraph::new();
+        let source = pattern.add_node(Node::new());
+        let target = pattern.add_node(Node::new());
+        let mut rel = Relationship::new();
+        rel.set_id(rel_id);
+        pattern.add_relationship(rel, source, target);
+
+        let proxy = GraphProxy::new(ge.repository.clone(), &pattern).expect("proxy");
+        assert_eq!(2, proxy.get_nodes_ids().len());
+        let res = ge.match_pattern(&pattern).expect("graphs");
+        assert_eq!(1, res.len());
+        assert_eq!(rel_id, res[0].get_relationships()[0].get_id());
+        assert_eq!(created.get_nodes()[2].get_id(), res[0].get_nodes()[0].get_id());
+    }
+
+    #[test]
+    fn test_retrieve_by_ids_batch() {
+        let main_dir = build_dir_path_and_rm_old("test_retrieve_by_ids_batch").expect("db path");
+        let conf = InitContext::new(&main_dir).expect("can't create context");
+        let mut ge = GraphEngine::new(&conf);
+        let mut graph = PropertyGraph::new();
+        let mut previous = None;
+        for i in 0..100 {
+            let mut n = Node::new();
+            n.set_labels(vec!["Item".to_string()]);
+            n.set_properties(vec![Property::new("rank".to_string(), PropertyValue::PInteger(i))]);
+            let id = graph.add_node(n);
+            if let Some(source) = previous {
+                graph.add_relationship(Relationship::new(), source, id);
+            }
+            previous = Some(id);
+        }
+        let created = ge.create_graph(&graph).expect("graph");
+        let nodes_ids = created.get_nodes().iter().map(|n| n.get_id().expect("id")).collect::<Vec<u64>>();
+        let rels_ids = created.get_relationships().iter().map(|r| r.get_id().expect("id")).collect::<Vec<u64>>();
+        ge.delete_relationship(rels_ids[0]).expect("delete");
+
+        let mut repository = ge.repository.lock().unwrap();
+        let wanted = vec![nodes_ids[42], nodes_ids[3], nodes_ids[42], nodes_ids[99]];
+        let nodes = repository.retrieve_nodes_by_ids(&wanted);
+        let mut expected = vec![nodes_ids[3], nodes_ids[42], nodes_ids[99]];
+        expected.sort_unstable();
+        assert_eq!(expected, nodes.iter().map(|n| n.0.get_id().expect("id")).collect::<Vec<u64>>());
+        let ranked = nodes.iter().find(|n| n.0.get_id() == Some(nodes_ids[42])).expect("node");
+        assert_eq!(&PropertyValue::PInteger(42), ranked.0.get_properties_ref()[0].get_value());
+        let max_id = *nodes_ids.iter().max().expect("id");
+        assert_eq!(100, repository.retrieve_nodes_by_id_range(0..max_id + 1).len());
+
+        let rels = repository.retrieve_relationships_by_ids(&rels_ids);
+        assert_eq!(98, rels.len());
+        assert!(rels.iter().all(|r| r.0.get_id() != Some(rels_ids[0])));
+        assert_eq!(98, repository.retrieve_relationships_by_id_range(0..rels_ids.iter().max().expect("id") + 1).len());
+        let edges = repository.retrieve_edges_data_by_ids(&[rels_ids[1], rels_ids[0]]);
+        assert_eq!(vec![(rels_ids[1], nodes_ids[1], nodes_ids[2])], edges.iter().map(|(id, e)| (*id, e.source, e.target)).collect::<Vec<(u64, u64, u64)>>());
+        drop(repository);
+
+        let mut pattern = PropertyGraph::new();
+        let mut item = Node::new();
+        item.set_labels(vec!["Item".to_string()]);
+        let source = pattern.add_node(item);
+        let target = pattern.add_node(Node::new());
+        pattern.add_relationship(Relationship::new(), source, target);
+        assert_eq!(98, ge.match_pattern(&pattern).expect("graphs").len());
+    }
+
+    #[test]
+    fn test_index_layout() {
+        let main_dir = build_dir_path_and_rm_old("test_index_layout_graph_engine").expect("db path");
+        let layout = IndexLayout::new(128, 16).expect("layout");
+        let label = "L".repeat(100);
+        {
+            let conf = InitContext::new(&main_dir).expect("can't create context").with_index_layout(layout);
+            let mut ge = GraphEngine::new(&conf);
+            for _ in 0..50 {
+                let mut n = Node::new();
+                n.set_labels(vec![label.clone()]);
+                ge.create_node(&n).expect("node");
+            }
+            ge.sync();
+        }
+        let conf = InitContext::new(&main_dir).expect("can't create context");
+        let ge = GraphEngine::new(&conf);
+        let mut repository = ge.repository.lock().unwrap();
+        assert_eq!(50, repository.fetch_nodes_ids_with_labels(&vec![label]).len());
+    }
+
+    #[test]
+    fn test_indexes_maintenance_and_rebuild() {
+        let main_dir = build_dir_path_and_rm_old("test_indexes_graph_engine").expect("db path");
+        let conf = InitContext::new(&main_dir).expect("can't create context");
+        let make_node = |label: &str, name: &str| {
+            let mut n = Node::new();
+            n.set_labels(vec![label.to_string()]);
+            n.set_properties(vec![Property::new(name.to_string(), PropertyValue::PInteger(1))]);
+            n
+        };
+        {
+            let mut ge = GraphEngine::new(&conf);
+            let removed = ge.create_node(&make_node("Person", "age")).expect("node").get_id().expect("id");
+            ge.create_node(&make_node("Person", "size")).expect("node");
+            ge.delete_node(removed).expect("delete");
+            ge.create_node(&make_node("City", "size")).expect("node");
+            ge.sync();
+
+            let mut repository = ge.repository.lock().unwrap();
+            assert_eq!(1, repository.fetch_nodes_ids_with_labels(&vec!["Person".to_string()]).len());
+            assert!(repository.fetch_nodes_ids_with_property_keys(&["age"]).is_empty());
+            assert_eq!(2, repository.fetch_nodes_ids_with_property_keys(&["size"]).len());
+        }
+
+        let mut ge = rebuild_indexes(&conf).expect("rebuild");
+        let mut repository = ge.repository.lock().unwrap();
+        assert_eq!(1, repository.fetch_nodes_ids_with_labels(&vec!["Person".to_string()]).len());
+        assert_eq!(1, repository.fetch_nodes_ids_with_labels(&vec!["City".to_string()]).len());
+        assert_eq!(2, repository.fetch_nodes_ids_with_property_keys(&["size"]).len());
+        drop(repository);
+
+        let mut pattern = PropertyGraph::new();
+        let mut n = Node::new();
+        n.set_properties(vec![Property::new("size".to_string(), PropertyValue::PInteger(1))]);
+        pattern.add_node(n);
+        assert_eq!(2, ge.match_pattern(&pattern).expect("match").len());
+    }
+
+    #[test]
+    fn test_check_indexes() {
+        let main_dir = build_dir_path_and_rm_old("test_check_indexes_graph_engine").expect("db path");
+        let conf = InitContext::new(&main_dir).expect("can't create context");
+        let mut ge = GraphEngine::new(&conf);
+        let mut ids = Vec::new();
+        for i in 0..200 {
+            let mut n = Node::new();
+            n.set_labels(vec![String::from("Person")]);
+            n.set_properties(vec![Property::new(String::from("rank"), PropertyValue::PInteger(i % 10))]);
+            ids.push(ge.create_node(&n).expect("node").get_id().expect("id"));
+        }
+        for pair in ids.windows(2) {
+            let mut r = Relationship::new();
+            r.set_labels(vec![String::from("KNOWS")]);
+            ge.create_relationship(&r, pair[0], pair[1]).expect("relationship");
+        }
+        let mut isolated = Node::new();
+        isolated.set_labels(vec![String::from("Person")]);
+        let isolated_id = ge.create_node(&isolated).expect("node").get_id().expect("id");
+        ge.delete_node(isolated_id).expect("delete");
+        ge.sync();
+
+        let reports = ge.check_indexes();
+        assert_eq!(5, reports.len());
+        assert!(reports.iter().all(|report| report.is_consistent()), "{:?}", reports);
+        let labels = reports.iter().find(|report| report.name == "nodes labels").expect("labels report");
+        assert_eq!(1, labels.keys);
+        assert_eq!(200, labels.data_ptrs);
+        let types = reports.iter().find(|report| report.name == "relationships types").expect("types report");
+        assert_eq!(199, types.data_ptrs);
+    }
+
+    #[test]
+    fn test_check_stores() {
+        let main_dir = build_dir_path_and_rm_old("test_check_stores_graph_engine").expect("db path");
+        let conf = InitContext::new(&main_dir).expect("can't create context");
+        let mut ge = GraphEngine::new(&conf);
+        let mut ids = Vec::new();
+        for i in 0..20 {
+            let mut n = Node::new();
+            n.set_labels(vec![String::from("Person")]);
+            n.set_properties(vec![
+                Property::new(String::from("rank"), PropertyValue::PInteger(i)),
+                Property::new(String::from("bio"), PropertyValue::PString("a long biography ".repeat(10))),
+            ]);
+            ids.push(ge.create_node(&n).expect("node").get_id().expect("id"));
+        }
+        let mut rels_ids = Vec::new();
+        for pair in ids.windows(2) {
+            let mut r = Relationship::new();
+            r.set_labels(vec![String::from("KNOWS")]);
+            r.set_properties(vec![Property::new(String::from("since"), PropertyValue::PInteger(2000))]);
+            rels_ids.push(ge.create_relationship(&r, pair[0], pair[1]).expect("relationship").get_id().expect("id"));
+            rels_ids.push(ge.create_relationship(&r, pair[1], pair[0]).expect("relationship").get_id().expect("id"));
+        }
+        let mut self_loop = Relationship::new();
+        self_loop.set_labels(vec![String::from("SELF")]);
+        ge.create_relationship(&self_loop, ids[0], ids[0]).expect("relationship");
+        ge.delete_relationship(rels_ids[10]).expect("delete");
+        ge.sync();
+
+        let report = ge.check_stores();
+        assert!(report.is_consistent(), "{:?}", report.errors);
+        assert_eq!(20, report.nodes);
+        assert_eq!(38, report.relationships);
+        assert_eq!(77, report.properties);
+        assert_eq!(0, report.unreferenced_properties);
+    }
+
+    #[test]
+    fn test_delete() {
+        let main_dir = build_dir_path_and_rm_old("test_delete_graph_engine").expect("db path");
+        let conf = InitContext::new(&main_dir).expect("can't create context");
+        let mut ge = GraphEngine::new(&conf);
+        let mut graph = PropertyGraph::new();
+        let mut n1 = Node::new();
+        n1.set_labels(vec!["Person".to_string()]);
+        let id1 = graph.add_node(n1);
+        let mut n2 = Node::new();
+        n2.set_labels(vec!["Person".to_string()]);
+        let id2 = graph.add_node(n2);
+        let mut r12 = Relationship::new();
+        r12.set_labels(vec!["KNOWS".to_string()]);
+        graph.add_relationship(r12, id1, id2);
+        let mut r21 = Relationship::new();
+        r21.set_labels(vec!["KNOWS".to_string()]);
+        graph.add_relationship(r21, id2, id1);
+        let created = ge.create_graph(&graph).expect("graph");
+        let n1_id = created.get_node_ref(&id1).get_id().expect("id");
+        let n2_id = created.get_node_ref(&id2).get_id().expect("id");
+        let rels_ids = created.get_relationships().iter().map(|r| r.get_id().expect("id")).collect::<Vec<u64>>();
+
+        let mut pattern = PropertyGraph::new();
+        let mut p1 = Node::new();
+        p1.set_labels(vec!["Person".to_string()]);
+        let pid1 = pattern.add_node(p1);
+        let mut p2 = Node::new();
+        p2.set_labels(vec!["Person".to_string()]);
+        let pid2 = pattern.add_node(p2);
+        let mut pr = Relationship::new();
+        pr.set_labels(vec!["KNOWS".to_string()]);
+        pattern.add_relationship(pr, pid1, pid2);
+        assert_eq!(2, ge.match_pattern(&pattern).expect("graphs").len());
+
+        assert_eq!(Err(GraphError::NodeHasRelationships(n1_id)), ge.delete_node(n1_id));
+        ge.delete_relationship(rels_ids[0]).expect("delete relationship");
+        assert_eq!(1, ge.match_pattern(&pattern).expect("graphs").len());
+
+        ge.detach_delete_node(n1_id).expect("detach delete node");
+        assert_eq!(0, ge.match_pattern(&pattern).expect("graphs").len());
+        ge.delete_node(n2_id).expect("delete node");
+
+        let mut single = PropertyGraph::new();
+        let mut p = Node::new();
+        p.set_labels(vec!["Person".to_string()]);
+        single.add_node(p);
+        assert_eq!(0, ge.match_pattern(&single).expect("graphs").len());
+    }
+
+    #[test]
+    fn test_unique_constraint() {
+        let main_dir = build_dir_path_and_rm_old("test_unique_constraint_graph_engine").expect("db path");
+        let make_person = |email: &str| {
+            let mut n = Node::new();
+            n.set_labels(vec!["Person".to_string()]);
+            n.set_properties(vec![Property::new("email".to_string(), PropertyValue::PString(email.to_string()))]);
+            n
+        };
+        {
+            let conf = InitContext::new(&main_dir).expect("can't create context");
+            let mut ge = GraphEngine::new(&conf);
+            ge.create_node(&make_person("a@zawgl.org")).expect("node");
+            let duplicate = ge.create_node(&make_person("a@zawgl.org")).expect("node");
+            assert_eq!(Err(GraphError::UniqueConstraintViolation(UniqueConstraint::new("Person", "email"))), ge.create_unique_constraint(&UniqueConstraint::new("Person", "email")));
+            assert!(ge.get_constraints().is_empty());
+            ge.detach_delete_node(duplicate.get_id().expect("id")).expect("delete");
+            ge.create_unique_constraint(&UniqueConstraint::new("Person", "email")).expect("constraint");
+            assert_eq!(Err(GraphError::UniqueConstraintViolation(UniqueConstraint::new("Person", "email"))), ge.create_node(&make_person("a@zawgl.org")).map(|_| ()));
+            ge.create_node(&make_person("b@zawgl.org")).expect("node");
+            for emails in [["d@zawgl.org", "a@zawgl.org"], ["d@zawgl.org", "d@zawgl.org"]] {
+                let mut pattern = PropertyGraph::new();
+                for email in emails {
+                    pattern.add_node(make_person(email));
+                }
+                assert_eq!(Err(GraphError::UniqueConstraintViolation(UniqueConstraint::new("Person", "email"))), ge.create_graph(&pattern).map(|_| ()));
+            }
+            ge.sync();
+        }
+        let conf = InitContext::new(&main_dir).expect("can't create context");
+        let mut ge = GraphEngine::new(&conf);
+        assert_eq!(vec![UniqueConstraint::new("Person", "email")], ge.get_constraints());
+        assert!(ge.create_node(&make_person("b@zawgl.org")).is_err());
+        ge.create_node(&make_person("c@zawgl.org")).expect("node");
+        ge.create_node(&make_person("d@zawgl.org")).expect("no node of a failed pattern is written");
+    }
+
+    #[test]
+    fn test_element_uuids() {
+        let main_dir = build_dir_path_and_rm_old("test_element_uuids_graph_engine").expect("db path");
+        let conf = InitContext::new(&main_dir).expect("can't create context").with_element_uuids(true);
+        let mut person = Node::new();
+        person.set_labels(vec!["Person".to_string()]);
+        let (a_id, a_uuid, b_uuid, rel_id, rel_uuid) = {
+            let mut ge = GraphEngine::new(&conf);
+            let a = ge.create_node(&person).expect("node");
+            let b = ge.create_node(&person).expect("node");
+            let mut knows = Relationship::new();
+            knows.set_labels(vec!["KNOWS".to_string()]);
+            let rel = ge.create_relationship(&knows, a.get_id().expect("id"), b.get_id().expect("id")).expect("relationship");
+            let a_uuid = get_element_uuid(a.get_properties_ref()).expect("uuid").to_string();
+            let b_uuid = get_element_uuid(b.get_properties_ref()).expect("uuid").to_string();
+            let rel_uuid = get_element_uuid(rel.get_properties_ref()).expect("uuid").to_string();
+            assert_eq!(36, a_uuid.len());
+            assert_ne!(a_uuid, b_uuid);
+            assert_eq!(a.get_id(), ge.find_node_by_uuid(&a_uuid));
+            assert_eq!(rel.get_id(), ge.find_relationship_by_uuid(&rel_uuid));
+
+            let mut duplicate = person.clone();
+            duplicate.set_properties(vec![Property::new(ELEMENT_UUID_PROPERTY.to_string(), PropertyValue::PString(a_uuid.clone()))]);
+            assert_eq!(Err(GraphError::DuplicateUuid(a_uuid.clone())), ge.create_node(&duplicate).map(|_| ()));
+            ge.sync();
+            (a.get_id().expect("id"), a_uuid, b_uuid, rel.get_id().expect("id"), rel_uuid)
+        };
+
+        let mut ge = rebuild_indexes(&conf).expect("rebuild");
+        assert_eq!(Some(a_id), ge.find_node_by_uuid(&a_uuid));
+        assert_eq!(Some(rel_id), ge.find_relationship_by_uuid(&rel_uuid));
+        let mut pattern = PropertyGraph::new();
+        let mut n = Node::new();
+        n.set_status(Status::Match);
+        n.set_properties(vec![Property::new(ELEMENT_UUID_PROPERTY.to_string(), PropertyValue::PString(a_uuid.clone()))]);
+        pattern.add_node(n);
+        let matched = ge.match_pattern(&pattern).expect("graphs");
+        assert_eq!(1, matched.len());
+        assert_eq!(Some(a_id), matched[0].get_nodes()[0].get_id());
+
+        let b_id = ge.find_node_by_uuid(&b_uuid).expect("node");
+        ge.detach_delete_node(b_id).expect("delete");
+        assert!(ge.find_node_by_uuid(&b_uuid).is_none());
+        assert!(ge.find_relationship_by_uuid(&rel_uuid).is_none());
+    }
+
+    #[test]
+    fn test_bounded_page_cache() {
+        let main_dir = build_dir_path_and_rm_old("test_bounded_page_cache_graph_engine").expect("db path");
+        let conf = InitContext::new(&main_dir).expect("can't create context").with_page_cache_size(4 * 4096);
+        let mut ids = Vec::new();
+        {
+            let mut ge = GraphEngine::new(&conf);
+            for i in 0..150 {
+                let mut n = Node::new();
+                n.set_labels(vec!["Person".to_string()]);
+                n.set_properties(vec![Property::new("index".to_string(), PropertyValue::PInteger(i))]);
+                ids.push(ge.create_node(&n).expect("node").get_id().expect("id"));
+            }
+            ge.sync();
+            let stats = ge.get_page_cache_statistics();
+            assert_eq!(stats.capacity, 4);
+            assert_eq!(stats.dirty_pages, 0);
+            assert!(stats.cached_pages <= 4);
+            assert!(stats.evictions > 0);
+        }
+        let mut ge = GraphEngine::new(&conf);
+        for (i, id) in ids.iter().enumerate() {
+            let mut pattern = PropertyGraph::new();
+            let mut n = Node::new();
+            n.set_id(Some(*id));
+            n.set_status(Status::Match);
+            pattern.add_node(n);
+            let matched = ge.match_pattern(&pattern).expect("match");
+            assert_eq!(matched[0].get_nodes()[0].get_properties_ref()[0].get_value(), &PropertyValue::PInteger(i as i64));
+        }
+        let stats = ge.get_page_cache_statistics();
+        assert!(stats.cached_pages <= 4);
+        assert!(stats.hits > 0 && stats.misses > 0);
+    }
+
+    #[test]
+    fn test_warm_up() {
+        let main_dir = build_dir_path_and_rm_old("test_warm_up_graph_engine").expect("db path");
+        let conf = InitContext::new(&main_dir).expect("can't create context");
+        let mut ids = Vec::new();
+        {
+            let mut ge = GraphEngine::new(&conf);
+            for _ in 0..150 {
+                let mut n = Node::new();
+                n.set_labels(vec!["Person".to_string()]);
+                ids.push(ge.create_node(&n).expect("node").get_id().expect("id"));
+            }
+            ge.sync();
+        }
+        let match_all = |ge: &mut GraphEngine| {
+            let misses = ge.get_page_cache_statistics().misses;
+            for id in &ids {
+                let mut pattern = PropertyGraph::new();
+                let mut n = Node::new();
+                n.set_id(Some(*id));
+                n.set_status(Status::Match);
+                pattern.add_node(n);
+                ge.match_pattern(&pattern).expect("match");
+            }
+            ge.get_page_cache_statistics().misses - misses
+        };
+        let cold_misses = match_all(&mut GraphEngine::new(&conf));
+        let mut ge = GraphEngine::new(&conf);
+        assert!(ge.warm_up(&["nodes labels", "unknown"]).is_none());
+        let report = ge.warm_up(&["nodes labels"]).expect("warm up");
+        let stores = report.stores.iter().map(|(name, _)| name.as_str()).collect::<Vec<&str>>();
+        assert_eq!(stores, vec!["nodes", "relationships", "nodes labels"]);
+        assert!(report.stores[0].1 > 1);
+        assert!(report.stores[2].1 > 0);
+        assert!(report.page_cache.cached_pages >= report.get_pages_count());
+        assert!(match_all(&mut ge) < cold_misses);
+
+        let bounded_conf = InitContext::new(&main_dir).expect("can't create context").with_page_cache_size(2 * 4096);
+        let ge = GraphEngine::new(&bounded_conf);
+        let evictions = ge.get_page_cache_statistics().evictions;
+        let report = ge.warm_up(&INDEXES_NAMES).expect("warm up");
+        assert!(report.get_pages_count() <= 2);
+        assert_eq!(report.page_cache.evictions, evictions);
+    }
+
+    #[test]
+    fn test_shared_buffer_pool() {
+        let main_dir = build_dir_path_and_rm_old("test_shared_buffer_pool_graph_engine").expect("db path");
+        let conf = InitContext::new(&main_dir).expect("can't create context");
+        let pool = BufferPool::from_context(&conf);
+        let count_persons = |ge: &mut GraphEngine| {
+            let mut n = Node::new();
+            n.set_labels(vec!["Person".to_string()]);
+            n.set_status(Status::Match);
+            let mut pattern = PropertyGraph::new();
+            pattern.add_node(n);
+            ge.match_pattern(&pattern).map_or(0, |matched| matched.len())
+        };
+        let mut writer = GraphEngine::with_pool(&conf, &pool);
+        let mut reader = GraphEngine::with_pool(&conf, &pool);
+        for _ in 0..10 {
+            let mut n = Node::new();
+            n.set_labels(vec!["Person".to_string()]);
+            writer.create_node(&n).expect("node");
+        }
+        assert!(writer.get_page_cache_statistics().dirty_pages > 0);
+        assert_eq!(count_persons(&mut reader), 0);
+        writer.sync();
+        drop(reader);
+        assert_eq!(writer.get_page_cache_statistics().dirty_pages, 0);
+
+        let mut ge = GraphEngine::with_pool(&conf, &pool);
+        let misses = ge.get_page_cache_statistics().misses;
+        assert_eq!(count_persons(&mut ge), 10);
+        assert_eq!(ge.get_page_cache_statistics().misses, misses);
+        drop(writer);
+        assert_eq!(count_persons(&mut GraphEngine::with_pool(&conf, &pool)), 10);
+    }
+
+    #[test]
+    fn test_engine_reads_the_commits_before_its_creation() {
+        let main_dir = build_dir_path_and_rm_old("test_engine_reads_the_commits_before_its_creation").expect("db path");
+        let conf = InitContext::new(&main_dir).expect("can't create context").with_page_cache_size(2 * 4096);
+        let pool = BufferPool::from_context(&conf);
+        let persons = |ge: &mut GraphEngine| {
+            let mut n = Node::new();
+            n.set_labels(vec!["Person".to_string()]);
+            n.set_status(Status::Match);
+            let mut pattern = PropertyGraph::new();
+            pattern.add_node(n);
+            let mut indexes = ge.match_pattern(&pattern).unwrap_or_default().iter()
+                .filter_map(|matched| matched.get_nodes()[0].get_properties_ref().first().map(|p| p.get_value().clone()))
+                .collect::<Vec<PropertyValue>>();
+            indexes.sort_by_key(|value| format!("{:?}", value));
+            indexes
+        };
+        let create_persons = |ge: &mut GraphEngine, range: std::ops::Range<i64>| range.map(|i| {
+            let mut n = Node::new();
+            n.set_labels(vec!["Person".to_string()]);
+            n.set_properties(vec![Property::new("index".to_string(), PropertyValue::PInteger(i))]);
+            ge.create_node(&n).expect("node").get_id().expect("id")
+        }).collect::<Vec<u64>>();
+        let mut writer = GraphEngine::open(&conf, &pool);
+        let ids = create_persons(&mut writer, 0..3);
+        writer.sync();
+
+        let mut snapshot = GraphEngine::with_pool(&conf, &pool);
+        let before = persons(&mut snapshot);
+        assert_eq!(before.len(), 3);
+        assert!(!snapshot.is_outdated());
+        let mut writer = GraphEngine::with_pool(&conf, &pool);
+        create_persons(&mut writer, 3..200);
+        writer.detach_delete_node(ids[0]).expect("delete");
+        writer.sync();
+        assert!(!writer.is_outdated());
+        assert!(snapshot.is_outdated());
+        assert_eq!(persons(&mut snapshot), before);
+        assert_eq!(persons(&mut GraphEngine::with_pool(&conf, &pool)).len(), 199);
+        drop(snapshot);
+        assert_eq!(persons(&mut writer).len(), 199);
+    }
+
+    #[test]
+    fn test_store_partitions() {
+        let main_dir = build_dir_path_and_rm_old("test_store_partitions_graph_engine").expect("db path");
+        let conf = InitContext::new(&main_dir).expect("can't create context").with_store_partitions(3);
+        {
+            let mut ge = GraphEngine::new(&conf);
+            let mut ids = Vec::new();
+            for _ in 0..60 {
+                let mut n = Node::new();
+                n.set_labels(vec!["Person".to_string()]);
+                ids.push(ge.create_node(&n).expect("node").get_id().expect("id"));
+            }
+            for pair in ids.windows(2) {
+                let mut r = Relationship::new();
+                r.set_labels(vec!["KNOWS".to_string()]);
+                let rel_id = ge.create_relationship(&r, pair[0], pair[1]).expect("relationship").get_id().expect("id");
+                assert_eq!((pair[0] - 1) % 3, (rel_id - 1) % 3);
+            }
+            ge.sync();
+        }
+        let partitions_dir = std::path::Path::new(&main_dir).join("partitions");
+        assert!(partitions_dir.join("2").join("nodes.db").exists());
+        assert!(partitions_dir.join("2").join("relationships.db").exists());
+
+        let count_persons = |ge: &mut GraphEngine| {
+            let mut pattern = PropertyGraph::new();
+            let mut p = Node::new();
+            p.set_labels(vec!["Person".to_string()]);
+            p.set_status(Status::Match);
+            pattern.add_node(p);
+            ge.match_pattern(&pattern).expect("match").len()
+        };
+        let mut ge = GraphEngine::new(&InitContext::new(&main_dir).expect("can't create context"));
+        assert_eq!(60, count_persons(&mut ge));
+        assert!(ge.check_stores().is_consistent());
+        drop(ge);
+
+        let report = super::compact::compact_database(&conf).expect("compaction");
+        assert_eq!(60, report.nodes);
+        assert_eq!(59, report.relationships);
+        let mut ge = GraphEngine::new(&conf);
+        assert_eq!(60, count_persons(&mut ge));
+        assert!(ge.check_stores().is_consistent());
+    }
+
+    #[test]
+    fn test_match_cursor() {
+        let main_dir = build_dir_path_and_rm_old("test_match_cursor_graph_engine").expect("db path");
+        let conf = InitContext::new(&main_dir).expect("can't create context");
+        let mut ge = GraphEngine::new(&conf);
+        for _ in 0..3 {
+            let mut n = Node::new();
+            n.set_labels(vec!["Person".to_string()]);
+            ge.create_node(&n).expect("node");
+        }
+        let mut pattern = PropertyGraph::new();
+        let mut p = Node::new();
+        p.set_labels(vec!["Person".to_string()]);
+        pattern.add_node(p);
+        let mut cursor = ge.match_pattern_cursor(&pattern).expect("cursor");
+        let first_page: Vec<PropertyGraph> = cursor.by_ref().take(2).collect();
+        assert_eq!(2, first_page.len());
+        let second_page: Vec<PropertyGraph> = cursor.by_ref().take(2).collect();
+        assert_eq!(1, second_page.len());
+        assert!(cursor.next().is_none());
+    }
+
+    #[test]
+    fn test_match_query_context() {
+        let main_dir = build_dir_path_and_rm_old("test_match_query_context").expect("db path");
+        let conf = InitContext::new(&main_dir).expect("can't create context");
+        let mut ge = GraphEngine::new(&conf);
+        for _ in 0..10 {
+            let mut n = Node::new();
+            n.set_labels(vec!["Person".to_string()]);
+            ge.create_node(&n).expect("node");
+        }
+        let mut pattern = PropertyGraph::new();
+        let mut p = Node::new();
+        p.set_labels(vec!["Person".to_string()]);
+        pattern.add_node(p);
+        let graph_size = estimate_graph_size(&ge.match_pattern(&pattern).expect("match")[0]);
+
+        ge.set_query_context(QueryContext::new().with_memory_limit(3 * graph_size));
+        assert_eq!(3, ge.match_pattern(&pattern).expect("match").len());
+        assert_eq!(Some(QueryAbort::MemoryLimitExceeded), ge.get_query_context().get_abort());
+
+        let query_context = QueryContext::new();
+        query_context.cancel();
+        ge.set_query_context(query_context.clone());
+        assert!(ge.match_pattern(&pattern).expect("match").is_empty());
+        assert_eq!(Some(QueryAbort::Cancelled), query_context.get_abort());
+    }
+
+    #[test]
+    fn test_statistics() {
+        let main_dir = build_dir_path_and_rm_old("test_statistics_graph_engine").expect("db path");
+        {
+            let conf = InitContext::new(&main_dir).expect("can't create context");
+            let mut ge = GraphEngine::new(&conf);
+            let mut graph = PropertyGraph::new();
+            let mut ids = Vec::new();
+            for label in ["Person", "Person", "Person", "City"] {
+                let mut n = Node::new();
+                n.set_labels(vec![label.to_string()]);
+                n.set_properties(vec![Property::new("name".to_string(), PropertyValue::PString(label.to_string()))]);
+                ids.push(graph.add_node(n));
+            }
+            for source in &ids[0..3] {
+                let mut r = Relationship::new();
+                r.set_labels(vec!["LIVES_IN".to_string()]);
+                graph.add_relationship(r, *source, ids[3]);
+            }
+            let created = ge.create_graph(&graph).expect("graph");
+            let rel_id = created.get_relationships()[0].get_id().expect("id");
+            ge.delete_relationship(rel_id).expect("delete");
+            let stats = ge.get_statistics();
+            assert_eq!(4, stats.get_nodes_count());
+            assert_eq!(3, stats.get_label_count("Person"));
+            assert_eq!(1, stats.get_label_count("City"));
+            assert_eq!(2, stats.get_relationship_type_count("LIVES_IN"));
+            assert_eq!(4, stats.get_node_property_count("name"));
+            ge.sync();
+        }
+        let conf = InitContext::new(&main_dir).expect("can't create context");
+        let mut ge = GraphEngine::new(&conf);
+        let stats = ge.get_statistics();
+        assert_eq!(4, stats.get_nodes_count());
+        assert_eq!(2, stats.get_relationships_count());
+        assert_eq!(3, stats.get_label_count("Person"));
+
+        let mut person = Node::new();
+        person.set_labels(vec!["Person".to_string()]);
+        let mut city = Node::new();
+        city.set_labels(vec!["City".to_string()]);
+        assert!(ge.estimate_node_cardinality(&city) < ge.estimate_node_cardinality(&person));
+
+        let mut pattern = PropertyGraph::new();
+        let pid = pattern.add_node(person);
+        let cid = pattern.add_node(city);
+        let mut r = Relationship::new();
+        r.set_labels(vec!["LIVES_IN".to_string()]);
+        pattern.add_relationship(r, pid, cid);
+        assert_eq!(2, ge.match_pattern(&pattern).expect("matches").len());
+    }
+
+    #[test]
+    fn test_node_labels_update() {
+        let main_dir = build_dir_path_and_rm_old("test_node_labels_update_graph_engine").expect("db path");
+        let conf = InitContext::new(&main_dir).expect("can't create context");
+        let mut ge = GraphEngine::new(&conf);
+        ge.create_unique_constraint(&UniqueConstraint{label: "Person".to_string(), property_name: "name".to_string()}).expect("constraint");
+        let mut ids = Vec::new();
+        for _ in 0..2 {
+            let mut n = Node::new();
+            n.set_labels(vec!["Ada".to_string()]);
+            n.set_properties(vec![Property::new("name".to_string(), PropertyValue::PString("Ada".to_string()))]);
+            ids.push(ge.create_node(&n).expect("node").get_id().expect("node id"));
+        }
+        let updated = ge.add_node_labels(ids[0], &["Person".to_string()]).expect("add label");
+        assert_eq!(&vec!["Ada".to_string(), "Person".to_string()], updated.get_labels_ref());
+        assert_eq!(Err(GraphError::UniqueConstraintViolation(UniqueConstraint::new("Person", "name"))), ge.add_node_labels(ids[1], &["Person".to_string()]).map(|_| ()));
+        assert_eq!(1, ge.get_statistics().get_label_count("Person"));
+
+        let mut pattern = PropertyGraph::new();
+        let mut person = Node::new();
+        person.set_labels(vec!["Person".to_string()]);
+        pattern.add_node(person);
+        let matched = ge.match_pattern(&pattern).expect("matches");
+        assert_eq!(1, matched.len());
+        assert_eq!(Some(ids[0]), matched[0].get_nodes()[0].get_id());
+
+        let updated = ge.remove_node_labels(ids[0], &["Ada".to_string()]).expect("remove label");
+        assert_eq!(&vec!["Person".to_string()], updated.get_labels_ref());
+        assert_eq!(1, ge.get_statistics().get_label_count("Ada"));
+        ge.remove_node_labels(ids[0], &["Person".to_string()]).expect("remove label");
+        assert!(ge.match_pattern(&pattern).expect("matches").is_empty());
+        ge.add_node_labels(ids[1], &["Person".to_string()]).expect("constraint released");
+        assert!(ge.check_indexes().iter().all(|report| report.is_consistent()));
+        assert!(ge.check_stores().is_consistent());
+    }
+
+    #[test]
+    fn test_remove_property() {
+        let main_dir = build_dir_path_and_rm_old("test_remove_property_graph_engine").expect("db path");
+        let conf = InitContext::new(&main_dir).expect("can't create context");
+        let mut ge = GraphEngine::new(&conf);
+        ge.create_unique_constraint(&UniqueConstraint{label: "Person".to_string(), property_name: "name".to_string()}).expect("constraint");
+        let mut graph = PropertyGraph::new();
+        let mut ids = Vec::new();
+        for name in ["Ada", "Alan"] {
+            let mut n = Node::new();
+            n.set_labels(vec!["Person".to_string()]);
+            n.set_properties(vec![Property::new("name".to_string(), PropertyValue::PString(name.to_string())),
+                Property::new("age".to_string(), PropertyValue::PInteger(36)),
+                Property::new("bio".to_string(), PropertyValue::PString("a long biography which is stored in dynamic records".to_string()))]);
+            ids.push(graph.add_node(n));
+        }
+        let mut r = Relationship::new();
+        r.set_labels(vec!["KNOWS".to_string()]);
+        r.set_properties(vec![Property::new("since".to_string(), PropertyValue::PInteger(1936))]);
+        graph.add_relationship(r, ids[0], ids[1]);
+        let created = ge.create_graph(&graph).expect("graph");
+        let ada_id = created.get_nodes()[0].get_id().expect("id");
+        let rel_id = created.get_relationships()[0].get_id().expect("id");
+
+        let updated = ge.remove_node_property(ada_id, "age").expect("remove age");
+        let names: Vec<&str> = updated.get_properties_ref().iter().map(|p| p.get_name()).collect();
+        assert_eq!(vec!["name", "bio"], names);
+        ge.remove_node_property(ada_id, "name").expect("remove name");
+        let updated = ge.remove_node_property(ada_id, "bio").expect("remove bio");
+        assert!(updated.get_properties_ref().is_empty());
+        assert_eq!(1, ge.get_statistics().get_node_property_count("age"));
+        let updated = ge.remove_relationship_property(rel_id, "since").expect("remove since");
+        assert!(updated.get_properties_ref().is_empty());
+
+        let mut pattern = PropertyGraph::new();
+        let mut ada = Node::new();
+        ada.set_id(Some(ada_id));
+        pattern.add_node(ada);
+        let matched = ge.match_pattern(&pattern).expect("matches");
+        assert!(matched[0].get_nodes()[0].get_properties_ref().is_empty());
+        let mut same_name = Node::new();
+        same_name.set_labels(vec!["Person".to_string()]);
+        same_name.set_properties(vec![Property::new("name".to_string(), PropertyValue::PString("Ada".to_string()))]);
+        assert!(ge.create_node(&same_name).is_ok());
+        assert!(ge.check_indexes().iter().all(|report| report.is_consistent()));
+        assert!(ge.check_stores().is_consistent());
+    }
+
+    #[test]
+    fn test_match_patterns_and_delete() {
+        let main_dir = build_dir_path_and_rm_old("test_delete_graph_engine").expect("db path");
+        let conf = InitContext::new(&main_dir).expect("can't create context");
+        let mut ge = GraphEngine::new(&conf);
+        let mut graph = PropertyGraph::new();
+        let mut ids = Vec::new();
+        for label in ["Person", "Person", "City"] {
+            let mut n = Node::new();
+            n.set_labels(vec![label.to_string()]);
+            ids.push(graph.add_node(n));
+        }
+        for source in &ids[0..2] {
+            let mut r = Relationship::new();
+            r.set_labels(vec!["LIVES_IN".to_string()]);
+            graph.add_relationship(r, *source, ids[2]);
+        }
+        let created = ge.create_graph(&graph).expect("graph");
+        let first_person_id = created.get_nodes()[0].get_id();
+
+        let mut pattern = PropertyGraph::new();
+        let mut person = Node::new();
+        person.set_id(first_person_id);
+        let pid = pattern.add_node(person);
+        let cid = pattern.add_node(Node::new());
+        let mut r = Relationship::new();
+        r.set_labels(vec!["LIVES_IN".to_string()]);
+        r.set_status(Status::Delete);
+        pattern.add_relationship(r, pid, cid);
+        ge.match_patterns_and_delete(&vec![pattern]).expect("delete relationship");
+        assert_eq!(3, ge.get_statistics().get_nodes_count());
+        assert_eq!(1, ge.get_statistics().get_relationships_count());
+
+        let mut city_pattern = PropertyGraph::new();
+        let mut city = Node::new();
+        city.set_labels(vec!["City".to_string()]);
+        city.set_status(Status::Delete);
+        city_pattern.add_node(city);
+        ge.match_patterns_and_delete(&vec![city_pattern]).expect("delete node");
+        assert_eq!(2, ge.get_statistics().get_nodes_count());
+        assert_eq!(0, ge.get_statistics().get_relationships_count());
+        assert_eq!(0, ge.get_statistics().get_label_count("City"));
+    }
+}
@@ -20,8 +20,10 @@
 
 use super::super::model::*;
 use super::super::graph::traits::*;
+use super::super::graph::NodeIndex;
 use super::super::repository::graph_repository::*;
 use super::MutableGraphRepository;
+use super::planner;
 
 use std::hash::{Hash, Hasher};
 use std::rc::Rc;
@@ -103,6 +105,68 @@ pub struct InnerEdgeData<NID: MemGraphId, EID: MemGraphId> {
     pub next_inbound_edge: Option<EID>,
 }
 
+pub const DEFAULT_ADJACENCY_CACHE_CAPACITY: usize = 1 << 18;
+const NODES_PREFETCH_SIZE: usize = 64;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AdjacencyCacheStatistics {
+    pub cached_edges: usize,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+/// Inbound and outbound edge lists of the vertices visited through a proxy, materialized on first access.
+/// Lists are no longer cached once `capacity` edges are held.
+struct AdjacencyCache {
+    edge_lists: HashMap<(u64, bool), Rc<Vec<ProxyRelationshipId>>>,
+    capacity: usize,
+    cached_edges: usize,
+    hits: u64,
+    misses: u64,
+}
+
+impl AdjacencyCache {
+    fn new(capacity: usize) -> Self {
+        AdjacencyCache{edge_lists: HashMap::new(), capacity, cached_edges: 0, hits: 0, misses: 0}
+    }
+
+    fn get(&mut self, vertex_id: u64, outbound: bool) -> Option<Rc<Vec<ProxyRelationshipId>>> {
+        let edges = self.edge_lists.get(&(vertex_id, outbound)).cloned();
+        if edges.is_some() {
+            self.hits += 1;
+        } else {
+            self.misses += 1;
+        }
+        edges
+    }
+
+    fn insert(&mut self, vertex_id: u64, outbound: bool, edges: Rc<Vec<ProxyRelationshipId>>) {
+        if self.cached_edges + edges.len() <= self.capacity {
+            self.cached_edges += edges.len();
+            self.edge_lists.insert((vertex_id, outbound), edges);
+        }
+    }
+
+    fn get_statistics(&self) -> AdjacencyCacheStatistics {
+        AdjacencyCacheStatistics{cached_edges: self.cached_edges, hits: self.hits, misses: self.misses}
+    }
+}
+
+pub struct AdjacentEdges {
+    edges: Rc<Vec<ProxyRelationshipId>>,
+    position: usize,
+}
+
+impl Iterator for AdjacentEdges {
+    type Item = ProxyRelationshipId;
+
+    fn next(&mut self) -> Option<ProxyRelationshipId> {
+        let edge = self.edges.get(self.position).copied();
+        self.position += 1;
+        edge
+    }
+}
+
 pub struct GraphProxy {
     nodes: Vec<Node>,
     relationships: Vec<Relationship>,
@@ -110,37 +174,24 @@ pub struct GraphProxy {
     edges: Rc<RefCell<Vec<InnerEdgeData<ProxyNodeId, ProxyRelationshipId>>>>,
     repository: MutableGraphRepository,
     retrieved_nodes_ids: Vec<ProxyNodeId>,
+    candidates_positions: HashMap<u64, usize>,
     map_vertices: Rc<RefCell<HashMap<u64, (ProxyNodeId, DbVertexData)>>>,
     map_edges: Rc<RefCell<HashMap<u64, (ProxyRelationshipId, DbEdgeData)>>>,
+    adjacency: AdjacencyCache,
+    /// Nodes of a subgraph, the edges to the other nodes are skipped.
+    members: Option<HashSet<u64>>,
 }
 
 
 impl GrowableGraphContainerTrait<ProxyNodeId, ProxyRelationshipId, Node, Relationship> for GraphProxy {
 
     fn get_node_ref(&mut self, id: &ProxyNodeId) -> Option<&Node> {
-        let ondata = self.map_vertices.borrow().get(&id.get_store_id()).map(|data|*data);
-        let index = {
-            let mut retrieve = true;
-            let mut vertex_exists = false;
-            let mut res = 0;
-            if let Some(ndata) = ondata {
-                vertex_exists = true;
-                if ndata.0.get_index() < self.nodes.len() {
-                    res = ndata.0.get_index();
-                    if self.nodes[res].get_id().is_some() {
-                        retrieve = false;
-                    }
-                }
-            }
-            if retrieve {
-                let rnode = self.repository.lock().unwrap().retrieve_node_by_id(id.get_store_id())?;
-                let pid = self.add_node(&rnode, !vertex_exists)?;
-                self.map_vertices.borrow_mut().insert(pid.get_store_id(), (pid, rnode.1));
-                res = pid.get_index();
-            }
-            res
-        };
-        Some(&self.nodes[index])
+        if !self.is_node_loaded(id.get_store_id()) {
+            let batch = self.nodes_batch(id.get_store_id());
+            self.load_nodes(&batch);
+        }
+        let index = self.map_vertices.borrow().get(&id.get_store_id())?.0.get_index();
+        self.nodes.get(index).filter(|node| node.get_id().is_some())
     }
 
     fn get_relationship_ref(&mut self, id: &ProxyRelationshipId) -> Option<&Relationship> {
@@ -294,20 +345,38 @@ impl Iterator for OutEdges {
 }
 
 impl GrowableGraphIteratorTrait<ProxyNodeId, ProxyRelationshipId> for GraphProxy {
-    type OutIt = OutEdges;
-    type InIt = InEdges;
+    type OutIt = AdjacentEdges;
+    type InIt = AdjacentEdges;
     fn out_edges(&mut self, source: &ProxyNodeId) -> Self::OutIt {
-        let pid = &self.map_vertices.borrow_mut()[&source.get_store_id()];
-        let first_outbound_edge = self.vertices.borrow()[pid.0.get_index()].first_outbound_edge;
-        OutEdges{ edges: self.edges.clone(), current_edge_index: first_outbound_edge, repository: self.repository.clone(),
-            map_vertices: self.map_vertices.clone(), map_edges: self.map_edges.clone(), vertices: self.vertices.clone() }
+        let edges = match self.adjacency.get(source.get_store_id(), true) {
+            Some(edges) => edges,
+            None => {
+                let pid = self.map_vertices.borrow()[&source.get_store_id()].0;
+                let first_outbound_edge = self.vertices.borrow()[pid.get_index()].first_outbound_edge;
+                let out_edges = OutEdges{ edges: self.edges.clone(), current_edge_index: first_outbound_edge, repository: self.repository.clone(),
+                    map_vertices: self.map_vertices.clone(), map_edges: self.map_edges.clone(), vertices: self.vertices.clone() };
+                let edges = Rc::new(self.retain_members_edges(out_edges.collect(), true));
+                self.adjacency.insert(source.get_store_id(), true, edges.clone());
+                edges
+            }
+        };
+        AdjacentEdges{edges, position: 0}
     }
 
     fn in_edges(&mut self, target: &ProxyNodeId) -> Self::InIt {
-        let pid = &self.map_vertices.borrow_mut()[&target.get_store_id()];
-        let first_inbound_edge = self.vertices.borrow()[pid.0.get_index()].first_inbound_edge;
-        InEdges{ edges: self.edges.clone(), current_edge_index: first_inbound_edge, repository: self.repository.clone(),
-            map_edges: self.map_edges.clone(), vertices: self.vertices.clone(), map_vertices: self.map_vertices.clone() }
+        let edges = match self.adjacency.get(target.get_store_id(), false) {
+            Some(edges) => edges,
+            None => {
+                let pid = self.map_vertices.borrow()[&target.get_store_id()].0;
+                let first_inbound_edge = self.vertices.borrow()[pid.get_index()].first_inbound_edge;
+                let in_edges = InEdges{ edges: self.edges.clone(), current_edge_index: first_inbound_edge, repository: self.repository.clone(),
+                    map_edges: self.map_edges.clone(), vertices: self.vertices.clone(), map_vertices: self.map_vertices.clone() };
+                let edges = Rc::new(self.retain_members_edges(in_edges.collect(), false));
+                self.adjacency.insert(target.get_store_id(), false, edges.clone());
+                edges
+            }
+        };
+        AdjacentEdges{edges, position: 0}
     }
     fn in_degree(&mut self, node: &ProxyNodeId) -> usize {
         self.in_edges(node).count()
@@ -358,6 +427,35 @@ fn retrieve_db_nodes_ids(repository: MutableGraphRepository, labels: &Vec<String
     res
 }
 
+fn retrieve_db_endpoints_ids(repository: MutableGraphRepository, rel: &Relationship, scan_types: bool) -> Option<Vec<ProxyNodeId>> {
+    let mut rel_ids: Option<HashSet<u64>> = rel.get_id().map(|id| HashSet::from([id]));
+    for prop in rel.get_properties_ref() {
+        let prop_rel_ids = repository.lock().unwrap().fetch_relationships_ids_with_property(prop.get_name(), prop.get_value());
+        rel_ids = Some(match rel_ids {
+            Some(ids) => ids.intersection(&prop_rel_ids).copied().collect(),
+            None => prop_rel_ids,
+        });
+    }
+    if !rel.get_labels_ref().is_empty() && (scan_types || rel_ids.is_some()) {
+        let type_rel_ids = repository.lock().unwrap().fetch_relationships_ids_with_labels(rel.get_labels_ref());
+        rel_ids = Some(match rel_ids {
+            Some(ids) => ids.intersection(&type_rel_ids).copied().collect(),
+            None => type_rel_ids,
+        });
+    }
+    let rel_ids = rel_ids?.into_iter().collect::<Vec<u64>>();
+    let mut res = Vec::new();
+    for (_, edge) in repository.lock().unwrap().retrieve_edges_data_by_ids(&rel_ids) {
+        res.push(ProxyNodeId::new_db(edge.source));
+        res.push(ProxyNodeId::new_db(edge.target));
+    }
+    Some(res)
+}
+
+fn build_candidates_positions(ids: &[ProxyNodeId]) -> HashMap<u64, usize> {
+    ids.iter().enumerate().map(|(position, id)| (id.get_store_id(), position)).collect()
+}
+
 impl GraphProxy {
     pub fn new(repo: MutableGraphRepository, pattern: &PropertyGraph) -> Option<Self> {
         let labels = extract_nodes_labels(pattern);
@@ -372,33 +470,89 @@ impl GraphProxy {
                 }
             }
         }
-        for v in pattern.get_nodes() {
-            if v.get_labels_ref().is_empty() && v.get_id() == None {
+        let mut indexed_nodes = HashSet::new();
+        let is_unbound = |n_index: &NodeIndex| {
+            let n = pattern.get_node_ref(n_index);
+            n.get_labels_ref().is_empty() && n.get_id().is_none()
+        };
+        for re in pattern.get_relationships_and_edges() {
+            let scan_types = is_unbound(&re.source) || is_unbound(&re.target);
+            if let Some(endpoints) = retrieve_db_endpoints_ids(repo.clone(), &re.relationship, scan_types) {
+                indexed_nodes.insert(re.source);
+                indexed_nodes.insert(re.target);
+                ids.extend(endpoints);
+            }
+        }
+        let expanded_nodes = planner::find_expanded_nodes(pattern);
+        for n_index in pattern.get_nodes_ids() {
+            let v = pattern.get_node_ref(&n_index);
+            if v.get_labels_ref().is_empty() && v.get_id().is_none() && !indexed_nodes.contains(&n_index) && !expanded_nodes.contains(&n_index) {
+                if v.get_properties_ref().is_empty() {
                     ids = repo.lock().unwrap().retrieve_all_nodes_ids().map(|v| v.into_iter().map(|id| ProxyNodeId::new_db(id)).collect())?;
                     break;
+                }
+                if let Some(uuid) = get_element_uuid(v.get_properties_ref()) {
+                    ids.extend(repo.lock().unwrap().find_node_by_uuid(uuid).map(ProxyNodeId::new_db));
+                    continue;
+                }
+                let names = v.get_properties_ref().iter().map(|p| p.get_name()).collect::<Vec<&str>>();
+                ids.extend(repo.lock().unwrap().fetch_nodes_ids_with_property_keys(&names).into_iter().map(ProxyNodeId::new_db));
             }
         }
+        let mut unique_ids = HashSet::new();
+        ids.retain(|id| unique_ids.insert(id.get_store_id()));
+        let candidates_positions = build_candidates_positions(&ids);
         Some(GraphProxy{repository: repo, nodes: Vec::new(),
             relationships: Vec::new(),
-            retrieved_nodes_ids: ids, vertices: Rc::new(RefCell::new(Vec::new())),
+            retrieved_nodes_ids: ids, candidates_positions, vertices: Rc::new(RefCell::new(Vec::new())),
             edges: Rc::new(RefCell::new(Vec::new())),
             map_vertices: Rc::new(RefCell::new(HashMap::new())),
             map_edges: Rc::new(RefCell::new(HashMap::new())),
+            adjacency: AdjacencyCache::new(DEFAULT_ADJACENCY_CACHE_CAPACITY),
+            members: None,
         })
     }
 
     pub fn new_full(repo: MutableGraphRepository) -> Option<Self> {
-        let ids = repo.lock().unwrap().retrieve_all_nodes_ids().map(|v| v.into_iter().map(|id| ProxyNodeId::new_db(id)).collect())?;
-
+        let ids = repo.lock().unwrap().retrieve_all_nodes_ids().map(|v| v.into_iter().map(ProxyNodeId::new_db).collect::<Vec<ProxyNodeId>>())?;
+        let candidates_positions = build_candidates_positions(&ids);
         Some(GraphProxy{repository: repo, nodes: Vec::new(),
             relationships: Vec::new(),
-            retrieved_nodes_ids: ids, vertices: Rc::new(RefCell::new(Vec::new())),
+            retrieved_nodes_ids: ids, candidates_positions, vertices: Rc::new(RefCell::new(Vec::new())),
             edges: Rc::new(RefCell::new(Vec::new())),
             map_vertices: Rc::new(RefCell::new(HashMap::new())),
             map_edges: Rc::new(RefCell::new(HashMap::new())),
+            adjacency: AdjacencyCache::new(DEFAULT_ADJACENCY_CACHE_CAPACITY),
+            members: None,
         })
     }
 
+    /// Proxy of the subgraph made of the nodes and of the relationships between them.
+    pub fn new_subgraph(repo: MutableGraphRepository, nodes_ids: Vec<u64>) -> Self {
+        let ids = nodes_ids.into_iter().map(ProxyNodeId::new_db).collect::<Vec<ProxyNodeId>>();
+        let candidates_positions = build_candidates_positions(&ids);
+        let members = Some(ids.iter().map(|id| id.get_store_id()).collect());
+        GraphProxy{repository: repo, nodes: Vec::new(),
+            relationships: Vec::new(),
+            retrieved_nodes_ids: ids, candidates_positions, vertices: Rc::new(RefCell::new(Vec::new())),
+            edges: Rc::new(RefCell::new(Vec::new())),
+            map_vertices: Rc::new(RefCell::new(HashMap::new())),
+            map_edges: Rc::new(RefCell::new(HashMap::new())),
+            adjacency: AdjacencyCache::new(DEFAULT_ADJACENCY_CACHE_CAPACITY),
+            members,
+        }
+    }
+
+    fn retain_members_edges(&self, mut edges: Vec<ProxyRelationshipId>, outbound: bool) -> Vec<ProxyRelationshipId> {
+        if let Some(members) = &self.members {
+            edges.retain(|edge| {
+                let node = if outbound { self.get_target_index(edge) } else { self.get_source_index(edge) };
+                members.contains(&node.get_store_id())
+            });
+        }
+        edges
+    }
+
     fn add_edge(&mut self, rel_db_id: u64) -> Option<ProxyRelationshipId> {
         let db_edge_data = self.repository.lock().unwrap().retrieve_edge_data_by_id(rel_db_id)?;
         add_edge(self.edges.clone(), self.vertices.clone(), self.map_vertices.clone(), self.repository.clone(), &db_edge_data, rel_db_id)
@@ -417,10 +571,10 @@ impl GraphProxy {
                 self.map_vertices.borrow()[&id].0
             }
         };
-        while pid.get_index() > self.nodes.len() {
+        while pid.get_index() >= self.nodes.len() {
             self.nodes.push(Node::new());
         }
-        self.nodes.insert(pid.get_index(), node.0.clone());
+        self.nodes[pid.get_index()] = node.0.clone();
         Some(pid)
     }
 
@@ -433,13 +587,57 @@ impl GraphProxy {
                 self.map_edges.borrow()[&id].0
             }
         };
-        while pid.get_index() > self.relationships.len() {
+        while pid.get_index() >= self.relationships.len() {
             self.relationships.push(Relationship::new());
         }
-        self.relationships.insert(pid.get_index(), rel.clone());
+        self.relationships[pid.get_index()] = rel.clone();
         Some(pid)
     }
 
+    fn is_node_loaded(&self, db_id: u64) -> bool {
+        let ondata = self.map_vertices.borrow().get(&db_id).map(|data| data.0);
+        ondata.and_then(|pid| self.nodes.get(pid.get_index())).is_some_and(|node| node.get_id().is_some())
+    }
+
+    /// A missing candidate node is read together with the next candidates not loaded yet.
+    fn nodes_batch(&self, db_id: u64) -> Vec<u64> {
+        let mut batch = vec![db_id];
+        if let Some(position) = self.candidates_positions.get(&db_id) {
+            batch.extend(self.retrieved_nodes_ids[position + 1..].iter()
+                .map(|id| id.get_store_id())
+                .filter(|id| !self.is_node_loaded(*id))
+                .take(NODES_PREFETCH_SIZE - 1));
+        }
+        batch
+    }
+
+    fn load_nodes(&mut self, db_ids: &[u64]) {
+        let rnodes = self.repository.lock().unwrap().retrieve_nodes_by_ids(db_ids);
+        for rnode in rnodes {
+            let vertex_exists = rnode.0.get_id().is_some_and(|id| self.map_vertices.borrow().contains_key(&id));
+            if let Some(pid) = self.add_node(&rnode, !vertex_exists) {
+                self.map_vertices.borrow_mut().insert(pid.get_store_id(), (pid, rnode.1));
+            }
+        }
+    }
+
+    /// Loads the given nodes that are not retrieved yet with a single repository read.
+    pub fn prefetch_nodes(&mut self, ids: &[ProxyNodeId]) {
+        let missing = ids.iter().map(|id| id.get_store_id()).filter(|id| !self.is_node_loaded(*id)).collect::<Vec<u64>>();
+        if !missing.is_empty() {
+            self.load_nodes(&missing);
+        }
+    }
+
+    /// Edge lists already cached are kept when the capacity is lowered.
+    pub fn set_adjacency_cache_capacity(&mut self, capacity: usize) {
+        self.adjacency.capacity = capacity;
+    }
+
+    pub fn get_adjacency_cache_statistics(&self) -> AdjacencyCacheStatistics {
+        self.adjacency.get_statistics()
+    }
+
     pub fn get_relationships_ref(&self) -> &Vec<Relationship> {
         &self.relationships
     }
@@ -455,7 +653,49 @@ impl GraphProxy {
 
 #[cfg(test)]
 mod test_cache_model {
+    use super::*;
+    use super::super::GraphEngine;
+    use crate::model::init::InitContext;
+    use crate::test_utils::build_dir_path_and_rm_old;
+
     fn test_add_prop_graphs() {
     }
 
+    #[test]
+    fn test_adjacency_cache() {
+        let main_dir = build_dir_path_and_rm_old("test_adjacency_cache").expect("db path");
+        let conf = InitContext::new(&main_dir).expect("can't create context");
+        let mut ge = GraphEngine::new(&conf);
+        let mut graph = PropertyGraph::new();
+        let id1 = graph.add_node(Node::new());
+        let id2 = graph.add_node(Node::new());
+        let id3 = graph.add_node(Node::new());
+        graph.add_relationship(Relationship::new(), id1, id2);
+        graph.add_relationship(Relationship::new(), id1, id3);
+        ge.create_graph(&graph).expect("graph");
+
+        let mut proxy = ge.retrieve_graph().expect("proxy");
+        let ids = proxy.get_nodes_ids();
+        for id in &ids {
+            proxy.get_node_ref(id).expect("node");
+        }
+        let out_degrees = ids.iter().map(|id| proxy.out_degree(id)).collect::<Vec<usize>>();
+        assert_eq!(2, out_degrees.iter().sum::<usize>());
+        let in_degrees = ids.iter().map(|id| proxy.in_degree(id)).collect::<Vec<usize>>();
+        assert_eq!(2, in_degrees.iter().sum::<usize>());
+        assert_eq!(AdjacencyCacheStatistics{cached_edges: 4, hits: 0, misses: 6}, proxy.get_adjacency_cache_statistics());
+
+        let source = ids[out_degrees.iter().position(|degree| *degree == 2).expect("source")];
+        let targets = proxy.out_edges(&source).map(|edge| proxy.get_target_index(&edge)).collect::<HashSet<ProxyNodeId>>();
+        assert_eq!(2, targets.len());
+        assert!(!targets.contains(&source));
+        assert_eq!(1, proxy.get_adjacency_cache_statistics().hits);
+
+        let mut proxy = ge.retrieve_graph().expect("proxy");
+        proxy.set_adjacency_cache_capacity(1);
+        proxy.get_node_ref(&source).expect("node");
+        assert_eq!(2, proxy.out_degree(&source));
+        assert_eq!(2, proxy.out_degree(&source));
+        assert_eq!(AdjacencyCacheStatistics{cached_edges: 0, hits: 0, misses: 2}, proxy.get_adjacency_cache_statistics());
+    }
 }
\ No newline at end of file
@@ -0,0 +1,147 @@
+// MIT License
+//
+// Copyright (c) 2022 Alexandre RICCIARDI
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use super::MutableGraphRepository;
+use super::super::model::*;
+
+/// Relationship of a page with the store ids of its nodes, the target may belong to another page.
+#[derive(Debug, Clone)]
+pub struct PageRelationship {
+    relationship: Relationship,
+    source: u64,
+    target: u64,
+}
+
+impl PageRelationship {
+    pub fn get_relationship(&self) -> &Relationship {
+        &self.relationship
+    }
+
+    pub fn get_source_id(&self) -> u64 {
+        self.source
+    }
+
+    pub fn get_target_id(&self) -> u64 {
+        self.target
+    }
+}
+
+/// Chunk of the graph: nodes in ascending id order and their outbound relationships, so that each
+/// relationship is returned exactly once while walking the pages.
+#[derive(Debug, Clone, Default)]
+pub struct GraphPage {
+    nodes: Vec<Node>,
+    relationships: Vec<PageRelationship>,
+    next_cursor: Option<u64>,
+}
+
+impl GraphPage {
+    pub(super) fn load(repository: &MutableGraphRepository, after: u64, limit: usize) -> Option<Self> {
+        let mut repository = repository.lock().unwrap();
+        let mut nodes_ids = repository.retrieve_nodes_ids_after(after, limit.saturating_add(1))?;
+        let next_cursor = if nodes_ids.len() > limit {
+            nodes_ids.truncate(limit);
+            nodes_ids.last().copied()
+        } else {
+            None
+        };
+        let mut page = GraphPage{next_cursor, ..Default::default()};
+        for (node, vertex) in repository.retrieve_nodes_by_ids(&nodes_ids) {
+            let mut next_edge = vertex.first_outbound_edge;
+            while let Some(rel_id) = next_edge {
+                let (relationship, edge) = repository.retrieve_relationship_by_id(rel_id)?;
+                page.relationships.push(PageRelationship{relationship, source: edge.source, target: edge.target});
+                next_edge = edge.next_outbound_edge;
+            }
+            page.nodes.push(node);
+        }
+        Some(page)
+    }
+
+    pub fn get_nodes(&self) -> &Vec<Node> {
+        &self.nodes
+    }
+
+    pub fn get_relationships(&self) -> &Vec<PageRelationship> {
+        &self.relationships
+    }
+
+    /// Cursor to pass to get the next page, none on the last page.
+    pub fn get_next_cursor(&self) -> Option<u64> {
+        self.next_cursor
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test_paging {
+    use std::collections::HashSet;
+    use super::*;
+    use super::super::GraphEngine;
+    use super::super::super::model::init::InitContext;
+    use super::super::super::test_utils::build_dir_path_and_rm_old;
+
+    #[test]
+    fn test_retrieve_graph_page() {
+        let main_dir = build_dir_path_and_rm_old("test_retrieve_graph_page").expect("db path");
+        let conf = InitContext::new(&main_dir).expect("can't create context");
+        let mut ge = GraphEngine::new(&conf);
+        let mut graph = PropertyGraph::new();
+        let nodes = (0..25).map(|_| graph.add_node(Node::new())).collect::<Vec<_>>();
+        for pair in nodes.windows(2) {
+            graph.add_relationship(Relationship::new(), pair[0], pair[1]);
+        }
+        let created = ge.create_graph(&graph).expect("graph");
+        let deleted = created.get_node_ref(&nodes[24]).get_id().expect("id");
+        ge.detach_delete_node(deleted).expect("delete");
+        ge.sync();
+
+        let mut nodes_ids = Vec::new();
+        let mut relationships_ids = HashSet::new();
+        let mut cursor = None;
+        let mut pages = 0;
+        loop {
+            let page = ge.retrieve_graph_page(cursor, 10).expect("page");
+            assert!(page.get_nodes().len() <= 10);
+            let page_ids = page.get_nodes().iter().map(|node| node.get_id().expect("id")).collect::<HashSet<u64>>();
+            for relationship in page.get_relationships() {
+                assert!(page_ids.contains(&relationship.get_source_id()));
+                assert!(relationships_ids.insert(relationship.get_relationship().get_id().expect("id")));
+            }
+            nodes_ids.extend(page.get_nodes().iter().map(|node| node.get_id().expect("id")));
+            pages += 1;
+            cursor = page.get_next_cursor();
+            if cursor.is_none() {
+                break;
+            }
+        }
+        assert_eq!(3, pages);
+        assert_eq!(24, nodes_ids.len());
+        assert!(nodes_ids.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(!nodes_ids.contains(&deleted));
+        assert_eq!(23, relationships_ids.len());
+        assert!(ge.retrieve_graph_page(nodes_ids.last().copied(), 10).expect("page").is_empty());
+    }
+}
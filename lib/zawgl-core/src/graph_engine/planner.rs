@@ -0,0 +1,224 @@
+// MIT License
+//
+// Copyright (c) 2022 Alexandre RICCIARDI
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use std::collections::HashSet;
+
+use super::super::model::*;
+use super::super::model::constraints::UniqueConstraint;
+use super::super::model::predicates::*;
+use super::super::model::statistics::GraphStatistics;
+use super::super::graph::*;
+
+const EQUALITY_SELECTIVITY: f64 = 0.1;
+const RANGE_SELECTIVITY: f64 = 0.3;
+
+fn predicate_selectivity(predicate: &PropertyPredicate) -> f64 {
+    match predicate {
+        PropertyPredicate::EqualTo(_) => EQUALITY_SELECTIVITY,
+        PropertyPredicate::Contain(list) => (EQUALITY_SELECTIVITY * list.len() as f64).min(1.0),
+        PropertyPredicate::GreaterThan(_) | PropertyPredicate::GeaterOrEqualTo(_) |
+        PropertyPredicate::LessThan(_) | PropertyPredicate::LessOrEqualTo(_) => RANGE_SELECTIVITY,
+    }
+}
+
+fn is_unique_lookup(node: &Node, pred: &NamedPropertyPredicate, constraints: &[UniqueConstraint]) -> bool {
+    matches!(pred.predicate, PropertyPredicate::EqualTo(_)) &&
+        constraints.iter().any(|c| c.property_name == pred.name && node.get_labels_ref().contains(&c.label))
+}
+
+/// Estimates the number of stored nodes a pattern node can be matched against.
+pub fn estimate_node_cardinality(node: &Node, statistics: &GraphStatistics, constraints: &[UniqueConstraint]) -> f64 {
+    if node.get_id().is_some() {
+        return 1.0;
+    }
+    let mut cardinality = match node.get_labels_ref().iter().map(|label| statistics.get_label_count(label)).min() {
+        Some(count) => count as f64,
+        None => statistics.get_nodes_count() as f64,
+    };
+    for pred in node.get_predicates_ref() {
+        if is_unique_lookup(node, pred, constraints) {
+            return cardinality.min(1.0);
+        }
+        cardinality *= statistics.get_node_property_ratio(&pred.name) * predicate_selectivity(&pred.predicate);
+    }
+    cardinality
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScanType {
+    NodeById,
+    RelationshipPropertyIndex,
+    LabelScan,
+    Expand,
+    AllNodesScan,
+}
+
+impl ScanType {
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            ScanType::NodeById => "NodeById",
+            ScanType::RelationshipPropertyIndex => "RelationshipPropertyIndex",
+            ScanType::LabelScan => "LabelScan",
+            ScanType::Expand => "Expand",
+            ScanType::AllNodesScan => "AllNodesScan",
+        }
+    }
+}
+
+pub struct NodePlan {
+    pub node: NodeIndex,
+    pub scan_type: ScanType,
+    pub estimated_rows: f64,
+}
+
+/// Pattern nodes retrieved from an index: by id, by label, by property or as endpoints of a typed or valued relationship.
+fn is_anchor(pattern: &PropertyGraph, node: &NodeIndex) -> bool {
+    let pattern_node = pattern.get_node_ref(node);
+    pattern_node.get_id().is_some() || !pattern_node.get_labels_ref().is_empty() || !pattern_node.get_properties_ref().is_empty() ||
+        pattern.get_relationships_and_edges().iter().any(|re| (re.source == *node || re.target == *node) &&
+            (!re.relationship.get_labels_ref().is_empty() || !re.relationship.get_properties_ref().is_empty()))
+}
+
+/// Pattern nodes that are not anchors but are connected to one, they are matched by expanding
+/// the adjacency of the anchor instead of scanning all the stored nodes.
+pub fn find_expanded_nodes(pattern: &PropertyGraph) -> HashSet<NodeIndex> {
+    let mut reached = pattern.get_nodes_ids().into_iter().filter(|node| is_anchor(pattern, node)).collect::<HashSet<NodeIndex>>();
+    let mut expanded = HashSet::new();
+    let mut frontier = reached.iter().copied().collect::<Vec<NodeIndex>>();
+    while let Some(node) = frontier.pop() {
+        for re in pattern.get_relationships_and_edges() {
+            let neighbour = if re.source == node {
+                re.target
+            } else if re.target == node {
+                re.source
+            } else {
+                continue;
+            };
+            if reached.insert(neighbour) {
+                expanded.insert(neighbour);
+                frontier.push(neighbour);
+            }
+        }
+    }
+    expanded
+}
+
+fn get_scan_type(pattern: &PropertyGraph, node: &NodeIndex, expanded_nodes: &HashSet<NodeIndex>) -> ScanType {
+    let pattern_node = pattern.get_node_ref(node);
+    if pattern_node.get_id().is_some() {
+        ScanType::NodeById
+    } else if pattern.get_relationships_and_edges().iter().any(|re| (re.source == *node || re.target == *node) && !re.relationship.get_properties_ref().is_empty()) {
+        ScanType::RelationshipPropertyIndex
+    } else if !pattern_node.get_labels_ref().is_empty() {
+        ScanType::LabelScan
+    } else if expanded_nodes.contains(node) {
+        ScanType::Expand
+    } else {
+        ScanType::AllNodesScan
+    }
+}
+
+/// Plans the pattern nodes so that matching starts from the most selective one.
+/// Ties are broken by the node degree, most connected first.
+pub fn plan_pattern(pattern: &PropertyGraph, statistics: &GraphStatistics, constraints: &[UniqueConstraint]) -> Vec<NodePlan> {
+    let expanded_nodes = find_expanded_nodes(pattern);
+    let mut plans = pattern.get_nodes_ids().into_iter()
+        .map(|id| (NodePlan{node: id, scan_type: get_scan_type(pattern, &id, &expanded_nodes), estimated_rows: estimate_node_cardinality(pattern.get_node_ref(&id), statistics, constraints)}, pattern.in_degree(&id) + pattern.out_degree(&id)))
+        .collect::<Vec<(NodePlan, usize)>>();
+    // expanded nodes come last so that the matching of each connected part starts from one of its anchors
+    plans.sort_by(|a, b| (a.0.scan_type == ScanType::Expand).cmp(&(b.0.scan_type == ScanType::Expand))
+        .then(a.0.estimated_rows.total_cmp(&b.0.estimated_rows)).then(b.1.cmp(&a.1)));
+    plans.into_iter().map(|(plan, _)| plan).collect()
+}
+
+pub fn plan_nodes_order(pattern: &PropertyGraph, statistics: &GraphStatistics, constraints: &[UniqueConstraint]) -> Vec<NodeIndex> {
+    plan_pattern(pattern, statistics, constraints).into_iter().map(|plan| plan.node).collect()
+}
+
+#[cfg(test)]
+mod test_planner {
+    use super::*;
+    use super::super::super::model::statistics::StatisticKey;
+
+    fn make_statistics() -> GraphStatistics {
+        let mut statistics = GraphStatistics::new();
+        statistics.set(StatisticKey::Nodes, 1000);
+        statistics.set(StatisticKey::Label(String::from("Person")), 900);
+        statistics.set(StatisticKey::Label(String::from("City")), 100);
+        statistics.set(StatisticKey::NodeProperty(String::from("name")), 1000);
+        statistics
+    }
+
+    fn make_node(label: &str) -> Node {
+        let mut node = Node::new();
+        node.set_labels(vec![String::from(label)]);
+        node
+    }
+
+    #[test]
+    fn test_estimate_node_cardinality() {
+        let statistics = make_statistics();
+        assert_eq!(900.0, estimate_node_cardinality(&make_node("Person"), &statistics, &[]));
+        assert_eq!(1000.0, estimate_node_cardinality(&Node::new(), &statistics, &[]));
+        let mut named = make_node("Person");
+        named.add_predicate(NamedPropertyPredicate{name: String::from("name"), predicate: PropertyPredicate::EqualTo(PropertyValue::PString(String::from("Alice")))});
+        assert_eq!(90.0, estimate_node_cardinality(&named, &statistics, &[]));
+        assert_eq!(1.0, estimate_node_cardinality(&named, &statistics, &[UniqueConstraint::new("Person", "name")]));
+        let mut with_id = make_node("Person");
+        with_id.set_id(Some(3));
+        assert_eq!(1.0, estimate_node_cardinality(&with_id, &statistics, &[]));
+    }
+
+    #[test]
+    fn test_plan_nodes_order() {
+        let statistics = make_statistics();
+        let mut pattern = PropertyGraph::new();
+        let person = pattern.add_node(make_node("Person"));
+        let city = pattern.add_node(make_node("City"));
+        let any = pattern.add_node(Node::new());
+        pattern.add_relationship(Relationship::new(), person, city);
+        pattern.add_relationship(Relationship::new(), person, any);
+        assert_eq!(vec![city, person, any], plan_nodes_order(&pattern, &statistics, &[]));
+        let scans = plan_pattern(&pattern, &statistics, &[]).into_iter().map(|plan| plan.scan_type).collect::<Vec<ScanType>>();
+        assert_eq!(vec![ScanType::LabelScan, ScanType::LabelScan, ScanType::Expand], scans);
+    }
+
+    #[test]
+    fn test_find_expanded_nodes() {
+        let statistics = make_statistics();
+        let mut pattern = PropertyGraph::new();
+        let person = pattern.add_node(make_node("Person"));
+        let mut named = Node::new();
+        named.add_predicate(NamedPropertyPredicate{name: String::from("name"), predicate: PropertyPredicate::EqualTo(PropertyValue::PString(String::from("Alice")))});
+        let friend = pattern.add_node(named);
+        let isolated = pattern.add_node(Node::new());
+        let source = pattern.add_node(Node::new());
+        let target = pattern.add_node(Node::new());
+        pattern.add_relationship(Relationship::new(), person, friend);
+        let mut knows = Relationship::new();
+        knows.set_labels(vec![String::from("KNOWS")]);
+        pattern.add_relationship(knows, source, target);
+        assert_eq!(HashSet::from([friend]), find_expanded_nodes(&pattern));
+        let plans = plan_pattern(&pattern, &statistics, &[]);
+        assert_eq!(friend, plans.last().expect("plan").node);
+        assert_eq!(Some(ScanType::AllNodesScan), plans.iter().find(|plan| plan.node == isolated).map(|plan| plan.scan_type));
+    }
+}
@@ -0,0 +1,184 @@
+// MIT License
+//
+// Copyright (c) 2022 Alexandre RICCIARDI
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use std::mem::size_of;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use thiserror::Error;
+
+use super::super::model::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum QueryAbort {
+    #[error("the query was cancelled")]
+    Cancelled,
+    #[error("the query exceeded its memory limit")]
+    MemoryLimitExceeded,
+    #[error("the query timed out")]
+    Timeout,
+}
+
+struct QueryState {
+    cancelled: AtomicBool,
+    memory_used: AtomicUsize,
+    abort: Mutex<Option<QueryAbort>>,
+}
+
+/// Execution context of a query, its clones share the state so that the query can be cancelled from another thread.
+/// The matcher checks it between its expansion steps and accounts the memory of the graphs it materializes.
+#[derive(Clone)]
+pub struct QueryContext {
+    state: Arc<QueryState>,
+    memory_limit: Option<usize>,
+    deadline: Option<Instant>,
+}
+
+impl QueryContext {
+    pub fn new() -> Self {
+        QueryContext{state: Arc::new(QueryState{cancelled: AtomicBool::new(false), memory_used: AtomicUsize::new(0), abort: Mutex::new(None)}), memory_limit: None, deadline: None}
+    }
+
+    /// Aborts the query once the estimated size in bytes of its materialized graphs exceeds the limit.
+    pub fn with_memory_limit(mut self, memory_limit: usize) -> Self {
+        self.memory_limit = Some(memory_limit);
+        self
+    }
+
+    pub fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Bounds the query to the timeout from now, an earlier deadline of the context is kept.
+    pub fn with_timeout(self, timeout: Duration) -> Self {
+        let deadline = Instant::now() + timeout;
+        match self.deadline {
+            Some(current) if current <= deadline => self,
+            _ => self.with_deadline(deadline),
+        }
+    }
+
+    pub fn get_deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    pub fn cancel(&self) {
+        self.state.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    /// Reason of the abort once a check failed.
+    pub fn get_abort(&self) -> Option<QueryAbort> {
+        *self.state.abort.lock().unwrap()
+    }
+
+    pub fn get_memory_used(&self) -> usize {
+        self.state.memory_used.load(Ordering::Relaxed)
+    }
+
+    fn abort(&self, reason: QueryAbort) -> Result<(), QueryAbort> {
+        Err(*self.state.abort.lock().unwrap().get_or_insert(reason))
+    }
+
+    pub fn check(&self) -> Result<(), QueryAbort> {
+        if let Some(abort) = self.get_abort() {
+            return Err(abort);
+        }
+        if self.state.cancelled.load(Ordering::SeqCst) {
+            return self.abort(QueryAbort::Cancelled);
+        }
+        if self.deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            return self.abort(QueryAbort::Timeout);
+        }
+        Ok(())
+    }
+
+    pub fn allocate(&self, size: usize) -> Result<(), QueryAbort> {
+        let memory_used = self.state.memory_used.fetch_add(size, Ordering::Relaxed) + size;
+        if self.memory_limit.is_some_and(|memory_limit| memory_used > memory_limit) {
+            return self.abort(QueryAbort::MemoryLimitExceeded);
+        }
+        self.check()
+    }
+}
+
+impl Default for QueryContext {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn estimate_value_size(value: &PropertyValue) -> usize {
+    size_of::<PropertyValue>() + match value {
+        PropertyValue::PString(s) => s.len(),
+        PropertyValue::PList(values) => values.iter().map(estimate_value_size).sum(),
+        PropertyValue::PMap(entries) => entries.iter().map(|(key, value)| key.len() + estimate_value_size(value)).sum(),
+        _ => 0,
+    }
+}
+
+fn estimate_properties_size(properties: &[Property]) -> usize {
+    properties.iter().map(|property| size_of::<Property>() + property.get_name().len() + estimate_value_size(property.get_value())).sum()
+}
+
+/// Approximate heap size in bytes of a matched graph.
+pub fn estimate_graph_size(graph: &PropertyGraph) -> usize {
+    let nodes_size: usize = graph.get_nodes().iter().map(|node| size_of::<Node>() + estimate_properties_size(node.get_properties_ref())
+        + node.get_labels_ref().iter().map(|label| label.len()).sum::<usize>()).sum();
+    let relationships_size: usize = graph.get_relationships().iter().map(|rel| size_of::<Relationship>() + estimate_properties_size(rel.get_properties_ref())
+        + rel.get_labels_ref().iter().map(|label| label.len()).sum::<usize>()).sum();
+    nodes_size + relationships_size
+}
+
+#[cfg(test)]
+mod test_query_context {
+    use super::*;
+
+    #[test]
+    fn test_query_context_abort() {
+        let ctx = QueryContext::new().with_memory_limit(100);
+        assert!(ctx.allocate(60).is_ok());
+        assert_eq!(Err(QueryAbort::MemoryLimitExceeded), ctx.allocate(60));
+        ctx.cancel();
+        assert_eq!(Err(QueryAbort::MemoryLimitExceeded), ctx.check());
+        assert_eq!(120, ctx.get_memory_used());
+
+        let ctx = QueryContext::new();
+        let client = ctx.clone();
+        assert!(ctx.check().is_ok());
+        client.cancel();
+        assert_eq!(Err(QueryAbort::Cancelled), ctx.check());
+        assert_eq!(Some(QueryAbort::Cancelled), client.get_abort());
+
+        let ctx = QueryContext::new().with_deadline(Instant::now() - Duration::from_millis(1));
+        assert_eq!(Err(QueryAbort::Timeout), ctx.check());
+
+        let ctx = QueryContext::new().with_timeout(Duration::from_secs(60));
+        let deadline = ctx.get_deadline();
+        assert!(ctx.check().is_ok());
+        assert_eq!(deadline, ctx.clone().with_timeout(Duration::from_secs(120)).get_deadline());
+        let ctx = ctx.with_timeout(Duration::ZERO);
+        assert!(ctx.get_deadline() < deadline);
+        assert_eq!(Err(QueryAbort::Timeout), ctx.check());
+    }
+}
@@ -0,0 +1,224 @@
+// MIT License
+//
+// Copyright (c) 2022 Alexandre RICCIARDI
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+
+use std::collections::HashMap;
+
+use super::model::GraphProxy;
+use super::super::graph::{EdgeIndex, Graph, NodeIndex};
+use super::super::graph::traits::*;
+use super::super::model::*;
+
+/// Labels and properties of the snapshot nodes or relationships, stored by column and indexed by the position
+/// of the items in the snapshot graph. Labels are interned.
+#[derive(Default, Clone)]
+pub struct AttributeTable {
+    labels: Vec<String>,
+    labels_ids: HashMap<String, usize>,
+    items_labels: Vec<Vec<usize>>,
+    properties: HashMap<String, Vec<Option<PropertyValue>>>,
+}
+
+impl AttributeTable {
+    fn push(&mut self, labels: &[String], properties: &[Property]) {
+        let index = self.items_labels.len();
+        let mut item_labels = Vec::new();
+        for label in labels {
+            let next_id = self.labels.len();
+            let label_id = *self.labels_ids.entry(label.clone()).or_insert(next_id);
+            if label_id == next_id {
+                self.labels.push(label.clone());
+            }
+            item_labels.push(label_id);
+        }
+        self.items_labels.push(item_labels);
+        for property in properties {
+            let column = self.properties.entry(String::from(property.get_name())).or_default();
+            column.resize(index, None);
+            column.push(Some(property.get_value().clone()));
+        }
+    }
+
+    /// Pads the property columns so that they all have a value per item.
+    fn complete_columns(&mut self) {
+        let len = self.len();
+        for column in self.properties.values_mut() {
+            column.resize(len, None);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.items_labels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items_labels.is_empty()
+    }
+
+    pub fn get_labels(&self, index: usize) -> Vec<&str> {
+        self.items_labels.get(index).map(|labels| labels.iter().map(|id| self.labels[*id].as_str()).collect()).unwrap_or_default()
+    }
+
+    pub fn has_label(&self, index: usize, label: &str) -> bool {
+        let label_id = self.labels_ids.get(label);
+        label_id.is_some_and(|id| self.items_labels.get(index).is_some_and(|labels| labels.contains(id)))
+    }
+
+    pub fn get_property(&self, index: usize, name: &str) -> Option<&PropertyValue> {
+        self.properties.get(name)?.get(index)?.as_ref()
+    }
+
+    /// Values of a property for all the items, `None` for the items without it.
+    pub fn get_property_column(&self, name: &str) -> Option<&[Option<PropertyValue>]> {
+        self.properties.get(name).map(|column| column.as_slice())
+    }
+
+    pub fn get_property_names(&self) -> Vec<&str> {
+        let mut names = self.properties.keys().map(|name| name.as_str()).collect::<Vec<&str>>();
+        names.sort_unstable();
+        names
+    }
+}
+
+/// Dense in-memory copy of a graph for the algorithms, the vertices and edges hold the store ids of
+/// the nodes and relationships and their attributes are kept in lookup tables.
+pub struct GraphSnapshot {
+    graph: Graph<u64, u64>,
+    nodes_indexes: HashMap<u64, NodeIndex>,
+    nodes_attributes: AttributeTable,
+    relationships_attributes: AttributeTable,
+}
+
+impl GraphSnapshot {
+    /// Copies the nodes retrieved by the proxy and the relationships between them.
+    pub fn from_proxy(proxy: &mut GraphProxy) -> Option<Self> {
+        let ids = proxy.get_nodes_ids();
+        proxy.prefetch_nodes(&ids);
+        let mut snapshot = GraphSnapshot{graph: Graph::new(), nodes_indexes: HashMap::new(), nodes_attributes: AttributeTable::default(), relationships_attributes: AttributeTable::default()};
+        for id in &ids {
+            let node = proxy.get_node_ref(id)?;
+            let index = snapshot.graph.add_vertex(id.store_id);
+            snapshot.nodes_attributes.push(node.get_labels_ref(), node.get_properties_ref());
+            snapshot.nodes_indexes.insert(id.store_id, index);
+        }
+        for id in &ids {
+            for rel_id in proxy.out_edges(id).collect::<Vec<_>>() {
+                let target = proxy.get_target_index(&rel_id);
+                let target_index = match snapshot.nodes_indexes.get(&target.store_id) {
+                    Some(index) => *index,
+                    None => continue,
+                };
+                let relationship = proxy.get_relationship_ref(&rel_id)?;
+                snapshot.graph.add_edge(relationship.get_id()?, snapshot.nodes_indexes[&id.store_id], target_index);
+                snapshot.relationships_attributes.push(relationship.get_labels_ref(), relationship.get_properties_ref());
+            }
+        }
+        snapshot.nodes_attributes.complete_columns();
+        snapshot.relationships_attributes.complete_columns();
+        Some(snapshot)
+    }
+
+    pub fn get_graph(&self) -> &Graph<u64, u64> {
+        &self.graph
+    }
+
+    pub fn get_node_index(&self, node_id: u64) -> Option<NodeIndex> {
+        self.nodes_indexes.get(&node_id).copied()
+    }
+
+    pub fn get_node_id(&self, index: NodeIndex) -> u64 {
+        self.graph.get_vertex(index).node
+    }
+
+    pub fn get_relationship_id(&self, index: EdgeIndex) -> u64 {
+        self.graph.get_edge_data(index).relationship
+    }
+
+    pub fn get_nodes_attributes(&self) -> &AttributeTable {
+        &self.nodes_attributes
+    }
+
+    pub fn get_relationships_attributes(&self) -> &AttributeTable {
+        &self.relationships_attributes
+    }
+}
+
+#[cfg(test)]
+mod test_snapshot {
+    use super::*;
+    use super::super::GraphEngine;
+    use super::super::super::model::init::InitContext;
+    use super::super::super::test_utils::build_dir_path_and_rm_old;
+
+    fn make_node(label: &str, name: &str) -> Node {
+        let mut node = Node::new();
+        node.set_labels(vec![String::from(label)]);
+        node.get_properties_mut().push(Property::new(String::from("name"), PropertyValue::PString(String::from(name))));
+        node
+    }
+
+    #[test]
+    fn test_graph_snapshot() {
+        let main_dir = build_dir_path_and_rm_old("test_graph_snapshot").expect("db path");
+        let conf = InitContext::new(&main_dir).expect("can't create context");
+        let mut ge = GraphEngine::new(&conf);
+        let mut graph = PropertyGraph::new();
+        let ada = graph.add_node(make_node("Person", "ada"));
+        let alan = graph.add_node(make_node("Person", "alan"));
+        let london = graph.add_node(make_node("City", "london"));
+        let mut knows = Relationship::new();
+        knows.set_labels(vec![String::from("KNOWS")]);
+        knows.get_properties_mut().push(Property::new(String::from("since"), PropertyValue::PInteger(1936)));
+        graph.add_relationship(knows, ada, alan);
+        let mut lives_in = Relationship::new();
+        lives_in.set_labels(vec![String::from("LIVES_IN")]);
+        graph.add_relationship(lives_in, ada, london);
+        let created = ge.create_graph(&graph).expect("graph");
+        ge.sync();
+
+        let snapshot = ge.retrieve_snapshot().expect("snapshot");
+        assert_eq!(3, snapshot.get_graph().nodes_len());
+        assert_eq!(2, snapshot.get_graph().edges_len());
+        let ada_id = created.get_nodes()[0].get_id().expect("id");
+        let ada_index = snapshot.get_node_index(ada_id).expect("ada");
+        assert_eq!(ada_id, snapshot.get_node_id(ada_index));
+        assert_eq!(2, snapshot.get_graph().out_degree(&ada_index));
+        assert_eq!(0, snapshot.get_graph().in_degree(&ada_index));
+
+        let nodes = snapshot.get_nodes_attributes();
+        assert_eq!(vec!["Person"], nodes.get_labels(ada_index.get_index()));
+        assert!(!nodes.has_label(ada_index.get_index(), "City"));
+        assert_eq!(Some(&PropertyValue::PString(String::from("ada"))), nodes.get_property(ada_index.get_index(), "name"));
+        assert_eq!(3, nodes.get_property_column("name").expect("names").len());
+
+        let relationships = snapshot.get_relationships_attributes();
+        assert_eq!(vec!["name"], nodes.get_property_names());
+        assert_eq!(vec!["since"], relationships.get_property_names());
+        for edge in snapshot.get_graph().out_edges(&ada_index) {
+            let target = snapshot.get_graph().get_target_index(&edge);
+            let knows = relationships.has_label(edge.get_index(), "KNOWS");
+            assert_eq!(knows, nodes.has_label(target.get_index(), "Person"));
+            let since = relationships.get_property(edge.get_index(), "since");
+            assert_eq!(knows, since == Some(&PropertyValue::PInteger(1936)));
+        }
+    }
+}
@@ -0,0 +1,179 @@
+// MIT License
+//
+// Copyright (c) 2022 Alexandre RICCIARDI
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+
+use std::collections::HashSet;
+
+use super::MutableGraphRepository;
+use super::super::model::*;
+
+const FILTER_BATCH_SIZE: usize = 1024;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PropertyPredicate {
+    Exists(String),
+    Equals(String, PropertyValue),
+}
+
+impl PropertyPredicate {
+    fn get_name(&self) -> &str {
+        match self {
+            PropertyPredicate::Exists(name) | PropertyPredicate::Equals(name, _) => name,
+        }
+    }
+
+    fn matches(&self, node: &Node) -> bool {
+        let property = node.get_properties_ref().iter().find(|property| property.get_name() == self.get_name());
+        match self {
+            PropertyPredicate::Exists(_) => property.is_some(),
+            PropertyPredicate::Equals(_, value) => property.is_some_and(|property| property.get_value() == value),
+        }
+    }
+}
+
+/// Selects the nodes having one of the labels, if any is given, and matching all the property predicates.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SubgraphFilter {
+    labels: Vec<String>,
+    properties: Vec<PropertyPredicate>,
+}
+
+impl SubgraphFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_labels(mut self, labels: &[String]) -> Self {
+        self.labels.extend_from_slice(labels);
+        self
+    }
+
+    pub fn with_label(mut self, label: &str) -> Self {
+        self.labels.push(String::from(label));
+        self
+    }
+
+    pub fn with_property(mut self, name: &str, value: PropertyValue) -> Self {
+        self.properties.push(PropertyPredicate::Equals(String::from(name), value));
+        self
+    }
+
+    pub fn with_property_key(mut self, name: &str) -> Self {
+        self.properties.push(PropertyPredicate::Exists(String::from(name)));
+        self
+    }
+
+    pub fn matches(&self, node: &Node) -> bool {
+        let has_label = self.labels.is_empty() || node.get_labels_ref().iter().any(|label| self.labels.contains(label));
+        has_label && self.properties.iter().all(|predicate| predicate.matches(node))
+    }
+
+    /// Candidates are taken from the labels and property keys indexes, their properties are then checked
+    /// by batches so that only the ids of the matching nodes are kept.
+    pub(super) fn fetch_nodes_ids(&self, repository: &MutableGraphRepository) -> Option<Vec<u64>> {
+        let mut repository = repository.lock().unwrap();
+        let mut candidates: Option<HashSet<u64>> = None;
+        if !self.labels.is_empty() {
+            candidates = Some(repository.fetch_nodes_ids_with_labels(&self.labels));
+        }
+        if !self.properties.is_empty() {
+            let names = self.properties.iter().map(|predicate| predicate.get_name()).collect::<Vec<&str>>();
+            let ids = repository.fetch_nodes_ids_with_property_keys(&names);
+            candidates = Some(match candidates {
+                Some(candidates) => candidates.intersection(&ids).copied().collect(),
+                None => ids,
+            });
+        }
+        let mut candidates = match candidates {
+            Some(candidates) => candidates.into_iter().collect::<Vec<u64>>(),
+            None => repository.retrieve_all_nodes_ids()?,
+        };
+        candidates.sort_unstable();
+        if !self.properties.iter().any(|predicate| matches!(predicate, PropertyPredicate::Equals(_, _))) {
+            return Some(candidates);
+        }
+        let mut ids = Vec::new();
+        for batch in candidates.chunks(FILTER_BATCH_SIZE) {
+            let nodes = repository.retrieve_nodes_by_ids(batch);
+            ids.extend(nodes.iter().filter(|(node, _)| self.matches(node)).filter_map(|(node, _)| node.get_id()));
+        }
+        Some(ids)
+    }
+}
+
+#[cfg(test)]
+mod test_subgraph {
+    use super::*;
+    use super::super::GraphEngine;
+    use super::super::super::graph::traits::*;
+    use super::super::super::model::init::InitContext;
+    use super::super::super::test_utils::build_dir_path_and_rm_old;
+
+    fn make_node(label: &str, name: &str, age: Option<i64>) -> Node {
+        let mut node = Node::new();
+        node.set_labels(vec![String::from(label)]);
+        node.get_properties_mut().push(Property::new(String::from("name"), PropertyValue::PString(String::from(name))));
+        if let Some(age) = age {
+            node.get_properties_mut().push(Property::new(String::from("age"), PropertyValue::PInteger(age)));
+        }
+        node
+    }
+
+    #[test]
+    fn test_retrieve_subgraph() {
+        let main_dir = build_dir_path_and_rm_old("test_retrieve_subgraph").expect("db path");
+        let conf = InitContext::new(&main_dir).expect("can't create context");
+        let mut ge = GraphEngine::new(&conf);
+        let mut graph = PropertyGraph::new();
+        let ada = graph.add_node(make_node("Person", "ada", Some(36)));
+        let alan = graph.add_node(make_node("Person", "alan", Some(41)));
+        let london = graph.add_node(make_node("City", "london", None));
+        let grace = graph.add_node(make_node("Person", "grace", None));
+        graph.add_relationship(Relationship::new(), ada, alan);
+        graph.add_relationship(Relationship::new(), ada, london);
+        graph.add_relationship(Relationship::new(), alan, grace);
+        let created = ge.create_graph(&graph).expect("graph");
+        ge.sync();
+        let ids = [ada, alan, london].iter().map(|index| created.get_node_ref(index).get_id().expect("id")).collect::<Vec<u64>>();
+
+        let mut persons = ge.retrieve_subgraph(&SubgraphFilter::new().with_label("Person").with_property_key("age")).expect("subgraph");
+        let nodes_ids = persons.get_nodes_ids();
+        assert_eq!(2, nodes_ids.len());
+        let ada_id = *nodes_ids.iter().find(|id| id.store_id == ids[0]).expect("ada");
+        let alan_id = *nodes_ids.iter().find(|id| id.store_id == ids[1]).expect("alan");
+        persons.get_node_ref(&ada_id).expect("ada");
+        assert_eq!(1, persons.out_degree(&ada_id));
+        let knows = persons.out_edges(&ada_id).next().expect("edge");
+        assert_eq!(ids[1], persons.get_target_index(&knows).store_id);
+        persons.get_node_ref(&alan_id).expect("alan");
+        assert_eq!(0, persons.out_degree(&alan_id));
+        assert_eq!(1, persons.in_degree(&alan_id));
+
+        let alan_subgraph = ge.retrieve_subgraph(&SubgraphFilter::new().with_property("age", PropertyValue::PInteger(41))).expect("subgraph");
+        assert_eq!(vec![ids[1]], alan_subgraph.get_nodes_ids().iter().map(|id| id.store_id).collect::<Vec<u64>>());
+        let cities = ge.retrieve_subgraph(&SubgraphFilter::new().with_label("City")).expect("subgraph");
+        assert_eq!(vec![ids[2]], cities.get_nodes_ids().iter().map(|id| id.store_id).collect::<Vec<u64>>());
+        assert_eq!(4, ge.retrieve_subgraph(&SubgraphFilter::new()).expect("subgraph").nodes_len());
+        assert!(SubgraphFilter::new().with_label("City").matches(created.get_node_ref(&london)));
+        assert!(!SubgraphFilter::new().with_property_key("age").matches(created.get_node_ref(&grace)));
+    }
+}
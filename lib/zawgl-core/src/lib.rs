@@ -20,14 +20,22 @@
 
 #![allow(dead_code)]
 extern crate log;
+#[cfg(feature = "serde")]
 extern crate serde;
 extern crate toml;
+extern crate chrono;
+extern crate memmap2;
+extern crate zstd;
+extern crate uuid;
+extern crate thiserror;
 
 pub mod test_utils;
 mod config;
 mod buf_config;
 pub mod graph;
 pub mod model;
+pub mod error;
 mod repository;
 mod matcher;
-pub mod graph_engine;
\ No newline at end of file
+pub mod graph_engine;
+pub mod metrics;
\ No newline at end of file
@@ -22,8 +22,11 @@ mod base_state;
 mod state;
 
 use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
 use crate::graph_engine::model::{ProxyNodeId, GraphProxy};
+use crate::graph_engine::query_context::QueryContext;
 use crate::model::{PropertyGraph, Relationship, Node};
+use crate::metrics::{self, Counter};
 
 use self::state::State;
 use super::super::graph::traits::*;
@@ -42,12 +45,12 @@ enum IterationStates {
 struct VecIterator<T> {
     index: usize,
     end: bool,
-    vector: Vec<T>,
+    vector: Rc<Vec<T>>,
 }
 
 impl <T> VecIterator<T> {
-    fn new(v: Vec<T>) -> Self {
-        VecIterator { index: 0, end: false, vector: v }
+    fn new(v: Rc<Vec<T>>) -> Self {
+        VecIterator { index: 0, end: v.is_empty(), vector: v }
     }
 
     fn end(&self) -> bool {
@@ -76,7 +79,7 @@ impl <T> VecIterator<T> {
     }
 
     fn reset(&mut self) {
-        self.end = false;
+        self.end = self.vector.is_empty();
         self.index = 0;
     }
 
@@ -89,88 +92,126 @@ impl <T> VecIterator<T> {
     }
 }
 
-pub struct Matcher<'g0: 'g1, 'g1, VCOMP, ECOMP, CALLBACK>
+pub struct Matcher<VCOMP, ECOMP, CALLBACK>
     where VCOMP: Fn(&Node, &Node) -> bool, ECOMP: Fn(&Relationship, &Relationship) -> bool,
     CALLBACK: FnMut(&HashMap<NodeIndex, ProxyNodeId>, &HashMap<ProxyNodeId, NodeIndex>, &PropertyGraph, &mut GraphProxy) -> Option<bool> {
-        state: State<'g0, 'g1, VCOMP, ECOMP>,
+        state: State<VCOMP, ECOMP>,
         callback: CALLBACK,
+        index0: VecIterator<NodeIndex>,
+        index1: VecIterator<ProxyNodeId>,
+        ids1: Rc<Vec<ProxyNodeId>>,
+        iteration_state: IterationStates,
+        found_match: bool,
+        exhausted: bool,
+        match_continuation: Vec<(usize, usize, Rc<Vec<ProxyNodeId>>)>,
+        query_context: QueryContext,
 }
 
-impl <'g0, 'g1, VCOMP, ECOMP, CALLBACK> Matcher <'g0, 'g1, VCOMP, ECOMP, CALLBACK>
+impl <VCOMP, ECOMP, CALLBACK> Matcher <VCOMP, ECOMP, CALLBACK>
     where VCOMP: Fn(&Node, &Node) -> bool, ECOMP: Fn(&Relationship, &Relationship) -> bool,
     CALLBACK: FnMut(&HashMap<NodeIndex, ProxyNodeId>, &HashMap<ProxyNodeId, NodeIndex>, &PropertyGraph, &mut GraphProxy) -> Option<bool> {
 
-        pub fn new(graph_0: &'g0 PropertyGraph, graph_1: &'g1 mut GraphProxy, vcomp: VCOMP, ecomp: ECOMP, callback: CALLBACK) -> Self {
+        pub fn new(graph_0: Rc<PropertyGraph>, graph_1: GraphProxy, vcomp: VCOMP, ecomp: ECOMP, callback: CALLBACK) -> Self {
+            let ids0 = sort_nodes(&graph_0);
+            Self::new_with_order(graph_0, graph_1, ids0, vcomp, ecomp, callback)
+        }
+
+        /// Builds a matcher exploring the pattern nodes in the given order.
+        pub fn new_with_order(graph_0: Rc<PropertyGraph>, graph_1: GraphProxy, ids0: Vec<NodeIndex>, vcomp: VCOMP, ecomp: ECOMP, callback: CALLBACK) -> Self {
+            let ids1 = Rc::new(graph_1.get_nodes_ids());
             Matcher {
                 state: State::new(graph_0, graph_1, vcomp, ecomp),
                 callback: callback,
+                index0: VecIterator::new(Rc::new(ids0)),
+                index1: VecIterator::new(ids1.clone()),
+                ids1,
+                iteration_state: IterationStates::Process,
+                found_match: false,
+                exhausted: false,
+                match_continuation: Vec::new(),
+                query_context: QueryContext::new(),
             }
         }
 
-        pub fn process(&mut self, ids0: Vec<NodeIndex>, ids1: Vec<ProxyNodeId>) -> Option<bool> {
-            let mut index0 = VecIterator::new(ids0);
-            let mut index1 = VecIterator::new(ids1);
-            let mut state = IterationStates::Process;
-            let mut found_match = false;
-            let mut match_continuation = Vec::new();
+        /// The matching fails once the query is aborted.
+        pub fn with_query_context(mut self, query_context: QueryContext) -> Self {
+            self.query_context = query_context;
+            self
+        }
+
+        pub fn is_exhausted(&self) -> bool {
+            self.exhausted
+        }
+
+        /// Runs the matching until the callback asks to stop or the search space is exhausted.
+        /// A stopped matcher resumes from the last match on the next call.
+        pub fn process(&mut self) -> Option<bool> {
+            if self.exhausted {
+                return Some(self.found_match);
+            }
             loop {
-                match state {
+                match self.iteration_state {
                     IterationStates::Process => {
                         if self.state.success() {
-                            found_match = true;
+                            self.found_match = true;
+                            self.iteration_state = IterationStates::Backtrack;
                             if !self.state.call_back(&mut self.callback)? {
                                 return Some(true);
-                            } else {
-                                state = IterationStates::Backtrack;
                             }
                         } else {
-                            state = IterationStates::Validate;
+                            self.iteration_state = IterationStates::Validate;
                         }
                     },
                     IterationStates::Validate => {
                         if !self.state.valid() {
-                            state = IterationStates::Backtrack;
+                            self.iteration_state = IterationStates::Backtrack;
                         } else {
-                            state = IterationStates::LookForCandidates;
+                            self.iteration_state = IterationStates::LookForCandidates;
                         }
                     },
                     IterationStates::LookForCandidates => {
-                        index0.reset();
-                        while !index0.end() && !self.state.possible_candidate_0(index0.value()) {
-                            index0.inc();
+                        self.index0.reset();
+                        while !self.index0.end() && !self.state.possible_candidate_0(self.index0.value()) {
+                            self.index0.inc();
                         }
-                        state = IterationStates::InitGraph1Loop;
+                        self.iteration_state = IterationStates::InitGraph1Loop;
                     },
                     IterationStates::InitGraph1Loop => {
-                        index1.reset();
-                        state = IterationStates::Graph1Loop;
+                        // once a node is matched its neighbours are the only candidates, they are reached through the adjacency
+                        // of the matched nodes whether or not they were retrieved from the indexes
+                        self.index1 = VecIterator::new(self.state.terminal_candidates_1().map(Rc::new).unwrap_or_else(|| self.ids1.clone()));
+                        self.iteration_state = IterationStates::Graph1Loop;
                     },
                     IterationStates::Graph1Loop => {
                         let mut backtrack = true;
-                        while !index1.end() {
-                            if self.state.possible_candidate_1(index1.value()) && self.state.feasible(index0.value(), index1.value())? {
-                                match_continuation.push((index0.index(), index1.index()));
-                                self.state.push(index0.value(), index1.value());
+                        while !self.index1.end() {
+                            if self.state.possible_candidate_1(self.index1.value()) && self.state.feasible(self.index0.value(), self.index1.value())? {
+                                self.match_continuation.push((self.index0.index(), self.index1.index(), self.index1.vector.clone()));
+                                self.state.push(self.index0.value(), self.index1.value());
+                                metrics::increment(Counter::MatcherSteps);
+                                self.query_context.check().ok()?;
                                 backtrack = false;
                                 break;
                             }
-                            index1.inc();
+                            self.index1.inc();
                         }
                         if !backtrack {
-                            state = IterationStates::Process;
+                            self.iteration_state = IterationStates::Process;
                         } else {
-                            state = IterationStates::Backtrack;
+                            self.iteration_state = IterationStates::Backtrack;
                         }
                     },
                     IterationStates::Backtrack => {
-                        if let Some(back) = match_continuation.pop() {
-                            index0.set_index(back.0);
-                            index1.set_index(back.1);
-                            self.state.pop(index0.value_at(back.0), index1.value_at(back.1));
-                            index1.inc();
-                            state = IterationStates::Graph1Loop;
+                        if let Some(back) = self.match_continuation.pop() {
+                            self.index0.set_index(back.0);
+                            self.index1 = VecIterator::new(back.2);
+                            self.index1.set_index(back.1);
+                            self.state.pop(self.index0.value_at(back.0), self.index1.value_at(back.1));
+                            self.index1.inc();
+                            self.iteration_state = IterationStates::Graph1Loop;
                         } else {
-                            return Some(found_match);
+                            self.exhausted = true;
+                            return Some(self.found_match);
                         }
                     }
                 }
@@ -184,14 +225,10 @@ fn sort_nodes<'g>(graph: &'g PropertyGraph) -> Vec<NodeIndex> {
     res
 }
 
-pub fn sub_graph_isomorphism<'g0: 'g1, 'g1, VCOMP, ECOMP, CALLBACK>
-(graph_0: &'g0 PropertyGraph, graph_1: &'g1 mut GraphProxy, vcomp: VCOMP, ecomp: ECOMP, callback: CALLBACK) -> Option<bool>
+pub fn sub_graph_isomorphism<VCOMP, ECOMP, CALLBACK>
+(graph_0: &PropertyGraph, graph_1: GraphProxy, vcomp: VCOMP, ecomp: ECOMP, callback: CALLBACK) -> Option<bool>
 where VCOMP: Fn(&Node, &Node) -> bool, ECOMP: Fn(&Relationship, &Relationship) -> bool,
 CALLBACK: FnMut(&HashMap<NodeIndex, ProxyNodeId>, &HashMap<ProxyNodeId, NodeIndex>, &PropertyGraph, &mut GraphProxy)-> Option<bool>  {
-
-    let id0 = sort_nodes(graph_0);
-    let id1 = graph_1.get_nodes_ids();
-    let mut matcher = Matcher::new(graph_0, graph_1, vcomp, ecomp, callback);
-    
-    matcher.process(id0, id1)
-}
\ No newline at end of file
+    let mut matcher = Matcher::new(Rc::new(graph_0.clone()), graph_1, vcomp, ecomp, callback);
+    matcher.process()
+}
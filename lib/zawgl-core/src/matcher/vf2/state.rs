@@ -20,6 +20,7 @@
 
 use std::collections::HashSet;
 use std::collections::HashMap;
+use std::rc::Rc;
 use log::trace;
 
 use crate::graph::container::GraphContainer;
@@ -243,10 +244,10 @@ pub fn pop_state_1<'g>(base_state: &mut BaseState<ProxyNodeId, NodeIndex>, graph
     base_state.core_count -= 1;
 }
 
-pub struct State<'g0, 'g1, VCOMP, ECOMP>
+pub struct State<VCOMP, ECOMP>
     where VCOMP: Fn(&Node, &Node) -> bool, ECOMP: Fn(&Relationship, &Relationship) -> bool {
-    graph_0: &'g0 PropertyGraph,
-    graph_1: &'g1 mut GraphProxy,
+    graph_0: Rc<PropertyGraph>,
+    graph_1: GraphProxy,
     vertex_comp: VCOMP,
     edge_comp: ECOMP,
     base_state_0: BaseState<NodeIndex, ProxyNodeId>,
@@ -254,11 +255,11 @@ pub struct State<'g0, 'g1, VCOMP, ECOMP>
 
 }
 
-impl <'g0, 'g1, VCOMP, ECOMP> State<'g0, 'g1, VCOMP, ECOMP>
+impl <VCOMP, ECOMP> State<VCOMP, ECOMP>
     where VCOMP: Fn(&Node, &Node) -> bool, ECOMP: Fn(&Relationship, &Relationship) -> bool {
 
 
-        pub fn new(graph_0: &'g0 PropertyGraph, graph_1: &'g1 mut GraphProxy, vcomp: VCOMP, ecomp: ECOMP) -> Self {
+        pub fn new(graph_0: Rc<PropertyGraph>, graph_1: GraphProxy, vcomp: VCOMP, ecomp: ECOMP) -> Self {
             State {
                 graph_0: graph_0,
                 graph_1: graph_1,
@@ -270,20 +271,21 @@ impl <'g0, 'g1, VCOMP, ECOMP> State<'g0, 'g1, VCOMP, ECOMP>
         }
 
         pub fn push(&mut self, v0: &NodeIndex, v1: &ProxyNodeId) {
-            push_state_0(&mut self.base_state_0, self.graph_0, v0, v1);
-            push_state_1(&mut self.base_state_1, self.graph_1, v1, v0);
+            push_state_0(&mut self.base_state_0, &self.graph_0, v0, v1);
+            push_state_1(&mut self.base_state_1, &mut self.graph_1, v1, v0);
         }
 
         pub fn pop(&mut self, v0: &NodeIndex, _v1: &ProxyNodeId) {
             if let Some(&w) = self.base_state_0.core(v0) {
-                pop_state_0(&mut self.base_state_0, self.graph_0, v0);
-                pop_state_1(&mut self.base_state_1, self.graph_1, &w);
+                pop_state_0(&mut self.base_state_0, &self.graph_0, v0);
+                pop_state_1(&mut self.base_state_1, &mut self.graph_1, &w);
             }
             
         }
 
         pub fn feasible(&mut self, v_new: &NodeIndex, w_new: &ProxyNodeId) -> Option<bool> {
-            let v = self.graph_0.get_node_ref(v_new);
+            let graph_0 = self.graph_0.clone();
+            let v = graph_0.get_node_ref(v_new);
             let w = self.graph_1.get_node_ref(w_new)?;
             if !(self.vertex_comp)(v, w) {
                 Some(false)
@@ -295,8 +297,8 @@ impl <'g0, 'g1, VCOMP, ECOMP> State<'g0, 'g1, VCOMP, ECOMP>
 
                 {
                     let mut matched_edge_set = HashSet::new();
-                    for edge_index in self.graph_0.in_edges(v_new) {
-                        let source_index = self.graph_0.get_source_index(&edge_index);
+                    for edge_index in graph_0.in_edges(v_new) {
+                        let source_index = graph_0.get_source_index(&edge_index);
                         if !self.inc_counters_match_edge_0(true, &mut term_in0_count, &mut term_out0_count, &mut rest0_count, v_new, &source_index, w_new, &edge_index, 
                             &mut matched_edge_set)? {
                             return Some(false);
@@ -305,8 +307,8 @@ impl <'g0, 'g1, VCOMP, ECOMP> State<'g0, 'g1, VCOMP, ECOMP>
                 }
                 {
                     let mut matched_edge_set = HashSet::new();
-                    for edge_index in self.graph_0.out_edges(v_new) {
-                        let target_index = self.graph_0.get_target_index(&edge_index);
+                    for edge_index in graph_0.out_edges(v_new) {
+                        let target_index = graph_0.get_target_index(&edge_index);
                         if !self.inc_counters_match_edge_0(false, &mut term_in0_count, &mut term_out0_count, &mut rest0_count, v_new, &target_index, w_new, &edge_index, 
                             &mut matched_edge_set)? {
                             return Some(false);
@@ -351,7 +353,8 @@ impl <'g0, 'g1, VCOMP, ECOMP> State<'g0, 'g1, VCOMP, ECOMP>
                     }
                 }
                 
-                let r0 = self.graph_0.get_relationship_ref(edge_index);
+                let graph_0 = self.graph_0.clone();
+                let r0 = graph_0.get_relationship_ref(edge_index);
 
                 if is_inbound {
                     if !self.edge_exists_1(&w, &w_new, r0, matched_edge_set)? {
@@ -445,6 +448,21 @@ impl <'g0, 'g1, VCOMP, ECOMP> State<'g0, 'g1, VCOMP, ECOMP>
             }
         }
 
+        /// Unmatched vertices of the terminal sets used by `possible_candidate_1`, none when no terminal set is active.
+        pub fn terminal_candidates_1(&self) -> Option<Vec<ProxyNodeId>> {
+            let mut candidates: Vec<ProxyNodeId> = if self.base_state_0.term_both() && self.base_state_1.term_both() {
+                self.base_state_1.in_map.keys().filter(|v1| self.base_state_1.term_both_vertex(v1)).copied().collect()
+            } else if self.base_state_0.term_out() && self.base_state_1.term_out() {
+                self.base_state_1.out_map.keys().filter(|v1| self.base_state_1.term_out_vertex(v1)).copied().collect()
+            } else if self.base_state_0.term_in() && self.base_state_1.term_in() {
+                self.base_state_1.in_map.keys().filter(|v1| self.base_state_1.term_in_vertex(v1)).copied().collect()
+            } else {
+                return None;
+            };
+            candidates.sort_by_key(|v1| v1.store_id);
+            Some(candidates)
+        }
+
         pub fn success(&self) -> bool {
             self.base_state_0.count() == self.graph_0.nodes_len()
         }
@@ -458,7 +476,7 @@ impl <'g0, 'g1, VCOMP, ECOMP> State<'g0, 'g1, VCOMP, ECOMP>
         pub fn call_back<CALLBACK>(&mut self, callback: &mut CALLBACK) -> Option<bool>
         where CALLBACK: FnMut(&HashMap<NodeIndex, ProxyNodeId>, &HashMap<ProxyNodeId, NodeIndex>, &PropertyGraph, &mut GraphProxy) -> Option<bool>
         {
-            callback(self.base_state_0.get_map(), self.base_state_1.get_map(), self.graph_0, self.graph_1)
+            callback(self.base_state_0.get_map(), self.base_state_1.get_map(), &self.graph_0, &mut self.graph_1)
         }
 
 }
@@ -0,0 +1,121 @@
+// MIT License
+//
+// Copyright (c) 2022 Alexandre RICCIARDI
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Process wide counters, updated by the storage layer, the matcher and the request handlers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Counter {
+    Queries,
+    TxCommits,
+    TxRollbacks,
+    QueryCacheHits,
+    QueryCacheMisses,
+    PageCacheHits,
+    PageCacheMisses,
+    PageReads,
+    PageWrites,
+    MatcherSteps,
+    RejectedQueries,
+}
+
+pub const COUNTERS: [Counter; 11] = [
+    Counter::Queries,
+    Counter::TxCommits,
+    Counter::TxRollbacks,
+    Counter::QueryCacheHits,
+    Counter::QueryCacheMisses,
+    Counter::PageCacheHits,
+    Counter::PageCacheMisses,
+    Counter::PageReads,
+    Counter::PageWrites,
+    Counter::MatcherSteps,
+    Counter::RejectedQueries,
+];
+
+static VALUES: [AtomicU64; COUNTERS.len()] = [const { AtomicU64::new(0) }; COUNTERS.len()];
+
+impl Counter {
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Counter::Queries => "zawgl_queries_total",
+            Counter::TxCommits => "zawgl_tx_commits_total",
+            Counter::TxRollbacks => "zawgl_tx_rollbacks_total",
+            Counter::QueryCacheHits => "zawgl_query_cache_hits_total",
+            Counter::QueryCacheMisses => "zawgl_query_cache_misses_total",
+            Counter::PageCacheHits => "zawgl_page_cache_hits_total",
+            Counter::PageCacheMisses => "zawgl_page_cache_misses_total",
+            Counter::PageReads => "zawgl_page_reads_total",
+            Counter::PageWrites => "zawgl_page_writes_total",
+            Counter::MatcherSteps => "zawgl_matcher_steps_total",
+            Counter::RejectedQueries => "zawgl_rejected_queries_total",
+        }
+    }
+
+    pub fn get_help(&self) -> &'static str {
+        match self {
+            Counter::Queries => "Requests handled by the transaction handler.",
+            Counter::TxCommits => "Committed transactions.",
+            Counter::TxRollbacks => "Rolled back transactions, including expired and deadlocked ones.",
+            Counter::QueryCacheHits => "Queries found in the parsed query cache.",
+            Counter::QueryCacheMisses => "Queries parsed as they were not in the query cache.",
+            Counter::PageCacheHits => "Pages found in the buffer pool.",
+            Counter::PageCacheMisses => "Pages loaded in the buffer pool.",
+            Counter::PageReads => "Pages read from the store files.",
+            Counter::PageWrites => "Pages written to the store files.",
+            Counter::MatcherSteps => "Candidate pairs added to the pattern matcher state.",
+            Counter::RejectedQueries => "Queries rejected as the server was busy.",
+        }
+    }
+
+    fn get_value(&self) -> &'static AtomicU64 {
+        &VALUES[*self as usize]
+    }
+}
+
+pub fn increment(counter: Counter) {
+    add(counter, 1);
+}
+
+pub fn add(counter: Counter, value: u64) {
+    counter.get_value().fetch_add(value, Ordering::Relaxed);
+}
+
+pub fn get(counter: Counter) -> u64 {
+    counter.get_value().load(Ordering::Relaxed)
+}
+
+#[cfg(test)]
+mod test_metrics {
+    use super::*;
+
+    #[test]
+    fn test_counters() {
+        for (index, counter) in COUNTERS.iter().enumerate() {
+            assert_eq!(index, *counter as usize);
+        }
+        let commits = get(Counter::TxCommits);
+        increment(Counter::TxCommits);
+        add(Counter::TxCommits, 2);
+        assert!(get(Counter::TxCommits) >= commits + 3);
+    }
+}
@@ -0,0 +1,64 @@
+// MIT License
+//
+// Copyright (c) 2022 Alexandre RICCIARDI
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use super::{Node, Relationship};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    Created,
+    Updated,
+    Deleted,
+}
+
+impl ChangeKind {
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            ChangeKind::Created => "created",
+            ChangeKind::Updated => "updated",
+            ChangeKind::Deleted => "deleted",
+        }
+    }
+}
+
+/// Change of a node or relationship, deleted elements carry their labels and properties before the deletion.
+#[derive(Debug, Clone)]
+pub enum ChangeEvent {
+    Node{kind: ChangeKind, node: Node},
+    Relationship{kind: ChangeKind, relationship: Relationship, source_id: u64, target_id: u64},
+}
+
+impl ChangeEvent {
+    pub fn get_kind(&self) -> ChangeKind {
+        match self {
+            ChangeEvent::Node{kind, ..} | ChangeEvent::Relationship{kind, ..} => *kind,
+        }
+    }
+}
+
+/// Changes of a committed transaction, `offset` locates it in the change log and `next_offset` the following one.
+#[derive(Debug, Clone)]
+pub struct ChangeSet {
+    pub offset: u64,
+    pub next_offset: u64,
+    pub timestamp_millis: i64,
+    pub events: Vec<ChangeEvent>,
+}
@@ -0,0 +1,64 @@
+// MIT License
+//
+// Copyright (c) 2022 Alexandre RICCIARDI
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+/// Names of the secondary indexes, as reported by their checks.
+pub const INDEXES_NAMES: [&str; 5] = ["nodes labels", "nodes property keys", "nodes properties", "relationships types", "relationships properties"];
+
+/// Result of the consistency check of a secondary index.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IndexCheckReport {
+    pub name: String,
+    /// Tree nodes visited from the root.
+    pub nodes: usize,
+    pub keys: usize,
+    pub data_ptrs: usize,
+    pub errors: Vec<String>,
+}
+
+impl IndexCheckReport {
+    pub fn new(name: &str) -> Self {
+        IndexCheckReport{name: String::from(name), ..Default::default()}
+    }
+
+    pub fn is_consistent(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+/// Result of the cross-store consistency check of a database.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StoreCheckReport {
+    pub nodes: usize,
+    pub relationships: usize,
+    pub properties: usize,
+    /// Property records in use which are not chained to any node or relationship.
+    pub unreferenced_properties: usize,
+    /// Dynamic records holding the property keys and values that are not inlined.
+    pub dynamic_records: usize,
+    pub errors: Vec<String>,
+}
+
+impl StoreCheckReport {
+    pub fn is_consistent(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
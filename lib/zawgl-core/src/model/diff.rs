@@ -0,0 +1,254 @@
+// MIT License
+//
+// Copyright (c) 2022 Alexandre RICCIARDI
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use std::collections::{HashMap, HashSet};
+
+use super::{Node, Property, PropertyGraph, PropertyValue, Relationship};
+use super::changes::{ChangeEvent, ChangeKind};
+
+/// How the elements of the two graphs are paired.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffKey {
+    Id,
+    Variable,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum ElementKey {
+    Id(u64),
+    Variable(String),
+}
+
+fn make_key(id: Option<u64>, var: &Option<String>, key: DiffKey) -> Option<ElementKey> {
+    match key {
+        DiffKey::Id => id.map(ElementKey::Id),
+        DiffKey::Variable => var.clone().map(ElementKey::Variable),
+    }
+}
+
+/// Relationship of a diff with its endpoints in the graph it comes from.
+#[derive(Debug, Clone)]
+pub struct DiffRelationship {
+    pub relationship: Relationship,
+    pub source: Node,
+    pub target: Node,
+}
+
+/// Changed elements are given as their version in the old graph then in the new one.
+#[derive(Debug, Clone, Default)]
+pub struct GraphDiff {
+    pub added_nodes: Vec<Node>,
+    pub removed_nodes: Vec<Node>,
+    pub changed_nodes: Vec<(Node, Node)>,
+    pub added_relationships: Vec<DiffRelationship>,
+    pub removed_relationships: Vec<DiffRelationship>,
+    pub changed_relationships: Vec<(DiffRelationship, DiffRelationship)>,
+}
+
+impl GraphDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added_nodes.is_empty() && self.removed_nodes.is_empty() && self.changed_nodes.is_empty()
+            && self.added_relationships.is_empty() && self.removed_relationships.is_empty() && self.changed_relationships.is_empty()
+    }
+
+    /// Change events of the diff, removed relationships first so that the events can be replayed in order.
+    /// None if an element or the endpoint of a relationship has no id.
+    pub fn to_change_events(&self) -> Option<Vec<ChangeEvent>> {
+        let node_event = |kind, node: &Node| node.get_id().map(|_| ChangeEvent::Node{kind, node: node.clone()});
+        let relationship_event = |kind, rel: &DiffRelationship| {
+            rel.relationship.get_id()?;
+            Some(ChangeEvent::Relationship{kind, relationship: rel.relationship.clone(), source_id: rel.source.get_id()?, target_id: rel.target.get_id()?})
+        };
+        let mut events = Vec::new();
+        for rel in &self.removed_relationships {
+            events.push(relationship_event(ChangeKind::Deleted, rel)?);
+        }
+        for node in &self.removed_nodes {
+            events.push(node_event(ChangeKind::Deleted, node)?);
+        }
+        for node in &self.added_nodes {
+            events.push(node_event(ChangeKind::Created, node)?);
+        }
+        for rel in &self.added_relationships {
+            events.push(relationship_event(ChangeKind::Created, rel)?);
+        }
+        for (_, node) in &self.changed_nodes {
+            events.push(node_event(ChangeKind::Updated, node)?);
+        }
+        for (_, rel) in &self.changed_relationships {
+            events.push(relationship_event(ChangeKind::Updated, rel)?);
+        }
+        Some(events)
+    }
+}
+
+fn same_labels(labels: &[String], other_labels: &[String]) -> bool {
+    labels.iter().collect::<HashSet<&String>>() == other_labels.iter().collect::<HashSet<&String>>()
+}
+
+fn get_properties_by_name(properties: &[Property]) -> HashMap<&str, &PropertyValue> {
+    properties.iter().map(|p| (p.get_name(), p.get_value())).collect()
+}
+
+/// Properties are compared by name and value, their ids and order are ignored.
+fn same_properties(properties: &[Property], other_properties: &[Property]) -> bool {
+    get_properties_by_name(properties) == get_properties_by_name(other_properties)
+}
+
+fn collect_relationships(graph: &PropertyGraph) -> Vec<DiffRelationship> {
+    graph.get_relationships_and_edges().iter().map(|edge| DiffRelationship{
+        relationship: edge.relationship.clone(),
+        source: graph.get_node_ref(&edge.source).clone(),
+        target: graph.get_node_ref(&edge.target).clone(),
+    }).collect()
+}
+
+/// Pairs the elements of both graphs having the same key then compares their labels, properties and
+/// for relationships the keys of their endpoints. Elements without key are always added or removed.
+pub fn diff_graphs(old: &PropertyGraph, new: &PropertyGraph, key: DiffKey) -> GraphDiff {
+    let mut diff = GraphDiff::default();
+    let node_key = |node: &Node| make_key(node.get_id(), node.get_var(), key);
+    let rel_key = |rel: &DiffRelationship| make_key(rel.relationship.get_id(), rel.relationship.get_var(), key);
+
+    let mut new_nodes = HashMap::new();
+    for node in new.get_nodes() {
+        if let Some(k) = node_key(node) {
+            new_nodes.entry(k).or_insert(node);
+        }
+    }
+    let mut paired = HashSet::new();
+    for node in old.get_nodes() {
+        match node_key(node).and_then(|k| new_nodes.get(&k).map(|new_node| (k, *new_node))) {
+            Some((k, new_node)) if paired.insert(k.clone()) => {
+                if !same_labels(node.get_labels_ref(), new_node.get_labels_ref()) || !same_properties(node.get_properties_ref(), new_node.get_properties_ref()) {
+                    diff.changed_nodes.push((node.clone(), new_node.clone()));
+                }
+            },
+            _ => diff.removed_nodes.push(node.clone()),
+        }
+    }
+    diff.added_nodes = new.get_nodes().into_iter().filter(|node| node_key(node).is_none_or(|k| !paired.contains(&k))).cloned().collect();
+
+    let new_relationships = collect_relationships(new);
+    let mut new_relationships_by_key = HashMap::new();
+    for rel in &new_relationships {
+        if let Some(k) = rel_key(rel) {
+            new_relationships_by_key.entry(k).or_insert(rel);
+        }
+    }
+    let mut paired = HashSet::new();
+    for rel in collect_relationships(old) {
+        match rel_key(&rel).and_then(|k| new_relationships_by_key.get(&k).map(|new_rel| (k, *new_rel))) {
+            Some((k, new_rel)) if paired.insert(k.clone()) => {
+                let same_endpoints = node_key(&rel.source) == node_key(&new_rel.source) && node_key(&rel.target) == node_key(&new_rel.target);
+                if !same_endpoints || !same_labels(rel.relationship.get_labels_ref(), new_rel.relationship.get_labels_ref())
+                    || !same_properties(rel.relationship.get_properties_ref(), new_rel.relationship.get_properties_ref()) {
+                    diff.changed_relationships.push((rel, new_rel.clone()));
+                }
+            },
+            _ => diff.removed_relationships.push(rel),
+        }
+    }
+    diff.added_relationships = new_relationships.into_iter().filter(|rel| rel_key(rel).is_none_or(|k| !paired.contains(&k))).collect();
+    diff
+}
+
+#[cfg(test)]
+mod test_diff {
+    use super::*;
+
+    fn make_node(id: u64, var: &str, label: &str, age: i64) -> Node {
+        let mut node = Node::new();
+        node.set_id(Some(id));
+        node.set_var(var);
+        node.set_labels(vec![String::from(label)]);
+        node.set_properties(vec![Property::new(String::from("age"), PropertyValue::PInteger(age))]);
+        node
+    }
+
+    fn make_relationship(id: u64, var: &str, label: &str) -> Relationship {
+        let mut rel = Relationship::new();
+        rel.set_id(Some(id));
+        rel.set_var(var);
+        rel.set_labels(vec![String::from(label)]);
+        rel
+    }
+
+    fn get_ids(nodes: &[Node]) -> Vec<Option<u64>> {
+        nodes.iter().map(|node| node.get_id()).collect()
+    }
+
+    #[test]
+    fn test_diff_by_id() {
+        let mut old = PropertyGraph::new();
+        let a = old.add_node(make_node(1, "a", "Person", 30));
+        let b = old.add_node(make_node(2, "b", "Person", 40));
+        let c = old.add_node(make_node(3, "c", "Person", 50));
+        old.add_relationship(make_relationship(10, "r", "KNOWS"), a, b);
+        old.add_relationship(make_relationship(11, "s", "KNOWS"), b, c);
+
+        let mut new = PropertyGraph::new();
+        let a = new.add_node(make_node(1, "a", "Person", 30));
+        let b = new.add_node(make_node(2, "b", "Person", 41));
+        let d = new.add_node(make_node(4, "d", "Person", 20));
+        new.add_relationship(make_relationship(10, "r", "KNOWS"), a, d);
+        new.add_relationship(make_relationship(12, "t", "LIKES"), b, a);
+
+        let diff = diff_graphs(&old, &new, DiffKey::Id);
+        assert_eq!(get_ids(&diff.added_nodes), vec![Some(4)]);
+        assert_eq!(get_ids(&diff.removed_nodes), vec![Some(3)]);
+        assert_eq!(diff.changed_nodes.len(), 1);
+        assert_eq!(diff.changed_nodes[0].1.get_properties_ref()[0].get_value(), &PropertyValue::PInteger(41));
+        assert_eq!(diff.changed_relationships.len(), 1);
+        assert_eq!(diff.changed_relationships[0].1.target.get_id(), Some(4));
+        assert_eq!(diff.removed_relationships[0].relationship.get_id(), Some(11));
+        assert_eq!(diff.added_relationships[0].relationship.get_id(), Some(12));
+
+        let events = diff.to_change_events().unwrap();
+        let kinds = events.iter().map(|event| event.get_kind()).collect::<Vec<ChangeKind>>();
+        assert_eq!(kinds, vec![ChangeKind::Deleted, ChangeKind::Deleted, ChangeKind::Created, ChangeKind::Created, ChangeKind::Updated, ChangeKind::Updated]);
+        assert!(diff_graphs(&new, &new, DiffKey::Id).is_empty());
+    }
+
+    #[test]
+    fn test_diff_by_variable() {
+        let mut old = PropertyGraph::new();
+        old.add_node(make_node(1, "a", "Person", 30));
+        let mut new = PropertyGraph::new();
+        let mut renamed = Node::new();
+        renamed.set_var("a");
+        renamed.set_labels(vec![String::from("Person")]);
+        renamed.set_properties(vec![Property::new(String::from("age"), PropertyValue::PInteger(30))]);
+        new.add_node(renamed);
+        new.add_node(Node::new());
+
+        let diff = diff_graphs(&old, &new, DiffKey::Variable);
+        assert!(diff.changed_nodes.is_empty());
+        assert!(diff.removed_nodes.is_empty());
+        assert_eq!(diff.added_nodes.len(), 1);
+        assert!(diff.to_change_events().is_none());
+
+        let diff = diff_graphs(&old, &new, DiffKey::Id);
+        assert_eq!(diff.removed_nodes.len(), 1);
+        assert_eq!(diff.added_nodes.len(), 2);
+    }
+}
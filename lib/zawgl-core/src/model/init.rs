@@ -19,10 +19,128 @@
 // SOFTWARE.
 
 use super::super::config::*;
+use super::super::buf_config::{PAGE_SIZE, HEADER_SIZE, FREE_LIST_PTR_SIZE, KEY_SIZE, NB_CELL, NODE_PTR_SIZE, CELL_HEADER_SIZE, OVERFLOW_CELL_PTR_SIZE, BTREE_NODE_HEADER_SIZE, FREE_CELLS_NEXT_NODE_PTR_SIZE};
 use std::path;
 use std::env;
+use std::time::Duration;
 use log::info;
 
+/// When the store files are fsynced.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Durability {
+    /// Every page write is fsynced.
+    Always,
+    /// Store files are fsynced once per sync of the records managers, at transaction commit.
+    OnCommit,
+    /// Store files are fsynced on sync if the interval has elapsed since their last fsync.
+    Periodic(Duration),
+}
+
+/// Compression of the values saved in the dynamic store.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PropertyCompression {
+    Disabled,
+    /// Values of at least `threshold` bytes are compressed with zstd when it makes them smaller.
+    Zstd{threshold: usize},
+}
+
+/// Allocation of the ids of the nodes and relationships created in the stores.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IdAllocation {
+    /// Ids freed by deletions are reused before new ones, keeping the stores dense.
+    #[default]
+    Recycle,
+    /// Ids grow from the high-water mark saved in the store files, freed ids are never reused.
+    Sequential,
+}
+
+/// How the store files pages are read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IoMode {
+    ReadWrite,
+    /// Pages are read from memory maps of the store files, falling back to file reads if a file can't be mapped.
+    Mmap,
+}
+
+/// Logging of the requests of the cypher and gremlin handlers, each request is logged at info level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct QueryLog {
+    /// Requests lasting longer are logged at warn level along with their parameters.
+    pub slow_query_threshold: Option<Duration>,
+    /// Replaces the parameters of the slow queries by a placeholder in the log.
+    pub redact_parameters: bool,
+}
+
+/// Settings that can be changed while the database is open, the requests started after the change use them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReloadableSettings {
+    pub page_cache_size: usize,
+    pub query_log: QueryLog,
+}
+
+impl Default for ReloadableSettings {
+    fn default() -> Self {
+        ReloadableSettings{page_cache_size: DEFAULT_PAGE_CACHE_SIZE, query_log: QueryLog::default()}
+    }
+}
+
+/// The key bytes of a cell also hold the data pointers of the leaf cells, after a 2 bytes counter.
+const MIN_INDEX_KEY_SIZE: usize = 2 + NODE_PTR_SIZE;
+const MIN_INDEX_NB_CELLS: usize = 4;
+
+/// Layout of the b-tree nodes of the secondary indexes, keys longer than the key size are chained in overflow cells.
+/// It is saved in the header of an index file when it is created.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IndexLayout {
+    key_size: usize,
+    nb_cells: usize,
+}
+
+impl IndexLayout {
+    /// None if a node of the layout does not fit in a page.
+    pub fn new(key_size: usize, nb_cells: usize) -> Option<Self> {
+        let layout = IndexLayout{key_size, nb_cells};
+        if key_size < MIN_INDEX_KEY_SIZE || nb_cells < MIN_INDEX_NB_CELLS || layout.get_nb_records_per_page() == 0 {
+            None
+        } else {
+            Some(layout)
+        }
+    }
+
+    pub fn get_key_size(&self) -> usize {
+        self.key_size
+    }
+
+    pub fn get_nb_cells(&self) -> usize {
+        self.nb_cells
+    }
+
+    pub fn get_cell_size(&self) -> usize {
+        CELL_HEADER_SIZE + NODE_PTR_SIZE + OVERFLOW_CELL_PTR_SIZE + self.key_size
+    }
+
+    pub fn get_node_record_size(&self) -> usize {
+        BTREE_NODE_HEADER_SIZE + self.get_cell_size() * self.nb_cells + NODE_PTR_SIZE + FREE_CELLS_NEXT_NODE_PTR_SIZE
+    }
+
+    /// Number of nodes per page once the free list of the page is reserved.
+    pub fn get_nb_records_per_page(&self) -> usize {
+        let page_payload_size = PAGE_SIZE - HEADER_SIZE;
+        let record_size = self.get_node_record_size();
+        let mut nb_records = page_payload_size / record_size;
+        while nb_records > 0 && page_payload_size - nb_records * record_size < FREE_LIST_PTR_SIZE * nb_records {
+            nb_records -= 1;
+        }
+        nb_records
+    }
+}
+
+impl Default for IndexLayout {
+    fn default() -> Self {
+        IndexLayout{key_size: KEY_SIZE, nb_cells: NB_CELL}
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct InitContext<'a> {
     db_dir: String,
@@ -32,7 +150,23 @@ pub struct InitContext<'a> {
     dynamic_store_name: &'a str,
     nodes_labels_index_name: &'a str,
     relationships_types_index_name: &'a str,
+    relationships_properties_index_name: &'a str,
     labels_store_name: &'a str,
+    nodes_properties_index_name: &'a str,
+    nodes_property_keys_index_name: &'a str,
+    constraints_store_name: &'a str,
+    statistics_store_name: &'a str,
+    page_cache_size: usize,
+    durability: Durability,
+    segment_size: u64,
+    io_mode: IoMode,
+    property_compression: PropertyCompression,
+    id_allocation: IdAllocation,
+    query_log: QueryLog,
+    change_data_capture: bool,
+    element_uuids: bool,
+    index_layout: IndexLayout,
+    store_partitions: usize,
 }
 
 fn build_path(dir: &str, file: &str) -> Option<String> {
@@ -42,6 +176,18 @@ fn build_path(dir: &str, file: &str) -> Option<String> {
     file_path.to_str().map(String::from)
 }
 
+/// Path of a partition of a store file, the first partition is the store file itself and the partition `p`
+/// is in the `partitions/p` directory next to it, which is created if it does not exist.
+pub fn build_partition_path(file: &str, partition: usize) -> Option<String> {
+    if partition == 0 {
+        return Some(String::from(file));
+    }
+    let file_path = path::Path::new(file);
+    let dir = file_path.parent()?.join(PARTITIONS_DIR_NAME).join(partition.to_string());
+    std::fs::create_dir_all(&dir).ok()?;
+    dir.join(file_path.file_name()?).to_str().map(String::from)
+}
+
 impl <'a> InitContext<'a> {
     pub fn new(dir: &str) -> Option<Self> {
         let mut dir_path_buf = path::PathBuf::new();
@@ -62,10 +208,147 @@ impl <'a> InitContext<'a> {
             dynamic_store_name: DYN_FILE_NAME,
             nodes_labels_index_name: NODES_LABELS_INDEX_FILE_NAME,
             relationships_types_index_name: RELATIONSHIPS_TYPES_INDEX_FILE_NAME,
+            relationships_properties_index_name: RELATIONSHIPS_PROPERTIES_INDEX_FILE_NAME,
             labels_store_name: LABELS_FILE_NAME,
+            nodes_properties_index_name: NODES_PROPERTIES_INDEX_FILE_NAME,
+            nodes_property_keys_index_name: NODES_PROPERTY_KEYS_INDEX_FILE_NAME,
+            constraints_store_name: CONSTRAINTS_FILE_NAME,
+            statistics_store_name: STATISTICS_FILE_NAME,
+            page_cache_size: DEFAULT_PAGE_CACHE_SIZE,
+            durability: Durability::Always,
+            segment_size: DEFAULT_SEGMENT_SIZE,
+            io_mode: IoMode::ReadWrite,
+            property_compression: PropertyCompression::Disabled,
+            id_allocation: IdAllocation::default(),
+            query_log: QueryLog::default(),
+            change_data_capture: false,
+            element_uuids: false,
+            index_layout: IndexLayout::default(),
+            store_partitions: 1,
         })
     }
 
+    /// Sets the memory budget in bytes of the page cache shared by the stores.
+    pub fn with_page_cache_size(mut self, size: usize) -> Self {
+        self.page_cache_size = size;
+        self
+    }
+
+    pub fn get_page_cache_size(&self) -> usize {
+        self.page_cache_size
+    }
+
+    /// Sets when the store files are fsynced, trading durability for write throughput.
+    pub fn with_durability(mut self, durability: Durability) -> Self {
+        self.durability = durability;
+        self
+    }
+
+    pub fn get_durability(&self) -> Durability {
+        self.durability
+    }
+
+    /// Sets the size in bytes of the segments of the store files, it is rounded down to a multiple of the page size.
+    /// Segments of an existing database keep the size they were created with.
+    pub fn with_segment_size(mut self, size: u64) -> Self {
+        self.segment_size = (size / PAGE_SIZE as u64).max(1) * PAGE_SIZE as u64;
+        self
+    }
+
+    pub fn get_segment_size(&self) -> u64 {
+        self.segment_size
+    }
+
+    pub fn with_io_mode(mut self, io_mode: IoMode) -> Self {
+        self.io_mode = io_mode;
+        self
+    }
+
+    pub fn get_io_mode(&self) -> IoMode {
+        self.io_mode
+    }
+
+    pub fn with_property_compression(mut self, property_compression: PropertyCompression) -> Self {
+        self.property_compression = property_compression;
+        self
+    }
+
+    pub fn get_property_compression(&self) -> PropertyCompression {
+        self.property_compression
+    }
+
+    pub fn with_id_allocation(mut self, id_allocation: IdAllocation) -> Self {
+        self.id_allocation = id_allocation;
+        self
+    }
+
+    pub fn get_id_allocation(&self) -> IdAllocation {
+        self.id_allocation
+    }
+
+    pub fn with_query_log(mut self, query_log: QueryLog) -> Self {
+        self.query_log = query_log;
+        self
+    }
+
+    pub fn get_query_log(&self) -> QueryLog {
+        self.query_log
+    }
+
+    /// Appends the changes of each committed transaction to the change log of the database.
+    pub fn with_change_data_capture(mut self, enabled: bool) -> Self {
+        self.change_data_capture = enabled;
+        self
+    }
+
+    pub fn get_change_data_capture(&self) -> bool {
+        self.change_data_capture
+    }
+
+    /// Gives the nodes and relationships created without one a random uuid property, unique and indexed so that they can be looked up after a dump/restore or a compaction.
+    pub fn with_element_uuids(mut self, enabled: bool) -> Self {
+        self.element_uuids = enabled;
+        self
+    }
+
+    pub fn get_element_uuids(&self) -> bool {
+        self.element_uuids
+    }
+
+    /// Sets the layout of the indexes created with the database, existing indexes keep the layout they were created with.
+    pub fn with_index_layout(mut self, index_layout: IndexLayout) -> Self {
+        self.index_layout = index_layout;
+        self
+    }
+
+    pub fn get_index_layout(&self) -> IndexLayout {
+        self.index_layout
+    }
+
+    /// Experimental: splits the nodes and relationships stores created with the database in partitions with their own files,
+    /// so that a disk can be mounted on each partition directory. Existing stores keep the count of partitions they were created with.
+    pub fn with_store_partitions(mut self, nb_partitions: usize) -> Self {
+        self.store_partitions = nb_partitions.max(1);
+        self
+    }
+
+    pub fn get_store_partitions(&self) -> usize {
+        self.store_partitions
+    }
+
+    pub fn get_reloadable_settings(&self) -> ReloadableSettings {
+        ReloadableSettings{page_cache_size: self.page_cache_size, query_log: self.query_log}
+    }
+
+    pub fn set_reloadable_settings(&mut self, settings: ReloadableSettings) {
+        self.page_cache_size = settings.page_cache_size;
+        self.query_log = settings.query_log;
+    }
+
+    pub fn get_page_cache_capacity(&self) -> usize {
+        (self.page_cache_size / PAGE_SIZE).max(1)
+    }
+
     pub fn get_nodes_store_path(&self) -> Option<String> {
         build_path(self.db_dir.as_str(), self.node_store_name)
     }
@@ -87,7 +370,68 @@ impl <'a> InitContext<'a> {
         build_path(self.db_dir.as_str(), self.relationships_types_index_name)
     }
 
+    pub fn get_relationships_properties_index_path(&self) -> Option<String> {
+        build_path(self.db_dir.as_str(), self.relationships_properties_index_name)
+    }
+
     pub fn get_labels_store_path(&self) -> Option<String> {
         build_path(self.db_dir.as_str(), self.labels_store_name)
     }
+
+    pub fn get_nodes_properties_index_path(&self) -> Option<String> {
+        build_path(self.db_dir.as_str(), self.nodes_properties_index_name)
+    }
+
+    pub fn get_nodes_property_keys_index_path(&self) -> Option<String> {
+        build_path(self.db_dir.as_str(), self.nodes_property_keys_index_name)
+    }
+
+    pub fn get_indexes_paths(&self) -> Option<Vec<String>> {
+        Some(vec![self.get_nodes_labels_index_path()?, self.get_relationships_types_index_path()?, self.get_relationships_properties_index_path()?,
+            self.get_nodes_properties_index_path()?, self.get_nodes_property_keys_index_path()?])
+    }
+
+    /// Paths of every store and index file of the database.
+    pub fn get_stores_paths(&self) -> Option<Vec<String>> {
+        let mut paths = vec![self.get_nodes_store_path()?, self.get_relationships_store_path()?, self.get_properties_store_path()?,
+            self.get_dynamic_store_path()?, self.get_labels_store_path()?, self.get_constraints_store_path()?, self.get_statistics_store_path()?];
+        paths.extend(self.get_indexes_paths()?);
+        for partition in 1..self.store_partitions {
+            paths.push(build_partition_path(&self.get_nodes_store_path()?, partition)?);
+            paths.push(build_partition_path(&self.get_relationships_store_path()?, partition)?);
+        }
+        Some(paths)
+    }
+
+    pub fn get_constraints_store_path(&self) -> Option<String> {
+        build_path(self.db_dir.as_str(), self.constraints_store_name)
+    }
+
+    pub fn get_statistics_store_path(&self) -> Option<String> {
+        build_path(self.db_dir.as_str(), self.statistics_store_name)
+    }
+
+    pub fn get_change_log_path(&self) -> Option<String> {
+        build_path(self.db_dir.as_str(), CHANGE_LOG_FILE_NAME)
+    }
+
+    pub fn get_db_dir(&self) -> &str {
+        &self.db_dir
+    }
+
+    /// Directory of the named databases, the stores of the default database are in the database directory itself.
+    pub fn get_databases_dir(&self) -> Option<String> {
+        build_path(self.db_dir.as_str(), DATABASES_DIR_NAME)
+    }
+
+    /// Context of a named database with the settings of this one, its directory is created if it does not exist.
+    pub fn for_database(&self, name: &str) -> Option<Self> {
+        self.for_directory(&build_path(&self.get_databases_dir()?, name)?)
+    }
+
+    /// Context with the settings of this one for the stores of another directory, it is created if it does not exist.
+    pub fn for_directory(&self, db_dir: &str) -> Option<Self> {
+        std::fs::create_dir_all(db_dir).ok()?;
+        Some(InitContext{db_dir: String::from(db_dir), ..self.clone()})
+    }
 }
\ No newline at end of file
@@ -19,20 +19,48 @@
 // SOFTWARE.
 
 use self::predicates::NamedPropertyPredicate;
+use self::temporal::{Date, DateTime, Duration};
 
 use super::graph::*;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 pub mod init;
 pub mod predicates;
+pub mod constraints;
+pub mod statistics;
+pub mod temporal;
+pub mod changes;
+pub mod diff;
+pub mod check;
 use std::cmp::Ordering;
+use std::collections::BTreeMap;
 use std::hash::Hash;
 use std::hash::Hasher;
 
+/// Name of the property holding the stable external id of the nodes and relationships.
+pub const ELEMENT_UUID_PROPERTY: &str = "uuid";
+
+/// Value of the uuid property when it is a string.
+pub fn get_element_uuid(properties: &[Property]) -> Option<&str> {
+    properties.iter().find(|p| p.get_name() == ELEMENT_UUID_PROPERTY).and_then(|p| match p.get_value() {
+        PropertyValue::PString(uuid) => Some(uuid.as_str()),
+        _ => None,
+    })
+}
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum PropertyValue {
     PString(String),
     PInteger(i64),
     PFloat(f64),
     PBool(bool),
+    PList(Vec<PropertyValue>),
+    PMap(BTreeMap<String, PropertyValue>),
+    PDateTime(DateTime),
+    PDate(Date),
+    PDuration(Duration),
+    PNull,
 }
 
 impl Hash for PropertyValue {
@@ -49,6 +77,24 @@ impl Hash for PropertyValue {
             },
             PropertyValue::PFloat(_) => {
                 
+            },
+            PropertyValue::PList(values) => {
+                values.hash(state);
+            },
+            PropertyValue::PMap(entries) => {
+                entries.hash(state);
+            },
+            PropertyValue::PDateTime(dt) => {
+                dt.epoch_nanos.hash(state);
+            },
+            PropertyValue::PDate(date) => {
+                date.hash(state);
+            },
+            PropertyValue::PDuration(duration) => {
+                duration.hash(state);
+            },
+            PropertyValue::PNull => {
+
             }
         }
     }
@@ -70,6 +116,24 @@ impl PartialEq for PropertyValue {
             (PFloat(_), PFloat(_))  => {
                 false
             },
+            (PList(sval), PList(oval))  => {
+                sval == oval
+            },
+            (PMap(sval), PMap(oval))  => {
+                sval == oval
+            },
+            (PDateTime(sval), PDateTime(oval))  => {
+                sval.epoch_nanos == oval.epoch_nanos
+            },
+            (PDate(sval), PDate(oval))  => {
+                sval == oval
+            },
+            (PDuration(sval), PDuration(oval))  => {
+                sval == oval
+            },
+            (PNull, PNull)  => {
+                true
+            },
             _ => {false}
         }
     }
@@ -92,12 +156,67 @@ impl PartialOrd for PropertyValue {
             (PFloat(sval), PFloat(oval))  => {
                 sval.partial_cmp(oval)
             },
+            (PList(sval), PList(oval))  => {
+                sval.partial_cmp(oval)
+            },
+            (PMap(sval), PMap(oval))  => {
+                sval.partial_cmp(oval)
+            },
+            (PDateTime(sval), PDateTime(oval))  => {
+                Some(sval.epoch_nanos.cmp(&oval.epoch_nanos))
+            },
+            (PDate(sval), PDate(oval))  => {
+                Some(sval.cmp(oval))
+            },
+            (PDuration(sval), PDuration(oval)) if sval == oval => {
+                Some(Ordering::Equal)
+            },
+            (PNull, PNull)  => {
+                Some(Ordering::Equal)
+            },
             _ => {None}
         }
     }
 }
 
+impl From<&str> for PropertyValue {
+    fn from(value: &str) -> Self {
+        PropertyValue::PString(String::from(value))
+    }
+}
+
+impl From<String> for PropertyValue {
+    fn from(value: String) -> Self {
+        PropertyValue::PString(value)
+    }
+}
+
+impl From<i64> for PropertyValue {
+    fn from(value: i64) -> Self {
+        PropertyValue::PInteger(value)
+    }
+}
+
+impl From<f64> for PropertyValue {
+    fn from(value: f64) -> Self {
+        PropertyValue::PFloat(value)
+    }
+}
+
+impl From<bool> for PropertyValue {
+    fn from(value: bool) -> Self {
+        PropertyValue::PBool(value)
+    }
+}
+
+impl <T: Into<PropertyValue>> From<Vec<T>> for PropertyValue {
+    fn from(values: Vec<T>) -> Self {
+        PropertyValue::PList(values.into_iter().map(Into::into).collect())
+    }
+}
+
 #[derive(Hash, Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Property {
     id: Option<u64>,
     name: String,
@@ -131,20 +250,24 @@ impl Property {
 
 
 #[derive(Hash, Eq, PartialEq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Status {
     Match,
     Create,
     Update,
+    Delete,
     Empty,
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Node {
     id: Option<u64>,
     var: Option<String>,
     properties: Vec<Property>,
     labels: Vec<String>,
     status: Status,
+    #[cfg_attr(feature = "serde", serde(skip))]
     property_predicates: Vec<NamedPropertyPredicate>,
 }
 
@@ -216,12 +339,14 @@ impl Node {
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Relationship {
     id: Option<u64>,
     var: Option<String>,
     properties: Vec<Property>,
     labels: Vec<String>,
     status: Status,
+    #[cfg_attr(feature = "serde", serde(skip))]
     property_predicates: Vec<NamedPropertyPredicate>,
 }
 
@@ -290,4 +415,56 @@ impl Relationship {
     }
 }
 
-pub type PropertyGraph = container::GraphContainer<Node, Relationship>;
\ No newline at end of file
+pub type PropertyGraph = container::GraphContainer<Node, Relationship>;
+/// Nodes and relationships of a matched path, the relationship at index `i` links the nodes at `i` and `i + 1`
+/// whatever its direction.
+#[derive(Clone, Debug)]
+pub struct Path {
+    nodes: Vec<Node>,
+    relationships: Vec<Relationship>,
+    forward: Vec<bool>,
+}
+
+impl Path {
+    pub fn new(start: Node) -> Self {
+        Path {nodes: vec![start], relationships: Vec::new(), forward: Vec::new()}
+    }
+
+    /// Extends the path to `node`, `forward` tells whether the relationship goes from the previous end node to `node`.
+    pub fn append(&mut self, relationship: Relationship, forward: bool, node: Node) {
+        self.relationships.push(relationship);
+        self.forward.push(forward);
+        self.nodes.push(node);
+    }
+
+    pub fn get_nodes_ref(&self) -> &Vec<Node> {
+        &self.nodes
+    }
+
+    pub fn get_relationships_ref(&self) -> &Vec<Relationship> {
+        &self.relationships
+    }
+
+    pub fn is_forward(&self, index: usize) -> bool {
+        self.forward.get(index).copied().unwrap_or(true)
+    }
+
+    /// Source and target nodes of the relationship at `index`.
+    pub fn get_endpoints(&self, index: usize) -> Option<(&Node, &Node)> {
+        let (first, second) = (self.nodes.get(index)?, self.nodes.get(index + 1)?);
+        if self.is_forward(index) {
+            Some((first, second))
+        } else {
+            Some((second, first))
+        }
+    }
+
+    /// Number of relationships of the path.
+    pub fn len(&self) -> usize {
+        self.relationships.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.relationships.is_empty()
+    }
+}
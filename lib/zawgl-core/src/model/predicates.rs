@@ -44,6 +44,11 @@ impl NamedPropertyPredicate {
 
 impl PropertyPredicate {
     pub fn eval(&self, value: &PropertyValue) -> bool {
+        if let PropertyValue::PList(values) = value {
+            if !matches!(self, PropertyPredicate::EqualTo(PropertyValue::PList(_))) {
+                return values.iter().any(|v| self.eval(v));
+            }
+        }
         match &self {
             PropertyPredicate::GreaterThan(v) => {
                 v < value
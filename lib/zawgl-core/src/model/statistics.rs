@@ -0,0 +1,209 @@
+// MIT License
+//
+// Copyright (c) 2022 Alexandre RICCIARDI
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use std::collections::{BTreeSet, HashMap};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum StatisticKey {
+    Nodes,
+    Relationships,
+    Label(String),
+    RelationshipType(String),
+    NodeProperty(String),
+    RelationshipProperty(String),
+}
+
+impl StatisticKey {
+    pub fn to_key_string(&self) -> String {
+        match self {
+            StatisticKey::Nodes => String::from("n"),
+            StatisticKey::Relationships => String::from("r"),
+            StatisticKey::Label(label) => format!("l:{}", label),
+            StatisticKey::RelationshipType(rel_type) => format!("t:{}", rel_type),
+            StatisticKey::NodeProperty(name) => format!("p:{}", name),
+            StatisticKey::RelationshipProperty(name) => format!("q:{}", name),
+        }
+    }
+
+    pub fn from_key_string(key: &str) -> Option<Self> {
+        match key {
+            "n" => Some(StatisticKey::Nodes),
+            "r" => Some(StatisticKey::Relationships),
+            _ => {
+                let (kind, name) = key.split_once(':')?;
+                match kind {
+                    "l" => Some(StatisticKey::Label(String::from(name))),
+                    "t" => Some(StatisticKey::RelationshipType(String::from(name))),
+                    "p" => Some(StatisticKey::NodeProperty(String::from(name))),
+                    "q" => Some(StatisticKey::RelationshipProperty(String::from(name))),
+                    _ => None,
+                }
+            }
+        }
+    }
+}
+
+/// Cardinalities maintained by the repository and used to plan pattern matching.
+#[derive(Debug, Clone, Default)]
+pub struct GraphStatistics {
+    counts: HashMap<StatisticKey, u64>,
+}
+
+impl GraphStatistics {
+    pub fn new() -> Self {
+        GraphStatistics{counts: HashMap::new()}
+    }
+
+    pub fn get(&self, key: &StatisticKey) -> u64 {
+        self.counts.get(key).copied().unwrap_or(0)
+    }
+
+    pub fn set(&mut self, key: StatisticKey, count: u64) {
+        self.counts.insert(key, count);
+    }
+
+    pub fn increment(&mut self, key: StatisticKey) {
+        *self.counts.entry(key).or_insert(0) += 1;
+    }
+
+    pub fn decrement(&mut self, key: StatisticKey) {
+        if let Some(count) = self.counts.get_mut(&key) {
+            *count = count.saturating_sub(1);
+        }
+    }
+
+    pub fn get_counts(&self) -> &HashMap<StatisticKey, u64> {
+        &self.counts
+    }
+
+    pub fn get_nodes_count(&self) -> u64 {
+        self.get(&StatisticKey::Nodes)
+    }
+
+    pub fn get_relationships_count(&self) -> u64 {
+        self.get(&StatisticKey::Relationships)
+    }
+
+    pub fn get_label_count(&self, label: &str) -> u64 {
+        self.get(&StatisticKey::Label(String::from(label)))
+    }
+
+    pub fn get_relationship_type_count(&self, rel_type: &str) -> u64 {
+        self.get(&StatisticKey::RelationshipType(String::from(rel_type)))
+    }
+
+    pub fn get_node_property_count(&self, name: &str) -> u64 {
+        self.get(&StatisticKey::NodeProperty(String::from(name)))
+    }
+
+    /// Sorted names of the keys still counting at least one node or relationship.
+    fn get_names<F>(&self, get_name: F) -> Vec<String> where F: Fn(&StatisticKey) -> Option<&String> {
+        let names: BTreeSet<&String> = self.counts.iter().filter(|(_, count)| **count > 0).filter_map(|(key, _)| get_name(key)).collect();
+        names.into_iter().cloned().collect()
+    }
+
+    pub fn get_labels(&self) -> Vec<String> {
+        self.get_names(|key| match key {
+            StatisticKey::Label(label) => Some(label),
+            _ => None,
+        })
+    }
+
+    pub fn get_relationship_types(&self) -> Vec<String> {
+        self.get_names(|key| match key {
+            StatisticKey::RelationshipType(rel_type) => Some(rel_type),
+            _ => None,
+        })
+    }
+
+    /// Property keys of both nodes and relationships.
+    pub fn get_property_keys(&self) -> Vec<String> {
+        self.get_names(|key| match key {
+            StatisticKey::NodeProperty(name) | StatisticKey::RelationshipProperty(name) => Some(name),
+            _ => None,
+        })
+    }
+
+    /// Fraction of the nodes carrying the given property.
+    pub fn get_node_property_ratio(&self, name: &str) -> f64 {
+        let nodes_count = self.get_nodes_count();
+        if nodes_count == 0 {
+            0.0
+        } else {
+            (self.get_node_property_count(name) as f64 / nodes_count as f64).min(1.0)
+        }
+    }
+}
+
+/// Page cache usage of a repository, counted since the repository was opened.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PageCacheStatistics {
+    pub capacity: usize,
+    pub cached_pages: usize,
+    pub dirty_pages: usize,
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+}
+
+/// Pages read into the page cache by a warm-up, per store in reading order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WarmUpReport {
+    pub stores: Vec<(String, usize)>,
+    pub page_cache: PageCacheStatistics,
+}
+
+impl WarmUpReport {
+    pub fn get_pages_count(&self) -> usize {
+        self.stores.iter().map(|(_, pages)| pages).sum()
+    }
+}
+
+#[cfg(test)]
+mod test_statistics {
+    use super::*;
+
+    #[test]
+    fn test_key_string() {
+        let keys = vec![StatisticKey::Nodes, StatisticKey::Relationships, StatisticKey::Label(String::from("Person")),
+            StatisticKey::RelationshipType(String::from("KNOWS")), StatisticKey::NodeProperty(String::from("name")),
+            StatisticKey::RelationshipProperty(String::from("since"))];
+        for key in keys {
+            assert_eq!(Some(key.clone()), StatisticKey::from_key_string(&key.to_key_string()));
+        }
+    }
+
+    #[test]
+    fn test_schema_names() {
+        let mut statistics = GraphStatistics::new();
+        statistics.increment(StatisticKey::Label(String::from("Person")));
+        statistics.increment(StatisticKey::Label(String::from("City")));
+        statistics.decrement(StatisticKey::Label(String::from("City")));
+        statistics.increment(StatisticKey::RelationshipType(String::from("KNOWS")));
+        statistics.increment(StatisticKey::NodeProperty(String::from("name")));
+        statistics.increment(StatisticKey::RelationshipProperty(String::from("since")));
+        statistics.increment(StatisticKey::RelationshipProperty(String::from("name")));
+        assert_eq!(vec!["Person"], statistics.get_labels());
+        assert_eq!(vec!["KNOWS"], statistics.get_relationship_types());
+        assert_eq!(vec!["name", "since"], statistics.get_property_keys());
+    }
+}
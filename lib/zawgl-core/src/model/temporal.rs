@@ -0,0 +1,287 @@
+// MIT License
+//
+// Copyright (c) 2022 Alexandre RICCIARDI
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::fmt::Write;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use chrono::{Datelike, FixedOffset, NaiveDate, NaiveDateTime, Offset, SecondsFormat, TimeZone, Utc};
+
+const NANOS_PER_SECOND: i64 = 1_000_000_000;
+const NANOS_PER_MINUTE: i64 = 60 * NANOS_PER_SECOND;
+const NANOS_PER_HOUR: i64 = 60 * NANOS_PER_MINUTE;
+const UNIX_EPOCH_DAYS_FROM_CE: i64 = 719_163;
+
+/// An instant stored as nanoseconds since the unix epoch along with the offset of its timezone in seconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DateTime {
+    pub epoch_nanos: i64,
+    pub offset_seconds: i32,
+}
+
+impl DateTime {
+    pub fn new(epoch_nanos: i64, offset_seconds: i32) -> Self {
+        DateTime{epoch_nanos: epoch_nanos, offset_seconds: offset_seconds}
+    }
+
+    pub fn now() -> Self {
+        DateTime::from_chrono(&Utc::now())
+    }
+
+    pub fn from_parts(date: &Date, hour: u32, minute: u32, second: u32, nanosecond: u32, offset_seconds: i32) -> Option<Self> {
+        let naive = date.to_naive()?.and_hms_nano_opt(hour, minute, second, nanosecond)?;
+        let local = FixedOffset::east_opt(offset_seconds)?.from_local_datetime(&naive).single()?;
+        Some(DateTime::from_chrono(&local))
+    }
+
+    /// Parses an ISO 8601 date time, values without an offset are read as UTC.
+    pub fn parse(value: &str) -> Option<Self> {
+        if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(value) {
+            return Some(DateTime::from_chrono(&dt))
+        }
+        let naive = match NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f") {
+            Ok(naive) => naive,
+            Err(_) => Date::parse(value)?.to_naive()?.and_hms_opt(0, 0, 0)?,
+        };
+        Some(DateTime::from_chrono(&Utc.from_utc_datetime(&naive)))
+    }
+
+    pub fn get_date(&self) -> Option<Date> {
+        self.to_chrono().map(|dt| Date::from_naive(&dt.naive_local().date()))
+    }
+
+    fn from_chrono<Tz: TimeZone>(dt: &chrono::DateTime<Tz>) -> Self {
+        let epoch_nanos = dt.timestamp() * NANOS_PER_SECOND + dt.timestamp_subsec_nanos() as i64;
+        DateTime::new(epoch_nanos, dt.offset().fix().local_minus_utc())
+    }
+
+    fn to_chrono(&self) -> Option<chrono::DateTime<FixedOffset>> {
+        let offset = FixedOffset::east_opt(self.offset_seconds)?;
+        let seconds = self.epoch_nanos.div_euclid(NANOS_PER_SECOND);
+        let nanos = self.epoch_nanos.rem_euclid(NANOS_PER_SECOND) as u32;
+        Utc.timestamp_opt(seconds, nanos).single().map(|utc| utc.with_timezone(&offset))
+    }
+}
+
+impl fmt::Display for DateTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.to_chrono() {
+            Some(dt) => f.write_str(&dt.to_rfc3339_opts(SecondsFormat::AutoSi, true)),
+            None => write!(f, "{}", self.epoch_nanos),
+        }
+    }
+}
+
+/// A calendar date stored as days since the unix epoch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Date {
+    pub epoch_days: i64,
+}
+
+impl Date {
+    pub fn new(epoch_days: i64) -> Self {
+        Date{epoch_days: epoch_days}
+    }
+
+    pub fn today() -> Self {
+        Date::from_naive(&Utc::now().naive_utc().date())
+    }
+
+    pub fn from_ymd(year: i32, month: u32, day: u32) -> Option<Self> {
+        NaiveDate::from_ymd_opt(year, month, day).map(|d| Date::from_naive(&d))
+    }
+
+    /// Parses an ISO 8601 `YYYY-MM-DD` date.
+    pub fn parse(value: &str) -> Option<Self> {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").ok().map(|d| Date::from_naive(&d))
+    }
+
+    fn from_naive(date: &NaiveDate) -> Self {
+        Date::new(date.num_days_from_ce() as i64 - UNIX_EPOCH_DAYS_FROM_CE)
+    }
+
+    fn to_naive(&self) -> Option<NaiveDate> {
+        NaiveDate::from_num_days_from_ce_opt(i32::try_from(self.epoch_days + UNIX_EPOCH_DAYS_FROM_CE).ok()?)
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.to_naive() {
+            Some(date) => write!(f, "{}", date.format("%Y-%m-%d")),
+            None => write!(f, "{}", self.epoch_days),
+        }
+    }
+}
+
+/// A duration keeps months and days apart from the time part as they don't have a fixed length.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Duration {
+    pub months: i64,
+    pub days: i64,
+    pub nanos: i64,
+}
+
+impl Duration {
+    pub fn new(months: i64, days: i64, nanos: i64) -> Self {
+        Duration{months: months, days: days, nanos: nanos}
+    }
+
+    /// Parses an ISO 8601 duration such as `P1Y2M10DT2H30M15.5S`.
+    pub fn parse(value: &str) -> Option<Self> {
+        let period = value.strip_prefix('P')?;
+        let (date_part, time_part) = match period.find('T') {
+            Some(index) => (&period[..index], Some(&period[index + 1..])),
+            None => (period, None),
+        };
+        let mut duration = Duration::new(0, 0, 0);
+        for (number, unit) in split_components(date_part)? {
+            let n: i64 = number.parse().ok()?;
+            match unit {
+                'Y' => duration.months += n * 12,
+                'M' => duration.months += n,
+                'W' => duration.days += n * 7,
+                'D' => duration.days += n,
+                _ => return None,
+            }
+        }
+        if let Some(time) = time_part {
+            for (number, unit) in split_components(time)? {
+                match unit {
+                    'H' => duration.nanos += number.parse::<i64>().ok()? * NANOS_PER_HOUR,
+                    'M' => duration.nanos += number.parse::<i64>().ok()? * NANOS_PER_MINUTE,
+                    'S' => duration.nanos += parse_seconds(number)?,
+                    _ => return None,
+                }
+            }
+        }
+        Some(duration)
+    }
+}
+
+fn split_components(value: &str) -> Option<Vec<(&str, char)>> {
+    let mut components = Vec::new();
+    let mut start = 0;
+    for (index, c) in value.char_indices() {
+        if c.is_ascii_alphabetic() {
+            if index == start {
+                return None
+            }
+            components.push((&value[start..index], c));
+            start = index + 1;
+        }
+    }
+    if start == value.len() {
+        Some(components)
+    } else {
+        None
+    }
+}
+
+fn parse_seconds(value: &str) -> Option<i64> {
+    let (seconds, fraction) = match value.find('.') {
+        Some(index) => (&value[..index], &value[index + 1..]),
+        None => (value, ""),
+    };
+    if fraction.len() > 9 || !fraction.chars().all(|c| c.is_ascii_digit()) {
+        return None
+    }
+    let whole: i64 = seconds.parse().ok()?;
+    let fraction_nanos = if fraction.is_empty() { 0 } else { format!("{:0<9}", fraction).parse::<i64>().ok()? };
+    let sign = if seconds.starts_with('-') { -1 } else { 1 };
+    Some(whole * NANOS_PER_SECOND + sign * fraction_nanos)
+}
+
+impl fmt::Display for Duration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut iso = String::from("P");
+        let (years, months) = (self.months / 12, self.months % 12);
+        if years != 0 {
+            write!(iso, "{}Y", years)?;
+        }
+        if months != 0 {
+            write!(iso, "{}M", months)?;
+        }
+        if self.days != 0 {
+            write!(iso, "{}D", self.days)?;
+        }
+        if self.nanos != 0 || iso.len() == 1 {
+            iso.push('T');
+            let sign = if self.nanos < 0 { "-" } else { "" };
+            let nanos = self.nanos.abs();
+            let (hours, minutes) = (nanos / NANOS_PER_HOUR, nanos % NANOS_PER_HOUR / NANOS_PER_MINUTE);
+            let (seconds, fraction) = (nanos % NANOS_PER_MINUTE / NANOS_PER_SECOND, nanos % NANOS_PER_SECOND);
+            if hours != 0 {
+                write!(iso, "{}{}H", sign, hours)?;
+            }
+            if minutes != 0 {
+                write!(iso, "{}{}M", sign, minutes)?;
+            }
+            if fraction != 0 {
+                write!(iso, "{}{}.{}S", sign, seconds, format!("{:09}", fraction).trim_end_matches('0'))?;
+            } else if seconds != 0 || nanos == 0 {
+                write!(iso, "{}{}S", sign, seconds)?;
+            }
+        }
+        f.write_str(&iso)
+    }
+}
+
+#[cfg(test)]
+mod test_temporal {
+    use super::*;
+
+    #[test]
+    fn test_date_time() {
+        let dt = DateTime::parse("2015-07-21T21:40:32.142+01:00").unwrap();
+        assert_eq!(3600, dt.offset_seconds);
+        assert_eq!(1437511232142000000, dt.epoch_nanos);
+        assert_eq!("2015-07-21T21:40:32.142+01:00", dt.to_string());
+        assert_eq!(Some(dt), DateTime::from_parts(&Date::from_ymd(2015, 7, 21).unwrap(), 21, 40, 32, 142000000, 3600));
+        assert_eq!("2015-07-21T00:00:00Z", DateTime::parse("2015-07-21").unwrap().to_string());
+        assert_eq!("2015-07-21", dt.get_date().unwrap().to_string());
+        assert!(DateTime::parse("21/07/2015").is_none());
+    }
+
+    #[test]
+    fn test_date() {
+        assert_eq!(Some(Date::new(0)), Date::parse("1970-01-01"));
+        assert_eq!(Some(Date::new(-1)), Date::from_ymd(1969, 12, 31));
+        assert_eq!("2000-02-29", Date::from_ymd(2000, 2, 29).unwrap().to_string());
+    }
+
+    #[test]
+    fn test_duration() {
+        let duration = Duration::parse("P1Y2M10DT2H30M15.5S").unwrap();
+        assert_eq!(Duration::new(14, 10, 2 * NANOS_PER_HOUR + 30 * NANOS_PER_MINUTE + 15_500_000_000), duration);
+        assert_eq!("P1Y2M10DT2H30M15.5S", duration.to_string());
+        assert_eq!("PT0S", Duration::new(0, 0, 0).to_string());
+        assert_eq!(Some(Duration::new(0, 14, 0)), Duration::parse("P2W"));
+        assert!(Duration::parse("P1X").is_none());
+        assert!(Duration::parse("1D").is_none());
+    }
+}
@@ -21,11 +21,21 @@
 use super::store::*;
 use super::properties_repository::*;
 use super::super::model::*;
+use super::super::model::constraints::UniqueConstraint;
+use super::super::model::statistics::*;
+use super::super::model::changes::{ChangeEvent, ChangeKind};
+use super::super::model::check::{IndexCheckReport, StoreCheckReport, INDEXES_NAMES};
+use super::super::error::*;
 use super::super::repository::index::b_tree::*;
 use self::records::*;
+use super::records::{BufferPool, SharedBufferPool};
 use std::borrow::BorrowMut;
 use std::collections::HashMap;
 use std::collections::HashSet;
+use std::ops::Range;
+use super::io::segmented_file_access::remove_segmented_file;
+use std::path::Path;
+use uuid::Uuid;
 use super::super::graph::traits::*;
 use super::super::graph::*;
 
@@ -33,24 +43,460 @@ fn parse_labels(labels: &str) -> Option<Vec<String>> {
     Some(labels.split(":").map(|s| String::from(s)).collect())
 }
 
+fn make_unique_index_key(constraint: &UniqueConstraint, value: &PropertyValue) -> String {
+    format!("{}:{}", constraint.label, make_property_index_key(&constraint.property_name, value))
+}
+
+fn make_uuid_index_key(uuid: &str) -> String {
+    make_property_index_key(ELEMENT_UUID_PROPERTY, &PropertyValue::PString(String::from(uuid)))
+}
+
+fn make_property_index_key(name: &str, value: &PropertyValue) -> String {
+    match value {
+        PropertyValue::PString(sval) => format!("{}:s:{}", name, sval),
+        PropertyValue::PInteger(ival) => format!("{}:i:{}", name, ival),
+        PropertyValue::PFloat(fval) => format!("{}:f:{}", name, fval),
+        PropertyValue::PBool(bval) => format!("{}:b:{}", name, bval),
+        PropertyValue::PList(values) => format!("{}:l:[{}]", name, values.iter().map(|v| make_property_index_key("", v)).collect::<Vec<String>>().join(",")),
+        PropertyValue::PMap(entries) => format!("{}:m:{{{}}}", name, entries.iter().map(|(k, v)| make_property_index_key(k, v)).collect::<Vec<String>>().join(",")),
+        PropertyValue::PDateTime(dt) => format!("{}:dt:{}", name, dt.epoch_nanos),
+        PropertyValue::PDate(date) => format!("{}:d:{}", name, date.epoch_days),
+        PropertyValue::PDuration(duration) => format!("{}:du:{}", name, duration),
+        PropertyValue::PNull => format!("{}:n", name),
+    }
+}
+
 pub struct GraphRepository {
     nodes_store: nodes_store::NodesStore,
     relationships_store: relationships_store::RelationshipsStore,
     properties_repository: PropertiesRespository,
     nodes_labels_index: BTreeIndex,
     relationships_labels_index: BTreeIndex,
+    relationships_properties_index: BTreeIndex,
     labels_store: dynamic_store::DynamicStore,
+    nodes_properties_index: BTreeIndex,
+    nodes_property_keys_index: BTreeIndex,
+    constraints_store: constraints_store::ConstraintsStore,
+    constraints: Vec<UniqueConstraint>,
+    statistics_store: statistics_store::StatisticsStore,
+    statistics: GraphStatistics,
+    statistics_ids: HashMap<StatisticKey, (u64, u64)>,
+    pool: SharedBufferPool,
+    commit_count: u64,
+    capture_changes: bool,
+    changes: Vec<ChangeEvent>,
+    element_uuids: bool,
 }
 
 impl GraphRepository {
     pub fn new(init_ctx: &init::InitContext) -> Self {
-        GraphRepository {nodes_store: nodes_store::NodesStore::new(&init_ctx.get_nodes_store_path().unwrap()),
-            relationships_store: relationships_store::RelationshipsStore::new(&init_ctx.get_relationships_store_path().unwrap()),
-            properties_repository: PropertiesRespository::new(&init_ctx.get_properties_store_path().unwrap(), &init_ctx.get_dynamic_store_path().unwrap()),
-            nodes_labels_index: BTreeIndex::new(&init_ctx.get_nodes_labels_index_path().unwrap()),
-            relationships_labels_index: BTreeIndex::new(&init_ctx.get_relationships_types_index_path().unwrap()),
-            labels_store: dynamic_store::DynamicStore::new(&init_ctx.get_labels_store_path().unwrap()),
+        Self::open(init_ctx, &BufferPool::from_context(init_ctx))
+    }
+
+    /// Opens the database, its secondary indexes are rebuilt from the stores and synced if the property keys index
+    /// is missing. The repositories of the database are then created with [`GraphRepository::with_pool`].
+    pub fn open(init_ctx: &init::InitContext, pool: &SharedBufferPool) -> Self {
+        let rebuild_indexes = !Path::new(&init_ctx.get_nodes_property_keys_index_path().unwrap()).exists();
+        if rebuild_indexes {
+            for index_path in init_ctx.get_indexes_paths().unwrap_or_default() {
+                let _ = remove_segmented_file(&index_path);
+            }
+        }
+        let mut repository = Self::with_pool(init_ctx, pool);
+        if rebuild_indexes && repository.rebuild_indexes().is_some() {
+            repository.sync();
+        }
+        repository
+    }
+
+    /// Repository of an opened database, reading and writing its pages through the buffer pool of the database.
+    pub fn with_pool(init_ctx: &init::InitContext, pool: &SharedBufferPool) -> Self {
+        let mut repository = GraphRepository {nodes_store: nodes_store::NodesStore::with_partitions(&init_ctx.get_nodes_store_path().unwrap(), pool, init_ctx.get_store_partitions()),
+            relationships_store: relationships_store::RelationshipsStore::with_partitions(&init_ctx.get_relationships_store_path().unwrap(), pool, init_ctx.get_store_partitions()),
+            properties_repository: PropertiesRespository::new(&init_ctx.get_properties_store_path().unwrap(), &init_ctx.get_dynamic_store_path().unwrap(), pool),
+            nodes_labels_index: BTreeIndex::with_layout(&init_ctx.get_nodes_labels_index_path().unwrap(), pool, init_ctx.get_index_layout()),
+            relationships_labels_index: BTreeIndex::with_layout(&init_ctx.get_relationships_types_index_path().unwrap(), pool, init_ctx.get_index_layout()),
+            relationships_properties_index: BTreeIndex::with_layout(&init_ctx.get_relationships_properties_index_path().unwrap(), pool, init_ctx.get_index_layout()),
+            labels_store: dynamic_store::DynamicStore::new(&init_ctx.get_labels_store_path().unwrap(), pool),
+            nodes_properties_index: BTreeIndex::with_layout(&init_ctx.get_nodes_properties_index_path().unwrap(), pool, init_ctx.get_index_layout()),
+            nodes_property_keys_index: BTreeIndex::with_layout(&init_ctx.get_nodes_property_keys_index_path().unwrap(), pool, init_ctx.get_index_layout()),
+            constraints_store: constraints_store::ConstraintsStore::new(&init_ctx.get_constraints_store_path().unwrap(), pool),
+            constraints: Vec::new(),
+            statistics_store: statistics_store::StatisticsStore::new(&init_ctx.get_statistics_store_path().unwrap(), pool),
+            statistics: GraphStatistics::new(),
+            statistics_ids: HashMap::new(),
+            pool: pool.clone(),
+            commit_count: pool.lock().unwrap().get_commit_count(),
+            capture_changes: init_ctx.get_change_data_capture(),
+            changes: Vec::new(),
+            element_uuids: init_ctx.get_element_uuids(),
+        };
+        repository.properties_repository.set_compression(init_ctx.get_property_compression());
+        repository.nodes_store.set_id_allocation(init_ctx.get_id_allocation());
+        repository.relationships_store.set_id_allocation(init_ctx.get_id_allocation());
+        repository.load_constraints();
+        repository.load_statistics();
+        repository
+    }
+
+    /// Fills the secondary indexes from the stores, they are expected to be empty.
+    pub fn rebuild_indexes(&mut self) -> Option<(usize, usize)> {
+        let mut nb_nodes = 0;
+        let mut nb_relationships = 0;
+        for node_id in self.retrieve_all_nodes_ids()? {
+            let (node, vertex) = self.retrieve_node_by_id(node_id)?;
+            self.index_node(node_id, &node);
+            for (constraint, value) in self.get_node_constrained_values(&node) {
+                self.nodes_properties_index.insert(&make_unique_index_key(&constraint, &value), node_id);
+            }
+            nb_nodes += 1;
+            let mut rel_id = vertex.first_outbound_edge;
+            while let Some(rid) = rel_id {
+                let (rel, edge) = self.retrieve_relationship_by_id(rid)?;
+                self.index_relationship(rid, &rel);
+                nb_relationships += 1;
+                rel_id = edge.next_outbound_edge;
+            }
+        }
+        Some((nb_nodes, nb_relationships))
+    }
+
+    fn index_node(&mut self, node_id: u64, node: &Node) {
+        for label in node.get_labels_ref() {
+            self.nodes_labels_index.insert(label, node_id);
+        }
+        for prop in node.get_properties_ref() {
+            self.nodes_property_keys_index.insert(prop.get_name(), node_id);
+        }
+        if let Some(uuid) = get_element_uuid(node.get_properties_ref()) {
+            self.nodes_properties_index.insert(&make_uuid_index_key(uuid), node_id);
+        }
+    }
+
+    fn unindex_node(&mut self, node_id: u64, node: &Node) {
+        for label in node.get_labels_ref() {
+            self.nodes_labels_index.remove(label, node_id);
+        }
+        for prop in node.get_properties_ref() {
+            self.nodes_property_keys_index.remove(prop.get_name(), node_id);
+        }
+        for (constraint, value) in self.get_node_constrained_values(node) {
+            self.nodes_properties_index.remove(&make_unique_index_key(&constraint, &value), node_id);
+        }
+        if let Some(uuid) = get_element_uuid(node.get_properties_ref()) {
+            self.nodes_properties_index.remove(&make_uuid_index_key(uuid), node_id);
+        }
+    }
+
+    fn index_relationship(&mut self, rel_id: u64, rel: &Relationship) {
+        for label in rel.get_labels_ref() {
+            self.relationships_labels_index.insert(label, rel_id);
+        }
+        for prop in rel.get_properties_ref() {
+            self.relationships_properties_index.insert(&make_property_index_key(prop.get_name(), prop.get_value()), rel_id);
+        }
+    }
+
+    fn unindex_relationship(&mut self, rel_id: u64, rel: &Relationship) {
+        for label in rel.get_labels_ref() {
+            self.relationships_labels_index.remove(label, rel_id);
+        }
+        for prop in rel.get_properties_ref() {
+            self.relationships_properties_index.remove(&make_property_index_key(prop.get_name(), prop.get_value()), rel_id);
+        }
+    }
+
+    fn load_constraints(&mut self) {
+        for cr in self.constraints_store.retrieve_all_constraints().unwrap_or_default() {
+            if let (Some(label), Some(property_name)) = (self.labels_store.load_string(cr.label_id), self.labels_store.load_string(cr.property_id)) {
+                self.constraints.push(UniqueConstraint{label: label, property_name: property_name});
+            }
+        }
+    }
+
+    fn load_statistics(&mut self) {
+        let records = self.statistics_store.retrieve_all_statistics().unwrap_or_default();
+        if records.is_empty() {
+            self.rebuild_statistics();
+            return;
+        }
+        for (record_id, sr) in records {
+            if let Some(key) = self.labels_store.load_string(sr.key_id).and_then(|k| StatisticKey::from_key_string(&k)) {
+                self.statistics.set(key.clone(), sr.count);
+                self.statistics_ids.insert(key, (record_id, sr.key_id));
+            }
+        }
+    }
+
+    fn rebuild_statistics(&mut self) {
+        let mut statistics = GraphStatistics::new();
+        for node_id in self.retrieve_all_nodes_ids().unwrap_or_default() {
+            if let Some((node, vertex)) = self.retrieve_node_by_id(node_id) {
+                add_node_statistics(&mut statistics, &node);
+                let mut rel_id = vertex.first_outbound_edge;
+                while let Some(rid) = rel_id {
+                    match self.retrieve_relationship_by_id(rid) {
+                        Some((rel, edge)) => {
+                            add_relationship_statistics(&mut statistics, &rel);
+                            rel_id = edge.next_outbound_edge;
+                        },
+                        None => break,
+                    }
+                }
+            }
+        }
+        self.statistics = statistics;
+    }
+
+    fn get_index_mut(&mut self, name: &str) -> Option<&mut BTreeIndex> {
+        match name {
+            "nodes labels" => Some(&mut self.nodes_labels_index),
+            "nodes property keys" => Some(&mut self.nodes_property_keys_index),
+            "nodes properties" => Some(&mut self.nodes_properties_index),
+            "relationships types" => Some(&mut self.relationships_labels_index),
+            "relationships properties" => Some(&mut self.relationships_properties_index),
+            _ => None,
+        }
+    }
+
+    /// Reads the nodes store, the relationships store then the given indexes into the page cache,
+    /// stopping once the cache is full so that the warmed pages are not evicted. None if an index is unknown.
+    pub fn warm_up(&mut self, indexes: &[&str]) -> Option<WarmUpReport> {
+        if indexes.iter().any(|name| !INDEXES_NAMES.contains(name)) {
+            return None;
+        }
+        let stats = self.get_page_cache_statistics();
+        let mut budget = stats.capacity.saturating_sub(stats.cached_pages);
+        let mut report = WarmUpReport::default();
+        let nodes_pages = self.nodes_store.prefetch(budget);
+        budget -= nodes_pages;
+        report.stores.push((String::from("nodes"), nodes_pages));
+        let relationships_pages = self.relationships_store.prefetch(budget);
+        budget -= relationships_pages;
+        report.stores.push((String::from("relationships"), relationships_pages));
+        for name in indexes {
+            let index_pages = self.get_index_mut(name)?.prefetch(budget);
+            budget -= index_pages;
+            report.stores.push((String::from(*name), index_pages));
         }
+        report.page_cache = self.get_page_cache_statistics();
+        Some(report)
+    }
+
+    /// Walks every secondary index and checks that its data pointers reference existing nodes or relationships.
+    pub fn check_indexes(&mut self) -> Vec<IndexCheckReport> {
+        let nodes_store = &mut self.nodes_store;
+        let relationships_store = &mut self.relationships_store;
+        vec![
+            self.nodes_labels_index.check("nodes labels", &mut |id| nodes_store.exists(id)),
+            self.nodes_property_keys_index.check("nodes property keys", &mut |id| nodes_store.exists(id)),
+            self.nodes_properties_index.check("nodes properties", &mut |id| nodes_store.exists(id)),
+            self.relationships_labels_index.check("relationships types", &mut |id| relationships_store.exists(id)),
+            self.relationships_properties_index.check("relationships properties", &mut |id| relationships_store.exists(id)),
+        ]
+    }
+
+    /// Checks the invariants between the stores: relationships endpoints exist, adjacency chains terminate on the relationships of their node and property chains are well formed.
+    pub fn check_stores(&mut self) -> StoreCheckReport {
+        let mut report = StoreCheckReport::default();
+        let nodes_ids = self.nodes_store.retrieve_all_nodes_ids().unwrap_or_else(|| {
+            report.errors.push(String::from("nodes store: records can't be listed"));
+            Vec::new()
+        });
+        let rels_ids = self.relationships_store.retrieve_all_relationships_ids().unwrap_or_else(|| {
+            report.errors.push(String::from("relationships store: records can't be listed"));
+            Vec::new()
+        });
+        report.nodes = nodes_ids.len();
+        report.relationships = rels_ids.len();
+
+        let mut outbound_chained = HashSet::new();
+        let mut inbound_chained = HashSet::new();
+        let mut referenced_properties = HashSet::new();
+        for node_id in nodes_ids {
+            let owner = format!("node {}", node_id);
+            let nr = match self.nodes_store.load(node_id) {
+                Some(nr) => nr,
+                None => {
+                    report.errors.push(format!("{}: record can't be loaded", owner));
+                    continue;
+                }
+            };
+            if nr.node_type != 0 && self.labels_store.load_string(nr.node_type).is_none() {
+                report.errors.push(format!("{}: labels record {} can't be loaded", owner, nr.node_type));
+            }
+            self.check_adjacency_chain(node_id, nr.first_outbound_edge, true, &mut outbound_chained, &mut report.errors);
+            self.check_adjacency_chain(node_id, nr.first_inbound_edge, false, &mut inbound_chained, &mut report.errors);
+            self.properties_repository.check_list(&owner, nr.next_prop_id, &mut referenced_properties, &mut report);
+        }
+        for rel_id in rels_ids {
+            let owner = format!("relationship {}", rel_id);
+            let rr = match self.relationships_store.load(rel_id) {
+                Some(rr) => rr,
+                None => {
+                    report.errors.push(format!("{}: record can't be loaded", owner));
+                    continue;
+                }
+            };
+            if !self.nodes_store.exists(rr.source) {
+                report.errors.push(format!("{}: source node {} does not exist", owner, rr.source));
+            } else if !outbound_chained.contains(&rel_id) {
+                report.errors.push(format!("{}: missing from the outbound chain of node {}", owner, rr.source));
+            }
+            if !self.nodes_store.exists(rr.target) {
+                report.errors.push(format!("{}: target node {} does not exist", owner, rr.target));
+            } else if !inbound_chained.contains(&rel_id) {
+                report.errors.push(format!("{}: missing from the inbound chain of node {}", owner, rr.target));
+            }
+            if rr.relationship_type != 0 && self.labels_store.load_string(rr.relationship_type).is_none() {
+                report.errors.push(format!("{}: type record {} can't be loaded", owner, rr.relationship_type));
+            }
+            self.properties_repository.check_list(&owner, rr.next_prop_id, &mut referenced_properties, &mut report);
+        }
+
+        match self.properties_repository.retrieve_all_properties_ids() {
+            Some(props_ids) => {
+                report.properties = props_ids.len();
+                report.unreferenced_properties = props_ids.iter().filter(|id| !referenced_properties.contains(id)).count();
+            },
+            None => report.errors.push(String::from("properties store: records can't be listed")),
+        }
+        report
+    }
+
+    fn check_adjacency_chain(&mut self, node_id: u64, first_rel_id: u64, outbound: bool, chained: &mut HashSet<u64>, errors: &mut Vec<String>) {
+        let direction = if outbound { "outbound" } else { "inbound" };
+        let mut visited = HashSet::new();
+        let mut curr_id = first_rel_id;
+        while curr_id != 0 {
+            if !visited.insert(curr_id) {
+                errors.push(format!("node {}: {} chain cycles on relationship {}", node_id, direction, curr_id));
+                return;
+            }
+            if !self.relationships_store.exists(curr_id) {
+                errors.push(format!("node {}: {} chain references missing relationship {}", node_id, direction, curr_id));
+                return;
+            }
+            let rr = match self.relationships_store.load(curr_id) {
+                Some(rr) => rr,
+                None => {
+                    errors.push(format!("node {}: {} chain relationship {} can't be loaded", node_id, direction, curr_id));
+                    return;
+                }
+            };
+            let (endpoint, next_id) = if outbound { (rr.source, rr.next_outbound_edge) } else { (rr.target, rr.next_inbound_edge) };
+            if endpoint != node_id {
+                errors.push(format!("node {}: {} chain contains relationship {} of node {}", node_id, direction, curr_id, endpoint));
+            } else {
+                chained.insert(curr_id);
+            }
+            curr_id = next_id;
+        }
+    }
+
+    pub fn get_statistics(&self) -> &GraphStatistics {
+        &self.statistics
+    }
+
+    fn sync_statistics(&mut self) {
+        let counts = self.statistics.get_counts().iter().map(|(k, c)| (k.clone(), *c)).collect::<Vec<(StatisticKey, u64)>>();
+        for (key, count) in counts {
+            if let Some((record_id, key_id)) = self.statistics_ids.get(&key).copied() {
+                self.statistics_store.save(record_id, &StatisticRecord{key_id: key_id, count: count});
+            } else if let Some(key_id) = self.labels_store.save_data(key.to_key_string().as_bytes()) {
+                if let Some(record_id) = self.statistics_store.create(&StatisticRecord{key_id: key_id, count: count}) {
+                    self.statistics_ids.insert(key, (record_id, key_id));
+                }
+            }
+        }
+    }
+
+    pub fn get_page_cache_statistics(&self) -> PageCacheStatistics {
+        self.pool.lock().unwrap().get_statistics()
+    }
+
+    pub fn get_constraints(&self) -> &Vec<UniqueConstraint> {
+        &self.constraints
+    }
+
+    fn find_node_with_unique_value(&mut self, constraint: &UniqueConstraint, value: &PropertyValue) -> Option<u64> {
+        let node_ids = self.nodes_properties_index.search(&make_unique_index_key(constraint, value))?;
+        for node_id in node_ids {
+            if !self.nodes_store.exists(node_id) {
+                continue;
+            }
+            if let Some((node, _)) = self.retrieve_node_by_id(node_id) {
+                let has_label = node.get_labels_ref().contains(&constraint.label);
+                let has_value = node.get_properties_ref().iter().any(|p| p.get_name() == constraint.property_name && p.get_value() == value);
+                if has_label && has_value {
+                    return Some(node_id);
+                }
+            }
+        }
+        None
+    }
+
+    /// Id of the node having the uuid property, looked up in the nodes properties index.
+    pub fn find_node_by_uuid(&mut self, uuid: &str) -> Option<u64> {
+        let node_ids = self.nodes_properties_index.search(&make_uuid_index_key(uuid))?;
+        for node_id in node_ids {
+            if !self.nodes_store.exists(node_id) {
+                continue;
+            }
+            if let Some((node, _)) = self.retrieve_node_by_id(node_id) {
+                if get_element_uuid(node.get_properties_ref()) == Some(uuid) {
+                    return Some(node_id);
+                }
+            }
+        }
+        None
+    }
+
+    /// Id of the relationship having the uuid property, looked up in the relationships properties index.
+    pub fn find_relationship_by_uuid(&mut self, uuid: &str) -> Option<u64> {
+        self.fetch_relationships_ids_with_property(ELEMENT_UUID_PROPERTY, &PropertyValue::PString(String::from(uuid))).into_iter().min()
+    }
+
+    fn get_node_constrained_values(&self, node: &Node) -> Vec<(UniqueConstraint, PropertyValue)> {
+        let mut res = Vec::new();
+        for constraint in &self.constraints {
+            if node.get_labels_ref().contains(&constraint.label) {
+                for prop in node.get_properties_ref() {
+                    if prop.get_name() == constraint.property_name {
+                        res.push((constraint.clone(), prop.get_value().clone()));
+                    }
+                }
+            }
+        }
+        res
+    }
+
+    /// Fails without creating the constraint if nodes with the label already share a value of the property.
+    pub fn create_unique_constraint(&mut self, constraint: &UniqueConstraint) -> GraphResult<()> {
+        if self.constraints.contains(constraint) {
+            return Ok(());
+        }
+        let mut values = HashSet::new();
+        let mut to_index = Vec::new();
+        for node_id in self.fetch_nodes_ids_with_labels(&vec![constraint.label.clone()]) {
+            let (node, _) = self.retrieve_node_by_id(node_id).ok_or(GraphError::record(NODES_STORE, node_id, StoreOperation::Load))?;
+            for prop in node.get_properties_ref() {
+                if prop.get_name() == constraint.property_name {
+                    if !values.insert(prop.get_value().clone()) {
+                        return Err(GraphError::UniqueConstraintViolation(constraint.clone()));
+                    }
+                    to_index.push((make_unique_index_key(constraint, prop.get_value()), node_id));
+                }
+            }
+        }
+        for (key, node_id) in to_index {
+            self.nodes_properties_index.insert(&key, node_id);
+        }
+        let label_id = self.labels_store.save_data(constraint.label.as_bytes()).ok_or(GraphError::StoreFull{store: LABELS_STORE})?;
+        let property_id = self.labels_store.save_data(constraint.property_name.as_bytes()).ok_or(GraphError::StoreFull{store: LABELS_STORE})?;
+        self.constraints_store.create(&ConstraintRecord{label_id: label_id, property_id: property_id}).ok_or(GraphError::StoreFull{store: CONSTRAINTS_STORE})?;
+        self.constraints.push(constraint.clone());
+        Ok(())
     }
 
     pub fn fetch_nodes_ids_with_labels(&mut self, labels: &Vec<String>) -> HashSet<u64> {
@@ -58,7 +504,49 @@ impl GraphRepository {
         for label in labels {
             let ids = self.nodes_labels_index.search(label);
             if let Some(node_ids) = &ids {
-                res.extend(node_ids.iter());
+                for node_id in node_ids {
+                    if self.nodes_store.exists(*node_id) {
+                        res.insert(*node_id);
+                    }
+                }
+            }
+        }
+        res
+    }
+
+    pub fn fetch_nodes_ids_with_property_keys(&mut self, names: &[&str]) -> HashSet<u64> {
+        let mut res: Option<HashSet<u64>> = None;
+        for name in names {
+            let ids = self.nodes_property_keys_index.search(name).unwrap_or_default().into_iter().collect::<HashSet<u64>>();
+            res = Some(match res {
+                Some(prev) => prev.intersection(&ids).copied().collect(),
+                None => ids,
+            });
+        }
+        res.unwrap_or_default().into_iter().filter(|id| self.nodes_store.exists(*id)).collect()
+    }
+
+    pub fn fetch_relationships_ids_with_labels(&mut self, labels: &Vec<String>) -> HashSet<u64> {
+        let mut res = HashSet::new();
+        for label in labels {
+            if let Some(rel_ids) = self.relationships_labels_index.search(label) {
+                for rel_id in rel_ids {
+                    if self.relationships_store.exists(rel_id) {
+                        res.insert(rel_id);
+                    }
+                }
+            }
+        }
+        res
+    }
+
+    pub fn fetch_relationships_ids_with_property(&mut self, name: &str, value: &PropertyValue) -> HashSet<u64> {
+        let mut res = HashSet::new();
+        if let Some(rel_ids) = self.relationships_properties_index.search(&make_property_index_key(name, value)) {
+            for rel_id in rel_ids {
+                if self.relationships_store.exists(rel_id) {
+                    res.insert(rel_id);
+                }
             }
         }
         res
@@ -68,6 +556,11 @@ impl GraphRepository {
         self.nodes_store.borrow_mut().retrieve_all_nodes_ids()
     }
 
+    /// Ids of at most `limit` nodes greater than `after`, in ascending order.
+    pub fn retrieve_nodes_ids_after(&mut self, after: u64, limit: usize) -> Option<Vec<u64>> {
+        self.nodes_store.retrieve_nodes_ids_after(after, limit)
+    }
+
     pub fn retrieve_node_by_id(&mut self, node_id: u64) -> Option<(Node, DbVertexData)> {
         let nr = self.nodes_store.load(node_id)?;
         let mut node = Node::new();
@@ -130,65 +623,357 @@ impl GraphRepository {
         Some(edge)
     }
 
-    pub fn create_node(&mut self, node: &Node) -> Option<Node> {
+    /// Ids are read in ascending order so that records sharing a page are loaded together, missing ids are skipped.
+    pub fn retrieve_nodes_by_ids(&mut self, nodes_ids: &[u64]) -> Vec<(Node, DbVertexData)> {
+        let mut ids = nodes_ids.to_vec();
+        ids.sort_unstable();
+        ids.dedup();
+        ids.retain(|id| self.nodes_store.exists(*id));
+        ids.into_iter().filter_map(|id| self.retrieve_node_by_id(id)).collect()
+    }
+
+    pub fn retrieve_nodes_by_id_range(&mut self, range: Range<u64>) -> Vec<(Node, DbVertexData)> {
+        let ids = range.collect::<Vec<u64>>();
+        self.retrieve_nodes_by_ids(&ids)
+    }
+
+    pub fn retrieve_relationships_by_ids(&mut self, rels_ids: &[u64]) -> Vec<(Relationship, DbEdgeData)> {
+        let mut ids = rels_ids.to_vec();
+        ids.sort_unstable();
+        ids.dedup();
+        ids.retain(|id| self.relationships_store.exists(*id));
+        ids.into_iter().filter_map(|id| self.retrieve_relationship_by_id(id)).collect()
+    }
+
+    pub fn retrieve_relationships_by_id_range(&mut self, range: Range<u64>) -> Vec<(Relationship, DbEdgeData)> {
+        let ids = range.collect::<Vec<u64>>();
+        self.retrieve_relationships_by_ids(&ids)
+    }
+
+    pub fn retrieve_edges_data_by_ids(&mut self, rels_ids: &[u64]) -> Vec<(u64, DbEdgeData)> {
+        let mut ids = rels_ids.to_vec();
+        ids.sort_unstable();
+        ids.dedup();
+        ids.retain(|id| self.relationships_store.exists(*id));
+        ids.into_iter().filter_map(|id| self.retrieve_edge_data_by_id(id).map(|edge| (id, edge))).collect()
+    }
+
+    pub fn create_node(&mut self, node: &Node) -> GraphResult<Node> {
+        let constrained_values = self.get_node_constrained_values(node);
+        for (constraint, value) in &constrained_values {
+            if self.find_node_with_unique_value(constraint, value).is_some() {
+                return Err(GraphError::UniqueConstraintViolation(constraint.clone()));
+            }
+        }
         let mut nr = NodeRecord::new();
         let mut res = node.clone();
-        nr.next_prop_id = self.properties_repository.create_list(res.get_properties_mut())?;
+        if self.element_uuids {
+            match get_element_uuid(node.get_properties_ref()) {
+                Some(uuid) => if self.find_node_by_uuid(uuid).is_some() {
+                    return Err(GraphError::DuplicateUuid(String::from(uuid)));
+                },
+                None => res.get_properties_mut().push(make_element_uuid_property()),
+            }
+        }
+        nr.next_prop_id = self.properties_repository.create_list(res.get_properties_mut()).ok_or(GraphError::StoreFull{store: PROPERTIES_STORE})?;
         if !node.get_labels_ref().is_empty() {
-            nr.node_type = self.labels_store.save_data(node.get_labels_ref().join(":").as_bytes())?;
+            nr.node_type = self.labels_store.save_data(node.get_labels_ref().join(":").as_bytes()).ok_or(GraphError::StoreFull{store: LABELS_STORE})?;
         }
-        let nid = self.nodes_store.create(&nr)?;
-        for label in node.get_labels_ref() {
-            self.nodes_labels_index.insert(label, nid);
+        let nid = self.nodes_store.create(&nr).ok_or(GraphError::StoreFull{store: NODES_STORE})?;
+        self.index_node(nid, &res);
+        for (constraint, value) in &constrained_values {
+            self.nodes_properties_index.insert(&make_unique_index_key(constraint, value), nid);
         }
+        add_node_statistics(&mut self.statistics, &res);
         
         res.set_id(Some(nid));
-        Some(res)
+        if self.capture_changes {
+            self.changes.push(ChangeEvent::Node{kind: ChangeKind::Created, node: res.clone()});
+        }
+        Ok(res)
     }
     
 
-    pub fn create_relationship(&mut self, rel: &Relationship, source: u64, target: u64) -> Option<Relationship> {
-        let mut source_record = self.nodes_store.load(source)?;
-        let mut target_record = self.nodes_store.load(target)?;
+    pub fn create_relationship(&mut self, rel: &Relationship, source: u64, target: u64) -> GraphResult<Relationship> {
+        let mut source_record = self.nodes_store.load(source).ok_or(GraphError::NodeNotFound(source))?;
+        let mut target_record = self.nodes_store.load(target).ok_or(GraphError::NodeNotFound(target))?;
         let mut rr = RelationshipRecord::new(source, target);
         rr.next_outbound_edge = source_record.first_outbound_edge;
         rr.next_inbound_edge = target_record.first_inbound_edge;
         let mut res = rel.clone();
-        rr.next_prop_id = self.properties_repository.create_list(res.get_properties_mut())?;
+        if self.element_uuids {
+            match get_element_uuid(rel.get_properties_ref()) {
+                Some(uuid) => if self.find_relationship_by_uuid(uuid).is_some() {
+                    return Err(GraphError::DuplicateUuid(String::from(uuid)));
+                },
+                None => res.get_properties_mut().push(make_element_uuid_property()),
+            }
+        }
+        rr.next_prop_id = self.properties_repository.create_list(res.get_properties_mut()).ok_or(GraphError::StoreFull{store: PROPERTIES_STORE})?;
         if !rel.get_labels_ref().is_empty() {
-            rr.relationship_type = self.labels_store.save_data(rel.get_labels_ref().join(":").as_bytes())?;
+            rr.relationship_type = self.labels_store.save_data(rel.get_labels_ref().join(":").as_bytes()).ok_or(GraphError::StoreFull{store: LABELS_STORE})?;
         }
-        let rid = self.relationships_store.create(&rr)?;
+        let rid = self.relationships_store.create(&rr).ok_or(GraphError::StoreFull{store: RELATIONSHIPS_STORE})?;
        
         res.set_id(Some(rid));
         
 
         if source == target {
             source_record.first_outbound_edge = rid;
-            source_record.first_outbound_edge = rid;
-            self.nodes_store.save(source, &source_record)?;
+            source_record.first_inbound_edge = rid;
+            self.save_node_record(source, &source_record)?;
         } else {
             source_record.first_outbound_edge = rid;
             target_record.first_inbound_edge = rid;
-            self.nodes_store.save(source, &source_record)?;
-            self.nodes_store.save(target, &target_record)?;
+            self.save_node_record(source, &source_record)?;
+            self.save_node_record(target, &target_record)?;
         }
         
-        for label in rel.get_labels_ref() {
-            self.relationships_labels_index.insert(label, rid);
+        self.index_relationship(rid, &res);
+        add_relationship_statistics(&mut self.statistics, &res);
+        if self.capture_changes {
+            self.changes.push(ChangeEvent::Relationship{kind: ChangeKind::Created, relationship: res.clone(), source_id: source, target_id: target});
         }
 
-        Some(res)
+        Ok(res)
     }
 
-    pub fn create_graph(&mut self, pgraph: &PropertyGraph) -> Option<PropertyGraph> {
+    fn load_node_record(&mut self, node_id: u64) -> GraphResult<NodeRecord> {
+        self.nodes_store.load(node_id).ok_or(GraphError::record(NODES_STORE, node_id, StoreOperation::Load))
+    }
+
+    fn save_node_record(&mut self, node_id: u64, record: &NodeRecord) -> GraphResult<()> {
+        self.nodes_store.save(node_id, record).ok_or(GraphError::record(NODES_STORE, node_id, StoreOperation::Save))
+    }
+
+    fn load_relationship_record(&mut self, rel_id: u64) -> GraphResult<RelationshipRecord> {
+        self.relationships_store.load(rel_id).ok_or(GraphError::record(RELATIONSHIPS_STORE, rel_id, StoreOperation::Load))
+    }
+
+    fn save_relationship_record(&mut self, rel_id: u64, record: &RelationshipRecord) -> GraphResult<()> {
+        self.relationships_store.save(rel_id, record).ok_or(GraphError::record(RELATIONSHIPS_STORE, rel_id, StoreOperation::Save))
+    }
+
+    pub fn delete_relationship(&mut self, rel_id: u64) -> GraphResult<()> {
+        let rr = self.relationships_store.load(rel_id).ok_or(GraphError::RelationshipNotFound(rel_id))?;
+        let mut source_record = self.load_node_record(rr.source)?;
+        if source_record.first_outbound_edge == rel_id {
+            source_record.first_outbound_edge = rr.next_outbound_edge;
+            self.save_node_record(rr.source, &source_record)?;
+        } else {
+            let mut prev_id = source_record.first_outbound_edge;
+            while prev_id != 0 {
+                let mut prev = self.load_relationship_record(prev_id)?;
+                if prev.next_outbound_edge == rel_id {
+                    prev.next_outbound_edge = rr.next_outbound_edge;
+                    self.save_relationship_record(prev_id, &prev)?;
+                    break;
+                }
+                prev_id = prev.next_outbound_edge;
+            }
+        }
+        let mut target_record = self.load_node_record(rr.target)?;
+        if target_record.first_inbound_edge == rel_id {
+            target_record.first_inbound_edge = rr.next_inbound_edge;
+            self.save_node_record(rr.target, &target_record)?;
+        } else {
+            let mut prev_id = target_record.first_inbound_edge;
+            while prev_id != 0 {
+                let mut prev = self.load_relationship_record(prev_id)?;
+                if prev.next_inbound_edge == rel_id {
+                    prev.next_inbound_edge = rr.next_inbound_edge;
+                    self.save_relationship_record(prev_id, &prev)?;
+                    break;
+                }
+                prev_id = prev.next_inbound_edge;
+            }
+        }
+        let (mut rel, _) = self.retrieve_relationship_by_id(rel_id).ok_or(GraphError::record(RELATIONSHIPS_STORE, rel_id, StoreOperation::Load))?;
+        self.properties_repository.delete_list(rr.next_prop_id).ok_or(GraphError::record(PROPERTIES_STORE, rr.next_prop_id, StoreOperation::Delete))?;
+        self.relationships_store.delete(rel_id).ok_or(GraphError::record(RELATIONSHIPS_STORE, rel_id, StoreOperation::Delete))?;
+        self.unindex_relationship(rel_id, &rel);
+        remove_relationship_statistics(&mut self.statistics, &rel);
+        if self.capture_changes {
+            rel.set_id(Some(rel_id));
+            self.changes.push(ChangeEvent::Relationship{kind: ChangeKind::Deleted, relationship: rel, source_id: rr.source, target_id: rr.target});
+        }
+        Ok(())
+    }
+
+    pub fn delete_node(&mut self, node_id: u64) -> GraphResult<()> {
+        let nr = self.nodes_store.load(node_id).ok_or(GraphError::NodeNotFound(node_id))?;
+        if nr.first_outbound_edge != 0 || nr.first_inbound_edge != 0 {
+            return Err(GraphError::NodeHasRelationships(node_id));
+        }
+        let (mut node, _) = self.retrieve_node_by_id(node_id).ok_or(GraphError::record(NODES_STORE, node_id, StoreOperation::Load))?;
+        self.properties_repository.delete_list(nr.next_prop_id).ok_or(GraphError::record(PROPERTIES_STORE, nr.next_prop_id, StoreOperation::Delete))?;
+        self.nodes_store.delete(node_id).ok_or(GraphError::record(NODES_STORE, node_id, StoreOperation::Delete))?;
+        self.unindex_node(node_id, &node);
+        remove_node_statistics(&mut self.statistics, &node);
+        if self.capture_changes {
+            node.set_id(Some(node_id));
+            self.changes.push(ChangeEvent::Node{kind: ChangeKind::Deleted, node});
+        }
+        Ok(())
+    }
+
+    pub fn detach_delete_node(&mut self, node_id: u64) -> GraphResult<()> {
+        let mut nr = self.nodes_store.load(node_id).ok_or(GraphError::NodeNotFound(node_id))?;
+        while nr.first_outbound_edge != 0 {
+            self.delete_relationship(nr.first_outbound_edge)?;
+            nr = self.load_node_record(node_id)?;
+        }
+        while nr.first_inbound_edge != 0 {
+            self.delete_relationship(nr.first_inbound_edge)?;
+            nr = self.load_node_record(node_id)?;
+        }
+        self.delete_node(node_id)
+    }
+
+    /// Adds the missing labels to a node, returns the updated node.
+    pub fn add_node_labels(&mut self, node_id: u64, labels: &[String]) -> GraphResult<Node> {
+        let (node, _) = self.retrieve_node_by_id(node_id).ok_or(GraphError::NodeNotFound(node_id))?;
+        let mut new_labels = node.get_labels_ref().clone();
+        for label in labels {
+            if !new_labels.contains(label) {
+                new_labels.push(label.clone());
+            }
+        }
+        self.set_node_labels(node_id, node, new_labels)
+    }
+
+    /// Removes the labels from a node, returns the updated node.
+    pub fn remove_node_labels(&mut self, node_id: u64, labels: &[String]) -> GraphResult<Node> {
+        let (node, _) = self.retrieve_node_by_id(node_id).ok_or(GraphError::NodeNotFound(node_id))?;
+        let new_labels = node.get_labels_ref().iter().filter(|label| !labels.contains(label)).cloned().collect();
+        self.set_node_labels(node_id, node, new_labels)
+    }
+
+    /// Rewrites the labels chain of the node record and keeps the labels and unique constraints indexes up to date.
+    fn set_node_labels(&mut self, node_id: u64, node: Node, labels: Vec<String>) -> GraphResult<Node> {
+        if *node.get_labels_ref() == labels {
+            return Ok(node);
+        }
+        let mut updated = node.clone();
+        updated.set_labels(labels);
+        let old_constrained_values = self.get_node_constrained_values(&node);
+        let new_constrained_values: Vec<(UniqueConstraint, PropertyValue)> = self.get_node_constrained_values(&updated).into_iter()
+            .filter(|constrained_value| !old_constrained_values.contains(constrained_value)).collect();
+        for (constraint, value) in &new_constrained_values {
+            if self.find_node_with_unique_value(constraint, value).is_some() {
+                return Err(GraphError::UniqueConstraintViolation(constraint.clone()));
+            }
+        }
+        let mut nr = self.load_node_record(node_id)?;
+        let old_labels_id = nr.node_type;
+        nr.node_type = 0;
+        if !updated.get_labels_ref().is_empty() {
+            nr.node_type = self.labels_store.save_data(updated.get_labels_ref().join(":").as_bytes()).ok_or(GraphError::StoreFull{store: LABELS_STORE})?;
+        }
+        self.save_node_record(node_id, &nr)?;
+        if old_labels_id != 0 {
+            self.labels_store.delete_data(old_labels_id).ok_or(GraphError::record(LABELS_STORE, old_labels_id, StoreOperation::Delete))?;
+        }
+        for label in node.get_labels_ref() {
+            if !updated.get_labels_ref().contains(label) {
+                self.nodes_labels_index.remove(label, node_id);
+                self.statistics.decrement(StatisticKey::Label(label.clone()));
+            }
+        }
+        for label in updated.get_labels_ref() {
+            if !node.get_labels_ref().contains(label) {
+                self.nodes_labels_index.insert(label, node_id);
+                self.statistics.increment(StatisticKey::Label(label.clone()));
+            }
+        }
+        let kept_constrained_values = self.get_node_constrained_values(&updated);
+        for (constraint, value) in &old_constrained_values {
+            if !kept_constrained_values.contains(&(constraint.clone(), value.clone())) {
+                self.nodes_properties_index.remove(&make_unique_index_key(constraint, value), node_id);
+            }
+        }
+        for (constraint, value) in &new_constrained_values {
+            self.nodes_properties_index.insert(&make_unique_index_key(constraint, value), node_id);
+        }
+        if self.capture_changes {
+            self.changes.push(ChangeEvent::Node{kind: ChangeKind::Updated, node: updated.clone()});
+        }
+        Ok(updated)
+    }
+
+    /// Removes a property from the chain of a node, returns the updated node.
+    pub fn remove_node_property(&mut self, node_id: u64, name: &str) -> GraphResult<Node> {
+        let (node, _) = self.retrieve_node_by_id(node_id).ok_or(GraphError::NodeNotFound(node_id))?;
+        if !node.get_properties_ref().iter().any(|prop| prop.get_name() == name) {
+            return Ok(node);
+        }
+        let mut nr = self.load_node_record(node_id)?;
+        nr.next_prop_id = self.properties_repository.remove_from_list(nr.next_prop_id, name).ok_or(GraphError::record(PROPERTIES_STORE, nr.next_prop_id, StoreOperation::Delete))?;
+        self.save_node_record(node_id, &nr)?;
+        self.nodes_property_keys_index.remove(name, node_id);
+        for (constraint, value) in self.get_node_constrained_values(&node) {
+            if constraint.property_name == name {
+                self.nodes_properties_index.remove(&make_unique_index_key(&constraint, &value), node_id);
+            }
+        }
+        if name == ELEMENT_UUID_PROPERTY {
+            if let Some(uuid) = get_element_uuid(node.get_properties_ref()) {
+                self.nodes_properties_index.remove(&make_uuid_index_key(uuid), node_id);
+            }
+        }
+        self.statistics.decrement(StatisticKey::NodeProperty(String::from(name)));
+        let mut updated = node;
+        updated.get_properties_mut().retain(|prop| prop.get_name() != name);
+        if self.capture_changes {
+            self.changes.push(ChangeEvent::Node{kind: ChangeKind::Updated, node: updated.clone()});
+        }
+        Ok(updated)
+    }
+
+    /// Removes a property from the chain of a relationship, returns the updated relationship.
+    pub fn remove_relationship_property(&mut self, rel_id: u64, name: &str) -> GraphResult<Relationship> {
+        let (rel, _) = self.retrieve_relationship_by_id(rel_id).ok_or(GraphError::RelationshipNotFound(rel_id))?;
+        if !rel.get_properties_ref().iter().any(|prop| prop.get_name() == name) {
+            return Ok(rel);
+        }
+        let mut rr = self.load_relationship_record(rel_id)?;
+        rr.next_prop_id = self.properties_repository.remove_from_list(rr.next_prop_id, name).ok_or(GraphError::record(PROPERTIES_STORE, rr.next_prop_id, StoreOperation::Delete))?;
+        self.save_relationship_record(rel_id, &rr)?;
+        for prop in rel.get_properties_ref().iter().filter(|prop| prop.get_name() == name) {
+            self.relationships_properties_index.remove(&make_property_index_key(prop.get_name(), prop.get_value()), rel_id);
+        }
+        self.statistics.decrement(StatisticKey::RelationshipProperty(String::from(name)));
+        let mut updated = rel;
+        updated.get_properties_mut().retain(|prop| prop.get_name() != name);
+        if self.capture_changes {
+            self.changes.push(ChangeEvent::Relationship{kind: ChangeKind::Updated, relationship: updated.clone(), source_id: rr.source, target_id: rr.target});
+        }
+        Ok(updated)
+    }
+
+    /// Checks the unique values of the nodes to create against the stored nodes and against each other.
+    fn check_unique_values(&mut self, nodes: &[&Node]) -> GraphResult<()> {
+        let mut keys = HashSet::new();
+        for node in nodes {
+            for (constraint, value) in self.get_node_constrained_values(node) {
+                if !keys.insert(make_unique_index_key(&constraint, &value)) || self.find_node_with_unique_value(&constraint, &value).is_some() {
+                    return Err(GraphError::UniqueConstraintViolation(constraint));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Creates the nodes and relationships of the pattern, a unique constraint broken by one of its nodes fails before any write.
+    pub fn create_graph(&mut self, pgraph: &PropertyGraph) -> GraphResult<PropertyGraph> {
+        self.check_unique_values(&pgraph.get_nodes())?;
         let mut res = pgraph.clone();
         let mut map_nodes = HashMap::new();
         let mut node_index = 0;
         let mut node_records = Vec::new();
         for node in res.get_nodes_mut() {
             let cnode = self.create_node(node)?;
-            map_nodes.insert(node_index, cnode.get_id()?);
+            map_nodes.insert(node_index, cnode.get_id().unwrap_or_default());
             node_records.push(cnode);
             node_index += 1;
         }
@@ -198,9 +983,9 @@ impl GraphRepository {
         let mut rel_records = Vec::new();
         for edge in res.get_edges_mut() {
             let crel = self.create_relationship(&edge.relationship, 
-                *map_nodes.get(&edge.source.get_index())?,
-                *map_nodes.get(&edge.target.get_index())?)?;
-            map_rel.insert(rel_index, crel.get_id()?);
+                map_nodes[&edge.source.get_index()],
+                map_nodes[&edge.target.get_index()])?;
+            map_rel.insert(rel_index, crel.get_id().unwrap_or_default());
             rel_records.push(crel);
             rel_index += 1;
         }
@@ -217,15 +1002,86 @@ impl GraphRepository {
             r_index += 1;
         }
 
-        Some(res)
+        Ok(res)
+    }
+
+    pub fn set_capture_changes(&mut self, capture_changes: bool) {
+        self.capture_changes = capture_changes;
+    }
+
+    pub fn get_changes(&self) -> &Vec<ChangeEvent> {
+        &self.changes
+    }
+
+    /// Changes made since the last call, recorded if change data capture is enabled.
+    pub fn take_changes(&mut self) -> Vec<ChangeEvent> {
+        std::mem::take(&mut self.changes)
+    }
+
+    /// True once another repository of the database synced since this one was created or synced, its stores
+    /// then read the pages of the previous commits.
+    pub fn is_outdated(&self) -> bool {
+        self.pool.lock().unwrap().get_commit_count() != self.commit_count
     }
 
     pub fn sync(&mut self) {
+        self.sync_statistics();
         self.nodes_labels_index.sync();
+        self.relationships_labels_index.sync();
+        self.relationships_properties_index.sync();
         self.relationships_store.sync();
         self.nodes_store.sync();
         self.properties_repository.sync();
         self.labels_store.sync();
+        self.nodes_properties_index.sync();
+        self.nodes_property_keys_index.sync();
+        self.constraints_store.sync();
+        self.statistics_store.sync();
+        self.commit_count = self.pool.lock().unwrap().count_commit();
+    }
+}
+
+fn make_element_uuid_property() -> Property {
+    Property::new(String::from(ELEMENT_UUID_PROPERTY), PropertyValue::PString(Uuid::new_v4().to_string()))
+}
+
+fn add_node_statistics(statistics: &mut GraphStatistics, node: &Node) {
+    statistics.increment(StatisticKey::Nodes);
+    for label in node.get_labels_ref() {
+        statistics.increment(StatisticKey::Label(label.clone()));
+    }
+    for prop in node.get_properties_ref() {
+        statistics.increment(StatisticKey::NodeProperty(String::from(prop.get_name())));
+    }
+}
+
+fn remove_node_statistics(statistics: &mut GraphStatistics, node: &Node) {
+    statistics.decrement(StatisticKey::Nodes);
+    for label in node.get_labels_ref() {
+        statistics.decrement(StatisticKey::Label(label.clone()));
+    }
+    for prop in node.get_properties_ref() {
+        statistics.decrement(StatisticKey::NodeProperty(String::from(prop.get_name())));
+    }
+}
+
+fn add_relationship_statistics(statistics: &mut GraphStatistics, rel: &Relationship) {
+    statistics.increment(StatisticKey::Relationships);
+    for label in rel.get_labels_ref() {
+        statistics.increment(StatisticKey::RelationshipType(label.clone()));
+    }
+    for prop in rel.get_properties_ref() {
+        statistics.increment(StatisticKey::RelationshipProperty(String::from(prop.get_name())));
+    }
+}
+
+fn remove_relationship_statistics(statistics: &mut GraphStatistics, rel: &Relationship) {
+    statistics.decrement(StatisticKey::Relationships);
+    for label in rel.get_labels_ref() {
+        statistics.decrement(StatisticKey::RelationshipType(label.clone()));
+    }
+    for prop in rel.get_properties_ref() {
+        statistics.decrement(StatisticKey::RelationshipProperty(String::from(prop.get_name())));
     }
 }
 
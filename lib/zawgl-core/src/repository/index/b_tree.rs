@@ -20,6 +20,11 @@
 
 use super::store::*;
 use super::model::*;
+use super::super::records::SharedBufferPool;
+use crate::model::init::IndexLayout;
+use crate::model::check::IndexCheckReport;
+use std::cmp::Ordering;
+use std::collections::HashSet;
 
 pub type DataPtr = u64;
 pub struct BTreeIndex {
@@ -27,22 +32,39 @@ pub struct BTreeIndex {
 }
 
 fn get_node_ptr(not_found_index: usize, node: &BTreeNode) -> Option<NodeId> {
-    let mut node_ptr = node.get_node_ptr()?;
-    if not_found_index != 0 {
-        node_ptr = node.get_cell_ref(not_found_index - 1).get_node_ptr()?;
+    if not_found_index == 0 {
+        node.get_node_ptr()
+    } else {
+        node.get_cell_ref(not_found_index - 1).get_node_ptr()
     }
-    Some(node_ptr)
+}
+
+fn compare_keys(key: &str, other: &str) -> Ordering {
+    Ord::cmp(&key.len(), &other.len()).then(key.cmp(other))
 }
 
 fn binary_search_keys(keys: &Vec<&str>, value: &str) -> Result<usize, usize> {
-    keys.binary_search_by(|&probe|{
-        Ord::cmp(&probe.len(), &value.len()).then(probe.cmp(value))
-    })
+    keys.binary_search_by(|&probe| compare_keys(probe, value))
+}
+
+struct CheckedNode {
+    id: NodeId,
+    depth: usize,
+    lower_bound: Option<String>,
+    upper_bound: Option<String>,
 }
 
 impl BTreeIndex {
-    pub fn new(file: &str) -> Self {
-        BTreeIndex{node_store: BTreeNodeStore::new(file)}
+    pub fn new(file: &str, pool: &SharedBufferPool) -> Self {
+        BTreeIndex{node_store: BTreeNodeStore::new(file, pool)}
+    }
+
+    pub fn with_layout(file: &str, pool: &SharedBufferPool, layout: IndexLayout) -> Self {
+        BTreeIndex{node_store: BTreeNodeStore::with_layout(file, pool, layout)}
+    }
+
+    pub fn get_layout(&self) -> IndexLayout {
+        self.node_store.get_layout()
     }
 
     fn tree_search(&mut self, value: &str, node: &BTreeNode) -> Option<Vec<DataPtr>> {
@@ -154,7 +176,7 @@ impl BTreeIndex {
             },
             Err(not_found) => {
                 if node.is_leaf() {
-                    if node.is_full() {
+                    if node.is_full(self.node_store.get_layout().get_nb_cells()) {
                         self.split_leaf_node(value, data_ptr, node, not_found)
                     } else {
                         node.insert_cell(not_found, Cell::new_leaf(value, data_ptr));
@@ -170,7 +192,7 @@ impl BTreeIndex {
                     let first_split_cell_key_search = binary_search_keys(&keys, first_cell_key);
                     match first_split_cell_key_search {
                         Err(not_found) => {
-                            if node.is_full() {
+                            if node.is_full(self.node_store.get_layout().get_nb_cells()) {
                                 self.split_interior_node(first_cell.get_key(), split_node.get_id(), node, not_found)
                             } else {
                                 node.insert_cell(not_found, Cell::new_ptr(first_cell.get_key(), split_node.get_id()));
@@ -196,23 +218,134 @@ impl BTreeIndex {
         self.insert_or_update_key_ptrs(value, data_ptr, &mut root).map(|_node|())
     }
 
-    pub fn delete(&mut self, value: u64) {
+    fn remove_key_ptr(&mut self, value: &str, data_ptr: u64, node: &mut BTreeNode) -> Option<()> {
+        let keys = node.get_keys();
+        match binary_search_keys(&keys, value) {
+            Ok(found) => {
+                if node.is_leaf() {
+                    node.get_cell_mut(found).delete_data_ptr(data_ptr);
+                    if node.get_cell_ref(found).get_data_ptrs_ref().is_empty() {
+                        node.remove_cell(found);
+                    }
+                    self.node_store.save(node)
+                } else {
+                    let mut child = node.get_cell_ref(found).get_node_ptr().and_then(|id| self.node_store.retrieve_node(id))?;
+                    self.remove_key_ptr(value, data_ptr, &mut child)
+                }
+            },
+            Err(not_found) => {
+                if node.is_leaf() {
+                    None
+                } else {
+                    let node_ptr = get_node_ptr(not_found, node)?;
+                    let mut child = self.node_store.retrieve_node(node_ptr)?;
+                    self.remove_key_ptr(value, data_ptr, &mut child)
+                }
+            }
+        }
+    }
 
+    pub fn remove(&mut self, value: &str, data_ptr: u64) -> Option<()> {
+        let mut root = self.node_store.load_or_create_root_node()?;
+        self.remove_key_ptr(value, data_ptr, &mut root)
     }
 
     pub fn sync(&mut self) {
         self.node_store.sync();
     }
+
+    pub fn prefetch(&mut self, max_pages: usize) -> usize {
+        self.node_store.prefetch(max_pages)
+    }
+
+    /// Walks the whole tree from its root and reports the structural errors found in nodes, cells and data pointers.
+    pub fn check(&mut self, name: &str, is_valid_data_ptr: &mut dyn FnMut(DataPtr) -> bool) -> IndexCheckReport {
+        let mut report = IndexCheckReport::new(name);
+        if self.node_store.is_empty() {
+            return report;
+        }
+        let root_id = self.node_store.get_root_node_id();
+        let mut visited = HashSet::new();
+        let mut leaves_depth = None;
+        let mut to_check = vec![CheckedNode{id: root_id, depth: 0, lower_bound: None, upper_bound: None}];
+        while let Some(checked) = to_check.pop() {
+            if !visited.insert(checked.id) {
+                report.errors.push(format!("node {}: referenced more than once", checked.id));
+                continue;
+            }
+            let node = match self.node_store.retrieve_node(checked.id) {
+                Some(node) => node,
+                None => {
+                    report.errors.push(format!("node {}: can't be loaded", checked.id));
+                    continue;
+                }
+            };
+            report.nodes += 1;
+            if node.is_root() != (checked.id == root_id) {
+                report.errors.push(format!("node {}: invalid root flag", checked.id));
+            }
+            self.node_store.check_node_records(checked.id, node.is_leaf(), &mut report.errors);
+
+            let keys = node.get_keys();
+            for pair in keys.windows(2) {
+                if compare_keys(pair[0], pair[1]) != Ordering::Less {
+                    report.errors.push(format!("node {}: keys '{}' and '{}' are not sorted", checked.id, pair[0], pair[1]));
+                }
+            }
+            for key in &keys {
+                let below_lower = checked.lower_bound.as_ref().is_some_and(|lower| compare_keys(key, lower) == Ordering::Less);
+                let above_upper = checked.upper_bound.as_ref().is_some_and(|upper| compare_keys(key, upper) != Ordering::Less);
+                if below_lower || above_upper {
+                    report.errors.push(format!("node {}: key '{}' is out of its parent bounds", checked.id, key));
+                }
+            }
+
+            if node.is_leaf() {
+                match leaves_depth {
+                    None => leaves_depth = Some(checked.depth),
+                    Some(depth) if depth != checked.depth => {
+                        report.errors.push(format!("node {}: leaf at depth {} instead of {}", checked.id, checked.depth, depth));
+                    },
+                    _ => {}
+                }
+                for cell in node.get_cells_ref() {
+                    report.keys += 1;
+                    for data_ptr in cell.get_data_ptrs_ref() {
+                        report.data_ptrs += 1;
+                        if !is_valid_data_ptr(*data_ptr) {
+                            report.errors.push(format!("key '{}': data pointer {} does not exist", cell.get_key(), data_ptr));
+                        }
+                    }
+                }
+            } else {
+                if let Some(first_child) = node.get_node_ptr() {
+                    let upper_bound = keys.first().map(|key| key.to_string());
+                    to_check.push(CheckedNode{id: first_child, depth: checked.depth + 1, lower_bound: checked.lower_bound.clone(), upper_bound});
+                }
+                for (index, cell) in node.get_cells_ref().iter().enumerate() {
+                    match cell.get_node_ptr() {
+                        Some(child) => {
+                            let upper_bound = keys.get(index + 1).map(|key| key.to_string()).or_else(|| checked.upper_bound.clone());
+                            to_check.push(CheckedNode{id: child, depth: checked.depth + 1, lower_bound: Some(cell.get_key().clone()), upper_bound});
+                        },
+                        None => report.errors.push(format!("node {}: interior cell '{}' without child pointer", checked.id, cell.get_key())),
+                    }
+                }
+            }
+        }
+        report
+    }
 }
 
 #[cfg(test)]
 mod test_b_tree {
     use super::*;
+    use super::super::super::records::BufferPool;
     use super::super::super::super::test_utils::*;
     #[test]
     fn test_insert() {
         let file = build_file_path_and_rm_old("b_tree", "test_insert.db").unwrap();
-        let mut index = BTreeIndex::new(&file);
+        let mut index = BTreeIndex::new(&file, &BufferPool::new_shared(TEST_POOL_CAPACITY));
         let key = "a short key";
         index.insert(key, 42);
         let long_key = "a long key a long key a long key a long key a long key a long key a long key a long key a long key a long key a long key a long key a long key ";
@@ -247,18 +380,64 @@ mod test_b_tree {
         assert!(data_ptrs_2.contains(&87968567));
     }
 
+    #[test]
+    fn test_remove() {
+        let file = build_file_path_and_rm_old("b_tree", "test_remove.db").unwrap();
+        let mut index = BTreeIndex::new(&file, &BufferPool::new_shared(TEST_POOL_CAPACITY));
+        for i in 0..500 {
+            index.insert(&format!("key # {}", i % 3), i);
+        }
+        index.insert("single", 1);
+        index.sync();
+
+        for i in (0..300).filter(|i| i % 2 == 0) {
+            index.remove(&format!("key # {}", i % 3), i).expect("remove");
+        }
+        index.remove("single", 1).expect("remove");
+        index.sync();
+
+        let expected_ptrs = |k: u64| (0..500).filter(|i| i % 3 == k && (*i >= 300 || i % 2 == 1)).collect::<Vec<u64>>();
+        for k in 0..3 {
+            let mut ptrs = index.search(&format!("key # {}", k)).expect("ptrs");
+            ptrs.sort();
+            assert_eq!(expected_ptrs(k), ptrs);
+        }
+        assert!(index.search("single").is_none());
+        index.insert("single", 2);
+        index.sync();
+
+        let mut reloaded = BTreeIndex::new(&file, &BufferPool::new_shared(TEST_POOL_CAPACITY));
+        assert_eq!(expected_ptrs(1).len(), reloaded.search("key # 1").expect("ptrs").len());
+        assert_eq!(Some(vec![2]), reloaded.search("single"));
+    }
+
+    #[test]
+    fn test_remove_first_key() {
+        let file = build_file_path_and_rm_old("b_tree", "test_remove_first_key.db").unwrap();
+        let mut index = BTreeIndex::new(&file, &BufferPool::new_shared(TEST_POOL_CAPACITY));
+        index.insert("a", 1);
+        index.insert("b", 2);
+        index.insert("c", 3);
+        index.remove("a", 1).expect("remove");
+        index.insert("a", 4);
+        assert_eq!(Some(vec![4]), index.search("a"));
+        assert_eq!(Some(vec![2]), index.search("b"));
+        assert_eq!(Some(vec![3]), index.search("c"));
+        assert!(index.check("test", &mut |_| true).is_consistent());
+    }
+
     #[test]
     fn test_root_split() {
         let file = build_file_path_and_rm_old("b_tree", "test_root_split.db").unwrap();
-        let mut index = BTreeIndex::new(&file);
+        let mut index = BTreeIndex::new(&file, &BufferPool::new_shared(TEST_POOL_CAPACITY));
 
-        for i in 0..1000 {
+        for i in 0..500 {
             index.insert(&format!("key # {}", i), i);
         }
 
         index.sync();
 
-        for i in 0..1000 {
+        for i in 0..500 {
             let optrs = index.search(&format!("key # {}", i));
             if let Some(ptrs) = optrs {
                 assert_eq!(ptrs.len(), 1);
@@ -275,9 +454,9 @@ mod test_b_tree {
     #[test]
     fn test_root_split_same_key() {
         let file = build_file_path_and_rm_old("b_tree", "test_root_split_same_key.db").unwrap();
-        let mut index = BTreeIndex::new(&file);
+        let mut index = BTreeIndex::new(&file, &BufferPool::new_shared(TEST_POOL_CAPACITY));
 
-        for i in 0..100 {
+        for i in 0..500 {
             index.insert("same key", i);
         }
 
@@ -285,10 +464,61 @@ mod test_b_tree {
 
         let optrs = index.search("same key");
         if let Some(ptrs) = optrs {
-            println!("{:?}", ptrs);
-            assert_eq!(ptrs.len(), 100);
+            assert_eq!(ptrs.len(), 500);
         } else {
             assert!(false, "empty search result for same key");
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_index_layout() {
+        assert!(IndexLayout::new(4, 10).is_none());
+        assert!(IndexLayout::new(2000, 10).is_none());
+        assert_eq!(1, IndexLayout::default().get_nb_records_per_page());
+
+        let file = build_file_path_and_rm_old("b_tree", "test_index_layout.db").unwrap();
+        let layout = IndexLayout::new(200, 8).expect("layout");
+        let mut index = BTreeIndex::with_layout(&file, &BufferPool::new_shared(TEST_POOL_CAPACITY), layout);
+        let long_key = "a long key ".repeat(15);
+        for i in 0..500 {
+            index.insert(&format!("{}{}", long_key, i), i);
+        }
+        index.sync();
+
+        let mut reloaded = BTreeIndex::with_layout(&file, &BufferPool::new_shared(TEST_POOL_CAPACITY), IndexLayout::default());
+        assert_eq!(layout, reloaded.get_layout());
+        for i in 0..500 {
+            assert_eq!(Some(vec![i]), reloaded.search(&format!("{}{}", long_key, i)));
+        }
+    }
+
+    #[test]
+    fn test_check() {
+        let file = build_file_path_and_rm_old("b_tree", "test_check.db").unwrap();
+        let mut index = BTreeIndex::with_layout(&file, &BufferPool::new_shared(TEST_POOL_CAPACITY), IndexLayout::new(64, 8).expect("layout"));
+        assert!(index.check("empty", &mut |_| true).is_consistent());
+        for i in 0..300 {
+            index.insert(&format!("key {}", i), i);
+            index.insert(&format!("key {}", i % 7), 1000 + i);
+        }
+        let report = index.check("healthy", &mut |ptr| ptr < 2000);
+        assert!(report.is_consistent(), "{:?}", report.errors);
+        assert_eq!(300, report.keys);
+        assert_eq!(600, report.data_ptrs);
+        assert!(report.nodes > 1);
+
+        let report = index.check("missing data", &mut |ptr| ptr < 1000);
+        assert_eq!(300, report.errors.len());
+    }
+
+    #[test]
+    fn test_check_unsorted_keys() {
+        let file = build_file_path_and_rm_old("b_tree", "test_check_unsorted_keys.db").unwrap();
+        let mut index = BTreeIndex::new(&file, &BufferPool::new_shared(TEST_POOL_CAPACITY));
+        let mut root = BTreeNode::new(true, true, vec![Cell::new_leaf("b", 1), Cell::new_leaf("a", 2)]);
+        index.node_store.create(&mut root);
+        let report = index.check("unsorted", &mut |_| true);
+        assert_eq!(1, report.errors.len());
+        assert!(report.errors[0].ends_with("keys 'b' and 'a' are not sorted"), "{:?}", report.errors);
+    }
+}
@@ -169,8 +169,8 @@ impl BTreeNode {
         BTreeNode{id: id, cells: cells, node_ptr: node_ptr, is_leaf: is_leaf, is_root: is_root, node_change_state: state}
     }
 
-    pub fn is_full(&self) -> bool {
-        self.cells.len() == NB_CELL
+    pub fn is_full(&self, nb_cells: usize) -> bool {
+        self.cells.len() >= nb_cells
     }
 
     pub fn get_keys(&self) -> Vec<&str> {
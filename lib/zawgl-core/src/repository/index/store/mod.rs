@@ -23,18 +23,70 @@ mod pool;
 
 use log::*;
 use std::sync::{Arc, Mutex};
+use std::collections::HashSet;
 
 use self::records::*;
 use super::super::super::buf_config::*;
 use super::model::*;
 use super::super::records::*;
 use self::pool::*;
+use crate::model::init::IndexLayout;
 
 
 pub type MutableRecordsManager = Arc<Mutex<RecordsManager>>;
 
 pub struct BTreeNodeStore {
     records_manager: MutableRecordsManager,
+    layout: IndexLayout,
+}
+
+const LAYOUT_KEY_SIZE_OFFSET: usize = 2 * NODE_PTR_SIZE;
+const LAYOUT_NB_CELLS_OFFSET: usize = LAYOUT_KEY_SIZE_OFFSET + 4;
+const LAYOUT_NB_RECORDS_PER_PAGE_OFFSET: usize = LAYOUT_NB_CELLS_OFFSET + 4;
+
+fn read_layout_value(header: &[u8], offset: usize) -> usize {
+    let mut buf = [0u8; 4];
+    buf.copy_from_slice(&header[offset..offset+4]);
+    u32::from_be_bytes(buf) as usize
+}
+
+fn write_layout_value(header: &mut [u8], offset: usize, value: usize) {
+    header[offset..offset+4].copy_from_slice(&(value as u32).to_be_bytes());
+}
+
+/// The layout saved in the header of the index file wins over the configured one,
+/// files created before the layout was saved use the default layout.
+fn load_or_save_layout(records_manager: &mut RecordsManager, file: &str, layout: IndexLayout) -> IndexLayout {
+    if records_manager.is_empty() {
+        let mut wrapper = records_manager.get_header_page_wrapper();
+        let header = wrapper.get_header_payload_slice_mut();
+        write_layout_value(header, LAYOUT_KEY_SIZE_OFFSET, layout.get_key_size());
+        write_layout_value(header, LAYOUT_NB_CELLS_OFFSET, layout.get_nb_cells());
+        write_layout_value(header, LAYOUT_NB_RECORDS_PER_PAGE_OFFSET, layout.get_nb_records_per_page());
+        return layout;
+    }
+    let (key_size, nb_cells, nb_records_per_page) = {
+        let wrapper = records_manager.get_header_page_wrapper();
+        let header = wrapper.get_header_payload_slice_ref();
+        (read_layout_value(header, LAYOUT_KEY_SIZE_OFFSET), read_layout_value(header, LAYOUT_NB_CELLS_OFFSET), read_layout_value(header, LAYOUT_NB_RECORDS_PER_PAGE_OFFSET))
+    };
+    let saved_layout = if key_size == 0 && nb_cells == 0 && nb_records_per_page == 0 {
+        Some(IndexLayout::default())
+    } else {
+        IndexLayout::new(key_size, nb_cells).filter(|saved| saved.get_nb_records_per_page() == nb_records_per_page)
+    };
+    match saved_layout {
+        Some(saved) => {
+            if saved != layout {
+                warn!("{} has keys of {} bytes and {} cells per node, configured index layout is ignored", file, saved.get_key_size(), saved.get_nb_cells());
+            }
+            saved
+        },
+        None => {
+            error!("{} has an invalid index layout, the indexes have to be rebuilt", file);
+            layout
+        }
+    }
 }
 
 fn append_key(vkey: &mut Vec<u8>, key_buf: &[u8]) {
@@ -72,9 +124,19 @@ enum CellLoadRes {
 }
 
 impl BTreeNodeStore {
-    pub fn new(file: &str) -> Self {
-        let rec_mngr = RecordsManager::new(file, BTREE_NODE_RECORD_SIZE, BTREE_NB_RECORDS_PER_PAGE, BTREE_NB_PAGES_PER_RECORD);
-        BTreeNodeStore{records_manager: Arc::new(Mutex::new(rec_mngr))}
+    pub fn new(file: &str, pool: &SharedBufferPool) -> Self {
+        Self::with_layout(file, pool, IndexLayout::default())
+    }
+
+    pub fn with_layout(file: &str, pool: &SharedBufferPool, layout: IndexLayout) -> Self {
+        let mut rec_mngr = RecordsManager::new(file, pool, layout.get_node_record_size(), layout.get_nb_records_per_page(), 0);
+        let layout = load_or_save_layout(&mut rec_mngr, file, layout);
+        rec_mngr.set_record_layout(layout.get_node_record_size(), layout.get_nb_records_per_page(), 0);
+        BTreeNodeStore{records_manager: Arc::new(Mutex::new(rec_mngr)), layout}
+    }
+
+    pub fn get_layout(&self) -> IndexLayout {
+        self.layout
     }
 
     fn retrieve_overflow_cells(&mut self, pool: &mut NodeRecordPool, cell_record: &CellRecord, vkey: &mut Vec<u8>) -> Option<CellLoadRes> {
@@ -118,7 +180,7 @@ impl BTreeNodeStore {
     }
 
     pub fn retrieve_node(&mut self, nid: NodeId) -> Option<BTreeNode> {
-        let mut pool = NodeRecordPool::new(self.records_manager.clone());
+        let mut pool = NodeRecordPool::new(self.records_manager.clone(), self.layout);
         let node = pool.load_node_record_clone(nid)?;
         let mut cells = Vec::new();
         for cell_record in &node.cells {
@@ -142,7 +204,7 @@ impl BTreeNodeStore {
         let mut curr_node_id = prev_cell_record.node_ptr;
         let mut curr_node_record = pool.load_node_record_mut(curr_node_id)?;
         for cell in cell_records {
-            curr_node_record.cells[curr_cell_id as usize] = *cell;
+            curr_node_record.cells[curr_cell_id as usize] = cell.clone();
             if cell.node_ptr != 0 && cell.has_overflow() && curr_node_id != cell.node_ptr {
                 curr_node_record = pool.load_node_record_mut(cell.node_ptr)?;
             } else if cell.node_ptr == 0 || !cell.has_overflow() {
@@ -164,28 +226,29 @@ impl BTreeNodeStore {
     }
 
     fn create_cell(&mut self, pool: &mut NodeRecordPool, cell: &Cell) -> Option<Vec<CellRecord>> {
+        let key_size = self.layout.get_key_size();
         let mut cell_records = Vec::new();
         let key_vec = cell.get_key().clone().into_bytes();
         
         let mut offset = 0;
         while offset < key_vec.len() {
-            let mut cell_record = CellRecord::new();
+            let mut cell_record = CellRecord::new(key_size);
             cell_record.set_is_active();
-            if offset + KEY_SIZE > key_vec.len() {
+            if offset + key_size > key_vec.len() {
                 let len = key_vec.len() - offset;
                 cell_record.key[..len].copy_from_slice(&key_vec[offset..key_vec.len()]);
                 cell_record.key[len] = b'\0';
             } else {
-                cell_record.key.copy_from_slice(&key_vec[offset..offset+KEY_SIZE]);
+                cell_record.key.copy_from_slice(&key_vec[offset..offset+key_size]);
             }
-            offset += KEY_SIZE;
+            offset += key_size;
 
             cell_records.push(cell_record);
         }
         
         if cell.get_data_ptrs_ref().len() > 0 {
             let mut data_ptr_offset = 2;
-            let mut cell_record = CellRecord::new();
+            let mut cell_record = CellRecord::new(key_size);
             cell_record.set_is_active();
             cell_record.set_is_list_ptr();
             let mut data_ptr_count: u16 = 0;
@@ -194,10 +257,10 @@ impl BTreeNodeStore {
                 data_ptr_offset += insert_data_ptr(&mut cell_record.key, data_ptr_offset, data_ptr);
                 data_ptr_count += 1;
                 whole_data_ptr_count += 1;
-                if  data_ptr_offset + NODE_PTR_SIZE >= KEY_SIZE {
+                if  data_ptr_offset + NODE_PTR_SIZE >= key_size {
                     update_counter(&mut cell_record.key, data_ptr_count);
                     cell_records.push(cell_record);
-                    cell_record = CellRecord::new();
+                    cell_record = CellRecord::new(key_size);
                     cell_record.set_is_active();
                     cell_record.set_is_list_ptr();
                     data_ptr_offset = 2;
@@ -232,8 +295,8 @@ impl BTreeNodeStore {
     }
 
     pub fn create(&mut self, node: &mut BTreeNode) -> Option<()> {
-        let mut node_record = BNodeRecord::new();
-        let mut pool = NodeRecordPool::new(self.records_manager.clone());
+        let mut node_record = BNodeRecord::new(&self.layout);
+        let mut pool = NodeRecordPool::new(self.records_manager.clone(), self.layout);
         if node.is_leaf() {
             node_record.set_leaf();
         }
@@ -248,7 +311,7 @@ impl BTreeNodeStore {
         let mut cell_id = 0;
         for cell in node.get_cells_ref() {
             let cell_records = self.create_cell(&mut pool, cell)?;
-            node_record.cells[cell_id] = *cell_records.first()?;
+            node_record.cells[cell_id] = cell_records.first()?.clone();
             cell_id += 1;
         }
         let id = pool.create_node_record(node_record)?;
@@ -272,16 +335,17 @@ impl BTreeNodeStore {
             while has_overflow {
                 let overflow_cell = &curr_node.cells[curr_overflow_cell_id as usize];
                 has_overflow = overflow_cell.has_overflow();
-                if has_overflow && curr_overflow_cell_id == overflow_cell.overflow_cell_ptr {
+                if has_overflow && (curr_node_id, curr_overflow_cell_id) == overflow_cell.get_next_cell_location() {
                     error!("cycle detected in node {} for cell {}", curr_node_id, curr_overflow_cell_id);
                     break;
                 }
-                curr_node_id = overflow_cell.node_ptr;
+                let next_node_id = overflow_cell.node_ptr;
                 curr_overflow_cell_id = overflow_cell.overflow_cell_ptr;
-                cells.push(*overflow_cell);
-                if curr_node_id != overflow_cell.node_ptr {
-                    curr_node = pool.load_node_record_ref(curr_node_id)?;
+                cells.push(overflow_cell.clone());
+                if has_overflow && curr_node_id != next_node_id {
+                    curr_node = pool.load_node_record_ref(next_node_id)?;
                 }
+                curr_node_id = next_node_id;
             }
         }
         
@@ -289,16 +353,17 @@ impl BTreeNodeStore {
     }
 
     fn update_cell_data_ptrs(&mut self, pool: &mut NodeRecordPool, root_cell_record: &CellRecord, data_ptrs: &Vec<NodeId>) -> Option<()> {
+        let key_size = self.layout.get_key_size();
         
         let overflow_cell_records = self.load_overflow_cell_records(pool, root_cell_record)?;
 
         let mut list_ptr_cells = Vec::new();
-        let mut prev_cell_record = *root_cell_record;
+        let mut prev_cell_record = root_cell_record.clone();
         for cell_record in &overflow_cell_records {
             if cell_record.is_list_ptr() {
-                list_ptr_cells.push(*cell_record);
+                list_ptr_cells.push(cell_record.clone());
             } else {
-                prev_cell_record = *cell_record;
+                prev_cell_record = cell_record.clone();
             }
         }
         
@@ -313,7 +378,7 @@ impl BTreeNodeStore {
             data_ptr_offset += insert_data_ptr(&mut curr_list_ptr_cell.key, data_ptr_offset, data_ptr);
             data_ptr_count += 1;
             whole_data_ptr_count += 1;
-            if data_ptr_offset + NODE_PTR_SIZE >= KEY_SIZE {
+            if data_ptr_offset + NODE_PTR_SIZE >= key_size {
                 update_counter(&mut curr_list_ptr_cell.key, data_ptr_count);
                 if to_create {
                     cells_to_create.push(curr_list_ptr_cell);
@@ -328,7 +393,7 @@ impl BTreeNodeStore {
                         cell
                     } else {
                         to_create = true;
-                        let mut new_cell = CellRecord::new();
+                        let mut new_cell = CellRecord::new(key_size);
                         new_cell.set_is_active();
                         new_cell.set_is_list_ptr();
                         new_cell
@@ -420,16 +485,20 @@ impl BTreeNodeStore {
 
     fn move_old_cell_records(&mut self, pool: &mut NodeRecordPool, node_record_id: NodeId, cells_context: &Vec<CellChangeContext>) -> Option<()> {
         let main_node_record = pool.load_node_record_mut(node_record_id)?;
-        let old_cell_records = main_node_record.cells;
+        let old_cell_records = main_node_record.cells.clone();
 
         //move and update old records
         let mut new_cell_id = 0;
         for ctx in cells_context {
             if !ctx.is_added {
-                main_node_record.cells[new_cell_id] = old_cell_records[ctx.old_cell_id];
+                main_node_record.cells[new_cell_id] = old_cell_records[ctx.old_cell_id].clone();
             }
             new_cell_id += 1;
         }
+        // the overflow records of moved cells are still referenced by their new slots
+        for cell in &mut main_node_record.cells[new_cell_id..] {
+            cell.set_inactive();
+        }
         Some(())
     }
 
@@ -457,7 +526,7 @@ impl BTreeNodeStore {
                 let current_cell = node.get_cell_ref(new_cell_id);
                 let cell_records = self.create_cell(pool, current_cell)?;
                 let main_node_record = pool.load_node_record_mut(node_record_id)?;
-                main_node_record.cells[new_cell_id] = cell_records[0];
+                main_node_record.cells[new_cell_id] = cell_records[0].clone();
             }
             new_cell_id += 1;
         }
@@ -465,7 +534,7 @@ impl BTreeNodeStore {
     }
 
     pub fn save(&mut self, node: &mut BTreeNode) -> Option<()> {
-        let mut pool = NodeRecordPool::new(self.records_manager.clone());
+        let mut pool = NodeRecordPool::new(self.records_manager.clone(), self.layout);
         
         let id = self.select_root_node(&mut pool, node)?;
 
@@ -482,6 +551,69 @@ impl BTreeNodeStore {
         Some(())
     }
 
+    /// Verifies the records of a tree node: active cells come first and every overflow chain ends on active cells of overflow nodes.
+    pub fn check_node_records(&mut self, nid: NodeId, is_leaf: bool, errors: &mut Vec<String>) {
+        let mut pool = NodeRecordPool::new(self.records_manager.clone(), self.layout);
+        let node = match pool.load_node_record_clone(nid) {
+            Some(node) => node,
+            None => {
+                errors.push(format!("node {}: record can't be loaded", nid));
+                return;
+            }
+        };
+        if node.is_overflow_node() {
+            errors.push(format!("node {}: overflow node referenced as a tree node", nid));
+        }
+        if let Some(first_inactive) = node.cells.iter().position(|cell| !cell.is_active()) {
+            if node.cells[first_inactive..].iter().any(|cell| cell.is_active()) {
+                errors.push(format!("node {}: active cell found after inactive cell {}", nid, first_inactive));
+            }
+        }
+        for (cell_id, cell) in node.cells.iter().enumerate().filter(|(_, cell)| cell.is_active()) {
+            let mut has_data_ptrs = cell.is_list_ptr();
+            let mut visited = HashSet::new();
+            let mut curr = cell.clone();
+            while curr.has_overflow() {
+                let (next_nid, next_cell_id) = curr.get_next_cell_location();
+                if !visited.insert((next_nid, next_cell_id)) {
+                    errors.push(format!("node {} cell {}: overflow chain cycles on node {} cell {}", nid, cell_id, next_nid, next_cell_id));
+                    break;
+                }
+                let next = match pool.load_node_record_ref(next_nid) {
+                    Some(record) if !record.is_overflow_node() => {
+                        errors.push(format!("node {} cell {}: overflow pointer to node {} which is not an overflow node", nid, cell_id, next_nid));
+                        break;
+                    },
+                    Some(record) => record.cells.get(next_cell_id as usize).cloned(),
+                    None => None,
+                };
+                match next {
+                    Some(next) if next.is_active() => {
+                        has_data_ptrs |= next.is_list_ptr();
+                        curr = next;
+                    },
+                    Some(_) => {
+                        errors.push(format!("node {} cell {}: overflow pointer to inactive cell {} of node {}", nid, cell_id, next_cell_id, next_nid));
+                        break;
+                    },
+                    None => {
+                        errors.push(format!("node {} cell {}: dangling overflow pointer to node {} cell {}", nid, cell_id, next_nid, next_cell_id));
+                        break;
+                    }
+                }
+            }
+            if is_leaf && !has_data_ptrs {
+                errors.push(format!("node {} cell {}: leaf cell without data pointers", nid, cell_id));
+            } else if !is_leaf && has_data_ptrs {
+                errors.push(format!("node {} cell {}: interior cell with data pointers", nid, cell_id));
+            }
+        }
+    }
+
+    pub fn get_root_node_id(&mut self) -> NodeId {
+        self.get_root_node_ptr()
+    }
+
     fn get_root_node_ptr(&mut self) -> NodeId {
         let mut buf = [0u8; NODE_PTR_SIZE];
         buf.copy_from_slice(&self.records_manager.lock().unwrap().get_header_page_wrapper().get_header_payload_slice_ref()[..NODE_PTR_SIZE]);
@@ -511,6 +643,10 @@ impl BTreeNodeStore {
     pub fn sync(&mut self) {
         self.records_manager.lock().unwrap().sync();
     }
+
+    pub fn prefetch(&mut self, max_pages: usize) -> usize {
+        self.records_manager.lock().unwrap().prefetch(max_pages)
+    }
 }
 
 struct CellChangeContext {
@@ -542,7 +678,7 @@ mod test_btree_node_store {
         let file = build_file_path_and_rm_old("b_tree_nodes", "test_create.db").unwrap();
         let long_key = "blabla6blabla6blabla6blabla6blabla6blabla6blabla6blabla6blabla6blabla6blabla6blabla6
         blabla6blabla6blabla6blabla6blabla6blabla6blabla6blabla6blabla6blabla6blabla6blabla6";
-        let mut store = BTreeNodeStore::new(&file);
+        let mut store = BTreeNodeStore::new(&file, &BufferPool::new_shared(TEST_POOL_CAPACITY));
         let mut cells = Vec::new();
         cells.push(Cell::new_ptr("blabla1", Some(1)));
         cells.push(Cell::new_ptr("blabla2", Some(2)));
@@ -555,7 +691,7 @@ mod test_btree_node_store {
         store.create(&mut node);
         store.sync();
 
-        let mut load_store = BTreeNodeStore::new(&file);
+        let mut load_store = BTreeNodeStore::new(&file, &BufferPool::new_shared(TEST_POOL_CAPACITY));
         let load =  node.get_id().and_then(|id| load_store.retrieve_node(id));
 
         if let Some(loaded) = &load {
@@ -583,7 +719,7 @@ mod test_btree_node_store {
         blabla6blabla6blabla6blabla6blabla6blabla6blabla6blabla6blabla6blabla6blabla6blabla6blabla6blabla6blabla6blabla6blabla6blabla6blabla6blabla6blabla6blabla6blabla6blabla6
         blabla6blabla6blabla6blabla6blabla6blabla6blabla6blabla6blabla6blabla6blabla6blabla6blabla6blabla6blabla6blabla6blabla6blabla6blabla6blabla6blabla6blabla6blabla6blabla6
         blabla6blabla6blabla6blabla6blabla6blabla6blabla6blabla6blabla6blabla6blabla6blabla6";
-        let mut store = BTreeNodeStore::new(&file);
+        let mut store = BTreeNodeStore::new(&file, &BufferPool::new_shared(TEST_POOL_CAPACITY));
         let mut cells = Vec::new();
         cells.push(Cell::new_ptr(long_key, Some(6)));
         let mut node = BTreeNode::new(false, false, cells);
@@ -591,7 +727,7 @@ mod test_btree_node_store {
         store.create(&mut node);
         store.sync();
 
-        let mut load_store = BTreeNodeStore::new(&file);
+        let mut load_store = BTreeNodeStore::new(&file, &BufferPool::new_shared(TEST_POOL_CAPACITY));
         let load =  node.get_id().and_then(|id| load_store.retrieve_node(id));
 
         if let Some(loaded) = &load {
@@ -608,7 +744,7 @@ mod test_btree_node_store {
     #[test]
     fn test_many_ptrs() {
         let file = build_file_path_and_rm_old("b_tree_nodes", "test_many_ptrs.db").unwrap();
-        let mut store = BTreeNodeStore::new(&file);
+        let mut store = BTreeNodeStore::new(&file, &BufferPool::new_shared(TEST_POOL_CAPACITY));
         let mut cells = Vec::new();
         cells.push(Cell::new("same key", Some(42), vec![12, 98, 78667867867, 21, 9], true));
         let mut node = BTreeNode::new(true, false, cells);
@@ -628,7 +764,7 @@ mod test_btree_node_store {
     #[test]
     fn test_many_ptrs_one_by_one() {
         let file = build_file_path_and_rm_old("b_tree_nodes", "test_many_ptrs_one_by_one.db").unwrap();
-        let mut store = BTreeNodeStore::new(&file);
+        let mut store = BTreeNodeStore::new(&file, &BufferPool::new_shared(TEST_POOL_CAPACITY));
         let mut cells = Vec::new();
         cells.push(Cell::new("same key", Some(42), vec![12, 98, 77867867, 21, 9], true));
         let mut node = BTreeNode::new(true, false, cells);
@@ -656,7 +792,7 @@ mod test_btree_node_store {
     #[test]
     fn test_update_ptrs() {
         let file = build_file_path_and_rm_old("b_tree_nodes", "test_update_ptrs.db").unwrap();
-        let mut store = BTreeNodeStore::new(&file);
+        let mut store = BTreeNodeStore::new(&file, &BufferPool::new_shared(TEST_POOL_CAPACITY));
 
         let long_key = "blabla3blabla3blabla3blabla3blabla3blabla3blabla3blabla3blabla3blabla3blabla3blabla3blabla3blabla3blabla3blabla3blabla3blabla3blabla3blabla3blabla3blabla3blabla3blabla3blabla3blabla3blabla3";
         let mut cells = Vec::new();
@@ -670,7 +806,7 @@ mod test_btree_node_store {
         store.create(&mut node);
         store.sync();
 
-        let mut load_store = BTreeNodeStore::new(&file);
+        let mut load_store = BTreeNodeStore::new(&file, &BufferPool::new_shared(TEST_POOL_CAPACITY));
         let mut loaded =  node.get_id().and_then(|id| load_store.retrieve_node(id));
 
         if let Some(load) = &mut loaded {
@@ -725,5 +861,29 @@ mod test_btree_node_store {
         
     }
 
-}
 
+    #[test]
+    fn test_check_node_records() {
+        let file = build_file_path_and_rm_old("b_tree_nodes", "test_check_node_records.db").unwrap();
+        let mut store = BTreeNodeStore::new(&file, &BufferPool::new_shared(TEST_POOL_CAPACITY));
+        let long_key = "a long key ".repeat(20);
+        let mut node = BTreeNode::new(true, true, vec![Cell::new_leaf(&long_key, 1), Cell::new_leaf("short", 2)]);
+        store.create(&mut node);
+        let id = node.get_id().expect("node id");
+
+        let mut errors = Vec::new();
+        store.check_node_records(id, true, &mut errors);
+        assert!(errors.is_empty(), "{:?}", errors);
+        store.check_node_records(id, false, &mut errors);
+        assert_eq!(2, errors.len());
+
+        let mut pool = NodeRecordPool::new(store.records_manager.clone(), store.layout);
+        pool.load_node_record_mut(id).expect("node record").cells[0].overflow_cell_ptr = 1000;
+        pool.save_all_node_records();
+        let mut errors = Vec::new();
+        store.check_node_records(id, true, &mut errors);
+        assert_eq!(2, errors.len());
+        assert!(errors[0].contains("dangling overflow pointer"), "{:?}", errors);
+        assert!(errors[1].contains("leaf cell without data pointers"), "{:?}", errors);
+    }
+}
@@ -21,6 +21,7 @@
 use super::{MutableRecordsManager, records::*};
 use super::super::super::super::buf_config::*;
 use std::collections::HashMap;
+use crate::model::init::IndexLayout;
 
 pub type BTreeNodeId = u64;
 pub type BTreeCellId = u32;
@@ -31,12 +32,22 @@ pub type BtreeCellLoc = (BTreeNodeId, BTreeCellId);
 pub struct NodeRecordPool {
     pub records: HashMap<u64, BNodeRecord>,
     pub records_manager: MutableRecordsManager,
+    pub layout: IndexLayout,
 }
 
 impl NodeRecordPool {
 
-    pub fn new(record_manager: MutableRecordsManager) -> Self {
-        NodeRecordPool{ records: HashMap::new(), records_manager: record_manager }
+    pub fn new(record_manager: MutableRecordsManager, layout: IndexLayout) -> Self {
+        NodeRecordPool{ records: HashMap::new(), records_manager: record_manager, layout }
+    }
+
+    fn load_node_record(&mut self, id: u64) -> Option<()> {
+        if !self.records.contains_key(&id) {
+            let mut data = vec![0u8; self.layout.get_node_record_size()];
+            self.records_manager.lock().unwrap().load(id, &mut data).ok()?;
+            self.records.insert(id, BNodeRecord::from_bytes(&data, &self.layout));
+        }
+        Some(())
     }
 
     pub fn is_empty_records_set(&mut self) -> bool {
@@ -44,42 +55,29 @@ impl NodeRecordPool {
     }
 
     pub fn load_node_record_clone(&mut self, id: u64) -> Option<BNodeRecord> {
-        if !self.records.contains_key(&id) {
-            let mut data = [0u8; BTREE_NODE_RECORD_SIZE];
-            self.records_manager.lock().unwrap().load(id, &mut data).ok()?;
-            self.records.insert(id, BNodeRecord::from_bytes(data));
-        }
+        self.load_node_record(id)?;
         Some(self.records.get(&id)?.clone())
     }
 
     pub fn load_node_record_ref(&mut self, id: u64) -> Option<&BNodeRecord> {
-        if !self.records.contains_key(&id) {
-            let mut data = [0u8; BTREE_NODE_RECORD_SIZE];
-            self.records_manager.lock().unwrap().load(id, &mut data).ok()?;
-            self.records.insert(id, BNodeRecord::from_bytes(data));
-        }
+        self.load_node_record(id)?;
         Some(self.records.get(&id)?)
     }
 
     pub fn load_node_record_mut(&mut self, id: u64) -> Option<&mut BNodeRecord> {
-        if !self.records.contains_key(&id) {
-            let mut data = [0u8; BTREE_NODE_RECORD_SIZE];
-            self.records_manager.lock().unwrap().load(id, &mut data).ok()?;
-            self.records.insert(id, BNodeRecord::from_bytes(data));
-            
-        }
+        self.load_node_record(id)?;
         Some(self.records.get_mut(&id)?)
     }
 
     pub fn create_node_record(&mut self, node_record: BNodeRecord) -> Option<u64> {
-        let id = self.records_manager.lock().unwrap().create(&node_record.to_bytes()).ok()?;
+        let id = self.records_manager.lock().unwrap().create(&node_record.to_bytes(&self.layout)).ok()?;
         self.records.insert(id, node_record);
         Some(id)
     }
 
     pub fn save_all_node_records(&mut self) -> Option<()> {
         for r in &self.records {
-            self.records_manager.lock().unwrap().save(*r.0, &r.1.to_bytes()).ok()?
+            self.records_manager.lock().unwrap().save(*r.0, &r.1.to_bytes(&self.layout)).ok()?
         }
         Some(())
     }
@@ -92,7 +90,7 @@ impl NodeRecordPool {
         let mut iter = self.free_cell_iter();
         let next_free_cell_loc = iter.next()?;
         let mut nr = self.load_node_record_mut(next_free_cell_loc.0)?;
-        nr.cells[next_free_cell_loc.1 as usize] = *cell_record;
+        nr.cells[next_free_cell_loc.1 as usize] = cell_record.clone();
         Some(next_free_cell_loc)
     }
 
@@ -100,7 +98,7 @@ impl NodeRecordPool {
         let mut next_cell_loc = root_cell_record_loc;
         while next_cell_loc.0 != 0 {
             let nr = self.load_node_record_mut(next_cell_loc.0)?;
-            let mut curr_cell = nr.cells[next_cell_loc.1 as usize];
+            let mut curr_cell = nr.cells[next_cell_loc.1 as usize].clone();
             curr_cell.set_inactive();
             next_cell_loc = curr_cell.get_next_cell_location();
         }
@@ -131,7 +129,7 @@ pub struct FreeCellIterator<'a> {
 impl <'a> FreeCellIterator<'a> {
     fn load_or_create_free_cells_overflow_node(&mut self) -> Option<BTreeNodeId> {
         if self.pool.is_empty_records_set() {
-            let mut first_free_node = BNodeRecord::new();
+            let mut first_free_node = BNodeRecord::new(&self.pool.layout);
             first_free_node.set_overflow_node();
             let new_record = self.pool.create_node_record(first_free_node)?;
             self.set_first_free_list_node_ptr(new_record);
@@ -164,7 +162,7 @@ impl <'a> FreeCellIterator<'a> {
 
     
     fn create_overflow_node(&mut self) -> Option<BTreeNodeId> {
-        let mut next_free_cells_overflow_node = BNodeRecord::new();
+        let mut next_free_cells_overflow_node = BNodeRecord::new(&self.pool.layout);
         next_free_cells_overflow_node.set_overflow_node();
         let id = self.pool.create_node_record(next_free_cells_overflow_node)?;
         Some(id)
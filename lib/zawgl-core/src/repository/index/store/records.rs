@@ -19,6 +19,7 @@
 // SOFTWARE.
 
 use crate::repository::index::model::{CellId, NodeId};
+use crate::model::init::IndexLayout;
 
 use super::super::super::super::buf_config::*;
 
@@ -31,17 +32,17 @@ const HAS_NEXT_NODE_FLAG: u8 = 0b0100_0000;
 const IS_OVERFLOW_NODE_FLAG: u8 = 0b0010_0000;
 const IS_ROOT_NODE_FLAG: u8 = 0b0001_0000;
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Clone)]
 pub struct CellRecord {
     pub header: u8,
     pub node_ptr: u64,
     pub overflow_cell_ptr: u32,
-    pub key: [u8; KEY_SIZE],
+    pub key: Vec<u8>,
 }
 
 impl CellRecord {
-    pub fn new() -> Self {
-        CellRecord{header: 0, key: [0u8; KEY_SIZE], node_ptr: 0, overflow_cell_ptr: 0}
+    pub fn new(key_size: usize) -> Self {
+        CellRecord{header: 0, key: vec![0u8; key_size], node_ptr: 0, overflow_cell_ptr: 0}
     }
     pub fn has_overflow(&self) -> bool {
         self.header & HAS_OVERFLOW_CELL_FLAG > 0
@@ -66,8 +67,8 @@ impl CellRecord {
     pub fn set_is_list_ptr(&mut self) {
         self.header = self.header | IS_LIST_PTR_CELL_FLAG;
     }
-    pub fn to_bytes(&self) -> [u8; CELL_SIZE] {
-        let mut bytes = [0u8; CELL_SIZE];
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![0u8; CELL_HEADER_SIZE + NODE_PTR_SIZE + OVERFLOW_CELL_PTR_SIZE + self.key.len()];
         let mut offset = 0;
         bytes[offset] = self.header;
         offset += CELL_HEADER_SIZE;
@@ -75,7 +76,7 @@ impl CellRecord {
         offset += NODE_PTR_SIZE;
         bytes[offset..offset+OVERFLOW_CELL_PTR_SIZE].copy_from_slice(&self.overflow_cell_ptr.to_be_bytes());
         offset += OVERFLOW_CELL_PTR_SIZE;
-        bytes[offset..offset+KEY_SIZE].copy_from_slice(&self.key);
+        bytes[offset..].copy_from_slice(&self.key);
         bytes
     }
     pub fn from_bytes(bytes: &[u8]) -> Self {
@@ -92,8 +93,7 @@ impl CellRecord {
         let overflow_cell_ptr = u32::from_be_bytes(overflow_cell_ptr_buf);
         offset += OVERFLOW_CELL_PTR_SIZE;
 
-        let mut key = [0u8; KEY_SIZE];
-        key.copy_from_slice(&bytes[offset..offset+KEY_SIZE]);
+        let key = bytes[offset..].to_vec();
         CellRecord{
             header: header,
             node_ptr: ptr,
@@ -111,11 +111,11 @@ impl CellRecord {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub struct BNodeRecord {
     pub header: u8,
     pub next_free_cells_node_ptr: u64,
-    pub cells: [CellRecord; NB_CELL],
+    pub cells: Vec<CellRecord>,
     pub ptr: u64,
 }
 
@@ -133,8 +133,9 @@ impl BNodeRecord {
         }
         keys
     }
-    pub fn to_bytes(&self) -> [u8; BTREE_NODE_RECORD_SIZE] {
-        let mut bytes = [0u8; BTREE_NODE_RECORD_SIZE];
+    pub fn to_bytes(&self, layout: &IndexLayout) -> Vec<u8> {
+        let cell_size = layout.get_cell_size();
+        let mut bytes = vec![0u8; layout.get_node_record_size()];
         let mut index = 0;
         bytes[index] = self.header;
         index += BTREE_NODE_HEADER_SIZE;
@@ -143,13 +144,14 @@ impl BNodeRecord {
         
         bytes[index..index+FREE_CELLS_NEXT_NODE_PTR_SIZE].copy_from_slice(&self.next_free_cells_node_ptr.to_be_bytes());
         index += FREE_CELLS_NEXT_NODE_PTR_SIZE;
-        for cell_id in 0..NB_CELL {
-            bytes[index..index+CELL_SIZE].copy_from_slice(&self.cells[cell_id].to_bytes());
-            index += CELL_SIZE;
+        for cell in &self.cells {
+            bytes[index..index+cell_size].copy_from_slice(&cell.to_bytes());
+            index += cell_size;
         }
         bytes
     }
-    pub fn from_bytes(bytes: [u8; BTREE_NODE_RECORD_SIZE]) -> Self {
+    pub fn from_bytes(bytes: &[u8], layout: &IndexLayout) -> Self {
+        let cell_size = layout.get_cell_size();
         let mut index = 0;
         let header = bytes[index];
         index += BTREE_NODE_HEADER_SIZE;
@@ -164,10 +166,10 @@ impl BNodeRecord {
         index += FREE_CELLS_NEXT_NODE_PTR_SIZE;
         let next_free_cells_node_ptr = u64::from_be_bytes(free_cells_buf);
 
-        let mut cells = [CellRecord::new(); NB_CELL];
-        for cell_id in 0..NB_CELL {
-            let offset = index + cell_id * CELL_SIZE;
-            cells[cell_id] = CellRecord::from_bytes(&bytes[offset..offset+CELL_SIZE]);
+        let mut cells = Vec::with_capacity(layout.get_nb_cells());
+        for cell_id in 0..layout.get_nb_cells() {
+            let offset = index + cell_id * cell_size;
+            cells.push(CellRecord::from_bytes(&bytes[offset..offset+cell_size]));
         }
         BNodeRecord{header: header, next_free_cells_node_ptr: next_free_cells_node_ptr, cells: cells, ptr: ptr}
     }
@@ -202,8 +204,8 @@ impl BNodeRecord {
         self.header = self.header | HAS_NEXT_NODE_FLAG;
     }
 
-    pub fn new() -> Self {
-        BNodeRecord{header: 0, next_free_cells_node_ptr: 0, cells: [CellRecord::new(); NB_CELL], ptr: 0}
+    pub fn new(layout: &IndexLayout) -> Self {
+        BNodeRecord{header: 0, next_free_cells_node_ptr: 0, cells: vec![CellRecord::new(layout.get_key_size()); layout.get_nb_cells()], ptr: 0}
     }
 
     pub fn is_full(&self) -> bool {
@@ -224,13 +226,14 @@ mod test_btree_node_records {
     use super::*;
     #[test]
     fn test_bytes() {
-        let mut node = BNodeRecord::new();
+        let layout = IndexLayout::default();
+        let mut node = BNodeRecord::new(&layout);
         node.set_leaf();
         node.next_free_cells_node_ptr = 28967;
         node.set_has_next_node();
         node.cells[0].set_is_active();
-        let bytes = node.to_bytes();
-        let mut from = BNodeRecord::from_bytes(bytes);
+        let bytes = node.to_bytes(&layout);
+        let mut from = BNodeRecord::from_bytes(&bytes, &layout);
 
         assert!(from.is_leaf());
         assert_eq!(from.next_free_cells_node_ptr, 28967);
@@ -239,8 +242,8 @@ mod test_btree_node_records {
         from.cells[0].set_is_list_ptr();
         from.cells[0].set_inactive();
 
-        let fbytes = from.to_bytes();
-        let from_1 = BNodeRecord::from_bytes(fbytes);
+        let fbytes = from.to_bytes(&layout);
+        let from_1 = BNodeRecord::from_bytes(&fbytes, &layout);
 
         assert!(from_1.is_leaf());
         assert!(from_1.has_next_node());
@@ -21,10 +21,14 @@
 use std::fs::File;
 use std::io::prelude::*;
 use std::io::SeekFrom;
-use log::error;
+use log::{error, warn};
+use memmap2::Mmap;
 
 pub struct FileAccess {
-    file: File
+    file: File,
+    fsync_on_write: bool,
+    use_mmap: bool,
+    mmap: Option<Mmap>,
 }
 
 impl FileAccess {
@@ -35,7 +39,7 @@ impl FileAccess {
             .create(true)
             .open(file)
             .expect("Cannot open file");
-        FileAccess {file: f}
+        FileAccess {file: f, fsync_on_write: true, use_mmap: false, mmap: None}
     }
     fn _write_at(&mut self, pos: u64, data: &[u8]) -> std::io::Result<()> {
         let mut written = 0;
@@ -45,9 +49,21 @@ impl FileAccess {
             written += bytes_written;
         }
         self.file.flush()?;
-        self.file.sync_all()?;
+        if self.fsync_on_write {
+            self.file.sync_all()?;
+        }
         Ok(())
     }
+
+    pub fn set_fsync_on_write(&mut self, fsync_on_write: bool) {
+        self.fsync_on_write = fsync_on_write;
+    }
+
+    pub fn sync_all(&mut self) {
+        if let Err(msg) = self.file.sync_all() {
+            error!("syncing file {}", msg);
+        }
+    }
     fn _read_at(&mut self, pos: u64 , mut data: &mut [u8]) -> std::io::Result<()> {
         self.file.seek(SeekFrom::Start(pos))?;
         self.file.read_exact(&mut data)?;
@@ -62,7 +78,47 @@ impl FileAccess {
             _ => {}
         }
     }
+    /// Reads from a memory map of the file if enabled, the file is mapped again when it has grown past the mapped length.
+    pub fn set_use_mmap(&mut self, use_mmap: bool) {
+        self.use_mmap = use_mmap;
+        if !use_mmap {
+            self.mmap = None;
+        }
+    }
+
+    fn remap(&mut self) {
+        self.mmap = None;
+        if self.get_file_len() == 0 {
+            return;
+        }
+        // the store files are only written through this process and never truncated
+        match unsafe { Mmap::map(&self.file) } {
+            Ok(mmap) => self.mmap = Some(mmap),
+            Err(msg) => {
+                warn!("mapping file {}, falling back to file reads", msg);
+                self.use_mmap = false;
+            }
+        }
+    }
+
+    fn _read_mapped(&mut self, pos: u64, data: &mut [u8]) -> bool {
+        let end = pos as usize + data.len();
+        if self.mmap.as_ref().is_none_or(|mmap| mmap.len() < end) {
+            self.remap();
+        }
+        match &self.mmap {
+            Some(mmap) if mmap.len() >= end => {
+                data.copy_from_slice(&mmap[pos as usize..end]);
+                true
+            },
+            _ => false,
+        }
+    }
+
     pub fn read_at(&mut self, pos: u64 , data: &mut [u8]) {
+        if self.use_mmap && self._read_mapped(pos, data) {
+            return;
+        }
         match self._read_at(pos, data) {
             Err(msg) => {
                 error!("reading file {}", msg);
@@ -79,4 +135,28 @@ impl FileAccess {
             Ok(md) => {md.len()}
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod test_file_access {
+    use super::*;
+    use crate::test_utils::build_file_path_and_rm_old;
+
+    #[test]
+    fn test_mapped_reads() {
+        let file = build_file_path_and_rm_old("test_file_access", "mapped.db").unwrap();
+        let mut fa = FileAccess::new(&file);
+        fa.set_use_mmap(true);
+        fa.write_at(0, &[1u8; 64]);
+        let mut data = [0u8; 32];
+        fa.read_at(32, &mut data);
+        assert_eq!([1u8; 32], data);
+        fa.write_at(64, &[2u8; 64]);
+        fa.write_at(0, &[3u8; 32]);
+        fa.read_at(96, &mut data);
+        assert_eq!([2u8; 32], data);
+        fa.read_at(0, &mut data);
+        assert_eq!([3u8; 32], data);
+        assert!(fa.mmap.is_some());
+    }
+}
@@ -18,4 +18,5 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-pub mod file_access;
\ No newline at end of file
+pub mod file_access;
+pub mod segmented_file_access;
\ No newline at end of file
@@ -0,0 +1,176 @@
+// MIT License
+//
+// Copyright (c) 2022 Alexandre RICCIARDI
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use std::fs;
+use std::io;
+use std::path::Path;
+use log::warn;
+use super::file_access::FileAccess;
+
+pub fn build_segment_path(file: &str, segment: usize) -> String {
+    if segment == 0 {
+        String::from(file)
+    } else {
+        format!("{}.{}", file, segment)
+    }
+}
+
+/// Removes a segmented file and all its segments.
+pub fn remove_segmented_file(file: &str) -> io::Result<()> {
+    let mut segment = 0;
+    loop {
+        let path = build_segment_path(file, segment);
+        match fs::remove_file(&path) {
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(err) => return Err(err),
+            Ok(_) => segment += 1,
+        }
+    }
+}
+
+/// Moves a segmented file and all its segments, the segments of the target path are expected to be removed.
+pub fn rename_segmented_file(file: &str, target: &str) -> io::Result<()> {
+    let mut segment = 0;
+    loop {
+        match fs::rename(build_segment_path(file, segment), build_segment_path(target, segment)) {
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(err) => return Err(err),
+            Ok(_) => segment += 1,
+        }
+    }
+}
+
+/// Size on disk of a segmented file and all its segments.
+pub fn segmented_file_len(file: &str) -> u64 {
+    (0..).map(|segment| fs::metadata(build_segment_path(file, segment)))
+        .take_while(|metadata| metadata.is_ok())
+        .filter_map(|metadata| metadata.ok())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+/// A file split in fixed size segments, the first segment keeps the file path and segment `n` is stored in `<file>.<n>`.
+/// Writes and reads must not cross a segment boundary.
+pub struct SegmentedFileAccess {
+    file: String,
+    segment_size: u64,
+    segments: Vec<FileAccess>,
+    fsync_on_write: bool,
+    use_mmap: bool,
+}
+
+impl SegmentedFileAccess {
+    pub fn new(file: &str, segment_size: u64, use_mmap: bool) -> Self {
+        let mut segments = vec![FileAccess::new(file)];
+        while Path::new(&build_segment_path(file, segments.len())).exists() {
+            segments.push(FileAccess::new(&build_segment_path(file, segments.len())));
+        }
+        for segment in &mut segments {
+            segment.set_use_mmap(use_mmap);
+        }
+        let mut segment_size = segment_size;
+        if segments.len() > 1 {
+            let first_segment_len = segments[0].get_file_len();
+            if first_segment_len != segment_size {
+                warn!("{} has segments of {} bytes, configured segment size {} is ignored", file, first_segment_len, segment_size);
+                segment_size = first_segment_len;
+            }
+        }
+        SegmentedFileAccess{file: String::from(file), segment_size, segments, fsync_on_write: true, use_mmap}
+    }
+
+    fn locate(&mut self, pos: u64) -> (&mut FileAccess, u64) {
+        let segment = (pos / self.segment_size) as usize;
+        while self.segments.len() <= segment {
+            let mut file_access = FileAccess::new(&build_segment_path(&self.file, self.segments.len()));
+            file_access.set_fsync_on_write(self.fsync_on_write);
+            file_access.set_use_mmap(self.use_mmap);
+            self.segments.push(file_access);
+        }
+        (&mut self.segments[segment], pos % self.segment_size)
+    }
+
+    pub fn write_at(&mut self, pos: u64, data: &[u8]) {
+        let (segment, segment_pos) = self.locate(pos);
+        segment.write_at(segment_pos, data);
+    }
+
+    pub fn read_at(&mut self, pos: u64, data: &mut [u8]) {
+        let (segment, segment_pos) = self.locate(pos);
+        segment.read_at(segment_pos, data);
+    }
+
+    pub fn get_file_len(&self) -> u64 {
+        let last = self.segments.len() - 1;
+        last as u64 * self.segment_size + self.segments[last].get_file_len()
+    }
+
+    pub fn get_segment_size(&self) -> u64 {
+        self.segment_size
+    }
+
+    pub fn get_segment_paths(&self) -> Vec<String> {
+        (0..self.segments.len()).map(|segment| build_segment_path(&self.file, segment)).collect()
+    }
+
+    pub fn set_fsync_on_write(&mut self, fsync_on_write: bool) {
+        self.fsync_on_write = fsync_on_write;
+        for segment in &mut self.segments {
+            segment.set_fsync_on_write(fsync_on_write);
+        }
+    }
+
+    pub fn sync_all(&mut self) {
+        for segment in &mut self.segments {
+            segment.sync_all();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_segmented_file_access {
+    use super::*;
+    use crate::test_utils::build_file_path_and_rm_old;
+
+    #[test]
+    fn test_segments() {
+        let file = build_file_path_and_rm_old("test_segmented_file_access", "segments.db").unwrap();
+        remove_segmented_file(&file).unwrap();
+        {
+            let mut sfa = SegmentedFileAccess::new(&file, 16, false);
+            sfa.write_at(0, &[1u8; 16]);
+            sfa.write_at(16, &[2u8; 16]);
+            sfa.write_at(40, &[3u8; 8]);
+            assert_eq!(48, sfa.get_file_len());
+            assert_eq!(3, sfa.get_segment_paths().len());
+        }
+        let mut sfa = SegmentedFileAccess::new(&file, 4096, true);
+        assert_eq!(16, sfa.get_segment_size());
+        let mut data = [0u8; 8];
+        sfa.read_at(40, &mut data);
+        assert_eq!([3u8; 8], data);
+        sfa.read_at(16, &mut data);
+        assert_eq!([2u8; 8], data);
+        remove_segmented_file(&file).unwrap();
+        assert!(!Path::new(&build_segment_path(&file, 1)).exists());
+    }
+}
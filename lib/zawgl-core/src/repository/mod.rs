@@ -23,6 +23,8 @@ mod pager;
 mod records;
 mod index;
 mod store;
-mod io;
-mod properties_repository;
-pub mod graph_repository;
\ No newline at end of file
+pub(crate) mod io;
+pub(crate) mod properties_repository;
+pub mod graph_repository;
+
+pub use self::records::{BufferPool, SharedBufferPool};
\ No newline at end of file
@@ -0,0 +1,459 @@
+// MIT License
+//
+// Copyright (c) 2022 Alexandre RICCIARDI
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+
+use super::super::super::buf_config::*;
+use super::super::super::config::DEFAULT_SEGMENT_SIZE;
+use super::super::super::model::init::{Durability, InitContext, IoMode};
+use super::super::super::model::statistics::PageCacheStatistics;
+use super::super::super::metrics::{self, Counter};
+use super::PageId;
+
+pub type FileId = u32;
+pub type SharedBufferPool = Arc<Mutex<BufferPool>>;
+
+type FrameKey = (FileId, PageId);
+
+/// Reads and writes the pages of a file registered in the pool.
+pub trait PageFile {
+    fn read_page(&mut self, pid: PageId, data: &mut [u8; PAGE_SIZE]);
+    fn write_page(&mut self, pid: PageId, data: &[u8; PAGE_SIZE]);
+}
+
+struct Frame {
+    key: FrameKey,
+    data: Box<[u8; PAGE_SIZE]>,
+    last_used: u64,
+}
+
+/// Page fetched through a view of a file: a cached page or a page replaced since the version of the view.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PageFrame {
+    Cached(usize),
+    Replaced(FileId, PageId, usize),
+}
+
+/// Page replaced by a sync, kept for the views older than the version of the sync.
+struct ReplacedPage {
+    replaced_at: u64,
+    data: Box<[u8; PAGE_SIZE]>,
+}
+
+/// Committed pages shared by the pagers of the graph engines of a database, files are identified by their path.
+/// A pager keeps the pages it writes until it syncs them, they then replace the cached pages: the pool only holds
+/// pages as they are in the files, evicted in least recently used order.
+///
+/// Each pager reads its file through a view opened at the version of the file when the pager is created.
+/// A sync moves the file to a new version and keeps the pages it replaces while older views are open,
+/// so that a pager keeps reading the pages as they were at its version until it syncs its own pages.
+pub struct BufferPool {
+    capacity: usize,
+    frames: Vec<Frame>,
+    free_slots: Vec<usize>,
+    page_table: HashMap<FrameKey, usize>,
+    lru: BTreeMap<u64, usize>,
+    files: HashMap<String, FileId>,
+    views: HashMap<FileId, BTreeMap<u64, usize>>,
+    replaced_pages: HashMap<FileId, HashMap<PageId, Vec<ReplacedPage>>>,
+    version: u64,
+    commit_count: u64,
+    dirty_pages: usize,
+    tick: u64,
+    hits: u64,
+    misses: u64,
+    evictions: u64,
+    durability: Durability,
+    segment_size: u64,
+    io_mode: IoMode,
+}
+
+/// Keeps the replaced pages read by a view: a page replaced at a version is read by the views from the previous
+/// replacement of the page to that version.
+fn retain_read_pages(replaced: &mut Vec<ReplacedPage>, views: Option<&BTreeMap<u64, usize>>) {
+    let mut from = 0;
+    replaced.retain(|page| {
+        let is_read = views.is_some_and(|views| views.range(from..page.replaced_at).next().is_some());
+        from = page.replaced_at;
+        is_read
+    });
+}
+
+impl BufferPool {
+    pub fn new(capacity: usize) -> Self {
+        BufferPool{capacity: capacity.max(1), frames: Vec::new(), free_slots: Vec::new(), page_table: HashMap::new(),
+            lru: BTreeMap::new(), files: HashMap::new(), views: HashMap::new(), replaced_pages: HashMap::new(), version: 0, commit_count: 0,
+            dirty_pages: 0, tick: 0, hits: 0, misses: 0, evictions: 0, durability: Durability::Always, segment_size: DEFAULT_SEGMENT_SIZE, io_mode: IoMode::ReadWrite}
+    }
+
+    pub fn new_shared(capacity: usize) -> SharedBufferPool {
+        Arc::new(Mutex::new(BufferPool::new(capacity)))
+    }
+
+    /// Pool of a database with the page cache settings of its context.
+    pub fn from_context(init_ctx: &InitContext) -> SharedBufferPool {
+        let mut pool = BufferPool::new(init_ctx.get_page_cache_capacity());
+        pool.set_durability(init_ctx.get_durability());
+        pool.set_segment_size(init_ctx.get_segment_size());
+        pool.set_io_mode(init_ctx.get_io_mode());
+        Arc::new(Mutex::new(pool))
+    }
+
+    /// Evicts the least recently used pages above the new capacity.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity.max(1);
+        self.shrink_to_capacity();
+    }
+
+    /// Sets the durability of the files registered afterwards.
+    pub fn set_durability(&mut self, durability: Durability) {
+        self.durability = durability;
+    }
+
+    pub fn get_durability(&self) -> Durability {
+        self.durability
+    }
+
+    /// Sets the segment size of the files registered afterwards.
+    pub fn set_segment_size(&mut self, segment_size: u64) {
+        self.segment_size = segment_size;
+    }
+
+    pub fn get_segment_size(&self) -> u64 {
+        self.segment_size
+    }
+
+    /// Sets the io mode of the files registered afterwards.
+    pub fn set_io_mode(&mut self, io_mode: IoMode) {
+        self.io_mode = io_mode;
+    }
+
+    pub fn get_io_mode(&self) -> IoMode {
+        self.io_mode
+    }
+
+    /// Id of the pages of a file, the pagers of the same file share its cached pages.
+    pub fn register_file(&mut self, file: &str) -> FileId {
+        let next_file_id = self.files.len() as FileId + 1;
+        *self.files.entry(String::from(file)).or_insert(next_file_id)
+    }
+
+    /// Opens a view of a file at its current version, which is returned.
+    pub fn open_view(&mut self, file_id: FileId) -> u64 {
+        *self.views.entry(file_id).or_default().entry(self.version).or_insert(0) += 1;
+        self.version
+    }
+
+    /// Closes a view of a file, the pages replaced since its version are dropped once no view reads them.
+    pub fn close_view(&mut self, file_id: FileId, version: u64) {
+        self.remove_view(file_id, version);
+        let views = self.views.get(&file_id);
+        if let Some(replaced_pages) = self.replaced_pages.get_mut(&file_id) {
+            replaced_pages.values_mut().for_each(|replaced| retain_read_pages(replaced, views));
+            replaced_pages.retain(|_, replaced| !replaced.is_empty());
+        }
+    }
+
+    fn remove_view(&mut self, file_id: FileId, version: u64) {
+        if let Some(views) = self.views.get_mut(&file_id) {
+            if let Some(count) = views.get_mut(&version) {
+                *count -= 1;
+                if *count == 0 {
+                    views.remove(&version);
+                }
+            }
+            if views.is_empty() {
+                self.views.remove(&file_id);
+            }
+        }
+    }
+
+    /// Counts a sync of the stores of a graph engine, returns the number of syncs.
+    pub fn count_commit(&mut self) -> u64 {
+        self.commit_count += 1;
+        self.commit_count
+    }
+
+    pub fn get_commit_count(&self) -> u64 {
+        self.commit_count
+    }
+
+    fn next_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+
+    fn touch(&mut self, slot: usize) {
+        let tick = self.next_tick();
+        let frame = &mut self.frames[slot];
+        self.lru.remove(&frame.last_used);
+        self.lru.insert(tick, slot);
+        frame.last_used = tick;
+    }
+
+    fn evict_lru(&mut self) -> Option<usize> {
+        let (tick, slot) = self.lru.iter().next().map(|(tick, slot)| (*tick, *slot))?;
+        self.lru.remove(&tick);
+        self.page_table.remove(&self.frames[slot].key);
+        self.evictions += 1;
+        Some(slot)
+    }
+
+    fn shrink_to_capacity(&mut self) {
+        while self.page_table.len() > self.capacity {
+            match self.evict_lru() {
+                Some(slot) => self.free_slots.push(slot),
+                None => break,
+            }
+        }
+    }
+
+    fn allocate_slot(&mut self, key: FrameKey) -> usize {
+        let tick = self.next_tick();
+        let reused = self.free_slots.pop().or_else(|| if self.frames.len() < self.capacity { None } else { self.evict_lru() });
+        let slot = match reused {
+            Some(slot) => {
+                let frame = &mut self.frames[slot];
+                frame.key = key;
+                frame.last_used = tick;
+                slot
+            },
+            None => {
+                self.frames.push(Frame{key, data: Box::new([0u8; PAGE_SIZE]), last_used: tick});
+                self.frames.len() - 1
+            }
+        };
+        self.lru.insert(tick, slot);
+        self.page_table.insert(key, slot);
+        slot
+    }
+
+    /// Returns the frame of the page as it was at the version of the view, the page is read from the file on a cache miss.
+    pub fn fetch<F: PageFile>(&mut self, file_id: FileId, pid: PageId, version: u64, file: &mut F) -> PageFrame {
+        let replaced = self.replaced_pages.get(&file_id).and_then(|pages| pages.get(&pid)).and_then(|replaced| replaced.iter().position(|page| page.replaced_at > version));
+        if let Some(index) = replaced {
+            self.hits += 1;
+            metrics::increment(Counter::PageCacheHits);
+            return PageFrame::Replaced(file_id, pid, index);
+        }
+        if let Some(slot) = self.page_table.get(&(file_id, pid)).copied() {
+            self.hits += 1;
+            metrics::increment(Counter::PageCacheHits);
+            self.touch(slot);
+            return PageFrame::Cached(slot);
+        }
+        self.misses += 1;
+        metrics::increment(Counter::PageCacheMisses);
+        let slot = self.allocate_slot((file_id, pid));
+        file.read_page(pid, &mut self.frames[slot].data);
+        PageFrame::Cached(slot)
+    }
+
+    pub fn get_data(&self, frame: PageFrame) -> &[u8; PAGE_SIZE] {
+        match frame {
+            PageFrame::Cached(slot) => &self.frames[slot].data,
+            PageFrame::Replaced(file_id, pid, index) => &self.replaced_pages[&file_id][&pid][index].data,
+        }
+    }
+
+    /// Counts a page written by a pager and not synced yet.
+    pub fn add_dirty_page(&mut self) {
+        self.dirty_pages += 1;
+    }
+
+    /// Uncounts the pages of a pager dropped without syncing them.
+    pub fn discard_dirty_pages(&mut self, count: usize) {
+        self.dirty_pages -= count;
+    }
+
+    /// Writes the pages synced by the view of a pager to the file, in the given order, and caches them in place of
+    /// the pages they replace, which are kept while the other views of the file read them. Returns the version of
+    /// the view of the pager, moved to the new version of the file if pages were written.
+    pub fn publish<'p, I, F>(&mut self, file_id: FileId, version: u64, pages: I, file: &mut F) -> u64
+        where I: IntoIterator<Item = (PageId, &'p [u8; PAGE_SIZE])>, F: PageFile {
+        let mut pages = pages.into_iter().peekable();
+        if pages.peek().is_none() {
+            return version;
+        }
+        self.version += 1;
+        self.remove_view(file_id, version);
+        for (pid, data) in pages {
+            if let Some(views) = self.views.get(&file_id) {
+                let from = self.replaced_pages.get(&file_id).and_then(|pages| pages.get(&pid)).and_then(|replaced| replaced.last()).map_or(0, |page| page.replaced_at);
+                if views.range(from..self.version).next().is_some() {
+                    let mut previous = Box::new([0u8; PAGE_SIZE]);
+                    match self.page_table.get(&(file_id, pid)) {
+                        Some(slot) => *previous = *self.frames[*slot].data,
+                        None => file.read_page(pid, &mut previous),
+                    }
+                    self.replaced_pages.entry(file_id).or_default().entry(pid).or_default().push(ReplacedPage{replaced_at: self.version, data: previous});
+                }
+            }
+            file.write_page(pid, data);
+            let slot = match self.page_table.get(&(file_id, pid)).copied() {
+                Some(slot) => {
+                    self.touch(slot);
+                    slot
+                },
+                None => self.allocate_slot((file_id, pid)),
+            };
+            *self.frames[slot].data = *data;
+            self.dirty_pages -= 1;
+        }
+        self.shrink_to_capacity();
+        self.open_view(file_id)
+    }
+
+    pub fn get_statistics(&self) -> PageCacheStatistics {
+        PageCacheStatistics{
+            capacity: self.capacity,
+            cached_pages: self.page_table.len(),
+            dirty_pages: self.dirty_pages,
+            hits: self.hits,
+            misses: self.misses,
+            evictions: self.evictions,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_buffer_pool {
+    use super::*;
+
+    /// Pages holding their first byte, pages never written hold their id.
+    #[derive(Default)]
+    struct MemFile {
+        pages: HashMap<PageId, u8>,
+        reads: usize,
+        writes: Vec<(PageId, u8)>,
+    }
+
+    impl PageFile for MemFile {
+        fn read_page(&mut self, pid: PageId, data: &mut [u8; PAGE_SIZE]) {
+            self.reads += 1;
+            data[0] = self.pages.get(&pid).copied().unwrap_or(pid as u8);
+        }
+
+        fn write_page(&mut self, pid: PageId, data: &[u8; PAGE_SIZE]) {
+            self.pages.insert(pid, data[0]);
+            self.writes.push((pid, data[0]));
+        }
+    }
+
+    fn read_first_byte(pool: &mut BufferPool, file_id: FileId, pid: PageId, version: u64, file: &mut MemFile) -> u8 {
+        let frame = pool.fetch(file_id, pid, version, file);
+        pool.get_data(frame)[0]
+    }
+
+    #[test]
+    fn test_lru_eviction() {
+        let mut pool = BufferPool::new(2);
+        let mut file = MemFile::default();
+        let nodes = pool.register_file("nodes");
+        let f1 = pool.fetch(nodes, 1, 0, &mut file);
+        pool.fetch(nodes, 2, 0, &mut file);
+        assert_eq!(pool.fetch(nodes, 1, 0, &mut file), f1);
+        pool.fetch(nodes, 3, 0, &mut file);
+        let stats = pool.get_statistics();
+        assert_eq!((stats.hits, stats.misses, stats.evictions, stats.cached_pages, file.reads), (1, 3, 1, 2, 3));
+        assert_eq!(read_first_byte(&mut pool, nodes, 1, 0, &mut file), 1);
+        assert_eq!(file.reads, 3);
+        assert_eq!(read_first_byte(&mut pool, nodes, 2, 0, &mut file), 2);
+        assert_eq!(file.reads, 4);
+        pool.set_capacity(1);
+        assert_eq!(pool.get_statistics().cached_pages, 1);
+    }
+
+    #[test]
+    fn test_files_are_shared_by_path() {
+        let mut pool = BufferPool::new(4);
+        let mut file = MemFile::default();
+        let nodes = pool.register_file("nodes");
+        let rels = pool.register_file("relationships");
+        assert_ne!(nodes, rels);
+        assert_eq!(nodes, pool.register_file("nodes"));
+        pool.fetch(nodes, 1, 0, &mut file);
+        pool.fetch(rels, 1, 0, &mut file);
+        let nodes = pool.register_file("nodes");
+        pool.fetch(nodes, 1, 0, &mut file);
+        assert_eq!(file.reads, 2);
+    }
+
+    #[test]
+    fn test_published_pages_replace_cached_pages() {
+        let mut pool = BufferPool::new(2);
+        let mut file = MemFile::default();
+        let nodes = pool.register_file("nodes");
+        let version = pool.open_view(nodes);
+        pool.fetch(nodes, 1, version, &mut file);
+        let (page_1, page_3) = ([42u8; PAGE_SIZE], [3u8; PAGE_SIZE]);
+        pool.add_dirty_page();
+        pool.add_dirty_page();
+        assert_eq!(pool.get_statistics().dirty_pages, 2);
+        let version = pool.publish(nodes, version, vec![(1, &page_1), (3, &page_3)], &mut file);
+        assert_eq!(file.writes, vec![(1, 42), (3, 3)]);
+        let stats = pool.get_statistics();
+        assert_eq!((stats.cached_pages, stats.dirty_pages), (2, 0));
+        assert_eq!(read_first_byte(&mut pool, nodes, 1, version, &mut file), 42);
+        assert_eq!(read_first_byte(&mut pool, nodes, 3, version, &mut file), 3);
+        assert_eq!(file.reads, 1);
+        assert_eq!(pool.publish(nodes, version, vec![], &mut file), version);
+
+        pool.add_dirty_page();
+        pool.discard_dirty_pages(1);
+        assert_eq!(pool.get_statistics().dirty_pages, 0);
+    }
+
+    #[test]
+    fn test_views_read_the_pages_of_their_version() {
+        let mut pool = BufferPool::new(1);
+        let mut file = MemFile::default();
+        let nodes = pool.register_file("nodes");
+        let writer = pool.open_view(nodes);
+        let old_reader = pool.open_view(nodes);
+        pool.fetch(nodes, 1, old_reader, &mut file);
+        pool.add_dirty_page();
+        pool.add_dirty_page();
+        let writer = pool.publish(nodes, writer, vec![(1, &[10u8; PAGE_SIZE]), (2, &[20u8; PAGE_SIZE])], &mut file);
+        assert_eq!(file.reads, 2);
+        let reader = pool.open_view(nodes);
+        pool.add_dirty_page();
+        let writer = pool.publish(nodes, writer, vec![(1, &[11u8; PAGE_SIZE])], &mut file);
+
+        let reads = file.reads;
+        assert_eq!(read_first_byte(&mut pool, nodes, 1, old_reader, &mut file), 1);
+        assert_eq!(read_first_byte(&mut pool, nodes, 2, old_reader, &mut file), 2);
+        assert_eq!(read_first_byte(&mut pool, nodes, 1, reader, &mut file), 10);
+        assert_eq!(read_first_byte(&mut pool, nodes, 2, reader, &mut file), 20);
+        assert_eq!(file.reads, reads + 1);
+        assert_eq!(read_first_byte(&mut pool, nodes, 1, writer, &mut file), 11);
+
+        pool.close_view(nodes, old_reader);
+        assert_eq!(pool.replaced_pages[&nodes].len(), 1);
+        assert_eq!(read_first_byte(&mut pool, nodes, 1, reader, &mut file), 10);
+        pool.close_view(nodes, reader);
+        assert!(pool.replaced_pages[&nodes].is_empty());
+        pool.close_view(nodes, writer);
+        assert!(pool.views.is_empty());
+    }
+}
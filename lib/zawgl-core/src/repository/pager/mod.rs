@@ -18,9 +18,17 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
+pub mod buffer_pool;
+
 use super::super::buf_config::*;
-use super::io::file_access::*;
+use super::io::segmented_file_access::*;
+use self::buffer_pool::*;
 use std::collections::HashMap;
+use std::ops::{Deref, DerefMut};
+use std::sync::MutexGuard;
+use std::time::Instant;
+use super::super::model::init::{Durability, IoMode};
+use super::super::metrics::{self, Counter};
 
 pub type PageId = u64;
 
@@ -51,27 +59,74 @@ impl  HeaderPage {
     }
 }
 
+/// Page bytes read from the buffer pool, which stays locked while the page is in use, or written by the pager:
+/// a page is copied into the pager when it is first written, the other pagers keep reading the synced page.
+pub struct PageData<'a> {
+    pool: MutexGuard<'a, BufferPool>,
+    pid: PageId,
+    frame: Option<PageFrame>,
+    dirty_pages: &'a mut HashMap<PageId, Box<[u8; PAGE_SIZE]>>,
+}
+
+impl <'a> Deref for PageData<'a> {
+    type Target = [u8; PAGE_SIZE];
+    fn deref(&self) -> &Self::Target {
+        match self.frame {
+            Some(frame) => self.pool.get_data(frame),
+            None => &self.dirty_pages[&self.pid],
+        }
+    }
+}
+
+impl <'a> DerefMut for PageData<'a> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        if let Some(frame) = self.frame.take() {
+            self.dirty_pages.insert(self.pid, Box::new(*self.pool.get_data(frame)));
+            self.pool.add_dirty_page();
+        }
+        self.dirty_pages.get_mut(&self.pid).expect("written page")
+    }
+}
+
 pub struct Page<'a> {
     pub id: PageId,
     pub header_page: &'a mut HeaderPage,
-    pub data: &'a mut [u8; PAGE_SIZE],
+    pub data: PageData<'a>,
 }
 
 impl <'a> Page<'a> {
-    fn new(id: PageId, header_page: &'a mut HeaderPage, data: &'a mut [u8; PAGE_SIZE]) -> Self {
+    fn new(id: PageId, header_page: &'a mut HeaderPage, data: PageData<'a>) -> Self {
         Page{id: id, header_page: header_page, data: data}
     }
 }
 
+impl PageFile for SegmentedFileAccess {
+    fn read_page(&mut self, pid: PageId, data: &mut [u8; PAGE_SIZE]) {
+        metrics::increment(Counter::PageReads);
+        self.read_at(pid * PAGE_SIZE as u64, data)
+    }
+
+    fn write_page(&mut self, pid: PageId, data: &[u8; PAGE_SIZE]) {
+        metrics::increment(Counter::PageWrites);
+        self.write_at(pid * PAGE_SIZE as u64, data)
+    }
+}
+
+/// Pages of a file read through a view of the buffer pool: the pager reads the pages synced before it was created
+/// or its last sync, and the pages it writes.
 pub struct Pager {
-    records_file: FileAccess,
-    page_cache: HashMap<PageId, [u8; PAGE_SIZE]>,
-    nb_pages: u64,
+    records_file: SegmentedFileAccess,
+    file_id: FileId,
+    pool: SharedBufferPool,
     header_page: HeaderPage,
+    dirty_pages: HashMap<PageId, Box<[u8; PAGE_SIZE]>>,
+    version: u64,
+    durability: Durability,
+    last_fsync: Instant,
 }
 
 
-fn load_or_create_header_page(io: &mut FileAccess) -> HeaderPage {
+fn load_or_create_header_page(io: &mut SegmentedFileAccess) -> HeaderPage {
     let mut header_page_data = [0u8; PAGE_SIZE];
     if io.get_file_len() == 0 {
         io.write_at(0, &header_page_data);
@@ -82,10 +137,16 @@ fn load_or_create_header_page(io: &mut FileAccess) -> HeaderPage {
 }
 
 impl Pager {
-    pub fn new(file: &str) -> Self {
-        let mut file_io = FileAccess::new(file);
+    pub fn new(file: &str, pool: &SharedBufferPool) -> Self {
+        let (file_id, version, durability, segment_size, io_mode) = {
+            let mut pool = pool.lock().unwrap();
+            let file_id = pool.register_file(file);
+            (file_id, pool.open_view(file_id), pool.get_durability(), pool.get_segment_size(), pool.get_io_mode())
+        };
+        let mut file_io = SegmentedFileAccess::new(file, segment_size, io_mode == IoMode::Mmap);
         let header_page = load_or_create_header_page(&mut file_io);
-        Pager { records_file: file_io, page_cache: HashMap::new(), nb_pages: 0u64, header_page: header_page}
+        file_io.set_fsync_on_write(durability == Durability::Always);
+        Pager { records_file: file_io, file_id, pool: pool.clone(), header_page, dirty_pages: HashMap::new(), version, durability, last_fsync: Instant::now()}
     }
 
     pub fn get_header_page_mut(&mut self) -> &mut HeaderPage {
@@ -96,41 +157,65 @@ impl Pager {
         &self.header_page
     }
     
-    fn read_page_data(&mut self, pid: PageId) -> [u8; PAGE_SIZE] {
-        let mut page_data = [0u8; PAGE_SIZE];
-        let page_begin_pos = pid * PAGE_SIZE as u64;
-        self.records_file.read_at(page_begin_pos, &mut page_data);
-        page_data
-    }
-
-    pub fn load_page(&mut self, pid: PageId) -> Option<Page> {
+    pub fn load_page(&mut self, pid: PageId) -> Option<Page<'_>> {
         if self.header_page.get_page_count() >= pid {
-            if !self.page_cache.contains_key(&pid) {
-                let page_data = self.read_page_data(pid);
-                self.page_cache.insert(pid, page_data);
-            }
-            Some(Page::new(pid, &mut self.header_page, self.page_cache.get_mut(&pid).unwrap()))
+            let mut pool = self.pool.lock().unwrap();
+            let records_file = &mut self.records_file;
+            let frame = if self.dirty_pages.contains_key(&pid) {
+                None
+            } else {
+                Some(pool.fetch(self.file_id, pid, self.version, records_file))
+            };
+            Some(Page::new(pid, &mut self.header_page, PageData{pool, pid, frame, dirty_pages: &mut self.dirty_pages}))
         } else {
             None
         }
         
     }
 
-    pub fn append(&mut self) -> Page {
+    /// Reads the pages in file order into the buffer pool, at most `max_pages` of them, returns the number of pages read.
+    pub fn prefetch(&mut self, max_pages: usize) -> usize {
+        let page_count = (self.header_page.get_page_count() as usize).min(max_pages);
+        for pid in 1..=page_count as PageId {
+            self.load_page(pid);
+        }
+        page_count
+    }
+
+    pub fn append(&mut self) -> Page<'_> {
         let next_pid = self.header_page.get_page_count() + 1;
         self.header_page.set_page_count(next_pid);
-        let page_data = [0u8; PAGE_SIZE];
-        self.page_cache.insert(next_pid, page_data);
-        Page::new(next_pid, &mut self.header_page, self.page_cache.get_mut(&next_pid).unwrap())
+        let mut pool = self.pool.lock().unwrap();
+        pool.add_dirty_page();
+        self.dirty_pages.insert(next_pid, Box::new([0u8; PAGE_SIZE]));
+        Page::new(next_pid, &mut self.header_page, PageData{pool, pid: next_pid, frame: None, dirty_pages: &mut self.dirty_pages})
     }
     
     pub fn sync(&mut self) {
         self.records_file.write_at(0, &self.header_page.data);
-        let mut pids = self.page_cache.keys().cloned().collect::<Vec<PageId>>();
-        pids.sort();
-        for pid in pids {
-            let pos = pid * PAGE_SIZE as u64;
-            self.records_file.write_at(pos, self.page_cache.get(&pid).unwrap());
+        let records_file = &mut self.records_file;
+        let mut pids = self.dirty_pages.keys().copied().collect::<Vec<PageId>>();
+        pids.sort_unstable();
+        let dirty_pages = &self.dirty_pages;
+        self.version = self.pool.lock().unwrap().publish(self.file_id, self.version, pids.into_iter().map(|pid| (pid, &*dirty_pages[&pid])), records_file);
+        self.dirty_pages.clear();
+        let fsync = match self.durability {
+            Durability::Always => false,
+            Durability::OnCommit => true,
+            Durability::Periodic(interval) => self.last_fsync.elapsed() >= interval,
+        };
+        if fsync {
+            self.records_file.sync_all();
+            self.last_fsync = Instant::now();
+        }
+    }
+}
+
+impl Drop for Pager {
+    fn drop(&mut self) {
+        if let Ok(mut pool) = self.pool.lock() {
+            pool.discard_dirty_pages(self.dirty_pages.len());
+            pool.close_view(self.file_id, self.version);
         }
     }
 }
\ No newline at end of file
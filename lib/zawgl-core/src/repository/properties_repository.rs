@@ -19,7 +19,13 @@
 // SOFTWARE.
 
 use super::store::*;
+use super::records::SharedBufferPool;
+use std::collections::{BTreeMap, HashSet};
+
 use super::super::model::*;
+use super::super::model::temporal::{Date, DateTime, Duration};
+use super::super::model::init::PropertyCompression;
+use super::super::model::check::StoreCheckReport;
 
 pub struct PropertiesRespository {
     prop_store: properties_store::PropertiesStore,
@@ -36,6 +42,12 @@ fn compute_prop_size(prop: &Property) -> Option<usize> {
         PropertyValue::PInteger(_) => std::mem::size_of::<i64>(),
         PropertyValue::PFloat(_) => std::mem::size_of::<f64>(),
         PropertyValue::PBool(_) => std::mem::size_of::<bool>(),
+        PropertyValue::PList(values) => encode_list(values).len(),
+        PropertyValue::PMap(entries) => encode_map(entries).len(),
+        PropertyValue::PDateTime(_) => std::mem::size_of::<i64>() + std::mem::size_of::<i32>(),
+        PropertyValue::PDate(_) => std::mem::size_of::<i64>(),
+        PropertyValue::PDuration(_) => 3 * std::mem::size_of::<i64>(),
+        PropertyValue::PNull => 0,
     };
     compute_prop_name_size(prop).map(|nsize| nsize + vsize)
 }
@@ -46,6 +58,12 @@ fn map_prop_type(prop: &Property) -> Option<u8> {
         PropertyValue::PInteger(_) => 1,
         PropertyValue::PFloat(_) => 2,
         PropertyValue::PBool(_) => 3,
+        PropertyValue::PList(_) => 4,
+        PropertyValue::PMap(_) => 5,
+        PropertyValue::PDateTime(_) => 6,
+        PropertyValue::PDate(_) => 7,
+        PropertyValue::PDuration(_) => 8,
+        PropertyValue::PNull => 9,
     })
 }
 
@@ -71,6 +89,18 @@ fn make_full_inlined_record(prop: &Property) -> Option<records::PropertyRecord>
                 PropertyValue::PInteger(ival) => block[skip..skip + std::mem::size_of::<i64>()].copy_from_slice(&ival.to_be_bytes()),
                 PropertyValue::PFloat(fval) => block[skip..skip + std::mem::size_of::<f64>()].copy_from_slice(&fval.to_be_bytes()),
                 PropertyValue::PBool(bval) => block[skip + 2] = *bval as u8,
+                PropertyValue::PList(values) => {
+                    let bytes = encode_list(values);
+                    block[skip..skip + bytes.len()].copy_from_slice(&bytes)
+                },
+                PropertyValue::PMap(entries) => {
+                    let bytes = encode_map(entries);
+                    block[skip..skip + bytes.len()].copy_from_slice(&bytes)
+                },
+                other => {
+                    let bytes = encode_value(other).1;
+                    block[skip..skip + bytes.len()].copy_from_slice(&bytes)
+                },
             };
             map_prop_type(prop).map(|ptype| 
                 records::PropertyRecord {
@@ -90,8 +120,12 @@ fn make_full_inlined_record(prop: &Property) -> Option<records::PropertyRecord>
 }
 
 impl PropertiesRespository {
-    pub fn new(props_file: &str, dyn_file: &str) -> Self {
-        PropertiesRespository {prop_store: properties_store::PropertiesStore::new(props_file), dyn_store: dynamic_store::DynamicStore::new(dyn_file)}
+    pub fn new(props_file: &str, dyn_file: &str, pool: &SharedBufferPool) -> Self {
+        PropertiesRespository {prop_store: properties_store::PropertiesStore::new(props_file, pool), dyn_store: dynamic_store::DynamicStore::new(dyn_file, pool)}
+    }
+
+    pub fn set_compression(&mut self, compression: PropertyCompression) {
+        self.dyn_store.set_compression(compression);
     }
 
     pub fn create(&mut self, prop: &mut Property) -> Option<()> {
@@ -140,6 +174,41 @@ impl PropertiesRespository {
         Some(res)
     }
 
+    pub fn delete_list(&mut self, prop_id: u64) -> Option<()> {
+        let mut curr_id = prop_id;
+        while curr_id != 0 {
+            let pr = self.prop_store.load(curr_id)?;
+            self.prop_store.delete(curr_id)?;
+            curr_id = pr.next_prop_id;
+        }
+        Some(())
+    }
+
+    /// Unlinks and deletes the records of the named property, returns the new first record id of the list.
+    pub fn remove_from_list(&mut self, prop_id: u64, name: &str) -> Option<u64> {
+        let mut first_id = prop_id;
+        let mut prev_id = 0;
+        let mut curr_id = prop_id;
+        while curr_id != 0 {
+            let pr = self.prop_store.load(curr_id)?;
+            let next_id = pr.next_prop_id;
+            if self.make_property(&pr)?.get_name() == name {
+                if prev_id == 0 {
+                    first_id = next_id;
+                } else {
+                    let mut prev = self.prop_store.load(prev_id)?;
+                    prev.next_prop_id = next_id;
+                    self.prop_store.save(prev_id, &prev)?;
+                }
+                self.prop_store.delete(curr_id)?;
+            } else {
+                prev_id = curr_id;
+            }
+            curr_id = next_id;
+        }
+        Some(first_id)
+    }
+
     fn make_record(&mut self, prop: &Property) -> Option<records::PropertyRecord> {
         let value_id = 
             match prop.get_value() {
@@ -147,6 +216,9 @@ impl PropertiesRespository {
                 PropertyValue::PInteger(ival) => self.dyn_store.save_data(&ival.to_be_bytes()),
                 PropertyValue::PFloat(fval) => self.dyn_store.save_data(&fval.to_be_bytes()),
                 PropertyValue::PBool(bval) => self.dyn_store.save_data(&[*bval as u8]),
+                PropertyValue::PList(values) => self.dyn_store.save_data(&encode_list(values)),
+                PropertyValue::PMap(entries) => self.dyn_store.save_data(&encode_map(entries)),
+                other => self.dyn_store.save_data(&encode_value(other).1),
             };
         let key_id = self.dyn_store.save_data(&String::from(prop.get_name()).into_bytes());
         value_id.and_then(|v_id| {
@@ -178,6 +250,9 @@ impl PropertiesRespository {
                     PropertyValue::PInteger(ival) => self.dyn_store.save_data(&ival.to_be_bytes()),
                     PropertyValue::PFloat(fval) => self.dyn_store.save_data(&fval.to_be_bytes()),
                     PropertyValue::PBool(bval) => self.dyn_store.save_data(&[*bval as u8]),
+                    PropertyValue::PList(values) => self.dyn_store.save_data(&encode_list(values)),
+                    PropertyValue::PMap(entries) => self.dyn_store.save_data(&encode_map(entries)),
+                    other => self.dyn_store.save_data(&encode_value(other).1),
                 };
 
                 value_id.and_then(|dr_id| {
@@ -212,7 +287,7 @@ impl PropertiesRespository {
             Some(Property::new(name_index.1, extract_value(key_end + 1, pr.prop_type, &pr.prop_block)?))
         } else if pr.key_inlined {
             let name_index = extract_string(&pr.prop_block)?;
-            let value_id = extract_id(&pr.prop_block);
+            let value_id = extract_id(&pr.prop_block[name_index.0 + 1..]);
             let data = self.dyn_store.load_data(value_id)?;
             Some(Property::new_with_id(value_id, name_index.1, extract_value(0, pr.prop_type, &data)?))
         } else {
@@ -231,6 +306,59 @@ impl PropertiesRespository {
         Some(prop)
     }
     
+    pub fn retrieve_all_properties_ids(&mut self) -> Option<Vec<u64>> {
+        self.prop_store.retrieve_all_properties_ids()
+    }
+
+    /// Checks the property records chain of an owner and the dynamic records of its keys and values.
+    pub fn check_list(&mut self, owner: &str, prop_id: u64, referenced: &mut HashSet<u64>, report: &mut StoreCheckReport) {
+        let mut curr_id = prop_id;
+        while curr_id != 0 {
+            if !referenced.insert(curr_id) {
+                report.errors.push(format!("{}: property record {} is already referenced", owner, curr_id));
+                return;
+            }
+            if !self.prop_store.exists(curr_id) {
+                report.errors.push(format!("{}: missing property record {}", owner, curr_id));
+                return;
+            }
+            let pr = match self.prop_store.load(curr_id) {
+                Some(pr) => pr,
+                None => {
+                    report.errors.push(format!("{}: property record {} can't be loaded", owner, curr_id));
+                    return;
+                }
+            };
+            if !pr.in_use {
+                report.errors.push(format!("{}: property record {} is not in use", owner, curr_id));
+            }
+            let mut dyn_ids = Vec::new();
+            if !pr.full_inlined && !pr.key_inlined {
+                dyn_ids.push(pr.key_id);
+                dyn_ids.push(extract_id(&pr.prop_block));
+            } else if pr.key_inlined {
+                match extract_string(&pr.prop_block).filter(|name_index| name_index.0 + 1 + std::mem::size_of::<u64>() <= pr.prop_block.len()) {
+                    Some(name_index) => dyn_ids.push(extract_id(&pr.prop_block[name_index.0 + 1..])),
+                    None => report.errors.push(format!("{}: property record {} has an invalid inlined key", owner, curr_id)),
+                }
+            }
+            let mut valid_chains = true;
+            for dyn_id in dyn_ids {
+                match self.dyn_store.check_chain(dyn_id) {
+                    Ok(nb_records) => report.dynamic_records += nb_records,
+                    Err(err) => {
+                        report.errors.push(format!("{}: property record {}: {}", owner, curr_id, err));
+                        valid_chains = false;
+                    }
+                }
+            }
+            if valid_chains && self.make_property(&pr).is_none() {
+                report.errors.push(format!("{}: property record {} can't be decoded", owner, curr_id));
+            }
+            curr_id = pr.next_prop_id;
+        }
+    }
+
     pub fn sync(&mut self) {
         self.prop_store.sync();
         self.dyn_store.sync();
@@ -268,21 +396,141 @@ fn extract_value(skip: usize, prop_type: u8, data: &[u8]) -> Option<PropertyValu
         Some(PropertyValue::PFloat(f64::from_be_bytes(bytes)))
     } else if prop_type == 3 {
         Some(PropertyValue::PBool(data[skip + 1] > 0))
+    } else if prop_type == 4 {
+        decode_list(&data[skip..]).map(|(values, _)| PropertyValue::PList(values))
+    } else if prop_type == 5 {
+        decode_map(&data[skip..]).map(PropertyValue::PMap)
+    } else if prop_type == 6 {
+        let offset = read_i32(data, skip + std::mem::size_of::<i64>())?;
+        Some(PropertyValue::PDateTime(DateTime::new(read_i64(data, skip)?, offset)))
+    } else if prop_type == 7 {
+        Some(PropertyValue::PDate(Date::new(read_i64(data, skip)?)))
+    } else if prop_type == 8 {
+        let size = std::mem::size_of::<i64>();
+        Some(PropertyValue::PDuration(Duration::new(read_i64(data, skip)?, read_i64(data, skip + size)?, read_i64(data, skip + 2 * size)?)))
+    } else if prop_type == 9 {
+        Some(PropertyValue::PNull)
     } else {
         None
     }
 }
 
+pub(crate) fn encode_value(value: &PropertyValue) -> (u8, Vec<u8>) {
+    match value {
+        PropertyValue::PString(sval) => (0, sval.clone().into_bytes()),
+        PropertyValue::PInteger(ival) => (1, ival.to_be_bytes().to_vec()),
+        PropertyValue::PFloat(fval) => (2, fval.to_be_bytes().to_vec()),
+        PropertyValue::PBool(bval) => (3, vec![*bval as u8]),
+        PropertyValue::PList(values) => (4, encode_list(values)),
+        PropertyValue::PMap(entries) => (5, encode_map(entries)),
+        PropertyValue::PDateTime(dt) => (6, [dt.epoch_nanos.to_be_bytes().to_vec(), dt.offset_seconds.to_be_bytes().to_vec()].concat()),
+        PropertyValue::PDate(date) => (7, date.epoch_days.to_be_bytes().to_vec()),
+        PropertyValue::PDuration(duration) => (8, [duration.months.to_be_bytes(), duration.days.to_be_bytes(), duration.nanos.to_be_bytes()].concat()),
+        PropertyValue::PNull => (9, Vec::new()),
+    }
+}
+
+/// Lists are encoded as an element count followed by the type, size and bytes of each element.
+fn encode_list(values: &Vec<PropertyValue>) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(&(values.len() as u32).to_be_bytes());
+    for value in values {
+        let (value_type, value_bytes) = encode_value(value);
+        bytes.push(value_type);
+        bytes.extend_from_slice(&(value_bytes.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(&value_bytes);
+    }
+    bytes
+}
+
+/// Maps are encoded as an entry count followed by the key size and bytes, then the type, size and bytes of each value.
+fn encode_map(entries: &BTreeMap<String, PropertyValue>) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(&(entries.len() as u32).to_be_bytes());
+    for (key, value) in entries {
+        bytes.extend_from_slice(&(key.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(key.as_bytes());
+        let (value_type, value_bytes) = encode_value(value);
+        bytes.push(value_type);
+        bytes.extend_from_slice(&(value_bytes.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(&value_bytes);
+    }
+    bytes
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    let mut bytes = [0u8; std::mem::size_of::<u32>()];
+    bytes.copy_from_slice(data.get(offset..offset + std::mem::size_of::<u32>())?);
+    Some(u32::from_be_bytes(bytes))
+}
+
+fn read_i32(data: &[u8], offset: usize) -> Option<i32> {
+    let mut bytes = [0u8; std::mem::size_of::<i32>()];
+    bytes.copy_from_slice(data.get(offset..offset + std::mem::size_of::<i32>())?);
+    Some(i32::from_be_bytes(bytes))
+}
+
+fn read_i64(data: &[u8], offset: usize) -> Option<i64> {
+    let mut bytes = [0u8; std::mem::size_of::<i64>()];
+    bytes.copy_from_slice(data.get(offset..offset + std::mem::size_of::<i64>())?);
+    Some(i64::from_be_bytes(bytes))
+}
+
+fn decode_list(data: &[u8]) -> Option<(Vec<PropertyValue>, usize)> {
+    let count = read_u32(data, 0)?;
+    let mut offset = std::mem::size_of::<u32>();
+    let mut values = Vec::new();
+    for _ in 0..count {
+        let value_type = *data.get(offset)?;
+        let size = read_u32(data, offset + 1)? as usize;
+        offset += 1 + std::mem::size_of::<u32>();
+        let value_data = data.get(offset..offset + size)?;
+        values.push(decode_element(value_type, value_data)?);
+        offset += size;
+    }
+    Some((values, offset))
+}
+
+fn decode_map(data: &[u8]) -> Option<BTreeMap<String, PropertyValue>> {
+    let count = read_u32(data, 0)?;
+    let mut offset = std::mem::size_of::<u32>();
+    let mut entries = BTreeMap::new();
+    for _ in 0..count {
+        let key_size = read_u32(data, offset)? as usize;
+        offset += std::mem::size_of::<u32>();
+        let key = String::from_utf8(data.get(offset..offset + key_size)?.to_vec()).ok()?;
+        offset += key_size;
+        let value_type = *data.get(offset)?;
+        let size = read_u32(data, offset + 1)? as usize;
+        offset += 1 + std::mem::size_of::<u32>();
+        let value_data = data.get(offset..offset + size)?;
+        entries.insert(key, decode_element(value_type, value_data)?);
+        offset += size;
+    }
+    Some(entries)
+}
+
+pub(crate) fn decode_element(value_type: u8, value_data: &[u8]) -> Option<PropertyValue> {
+    match value_type {
+        0 => Some(PropertyValue::PString(String::from_utf8(value_data.to_vec()).ok()?)),
+        3 => Some(PropertyValue::PBool(*value_data.first()? > 0)),
+        4 => Some(PropertyValue::PList(decode_list(value_data)?.0)),
+        5 => Some(PropertyValue::PMap(decode_map(value_data)?)),
+        _ => extract_value(0, value_type, value_data),
+    }
+}
+
 
 #[cfg(test)]
 mod test_prop_repo {
     use super::*;
+    use super::super::records::BufferPool;
     use super::super::super::test_utils::*;
     #[test]
     fn test_save_load_0() {
         let dyn_file = build_file_path_and_rm_old("test_save_load_0", "dyn.db").unwrap();
         let prop_file = build_file_path_and_rm_old("test_save_load_0", "prop.db").unwrap();
-        let mut pr = PropertiesRespository::new(&prop_file, &dyn_file);
+        let mut pr = PropertiesRespository::new(&prop_file, &dyn_file, &BufferPool::new_shared(TEST_POOL_CAPACITY));
         let mut prop = Property::new(String::from("qsfsqdf"), PropertyValue::PString(String::from("qgkfdgsdf")));
         pr.create(&mut prop);
         let load = pr.load(prop.get_id().unwrap()).unwrap();
@@ -294,7 +542,7 @@ mod test_prop_repo {
     fn test_save_load_1() {
         let dyn_file = build_file_path_and_rm_old("test_save_load_1", "dyn.db").unwrap();
         let prop_file = build_file_path_and_rm_old("test_save_load_1", "prop.db").unwrap();
-        let mut pr = PropertiesRespository::new(&prop_file, &dyn_file);
+        let mut pr = PropertiesRespository::new(&prop_file, &dyn_file, &BufferPool::new_shared(TEST_POOL_CAPACITY));
         let mut prop = Property::new(String::from("qsfsqdfqsdfq"), PropertyValue::PString(String::from("qgkfdgsdf")));
         pr.create(&mut prop);
         let load = pr.load(prop.get_id().unwrap()).unwrap();
@@ -306,7 +554,7 @@ mod test_prop_repo {
     fn test_save_load_2() {
         let dyn_file = build_file_path_and_rm_old("test_save_load_2", "dyn.db").unwrap();
         let props_file = build_file_path_and_rm_old("test_save_load_2", "prop.db").unwrap();
-        let mut pr = PropertiesRespository::new(&props_file, &dyn_file);
+        let mut pr = PropertiesRespository::new(&props_file, &dyn_file, &BufferPool::new_shared(TEST_POOL_CAPACITY));
         let mut prop = Property::new(String::from("qsfsqdfqsdfqdhgfdhgdfhgdfhqzerqzerqzregdfqsfdqsfderhryjsrrefqzeqgdsfdfsdrrdsredfsqer"),
         PropertyValue::PString(String::from("qgkfdgsdfqerqzerqzerqzerqzerqzerqzerarthdtrsdqeqtrshsreqsgstreq")));
         pr.create(&mut prop);
@@ -314,4 +562,106 @@ mod test_prop_repo {
         assert_eq!(load.get_name(), prop.get_name());
         assert_eq!(load.get_value(), prop.get_value());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_save_load_list() {
+        let dyn_file = build_file_path_and_rm_old("test_save_load_list", "dyn.db").unwrap();
+        let props_file = build_file_path_and_rm_old("test_save_load_list", "prop.db").unwrap();
+        let mut pr = PropertiesRespository::new(&props_file, &dyn_file, &BufferPool::new_shared(TEST_POOL_CAPACITY));
+        let nested = PropertyValue::PList(vec![PropertyValue::PInteger(7), PropertyValue::PBool(true)]);
+        let mut short = Property::new(String::from("a"), PropertyValue::PList(vec![PropertyValue::PInteger(1)]));
+        let mut long = Property::new(String::from("aliases"), PropertyValue::PList(vec![
+            PropertyValue::PString(String::from("marko")), PropertyValue::PString(String::from("marko rodriguez")),
+            PropertyValue::PFloat(1.5), nested]));
+        let mut props = vec![short.clone(), long.clone()];
+        let list_id = pr.create_list(&mut props).unwrap();
+        pr.create(&mut short);
+        pr.create(&mut long);
+        let loaded = pr.retrieve_list(list_id).unwrap();
+        assert_eq!(props[0].get_value(), loaded[0].get_value());
+        assert_eq!(format!("{:?}", props[1].get_value()), format!("{:?}", loaded[1].get_value()));
+        assert_eq!(short.get_value(), pr.load(short.get_id().unwrap()).unwrap().get_value());
+    }
+
+    #[test]
+    fn test_save_load_map() {
+        let dyn_file = build_file_path_and_rm_old("test_save_load_map", "dyn.db").unwrap();
+        let props_file = build_file_path_and_rm_old("test_save_load_map", "prop.db").unwrap();
+        let mut pr = PropertiesRespository::new(&props_file, &dyn_file, &BufferPool::new_shared(TEST_POOL_CAPACITY));
+        let mut address = BTreeMap::new();
+        address.insert(String::from("city"), PropertyValue::PString(String::from("Santa Fe")));
+        address.insert(String::from("zip"), PropertyValue::PInteger(87501));
+        let mut entries = BTreeMap::new();
+        entries.insert(String::from("address"), PropertyValue::PMap(address));
+        entries.insert(String::from("tags"), PropertyValue::PList(vec![PropertyValue::PString(String::from("a")), PropertyValue::PBool(false)]));
+        entries.insert(String::from("active"), PropertyValue::PBool(true));
+        let mut small = BTreeMap::new();
+        small.insert(String::from("a"), PropertyValue::PBool(true));
+        let mut short = Property::new(String::from("m"), PropertyValue::PMap(small));
+        let mut long = Property::new(String::from("profile"), PropertyValue::PMap(entries));
+        pr.create(&mut short);
+        pr.create(&mut long);
+        assert_eq!(short.get_value(), pr.load(short.get_id().unwrap()).unwrap().get_value());
+        assert_eq!(long.get_value(), pr.load(long.get_id().unwrap()).unwrap().get_value());
+    }
+
+    #[test]
+    fn test_save_load_temporal() {
+        let dyn_file = build_file_path_and_rm_old("test_save_load_temporal", "dyn.db").unwrap();
+        let props_file = build_file_path_and_rm_old("test_save_load_temporal", "prop.db").unwrap();
+        let mut pr = PropertiesRespository::new(&props_file, &dyn_file, &BufferPool::new_shared(TEST_POOL_CAPACITY));
+        let mut props = vec![
+            Property::new(String::from("at"), PropertyValue::PDateTime(DateTime::new(1437511232142000000, 3600))),
+            Property::new(String::from("birthday"), PropertyValue::PDate(Date::new(16637))),
+            Property::new(String::from("d"), PropertyValue::PDuration(Duration::new(14, 10, 9_015_500_000_000))),
+            Property::new(String::from("a_long_property_name_for_durations"), PropertyValue::PDuration(Duration::new(-1, 0, 5))),
+        ];
+        let list_id = pr.create_list(&mut props).unwrap();
+        let loaded = pr.retrieve_list(list_id).unwrap();
+        for (prop, load) in props.iter().zip(loaded.iter()) {
+            assert_eq!(format!("{:?}", prop.get_value()), format!("{:?}", load.get_value()));
+        }
+    }
+
+    #[test]
+    fn test_save_load_null() {
+        let dyn_file = build_file_path_and_rm_old("test_save_load_null", "dyn.db").unwrap();
+        let props_file = build_file_path_and_rm_old("test_save_load_null", "prop.db").unwrap();
+        let mut pr = PropertiesRespository::new(&props_file, &dyn_file, &BufferPool::new_shared(TEST_POOL_CAPACITY));
+        let mut props = vec![
+            Property::new(String::from("n"), PropertyValue::PNull),
+            Property::new(String::from("values"), PropertyValue::PList(vec![PropertyValue::PInteger(1), PropertyValue::PNull])),
+            Property::new(String::from("a_property_name_longer_than_a_block"), PropertyValue::PNull),
+        ];
+        let list_id = pr.create_list(&mut props).unwrap();
+        let loaded = pr.retrieve_list(list_id).unwrap();
+        assert_eq!(props.iter().map(|p| p.get_value().clone()).collect::<Vec<PropertyValue>>(), loaded.iter().map(|p| p.get_value().clone()).collect::<Vec<PropertyValue>>());
+    }
+
+    #[test]
+    fn test_check_list() {
+        let dyn_file = build_file_path_and_rm_old("test_check_list", "dyn.db").unwrap();
+        let props_file = build_file_path_and_rm_old("test_check_list", "prop.db").unwrap();
+        let mut pr = PropertiesRespository::new(&props_file, &dyn_file, &BufferPool::new_shared(TEST_POOL_CAPACITY));
+        let mut props = vec![
+            Property::new(String::from("inlined"), PropertyValue::PInteger(1)),
+            Property::new(String::from("key"), PropertyValue::PString("a long value ".repeat(20))),
+            Property::new("a long key ".repeat(5), PropertyValue::PString(String::from("value"))),
+        ];
+        let id = pr.create_list(&mut props).unwrap();
+
+        let mut referenced = HashSet::new();
+        let mut report = StoreCheckReport::default();
+        pr.check_list("node 1", id, &mut referenced, &mut report);
+        assert!(report.is_consistent(), "{:?}", report.errors);
+        assert_eq!(3, referenced.len());
+        assert_eq!(5, report.dynamic_records);
+
+        pr.check_list("node 2", id, &mut referenced, &mut report);
+        assert_eq!(vec![format!("node 2: property record {} is already referenced", id)], report.errors);
+
+        let mut report = StoreCheckReport::default();
+        pr.check_list("node 3", 1000, &mut HashSet::new(), &mut report);
+        assert_eq!(vec![String::from("node 3: missing property record 1000")], report.errors);
+    }
+}
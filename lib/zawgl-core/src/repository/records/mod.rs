@@ -18,9 +18,13 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
+pub mod partitions;
+
 use super::super::buf_config::*;
+use super::super::model::init::IdAllocation;
 
 use super::pager::*;
+pub use super::pager::buffer_pool::{BufferPool, SharedBufferPool};
 
 pub type RecordId = u64;
 pub type PageRecordId = usize;
@@ -46,6 +50,7 @@ pub struct RecordsManager {
     record_size: usize,
     nb_records_per_page: usize,
     page_map: PageMap,
+    id_allocation: IdAllocation,
 }
 
 pub struct HeaderPageWrapper<'a> {
@@ -104,6 +109,17 @@ impl <'a> HeaderPageWrapper<'a> {
         let bounds = self.page_map.header_page_records_version_counter;
         self.get_header_slice_mut(bounds).copy_from_slice(&id.to_be_bytes());
     }
+
+    fn get_header_high_water_mark(&self) -> RecordId {
+        let mut bytes = [0u8; RECORDS_COUNTER_SIZE];
+        bytes.copy_from_slice(self.get_header_slice_ref(self.page_map.header_page_high_water_mark));
+        u64::from_be_bytes(bytes)
+    }
+
+    fn set_header_high_water_mark(&'a mut self, id: RecordId) {
+        let bounds = self.page_map.header_page_high_water_mark;
+        self.get_header_slice_mut(bounds).copy_from_slice(&id.to_be_bytes());
+    }
 }
 
 struct RecordPageWrapper<'a> {
@@ -133,7 +149,7 @@ impl <'a> RecordPageWrapper<'a> {
         u64::from_be_bytes(bytes)
     }
     fn set_free_next_page_ptr(&mut self, id: u64) {
-        let bounds = self.page_map.free_list_len;
+        let bounds = self.page_map.next_free_page_ptr;
         self.get_slice_mut(bounds).copy_from_slice(&id.to_be_bytes());
     }
     fn get_slice_ref(&self, bounds: Bounds) -> &[u8] {
@@ -183,17 +199,30 @@ impl <'a> RecordPageWrapper<'a> {
         }
         
     }
-    fn append_free_list_item(&mut self, page_record_id: PageRecordId) {
-        let mut free_records = self.get_page_free_list();
-        free_records.push(page_record_id);
-        free_records.sort();
+    fn set_page_free_list(&mut self, free_records: Vec<PageRecordId>) {
         self.set_free_list_len(free_records.len());
-        let mut bounds = self.page_map.free_list.shift(FREE_LIST_PTR_SIZE);
+        let mut bounds = self.page_map.free_list.sub(0, FREE_LIST_PTR_SIZE);
         for free_rec_id in free_records {
             self.get_slice_mut(bounds).copy_from_slice(&(free_rec_id as u32).to_be_bytes());
             bounds = bounds.shift(FREE_LIST_PTR_SIZE);
         }
     }
+    fn append_free_list_item(&mut self, page_record_id: PageRecordId) {
+        let mut free_records = self.get_page_free_list();
+        free_records.push(page_record_id);
+        free_records.sort();
+        self.set_page_free_list(free_records);
+    }
+    fn remove_free_list_item(&mut self, page_record_id: PageRecordId) -> bool {
+        let mut free_records = self.get_page_free_list();
+        let free_list_len = free_records.len();
+        free_records.retain(|free_rec_id| *free_rec_id != page_record_id);
+        if free_records.len() == free_list_len {
+            return false;
+        }
+        self.set_page_free_list(free_records);
+        true
+    }
     fn is_page_free_list_empty(&self) -> bool {
         self.get_free_list_len() == 0
     }
@@ -240,6 +269,7 @@ struct PageMap {
     header_page_free_list_ptr: Bounds,
     header_page_records_counter: Bounds,
     header_page_records_version_counter: Bounds,
+    header_page_high_water_mark: Bounds,
     header_page_payload: Bounds,
 }
 
@@ -269,7 +299,8 @@ fn compute_page_map(nb_records_per_page: usize, nb_pages_per_record: usize) -> P
     let header_page_free_list_ptr_bounds = Bounds::new(PAGE_COUNTER_SIZE, PAGE_COUNTER_SIZE + FIRST_FREE_PAGE_PTR);
     let header_page_records_counter_bounds = header_page_free_list_ptr_bounds.shift(RECORDS_COUNTER_SIZE);
     let header_page_records_version_counter_bounds = header_page_records_counter_bounds.shift(RECORDS_COUNTER_SIZE);
-    let header_page_payload_bounds = Bounds::new(header_page_records_counter_bounds.end, PAGE_SIZE);
+    let header_page_high_water_mark_bounds = Bounds::new(PAGE_SIZE - RECORDS_COUNTER_SIZE, PAGE_SIZE);
+    let header_page_payload_bounds = Bounds::new(header_page_records_counter_bounds.end, header_page_high_water_mark_bounds.begin);
     PageMap{
         header_flags: header_flags_bounds,
         next_free_page_ptr: next_free_page_ptr_bounds,
@@ -283,6 +314,7 @@ fn compute_page_map(nb_records_per_page: usize, nb_pages_per_record: usize) -> P
         header_page_free_list_ptr: header_page_free_list_ptr_bounds,
         header_page_records_counter: header_page_records_counter_bounds,
         header_page_records_version_counter: header_page_records_version_counter_bounds,
+        header_page_high_water_mark: header_page_high_water_mark_bounds,
         header_page_payload: header_page_payload_bounds,
     }
 }
@@ -304,8 +336,47 @@ fn copy_buffer_to_payload(payload: &mut [u8], data: &[u8]) {
 }
 
 impl RecordsManager {
-    pub fn new(file: &str, record_size: usize, nb_records_per_page: usize, nb_pages_per_record: usize) -> Self {
-        RecordsManager{pager: Pager::new(file), record_size: record_size, nb_records_per_page: nb_records_per_page, page_map: compute_page_map(nb_records_per_page, nb_pages_per_record)}
+    pub fn new(file: &str, pool: &SharedBufferPool, record_size: usize, nb_records_per_page: usize, nb_pages_per_record: usize) -> Self {
+        RecordsManager{pager: Pager::new(file, pool), record_size, nb_records_per_page, page_map: compute_page_map(nb_records_per_page, nb_pages_per_record), id_allocation: IdAllocation::default()}
+    }
+
+    /// Sets how the ids of the records created afterwards are allocated, records spanning several pages always recycle their ids.
+    pub fn set_id_allocation(&mut self, id_allocation: IdAllocation) {
+        self.id_allocation = id_allocation;
+    }
+
+    /// The highest id allocated in the file, files created before it was saved in the header have it computed from their pages.
+    pub fn get_high_water_mark(&mut self) -> RecordsManagerResult<RecordId> {
+        let high_water_mark = self.get_header_page_wrapper().get_header_high_water_mark();
+        if high_water_mark > 0 || self.is_empty() {
+            return Ok(high_water_mark);
+        }
+        let last_record_id = self.find_last_record_id()?;
+        self.get_header_page_wrapper().set_header_high_water_mark(last_record_id);
+        Ok(last_record_id)
+    }
+
+    fn find_last_record_id(&mut self) -> RecordsManagerResult<RecordId> {
+        let page_count = self.get_header_page_wrapper().header_page.get_page_count();
+        if self.page_map.is_multi_page_record {
+            return Ok(page_count / self.page_map.nb_pages_per_record as u64);
+        }
+        let nb_records_per_page = self.page_map.nb_records_per_page;
+        for pid in (1..=page_count).rev() {
+            let rpage = self.load_page_wrapper(pid).ok_or(RecordsManagerError::NotFound)?;
+            let free_list = rpage.get_page_free_list();
+            if let Some(page_record_id) = (0..nb_records_per_page).rev().find(|page_record_id| !free_list.contains(page_record_id)) {
+                return Ok((pid - 1) * nb_records_per_page as u64 + page_record_id as u64 + 1);
+            }
+        }
+        Ok(0)
+    }
+
+    /// Changes the size of the records of the file, before any of them is read or written.
+    pub fn set_record_layout(&mut self, record_size: usize, nb_records_per_page: usize, nb_pages_per_record: usize) {
+        self.record_size = record_size;
+        self.nb_records_per_page = nb_records_per_page;
+        self.page_map = compute_page_map(nb_records_per_page, nb_pages_per_record);
     }
 
     fn compute_location(&self, record_id: u64) -> RecordLocation {
@@ -365,18 +436,12 @@ impl RecordsManager {
         let record_size = self.record_size;
         if location.is_multi_pages_record {
             let mut wrapper = self.load_page_wrapper(location.page_id).ok_or(RecordsManagerError::NotFound)?;
-            let next_free_page_ptr = wrapper.get_free_next_page_ptr();
-            wrapper.get_header_page_wrapper().set_header_first_free_page_ptr(next_free_page_ptr);
             for page_count in 0..nb_pages_per_record {
                 copy_buffer_to_payload(wrapper.get_slice_mut(payload_bounds), &data[page_count*payload_bounds.len()..]);
             }
         } else {
             let mut wrapper = self.load_page_wrapper(location.page_id).ok_or(RecordsManagerError::NotFound)?;
             wrapper.get_slice_mut(payload_bounds.sub(location.record_id_in_page * record_size, record_size)).copy_from_slice(&data);
-            if wrapper.is_page_free_list_empty() {
-                let next_free_page_ptr = wrapper.get_free_next_page_ptr();
-                wrapper.get_header_page_wrapper().set_header_first_free_page_ptr(next_free_page_ptr);
-            }
         }
         self.increment_records_version_counter();
         Ok(())
@@ -401,6 +466,68 @@ impl RecordsManager {
     }
 
     pub fn create(&mut self, data: &[u8]) -> RecordsManagerResult<RecordId> {
+        let record_id = match self.id_allocation {
+            IdAllocation::Sequential if !self.page_map.is_multi_page_record => self.create_sequential(data)?,
+            _ => self.create_recycled(data)?,
+        };
+        if record_id > self.get_high_water_mark()? {
+            self.get_header_page_wrapper().set_header_high_water_mark(record_id);
+        }
+        self.increment_records_version_counter();
+        self.increment_records_counter();
+        Ok(record_id)
+    }
+
+    /// Saves the record in the slot following the high-water mark, appending pages up to it.
+    fn create_sequential(&mut self, data: &[u8]) -> RecordsManagerResult<RecordId> {
+        let record_id = self.get_high_water_mark()? + 1;
+        let location = self.compute_location(record_id);
+        while self.get_header_page_wrapper().header_page.get_page_count() < location.page_id {
+            let new_page = self.pager.append();
+            let mut wrapper = RecordPageWrapper::new(new_page, self.page_map);
+            wrapper.init_page_free_list();
+            let first_free_page_ptr = wrapper.get_header_page_wrapper().get_header_first_free_page_ptr();
+            wrapper.set_free_next_page_ptr(first_free_page_ptr);
+            let page_id = wrapper.get_id();
+            wrapper.get_header_page_wrapper().set_header_first_free_page_ptr(page_id);
+        }
+        let payload_bounds = self.page_map.payload;
+        let record_size = self.record_size;
+        let is_page_full = {
+            let mut wrapper = self.load_page_wrapper(location.page_id).ok_or(RecordsManagerError::NotFound)?;
+            if !wrapper.remove_free_list_item(location.record_id_in_page) {
+                return Err(RecordsManagerError::NotFound);
+            }
+            wrapper.get_slice_mut(payload_bounds.sub(location.record_id_in_page * record_size, record_size)).copy_from_slice(data);
+            wrapper.is_page_free_list_empty()
+        };
+        if is_page_full {
+            self.unlink_free_page(location.page_id)?;
+        }
+        Ok(record_id)
+    }
+
+    /// Removes a page without free records from the chain of pages having some.
+    fn unlink_free_page(&mut self, page_id: PageId) -> RecordsManagerResult<()> {
+        let next_free_page_ptr = self.load_page_wrapper(page_id).ok_or(RecordsManagerError::NotFound)?.get_free_next_page_ptr();
+        let mut previous_page_ptr = 0;
+        let mut free_page_ptr = self.get_header_page_wrapper().get_header_first_free_page_ptr();
+        while free_page_ptr != 0 && free_page_ptr != page_id {
+            previous_page_ptr = free_page_ptr;
+            free_page_ptr = self.load_page_wrapper(free_page_ptr).ok_or(RecordsManagerError::NotFound)?.get_free_next_page_ptr();
+        }
+        if free_page_ptr == 0 {
+            return Ok(());
+        }
+        if previous_page_ptr == 0 {
+            self.get_header_page_wrapper().set_header_first_free_page_ptr(next_free_page_ptr);
+        } else {
+            self.load_page_wrapper(previous_page_ptr).ok_or(RecordsManagerError::NotFound)?.set_free_next_page_ptr(next_free_page_ptr);
+        }
+        Ok(())
+    }
+
+    fn create_recycled(&mut self, data: &[u8]) -> RecordsManagerResult<RecordId> {
         let record_size = self.record_size;
         let nb_pages_per_record = self.page_map.nb_pages_per_record;
         let nb_records_per_page = self.page_map.nb_records_per_page;
@@ -463,8 +590,6 @@ impl RecordsManager {
                 
             }
         }
-        self.increment_records_version_counter();
-        self.increment_records_counter();
         Ok(record_id + 1)
     }
 
@@ -486,12 +611,15 @@ impl RecordsManager {
     }
 
     pub fn delete(&mut self, id: RecordId) -> RecordsManagerResult<()> {
-        let free_list_capacity = self.page_map.free_list_capacity;
         let loc = self.compute_location(id);
         let mut rpage = self.load_page_wrapper(loc.page_id).ok_or(RecordsManagerError::NotFound)?;
         let mut append_page_to_free_list = true;
         if !loc.is_multi_pages_record {
-            if rpage.get_free_list_len() != free_list_capacity {
+            let free_list = rpage.get_page_free_list();
+            if free_list.contains(&loc.record_id_in_page) {
+                return Err(RecordsManagerError::NotFound);
+            }
+            if !free_list.is_empty() {
                 append_page_to_free_list = false;
             }
             rpage.append_free_list_item(loc.record_id_in_page);
@@ -505,11 +633,26 @@ impl RecordsManager {
             rpage.set_free_next_page_ptr(first_free_page_ptr);
             rpage.get_header_page_wrapper().set_header_first_free_page_ptr(loc.page_id);
         }
+        drop(rpage);
         self.decrement_records_counter();
         self.increment_records_version_counter();
         Ok(())
     }
 
+    pub fn exists(&mut self, id: RecordId) -> bool {
+        if id == 0 {
+            return false;
+        }
+        let loc = self.compute_location(id);
+        if loc.page_id > self.get_header_page_wrapper().header_page.get_page_count() {
+            return false;
+        }
+        if loc.is_multi_pages_record {
+            return true;
+        }
+        self.load_page_wrapper(loc.page_id).map(|rpage| !rpage.get_page_free_list().contains(&loc.record_id_in_page)).unwrap_or(false)
+    }
+
     pub fn get_payload_len(&self) -> usize {
         self.page_map.payload.len()
     }
@@ -522,6 +665,10 @@ impl RecordsManager {
         self.pager.sync();
     }
 
+    pub fn prefetch(&mut self, max_pages: usize) -> usize {
+        self.pager.prefetch(max_pages)
+    }
+
     pub fn retrieve_all_records_ids(&mut self) -> RecordsManagerResult<Vec<u64>> {
         let header_page_wrapper = self.get_header_page_wrapper();
         let page_count = header_page_wrapper.header_page.get_page_count();
@@ -530,17 +677,37 @@ impl RecordsManager {
         for pid in 0..page_count {
             let rpage = self.load_page_wrapper(pid + 1).ok_or(RecordsManagerError::NotFound)?;
             let free_list = rpage.get_page_free_list();
-            let mut free_list_iter = free_list.iter();
             for page_record_id in 0..nb_records_per_page {
-                if let Some(free_list_item_id) = free_list_iter.next() {
-                    if page_record_id != *free_list_item_id {
-                        res.push((rpage.get_id() - 1) * nb_records_per_page as u64 + page_record_id as u64 + 1);
-                    }
-                } else {
+                if !free_list.contains(&page_record_id) {
                     res.push((rpage.get_id() - 1) * nb_records_per_page as u64 + page_record_id as u64 + 1);
                 }
             }
         }
         Ok(res)
     }
+
+    /// Ids of at most `limit` records greater than `after`, in ascending order.
+    pub fn retrieve_records_ids_after(&mut self, after: RecordId, limit: usize) -> RecordsManagerResult<Vec<u64>> {
+        let header_page_wrapper = self.get_header_page_wrapper();
+        let page_count = header_page_wrapper.header_page.get_page_count();
+        let nb_records_per_page = self.page_map.nb_records_per_page;
+        let mut res = Vec::new();
+        if limit == 0 {
+            return Ok(res);
+        }
+        for pid in after / nb_records_per_page as u64..page_count {
+            let rpage = self.load_page_wrapper(pid + 1).ok_or(RecordsManagerError::NotFound)?;
+            let free_list = rpage.get_page_free_list();
+            for page_record_id in 0..nb_records_per_page {
+                let id = (rpage.get_id() - 1) * nb_records_per_page as u64 + page_record_id as u64 + 1;
+                if id > after && !free_list.contains(&page_record_id) {
+                    res.push(id);
+                    if res.len() == limit {
+                        return Ok(res);
+                    }
+                }
+            }
+        }
+        Ok(res)
+    }
 }
\ No newline at end of file
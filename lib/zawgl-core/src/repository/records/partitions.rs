@@ -0,0 +1,248 @@
+// MIT License
+//
+// Copyright (c) 2022 Alexandre RICCIARDI
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use std::thread;
+use log::warn;
+use super::*;
+use super::super::super::model::init::build_partition_path;
+
+const PARTITIONS_COUNT_SIZE: usize = 8;
+
+/// Records of a store split in partitions with their own file, the record `id` is the record `(id - 1) / n + 1`
+/// of the partition `(id - 1) % n`, `n` being the count of partitions saved in the header of the first one.
+pub struct PartitionedRecordsManager {
+    partitions: Vec<RecordsManager>,
+    next_partition: usize,
+}
+
+fn read_partitions_count(records_manager: &mut RecordsManager) -> u64 {
+    let mut bytes = [0u8; PARTITIONS_COUNT_SIZE];
+    bytes.copy_from_slice(&records_manager.get_header_page_wrapper().get_header_payload_slice_ref()[..PARTITIONS_COUNT_SIZE]);
+    u64::from_be_bytes(bytes)
+}
+
+fn write_partitions_count(records_manager: &mut RecordsManager, count: u64) {
+    records_manager.get_header_page_wrapper().get_header_payload_slice_mut()[..PARTITIONS_COUNT_SIZE].copy_from_slice(&count.to_be_bytes());
+}
+
+impl PartitionedRecordsManager {
+    /// Opens the partitions of a store, a store which already has records keeps the count of partitions it was created with,
+    /// a count of 0 in its header being a single partition.
+    pub fn new(file: &str, nb_partitions: usize, pool: &SharedBufferPool, record_size: usize, nb_records_per_page: usize, nb_pages_per_record: usize) -> Self {
+        let open = |path: &str| RecordsManager::new(path, pool, record_size, nb_records_per_page, nb_pages_per_record);
+        let mut first = open(file);
+        let nb_partitions = match read_partitions_count(&mut first) {
+            0 if first.is_empty() && nb_partitions > 1 => {
+                write_partitions_count(&mut first, nb_partitions as u64);
+                nb_partitions
+            },
+            0 => {
+                if nb_partitions > 1 {
+                    warn!("store {} has records in a single partition, {} partitions are ignored", file, nb_partitions);
+                }
+                1
+            },
+            count => {
+                if count as usize != nb_partitions {
+                    warn!("store {} was created with {} partitions, {} partitions are ignored", file, count, nb_partitions);
+                }
+                count as usize
+            },
+        };
+        let mut partitions = vec![first];
+        for partition in 1..nb_partitions {
+            let path = build_partition_path(file, partition).unwrap_or_else(|| panic!("can't create partition {} of store {}", partition, file));
+            partitions.push(open(&path));
+        }
+        PartitionedRecordsManager{partitions, next_partition: 0}
+    }
+
+    pub fn get_partitions_count(&self) -> usize {
+        self.partitions.len()
+    }
+
+    pub fn get_partition(&self, id: RecordId) -> usize {
+        ((id - 1) % self.partitions.len() as u64) as usize
+    }
+
+    fn to_local_id(&self, id: RecordId) -> RecordId {
+        (id - 1) / self.partitions.len() as u64 + 1
+    }
+
+    fn to_global_id(&self, partition: usize, local_id: RecordId) -> RecordId {
+        (local_id - 1) * self.partitions.len() as u64 + partition as u64 + 1
+    }
+
+    pub fn set_id_allocation(&mut self, id_allocation: IdAllocation) {
+        for partition in &mut self.partitions {
+            partition.set_id_allocation(id_allocation);
+        }
+    }
+
+    pub fn load(&mut self, id: RecordId, data: &mut [u8]) -> RecordsManagerResult<()> {
+        let (partition, local_id) = (self.get_partition(id), self.to_local_id(id));
+        self.partitions[partition].load(local_id, data)
+    }
+
+    pub fn save(&mut self, id: RecordId, data: &[u8]) -> RecordsManagerResult<()> {
+        let (partition, local_id) = (self.get_partition(id), self.to_local_id(id));
+        self.partitions[partition].save(local_id, data)
+    }
+
+    /// Creates the record in the partitions in turn.
+    pub fn create(&mut self, data: &[u8]) -> RecordsManagerResult<RecordId> {
+        let partition = self.next_partition;
+        self.next_partition = (partition + 1) % self.partitions.len();
+        self.create_in_partition(partition, data)
+    }
+
+    /// Creates the record in the partition of the record `id`.
+    pub fn create_near(&mut self, id: RecordId, data: &[u8]) -> RecordsManagerResult<RecordId> {
+        let partition = if id == 0 { 0 } else { self.get_partition(id) };
+        self.create_in_partition(partition, data)
+    }
+
+    fn create_in_partition(&mut self, partition: usize, data: &[u8]) -> RecordsManagerResult<RecordId> {
+        let local_id = self.partitions[partition].create(data)?;
+        Ok(self.to_global_id(partition, local_id))
+    }
+
+    pub fn delete(&mut self, id: RecordId) -> RecordsManagerResult<()> {
+        let (partition, local_id) = (self.get_partition(id), self.to_local_id(id));
+        self.partitions[partition].delete(local_id)
+    }
+
+    pub fn exists(&mut self, id: RecordId) -> bool {
+        id > 0 && {
+            let (partition, local_id) = (self.get_partition(id), self.to_local_id(id));
+            self.partitions[partition].exists(local_id)
+        }
+    }
+
+    /// Syncs the partitions in parallel, each one with its own file.
+    pub fn sync(&mut self) {
+        if let [partition] = self.partitions.as_mut_slice() {
+            return partition.sync();
+        }
+        thread::scope(|scope| {
+            for partition in &mut self.partitions {
+                scope.spawn(move || partition.sync());
+            }
+        });
+    }
+
+    pub fn prefetch(&mut self, max_pages: usize) -> usize {
+        let mut nb_pages = 0;
+        for partition in &mut self.partitions {
+            nb_pages += partition.prefetch(max_pages - nb_pages);
+        }
+        nb_pages
+    }
+
+    pub fn retrieve_all_records_ids(&mut self) -> RecordsManagerResult<Vec<RecordId>> {
+        let mut ids = Vec::new();
+        for partition in 0..self.partitions.len() {
+            let local_ids = self.partitions[partition].retrieve_all_records_ids()?;
+            ids.extend(local_ids.into_iter().map(|local_id| self.to_global_id(partition, local_id)));
+        }
+        ids.sort_unstable();
+        Ok(ids)
+    }
+
+    /// Each partition gives its first records after the last of its ids not above `after`.
+    pub fn retrieve_records_ids_after(&mut self, after: RecordId, limit: usize) -> RecordsManagerResult<Vec<RecordId>> {
+        let nb_partitions = self.partitions.len() as u64;
+        let mut ids = Vec::new();
+        for partition in 0..self.partitions.len() {
+            let local_after = if after > partition as u64 { (after - partition as u64 - 1) / nb_partitions + 1 } else { 0 };
+            let local_ids = self.partitions[partition].retrieve_records_ids_after(local_after, limit)?;
+            ids.extend(local_ids.into_iter().map(|local_id| self.to_global_id(partition, local_id)));
+        }
+        ids.sort_unstable();
+        ids.truncate(limit);
+        Ok(ids)
+    }
+}
+
+#[cfg(test)]
+mod test_partitions {
+    use super::*;
+    use super::super::super::io::segmented_file_access::remove_segmented_file;
+    use super::super::super::super::test_utils::*;
+
+    const RECORD_SIZE: usize = 8;
+
+    fn open(file: &str, nb_partitions: usize, pool: &SharedBufferPool) -> PartitionedRecordsManager {
+        PartitionedRecordsManager::new(file, nb_partitions, pool, RECORD_SIZE, 100, 0)
+    }
+
+    #[test]
+    fn test_partitioned_records() {
+        let file = build_file_path_and_rm_old("test_partitioned_records", "records.db").unwrap();
+        for partition in 1..3 {
+            remove_segmented_file(&build_partition_path(&file, partition).unwrap()).unwrap();
+        }
+        let pool = BufferPool::new_shared(TEST_POOL_CAPACITY);
+        let mut records = open(&file, 3, &pool);
+        let ids = (0..250u64).map(|i| records.create(&i.to_be_bytes()).unwrap()).collect::<Vec<RecordId>>();
+        let mut sorted_ids = ids.clone();
+        sorted_ids.sort_unstable();
+        sorted_ids.dedup();
+        assert_eq!(250, sorted_ids.len());
+        assert_eq!(vec![0, 1, 2], ids[..3].iter().map(|id| records.get_partition(*id)).collect::<Vec<usize>>());
+        let near = records.create_near(ids[1], &1000u64.to_be_bytes()).unwrap();
+        assert_eq!(1, records.get_partition(near));
+        records.delete(ids[7]).unwrap();
+        assert!(!records.exists(ids[7]) && records.exists(ids[8]) && !records.exists(0));
+        records.sync();
+        drop(records);
+
+        let mut records = open(&file, 1, &pool);
+        assert_eq!(3, records.get_partitions_count());
+        let mut data = [0u8; RECORD_SIZE];
+        records.load(ids[249], &mut data).unwrap();
+        assert_eq!(249u64.to_be_bytes(), data);
+        records.load(near, &mut data).unwrap();
+        assert_eq!(1000u64.to_be_bytes(), data);
+        let all_ids = records.retrieve_all_records_ids().unwrap();
+        assert_eq!(250, all_ids.len());
+        assert!(!all_ids.contains(&ids[7]) && all_ids.windows(2).all(|pair| pair[0] < pair[1]));
+        let mut after = 0;
+        let mut paged_ids = Vec::new();
+        while let Some(last) = records.retrieve_records_ids_after(after, 17).unwrap().into_iter().inspect(|id| paged_ids.push(*id)).last() {
+            after = last;
+        }
+        assert_eq!(all_ids, paged_ids);
+    }
+
+    #[test]
+    fn test_single_partition_store_is_kept() {
+        let file = build_file_path_and_rm_old("test_partitioned_records", "single.db").unwrap();
+        let pool = BufferPool::new_shared(TEST_POOL_CAPACITY);
+        let mut records = open(&file, 1, &pool);
+        records.create(&1u64.to_be_bytes()).unwrap();
+        records.sync();
+        drop(records);
+        let records = open(&file, 4, &pool);
+        assert_eq!(1, records.get_partitions_count());
+    }
+}
@@ -0,0 +1,75 @@
+// MIT License
+//
+// Copyright (c) 2022 Alexandre RICCIARDI
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use super::super::super::buf_config::*;
+use super::super::records::*;
+use super::records::*;
+
+pub struct ConstraintsStore {
+    records_manager: RecordsManager,
+}
+
+impl ConstraintsStore {
+    pub fn new(file: &str, pool: &SharedBufferPool) -> Self {
+        ConstraintsStore {records_manager: RecordsManager::new(file, pool, CONSTRAINT_RECORD_SIZE, CONSTRAINT_NB_RECORDS_PER_PAGE, CONSTRAINT_NB_PAGES_PER_RECORD)}
+    }
+    pub fn create(&mut self, constraint: &ConstraintRecord) -> Option<u64> {
+        self.records_manager.create(&constraint.to_bytes()).ok()
+    }
+    pub fn load(&mut self, constraint_id: u64) -> Option<ConstraintRecord> {
+        let mut data: [u8; CONSTRAINT_RECORD_SIZE] = [0; CONSTRAINT_RECORD_SIZE];
+        self.records_manager.load(constraint_id, &mut data).ok()?;
+        Some(ConstraintRecord::from_bytes(data))
+    }
+    pub fn retrieve_all_constraints(&mut self) -> Option<Vec<ConstraintRecord>> {
+        let ids = self.records_manager.retrieve_all_records_ids().ok()?;
+        let mut res = Vec::new();
+        for id in ids {
+            res.push(self.load(id)?);
+        }
+        Some(res)
+    }
+    pub fn sync(&mut self) {
+        self.records_manager.sync();
+    }
+}
+
+#[cfg(test)]
+mod test_constraints_store {
+    use super::*;
+    use super::super::super::super::test_utils::*;
+
+    #[test]
+    fn test_create_constraint() {
+        let file = build_file_path_and_rm_old("test_constraints_store", "test_create_constraint.db").unwrap();
+        {
+            let mut store = ConstraintsStore::new(&file, &BufferPool::new_shared(TEST_POOL_CAPACITY));
+            store.create(&ConstraintRecord{label_id: 12, property_id: 42}).unwrap();
+            store.sync();
+        }
+        let mut store = ConstraintsStore::new(&file, &BufferPool::new_shared(TEST_POOL_CAPACITY));
+        let constraints = store.retrieve_all_constraints().unwrap();
+        assert_eq!(1, constraints.len());
+        assert_eq!(12, constraints[0].label_id);
+        assert_eq!(42, constraints[0].property_id);
+    }
+}
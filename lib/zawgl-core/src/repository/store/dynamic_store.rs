@@ -21,19 +21,54 @@
 use super::super::super::buf_config::*;
 use super::super::records::*;
 use super::records::*;
+use super::super::super::model::init::PropertyCompression;
+use zstd::{bulk, stream};
+use std::collections::HashSet;
+
+const COMPRESSED_LEN_SIZE: usize = 4;
 
 pub struct DynamicStore {
     records_manager: RecordsManager,
+    compression: PropertyCompression,
 }
 
 impl DynamicStore {
-    pub fn new(file: &str) -> Self {
-        DynamicStore {records_manager: RecordsManager::new(file, DYN_RECORD_SIZE, DYN_NB_RECORDS_PER_PAGE, DYN_NB_PAGES_PER_RECORD)}
+    pub fn new(file: &str, pool: &SharedBufferPool) -> Self {
+        DynamicStore {records_manager: RecordsManager::new(file, pool, DYN_RECORD_SIZE, DYN_NB_RECORDS_PER_PAGE, DYN_NB_PAGES_PER_RECORD), compression: PropertyCompression::Disabled}
+    }
+
+    /// Sets the compression of the data saved afterwards, compressed data is flagged in its first record.
+    pub fn set_compression(&mut self, compression: PropertyCompression) {
+        self.compression = compression;
+    }
+
+    fn compress(&self, data: &[u8]) -> Option<Vec<u8>> {
+        match self.compression {
+            PropertyCompression::Zstd{threshold} if data.len() >= threshold => {
+                let frame = bulk::compress(data, zstd::DEFAULT_COMPRESSION_LEVEL).ok()?;
+                if frame.len() + COMPRESSED_LEN_SIZE >= data.len() {
+                    return None;
+                }
+                let mut compressed = Vec::with_capacity(frame.len() + COMPRESSED_LEN_SIZE);
+                compressed.extend_from_slice(&(frame.len() as u32).to_be_bytes());
+                compressed.extend_from_slice(&frame);
+                Some(compressed)
+            },
+            _ => None,
+        }
     }
+
     pub fn create(&mut self, dr: &DynamicStoreRecord) -> Option<u64> {
         self.records_manager.create(&dr_to_bytes(dr)).ok()
     }
     pub fn save_data(&mut self, data: &[u8]) -> Option<u64> {
+        match self.compress(data) {
+            Some(compressed) => self.save_records(&compressed, true),
+            None => self.save_records(data, false),
+        }
+    }
+
+    fn save_records(&mut self, data: &[u8], compressed: bool) -> Option<u64> {
         let mut count = data.len() / 120;
         let rest = data.len() % 120;
         let mut next = 0u64;
@@ -43,6 +78,7 @@ impl DynamicStore {
             let mut dr = DynamicStoreRecord {
                 in_use: true,
                 has_next: has_next,
+                compressed: compressed && count == 0,
                 data: [0u8; 120],
                 next: next
             };
@@ -65,12 +101,20 @@ impl DynamicStore {
         let mut data = Vec::new();
         let mut next = id;
         let mut has_next = true;
+        let mut compressed = None;
         while has_next {
             let dr = self.load(next)?;
+            compressed.get_or_insert(dr.compressed);
             data.extend_from_slice(&dr.data);
             has_next = dr.has_next;
             next = dr.next;
         }
+        if compressed == Some(true) {
+            let mut len = [0u8; COMPRESSED_LEN_SIZE];
+            len.copy_from_slice(data.get(..COMPRESSED_LEN_SIZE)?);
+            let frame = data.get(COMPRESSED_LEN_SIZE..COMPRESSED_LEN_SIZE + u32::from_be_bytes(len) as usize)?;
+            return stream::decode_all(frame).ok().map(|data| data.into_boxed_slice());
+        }
         Some(data.into_boxed_slice())
     }
 
@@ -88,6 +132,45 @@ impl DynamicStore {
         self.records_manager.load(dr_id, &mut data).ok()?;
         Some(dr_from_bytes(data))
     }
+    pub fn exists(&mut self, dr_id: u64) -> bool {
+        self.records_manager.exists(dr_id)
+    }
+
+    /// Frees every record of the data chain starting at `id`.
+    pub fn delete_data(&mut self, id: u64) -> Option<()> {
+        let mut next = id;
+        loop {
+            let dr = self.load(next)?;
+            self.records_manager.delete(next).ok()?;
+            if !dr.has_next {
+                return Some(());
+            }
+            next = dr.next;
+        }
+    }
+
+    /// Follows the records chain of a data id and returns its length, or the first broken link found.
+    pub fn check_chain(&mut self, id: u64) -> Result<usize, String> {
+        let mut visited = HashSet::new();
+        let mut next = id;
+        loop {
+            if !visited.insert(next) {
+                return Err(format!("dynamic records chain {} cycles on record {}", id, next));
+            }
+            if !self.exists(next) {
+                return Err(format!("dynamic records chain {} references missing record {}", id, next));
+            }
+            let dr = self.load(next).ok_or_else(|| format!("dynamic record {} can't be loaded", next))?;
+            if !dr.in_use {
+                return Err(format!("dynamic records chain {} references unused record {}", id, next));
+            }
+            if !dr.has_next {
+                return Ok(visited.len());
+            }
+            next = dr.next;
+        }
+    }
+
     pub fn sync(&mut self) {
         self.records_manager.sync();
     }
@@ -100,7 +183,7 @@ mod test_dyn_store {
     #[test]
     fn test_dyn_short() {
         let file = build_file_path_and_rm_old("test_dyn_store", "test_dyn_short.db").unwrap();
-        let mut ds = DynamicStore::new(&file);
+        let mut ds = DynamicStore::new(&file, &BufferPool::new_shared(TEST_POOL_CAPACITY));
         let short = b"qsdfqsdfqsdf";
         let id = ds.save_data(short).unwrap();
         let data = ds.load_data(id).unwrap();
@@ -110,7 +193,7 @@ mod test_dyn_store {
     #[test]
     fn test_dyn_long() {
         let file = build_file_path_and_rm_old("test_dyn_store", "test_dyn_long.db").unwrap();
-        let mut ds = DynamicStore::new(&file);
+        let mut ds = DynamicStore::new(&file, &BufferPool::new_shared(TEST_POOL_CAPACITY));
         for i in 0..10 {
             let long = ["qsdfqsdfqsdlkqshdfhljbqlcznzelfnqelincqzlnfqzlnec
             qfqsdfqsdfqsdlkqshdfhljbqlcznzelfnqelincqzlnfqzlnecqfqsdfqsdfqsdlkqsh
@@ -120,5 +203,42 @@ mod test_dyn_store {
             let load = ds.load_string(id).unwrap();
             assert_eq!(input, load);
         }
-    }  
-}
\ No newline at end of file
+    }
+
+    #[test]
+    fn test_dyn_compressed() {
+        let file = build_file_path_and_rm_old("test_dyn_store", "test_dyn_compressed.db").unwrap();
+        let mut ds = DynamicStore::new(&file, &BufferPool::new_shared(TEST_POOL_CAPACITY));
+        ds.set_compression(PropertyCompression::Zstd{threshold: 64});
+        let long = "compressed property value ".repeat(100);
+        let id = ds.save_data(long.as_bytes()).unwrap();
+        assert!(ds.load(id).unwrap().compressed);
+        assert!(!ds.load(id).unwrap().has_next);
+        assert_eq!(long, ds.load_string(id).unwrap());
+        let short = "short value";
+        let id = ds.save_data(short.as_bytes()).unwrap();
+        assert!(!ds.load(id).unwrap().compressed);
+        assert_eq!(short, ds.load_string(id).unwrap());
+        ds.set_compression(PropertyCompression::Disabled);
+        let id = ds.save_data(long.as_bytes()).unwrap();
+        assert!(!ds.load(id).unwrap().compressed);
+        assert_eq!(long, ds.load_string(id).unwrap());
+    }
+
+    #[test]
+    fn test_check_chain() {
+        let file = build_file_path_and_rm_old("test_dyn_store", "test_check_chain.db").unwrap();
+        let mut ds = DynamicStore::new(&file, &BufferPool::new_shared(TEST_POOL_CAPACITY));
+        let id = ds.save_data("a long value ".repeat(30).as_bytes()).unwrap();
+        assert_eq!(Ok(4), ds.check_chain(id));
+
+        let mut dr = ds.load(id).unwrap();
+        dr.next = id;
+        ds.records_manager.save(id, &dr_to_bytes(&dr)).unwrap();
+        assert!(ds.check_chain(id).unwrap_err().contains("cycles"));
+
+        dr.next = 1000;
+        ds.records_manager.save(id, &dr_to_bytes(&dr)).unwrap();
+        assert!(ds.check_chain(id).unwrap_err().contains("missing record 1000"));
+    }
+}
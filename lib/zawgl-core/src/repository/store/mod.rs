@@ -22,4 +22,6 @@ pub mod nodes_store;
 pub mod records;
 pub mod relationships_store;
 pub mod dynamic_store;
-pub mod properties_store;
\ No newline at end of file
+pub mod properties_store;
+pub mod constraints_store;
+pub mod statistics_store;
\ No newline at end of file
@@ -20,15 +20,24 @@
 
 use super::super::super::buf_config::*;
 use super::super::records::*;
+use super::super::records::partitions::PartitionedRecordsManager;
+use super::super::super::model::init::IdAllocation;
 use super::records::*;
 
 pub struct NodesStore {
-    records_manager: RecordsManager,
+    records_manager: PartitionedRecordsManager,
 }
 
 impl NodesStore {
-    pub fn new(file: &str) -> Self {
-        NodesStore {records_manager: RecordsManager::new(file, NODE_RECORD_SIZE, NODE_NB_RECORDS_PER_PAGE, NODE_NB_PAGES_PER_RECORD)}
+    pub fn new(file: &str, pool: &SharedBufferPool) -> Self {
+        Self::with_partitions(file, pool, 1)
+    }
+    /// Store split in partitions by node id, nodes are created in the partitions in turn.
+    pub fn with_partitions(file: &str, pool: &SharedBufferPool, nb_partitions: usize) -> Self {
+        NodesStore {records_manager: PartitionedRecordsManager::new(file, nb_partitions, pool, NODE_RECORD_SIZE, NODE_NB_RECORDS_PER_PAGE, NODE_NB_PAGES_PER_RECORD)}
+    }
+    pub fn set_id_allocation(&mut self, id_allocation: IdAllocation) {
+        self.records_manager.set_id_allocation(id_allocation);
     }
     pub fn save(&mut self, id: u64, node: &NodeRecord) -> Option<()> {
         self.records_manager.save(id, &node.to_bytes()).ok()
@@ -41,13 +50,27 @@ impl NodesStore {
         self.records_manager.load(node_id, &mut data).ok()?;
         Some(NodeRecord::from_bytes(data))
     }
+    pub fn delete(&mut self, node_id: u64) -> Option<()> {
+        self.records_manager.delete(node_id).ok()
+    }
+    pub fn exists(&mut self, node_id: u64) -> bool {
+        self.records_manager.exists(node_id)
+    }
     pub fn sync(&mut self) {
         self.records_manager.sync();
     }
 
+    pub fn prefetch(&mut self, max_pages: usize) -> usize {
+        self.records_manager.prefetch(max_pages)
+    }
+
     pub fn retrieve_all_nodes_ids(&mut self) -> Option<Vec<u64>> {
         self.records_manager.retrieve_all_records_ids().ok()
     }
+
+    pub fn retrieve_nodes_ids_after(&mut self, after: u64, limit: usize) -> Option<Vec<u64>> {
+        self.records_manager.retrieve_records_ids_after(after, limit).ok()
+    }
 }
 
 #[cfg(test)]
@@ -58,7 +81,7 @@ mod test_nodes_store {
     #[test]
     fn test_create_node_0() {
         let file = build_file_path_and_rm_old("test_nodes_store", "test_create_node_0.db").unwrap();
-        let mut store = NodesStore::new(&file);
+        let mut store = NodesStore::new(&file, &BufferPool::new_shared(TEST_POOL_CAPACITY));
         let nr = NodeRecord {
             first_inbound_edge: 11287665,
             first_outbound_edge: 87687554,
@@ -75,4 +98,98 @@ mod test_nodes_store {
         assert_eq!(1, rids.len());
         assert_eq!(id, rids[0]);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_delete_node() {
+        let file = build_file_path_and_rm_old("test_nodes_store", "test_delete_node.db").unwrap();
+        let mut store = NodesStore::new(&file, &BufferPool::new_shared(TEST_POOL_CAPACITY));
+        let nr = NodeRecord::new();
+        let id0 = store.create(&nr).unwrap();
+        let id1 = store.create(&nr).unwrap();
+        store.delete(id0).unwrap();
+        assert!(!store.exists(id0));
+        assert!(store.exists(id1));
+        assert!(store.delete(id0).is_none());
+        assert_eq!(vec![id1], store.retrieve_all_nodes_ids().unwrap());
+
+        let id2 = store.create(&nr).unwrap();
+        assert_eq!(id0, id2);
+        assert!(store.exists(id2));
+    }
+
+    #[test]
+    fn test_retrieve_nodes_ids_after() {
+        let file = build_file_path_and_rm_old("test_nodes_store", "test_retrieve_nodes_ids_after.db").unwrap();
+        let mut store = NodesStore::new(&file, &BufferPool::new_shared(TEST_POOL_CAPACITY));
+        let nr = NodeRecord::new();
+        let ids = (0..1000).map(|_| store.create(&nr).unwrap()).collect::<Vec<u64>>();
+        for id in ids.iter().step_by(3) {
+            store.delete(*id).unwrap();
+        }
+        let mut paged = Vec::new();
+        let mut after = 0;
+        loop {
+            let page = store.retrieve_nodes_ids_after(after, 100).unwrap();
+            assert!(page.len() <= 100);
+            match page.last() {
+                Some(last) => after = *last,
+                None => break,
+            }
+            paged.extend(page);
+        }
+        assert_eq!(store.retrieve_all_nodes_ids().unwrap(), paged);
+        assert!(store.retrieve_nodes_ids_after(after, 0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_sequential_id_allocation() {
+        let file = build_file_path_and_rm_old("test_nodes_store", "test_sequential_id_allocation.db").unwrap();
+        let nr = NodeRecord::new();
+        {
+            let mut store = NodesStore::new(&file, &BufferPool::new_shared(TEST_POOL_CAPACITY));
+            store.set_id_allocation(IdAllocation::Sequential);
+            let ids = (0..3).map(|_| store.create(&nr).unwrap()).collect::<Vec<u64>>();
+            assert_eq!(vec![1, 2, 3], ids);
+            store.delete(3).unwrap();
+            store.delete(1).unwrap();
+            assert_eq!(Some(4), store.create(&nr));
+            assert_eq!(vec![2, 4], store.retrieve_all_nodes_ids().unwrap());
+            store.sync();
+        }
+        let mut store = NodesStore::new(&file, &BufferPool::new_shared(TEST_POOL_CAPACITY));
+        store.set_id_allocation(IdAllocation::Sequential);
+        assert_eq!(Some(5), store.create(&nr));
+
+        store.set_id_allocation(IdAllocation::Recycle);
+        let recycled = store.create(&nr).unwrap();
+        assert!(recycled <= NODE_NB_RECORDS_PER_PAGE as u64);
+        assert!(![2, 4, 5].contains(&recycled));
+    }
+
+    #[test]
+    fn test_sequential_id_allocation_over_pages() {
+        let file = build_file_path_and_rm_old("test_nodes_store", "test_sequential_id_allocation_over_pages.db").unwrap();
+        let mut store = NodesStore::new(&file, &BufferPool::new_shared(TEST_POOL_CAPACITY));
+        store.set_id_allocation(IdAllocation::Sequential);
+        let nr = NodeRecord::new();
+        let count = 3 * NODE_NB_RECORDS_PER_PAGE as u64;
+        for id in 1..=count {
+            assert_eq!(Some(id), store.create(&nr));
+        }
+        for id in 1..=count {
+            store.delete(id).unwrap();
+        }
+        assert_eq!(Some(count + 1), store.create(&nr));
+        store.set_id_allocation(IdAllocation::Recycle);
+        let mut recycled = (0..count).map(|_| store.create(&nr).unwrap()).collect::<Vec<u64>>();
+        recycled.sort();
+        recycled.dedup();
+        assert_eq!(count as usize, recycled.len());
+        assert!(!recycled.contains(&(count + 1)));
+        assert!(recycled.iter().all(|id| *id <= 4 * NODE_NB_RECORDS_PER_PAGE as u64));
+
+        store.set_id_allocation(IdAllocation::Sequential);
+        let high_water_mark = recycled.last().map_or(count + 1, |last| (*last).max(count + 1));
+        assert_eq!(Some(high_water_mark + 1), store.create(&nr));
+    }
+}
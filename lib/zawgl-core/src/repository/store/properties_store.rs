@@ -27,8 +27,8 @@ pub struct PropertiesStore {
 }
 
 impl PropertiesStore {
-    pub fn new(file: &str) -> Self {
-        PropertiesStore {records_manager: RecordsManager::new(file, PROPERTY_RECORD_SIZE, PROPERTY_NB_RECORDS_PER_PAGE, PROPERTY_NB_PAGES_PER_RECORD)}
+    pub fn new(file: &str, pool: &SharedBufferPool) -> Self {
+        PropertiesStore {records_manager: RecordsManager::new(file, pool, PROPERTY_RECORD_SIZE, PROPERTY_NB_RECORDS_PER_PAGE, PROPERTY_NB_PAGES_PER_RECORD)}
     }
     pub fn create(&mut self, pr: &PropertyRecord) -> Option<u64> {
         self.records_manager.create(&pr_to_bytes(pr)).ok()
@@ -38,6 +38,19 @@ impl PropertiesStore {
         self.records_manager.load(pr_id, &mut data).ok()?;
         Some(pr_from_bytes(data))
     }
+    pub fn save(&mut self, pr_id: u64, pr: &PropertyRecord) -> Option<()> {
+        self.records_manager.save(pr_id, &pr_to_bytes(pr)).ok()
+    }
+    pub fn delete(&mut self, pr_id: u64) -> Option<()> {
+        self.records_manager.delete(pr_id).ok()
+    }
+    pub fn exists(&mut self, pr_id: u64) -> bool {
+        self.records_manager.exists(pr_id)
+    }
+
+    pub fn retrieve_all_properties_ids(&mut self) -> Option<Vec<u64>> {
+        self.records_manager.retrieve_all_records_ids().ok()
+    }
     pub fn sync(&mut self) {
         self.records_manager.sync();
     }
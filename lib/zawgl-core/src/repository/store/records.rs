@@ -60,6 +60,26 @@ impl NodeRecord {
     }
 }
 
+pub struct ConstraintRecord {
+    pub label_id: u64,
+    pub property_id: u64,
+}
+
+impl ConstraintRecord {
+    pub fn to_bytes(&self) -> [u8; CONSTRAINT_RECORD_SIZE] {
+        let mut bytes: [u8; CONSTRAINT_RECORD_SIZE] = [0; CONSTRAINT_RECORD_SIZE];
+        bytes[0..DYN_ID_SIZE].copy_from_slice(&u64_to_bytes(self.label_id));
+        bytes[DYN_ID_SIZE..2*DYN_ID_SIZE].copy_from_slice(&u64_to_bytes(self.property_id));
+        bytes
+    }
+
+    pub fn from_bytes(bytes: [u8; CONSTRAINT_RECORD_SIZE]) -> ConstraintRecord {
+        let label_id = u64_from_bytes(&bytes[0..DYN_ID_SIZE]);
+        let property_id = u64_from_bytes(&bytes[DYN_ID_SIZE..2*DYN_ID_SIZE]);
+        ConstraintRecord {label_id: label_id, property_id: property_id}
+    }
+}
+
 pub struct RelationshipRecord {
     pub source: u64,
     pub target: u64,
@@ -125,6 +145,7 @@ pub struct PropertyRecord {
 pub struct DynamicStoreRecord {
     pub in_use: bool,
     pub has_next: bool,
+    pub compressed: bool,
     pub next: u64,
     pub data: [u8; 120]
 }
@@ -138,6 +159,9 @@ pub fn dr_to_bytes(dr: &DynamicStoreRecord) -> [u8; 129] {
     if dr.has_next {
         bytes[0] = bytes[0] | 0b0000_1000;
     }
+    if dr.compressed {
+        bytes[0] |= 0b0001_0000;
+    }
     bytes[1..9].clone_from_slice(&u64_to_bytes(dr.next));
     bytes[9..129].clone_from_slice(&dr.data);
     bytes
@@ -146,10 +170,11 @@ pub fn dr_to_bytes(dr: &DynamicStoreRecord) -> [u8; 129] {
 pub fn dr_from_bytes(bytes: [u8; 129]) -> DynamicStoreRecord {
     let in_use = bytes[0] & 0b0000_0001 > 0;
     let has_next = bytes[0] & 0b0000_1000 > 0;
+    let compressed = bytes[0] & 0b0001_0000 > 0;
     let next = u64_from_bytes(&bytes[1..9]);
     let mut data = [0u8; 120];
     data.copy_from_slice(&bytes[9..129]);
-    DynamicStoreRecord {in_use: in_use, has_next: has_next, next: next, data: data}
+    DynamicStoreRecord {in_use, has_next, compressed, next, data}
 }
 
 pub fn pr_to_bytes(pr: &PropertyRecord) -> [u8; 42] {
@@ -222,4 +247,24 @@ mod test_records {
         assert_eq!(rr.relationship_type, 33);
         assert_eq!(rr.next_prop_id, 100);
     }
-}
\ No newline at end of file
+}
+
+pub struct StatisticRecord {
+    pub key_id: u64,
+    pub count: u64,
+}
+
+impl StatisticRecord {
+    pub fn to_bytes(&self) -> [u8; STATISTIC_RECORD_SIZE] {
+        let mut bytes: [u8; STATISTIC_RECORD_SIZE] = [0; STATISTIC_RECORD_SIZE];
+        bytes[0..DYN_ID_SIZE].copy_from_slice(&u64_to_bytes(self.key_id));
+        bytes[DYN_ID_SIZE..DYN_ID_SIZE+STATISTIC_COUNT_SIZE].copy_from_slice(&u64_to_bytes(self.count));
+        bytes
+    }
+
+    pub fn from_bytes(bytes: [u8; STATISTIC_RECORD_SIZE]) -> StatisticRecord {
+        let key_id = u64_from_bytes(&bytes[0..DYN_ID_SIZE]);
+        let count = u64_from_bytes(&bytes[DYN_ID_SIZE..DYN_ID_SIZE+STATISTIC_COUNT_SIZE]);
+        StatisticRecord {key_id: key_id, count: count}
+    }
+}
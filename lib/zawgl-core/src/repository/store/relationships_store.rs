@@ -21,29 +21,52 @@
 use super::records::*;
 use super::super::super::buf_config::*;
 use super::super::records::*;
+use super::super::records::partitions::PartitionedRecordsManager;
+use super::super::super::model::init::IdAllocation;
 
 pub struct RelationshipsStore {
-    records_manager: RecordsManager,
+    records_manager: PartitionedRecordsManager,
 }
 
 impl RelationshipsStore {
-    pub fn new(file: &str) -> Self {
-        RelationshipsStore {records_manager: RecordsManager::new(file, RELATIONSHIP_RECORD_SIZE, RELATIONSHIP_NB_RECORDS_PER_PAGE, RELATIONSHIP_NB_PAGES_PER_RECORD)}
+    pub fn new(file: &str, pool: &SharedBufferPool) -> Self {
+        Self::with_partitions(file, pool, 1)
+    }
+    /// Store split in partitions by source node id, a relationship is created in the partition of its source node.
+    pub fn with_partitions(file: &str, pool: &SharedBufferPool, nb_partitions: usize) -> Self {
+        RelationshipsStore {records_manager: PartitionedRecordsManager::new(file, nb_partitions, pool, RELATIONSHIP_RECORD_SIZE, RELATIONSHIP_NB_RECORDS_PER_PAGE, RELATIONSHIP_NB_PAGES_PER_RECORD)}
+    }
+    pub fn set_id_allocation(&mut self, id_allocation: IdAllocation) {
+        self.records_manager.set_id_allocation(id_allocation);
     }
     pub fn save(&mut self, id: u64, rel: &RelationshipRecord) -> Option<()> {
         self.records_manager.save(id, &rel.to_bytes()).ok()
     }
     pub fn create(&mut self, rel: &RelationshipRecord) -> Option<u64> {
-        self.records_manager.create(&rel.to_bytes()).ok()
+        self.records_manager.create_near(rel.source, &rel.to_bytes()).ok()
     }
     pub fn load(&mut self, rel_id: u64) -> Option<RelationshipRecord> {
         let mut data: [u8; RELATIONSHIP_RECORD_SIZE] = [0; RELATIONSHIP_RECORD_SIZE];
         self.records_manager.load(rel_id, &mut data).ok()?;
         Some(RelationshipRecord::from_bytes(data))
     }
+    pub fn delete(&mut self, rel_id: u64) -> Option<()> {
+        self.records_manager.delete(rel_id).ok()
+    }
+    pub fn exists(&mut self, rel_id: u64) -> bool {
+        self.records_manager.exists(rel_id)
+    }
     pub fn sync(&mut self) {
         self.records_manager.sync();
     }
+
+    pub fn prefetch(&mut self, max_pages: usize) -> usize {
+        self.records_manager.prefetch(max_pages)
+    }
+
+    pub fn retrieve_all_relationships_ids(&mut self) -> Option<Vec<u64>> {
+        self.records_manager.retrieve_all_records_ids().ok()
+    }
 }
 
 
@@ -54,7 +77,7 @@ mod test_relationship_store {
     #[test]
     fn test_create_relationship_0() {
         let file = build_file_path_and_rm_old("test_relationship_store", "test_create_relationship_0.db").unwrap();
-        let mut store = RelationshipsStore::new(&file);
+        let mut store = RelationshipsStore::new(&file, &BufferPool::new_shared(TEST_POOL_CAPACITY));
         let rr = RelationshipRecord {
             source: 45465,
             target: 9871321,
@@ -0,0 +1,79 @@
+// MIT License
+//
+// Copyright (c) 2022 Alexandre RICCIARDI
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use super::super::super::buf_config::*;
+use super::super::records::*;
+use super::records::*;
+
+pub struct StatisticsStore {
+    records_manager: RecordsManager,
+}
+
+impl StatisticsStore {
+    pub fn new(file: &str, pool: &SharedBufferPool) -> Self {
+        StatisticsStore {records_manager: RecordsManager::new(file, pool, STATISTIC_RECORD_SIZE, STATISTIC_NB_RECORDS_PER_PAGE, STATISTIC_NB_PAGES_PER_RECORD)}
+    }
+    pub fn create(&mut self, statistic: &StatisticRecord) -> Option<u64> {
+        self.records_manager.create(&statistic.to_bytes()).ok()
+    }
+    pub fn save(&mut self, statistic_id: u64, statistic: &StatisticRecord) -> Option<()> {
+        self.records_manager.save(statistic_id, &statistic.to_bytes()).ok()
+    }
+    pub fn load(&mut self, statistic_id: u64) -> Option<StatisticRecord> {
+        let mut data: [u8; STATISTIC_RECORD_SIZE] = [0; STATISTIC_RECORD_SIZE];
+        self.records_manager.load(statistic_id, &mut data).ok()?;
+        Some(StatisticRecord::from_bytes(data))
+    }
+    pub fn retrieve_all_statistics(&mut self) -> Option<Vec<(u64, StatisticRecord)>> {
+        let ids = self.records_manager.retrieve_all_records_ids().ok()?;
+        let mut res = Vec::new();
+        for id in ids {
+            res.push((id, self.load(id)?));
+        }
+        Some(res)
+    }
+    pub fn sync(&mut self) {
+        self.records_manager.sync();
+    }
+}
+
+#[cfg(test)]
+mod test_statistics_store {
+    use super::*;
+    use super::super::super::super::test_utils::*;
+
+    #[test]
+    fn test_save_statistic() {
+        let file = build_file_path_and_rm_old("test_statistics_store", "test_save_statistic.db").unwrap();
+        {
+            let mut store = StatisticsStore::new(&file, &BufferPool::new_shared(TEST_POOL_CAPACITY));
+            let id = store.create(&StatisticRecord{key_id: 7, count: 1}).unwrap();
+            store.save(id, &StatisticRecord{key_id: 7, count: 33}).unwrap();
+            store.sync();
+        }
+        let mut store = StatisticsStore::new(&file, &BufferPool::new_shared(TEST_POOL_CAPACITY));
+        let statistics = store.retrieve_all_statistics().unwrap();
+        assert_eq!(1, statistics.len());
+        assert_eq!(7, statistics[0].1.key_id);
+        assert_eq!(33, statistics[0].1.count);
+    }
+}
@@ -26,6 +26,9 @@ const UNIX_TEST_DIR: &str = "/tmp";
 const UNIX_SEPARATOR: &str = "/";
 const WIN_SEPARATOR: &str = "\\";
 
+/// Small page cache used by the stores tests so that pages get evicted.
+pub const TEST_POOL_CAPACITY: usize = 8;
+
 pub fn get_tmp_dir_path(dir_name: &str) -> String {
     let dir;
     if cfg!(target_os = "linux") || cfg!(target_os = "macos") {
@@ -28,6 +28,7 @@ use crate::token::TokenType;
 pub enum AstTag  {
     Create,
     Match,
+    OptionalMatch,
     Node,
     Path,
     Property,
@@ -44,14 +45,47 @@ pub enum AstTag  {
     Item,
     AndOperator,
     OrOperator,
+    NotOperator,
     EqualityOperator,
+    InequalityOperator,
+    LessThanOperator,
+    LessThanOrEqualOperator,
+    GreaterThanOperator,
+    GreaterThanOrEqualOperator,
     ItemPropertyIdentifier,
     Parameter,
+    CreateConstraint,
+    Explain,
+    Profile,
+    List,
+    Map,
+    IsNullOperator,
+    IsNotNullOperator,
+    AddOperator,
+    SubtractOperator,
+    MultiplyOperator,
+    DivideOperator,
+    ModuloOperator,
+    Alias,
+    Distinct,
+    Unwind,
+    Call,
+    Yield,
+    ContainsOperator,
+    StartsWithOperator,
+    EndsWithOperator,
+    Set,
+    Remove,
+    Union,
+    UnionAll,
+    Foreach,
+    PatternPredicate,
 }
 
 pub trait AstVisitor {
     fn enter_create(&mut self, node: &AstTagNode) -> AstVisitorResult<bool>;
     fn enter_match(&mut self, node: &AstTagNode) -> AstVisitorResult<bool>;
+    fn enter_optional_match(&mut self, node: &AstTagNode) -> AstVisitorResult<bool>;
     fn enter_path(&mut self, node: &AstTagNode) -> AstVisitorResult<bool>;
     fn enter_node(&mut self, node: &AstTagNode) -> AstVisitorResult<bool>;
     fn enter_relationship(&mut self, node: &AstTagNode) -> AstVisitorResult<bool>;
@@ -60,6 +94,7 @@ pub trait AstVisitor {
     fn enter_float_value(&mut self, value: Option<f64>) -> AstVisitorResult<bool>;
     fn enter_string_value(&mut self, value: Option<&str>) -> AstVisitorResult<bool>;
     fn enter_bool_value(&mut self, value: Option<bool>) -> AstVisitorResult<bool>;
+    fn enter_null_value(&mut self) -> AstVisitorResult<bool>;
     fn enter_identifier(&mut self, key: &str) -> AstVisitorResult<bool>;
     fn enter_variable(&mut self) -> AstVisitorResult<bool>;
     fn enter_label(&mut self) -> AstVisitorResult<bool>;
@@ -70,8 +105,26 @@ pub trait AstVisitor {
     fn enter_item(&mut self) -> AstVisitorResult<bool>;
     fn enter_where(&mut self, node: &AstTagNode) -> AstVisitorResult<bool>;
     fn enter_parameter(&mut self, name: &str) -> AstVisitorResult<bool>;
+    fn enter_item_property_identifier(&mut self) -> AstVisitorResult<bool>;
+    fn enter_operator(&mut self, node: &AstTagNode) -> AstVisitorResult<bool>;
+    fn enter_create_constraint(&mut self, node: &AstTagNode) -> AstVisitorResult<bool>;
+    fn enter_explain(&mut self) -> AstVisitorResult<bool>;
+    fn enter_profile(&mut self) -> AstVisitorResult<bool>;
+    fn enter_list(&mut self) -> AstVisitorResult<bool>;
+    fn enter_map(&mut self) -> AstVisitorResult<bool>;
+    fn enter_alias(&mut self) -> AstVisitorResult<bool>;
+    fn enter_distinct(&mut self) -> AstVisitorResult<bool>;
+    fn enter_unwind(&mut self, node: &AstTagNode) -> AstVisitorResult<bool>;
+    fn enter_call(&mut self, node: &AstTagNode) -> AstVisitorResult<bool>;
+    fn enter_yield(&mut self) -> AstVisitorResult<bool>;
+    fn enter_set(&mut self, node: &AstTagNode) -> AstVisitorResult<bool>;
+    fn enter_remove(&mut self, node: &AstTagNode) -> AstVisitorResult<bool>;
+    fn enter_union(&mut self, node: &AstTagNode) -> AstVisitorResult<bool>;
+    fn enter_foreach(&mut self, node: &AstTagNode) -> AstVisitorResult<bool>;
+    fn enter_pattern_predicate(&mut self, node: &AstTagNode) -> AstVisitorResult<bool>;
     fn exit_create(&mut self) -> AstVisitorResult<bool>;
     fn exit_match(&mut self) -> AstVisitorResult<bool>;
+    fn exit_optional_match(&mut self) -> AstVisitorResult<bool>;
     fn exit_path(&mut self) -> AstVisitorResult<bool>;
     fn exit_node(&mut self) -> AstVisitorResult<bool>;
     fn exit_relationship(&mut self) -> AstVisitorResult<bool>;
@@ -90,6 +143,23 @@ pub trait AstVisitor {
     fn exit_item(&mut self) -> AstVisitorResult<bool>;
     fn exit_where(&mut self) -> AstVisitorResult<bool>;
     fn exit_parameter(&mut self) -> AstVisitorResult<bool>;
+    fn exit_item_property_identifier(&mut self) -> AstVisitorResult<bool>;
+    fn exit_operator(&mut self, node: &AstTagNode) -> AstVisitorResult<bool>;
+    fn exit_create_constraint(&mut self) -> AstVisitorResult<bool>;
+    fn exit_explain(&mut self) -> AstVisitorResult<bool>;
+    fn exit_profile(&mut self) -> AstVisitorResult<bool>;
+    fn exit_list(&mut self) -> AstVisitorResult<bool>;
+    fn exit_map(&mut self) -> AstVisitorResult<bool>;
+    fn exit_alias(&mut self) -> AstVisitorResult<bool>;
+    fn exit_distinct(&mut self) -> AstVisitorResult<bool>;
+    fn exit_unwind(&mut self) -> AstVisitorResult<bool>;
+    fn exit_call(&mut self) -> AstVisitorResult<bool>;
+    fn exit_yield(&mut self) -> AstVisitorResult<bool>;
+    fn exit_set(&mut self) -> AstVisitorResult<bool>;
+    fn exit_remove(&mut self) -> AstVisitorResult<bool>;
+    fn exit_union(&mut self) -> AstVisitorResult<bool>;
+    fn exit_foreach(&mut self) -> AstVisitorResult<bool>;
+    fn exit_pattern_predicate(&mut self) -> AstVisitorResult<bool>;
 }
 
 #[derive(Debug, Clone)]
@@ -99,7 +169,7 @@ pub enum AstVisitorError {
 
 pub type AstVisitorResult<T> = std::result::Result<T, AstVisitorError>;
 
-pub trait Ast : fmt::Display {
+pub trait Ast : fmt::Display + Send + Sync {
     fn append(&mut self, ast: Box<dyn Ast>);
     fn accept(&self, visitor: &mut dyn AstVisitor) -> AstVisitorResult<bool>;
     fn accept_exit(&self, visitor: &mut dyn AstVisitor) -> AstVisitorResult<bool>;
@@ -145,9 +215,58 @@ impl Ast for AstTagNode {
                     AstTag::Create => {
                         visitor.enter_create(self)
                     },
+                    AstTag::CreateConstraint => {
+                        visitor.enter_create_constraint(self)
+                    },
+                    AstTag::Explain => {
+                        visitor.enter_explain()
+                    },
+                    AstTag::Profile => {
+                        visitor.enter_profile()
+                    },
+                    AstTag::List => {
+                        visitor.enter_list()
+                    },
+                    AstTag::Map => {
+                        visitor.enter_map()
+                    },
+                    AstTag::Alias => {
+                        visitor.enter_alias()
+                    },
+                    AstTag::Distinct => {
+                        visitor.enter_distinct()
+                    },
+                    AstTag::Unwind => {
+                        visitor.enter_unwind(self)
+                    },
+                    AstTag::Call => {
+                        visitor.enter_call(self)
+                    },
+                    AstTag::Yield => {
+                        visitor.enter_yield()
+                    },
+                    AstTag::Set => {
+                        visitor.enter_set(self)
+                    },
+                    AstTag::Remove => {
+                        visitor.enter_remove(self)
+                    },
+                    AstTag::Union |
+                    AstTag::UnionAll => {
+                        visitor.enter_union(self)
+                    },
+                    AstTag::Foreach => {
+                        visitor.enter_foreach(self)
+                    },
+                    AstTag::PatternPredicate => {
+                        visitor.enter_pattern_predicate(self)
+                    },
                     AstTag::Match => {
                         visitor.enter_match(self)
                     },
+                    AstTag::OptionalMatch => {
+                        visitor.enter_optional_match(self)
+                    },
                     AstTag::RelDirectedLR |
                     AstTag::RelDirectedRL |
                     AstTag::RelUndirected => {
@@ -186,6 +305,30 @@ impl Ast for AstTagNode {
                     AstTag::Where => {
                         visitor.enter_where(self)
                     },
+                    AstTag::ItemPropertyIdentifier => {
+                        visitor.enter_item_property_identifier()
+                    },
+                    AstTag::AndOperator |
+                    AstTag::OrOperator |
+                    AstTag::NotOperator |
+                    AstTag::EqualityOperator |
+                    AstTag::InequalityOperator |
+                    AstTag::LessThanOperator |
+                    AstTag::LessThanOrEqualOperator |
+                    AstTag::GreaterThanOperator |
+                    AstTag::GreaterThanOrEqualOperator |
+                    AstTag::IsNullOperator |
+                    AstTag::IsNotNullOperator |
+                    AstTag::AddOperator |
+                    AstTag::SubtractOperator |
+                    AstTag::MultiplyOperator |
+                    AstTag::DivideOperator |
+                    AstTag::ModuloOperator |
+                    AstTag::ContainsOperator |
+                    AstTag::StartsWithOperator |
+                    AstTag::EndsWithOperator => {
+                        visitor.enter_operator(self)
+                    },
                     _ => {
                         Ok(true)
                     }
@@ -204,9 +347,58 @@ impl Ast for AstTagNode {
                     AstTag::Create => {
                         visitor.exit_create()
                     },
+                    AstTag::CreateConstraint => {
+                        visitor.exit_create_constraint()
+                    },
+                    AstTag::Explain => {
+                        visitor.exit_explain()
+                    },
+                    AstTag::Profile => {
+                        visitor.exit_profile()
+                    },
+                    AstTag::List => {
+                        visitor.exit_list()
+                    },
+                    AstTag::Map => {
+                        visitor.exit_map()
+                    },
+                    AstTag::Alias => {
+                        visitor.exit_alias()
+                    },
+                    AstTag::Distinct => {
+                        visitor.exit_distinct()
+                    },
+                    AstTag::Unwind => {
+                        visitor.exit_unwind()
+                    },
+                    AstTag::Call => {
+                        visitor.exit_call()
+                    },
+                    AstTag::Yield => {
+                        visitor.exit_yield()
+                    },
+                    AstTag::Set => {
+                        visitor.exit_set()
+                    },
+                    AstTag::Remove => {
+                        visitor.exit_remove()
+                    },
+                    AstTag::Union |
+                    AstTag::UnionAll => {
+                        visitor.exit_union()
+                    },
+                    AstTag::Foreach => {
+                        visitor.exit_foreach()
+                    },
+                    AstTag::PatternPredicate => {
+                        visitor.exit_pattern_predicate()
+                    },
                     AstTag::Match => {
                         visitor.exit_match()
                     },
+                    AstTag::OptionalMatch => {
+                        visitor.exit_optional_match()
+                    },
                     AstTag::RelDirectedLR |
                     AstTag::RelDirectedRL |
                     AstTag::RelUndirected => {
@@ -247,6 +439,30 @@ impl Ast for AstTagNode {
                     },
                     AstTag::Parameter => {
                         visitor.exit_parameter()
+                    },
+                    AstTag::ItemPropertyIdentifier => {
+                        visitor.exit_item_property_identifier()
+                    },
+                    AstTag::AndOperator |
+                    AstTag::OrOperator |
+                    AstTag::NotOperator |
+                    AstTag::EqualityOperator |
+                    AstTag::InequalityOperator |
+                    AstTag::LessThanOperator |
+                    AstTag::LessThanOrEqualOperator |
+                    AstTag::GreaterThanOperator |
+                    AstTag::GreaterThanOrEqualOperator |
+                    AstTag::IsNullOperator |
+                    AstTag::IsNotNullOperator |
+                    AstTag::AddOperator |
+                    AstTag::SubtractOperator |
+                    AstTag::MultiplyOperator |
+                    AstTag::DivideOperator |
+                    AstTag::ModuloOperator |
+                    AstTag::ContainsOperator |
+                    AstTag::StartsWithOperator |
+                    AstTag::EndsWithOperator => {
+                        visitor.exit_operator(self)
                     }
                     _ => {
                         Ok(true)
@@ -283,10 +499,7 @@ impl Ast for AstTokenNode {
     }
     fn accept(&self, visitor: &mut dyn AstVisitor) -> AstVisitorResult<bool> {
         match self.token_type {
-            TokenType::StringType => {
-                let sval = self.token_value.get(1..self.token_value.len() -1);
-                visitor.enter_string_value(sval)
-            },
+            TokenType::StringType => visitor.enter_string_value(Some(&self.token_value)),
             TokenType::Float => {
                 let res = self.token_value.parse::<f64>().ok();
                 visitor.enter_float_value(res)
@@ -300,6 +513,7 @@ impl Ast for AstTokenNode {
                 let res = self.token_value.parse::<bool>().ok();
                 visitor.enter_bool_value(res)
             },
+            TokenType::Null => visitor.enter_null_value(),
             TokenType::Identifier => visitor.enter_identifier(&self.token_value),
             TokenType::Parameter => visitor.enter_parameter(&self.token_value),
             _ => {
@@ -1,5 +1,6 @@
-use model::WhereClause;
+use model::{WhereClause, ProcedureCall, UpdateItem};
 use zawgl_core::model::PropertyGraph;
+use zawgl_core::model::constraints::UniqueConstraint;
 
 pub mod model;
 pub mod ast;
@@ -26,23 +27,55 @@ pub mod token;
 // LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum StepType {
-    MATCH, CREATE, DELETE, WHERE
+    MATCH, OPTIONAL_MATCH, CREATE, DELETE, WHERE, CREATE_CONSTRAINT, UNWIND, CALL, SET, REMOVE, FOREACH
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExecutionMode {
+    Run, Explain, Profile
+}
+
+#[derive(Clone)]
 pub struct QueryStep {
     pub patterns: Vec<PropertyGraph>,
     pub step_type: StepType,
     pub where_clause: Option<WhereClause>,
+    pub constraint: Option<UniqueConstraint>,
+    /// Downstream steps of an UNWIND or update steps of a FOREACH, built once per element of the list.
+    pub unwind_batches: Vec<Vec<QueryStep>>,
+    pub procedure: Option<ProcedureCall>,
+    pub updates: Vec<UpdateItem>,
 }
 
 impl QueryStep {
     pub fn new(step_type: StepType) -> Self {
-        QueryStep {step_type: step_type, patterns: Vec::new(), where_clause: None }
+        QueryStep {step_type: step_type, patterns: Vec::new(), where_clause: None, constraint: None, unwind_batches: Vec::new(), procedure: None, updates: Vec::new() }
     }
 
     pub fn new_where_clause(where_clause: WhereClause) -> Self {
-        QueryStep {step_type: StepType::WHERE, patterns: Vec::new(), where_clause: Some(where_clause) }
+        QueryStep {step_type: StepType::WHERE, patterns: Vec::new(), where_clause: Some(where_clause), constraint: None, unwind_batches: Vec::new(), procedure: None, updates: Vec::new() }
+    }
+
+    pub fn new_create_constraint(constraint: UniqueConstraint) -> Self {
+        QueryStep {step_type: StepType::CREATE_CONSTRAINT, patterns: Vec::new(), where_clause: None, constraint: Some(constraint), unwind_batches: Vec::new(), procedure: None, updates: Vec::new() }
+    }
+
+    pub fn new_unwind(unwind_batches: Vec<Vec<QueryStep>>) -> Self {
+        QueryStep {step_type: StepType::UNWIND, patterns: Vec::new(), where_clause: None, constraint: None, unwind_batches: unwind_batches, procedure: None, updates: Vec::new() }
+    }
+
+    pub fn new_foreach(update_batches: Vec<Vec<QueryStep>>) -> Self {
+        QueryStep {step_type: StepType::FOREACH, patterns: Vec::new(), where_clause: None, constraint: None, unwind_batches: update_batches, procedure: None, updates: Vec::new() }
+    }
+
+    pub fn new_call(procedure: ProcedureCall) -> Self {
+        QueryStep {step_type: StepType::CALL, patterns: Vec::new(), where_clause: None, constraint: None, unwind_batches: Vec::new(), procedure: Some(procedure), updates: Vec::new() }
+    }
+
+    pub fn new_update(step_type: StepType, updates: Vec<UpdateItem>) -> Self {
+        QueryStep {step_type, patterns: Vec::new(), where_clause: None, constraint: None, unwind_batches: Vec::new(), procedure: None, updates }
     }
 }
 
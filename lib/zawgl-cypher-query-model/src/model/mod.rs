@@ -20,7 +20,9 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-use crate::{ast::Ast, QueryStep};
+use zawgl_core::model::{PropertyGraph, PropertyValue};
+
+use crate::{ast::Ast, QueryStep, ExecutionMode};
 
 pub enum Directive {
     CREATE,
@@ -28,48 +30,256 @@ pub enum Directive {
     DELETE
 }
 
+#[derive(Clone)]
 pub struct FunctionCall {
     pub name: String,
-    pub args: Vec<String>,
+    pub args: Vec<ReturnExpression>,
 }
 
 impl FunctionCall {
     pub fn new(name: &str) -> Self {
         FunctionCall{name: String::from(name), args: Vec::new()}
     }
+
+    pub fn is_aggregation(&self) -> bool {
+        match self.name.to_lowercase().as_str() {
+            "count" | "sum" | "avg" | "min" | "max" | "collect" => true,
+            _ => false,
+        }
+    }
+
+    pub fn get_name(&self) -> String {
+        let args: Vec<String> = self.args.iter().map(|arg| arg.get_name()).collect();
+        format!("{}({})", self.name, args.join(", "))
+    }
+}
+
+/// Call of a registered procedure, yielding all its columns when none are listed.
+#[derive(Clone)]
+pub struct ProcedureCall {
+    pub name: String,
+    pub args: Vec<Expression>,
+    pub yields: Vec<String>,
+}
+
+impl ProcedureCall {
+    pub fn new(name: &str) -> Self {
+        ProcedureCall{name: String::from(name), args: Vec::new(), yields: Vec::new()}
+    }
+}
+
+/// Item of a SET or REMOVE clause, the labels are added by SET and removed by REMOVE.
+#[derive(Debug, Clone, PartialEq)]
+pub enum UpdateItem {
+    Labels(String, Vec<String>),
+    /// Variable and name of a removed property.
+    Property(String, String),
+}
+
+#[derive(Clone)]
+pub struct ItemPropertyName {
+    pub item_name: String,
+    pub property_name: String,
+}
+
+impl ItemPropertyName {
+    pub fn new(item_name: &str, property_name: &str) -> Self {
+        ItemPropertyName{item_name: String::from(item_name), property_name: String::from(property_name)}
+    }
 }
 
+#[derive(Clone)]
 pub enum ReturnExpression {
     FunctionCall(FunctionCall),
     Item(String),
+    ItemPropertyName(ItemPropertyName),
+    Value(PropertyValue),
+    Expression(Box<Expression>),
+    Alias(Box<ReturnExpression>, String),
+}
+
+fn get_value_name(value: &PropertyValue) -> String {
+    match value {
+        PropertyValue::PString(s) => format!("'{}'", s),
+        PropertyValue::PInteger(i) => i.to_string(),
+        PropertyValue::PFloat(f) => f.to_string(),
+        PropertyValue::PBool(b) => b.to_string(),
+        PropertyValue::PList(values) => format!("[{}]", values.iter().map(get_value_name).collect::<Vec<String>>().join(", ")),
+        PropertyValue::PMap(entries) => format!("{{{}}}", entries.iter().map(|(k, v)| format!("{}: {}", k, get_value_name(v))).collect::<Vec<String>>().join(", ")),
+        PropertyValue::PDateTime(dt) => dt.to_string(),
+        PropertyValue::PDate(date) => date.to_string(),
+        PropertyValue::PDuration(duration) => duration.to_string(),
+        PropertyValue::PNull => String::from("null"),
+    }
+}
+
+fn get_element_name(var: &Option<String>, labels: &[String], separator: &str) -> String {
+    let labels = labels.iter().map(|label| format!(":{}", label)).collect::<Vec<String>>().join(separator);
+    format!("{}{}", var.as_deref().unwrap_or_default(), labels)
+}
+
+fn get_pattern_name(pattern: &PropertyGraph) -> String {
+    let node_name = |index| {
+        let node = pattern.get_node_ref(&index);
+        format!("({})", get_element_name(node.get_var(), node.get_labels_ref(), ""))
+    };
+    pattern.get_relationships_and_edges().iter().map(|edge| {
+        let rel = &edge.relationship;
+        format!("{}-[{}]->{}", node_name(edge.get_source()), get_element_name(rel.get_var(), rel.get_labels_ref(), "|"), node_name(edge.get_target()))
+    }).collect::<Vec<String>>().join(", ")
+}
+
+impl ReturnExpression {
+    pub fn get_name(&self) -> String {
+        match self {
+            ReturnExpression::FunctionCall(func) => func.get_name(),
+            ReturnExpression::Item(name) => name.to_string(),
+            ReturnExpression::ItemPropertyName(prop) => format!("{}.{}", prop.item_name, prop.property_name),
+            ReturnExpression::Value(value) => get_value_name(value),
+            ReturnExpression::Expression(expr) => expr.get_name(),
+            ReturnExpression::Alias(_, alias) => alias.to_string(),
+        }
+    }
+
+    /// The aliased expression, or self when there is no alias.
+    pub fn get_expression(&self) -> &ReturnExpression {
+        match self {
+            ReturnExpression::Alias(expr, _) => expr.get_expression(),
+            _ => self,
+        }
+    }
+
+    pub fn is_aggregation(&self) -> bool {
+        matches!(self.get_expression(), ReturnExpression::FunctionCall(func) if func.is_aggregation())
+    }
 }
 
 pub struct ReturnClause {
     pub expressions: Vec<ReturnExpression>,
+    pub distinct: bool,
 }
 
 impl ReturnClause {
     pub fn new() -> Self {
-        ReturnClause{expressions: Vec::new()}
+        ReturnClause{expressions: Vec::new(), distinct: false}
+    }
+
+    pub fn has_aggregation(&self) -> bool {
+        self.expressions.iter().any(|expr| expr.is_aggregation())
     }
 }
 
 pub struct WhereClause {
     pub expressions: Box<dyn Ast>,
+    pub condition: Option<Expression>,
 }
 
 impl WhereClause {
     pub fn new(ast: Box<dyn Ast>) -> Self {
-        WhereClause{expressions: ast}
+        WhereClause{expressions: ast, condition: None}
+    }
+}
+
+impl Clone for WhereClause {
+    fn clone(&self) -> Self {
+        WhereClause{expressions: self.expressions.clone_ast(), condition: self.condition.clone()}
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Operator {
     Equal,
+    NotEqual,
     Inferior,
     Superior,
     InferiorOrEqual,
     SuperiorOrEqual,
+    And,
+    Or,
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Modulo,
+    Contains,
+    StartsWith,
+    EndsWith,
+}
+
+impl Operator {
+    fn get_symbol(&self) -> &'static str {
+        match self {
+            Operator::Equal => "=",
+            Operator::NotEqual => "<>",
+            Operator::Inferior => "<",
+            Operator::Superior => ">",
+            Operator::InferiorOrEqual => "<=",
+            Operator::SuperiorOrEqual => ">=",
+            Operator::And => "AND",
+            Operator::Or => "OR",
+            Operator::Add => "+",
+            Operator::Subtract => "-",
+            Operator::Multiply => "*",
+            Operator::Divide => "/",
+            Operator::Modulo => "%",
+            Operator::Contains => "CONTAINS",
+            Operator::StartsWith => "STARTS WITH",
+            Operator::EndsWith => "ENDS WITH",
+        }
+    }
+
+    fn get_precedence(&self) -> u8 {
+        match self {
+            Operator::Or => 0,
+            Operator::And => 1,
+            Operator::Equal | Operator::NotEqual | Operator::Inferior | Operator::Superior | Operator::InferiorOrEqual | Operator::SuperiorOrEqual
+                | Operator::Contains | Operator::StartsWith | Operator::EndsWith => 2,
+            Operator::Add | Operator::Subtract => 3,
+            Operator::Multiply | Operator::Divide | Operator::Modulo => 4,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub enum Expression {
+    Value(PropertyValue),
+    Item(String),
+    ItemPropertyName(ItemPropertyName),
+    FunctionCall(FunctionCall),
+    Not(Box<Expression>),
+    IsNull(Box<Expression>),
+    IsNotNull(Box<Expression>),
+    BinaryOperation(Box<Expression>, Operator, Box<Expression>),
+    /// Pattern predicate, it is true when the pattern has a match for the variables of the row.
+    Pattern(PropertyGraph),
+}
+
+impl Expression {
+    pub fn get_name(&self) -> String {
+        match self {
+            Expression::Value(value) => get_value_name(value),
+            Expression::Item(name) => name.to_string(),
+            Expression::ItemPropertyName(prop) => format!("{}.{}", prop.item_name, prop.property_name),
+            Expression::FunctionCall(func) => func.get_name(),
+            Expression::Not(operand) => format!("NOT {}", operand.get_name()),
+            Expression::IsNull(operand) => format!("{} IS NULL", operand.get_name()),
+            Expression::IsNotNull(operand) => format!("{} IS NOT NULL", operand.get_name()),
+            Expression::BinaryOperation(first, operator, second) => {
+                format!("{} {} {}", first.get_operand_name(*operator, false), operator.get_symbol(), second.get_operand_name(*operator, true))
+            },
+            Expression::Pattern(pattern) => get_pattern_name(pattern),
+        }
+    }
+
+    /// Operands binding looser than the enclosing operator are parenthesized, as well as right operands of equal precedence.
+    fn get_operand_name(&self, parent: Operator, is_right: bool) -> String {
+        match self {
+            Expression::BinaryOperation(_, operator, _) if operator.get_precedence() < parent.get_precedence() || (is_right && operator.get_precedence() == parent.get_precedence()) => {
+                format!("({})", self.get_name())
+            },
+            _ => self.get_name(),
+        }
+    }
 }
 pub struct BoolCondition {
     pub first_member: Box<dyn Ast>,
@@ -77,13 +287,33 @@ pub struct BoolCondition {
     pub operator: Operator,
 }
 
+/// Path variable of a pattern, its elements are the variables of its nodes and relationships in the order of the pattern.
+#[derive(Clone, Debug)]
+pub struct NamedPath {
+    pub name: String,
+    pub elements: Vec<String>,
+}
+
 pub struct Request {
     pub steps: Vec<QueryStep>,
     pub return_clause: Option<ReturnClause>,
+    pub mode: ExecutionMode,
+    pub paths: Vec<NamedPath>,
+    pub unions: Vec<UnionPart>,
 }
 
 impl Request {
     pub fn new() -> Self {
-        Request {steps: Vec::new(), return_clause: None}
+        Request {steps: Vec::new(), return_clause: None, mode: ExecutionMode::Run, paths: Vec::new(), unions: Vec::new()}
     }
+
+    pub fn get_column_names(&self) -> Option<Vec<String>> {
+        self.return_clause.as_ref().map(|return_clause| return_clause.expressions.iter().map(|expr| expr.get_name()).collect())
+    }
+}
+
+/// Query part following a UNION, it runs on its own and its rows are appended to the ones of the previous parts.
+pub struct UnionPart {
+    pub request: Request,
+    pub all: bool,
 }
\ No newline at end of file
@@ -34,6 +34,7 @@ pub enum TokenType {
     And,
     Or,
     Match,
+    Optional,
     Create,
     Delete,
     Where,
@@ -55,8 +56,38 @@ pub enum TokenType {
     Pipe,
     StringType,
     Equals,
+    NotEqual,
+    LessThan,
+    LessThanOrEqual,
+    GreaterThan,
+    GreaterThanOrEqual,
+    Not,
     Dot,
     Parameter,
+    Constraint,
+    On,
+    Assert,
+    Is,
+    Unique,
+    Explain,
+    Profile,
+    Null,
+    As,
+    Modulo,
+    Distinct,
+    Unwind,
+    Call,
+    Yield,
+    Contains,
+    Starts,
+    Ends,
+    With,
+    Set,
+    Remove,
+    Union,
+    All,
+    Foreach,
+    In,
 }
 
 
@@ -83,4 +114,49 @@ impl <'a> Token<'a> {
     pub fn size(&self) -> usize {
         self.end - self.begin
     }
+
+    /// Value of the token, string literals are unquoted and unescaped, escaped identifiers are unquoted.
+    pub fn get_value(&self) -> String {
+        let unquoted = self.content.get(1..self.content.len().saturating_sub(1)).unwrap_or_default();
+        match self.token_type {
+            TokenType::StringType => unescape_string(unquoted).unwrap_or_default(),
+            TokenType::Identifier if self.content.starts_with('`') => unquoted.replace("``", "`"),
+            _ => self.content.to_owned(),
+        }
+    }
+}
+
+/// Replaces the escape sequences of a string literal by their characters, returns None on an invalid sequence.
+pub fn unescape_string(literal: &str) -> Option<String> {
+    let mut value = String::with_capacity(literal.len());
+    let mut chars = literal.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+        let escaped = match chars.next()? {
+            't' => '\t',
+            'b' => '\u{8}',
+            'n' => '\n',
+            'r' => '\r',
+            'f' => '\u{c}',
+            '\'' => '\'',
+            '"' => '"',
+            '\\' => '\\',
+            'u' => read_code_point(&mut chars, 4)?,
+            'U' => read_code_point(&mut chars, 8)?,
+            _ => return None,
+        };
+        value.push(escaped);
+    }
+    Some(value)
+}
+
+fn read_code_point(chars: &mut std::str::Chars, digits: usize) -> Option<char> {
+    let hex = chars.take(digits).collect::<String>();
+    if hex.len() != digits || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32)
 }
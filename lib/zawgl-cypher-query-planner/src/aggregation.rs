@@ -0,0 +1,274 @@
+// MIT License
+//
+// Copyright (c) 2022 Alexandre RICCIARDI
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use std::collections::HashMap;
+
+use zawgl_core::model::PropertyValue;
+use zawgl_cypher_query_model::model::{FunctionCall, ReturnClause, ReturnExpression};
+
+use crate::evaluator::{Bindings, EvalResult, eval_return_expression};
+
+/// Hashable image of an evaluated value, nodes and relationships are identified by their ids.
+#[derive(Hash, PartialEq, Eq, Clone)]
+pub(crate) enum GroupKey {
+    Null,
+    Bool(bool),
+    Integer(i64),
+    Float(u64),
+    String(String),
+    NodeId(Option<u64>),
+    RelationshipId(Option<u64>),
+    List(Vec<GroupKey>),
+    Map(Vec<(String, GroupKey)>),
+    DateTime(i64),
+    Date(i64),
+    Duration(i64, i64, i64),
+}
+
+pub(crate) fn make_group_key(value: &EvalResult) -> GroupKey {
+    match value {
+        EvalResult::Null => GroupKey::Null,
+        EvalResult::Scalar(PropertyValue::PBool(b)) => GroupKey::Bool(*b),
+        EvalResult::Scalar(PropertyValue::PInteger(i)) => GroupKey::Integer(*i),
+        EvalResult::Scalar(PropertyValue::PFloat(f)) => GroupKey::Float(f.to_bits()),
+        EvalResult::Scalar(PropertyValue::PString(s)) => GroupKey::String(s.to_string()),
+        EvalResult::Scalar(PropertyValue::PList(values)) => GroupKey::List(values.iter().map(|v| make_group_key(&EvalResult::Scalar(v.clone()))).collect()),
+        EvalResult::Scalar(PropertyValue::PMap(entries)) => GroupKey::Map(entries.iter().map(|(k, v)| (k.clone(), make_group_key(&EvalResult::Scalar(v.clone())))).collect()),
+        EvalResult::Scalar(PropertyValue::PDateTime(dt)) => GroupKey::DateTime(dt.epoch_nanos),
+        EvalResult::Scalar(PropertyValue::PDate(date)) => GroupKey::Date(date.epoch_days),
+        EvalResult::Scalar(PropertyValue::PDuration(d)) => GroupKey::Duration(d.months, d.days, d.nanos),
+        EvalResult::Scalar(PropertyValue::PNull) => GroupKey::Null,
+        EvalResult::Node(n) => GroupKey::NodeId(n.get_id()),
+        EvalResult::Relationship(r) => GroupKey::RelationshipId(r.get_id()),
+        EvalResult::Path(p) => GroupKey::List(p.get_nodes_ref().iter().map(|n| GroupKey::NodeId(n.get_id())).chain(p.get_relationships_ref().iter().map(|r| GroupKey::RelationshipId(r.get_id()))).collect()),
+        EvalResult::List(values) => GroupKey::List(values.iter().map(make_group_key).collect()),
+    }
+}
+
+fn sum_values(values: &Vec<EvalResult>) -> EvalResult {
+    let mut int_sum = 0i64;
+    let mut float_sum = 0f64;
+    let mut has_float = false;
+    for value in values {
+        match value {
+            EvalResult::Scalar(PropertyValue::PInteger(i)) => int_sum += i,
+            EvalResult::Scalar(PropertyValue::PFloat(f)) => {
+                float_sum += f;
+                has_float = true;
+            },
+            _ => {}
+        }
+    }
+    if has_float {
+        EvalResult::Scalar(PropertyValue::PFloat(float_sum + int_sum as f64))
+    } else {
+        EvalResult::Scalar(PropertyValue::PInteger(int_sum))
+    }
+}
+
+fn avg_values(values: &Vec<EvalResult>) -> EvalResult {
+    let numbers: Vec<f64> = values.iter().filter_map(|value| match value {
+        EvalResult::Scalar(PropertyValue::PInteger(i)) => Some(*i as f64),
+        EvalResult::Scalar(PropertyValue::PFloat(f)) => Some(*f),
+        _ => None,
+    }).collect();
+    if numbers.is_empty() {
+        EvalResult::Null
+    } else {
+        EvalResult::Scalar(PropertyValue::PFloat(numbers.iter().sum::<f64>() / numbers.len() as f64))
+    }
+}
+
+fn select_value(values: &Vec<EvalResult>, keep_new: fn(std::cmp::Ordering) -> bool) -> EvalResult {
+    let mut res: Option<&PropertyValue> = None;
+    for value in values {
+        if let EvalResult::Scalar(v) = value {
+            res = match res {
+                Some(curr) if !curr.partial_cmp(v).map_or(false, keep_new) => Some(curr),
+                _ => Some(v),
+            };
+        }
+    }
+    res.map(|v| EvalResult::Scalar(v.clone())).unwrap_or(EvalResult::Null)
+}
+
+fn compute_aggregation(func: &FunctionCall, values: Vec<EvalResult>, nb_rows: usize) -> EvalResult {
+    match func.name.to_lowercase().as_str() {
+        "count" => {
+            if func.args.is_empty() {
+                EvalResult::Scalar(PropertyValue::PInteger(nb_rows as i64))
+            } else {
+                EvalResult::Scalar(PropertyValue::PInteger(values.len() as i64))
+            }
+        },
+        "sum" => sum_values(&values),
+        "avg" => avg_values(&values),
+        "min" => select_value(&values, |ord| ord == std::cmp::Ordering::Greater),
+        "max" => select_value(&values, |ord| ord == std::cmp::Ordering::Less),
+        "collect" => EvalResult::List(values),
+        _ => EvalResult::Null,
+    }
+}
+
+struct Group {
+    keys: Vec<EvalResult>,
+    aggregated_values: Vec<Vec<EvalResult>>,
+    nb_rows: usize,
+}
+
+pub fn aggregate<G: Bindings>(return_clause: &ReturnClause, graphs: &[G]) -> Vec<Vec<EvalResult>> {
+    let key_expressions: Vec<&ReturnExpression> = return_clause.expressions.iter().filter(|expr| !is_aggregation(expr)).collect();
+    let aggregations: Vec<&FunctionCall> = return_clause.expressions.iter().filter_map(|expr| match expr.get_expression() {
+        ReturnExpression::FunctionCall(func) if func.is_aggregation() => Some(func),
+        _ => None,
+    }).collect();
+
+    let mut groups: Vec<Group> = Vec::new();
+    let mut map_key_group = HashMap::new();
+    for graph in graphs {
+        let keys: Vec<EvalResult> = key_expressions.iter().map(|expr| eval_return_expression(expr, graph)).collect();
+        let group_key: Vec<GroupKey> = keys.iter().map(make_group_key).collect();
+        let group_index = *map_key_group.entry(group_key).or_insert_with(|| {
+            groups.push(Group{keys: keys, aggregated_values: vec![Vec::new(); aggregations.len()], nb_rows: 0});
+            groups.len() - 1
+        });
+        let group = &mut groups[group_index];
+        group.nb_rows += 1;
+        for (index, func) in aggregations.iter().enumerate() {
+            if let Some(arg) = func.args.first() {
+                let value = eval_return_expression(arg, graph);
+                if !matches!(value, EvalResult::Null) {
+                    group.aggregated_values[index].push(value);
+                }
+            }
+        }
+    }
+    if groups.is_empty() && key_expressions.is_empty() {
+        groups.push(Group{keys: Vec::new(), aggregated_values: vec![Vec::new(); aggregations.len()], nb_rows: 0});
+    }
+
+    let mut rows = Vec::new();
+    for group in groups {
+        let mut keys = group.keys.into_iter();
+        let mut aggregated_values = group.aggregated_values.into_iter().zip(aggregations.iter());
+        let mut row = Vec::new();
+        for expr in &return_clause.expressions {
+            if is_aggregation(expr) {
+                if let Some((values, func)) = aggregated_values.next() {
+                    row.push(compute_aggregation(func, values, group.nb_rows));
+                }
+            } else if let Some(key) = keys.next() {
+                row.push(key);
+            }
+        }
+        rows.push(row);
+    }
+    rows
+}
+
+fn is_aggregation(expr: &ReturnExpression) -> bool {
+    expr.is_aggregation()
+}
+
+#[cfg(test)]
+mod test_aggregation {
+    use super::*;
+    use zawgl_core::model::{Node, Property, PropertyGraph};
+    use zawgl_cypher_query_model::model::ItemPropertyName;
+
+    fn make_person(id: u64, city: &str, age: i64) -> PropertyGraph {
+        let mut graph = PropertyGraph::new();
+        let mut node = Node::new();
+        node.set_id(Some(id));
+        node.set_var("p");
+        node.get_labels_mut().push(String::from("Person"));
+        node.get_properties_mut().push(Property::new(String::from("city"), PropertyValue::PString(String::from(city))));
+        node.get_properties_mut().push(Property::new(String::from("age"), PropertyValue::PInteger(age)));
+        graph.add_node(node);
+        graph
+    }
+
+    fn make_function(name: &str, arg: Option<ReturnExpression>) -> ReturnExpression {
+        let mut func = FunctionCall::new(name);
+        if let Some(a) = arg {
+            func.args.push(a);
+        }
+        ReturnExpression::FunctionCall(func)
+    }
+
+    fn age() -> Option<ReturnExpression> {
+        Some(ReturnExpression::ItemPropertyName(ItemPropertyName::new("p", "age")))
+    }
+
+    fn assert_integer(value: &EvalResult, expected: i64) {
+        match value {
+            EvalResult::Scalar(PropertyValue::PInteger(i)) => assert_eq!(*i, expected),
+            _ => assert!(false, "expected an integer"),
+        }
+    }
+
+    #[test]
+    fn test_group_by_property() {
+        let graphs = vec![make_person(1, "Paris", 30), make_person(2, "Lyon", 40), make_person(3, "Paris", 50)];
+        let mut ret = ReturnClause::new();
+        ret.expressions.push(ReturnExpression::ItemPropertyName(ItemPropertyName::new("p", "city")));
+        ret.expressions.push(make_function("count", Some(ReturnExpression::Item(String::from("p")))));
+        ret.expressions.push(make_function("sum", age()));
+        ret.expressions.push(make_function("min", age()));
+        ret.expressions.push(make_function("max", age()));
+        ret.expressions.push(make_function("collect", age()));
+        let rows = aggregate(&ret, &graphs);
+        assert_eq!(rows.len(), 2);
+        match &rows[0][0] {
+            EvalResult::Scalar(PropertyValue::PString(city)) => assert_eq!(city, "Paris"),
+            _ => assert!(false, "expected a city"),
+        }
+        assert_integer(&rows[0][1], 2);
+        assert_integer(&rows[0][2], 80);
+        assert_integer(&rows[0][3], 30);
+        assert_integer(&rows[0][4], 50);
+        match &rows[0][5] {
+            EvalResult::List(values) => assert_eq!(values.len(), 2),
+            _ => assert!(false, "expected a list"),
+        }
+        assert_integer(&rows[1][1], 1);
+    }
+
+    #[test]
+    fn test_aggregate_without_grouping() {
+        let mut ret = ReturnClause::new();
+        ret.expressions.push(make_function("count", None));
+        ret.expressions.push(make_function("avg", age()));
+        let rows = aggregate(&ret, &Vec::<PropertyGraph>::new());
+        assert_eq!(rows.len(), 1);
+        assert_integer(&rows[0][0], 0);
+        assert!(matches!(rows[0][1], EvalResult::Null));
+
+        let graphs = vec![make_person(1, "Paris", 30), make_person(2, "Lyon", 40)];
+        let rows = aggregate(&ret, &graphs);
+        assert_integer(&rows[0][0], 2);
+        match &rows[0][1] {
+            EvalResult::Scalar(PropertyValue::PFloat(avg)) => assert_eq!(*avg, 35.0),
+            _ => assert!(false, "expected a float"),
+        }
+    }
+}
@@ -0,0 +1,192 @@
+// MIT License
+//
+// Copyright (c) 2022 Alexandre RICCIARDI
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use zawgl_core::model::{PropertyGraph, PropertyValue, Status};
+use zawgl_cypher_query_model::{QueryStep, StepType};
+use zawgl_cypher_query_model::model::{Expression, ProcedureCall, Request, ReturnClause, ReturnExpression};
+
+pub const COUNT_PROCEDURE_NAME: &str = "db.stats.count";
+const COUNT_COLUMN: &str = "count";
+
+/// Variable counted by a single `count(var)` return item without DISTINCT.
+fn get_counted_variable(return_clause: &ReturnClause) -> Option<&str> {
+    if return_clause.distinct || return_clause.expressions.len() != 1 {
+        return None;
+    }
+    match return_clause.expressions[0].get_expression() {
+        ReturnExpression::FunctionCall(func) if func.name.eq_ignore_ascii_case("count") && func.args.len() == 1 => match &func.args[0] {
+            ReturnExpression::Item(name) => Some(name),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn get_single_label(labels: &[String]) -> Option<PropertyValue> {
+    match labels {
+        [] => Some(PropertyValue::PNull),
+        [label] => Some(PropertyValue::PString(label.clone())),
+        _ => None,
+    }
+}
+
+/// Kind and label or type of the elements counted by a pattern made of a node, or of a relationship between two distinct nodes,
+/// without properties nor predicates.
+fn get_counted_statistic(pattern: &PropertyGraph, variable: &str) -> Option<(&'static str, PropertyValue)> {
+    let nodes = pattern.get_nodes();
+    if nodes.iter().any(|node| *node.get_status() != Status::Match || !node.get_properties_ref().is_empty() || !node.get_predicates_ref().is_empty()) {
+        return None;
+    }
+    let relationships = pattern.get_relationships();
+    match (nodes.len(), relationships.len()) {
+        (1, 0) if nodes[0].get_var().as_deref() == Some(variable) => Some(("node", get_single_label(nodes[0].get_labels_ref())?)),
+        (2, 1) => {
+            let rel = &relationships[0];
+            let unconstrained_ends = nodes.iter().all(|node| node.get_labels_ref().is_empty());
+            if unconstrained_ends && *rel.get_status() == Status::Match && rel.get_var().as_deref() == Some(variable)
+                && rel.get_properties_ref().is_empty() && rel.get_predicates_ref().is_empty() {
+                Some(("relationship", get_single_label(rel.get_labels_ref())?))
+            } else {
+                None
+            }
+        },
+        _ => None,
+    }
+}
+
+/// Rewrites a `MATCH (n:Label) RETURN count(n)` or `MATCH ()-[r:TYPE]->() RETURN count(r)` request as a call of the
+/// count procedure answered from the repository statistics.
+/// Requests with a WHERE clause, properties or any other step or return item are returned unchanged.
+pub fn rewrite_count_request(request: Request) -> Request {
+    let statistic = match (&request.steps[..], &request.return_clause) {
+        ([step], Some(return_clause)) if step.step_type == StepType::MATCH && step.patterns.len() == 1 && step.where_clause.is_none() => {
+            get_counted_variable(return_clause).and_then(|variable| get_counted_statistic(&step.patterns[0], variable))
+        },
+        _ => None,
+    };
+    let (kind, label) = match statistic {
+        Some(statistic) => statistic,
+        None => return request,
+    };
+    let column_name = request.return_clause.as_ref().map(|return_clause| return_clause.expressions[0].get_name()).unwrap_or_default();
+    let mut call = ProcedureCall::new(COUNT_PROCEDURE_NAME);
+    call.args.push(Expression::Value(PropertyValue::PString(String::from(kind))));
+    call.args.push(Expression::Value(label));
+    let mut return_clause = ReturnClause::new();
+    return_clause.expressions.push(ReturnExpression::Alias(Box::new(ReturnExpression::Item(String::from(COUNT_COLUMN))), column_name));
+    Request {steps: vec![QueryStep::new_call(call)], return_clause: Some(return_clause), mode: request.mode, paths: Vec::new(), unions: request.unions}
+}
+
+#[cfg(test)]
+mod test_count {
+    use super::*;
+    use zawgl_core::graph_engine::GraphEngine;
+    use zawgl_core::model::{Node, Property, Relationship};
+    use zawgl_core::model::init::InitContext;
+    use zawgl_core::test_utils::build_dir_path_and_rm_old;
+    use zawgl_cypher_query_model::ExecutionMode;
+    use zawgl_cypher_query_model::model::FunctionCall;
+    use crate::evaluator::EvalResult;
+    use crate::{handle_query, ResultOrder};
+    use crate::procedures::ProcedureRegistry;
+    use crate::projection::project_table;
+
+    fn make_node(var: &str, label: Option<&str>) -> Node {
+        let mut node = Node::new();
+        node.set_var(var);
+        node.set_status(Status::Match);
+        if let Some(label) = label {
+            node.set_labels(vec![String::from(label)]);
+        }
+        node
+    }
+
+    fn make_count_request(pattern: PropertyGraph, variable: &str) -> Request {
+        let mut step = QueryStep::new(StepType::MATCH);
+        step.patterns.push(pattern);
+        let mut count = FunctionCall::new("count");
+        count.args.push(ReturnExpression::Item(String::from(variable)));
+        let mut return_clause = ReturnClause::new();
+        return_clause.expressions.push(ReturnExpression::FunctionCall(count));
+        Request {steps: vec![step], return_clause: Some(return_clause), mode: ExecutionMode::Run, paths: Vec::new(), unions: Vec::new()}
+    }
+
+    fn run_count(request: &Request, graph_engine: &mut GraphEngine) -> (String, i64) {
+        let result = handle_query(&request.steps, graph_engine, &ProcedureRegistry::with_builtins(), None, request.mode, &ResultOrder::Matcher).expect("result");
+        let table = project_table(request.return_clause.as_ref().unwrap(), result.records.as_ref().expect("records"));
+        match &table.rows[0][0] {
+            EvalResult::Scalar(PropertyValue::PInteger(count)) => (table.columns[0].clone(), *count),
+            _ => panic!("count"),
+        }
+    }
+
+    #[test]
+    fn test_rewrite_count_request() {
+        let main_dir = build_dir_path_and_rm_old("test_rewrite_count_request").expect("db path");
+        let conf = InitContext::new(&main_dir).expect("can't create context");
+        let mut graph_engine = GraphEngine::new(&conf);
+        let mut graph = PropertyGraph::new();
+        let mut city = Node::new();
+        city.set_labels(vec![String::from("City")]);
+        let city_id = graph.add_node(city);
+        for name in ["ada", "alan"] {
+            let mut person = Node::new();
+            person.set_labels(vec![String::from("Person")]);
+            person.get_properties_mut().push(Property::new(String::from("name"), PropertyValue::PString(String::from(name))));
+            let person_id = graph.add_node(person);
+            let mut lives_in = Relationship::new();
+            lives_in.set_labels(vec![String::from("LIVES_IN")]);
+            graph.add_relationship(lives_in, person_id, city_id);
+        }
+        graph_engine.create_graph(&graph).expect("graph");
+
+        let mut pattern = PropertyGraph::new();
+        pattern.add_node(make_node("p", Some("Person")));
+        let request = rewrite_count_request(make_count_request(pattern, "p"));
+        assert_eq!(StepType::CALL, request.steps[0].step_type);
+        assert_eq!((String::from("count(p)"), 2), run_count(&request, &mut graph_engine));
+
+        let mut pattern = PropertyGraph::new();
+        pattern.add_node(make_node("n", None));
+        assert_eq!((String::from("count(n)"), 3), run_count(&rewrite_count_request(make_count_request(pattern, "n")), &mut graph_engine));
+
+        let mut pattern = PropertyGraph::new();
+        let source = pattern.add_node(make_node("a", None));
+        let target = pattern.add_node(make_node("b", None));
+        let mut rel = Relationship::new();
+        rel.set_var("r");
+        rel.set_status(Status::Match);
+        rel.set_labels(vec![String::from("LIVES_IN")]);
+        pattern.add_relationship(rel, source, target);
+        assert_eq!((String::from("count(r)"), 2), run_count(&rewrite_count_request(make_count_request(pattern, "r")), &mut graph_engine));
+
+        let mut person = make_node("p", Some("Person"));
+        person.get_properties_mut().push(Property::new(String::from("name"), PropertyValue::PString(String::from("ada"))));
+        let mut pattern = PropertyGraph::new();
+        pattern.add_node(person);
+        assert_eq!(StepType::MATCH, rewrite_count_request(make_count_request(pattern, "p")).steps[0].step_type);
+
+        let mut pattern = PropertyGraph::new();
+        pattern.add_node(make_node("p", Some("Person")));
+        assert_eq!(StepType::MATCH, rewrite_count_request(make_count_request(pattern, "q")).steps[0].step_type);
+    }
+}
@@ -0,0 +1,517 @@
+// MIT License
+//
+// Copyright (c) 2022 Alexandre RICCIARDI
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+use zawgl_core::model::{Node, Path, PropertyGraph, PropertyValue, Relationship, Property, get_element_uuid};
+use zawgl_cypher_query_model::model::{Expression, Operator, ReturnExpression, FunctionCall, ItemPropertyName, NamedPath};
+
+use crate::temporal::eval_temporal_function;
+
+#[derive(Debug, Clone)]
+pub enum EvalResult {
+    Null,
+    Scalar(PropertyValue),
+    Node(Node),
+    Relationship(Relationship),
+    Path(Path),
+    List(Vec<EvalResult>),
+}
+
+fn make_scalar(value: PropertyValue) -> EvalResult {
+    match value {
+        PropertyValue::PNull => EvalResult::Null,
+        _ => EvalResult::Scalar(value),
+    }
+}
+
+fn find_property(properties: &Vec<Property>, property_name: &str) -> EvalResult {
+    properties.iter().find(|p| p.get_name() == property_name).map(|p| make_scalar(p.get_value().clone())).unwrap_or(EvalResult::Null)
+}
+
+/// Values of the variables expressions are evaluated against.
+pub trait Bindings {
+    fn get_item(&self, item_name: &str) -> EvalResult;
+
+    /// Whether a pattern predicate has a match, it is unknown without a graph to match it against.
+    fn has_match(&self, _pattern: &PropertyGraph) -> EvalResult {
+        EvalResult::Null
+    }
+}
+
+impl Bindings for PropertyGraph {
+    fn get_item(&self, item_name: &str) -> EvalResult {
+        for node in self.get_nodes() {
+            if node.get_var().as_deref() == Some(item_name) {
+                return EvalResult::Node(node.clone());
+            }
+        }
+        for rel in self.get_relationships() {
+            if rel.get_var().as_deref() == Some(item_name) {
+                return EvalResult::Relationship(rel.clone());
+            }
+        }
+        EvalResult::Null
+    }
+}
+
+/// Matched graph whose named paths are bound to their variables as well.
+pub struct PathBindings<'a> {
+    graph: &'a PropertyGraph,
+    paths: &'a [NamedPath],
+}
+
+impl <'a> PathBindings<'a> {
+    pub fn new(graph: &'a PropertyGraph, paths: &'a [NamedPath]) -> Self {
+        PathBindings{graph, paths}
+    }
+}
+
+impl Bindings for PathBindings<'_> {
+    fn get_item(&self, item_name: &str) -> EvalResult {
+        match self.paths.iter().find(|path| path.name == item_name) {
+            Some(path) => build_path(self.graph, path).map(EvalResult::Path).unwrap_or(EvalResult::Null),
+            None => self.graph.get_item(item_name),
+        }
+    }
+}
+
+/// The direction of each relationship is the one of the matched edge bound to its variable.
+fn build_path(graph: &PropertyGraph, named_path: &NamedPath) -> Option<Path> {
+    let find_node = |var: &str| graph.get_nodes_with_ids().into_iter().find(|(node, _)| node.get_var().as_deref() == Some(var));
+    let mut elements = named_path.elements.iter();
+    let (start, mut previous_index) = find_node(elements.next()?)?;
+    let mut path = Path::new(start.clone());
+    while let (Some(rel_var), Some(node_var)) = (elements.next(), elements.next()) {
+        let (node, node_index) = find_node(node_var)?;
+        let edge = graph.get_relationships_and_edges().iter().find(|edge| edge.relationship.get_var().as_deref() == Some(rel_var.as_str())
+            && ((edge.get_source() == previous_index && edge.get_target() == node_index) || (edge.get_source() == node_index && edge.get_target() == previous_index)))?;
+        path.append(edge.relationship.clone(), edge.get_source() == previous_index, node.clone());
+        previous_index = node_index;
+    }
+    Some(path)
+}
+
+fn eval_item_property<G: Bindings + ?Sized>(prop: &ItemPropertyName, graph: &G) -> EvalResult {
+    match graph.get_item(&prop.item_name) {
+        EvalResult::Node(n) => find_property(n.get_properties_ref(), &prop.property_name),
+        EvalResult::Relationship(r) => find_property(r.get_properties_ref(), &prop.property_name),
+        _ => EvalResult::Null,
+    }
+}
+
+fn make_string_list<'a, I: Iterator<Item = &'a String>>(values: I) -> EvalResult {
+    EvalResult::Scalar(PropertyValue::PList(values.cloned().map(PropertyValue::PString).collect()))
+}
+
+/// Property map of a node or a relationship, or the map itself.
+fn eval_property_map(value: EvalResult) -> Option<BTreeMap<String, PropertyValue>> {
+    let properties = match value {
+        EvalResult::Node(n) => n.get_properties_ref().clone(),
+        EvalResult::Relationship(r) => r.get_properties_ref().clone(),
+        EvalResult::Scalar(PropertyValue::PMap(entries)) => return Some(entries),
+        _ => return None,
+    };
+    Some(properties.into_iter().map(|p| (p.get_name().to_string(), p.get_value().clone())).collect())
+}
+
+fn eval_scalar_args<G: Bindings + ?Sized>(func: &FunctionCall, graph: &G) -> Option<Vec<PropertyValue>> {
+    func.args.iter().map(|arg| match eval_return_expression(arg, graph) {
+        EvalResult::Scalar(value) => Some(value),
+        _ => None,
+    }).collect()
+}
+
+fn make_element_uuid(properties: &[Property]) -> EvalResult {
+    get_element_uuid(properties).map(|uuid| EvalResult::Scalar(PropertyValue::PString(String::from(uuid)))).unwrap_or(EvalResult::Null)
+}
+
+fn eval_function_call<G: Bindings + ?Sized>(func: &FunctionCall, graph: &G) -> EvalResult {
+    let name = func.name.to_lowercase();
+    match (name.as_str(), func.args.first().map(|arg| eval_return_expression(arg, graph))) {
+        ("id", Some(EvalResult::Node(n))) => n.get_id().map(|id| EvalResult::Scalar(PropertyValue::PInteger(id as i64))).unwrap_or(EvalResult::Null),
+        ("id", Some(EvalResult::Relationship(r))) => r.get_id().map(|id| EvalResult::Scalar(PropertyValue::PInteger(id as i64))).unwrap_or(EvalResult::Null),
+        ("elementid", Some(EvalResult::Node(n))) => make_element_uuid(n.get_properties_ref()),
+        ("elementid", Some(EvalResult::Relationship(r))) => make_element_uuid(r.get_properties_ref()),
+        ("length", Some(EvalResult::Path(p))) => EvalResult::Scalar(PropertyValue::PInteger(p.len() as i64)),
+        ("nodes", Some(EvalResult::Path(p))) => EvalResult::List(p.get_nodes_ref().iter().cloned().map(EvalResult::Node).collect()),
+        ("relationships", Some(EvalResult::Path(p))) => EvalResult::List(p.get_relationships_ref().iter().cloned().map(EvalResult::Relationship).collect()),
+        ("labels", Some(EvalResult::Node(n))) => make_string_list(n.get_labels_ref().iter()),
+        ("type", Some(EvalResult::Relationship(r))) => r.get_labels_ref().first().map(|label| EvalResult::Scalar(PropertyValue::PString(label.clone()))).unwrap_or(EvalResult::Null),
+        ("keys", Some(value)) => eval_property_map(value).map(|entries| make_string_list(entries.keys())).unwrap_or(EvalResult::Null),
+        ("properties", Some(value)) => eval_property_map(value).map(|entries| EvalResult::Scalar(PropertyValue::PMap(entries))).unwrap_or(EvalResult::Null),
+        ("exists", Some(value)) => EvalResult::Scalar(PropertyValue::PBool(!matches!(value, EvalResult::Null))),
+        ("toupper", Some(EvalResult::Scalar(PropertyValue::PString(s)))) => EvalResult::Scalar(PropertyValue::PString(s.to_uppercase())),
+        ("tolower", Some(EvalResult::Scalar(PropertyValue::PString(s)))) => EvalResult::Scalar(PropertyValue::PString(s.to_lowercase())),
+        ("datetime", _) | ("date", _) | ("duration", _) => {
+            eval_scalar_args(func, graph).and_then(|args| eval_temporal_function(&name, &args)).map(make_scalar).unwrap_or(EvalResult::Null)
+        },
+        _ => EvalResult::Null,
+    }
+}
+
+pub fn eval_return_expression<G: Bindings + ?Sized>(expr: &ReturnExpression, graph: &G) -> EvalResult {
+    match expr {
+        ReturnExpression::Item(item_name) => graph.get_item(item_name),
+        ReturnExpression::ItemPropertyName(prop) => eval_item_property(prop, graph),
+        ReturnExpression::FunctionCall(func) => eval_function_call(func, graph),
+        ReturnExpression::Value(value) => make_scalar(value.clone()),
+        ReturnExpression::Expression(expr) => eval_expression(expr, graph),
+        ReturnExpression::Alias(expr, _) => eval_return_expression(expr, graph),
+    }
+}
+
+pub fn eval_expression<G: Bindings + ?Sized>(expr: &Expression, graph: &G) -> EvalResult {
+    match expr {
+        Expression::Value(value) => make_scalar(value.clone()),
+        Expression::Item(item_name) => graph.get_item(item_name),
+        Expression::ItemPropertyName(prop) => eval_item_property(prop, graph),
+        Expression::FunctionCall(func) => eval_function_call(func, graph),
+        Expression::Not(operand) => {
+            match as_bool(&eval_expression(operand, graph)) {
+                Some(b) => EvalResult::Scalar(PropertyValue::PBool(!b)),
+                None => EvalResult::Null,
+            }
+        },
+        Expression::IsNull(operand) => EvalResult::Scalar(PropertyValue::PBool(matches!(eval_expression(operand, graph), EvalResult::Null))),
+        Expression::IsNotNull(operand) => EvalResult::Scalar(PropertyValue::PBool(!matches!(eval_expression(operand, graph), EvalResult::Null))),
+        Expression::BinaryOperation(first, operator, second) => {
+            let first_value = eval_expression(first, graph);
+            let second_value = eval_expression(second, graph);
+            eval_binary_operation(&first_value, *operator, &second_value)
+        },
+        Expression::Pattern(pattern) => graph.has_match(pattern),
+    }
+}
+
+pub fn is_true(value: &EvalResult) -> bool {
+    as_bool(value) == Some(true)
+}
+
+fn as_bool(value: &EvalResult) -> Option<bool> {
+    match value {
+        EvalResult::Scalar(PropertyValue::PBool(b)) => Some(*b),
+        _ => None,
+    }
+}
+
+fn compare_values(first: &EvalResult, second: &EvalResult) -> Option<Ordering> {
+    use PropertyValue::*;
+    match (first, second) {
+        (EvalResult::Scalar(PInteger(i)), EvalResult::Scalar(PFloat(f))) => (*i as f64).partial_cmp(f),
+        (EvalResult::Scalar(PFloat(f)), EvalResult::Scalar(PInteger(i))) => f.partial_cmp(&(*i as f64)),
+        (EvalResult::Scalar(v0), EvalResult::Scalar(v1)) => v0.partial_cmp(v1),
+        (EvalResult::Node(n0), EvalResult::Node(n1)) if n0.get_id().is_some() && n0.get_id() == n1.get_id() => Some(Ordering::Equal),
+        (EvalResult::Relationship(r0), EvalResult::Relationship(r1)) if r0.get_id().is_some() && r0.get_id() == r1.get_id() => Some(Ordering::Equal),
+        _ => None,
+    }
+}
+
+fn to_concatenated_string(value: &PropertyValue) -> Option<String> {
+    match value {
+        PropertyValue::PString(s) => Some(s.to_string()),
+        PropertyValue::PInteger(i) => Some(i.to_string()),
+        PropertyValue::PFloat(f) => Some(f.to_string()),
+        PropertyValue::PBool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+fn eval_integer_operation(first: i64, operator: Operator, second: i64) -> Option<i64> {
+    match operator {
+        Operator::Add => first.checked_add(second),
+        Operator::Subtract => first.checked_sub(second),
+        Operator::Multiply => first.checked_mul(second),
+        Operator::Divide => first.checked_div(second),
+        Operator::Modulo => first.checked_rem(second),
+        _ => None,
+    }
+}
+
+fn eval_float_operation(first: f64, operator: Operator, second: f64) -> Option<f64> {
+    match operator {
+        Operator::Add => Some(first + second),
+        Operator::Subtract => Some(first - second),
+        Operator::Multiply => Some(first * second),
+        Operator::Divide => Some(first / second),
+        Operator::Modulo => Some(first % second),
+        _ => None,
+    }
+}
+
+/// Integer overflow and division by zero evaluate to null, '+' also concatenates strings and lists.
+fn eval_arithmetic_operation(first: &EvalResult, operator: Operator, second: &EvalResult) -> Option<PropertyValue> {
+    use PropertyValue::*;
+    match (first, second) {
+        (EvalResult::Scalar(PInteger(i0)), EvalResult::Scalar(PInteger(i1))) => eval_integer_operation(*i0, operator, *i1).map(PInteger),
+        (EvalResult::Scalar(PInteger(i)), EvalResult::Scalar(PFloat(f))) => eval_float_operation(*i as f64, operator, *f).map(PFloat),
+        (EvalResult::Scalar(PFloat(f)), EvalResult::Scalar(PInteger(i))) => eval_float_operation(*f, operator, *i as f64).map(PFloat),
+        (EvalResult::Scalar(PFloat(f0)), EvalResult::Scalar(PFloat(f1))) => eval_float_operation(*f0, operator, *f1).map(PFloat),
+        (EvalResult::Scalar(PString(s0)), EvalResult::Scalar(v1)) if operator == Operator::Add => {
+            to_concatenated_string(v1).map(|s1| PString(format!("{}{}", s0, s1)))
+        },
+        (EvalResult::Scalar(v0), EvalResult::Scalar(PString(s1))) if operator == Operator::Add => {
+            to_concatenated_string(v0).map(|s0| PString(format!("{}{}", s0, s1)))
+        },
+        (EvalResult::Scalar(PList(l0)), EvalResult::Scalar(PList(l1))) if operator == Operator::Add => {
+            Some(PList(l0.iter().chain(l1.iter()).cloned().collect()))
+        },
+        (EvalResult::Scalar(PList(l0)), EvalResult::Scalar(v1)) if operator == Operator::Add => {
+            Some(PList(l0.iter().cloned().chain(std::iter::once(v1.clone())).collect()))
+        },
+        _ => None,
+    }
+}
+
+/// String predicates are null unless both operands are strings.
+fn eval_string_predicate(first: &EvalResult, second: &EvalResult, predicate: fn(&str, &str) -> bool) -> Option<bool> {
+    match (first, second) {
+        (EvalResult::Scalar(PropertyValue::PString(s0)), EvalResult::Scalar(PropertyValue::PString(s1))) => Some(predicate(s0, s1)),
+        _ => None,
+    }
+}
+
+fn eval_binary_operation(first: &EvalResult, operator: Operator, second: &EvalResult) -> EvalResult {
+    let res = match operator {
+        Operator::Add | Operator::Subtract | Operator::Multiply | Operator::Divide | Operator::Modulo => {
+            return eval_arithmetic_operation(first, operator, second).map(make_scalar).unwrap_or(EvalResult::Null)
+        },
+        Operator::And => {
+            match (as_bool(first), as_bool(second)) {
+                (Some(false), _) | (_, Some(false)) => Some(false),
+                (Some(true), Some(true)) => Some(true),
+                _ => None,
+            }
+        },
+        Operator::Or => {
+            match (as_bool(first), as_bool(second)) {
+                (Some(true), _) | (_, Some(true)) => Some(true),
+                (Some(false), Some(false)) => Some(false),
+                _ => None,
+            }
+        },
+        Operator::Equal => compare_values(first, second).map(|ord| ord == Ordering::Equal),
+        Operator::NotEqual => compare_values(first, second).map(|ord| ord != Ordering::Equal),
+        Operator::Inferior => compare_values(first, second).map(|ord| ord == Ordering::Less),
+        Operator::InferiorOrEqual => compare_values(first, second).map(|ord| ord != Ordering::Greater),
+        Operator::Superior => compare_values(first, second).map(|ord| ord == Ordering::Greater),
+        Operator::SuperiorOrEqual => compare_values(first, second).map(|ord| ord != Ordering::Less),
+        Operator::Contains => eval_string_predicate(first, second, |s0, s1| s0.contains(s1)),
+        Operator::StartsWith => eval_string_predicate(first, second, |s0, s1| s0.starts_with(s1)),
+        Operator::EndsWith => eval_string_predicate(first, second, |s0, s1| s0.ends_with(s1)),
+    };
+    res.map(|b| EvalResult::Scalar(PropertyValue::PBool(b))).unwrap_or(EvalResult::Null)
+}
+
+#[cfg(test)]
+mod test_evaluator {
+    use super::*;
+    use zawgl_cypher_query_model::model::ItemPropertyName;
+
+    fn make_person(age: i64, name: &str) -> PropertyGraph {
+        let mut graph = PropertyGraph::new();
+        let mut node = Node::new();
+        node.set_id(Some(1));
+        node.set_var("n");
+        node.get_properties_mut().push(Property::new(String::from("age"), PropertyValue::PInteger(age)));
+        node.get_properties_mut().push(Property::new(String::from("name"), PropertyValue::PString(String::from(name))));
+        graph.add_node(node);
+        graph
+    }
+
+    fn compare(property_name: &str, operator: Operator, value: PropertyValue) -> Expression {
+        Expression::BinaryOperation(Box::new(Expression::ItemPropertyName(ItemPropertyName::new("n", property_name))), operator, Box::new(Expression::Value(value)))
+    }
+
+    #[test]
+    fn test_comparisons() {
+        let graph = make_person(30, "Alice");
+        assert!(is_true(&eval_expression(&compare("age", Operator::Superior, PropertyValue::PInteger(18)), &graph)));
+        assert!(is_true(&eval_expression(&compare("age", Operator::SuperiorOrEqual, PropertyValue::PInteger(30)), &graph)));
+        assert!(!is_true(&eval_expression(&compare("age", Operator::Inferior, PropertyValue::PInteger(30)), &graph)));
+        assert!(is_true(&eval_expression(&compare("age", Operator::InferiorOrEqual, PropertyValue::PFloat(30.5)), &graph)));
+        assert!(is_true(&eval_expression(&compare("name", Operator::NotEqual, PropertyValue::PString(String::from("Bob"))), &graph)));
+        assert!(is_true(&eval_expression(&compare("name", Operator::Inferior, PropertyValue::PString(String::from("Bob"))), &graph)));
+    }
+
+    #[test]
+    fn test_boolean_operators() {
+        let graph = make_person(30, "Alice");
+        let adult = compare("age", Operator::SuperiorOrEqual, PropertyValue::PInteger(18));
+        let bob = compare("name", Operator::Equal, PropertyValue::PString(String::from("Bob")));
+        let expr = Expression::BinaryOperation(Box::new(adult), Operator::And, Box::new(Expression::Not(Box::new(bob))));
+        assert!(is_true(&eval_expression(&expr, &graph)));
+        let missing = compare("city", Operator::Equal, PropertyValue::PString(String::from("Paris")));
+        assert!(!is_true(&eval_expression(&Expression::Not(Box::new(missing)), &graph)));
+    }
+
+    #[test]
+    fn test_temporal_comparisons() {
+        let mut graph = PropertyGraph::new();
+        let mut node = Node::new();
+        node.set_var("n");
+        let at = eval_temporal_function("datetime", &[PropertyValue::PString(String::from("2015-07-21T21:40:32+01:00"))]).unwrap();
+        node.get_properties_mut().push(Property::new(String::from("at"), at));
+        graph.add_node(node);
+        let mut before = FunctionCall::new("datetime");
+        before.args.push(ReturnExpression::Value(PropertyValue::PString(String::from("2015-07-21T20:00:00Z"))));
+        let expr = Expression::BinaryOperation(Box::new(Expression::ItemPropertyName(ItemPropertyName::new("n", "at"))), Operator::Superior, Box::new(Expression::FunctionCall(before)));
+        assert!(is_true(&eval_expression(&expr, &graph)));
+        let same_instant = compare("at", Operator::Equal, eval_temporal_function("datetime", &[PropertyValue::PString(String::from("2015-07-21T20:40:32Z"))]).unwrap());
+        assert!(is_true(&eval_expression(&same_instant, &graph)));
+    }
+
+    #[test]
+    fn test_null_logic() {
+        let graph = make_person(30, "Alice");
+        let missing = Expression::ItemPropertyName(ItemPropertyName::new("n", "city"));
+        let null_compare = compare("city", Operator::Equal, PropertyValue::PNull);
+        assert!(matches!(eval_expression(&null_compare, &graph), EvalResult::Null));
+        assert!(is_true(&eval_expression(&Expression::IsNull(Box::new(missing)), &graph)));
+        assert!(is_true(&eval_expression(&Expression::IsNotNull(Box::new(Expression::ItemPropertyName(ItemPropertyName::new("n", "age")))), &graph)));
+        let adult = compare("age", Operator::SuperiorOrEqual, PropertyValue::PInteger(18));
+        let unknown_or_true = Expression::BinaryOperation(Box::new(compare("city", Operator::Equal, PropertyValue::PString(String::from("Paris")))), Operator::Or, Box::new(adult));
+        assert!(is_true(&eval_expression(&unknown_or_true, &graph)));
+        let unknown_and_false = Expression::BinaryOperation(Box::new(compare("city", Operator::Equal, PropertyValue::PString(String::from("Paris")))), Operator::And, Box::new(compare("age", Operator::Inferior, PropertyValue::PInteger(18))));
+        assert!(matches!(eval_expression(&Expression::Not(Box::new(unknown_and_false)), &graph), EvalResult::Scalar(PropertyValue::PBool(true))));
+        assert!(matches!(eval_expression(&Expression::Not(Box::new(null_compare)), &graph), EvalResult::Null));
+    }
+
+    #[test]
+    fn test_exists() {
+        let graph = make_person(30, "Alice");
+        let exists = |property_name: &str| {
+            let mut func = FunctionCall::new("exists");
+            func.args.push(ReturnExpression::ItemPropertyName(ItemPropertyName::new("n", property_name)));
+            eval_function_call(&func, &graph)
+        };
+        assert!(matches!(exists("age"), EvalResult::Scalar(PropertyValue::PBool(true))));
+        assert!(matches!(exists("city"), EvalResult::Scalar(PropertyValue::PBool(false))));
+        // patterns are only matched against a graph engine
+        assert!(matches!(eval_expression(&Expression::Pattern(graph.clone()), &graph), EvalResult::Null));
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let graph = make_person(30, "Alice");
+        let operation = |first: Expression, operator: Operator, second: PropertyValue| Expression::BinaryOperation(Box::new(first), operator, Box::new(Expression::Value(second)));
+        let age = || Expression::ItemPropertyName(ItemPropertyName::new("n", "age"));
+        assert!(matches!(eval_expression(&operation(age(), Operator::Add, PropertyValue::PInteger(1)), &graph), EvalResult::Scalar(PropertyValue::PInteger(31))));
+        assert!(matches!(eval_expression(&operation(age(), Operator::Divide, PropertyValue::PInteger(7)), &graph), EvalResult::Scalar(PropertyValue::PInteger(4))));
+        assert!(matches!(eval_expression(&operation(age(), Operator::Modulo, PropertyValue::PInteger(7)), &graph), EvalResult::Scalar(PropertyValue::PInteger(2))));
+        assert!(matches!(eval_expression(&operation(age(), Operator::Multiply, PropertyValue::PFloat(0.5)), &graph), EvalResult::Scalar(PropertyValue::PFloat(f)) if f == 15.0));
+        assert!(matches!(eval_expression(&operation(age(), Operator::Divide, PropertyValue::PInteger(0)), &graph), EvalResult::Null));
+        assert!(matches!(eval_expression(&operation(age(), Operator::Subtract, PropertyValue::PNull), &graph), EvalResult::Null));
+        let name = Expression::ItemPropertyName(ItemPropertyName::new("n", "name"));
+        assert!(matches!(eval_expression(&operation(name, Operator::Add, PropertyValue::PInteger(1)), &graph), EvalResult::Scalar(PropertyValue::PString(s)) if s == "Alice1"));
+        let age_plus_one = ReturnExpression::Alias(Box::new(ReturnExpression::Expression(Box::new(operation(age(), Operator::Add, PropertyValue::PInteger(1))))), String::from("next"));
+        assert!(matches!(eval_return_expression(&age_plus_one, &graph), EvalResult::Scalar(PropertyValue::PInteger(31))));
+    }
+
+    #[test]
+    fn test_string_functions() {
+        let graph = make_person(30, "Alice");
+        let string = |s: &str| PropertyValue::PString(String::from(s));
+        assert!(is_true(&eval_expression(&compare("name", Operator::StartsWith, string("Al")), &graph)));
+        assert!(is_true(&eval_expression(&compare("name", Operator::EndsWith, string("ce")), &graph)));
+        assert!(is_true(&eval_expression(&compare("name", Operator::Contains, string("lic")), &graph)));
+        assert!(!is_true(&eval_expression(&compare("name", Operator::Contains, string("bob")), &graph)));
+        assert!(matches!(eval_expression(&compare("age", Operator::StartsWith, string("3")), &graph), EvalResult::Null));
+        assert!(matches!(eval_expression(&compare("city", Operator::Contains, string("a")), &graph), EvalResult::Null));
+        let call = |name: &str, property_name: &str| {
+            let mut func = FunctionCall::new(name);
+            func.args.push(ReturnExpression::ItemPropertyName(ItemPropertyName::new("n", property_name)));
+            eval_function_call(&func, &graph)
+        };
+        assert!(matches!(call("toUpper", "name"), EvalResult::Scalar(PropertyValue::PString(s)) if s == "ALICE"));
+        assert!(matches!(call("tolower", "name"), EvalResult::Scalar(PropertyValue::PString(s)) if s == "alice"));
+        assert!(matches!(call("toUpper", "age"), EvalResult::Null));
+    }
+
+    #[test]
+    fn test_element_functions() {
+        let mut graph = make_person(30, "Alice");
+        let mut node = Node::new();
+        node.set_var("m");
+        node.get_labels_mut().push(String::from("Person"));
+        let m = graph.add_node(node);
+        let mut rel = Relationship::new();
+        rel.set_var("r");
+        rel.get_labels_mut().push(String::from("KNOWS"));
+        rel.get_properties_mut().push(Property::new(String::from("since"), PropertyValue::PInteger(2010)));
+        rel.get_properties_mut().push(Property::new(String::from("uuid"), PropertyValue::PString(String::from("41d2e28a-20a4-4ab0-b379-d810dede3786"))));
+        graph.add_relationship(rel, m, m);
+        let call = |name: &str, item: &str| {
+            let mut func = FunctionCall::new(name);
+            func.args.push(ReturnExpression::Item(String::from(item)));
+            eval_function_call(&func, &graph)
+        };
+        let strings = |values: &[&str]| PropertyValue::PList(values.iter().map(|v| PropertyValue::PString(String::from(*v))).collect());
+        assert!(matches!(call("labels", "m"), EvalResult::Scalar(labels) if labels == strings(&["Person"])));
+        assert!(matches!(call("labels", "n"), EvalResult::Scalar(labels) if labels == strings(&[])));
+        assert!(matches!(call("type", "r"), EvalResult::Scalar(PropertyValue::PString(t)) if t == "KNOWS"));
+        assert!(matches!(call("type", "n"), EvalResult::Null));
+        assert!(matches!(call("keys", "n"), EvalResult::Scalar(keys) if keys == strings(&["age", "name"])));
+        assert!(matches!(call("keys", "r"), EvalResult::Scalar(keys) if keys == strings(&["since", "uuid"])));
+        assert!(matches!(call("elementId", "r"), EvalResult::Scalar(PropertyValue::PString(uuid)) if uuid == "41d2e28a-20a4-4ab0-b379-d810dede3786"));
+        assert!(matches!(call("elementId", "m"), EvalResult::Null));
+        match call("properties", "n") {
+            EvalResult::Scalar(PropertyValue::PMap(entries)) => {
+                assert_eq!(Some(&PropertyValue::PInteger(30)), entries.get("age"));
+                assert_eq!(Some(&PropertyValue::PString(String::from("Alice"))), entries.get("name"));
+            },
+            other => panic!("unexpected properties {:?}", other),
+        }
+        assert!(matches!(call("properties", "missing"), EvalResult::Null));
+    }
+
+    #[test]
+    fn test_named_path() {
+        let mut graph = PropertyGraph::new();
+        let mut ids = Vec::new();
+        for (id, var) in [(1, "a"), (2, "b")] {
+            let mut node = Node::new();
+            node.set_id(Some(id));
+            node.set_var(var);
+            ids.push(graph.add_node(node));
+        }
+        let mut rel = Relationship::new();
+        rel.set_id(Some(3));
+        rel.set_var("r");
+        graph.add_relationship(rel, ids[1], ids[0]);
+        let paths = vec![NamedPath{name: String::from("p"), elements: vec![String::from("a"), String::from("r"), String::from("b")]}];
+        let bindings = PathBindings::new(&graph, &paths);
+        match bindings.get_item("p") {
+            EvalResult::Path(path) => {
+                assert_eq!(1, path.len());
+                assert!(!path.is_forward(0));
+                assert_eq!(Some(2), path.get_endpoints(0).and_then(|(source, _)| source.get_id()));
+            },
+            other => panic!("unexpected path {:?}", other),
+        }
+        let mut length = FunctionCall::new("length");
+        length.args.push(ReturnExpression::Item(String::from("p")));
+        assert!(matches!(eval_function_call(&length, &bindings), EvalResult::Scalar(PropertyValue::PInteger(1))));
+        assert!(matches!(bindings.get_item("a"), EvalResult::Node(_)));
+    }
+}
@@ -0,0 +1,160 @@
+// MIT License
+//
+// Copyright (c) 2022 Alexandre RICCIARDI
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use std::time::Instant;
+
+use zawgl_core::graph_engine::GraphEngine;
+use zawgl_core::error::GraphError;
+use zawgl_core::graph_engine::planner::ScanType;
+use zawgl_core::model::{PropertyGraph, Status};
+use zawgl_cypher_query_model::{QueryStep, StepType};
+
+use super::{handle_query_step, flatten_results};
+use crate::procedures::{ProcedureRegistry, ProcedureError};
+use crate::projection::ResultTable;
+
+pub struct PatternPlan {
+    pub start_variable: Option<String>,
+    pub start_labels: Vec<String>,
+    pub scan_type: ScanType,
+    pub estimated_rows: f64,
+}
+
+pub struct StepPlan {
+    pub step_name: &'static str,
+    pub patterns: Vec<PatternPlan>,
+    pub rows: Option<usize>,
+    pub elapsed_micros: Option<u128>,
+}
+
+fn get_step_name(step_type: &StepType) -> &'static str {
+    match step_type {
+        StepType::MATCH => "MATCH",
+        StepType::OPTIONAL_MATCH => "OPTIONAL MATCH",
+        StepType::CREATE => "CREATE",
+        StepType::DELETE => "DELETE",
+        StepType::WHERE => "WHERE",
+        StepType::CREATE_CONSTRAINT => "CREATE CONSTRAINT",
+        StepType::UNWIND => "UNWIND",
+        StepType::CALL => "CALL",
+        StepType::SET => "SET",
+        StepType::REMOVE => "REMOVE",
+        StepType::FOREACH => "FOREACH",
+    }
+}
+
+fn plan_pattern(pattern: &PropertyGraph, graph_engine: &GraphEngine) -> Option<PatternPlan> {
+    let start = graph_engine.plan_pattern(pattern).into_iter().find(|plan| *pattern.get_node_ref(&plan.node).get_status() != Status::Create)?;
+    let start_node = pattern.get_node_ref(&start.node);
+    Some(PatternPlan{start_variable: start_node.get_var().clone(), start_labels: start_node.get_labels_ref().clone(),
+        scan_type: start.scan_type, estimated_rows: start.estimated_rows})
+}
+
+fn plan_step(step: &QueryStep, graph_engine: &GraphEngine) -> StepPlan {
+    let patterns = step.patterns.iter().filter_map(|pattern| plan_pattern(pattern, graph_engine)).collect();
+    StepPlan{step_name: get_step_name(&step.step_type), patterns: patterns, rows: None, elapsed_micros: None}
+}
+
+/// Describes how each step would be matched without executing the query.
+pub fn explain_query_steps(steps: &Vec<QueryStep>, graph_engine: &GraphEngine) -> Vec<StepPlan> {
+    steps.iter().map(|step| plan_step(step, graph_engine)).collect()
+}
+
+/// Executes the query and records the rows produced and the time spent by each step.
+pub fn profile_query_steps(steps: &Vec<QueryStep>, graph_engine: &mut GraphEngine) -> Result<(Vec<PropertyGraph>, Vec<StepPlan>), GraphError> {
+    let mut results = Vec::<Vec<PropertyGraph>>::new();
+    let mut plans = Vec::new();
+    for step in steps {
+        let mut plan = plan_step(step, graph_engine);
+        let start = Instant::now();
+        handle_query_step(step, &mut results, graph_engine)?;
+        plan.elapsed_micros = Some(start.elapsed().as_micros());
+        plan.rows = Some(results.iter().map(|res| res.len()).sum());
+        plans.push(plan);
+    }
+    Ok((flatten_results(results), plans))
+}
+
+/// Calls the procedure of the steps and records the rows it yields.
+pub fn profile_procedure_call(steps: &Vec<QueryStep>, graph_engine: &mut GraphEngine, procedures: &ProcedureRegistry) -> Result<(ResultTable, Vec<StepPlan>), ProcedureError> {
+    let mut table = None;
+    let mut plans = Vec::new();
+    for step in steps {
+        let mut plan = plan_step(step, graph_engine);
+        if let Some(call) = &step.procedure {
+            let start = Instant::now();
+            let records = procedures.call(call, graph_engine)?;
+            plan.elapsed_micros = Some(start.elapsed().as_micros());
+            plan.rows = Some(records.rows.len());
+            table = Some(records);
+        }
+        plans.push(plan);
+    }
+    Ok((table.unwrap_or_else(|| ResultTable::new(Vec::new())), plans))
+}
+
+#[cfg(test)]
+mod test_explain {
+    use super::*;
+    use zawgl_core::model::Node;
+    use zawgl_core::model::init::InitContext;
+    use zawgl_core::test_utils::build_dir_path_and_rm_old;
+
+    fn make_match_step(labels: Vec<&str>) -> QueryStep {
+        let mut step = QueryStep::new(StepType::MATCH);
+        let mut pattern = PropertyGraph::new();
+        for label in labels {
+            let mut node = Node::new();
+            node.set_labels(vec![label.to_string()]);
+            node.set_var(&label.to_lowercase());
+            pattern.add_node(node);
+        }
+        step.patterns.push(pattern);
+        step
+    }
+
+    #[test]
+    fn test_explain_and_profile() {
+        let main_dir = build_dir_path_and_rm_old("test_explain_and_profile").expect("db path");
+        let conf = InitContext::new(&main_dir).expect("can't create context");
+        let mut graph_engine = GraphEngine::new(&conf);
+        for label in ["Person", "Person", "Person", "City"] {
+            let mut node = Node::new();
+            node.set_labels(vec![label.to_string()]);
+            graph_engine.create_node(&node).expect("node");
+        }
+        let steps = vec![make_match_step(vec!["Person", "City"])];
+
+        let plans = explain_query_steps(&steps, &graph_engine);
+        assert_eq!(1, plans.len());
+        assert_eq!("MATCH", plans[0].step_name);
+        assert_eq!(Some("city".to_string()), plans[0].patterns[0].start_variable);
+        assert_eq!(ScanType::LabelScan, plans[0].patterns[0].scan_type);
+        assert_eq!(1.0, plans[0].patterns[0].estimated_rows);
+        assert_eq!(None, plans[0].rows);
+
+        let (graphs, plans) = profile_query_steps(&steps, &mut graph_engine).expect("profile");
+        assert_eq!(3, graphs.len());
+        assert_eq!(Some(3), plans[0].rows);
+        assert!(plans[0].elapsed_micros.is_some());
+    }
+}
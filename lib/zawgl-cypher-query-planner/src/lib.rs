@@ -19,12 +19,81 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-use zawgl_core::{model::*, graph_engine::GraphEngine};
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::sync::Arc;
+
+use thiserror::Error;
+use zawgl_core::{model::*, graph_engine::GraphEngine, error::GraphError};
 
 mod pattern_builder;
+pub mod evaluator;
+pub mod aggregation;
+pub mod projection;
+pub mod explain;
+pub mod temporal;
+pub mod procedures;
+pub mod count;
+
+use evaluator::{eval_expression, is_true, Bindings, EvalResult};
+use pattern_builder::{bind_pattern, build_pattern, merge_patterns};
+use explain::{StepPlan, explain_query_steps, profile_query_steps, profile_procedure_call};
+use zawgl_cypher_query_model::{QueryStep, StepType, ExecutionMode};
+use zawgl_cypher_query_model::model::{Expression, UpdateItem};
+use procedures::{ProcedureRegistry, ProcedureError};
+use projection::ResultTable;
+
+/// Failures of a query, the engine errors carry the store and the record involved.
+#[derive(Debug, Error)]
+pub enum QueryError {
+    #[error(transparent)]
+    Procedure(#[from] ProcedureError),
+    #[error(transparent)]
+    Graph(#[from] GraphError),
+}
+
+pub struct ResultPage {
+    pub skip: usize,
+    pub limit: usize,
+}
+
+impl ResultPage {
+    pub fn new(skip: usize, limit: usize) -> Self {
+        ResultPage {skip: skip, limit: limit}
+    }
+}
+
+pub type ResultComparator = Arc<dyn Fn(&PropertyGraph, &PropertyGraph) -> Ordering + Send + Sync>;
 
-use pattern_builder::{build_pattern, merge_patterns};
-use zawgl_cypher_query_model::{QueryStep, StepType};
+/// Order of the matched graphs, it is applied before the page is taken.
+#[derive(Clone)]
+pub enum ResultOrder {
+    /// Order of the matcher, it depends on the store layout. Single pattern pages are streamed.
+    Matcher,
+    /// Graphs are sorted by the ids of their nodes then of their relationships, in pattern order.
+    Id,
+    Key(ResultComparator),
+}
+
+fn get_ids_key(graph: &PropertyGraph) -> (Vec<Option<u64>>, Vec<Option<u64>>) {
+    (graph.get_nodes().iter().map(|node| node.get_id()).collect(), graph.get_relationships().iter().map(|rel| rel.get_id()).collect())
+}
+
+fn sort_graphs(graphs: &mut [PropertyGraph], order: &ResultOrder) {
+    match order {
+        ResultOrder::Matcher => {},
+        ResultOrder::Id => graphs.sort_by_cached_key(get_ids_key),
+        ResultOrder::Key(compare) => graphs.sort_by(|graph, other| compare(graph, other)),
+    }
+}
+
+pub struct PagedResult {
+    pub graphs: Vec<PropertyGraph>,
+    pub has_more: bool,
+    pub plan: Option<Vec<StepPlan>>,
+    /// Rows yielded by a procedure call.
+    pub records: Option<ResultTable>,
+}
 
 fn make_cartesian_product(pools: &Vec<Vec<PropertyGraph>>) -> Vec<Vec<&PropertyGraph>> {
     let mut res = vec![];
@@ -49,63 +118,297 @@ fn make_cartesian_product(pools: &Vec<Vec<PropertyGraph>>) -> Vec<Vec<&PropertyG
     res
 }
 
-pub fn handle_query_steps<'a>(steps: &Vec<QueryStep>, graph_engine: &mut GraphEngine) -> Vec<PropertyGraph> {
-    let mut results = Vec::<Vec<PropertyGraph>>::new();
-    for step in steps {
-        match step.step_type {
-            StepType::MATCH => {
-                if results.is_empty() {
-                    for pattern in &step.patterns {
-                        let matched = graph_engine.match_pattern(pattern);
-                        if let Some(res) = matched {
-                            results.push(res);
-                        }
+/// Matched graph a where condition is evaluated against, its pattern predicates are matched with the ids of its variables
+/// and only their first match is looked for, so that the graph is kept or filtered out without being expanded.
+struct RowBindings<'a> {
+    row: &'a PropertyGraph,
+    graph_engine: RefCell<&'a mut GraphEngine>,
+}
+
+impl Bindings for RowBindings<'_> {
+    fn get_item(&self, item_name: &str) -> EvalResult {
+        self.row.get_item(item_name)
+    }
+
+    fn has_match(&self, pattern: &PropertyGraph) -> EvalResult {
+        let bound = bind_pattern(pattern, self.row);
+        let found = self.graph_engine.borrow_mut().match_pattern_cursor(&bound).is_some_and(|mut cursor| cursor.next().is_some());
+        EvalResult::Scalar(PropertyValue::PBool(found))
+    }
+}
+
+fn has_pattern_predicate(expr: &Expression) -> bool {
+    match expr {
+        Expression::Pattern(_) => true,
+        Expression::Not(operand) | Expression::IsNull(operand) | Expression::IsNotNull(operand) => has_pattern_predicate(operand),
+        Expression::BinaryOperation(first, _, second) => has_pattern_predicate(first) || has_pattern_predicate(second),
+        _ => false,
+    }
+}
+
+fn handle_query_step(step: &QueryStep, results: &mut Vec<Vec<PropertyGraph>>, graph_engine: &mut GraphEngine) -> Result<(), GraphError> {
+    match step.step_type {
+        StepType::MATCH => {
+            if results.is_empty() {
+                for pattern in &step.patterns {
+                    let matched = graph_engine.match_pattern(pattern);
+                    if let Some(res) = matched {
+                        results.push(res);
                     }
-                } else {
-                    let mut new_res = Vec::new();
-                    for pattern in &step.patterns {
-                        let products = make_cartesian_product(&results);
-                        for product in &products {
-                            let merge_sources = merge_patterns(product);
-                            let merge = build_pattern(&merge_sources, pattern);
-                            let matched = graph_engine.match_pattern(&merge);
-                            if let Some(c) = matched {
-                                new_res.push(c);
-                            }
+                }
+            } else {
+                let mut new_res = Vec::new();
+                for pattern in &step.patterns {
+                    let products = make_cartesian_product(results);
+                    for product in &products {
+                        let merge_sources = merge_patterns(product);
+                        let merge = build_pattern(&merge_sources, pattern);
+                        let matched = graph_engine.match_pattern(&merge);
+                        if let Some(c) = matched {
+                            new_res.push(c);
                         }
                     }
-                    results = new_res;
                 }
-            },
-            StepType::CREATE => {
-                if results.is_empty() {
-                    let created = graph_engine.match_patterns_and_create(&step.patterns);
-                    if let Some(created_graphs) = created {
-                        results = created_graphs;
+                *results = new_res;
+            }
+        },
+        StepType::OPTIONAL_MATCH => {
+            if results.is_empty() {
+                for pattern in &step.patterns {
+                    let matched = graph_engine.match_pattern(pattern);
+                    if let Some(res) = matched {
+                        results.push(res);
                     }
-                } else {
-                    let mut to_match_and_create = Vec::new();
-                    for pattern in &step.patterns {
-                        let products = make_cartesian_product(&results);
-                        for product in &products {
-                            let merge_sources = merge_patterns(product);
-                            let merge = build_pattern(&merge_sources, pattern);
-                            to_match_and_create.push(merge);
+                }
+            } else {
+                let mut new_res = Vec::new();
+                for pattern in &step.patterns {
+                    let products = make_cartesian_product(results);
+                    for product in &products {
+                        let merge_sources = merge_patterns(product);
+                        let merge = build_pattern(&merge_sources, pattern);
+                        match graph_engine.match_pattern(&merge) {
+                            Some(c) if !c.is_empty() => new_res.push(c),
+                            _ => new_res.push(vec![merge_sources]),
                         }
                     }
-                    let created = graph_engine.match_patterns_and_create(&to_match_and_create);
-                    if let Some(created_graphs) = created {
-                        results = created_graphs;
+                }
+                *results = new_res;
+            }
+        },
+        StepType::CREATE => {
+            if results.is_empty() {
+                *results = graph_engine.match_patterns_and_create(&step.patterns)?;
+            } else {
+                let mut to_match_and_create = Vec::new();
+                for pattern in &step.patterns {
+                    let products = make_cartesian_product(results);
+                    for product in &products {
+                        let merge_sources = merge_patterns(product);
+                        let merge = build_pattern(&merge_sources, pattern);
+                        to_match_and_create.push(merge);
+                    }
+                }
+                *results = graph_engine.match_patterns_and_create(&to_match_and_create)?;
+            }
+        },
+        StepType::DELETE => {
+            if results.is_empty() {
+                graph_engine.match_patterns_and_delete(&step.patterns)?;
+            } else {
+                let mut to_match_and_delete = Vec::new();
+                for pattern in &step.patterns {
+                    let products = make_cartesian_product(results);
+                    for product in &products {
+                        let merge_sources = merge_patterns(product);
+                        let merge = build_pattern(&merge_sources, pattern);
+                        to_match_and_delete.push(merge);
+                    }
+                }
+                graph_engine.match_patterns_and_delete(&to_match_and_delete)?;
+            }
+            results.clear();
+        },
+        StepType::WHERE => {
+            if let Some(condition) = step.where_clause.as_ref().and_then(|where_clause| where_clause.condition.as_ref()) {
+                let mut filtered = Vec::new();
+                for product in &make_cartesian_product(results) {
+                    let merge = merge_patterns(product);
+                    let bindings = RowBindings {row: &merge, graph_engine: RefCell::new(&mut *graph_engine)};
+                    if is_true(&eval_expression(condition, &bindings)) {
+                        filtered.push(merge);
                     }
                 }
-            },
-            StepType::DELETE => todo!(),
-            StepType::WHERE => todo!(),
+                *results = vec![filtered];
+            }
+        },
+        StepType::CREATE_CONSTRAINT => {
+            if let Some(constraint) = &step.constraint {
+                graph_engine.create_unique_constraint(constraint)?;
+            }
+        },
+        StepType::UNWIND => {
+            let mut unwound = Vec::new();
+            for batch in &step.unwind_batches {
+                let mut batch_results = results.clone();
+                for batch_step in batch {
+                    handle_query_step(batch_step, &mut batch_results, graph_engine)?;
+                }
+                unwound.append(&mut batch_results);
+            }
+            *results = unwound;
+        },
+        // the updates of each list element are applied to the rows which are then kept unchanged
+        StepType::FOREACH => {
+            for batch in &step.unwind_batches {
+                let mut batch_results = results.clone();
+                for batch_step in batch {
+                    handle_query_step(batch_step, &mut batch_results, graph_engine)?;
+                }
+            }
+        },
+        StepType::SET | StepType::REMOVE => {
+            let mut updated = Vec::new();
+            for product in &make_cartesian_product(results) {
+                let mut merge = merge_patterns(product);
+                for update in &step.updates {
+                    apply_update(update, &step.step_type, &mut merge, graph_engine)?;
+                }
+                updated.push(merge);
+            }
+            *results = vec![updated];
+        },
+        // procedures are called alone by handle_query as they yield rows instead of graphs
+        StepType::CALL => {},
+    }
+    Ok(())
+}
+
+/// Updated elements are reloaded so that the following steps see their labels and properties.
+/// A label breaking a unique constraint or an element which can't be updated fails the step.
+fn apply_update(update: &UpdateItem, step_type: &StepType, graph: &mut PropertyGraph, graph_engine: &mut GraphEngine) -> Result<(), GraphError> {
+    match update {
+        UpdateItem::Labels(var, labels) => {
+            for node in graph.get_nodes_mut() {
+                if node.get_var().as_ref() != Some(var) {
+                    continue;
+                }
+                if let Some(node_id) = node.get_id() {
+                    let updated = if *step_type == StepType::SET {
+                        graph_engine.add_node_labels(node_id, labels)?
+                    } else {
+                        graph_engine.remove_node_labels(node_id, labels)?
+                    };
+                    node.set_labels(updated.get_labels_ref().clone());
+                }
+            }
+        },
+        UpdateItem::Property(var, name) => {
+            for node in graph.get_nodes_mut() {
+                if let Some(node_id) = node.get_id().filter(|_| node.get_var().as_ref() == Some(var)) {
+                    let updated = graph_engine.remove_node_property(node_id, name)?;
+                    node.set_properties(updated.get_properties_ref().clone());
+                }
+            }
+            for rel in graph.get_relationships_mut() {
+                if let Some(rel_id) = rel.get_id().filter(|_| rel.get_var().as_ref() == Some(var)) {
+                    let updated = graph_engine.remove_relationship_property(rel_id, name)?;
+                    rel.set_properties(updated.get_properties_ref().clone());
+                }
+            }
+        },
+    }
+    Ok(())
+}
+
+fn flatten_results(results: Vec<Vec<PropertyGraph>>) -> Vec<PropertyGraph> {
+    results.into_iter().flatten().collect()
+}
+
+pub fn handle_query_steps<'a>(steps: &Vec<QueryStep>, graph_engine: &mut GraphEngine) -> Result<Vec<PropertyGraph>, GraphError> {
+    let mut results = Vec::<Vec<PropertyGraph>>::new();
+    for step in steps {
+        if graph_engine.get_query_context().get_abort().is_some() {
+            break;
         }
+        handle_query_step(step, &mut results, graph_engine)?;
     }
-    let mut result = Vec::new();
-    for res in &mut results {
-        result.append(res);
+    Ok(flatten_results(results))
+}
+
+fn get_streamable_pattern(steps: &Vec<QueryStep>) -> Option<(&PropertyGraph, Option<&Expression>)> {
+    let mut steps_iter = steps.iter();
+    let match_step = steps_iter.next()?;
+    if !matches!(match_step.step_type, StepType::MATCH) || match_step.patterns.len() != 1 {
+        return None;
     }
-    result
-}
\ No newline at end of file
+    let condition = match steps_iter.next() {
+        Some(step) if matches!(step.step_type, StepType::WHERE) => step.where_clause.as_ref().and_then(|where_clause| where_clause.condition.as_ref()),
+        Some(_) => return None,
+        None => None,
+    };
+    // pattern predicates need the graph engine the cursor is read from
+    if condition.is_some_and(has_pattern_predicate) {
+        return None;
+    }
+    if steps_iter.next().is_some() {
+        return None;
+    }
+    Some((&match_step.patterns[0], condition))
+}
+
+/// Returns a page of the results, single pattern matches in matcher order are streamed from the matcher
+/// so that only the requested page is kept in memory.
+pub fn handle_query_steps_page(steps: &Vec<QueryStep>, graph_engine: &mut GraphEngine, page: Option<&ResultPage>, order: &ResultOrder) -> Result<PagedResult, GraphError> {
+    let page = match page {
+        Some(page) => page,
+        None => {
+            let mut graphs = handle_query_steps(steps, graph_engine)?;
+            sort_graphs(&mut graphs, order);
+            return Ok(PagedResult {graphs: graphs, has_more: false, plan: None, records: None});
+        },
+    };
+    let streamable_pattern = match order {
+        ResultOrder::Matcher => get_streamable_pattern(steps),
+        _ => None,
+    };
+    let mut graphs: Vec<PropertyGraph> = match streamable_pattern {
+        Some((pattern, condition)) => {
+            match graph_engine.match_pattern_cursor(pattern) {
+                Some(cursor) => cursor
+                    .filter(|graph| condition.map_or(true, |cond| is_true(&eval_expression(cond, &merge_patterns(&vec![graph])))))
+                    .skip(page.skip).take(page.limit + 1).collect(),
+                None => Vec::new(),
+            }
+        },
+        None => {
+            let mut graphs = handle_query_steps(steps, graph_engine)?;
+            sort_graphs(&mut graphs, order);
+            graphs.into_iter().skip(page.skip).take(page.limit + 1).collect()
+        },
+    };
+    let has_more = graphs.len() > page.limit;
+    graphs.truncate(page.limit);
+    Ok(PagedResult {graphs: graphs, has_more: has_more, plan: None, records: None})
+}
+
+pub fn handle_query(steps: &Vec<QueryStep>, graph_engine: &mut GraphEngine, procedures: &ProcedureRegistry, page: Option<&ResultPage>, mode: ExecutionMode, order: &ResultOrder) -> Result<PagedResult, QueryError> {
+    let procedure_call = steps.iter().find_map(|step| step.procedure.as_ref());
+    match (mode, procedure_call) {
+        (ExecutionMode::Run, Some(call)) => {
+            let table = procedures.call(call, graph_engine)?;
+            Ok(PagedResult {graphs: Vec::new(), has_more: false, plan: None, records: Some(table)})
+        },
+        (ExecutionMode::Run, None) => Ok(handle_query_steps_page(steps, graph_engine, page, order)?),
+        (ExecutionMode::Explain, _) => Ok(PagedResult {graphs: Vec::new(), has_more: false, plan: Some(explain_query_steps(steps, graph_engine)), records: None}),
+        (ExecutionMode::Profile, Some(_)) => {
+            let (table, plan) = profile_procedure_call(steps, graph_engine, procedures)?;
+            Ok(PagedResult {graphs: Vec::new(), has_more: false, plan: Some(plan), records: Some(table)})
+        },
+        (ExecutionMode::Profile, None) => {
+            let (mut graphs, plan) = profile_query_steps(steps, graph_engine)?;
+            sort_graphs(&mut graphs, order);
+            Ok(PagedResult {graphs: graphs, has_more: false, plan: Some(plan), records: None})
+        },
+    }
+}
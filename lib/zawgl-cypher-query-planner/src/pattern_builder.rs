@@ -186,4 +186,20 @@ pub fn merge_patterns(patterns: &Vec<&PropertyGraph>) -> PropertyGraph {
         pattern_id += 1;
     }
     result
-}
\ No newline at end of file
+}
+
+/// Anchors the nodes and relationships of a pattern on the ones bound to the same variables in a matched graph.
+pub fn bind_pattern(pattern: &PropertyGraph, bindings: &PropertyGraph) -> PropertyGraph {
+    let mut result = pattern.clone();
+    for node in result.get_nodes_mut() {
+        if let Some(bound) = bindings.get_nodes().iter().find(|bound| node.get_var().is_some() && bound.get_var() == node.get_var()) {
+            node.set_id(bound.get_id());
+        }
+    }
+    for rel in result.get_relationships_mut() {
+        if let Some(bound) = bindings.get_relationships().iter().find(|bound| rel.get_var().is_some() && bound.get_var() == rel.get_var()) {
+            rel.set_id(bound.get_id());
+        }
+    }
+    result
+}
@@ -0,0 +1,422 @@
+// MIT License
+//
+// Copyright (c) 2022 Alexandre RICCIARDI
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+use thiserror::Error;
+use zawgl_core::graph_engine::GraphEngine;
+use zawgl_core::model::{Node, PropertyGraph, PropertyValue, Status};
+use zawgl_cypher_query_model::model::ProcedureCall;
+
+use crate::evaluator::{EvalResult, eval_expression};
+use crate::projection::ResultTable;
+use crate::count::COUNT_PROCEDURE_NAME;
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum ProcedureError {
+    #[error("unknown procedure {0}")]
+    UnknownProcedure(String),
+    /// Expected then given number of arguments.
+    #[error("the procedure expects {0} arguments, {1} were given")]
+    WrongArgumentCount(usize, usize),
+    #[error("invalid argument at position {0}")]
+    InvalidArgument(usize),
+    #[error("the procedure yields no column {0}")]
+    UnknownColumn(String),
+}
+
+pub type ProcedureResult = Result<Vec<Vec<EvalResult>>, ProcedureError>;
+
+type ProcedureBody = Box<dyn Fn(&mut GraphEngine, &[PropertyValue]) -> ProcedureResult + Send + Sync>;
+
+pub struct Procedure {
+    name: String,
+    parameters: Vec<String>,
+    required_parameters: usize,
+    columns: Vec<String>,
+    body: ProcedureBody,
+}
+
+impl Procedure {
+    /// The body receives one value per parameter and returns rows with one value per column.
+    pub fn new<F>(name: &str, parameters: &[&str], columns: &[&str], body: F) -> Self
+        where F: Fn(&mut GraphEngine, &[PropertyValue]) -> ProcedureResult + Send + Sync + 'static {
+        Procedure{name: String::from(name), parameters: parameters.iter().map(|p| String::from(*p)).collect(), required_parameters: parameters.len(),
+            columns: columns.iter().map(|c| String::from(*c)).collect(), body: Box::new(body)}
+    }
+
+    /// Appends parameters that may be left out of a call, the body receives null for the missing ones.
+    pub fn with_optional_parameters(mut self, parameters: &[&str]) -> Self {
+        self.parameters.extend(parameters.iter().map(|p| String::from(*p)));
+        self
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_parameters(&self) -> &Vec<String> {
+        &self.parameters
+    }
+
+    pub fn get_required_parameters(&self) -> &[String] {
+        &self.parameters[..self.required_parameters]
+    }
+
+    pub fn get_columns(&self) -> &Vec<String> {
+        &self.columns
+    }
+}
+
+fn db_constraints(graph_engine: &mut GraphEngine, _args: &[PropertyValue]) -> ProcedureResult {
+    Ok(graph_engine.get_constraints().into_iter().map(|constraint| vec![
+        EvalResult::Scalar(PropertyValue::PString(constraint.label)),
+        EvalResult::Scalar(PropertyValue::PString(constraint.property_name)),
+    ]).collect())
+}
+
+fn make_name_rows(names: Vec<String>) -> ProcedureResult {
+    Ok(names.into_iter().map(|name| vec![EvalResult::Scalar(PropertyValue::PString(name))]).collect())
+}
+
+fn db_labels(graph_engine: &mut GraphEngine, _args: &[PropertyValue]) -> ProcedureResult {
+    make_name_rows(graph_engine.get_statistics().get_labels())
+}
+
+fn db_relationship_types(graph_engine: &mut GraphEngine, _args: &[PropertyValue]) -> ProcedureResult {
+    make_name_rows(graph_engine.get_statistics().get_relationship_types())
+}
+
+fn db_property_keys(graph_engine: &mut GraphEngine, _args: &[PropertyValue]) -> ProcedureResult {
+    make_name_rows(graph_engine.get_statistics().get_property_keys())
+}
+
+/// Number of nodes with the label, or of relationships with the type, all of them when it is null.
+fn db_stats_count(graph_engine: &mut GraphEngine, args: &[PropertyValue]) -> ProcedureResult {
+    let statistics = graph_engine.get_statistics();
+    let count = match (&args[0], &args[1]) {
+        (PropertyValue::PString(kind), PropertyValue::PNull) if kind == "node" => statistics.get_nodes_count(),
+        (PropertyValue::PString(kind), PropertyValue::PString(label)) if kind == "node" => statistics.get_label_count(label),
+        (PropertyValue::PString(kind), PropertyValue::PNull) if kind == "relationship" => statistics.get_relationships_count(),
+        (PropertyValue::PString(kind), PropertyValue::PString(rel_type)) if kind == "relationship" => statistics.get_relationship_type_count(rel_type),
+        (PropertyValue::PString(_), _) => return Err(ProcedureError::InvalidArgument(1)),
+        _ => return Err(ProcedureError::InvalidArgument(0)),
+    };
+    Ok(vec![vec![EvalResult::Scalar(PropertyValue::PInteger(count as i64))]])
+}
+
+pub const DISTRIBUTION_PROCEDURE_NAME: &str = "db.stats.propertyDistribution";
+const DISTRIBUTION_BUCKETS: usize = 10;
+
+fn get_type_rank(value: &PropertyValue) -> u8 {
+    match value {
+        PropertyValue::PInteger(_) | PropertyValue::PFloat(_) => 0,
+        PropertyValue::PString(_) => 1,
+        PropertyValue::PBool(_) => 2,
+        PropertyValue::PDate(_) => 3,
+        PropertyValue::PDateTime(_) => 4,
+        PropertyValue::PDuration(_) => 5,
+        PropertyValue::PList(_) => 6,
+        PropertyValue::PMap(_) => 7,
+        PropertyValue::PNull => 8,
+    }
+}
+
+/// Total order of the sampled values, integers and floats are compared together and other values are grouped by type.
+fn compare_sampled_values(first: &PropertyValue, second: &PropertyValue) -> Ordering {
+    match (first, second) {
+        (PropertyValue::PInteger(i), PropertyValue::PFloat(f)) => (*i as f64).total_cmp(f),
+        (PropertyValue::PFloat(f), PropertyValue::PInteger(i)) => f.total_cmp(&(*i as f64)),
+        (PropertyValue::PFloat(f0), PropertyValue::PFloat(f1)) => f0.total_cmp(f1),
+        _ => get_type_rank(first).cmp(&get_type_rank(second)).then_with(|| first.partial_cmp(second).unwrap_or(Ordering::Equal)),
+    }
+}
+
+/// Equi-depth histogram of the values, a value is never split across two buckets.
+fn make_histogram(mut values: Vec<PropertyValue>, sampled: usize) -> Vec<Vec<EvalResult>> {
+    values.sort_by(compare_sampled_values);
+    let depth = values.len().div_ceil(DISTRIBUTION_BUCKETS).max(1);
+    let mut rows = Vec::new();
+    let mut start = 0;
+    while start < values.len() {
+        let mut end = (start + depth).min(values.len());
+        while end < values.len() && compare_sampled_values(&values[end - 1], &values[end]) == Ordering::Equal {
+            end += 1;
+        }
+        let bucket = &values[start..end];
+        let distinct = 1 + bucket.windows(2).filter(|pair| compare_sampled_values(&pair[0], &pair[1]) != Ordering::Equal).count();
+        rows.push(vec![
+            EvalResult::Scalar(bucket[0].clone()),
+            EvalResult::Scalar(bucket[bucket.len() - 1].clone()),
+            EvalResult::Scalar(PropertyValue::PInteger(bucket.len() as i64)),
+            EvalResult::Scalar(PropertyValue::PInteger(distinct as i64)),
+            EvalResult::Scalar(PropertyValue::PFloat(bucket.len() as f64 / sampled as f64)),
+        ]);
+        start = end;
+    }
+    rows
+}
+
+/// Histogram of the values of a property of the nodes with the label, of all nodes when it is null.
+/// Nodes are read from the stores, evenly spaced ones are read when a sample size is given.
+/// The fraction of a bucket is relative to the read nodes, those without the property included.
+fn db_stats_property_distribution(graph_engine: &mut GraphEngine, args: &[PropertyValue]) -> ProcedureResult {
+    let statistics = graph_engine.get_statistics();
+    let mut node = Node::new();
+    node.set_status(Status::Match);
+    let nodes_count = match &args[0] {
+        PropertyValue::PString(label) => {
+            node.set_labels(vec![label.clone()]);
+            statistics.get_label_count(label)
+        },
+        PropertyValue::PNull => statistics.get_nodes_count(),
+        _ => return Err(ProcedureError::InvalidArgument(0)),
+    };
+    let key = match &args[1] {
+        PropertyValue::PString(key) => key,
+        _ => return Err(ProcedureError::InvalidArgument(1)),
+    };
+    let sample_size = match &args[2] {
+        PropertyValue::PInteger(size) if *size > 0 => *size as usize,
+        PropertyValue::PNull => usize::MAX,
+        _ => return Err(ProcedureError::InvalidArgument(2)),
+    };
+    let step = (nodes_count as usize / sample_size).max(1);
+    let mut pattern = PropertyGraph::new();
+    pattern.add_node(node);
+    let mut values = Vec::new();
+    let mut sampled = 0;
+    if let Some(cursor) = graph_engine.match_pattern_cursor(&pattern) {
+        for graph in cursor.step_by(step).take(sample_size) {
+            sampled += 1;
+            for node in graph.get_nodes() {
+                values.extend(node.get_properties_ref().iter().filter(|property| property.get_name() == key).map(|property| property.get_value().clone()));
+            }
+        }
+    }
+    Ok(make_histogram(values, sampled))
+}
+
+fn make_argument(index: usize, value: EvalResult) -> Result<PropertyValue, ProcedureError> {
+    match value {
+        EvalResult::Null => Ok(PropertyValue::PNull),
+        EvalResult::Scalar(value) => Ok(value),
+        _ => Err(ProcedureError::InvalidArgument(index)),
+    }
+}
+
+/// Procedures callable with CALL, registered under their dotted name.
+pub struct ProcedureRegistry {
+    procedures: BTreeMap<String, Procedure>,
+}
+
+impl ProcedureRegistry {
+    pub fn new() -> Self {
+        ProcedureRegistry{procedures: BTreeMap::new()}
+    }
+
+    pub fn with_builtins() -> Self {
+        let mut registry = Self::new();
+        registry.register(Procedure::new("db.constraints", &[], &["label", "property"], db_constraints));
+        registry.register(Procedure::new("db.labels", &[], &["label"], db_labels));
+        registry.register(Procedure::new("db.relationshipTypes", &[], &["relationshipType"], db_relationship_types));
+        registry.register(Procedure::new("db.propertyKeys", &[], &["propertyKey"], db_property_keys));
+        registry.register(Procedure::new(COUNT_PROCEDURE_NAME, &["kind", "name"], &["count"], db_stats_count));
+        registry.register(Procedure::new(DISTRIBUTION_PROCEDURE_NAME, &["label", "key"], &["lower", "upper", "count", "distinct", "fraction"], db_stats_property_distribution)
+            .with_optional_parameters(&["sampleSize"]));
+        registry
+    }
+
+    /// Registers a procedure, returning the one previously registered under the same name.
+    pub fn register(&mut self, procedure: Procedure) -> Option<Procedure> {
+        self.procedures.insert(procedure.name.clone(), procedure)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Procedure> {
+        self.procedures.get(name)
+    }
+
+    pub fn get_procedures(&self) -> impl Iterator<Item = &Procedure> {
+        self.procedures.values()
+    }
+
+    /// Evaluates the arguments, runs the procedure and keeps the yielded columns of its rows.
+    pub fn call(&self, call: &ProcedureCall, graph_engine: &mut GraphEngine) -> Result<ResultTable, ProcedureError> {
+        let procedure = self.get(&call.name).ok_or_else(|| ProcedureError::UnknownProcedure(call.name.clone()))?;
+        if call.args.len() < procedure.required_parameters {
+            return Err(ProcedureError::WrongArgumentCount(procedure.required_parameters, call.args.len()));
+        }
+        if call.args.len() > procedure.parameters.len() {
+            return Err(ProcedureError::WrongArgumentCount(procedure.parameters.len(), call.args.len()));
+        }
+        let no_bindings = PropertyGraph::new();
+        let mut args = call.args.iter().enumerate()
+            .map(|(index, arg)| make_argument(index, eval_expression(arg, &no_bindings)))
+            .collect::<Result<Vec<PropertyValue>, ProcedureError>>()?;
+        args.resize(procedure.parameters.len(), PropertyValue::PNull);
+        let columns = if call.yields.is_empty() { procedure.columns.clone() } else { call.yields.clone() };
+        let indexes = columns.iter()
+            .map(|column| procedure.columns.iter().position(|c| c == column).ok_or_else(|| ProcedureError::UnknownColumn(column.clone())))
+            .collect::<Result<Vec<usize>, ProcedureError>>()?;
+        let mut table = ResultTable::new(columns);
+        for row in (procedure.body)(graph_engine, &args)? {
+            table.rows.push(indexes.iter().map(|index| row.get(*index).cloned().unwrap_or(EvalResult::Null)).collect());
+        }
+        Ok(table)
+    }
+}
+
+#[cfg(test)]
+mod test_procedures {
+    use super::*;
+    use zawgl_core::model::{Node, Property, Relationship};
+    use zawgl_core::model::constraints::UniqueConstraint;
+    use zawgl_core::model::init::InitContext;
+    use zawgl_core::test_utils::build_dir_path_and_rm_old;
+    use zawgl_cypher_query_model::model::Expression;
+
+    fn repeat(_graph_engine: &mut GraphEngine, args: &[PropertyValue]) -> ProcedureResult {
+        match &args[1] {
+            PropertyValue::PInteger(times) => Ok((0..*times).map(|index| vec![EvalResult::Scalar(PropertyValue::PInteger(index)), EvalResult::Scalar(args[0].clone())]).collect()),
+            _ => Err(ProcedureError::InvalidArgument(1)),
+        }
+    }
+
+    #[test]
+    fn test_call_procedures() {
+        let main_dir = build_dir_path_and_rm_old("test_call_procedures").expect("db path");
+        let conf = InitContext::new(&main_dir).expect("can't create context");
+        let mut graph_engine = GraphEngine::new(&conf);
+        graph_engine.create_unique_constraint(&UniqueConstraint::new("Person", "email")).expect("constraint");
+
+        let mut registry = ProcedureRegistry::with_builtins();
+        assert!(registry.register(Procedure::new("test.repeat", &["value", "times"], &["index", "value"], repeat)).is_none());
+
+        let table = registry.call(&ProcedureCall::new("db.constraints"), &mut graph_engine).expect("constraints");
+        assert_eq!(vec!["label", "property"], table.columns);
+        assert_eq!(1, table.rows.len());
+        assert!(matches!(&table.rows[0][1], EvalResult::Scalar(PropertyValue::PString(name)) if name == "email"));
+
+        let mut call = ProcedureCall::new("test.repeat");
+        call.args.push(Expression::Value(PropertyValue::PString(String::from("a"))));
+        call.args.push(Expression::Value(PropertyValue::PInteger(3)));
+        call.yields.push(String::from("index"));
+        let table = registry.call(&call, &mut graph_engine).expect("repeat");
+        assert_eq!(vec!["index"], table.columns);
+        assert_eq!(3, table.rows.len());
+        assert!(matches!(table.rows[2][0], EvalResult::Scalar(PropertyValue::PInteger(2))));
+
+        call.yields.push(String::from("missing"));
+        assert_eq!(Some(ProcedureError::UnknownColumn(String::from("missing"))), registry.call(&call, &mut graph_engine).err());
+        call.args.pop();
+        assert_eq!(Some(ProcedureError::WrongArgumentCount(2, 1)), registry.call(&call, &mut graph_engine).err());
+        assert_eq!(Some(ProcedureError::UnknownProcedure(String::from("test.missing"))), registry.call(&ProcedureCall::new("test.missing"), &mut graph_engine).err());
+    }
+
+    fn get_names(table: &ResultTable) -> Vec<String> {
+        table.rows.iter().filter_map(|row| match &row[0] {
+            EvalResult::Scalar(PropertyValue::PString(name)) => Some(name.clone()),
+            _ => None,
+        }).collect()
+    }
+
+    #[test]
+    fn test_schema_procedures() {
+        let main_dir = build_dir_path_and_rm_old("test_schema_procedures").expect("db path");
+        let conf = InitContext::new(&main_dir).expect("can't create context");
+        let mut graph_engine = GraphEngine::new(&conf);
+        let mut graph = PropertyGraph::new();
+        let mut person = Node::new();
+        person.set_labels(vec![String::from("Person")]);
+        person.set_properties(vec![Property::new(String::from("name"), PropertyValue::PString(String::from("ada")))]);
+        let mut city = Node::new();
+        city.set_labels(vec![String::from("City")]);
+        let mut lives_in = Relationship::new();
+        lives_in.set_labels(vec![String::from("LIVES_IN")]);
+        lives_in.set_properties(vec![Property::new(String::from("since"), PropertyValue::PInteger(1843))]);
+        let person_id = graph.add_node(person);
+        let city_id = graph.add_node(city);
+        graph.add_relationship(lives_in, person_id, city_id);
+        graph_engine.create_graph(&graph).expect("graph");
+
+        let registry = ProcedureRegistry::with_builtins();
+        let labels = registry.call(&ProcedureCall::new("db.labels"), &mut graph_engine).expect("labels");
+        assert_eq!(vec!["label"], labels.columns);
+        assert_eq!(vec!["City", "Person"], get_names(&labels));
+        let types = registry.call(&ProcedureCall::new("db.relationshipTypes"), &mut graph_engine).expect("types");
+        assert_eq!(vec!["LIVES_IN"], get_names(&types));
+        let keys = registry.call(&ProcedureCall::new("db.propertyKeys"), &mut graph_engine).expect("keys");
+        assert_eq!(vec!["name", "since"], get_names(&keys));
+    }
+
+    fn get_integers(table: &ResultTable, column: usize) -> Vec<i64> {
+        table.rows.iter().filter_map(|row| match &row[column] {
+            EvalResult::Scalar(PropertyValue::PInteger(value)) => Some(*value),
+            _ => None,
+        }).collect()
+    }
+
+    #[test]
+    fn test_property_distribution() {
+        let main_dir = build_dir_path_and_rm_old("test_property_distribution").expect("db path");
+        let conf = InitContext::new(&main_dir).expect("can't create context");
+        let mut graph_engine = GraphEngine::new(&conf);
+        for index in 0..25 {
+            let mut person = Node::new();
+            person.set_labels(vec![String::from("Person")]);
+            if index < 20 {
+                person.set_properties(vec![Property::new(String::from("age"), PropertyValue::PInteger(index % 5 * 10))]);
+            }
+            graph_engine.create_node(&person).expect("person");
+        }
+        let mut city = Node::new();
+        city.set_labels(vec![String::from("City")]);
+        city.set_properties(vec![Property::new(String::from("age"), PropertyValue::PFloat(1000.5))]);
+        graph_engine.create_node(&city).expect("city");
+
+        let registry = ProcedureRegistry::with_builtins();
+        let mut call = ProcedureCall::new(DISTRIBUTION_PROCEDURE_NAME);
+        call.args.push(Expression::Value(PropertyValue::PString(String::from("Person"))));
+        call.args.push(Expression::Value(PropertyValue::PString(String::from("age"))));
+        let table = registry.call(&call, &mut graph_engine).expect("distribution");
+        assert_eq!(vec!["lower", "upper", "count", "distinct", "fraction"], table.columns);
+        assert_eq!(vec![0, 10, 20, 30, 40], get_integers(&table, 0));
+        assert_eq!(vec![4; 5], get_integers(&table, 2));
+        assert_eq!(vec![1; 5], get_integers(&table, 3));
+        assert!(matches!(table.rows[0][4], EvalResult::Scalar(PropertyValue::PFloat(fraction)) if fraction == 4.0 / 25.0));
+
+        call.args[0] = Expression::Value(PropertyValue::PNull);
+        let table = registry.call(&call, &mut graph_engine).expect("all nodes distribution");
+        assert_eq!(21, get_integers(&table, 2).iter().sum::<i64>());
+        assert!(matches!(&table.rows[5][1], EvalResult::Scalar(PropertyValue::PFloat(age)) if *age == 1000.5));
+
+        call.args.push(Expression::Value(PropertyValue::PInteger(5)));
+        let table = registry.call(&call, &mut graph_engine).expect("sampled distribution");
+        assert!(get_integers(&table, 2).iter().sum::<i64>() <= 5);
+
+        call.args[2] = Expression::Value(PropertyValue::PInteger(0));
+        assert_eq!(Some(ProcedureError::InvalidArgument(2)), registry.call(&call, &mut graph_engine).err());
+        call.args.truncate(1);
+        assert_eq!(Some(ProcedureError::WrongArgumentCount(2, 1)), registry.call(&call, &mut graph_engine).err());
+    }
+}
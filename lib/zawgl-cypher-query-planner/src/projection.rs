@@ -0,0 +1,183 @@
+// MIT License
+//
+// Copyright (c) 2022 Alexandre RICCIARDI
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use zawgl_cypher_query_model::model::ReturnClause;
+
+use std::collections::HashSet;
+
+use crate::aggregation::{aggregate, make_group_key, GroupKey};
+use crate::evaluator::{Bindings, EvalResult, eval_return_expression};
+
+pub struct ResultTable {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<EvalResult>>,
+}
+
+impl ResultTable {
+    pub fn new(columns: Vec<String>) -> Self {
+        ResultTable {columns, rows: Vec::new()}
+    }
+}
+
+/// Row of a result table, its values are bound to the column names.
+struct Record<'a> {
+    columns: &'a [String],
+    values: &'a [EvalResult],
+}
+
+impl Bindings for Record<'_> {
+    fn get_item(&self, item_name: &str) -> EvalResult {
+        self.columns.iter().position(|column| column == item_name).and_then(|index| self.values.get(index)).cloned().unwrap_or(EvalResult::Null)
+    }
+}
+
+/// Keeps the first occurrence of each row.
+/// Scalars are compared by value, floats by their bit pattern, while nodes and relationships
+/// are the same when they have the same id whatever their variables or properties.
+fn distinct_rows(rows: Vec<Vec<EvalResult>>) -> Vec<Vec<EvalResult>> {
+    let mut keys = HashSet::new();
+    rows.into_iter().filter(|row| keys.insert(row.iter().map(make_group_key).collect::<Vec<GroupKey>>())).collect()
+}
+
+/// Builds the rows of a RETURN clause, one per matched graph unless it aggregates them.
+pub fn project<G: Bindings>(return_clause: &ReturnClause, graphs: &[G]) -> ResultTable {
+    let mut table = ResultTable::new(return_clause.expressions.iter().map(|expr| expr.get_name()).collect());
+    if return_clause.has_aggregation() {
+        table.rows = aggregate(return_clause, graphs);
+    } else {
+        for graph in graphs {
+            table.rows.push(return_clause.expressions.iter().map(|expr| eval_return_expression(expr, graph)).collect());
+        }
+    }
+    if return_clause.distinct {
+        table.rows = distinct_rows(table.rows);
+    }
+    table
+}
+
+/// Builds the rows of a RETURN clause from the rows of a table such as the columns yielded by a procedure.
+pub fn project_table(return_clause: &ReturnClause, table: &ResultTable) -> ResultTable {
+    let records: Vec<Record> = table.rows.iter().map(|values| Record{columns: &table.columns, values: values}).collect();
+    project(return_clause, &records)
+}
+
+/// Appends the rows of a union part, the rows of a UNION without ALL are deduplicated.
+pub fn union_tables(mut table: ResultTable, part: ResultTable, all: bool) -> ResultTable {
+    table.rows.extend(part.rows);
+    if !all {
+        table.rows = distinct_rows(table.rows);
+    }
+    table
+}
+
+#[cfg(test)]
+mod test_projection {
+    use super::*;
+    use zawgl_core::model::{Node, Property, PropertyGraph, PropertyValue};
+    use zawgl_cypher_query_model::model::{FunctionCall, ItemPropertyName, ReturnExpression};
+
+    fn make_person(id: u64, name: &str) -> PropertyGraph {
+        let mut graph = PropertyGraph::new();
+        let mut node = Node::new();
+        node.set_id(Some(id));
+        node.set_var("p");
+        node.get_properties_mut().push(Property::new(String::from("name"), PropertyValue::PString(String::from(name))));
+        graph.add_node(node);
+        graph
+    }
+
+    #[test]
+    fn test_project_rows() {
+        let graphs = vec![make_person(1, "ada"), make_person(2, "alan")];
+        let mut ret = ReturnClause::new();
+        ret.expressions.push(ReturnExpression::Item(String::from("p")));
+        ret.expressions.push(ReturnExpression::ItemPropertyName(ItemPropertyName::new("p", "name")));
+        let table = project(&ret, &graphs);
+        assert_eq!(vec!["p", "p.name"], table.columns);
+        assert_eq!(2, table.rows.len());
+        assert!(matches!(&table.rows[0][0], EvalResult::Node(n) if n.get_id() == Some(1)));
+        assert!(matches!(&table.rows[1][1], EvalResult::Scalar(PropertyValue::PString(name)) if name == "alan"));
+
+        ret.expressions.push(ReturnExpression::FunctionCall(FunctionCall::new("count")));
+        let table = project(&ret, &graphs);
+        assert_eq!(vec!["p", "p.name", "count()"], table.columns);
+        assert_eq!(2, table.rows.len());
+    }
+
+    #[test]
+    fn test_project_distinct() {
+        let graphs = vec![make_person(1, "ada"), make_person(2, "ada"), make_person(1, "ada")];
+        let mut ret = ReturnClause::new();
+        ret.distinct = true;
+        ret.expressions.push(ReturnExpression::ItemPropertyName(ItemPropertyName::new("p", "name")));
+        assert_eq!(1, project(&ret, &graphs).rows.len());
+        ret.expressions.push(ReturnExpression::Item(String::from("p")));
+        let table = project(&ret, &graphs);
+        assert_eq!(2, table.rows.len());
+        assert!(matches!(&table.rows[1][1], EvalResult::Node(n) if n.get_id() == Some(2)));
+        ret.distinct = false;
+        assert_eq!(3, project(&ret, &graphs).rows.len());
+    }
+
+    #[test]
+    fn test_union_tables() {
+        let mut ret = ReturnClause::new();
+        ret.expressions.push(ReturnExpression::ItemPropertyName(ItemPropertyName::new("p", "name")));
+        let first = || project(&ret, &[make_person(1, "ada"), make_person(2, "ada")]);
+        let second = || project(&ret, &[make_person(3, "alan"), make_person(4, "ada")]);
+        assert_eq!(4, union_tables(first(), second(), true).rows.len());
+        let table = union_tables(first(), second(), false);
+        assert_eq!(vec!["p.name"], table.columns);
+        assert_eq!(2, table.rows.len());
+        assert!(matches!(&table.rows[1][0], EvalResult::Scalar(PropertyValue::PString(name)) if name == "alan"));
+    }
+
+    #[test]
+    fn test_project_aliases() {
+        let graphs = vec![make_person(1, "ada"), make_person(2, "alan")];
+        let mut ret = ReturnClause::new();
+        ret.expressions.push(ReturnExpression::Alias(Box::new(ReturnExpression::ItemPropertyName(ItemPropertyName::new("p", "name"))), String::from("name")));
+        ret.expressions.push(ReturnExpression::Alias(Box::new(ReturnExpression::FunctionCall(FunctionCall::new("count"))), String::from("total")));
+        let table = project(&ret, &graphs);
+        assert_eq!(vec!["name", "total"], table.columns);
+        assert_eq!(2, table.rows.len());
+        assert!(matches!(&table.rows[1][1], EvalResult::Scalar(PropertyValue::PInteger(1))));
+    }
+
+    #[test]
+    fn test_project_table() {
+        let mut records = ResultTable::new(vec![String::from("label"), String::from("count")]);
+        records.rows.push(vec![EvalResult::Scalar(PropertyValue::PString(String::from("Person"))), EvalResult::Scalar(PropertyValue::PInteger(3))]);
+        records.rows.push(vec![EvalResult::Scalar(PropertyValue::PString(String::from("City"))), EvalResult::Scalar(PropertyValue::PInteger(2))]);
+        let mut sum = FunctionCall::new("sum");
+        sum.args.push(ReturnExpression::Item(String::from("count")));
+        let mut ret = ReturnClause::new();
+        ret.expressions.push(ReturnExpression::Item(String::from("label")));
+        ret.expressions.push(ReturnExpression::Alias(Box::new(ReturnExpression::FunctionCall(sum)), String::from("total")));
+        let table = project_table(&ret, &records);
+        assert_eq!(vec!["label", "total"], table.columns);
+        assert_eq!(2, table.rows.len());
+        assert!(matches!(&table.rows[1][1], EvalResult::Scalar(PropertyValue::PInteger(2))));
+        ret.expressions.pop();
+        ret.expressions.push(ReturnExpression::Item(String::from("missing")));
+        assert!(matches!(project_table(&ret, &records).rows[0][1], EvalResult::Null));
+    }
+}
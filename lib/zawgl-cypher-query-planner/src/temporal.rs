@@ -0,0 +1,128 @@
+// MIT License
+//
+// Copyright (c) 2022 Alexandre RICCIARDI
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+
+use zawgl_core::model::PropertyValue;
+use zawgl_core::model::temporal::{Date, DateTime, Duration};
+
+const NANOS_PER_SECOND: i64 = 1_000_000_000;
+
+/// Evaluates the `datetime`, `date` and `duration` functions from a string or a map of components.
+pub fn eval_temporal_function(name: &str, args: &[PropertyValue]) -> Option<PropertyValue> {
+    use PropertyValue::*;
+    match (name.to_lowercase().as_str(), args.first()) {
+        ("datetime", Some(PNull)) | ("date", Some(PNull)) | ("duration", Some(PNull)) => Some(PNull),
+        ("datetime", None) => Some(PDateTime(DateTime::now())),
+        ("datetime", Some(PString(value))) => DateTime::parse(value).map(PDateTime),
+        ("datetime", Some(PMap(fields))) => make_date_time(fields).map(PDateTime),
+        ("date", None) => Some(PDate(Date::today())),
+        ("date", Some(PString(value))) => Date::parse(value).map(PDate),
+        ("date", Some(PMap(fields))) => make_date(fields).map(PDate),
+        ("date", Some(PDateTime(dt))) => dt.get_date().map(PDate),
+        ("duration", Some(PString(value))) => Duration::parse(value).map(PDuration),
+        ("duration", Some(PMap(fields))) => make_duration(fields).map(PDuration),
+        _ => None,
+    }
+}
+
+fn get_integer(fields: &BTreeMap<String, PropertyValue>, name: &str, default: i64) -> Option<i64> {
+    match fields.get(name) {
+        None => Some(default),
+        Some(PropertyValue::PInteger(value)) => Some(*value),
+        _ => None,
+    }
+}
+
+fn make_date(fields: &BTreeMap<String, PropertyValue>) -> Option<Date> {
+    if !fields.contains_key("year") {
+        return None
+    }
+    let year = i32::try_from(get_integer(fields, "year", 0)?).ok()?;
+    let month = u32::try_from(get_integer(fields, "month", 1)?).ok()?;
+    let day = u32::try_from(get_integer(fields, "day", 1)?).ok()?;
+    Date::from_ymd(year, month, day)
+}
+
+fn make_date_time(fields: &BTreeMap<String, PropertyValue>) -> Option<DateTime> {
+    let date = make_date(fields)?;
+    let offset = match fields.get("timezone") {
+        None => 0,
+        Some(PropertyValue::PString(timezone)) => parse_offset(timezone)?,
+        _ => return None,
+    };
+    let nanosecond = get_integer(fields, "millisecond", 0)? * 1_000_000 + get_integer(fields, "microsecond", 0)? * 1_000 + get_integer(fields, "nanosecond", 0)?;
+    DateTime::from_parts(&date,
+        u32::try_from(get_integer(fields, "hour", 0)?).ok()?,
+        u32::try_from(get_integer(fields, "minute", 0)?).ok()?,
+        u32::try_from(get_integer(fields, "second", 0)?).ok()?,
+        u32::try_from(nanosecond).ok()?, offset)
+}
+
+/// Parses a `Z` or `+HH:MM` timezone offset into seconds.
+fn parse_offset(timezone: &str) -> Option<i32> {
+    if timezone == "Z" {
+        return Some(0)
+    }
+    let sign = match timezone.get(..1)? {
+        "+" => 1,
+        "-" => -1,
+        _ => return None,
+    };
+    let mut parts = timezone[1..].split(':');
+    let hours: i32 = parts.next()?.parse().ok()?;
+    let minutes: i32 = parts.next().unwrap_or("0").parse().ok()?;
+    Some(sign * (hours * 3600 + minutes * 60))
+}
+
+fn make_duration(fields: &BTreeMap<String, PropertyValue>) -> Option<Duration> {
+    let months = get_integer(fields, "years", 0)? * 12 + get_integer(fields, "months", 0)?;
+    let days = get_integer(fields, "weeks", 0)? * 7 + get_integer(fields, "days", 0)?;
+    let seconds = get_integer(fields, "hours", 0)? * 3600 + get_integer(fields, "minutes", 0)? * 60 + get_integer(fields, "seconds", 0)?;
+    let nanos = seconds * NANOS_PER_SECOND + get_integer(fields, "milliseconds", 0)? * 1_000_000
+        + get_integer(fields, "microseconds", 0)? * 1_000 + get_integer(fields, "nanoseconds", 0)?;
+    Some(Duration::new(months, days, nanos))
+}
+
+#[cfg(test)]
+mod test_temporal_functions {
+    use super::*;
+
+    fn make_fields(fields: &[(&str, PropertyValue)]) -> PropertyValue {
+        PropertyValue::PMap(fields.iter().map(|(k, v)| (String::from(*k), v.clone())).collect())
+    }
+
+    #[test]
+    fn test_temporal_functions() {
+        let parsed = eval_temporal_function("datetime", &[PropertyValue::PString(String::from("2015-07-21T21:40:32+01:00"))]);
+        let built = eval_temporal_function("datetime", &[make_fields(&[("year", PropertyValue::PInteger(2015)), ("month", PropertyValue::PInteger(7)),
+            ("day", PropertyValue::PInteger(21)), ("hour", PropertyValue::PInteger(20)), ("minute", PropertyValue::PInteger(40)),
+            ("second", PropertyValue::PInteger(32)), ("timezone", PropertyValue::PString(String::from("Z")))])]);
+        assert_eq!(parsed, built);
+        assert!(matches!(eval_temporal_function("date", &[parsed.unwrap()]), Some(PropertyValue::PDate(date)) if date.to_string() == "2015-07-21"));
+        let duration = eval_temporal_function("duration", &[make_fields(&[("days", PropertyValue::PInteger(1)), ("hours", PropertyValue::PInteger(2))])]);
+        assert_eq!(eval_temporal_function("duration", &[PropertyValue::PString(String::from("P1DT2H"))]), duration);
+        assert!(eval_temporal_function("date", &[make_fields(&[("month", PropertyValue::PInteger(2))])]).is_none());
+        assert!(eval_temporal_function("datetime", &[PropertyValue::PInteger(3)]).is_none());
+    }
+}
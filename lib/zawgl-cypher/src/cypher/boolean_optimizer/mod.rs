@@ -31,6 +31,9 @@ impl AstVisitor for BoolExprVisitor {
     fn enter_match(&mut self, node: &AstTagNode) -> AstVisitorResult<bool> {
         Ok(true)
     }
+    fn enter_optional_match(&mut self, node: &AstTagNode) -> AstVisitorResult<bool> {
+        Ok(true)
+    }
     fn enter_path(&mut self, node: &AstTagNode) -> AstVisitorResult<bool> {
         Ok(true)
     }
@@ -55,6 +58,9 @@ impl AstVisitor for BoolExprVisitor {
     fn enter_bool_value(&mut self, value: Option<bool>) -> AstVisitorResult<bool> {
         Ok(true)
     }
+    fn enter_null_value(&mut self) -> AstVisitorResult<bool> {
+        Ok(true)
+    }
     fn enter_identifier(&mut self, key: &str) -> AstVisitorResult<bool> {
         Ok(true)
     }
@@ -90,6 +96,9 @@ impl AstVisitor for BoolExprVisitor {
     fn exit_match(&mut self) -> AstVisitorResult<bool> {
         todo!()
     }
+    fn exit_optional_match(&mut self) -> AstVisitorResult<bool> {
+        todo!()
+    }
 
     fn exit_path(&mut self) -> AstVisitorResult<bool> {
         todo!()
@@ -164,6 +173,108 @@ impl AstVisitor for BoolExprVisitor {
     fn exit_parameter(&mut self) -> AstVisitorResult<bool> {
         todo!()
     }
+    fn enter_item_property_identifier(&mut self) -> AstVisitorResult<bool> {
+        Ok(true)
+    }
+    fn exit_item_property_identifier(&mut self) -> AstVisitorResult<bool> {
+        Ok(true)
+    }
+    fn enter_operator(&mut self, node: &AstTagNode) -> AstVisitorResult<bool> {
+        Ok(true)
+    }
+    fn exit_operator(&mut self, node: &AstTagNode) -> AstVisitorResult<bool> {
+        Ok(true)
+    }
+    fn enter_create_constraint(&mut self, node: &AstTagNode) -> AstVisitorResult<bool> {
+        Ok(true)
+    }
+    fn exit_create_constraint(&mut self) -> AstVisitorResult<bool> {
+        Ok(true)
+    }
+    fn enter_explain(&mut self) -> AstVisitorResult<bool> {
+        Ok(true)
+    }
+    fn exit_explain(&mut self) -> AstVisitorResult<bool> {
+        Ok(true)
+    }
+    fn enter_profile(&mut self) -> AstVisitorResult<bool> {
+        Ok(true)
+    }
+    fn exit_profile(&mut self) -> AstVisitorResult<bool> {
+        Ok(true)
+    }
+    fn enter_list(&mut self) -> AstVisitorResult<bool> {
+        Ok(true)
+    }
+    fn enter_map(&mut self) -> AstVisitorResult<bool> {
+        Ok(true)
+    }
+    fn exit_list(&mut self) -> AstVisitorResult<bool> {
+        Ok(true)
+    }
+    fn exit_map(&mut self) -> AstVisitorResult<bool> {
+        Ok(true)
+    }
+    fn enter_alias(&mut self) -> AstVisitorResult<bool> {
+        Ok(true)
+    }
+    fn exit_alias(&mut self) -> AstVisitorResult<bool> {
+        Ok(true)
+    }
+    fn enter_distinct(&mut self) -> AstVisitorResult<bool> {
+        Ok(true)
+    }
+    fn exit_distinct(&mut self) -> AstVisitorResult<bool> {
+        Ok(true)
+    }
+    fn enter_unwind(&mut self, node: &AstTagNode) -> AstVisitorResult<bool> {
+        Ok(true)
+    }
+    fn exit_unwind(&mut self) -> AstVisitorResult<bool> {
+        Ok(true)
+    }
+    fn enter_call(&mut self, node: &AstTagNode) -> AstVisitorResult<bool> {
+        Ok(true)
+    }
+    fn exit_call(&mut self) -> AstVisitorResult<bool> {
+        Ok(true)
+    }
+    fn enter_yield(&mut self) -> AstVisitorResult<bool> {
+        Ok(true)
+    }
+    fn exit_yield(&mut self) -> AstVisitorResult<bool> {
+        Ok(true)
+    }
+    fn enter_set(&mut self, node: &AstTagNode) -> AstVisitorResult<bool> {
+        Ok(true)
+    }
+    fn exit_set(&mut self) -> AstVisitorResult<bool> {
+        Ok(true)
+    }
+    fn enter_remove(&mut self, node: &AstTagNode) -> AstVisitorResult<bool> {
+        Ok(true)
+    }
+    fn exit_remove(&mut self) -> AstVisitorResult<bool> {
+        Ok(true)
+    }
+    fn enter_union(&mut self, node: &AstTagNode) -> AstVisitorResult<bool> {
+        Ok(true)
+    }
+    fn exit_union(&mut self) -> AstVisitorResult<bool> {
+        Ok(true)
+    }
+    fn enter_foreach(&mut self, node: &AstTagNode) -> AstVisitorResult<bool> {
+        Ok(true)
+    }
+    fn exit_foreach(&mut self) -> AstVisitorResult<bool> {
+        Ok(true)
+    }
+    fn enter_pattern_predicate(&mut self, node: &AstTagNode) -> AstVisitorResult<bool> {
+        Ok(true)
+    }
+    fn exit_pattern_predicate(&mut self) -> AstVisitorResult<bool> {
+        Ok(true)
+    }
 }
 
 pub fn extract_mandatory_conditions_from_bool_expr(ast: &Box<dyn Ast>) -> Vec<BoolCondition> {
@@ -37,11 +37,10 @@ impl <S, NS, AC> FSM<S, NS, AC> where S: PartialEq + Copy + Clone, NS: Fn(S, cha
 
 impl <S, NS, AC> RunnableFSM<S> for  FSM<S, NS, AC> where S: PartialEq + Copy + Clone, NS: Fn(S, char) -> Option<S>, AC: Fn(S) -> bool {
 
+    /// Returns the size in bytes of the longest accepted prefix of the input.
     fn run(&mut self, input: & str) -> Option<(usize, S)> {
         let mut current_state = self.initial_state;
-        let mut position = 0;
-        for (i, c) in input.chars().enumerate() {
-            position = i;
+        for (position, c) in input.char_indices() {
             match (self.next_state)(current_state, c) {
                 Some(next_state) => {
                     current_state = next_state;
@@ -56,7 +55,7 @@ impl <S, NS, AC> RunnableFSM<S> for  FSM<S, NS, AC> where S: PartialEq + Copy +
             };
         }
         if (self.accepting_states)(current_state) {
-            return Some((position + 1, current_state));
+            return Some((input.len(), current_state));
         }
         return None;
     }
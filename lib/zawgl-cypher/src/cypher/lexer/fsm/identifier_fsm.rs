@@ -20,10 +20,13 @@
 
 use super::fsm::{FSM, RunnableFSM};
 
+/// Escaped identifiers are quoted with backticks, a backtick is escaped by doubling it.
 #[derive(PartialEq, Copy, Clone, Debug)]
 pub enum IdentifierState {
         Initial,
         MatchIdentifier(usize),
+        MatchEscapedIdentifier(usize),
+        MatchEndBacktick(usize),
 }
 
 fn is_valid_id_char(c: char) -> bool {
@@ -37,6 +40,8 @@ pub fn make_identifier_fsm() -> Box<dyn RunnableFSM<IdentifierState>>  {
                IdentifierState::Initial => {
                    if is_valid_id_char(c) {
                         res = Some(IdentifierState::MatchIdentifier(0));
+                   } else if c == '`' {
+                        res = Some(IdentifierState::MatchEscapedIdentifier(0));
                    }
                },
                IdentifierState::MatchIdentifier(i) => {
@@ -44,6 +49,18 @@ pub fn make_identifier_fsm() -> Box<dyn RunnableFSM<IdentifierState>>  {
                         res = Some(IdentifierState::MatchIdentifier(i + 1));
                    }
                },
+               IdentifierState::MatchEscapedIdentifier(i) => {
+                   if c == '`' {
+                        res = Some(IdentifierState::MatchEndBacktick(i + 1));
+                   } else {
+                        res = Some(IdentifierState::MatchEscapedIdentifier(i + 1));
+                   }
+               },
+               IdentifierState::MatchEndBacktick(i) => {
+                   if c == '`' {
+                        res = Some(IdentifierState::MatchEscapedIdentifier(i + 1));
+                   }
+               },
            };
         
         res
@@ -52,6 +69,7 @@ pub fn make_identifier_fsm() -> Box<dyn RunnableFSM<IdentifierState>>  {
     let accepting_states = |s| -> bool {
            let res = match s {
                 IdentifierState::MatchIdentifier(_i) => true,
+                IdentifierState::MatchEndBacktick(i) => i > 1,
                 _ => false,
             };
             res
@@ -68,4 +86,12 @@ mod test_identifier_fsm {
         let mut fsm = make_identifier_fsm();
         assert_eq!(fsm.run("blabla:"), Some((6, IdentifierState::MatchIdentifier(5))));
     }
-}
\ No newline at end of file
+    #[test]
+    fn test_escaped_identifier() {
+        let mut fsm = make_identifier_fsm();
+        assert_eq!(fsm.run("`first name`:"), Some((12, IdentifierState::MatchEndBacktick(11))));
+        assert_eq!(fsm.run("`a``b` "), Some((6, IdentifierState::MatchEndBacktick(5))));
+        assert_eq!(fsm.run("`unterminated"), None);
+        assert_eq!(fsm.run("``"), None);
+    }
+}
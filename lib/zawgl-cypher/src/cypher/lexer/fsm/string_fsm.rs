@@ -20,16 +20,14 @@
 
 use super::fsm::{FSM, RunnableFSM};
 
+/// Strings are quoted with simple or double quotes, the escaped characters are checked once the string is read.
 #[derive(PartialEq, Copy, Clone, Debug)]
 pub enum StringState {
         Initial,
-        MatchBeginSimpleQuote(usize),
-        MatchString(usize),
-        MatchEndSimpleQuote(usize),
-}
-
-fn is_valid_string_char(c: char) -> bool {
-    c.is_alphanumeric() || c.is_whitespace()
+        MatchBeginQuote(char),
+        MatchString(char, usize),
+        MatchEscape(char, usize),
+        MatchEndQuote(usize),
 }
 
 pub fn make_string_fsm() -> Box<dyn RunnableFSM<StringState>>  {
@@ -37,21 +35,18 @@ pub fn make_string_fsm() -> Box<dyn RunnableFSM<StringState>>  {
         let mut res = None;
         match s {
                 StringState::Initial => {
-                   if c == '\'' {
-                        res = Some(StringState::MatchBeginSimpleQuote(0));
+                   if c == '\'' || c == '"' {
+                        res = Some(StringState::MatchBeginQuote(c));
                    }
                 },
-                StringState::MatchBeginSimpleQuote(i) => {
-                    if is_valid_string_char(c) {
-                        res = Some(StringState::MatchString(i + 1));
-                    }
+                StringState::MatchBeginQuote(quote) => {
+                    res = Some(match_string_char(quote, 0, c));
                 },
-                StringState::MatchString(i) => {
-                   if is_valid_string_char(c) {
-                        res = Some(StringState::MatchString(i + 1));
-                   } else if c == '\'' {
-                       res = Some(StringState::MatchEndSimpleQuote(i + 1));
-                   }
+                StringState::MatchString(quote, i) => {
+                    res = Some(match_string_char(quote, i, c));
+                },
+                StringState::MatchEscape(quote, i) => {
+                    res = Some(StringState::MatchString(quote, i + 1));
                 },
                 _ => {},
            };
@@ -61,7 +56,7 @@ pub fn make_string_fsm() -> Box<dyn RunnableFSM<StringState>>  {
 
     let accepting_states = |s| -> bool {
            let res = match s {
-                StringState::MatchEndSimpleQuote(_i) => true,
+                StringState::MatchEndQuote(_i) => true,
                 _ => false,
             };
             res
@@ -70,17 +65,42 @@ pub fn make_string_fsm() -> Box<dyn RunnableFSM<StringState>>  {
     Box::new(FSM::new(StringState::Initial, accepting_states, next_state))
 }
 
+fn match_string_char(quote: char, i: usize, c: char) -> StringState {
+    if c == quote {
+        StringState::MatchEndQuote(i + 1)
+    } else if c == '\\' {
+        StringState::MatchEscape(quote, i + 1)
+    } else {
+        StringState::MatchString(quote, i + 1)
+    }
+}
+
 #[cfg(test)]
 mod test_identifier_fsm {
     use super::*;
     #[test]
     fn test_string_fsm() {
         let mut fsm = make_string_fsm();
-        assert_eq!(fsm.run("'blabla' test"), Some((8, StringState::MatchEndSimpleQuote(7))));
+        assert_eq!(fsm.run("'blabla' test"), Some((8, StringState::MatchEndQuote(7))));
     }
     #[test]
     fn test_string_ws_fsm() {
         let mut fsm = make_string_fsm();
-        assert_eq!(fsm.run("'blab la' test"), Some((9, StringState::MatchEndSimpleQuote(8))));
+        assert_eq!(fsm.run("'blab la' test"), Some((9, StringState::MatchEndQuote(8))));
     }
-}
\ No newline at end of file
+    #[test]
+    fn test_string_punctuation_fsm() {
+        let mut fsm = make_string_fsm();
+        assert_eq!(fsm.run("'2015-07-21T21:40:32.142+01:00')"), Some((31, StringState::MatchEndQuote(30))));
+        assert_eq!(fsm.run("'' test"), Some((2, StringState::MatchEndQuote(1))));
+    }
+    #[test]
+    fn test_string_escape_fsm() {
+        let mut fsm = make_string_fsm();
+        assert_eq!(fsm.run("'it\\'s' test"), Some((7, StringState::MatchEndQuote(6))));
+        assert_eq!(fsm.run("\"say \\\"hi\\\"\", 'a'"), Some((12, StringState::MatchEndQuote(11))));
+        assert_eq!(fsm.run("'h\\u00e9llo' test"), Some((12, StringState::MatchEndQuote(11))));
+        assert_eq!(fsm.run("'héllo' test"), Some((8, StringState::MatchEndQuote(6))));
+        assert_eq!(fsm.run("'unterminated\\'"), None);
+    }
+}
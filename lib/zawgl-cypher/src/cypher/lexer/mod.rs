@@ -23,10 +23,41 @@ use std;
 use std::fmt;
 use std::error::Error;
 
-use zawgl_cypher_query_model::token::{TokenType, Token};
+use zawgl_cypher_query_model::token::{TokenType, Token, unescape_string};
 
 use self::fsm::parameter_fsm;
 
+const KEYWORDS: &[(TokenType, &str)] = &[(TokenType::True, "true"), (TokenType::False, "false"),
+    (TokenType::And, "and"), (TokenType::Or, "or"),
+    (TokenType::Plus, "+"), (TokenType::Dot, "."),
+    (TokenType::Divide, "/"), (TokenType::Mult, "*"),
+    (TokenType::Match, "match"), (TokenType::Optional, "optional"),
+    (TokenType::Where, "where"),
+    (TokenType::Return, "return"), (TokenType::CloseParenthesis, ")"),
+    (TokenType::OpenParenthesis, "("), (TokenType::Colon, ":"),
+    (TokenType::OpenBrace, "{"), (TokenType::CloseBrace, "}"),
+    (TokenType::LeftSourceRel, "-["), (TokenType::RightTargetRel, "]->"),
+    (TokenType::LeftTargetRel, "<-["), (TokenType::RightSourceRel, "]-"),
+    (TokenType::UndirectedRel, "{"), (TokenType::Create, "create"),
+    (TokenType::Comma, ","), (TokenType::Equals, "="),
+    (TokenType::NotEqual, "<>"), (TokenType::LessThanOrEqual, "<="),
+    (TokenType::LessThan, "<"), (TokenType::GreaterThanOrEqual, ">="),
+    (TokenType::GreaterThan, ">"), (TokenType::Not, "not"),
+    (TokenType::Pipe, "|"), (TokenType::Minus, "-"),
+    (TokenType::Constraint, "constraint"), (TokenType::On, "on"),
+    (TokenType::Assert, "assert"), (TokenType::Is, "is"),
+    (TokenType::Unique, "unique"), (TokenType::Explain, "explain"),
+    (TokenType::Profile, "profile"), (TokenType::OpenBracket, "["),
+    (TokenType::CloseBracket, "]"), (TokenType::Null, "null"),
+    (TokenType::As, "as"), (TokenType::Modulo, "%"),
+    (TokenType::Distinct, "distinct"), (TokenType::Unwind, "unwind"),
+    (TokenType::Call, "call"), (TokenType::Yield, "yield"),
+    (TokenType::Contains, "contains"), (TokenType::Starts, "starts"),
+    (TokenType::Ends, "ends"), (TokenType::With, "with"),
+    (TokenType::Set, "set"), (TokenType::Remove, "remove"),
+    (TokenType::Union, "union"), (TokenType::All, "all"),
+    (TokenType::Foreach, "foreach"), (TokenType::In, "in")];
+
 pub struct Lexer<'a> {
     keywords: Vec<(TokenType, &'static str)>,
     input: &'a str,
@@ -42,7 +73,29 @@ fn make_token(ttype: TokenType, beg: usize, end: usize, input: &str) -> Option<T
 
 fn run_keyword_fsm<'a>(tok_type: TokenType, keyword: &'static str, input: &'a str, index: usize) -> Option<Token<'a>> {
     let mut kfsm = fsm::keyword_fsm::make_keyword_ignorecase_fsm(keyword);
-    input.get(index..).and_then(|rest| kfsm.run(&rest)).and_then(|size| input.get(index..index + size.0)).map(|tok_expr| Token::new(tok_type, index, index + tok_expr.len(), tok_expr))
+    input.get(index..).and_then(|rest| kfsm.run(&rest)).and_then(|size| input.get(index..index + size.0))
+        .filter(|tok_expr| !is_keyword_prefix(keyword, input, index + tok_expr.len()))
+        .map(|tok_expr| Token::new(tok_type, index, index + tok_expr.len(), tok_expr))
+}
+
+fn is_keyword_prefix(keyword: &'static str, input: &str, end: usize) -> bool {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    keyword.chars().last().map_or(false, is_word) && input.get(end..).and_then(|rest| rest.chars().next()).map_or(false, is_word)
+}
+
+/// Name of a token type in the syntax errors, keywords and symbols are quoted as written in a query.
+pub fn get_token_type_name(token_type: TokenType) -> String {
+    match token_type {
+        TokenType::Identifier => String::from("identifier"),
+        TokenType::Integer => String::from("integer"),
+        TokenType::Float => String::from("float"),
+        TokenType::StringType => String::from("string"),
+        TokenType::Parameter => String::from("parameter"),
+        _ => {
+            let keyword = KEYWORDS.iter().find(|(keyword_type, _)| *keyword_type == token_type).map(|(_, keyword)| keyword.to_uppercase());
+            format!("'{}'", keyword.unwrap_or_else(|| format!("{:?}", token_type).to_uppercase()))
+        },
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -51,6 +104,19 @@ pub enum LexerError {
     WrongNumberFormat(usize),
     EndOfFile(usize),
     WrongIdentifierFormat(usize),
+    WrongStringFormat(usize),
+    UnterminatedComment(usize),
+}
+
+impl LexerError {
+    /// Byte offset in the input of the error.
+    pub fn get_position(&self) -> Option<usize> {
+        match *self {
+            LexerError::NotFound => None,
+            LexerError::WrongNumberFormat(pos) | LexerError::EndOfFile(pos) | LexerError::WrongIdentifierFormat(pos) |
+            LexerError::WrongStringFormat(pos) | LexerError::UnterminatedComment(pos) => Some(pos),
+        }
+    }
 }
 
 pub type LexerResult<T> = std::result::Result<T, LexerError>;
@@ -63,6 +129,8 @@ impl fmt::Display for LexerError {
             LexerError::EndOfFile(pos) => f.write_str(&format!("end of file at position : {}", pos)),
             LexerError::WrongNumberFormat(pos) => f.write_str(&format!("wrong format for number at position : {}", pos)),
             LexerError::WrongIdentifierFormat(pos) => f.write_str(&format!("wrong identifier format at position : {}", pos)),
+            LexerError::WrongStringFormat(pos) => f.write_str(&format!("invalid escape sequence in string at position : {}", pos)),
+            LexerError::UnterminatedComment(pos) => f.write_str(&format!("unterminated comment at position : {}", pos)),
         }
     }
 }
@@ -74,7 +142,8 @@ impl Error for LexerError {
             LexerError::EndOfFile(_pos) => "Internal server error",
             LexerError::WrongNumberFormat(_pos) => "wrong number format",
             LexerError::WrongIdentifierFormat(_pos) => "wrong identifier format",
-
+            LexerError::WrongStringFormat(_pos) => "wrong string format",
+            LexerError::UnterminatedComment(_pos) => "unterminated comment",
         }
     }
 }
@@ -82,90 +151,98 @@ impl Error for LexerError {
 impl <'a> Lexer<'a> {
     pub fn new(input: &'a str) -> Lexer {
         Lexer {
-            keywords: vec![(TokenType::True, "true"), (TokenType::False, "false"),
-                            (TokenType::And, "and"), (TokenType::Or, "or"),
-                            (TokenType::Plus, "+"), (TokenType::Dot, "."),
-                            (TokenType::Divide, "/"), (TokenType::Mult, "*"),
-                            (TokenType::Match, "match"), (TokenType::Where, "where"),
-                            (TokenType::Return, "return"), (TokenType::CloseParenthesis, ")"),
-                            (TokenType::OpenParenthesis, "("), (TokenType::Colon, ":"),
-                            (TokenType::OpenBrace, "{"), (TokenType::CloseBrace, "}"),
-                            (TokenType::LeftSourceRel, "-["), (TokenType::RightTargetRel, "]->"),
-                            (TokenType::LeftTargetRel, "<-["), (TokenType::RightSourceRel, "]-"),
-                            (TokenType::UndirectedRel, "{"), (TokenType::Create, "create"),
-                            (TokenType::Comma, ","), (TokenType::Equals, "="),
-                            (TokenType::Pipe, "|"), (TokenType::Minus, "-")],
+            keywords: KEYWORDS.to_vec(),
             input: input, position: 0, line: 0, column: 0, lookahead: 0}
     }
     pub  fn  next_token(&mut self) -> LexerResult<Token<'a>> {
         
-        self.position = self.position + self.lookahead;
-        if self.position >= self.input.len() {
-            return Err(LexerError::EndOfFile(self.position));
+        self.position = self.skip_ignored(self.position + self.lookahead)?;
+        self.lookahead = 0;
+        let c = match self.input.get(self.position..).and_then(|rest| rest.chars().next()) {
+            Some(c) => c,
+            None => return Err(LexerError::EndOfFile(self.position)),
+        };
+        if c.is_numeric() {
+            let mut number_fsm = fsm::number_fsm::make_number_fsm();
+            return match number_fsm.run(&self.input.get(self.position..self.input.len()).unwrap()) {
+                Some(numlen) =>{
+                    self.lookahead = numlen.0;
+                    match numlen.1 {
+                        fsm::number_fsm::NumberState::Integer => make_token(TokenType::Integer, self.position, self.position + numlen.0, &self.input).ok_or(LexerError::NotFound),
+                        fsm::number_fsm::NumberState::NumberWithFractionalPart => make_token(TokenType::Float, self.position, self.position + numlen.0, &self.input).ok_or(LexerError::NotFound),
+                        fsm::number_fsm::NumberState::NumberWithExponent => make_token(TokenType::Float, self.position, self.position + numlen.0, &self.input).ok_or(LexerError::NotFound),
+                        _ => Err(LexerError::WrongNumberFormat(self.position))
+                    }
+                } ,
+                None => Err(LexerError::WrongNumberFormat(self.position)),
+            };
         }
-        for c in self.input.get(self.position..self.input.len()).unwrap().chars() {
-            if c.is_whitespace() {
-                self.position += 1;
-                continue;
-            }
-            if c.is_numeric() {
-                let mut number_fsm = fsm::number_fsm::make_number_fsm();
-                return match number_fsm.run(&self.input.get(self.position..self.input.len()).unwrap()) {
-                    Some(numlen) =>{
-                        self.lookahead = numlen.0;
-                        match numlen.1 {
-                            fsm::number_fsm::NumberState::Integer => make_token(TokenType::Integer, self.position, self.position + numlen.0, &self.input).ok_or(LexerError::NotFound),
-                            fsm::number_fsm::NumberState::NumberWithFractionalPart => make_token(TokenType::Float, self.position, self.position + numlen.0, &self.input).ok_or(LexerError::NotFound),
-                            fsm::number_fsm::NumberState::NumberWithExponent => make_token(TokenType::Float, self.position, self.position + numlen.0, &self.input).ok_or(LexerError::NotFound),
-                            _ => Err(LexerError::WrongNumberFormat(self.position))
-                        }
-                    } ,
-                    None => Err(LexerError::WrongNumberFormat(self.position)),
-                };
-            }
-            for keyword in &self.keywords {
-                match run_keyword_fsm(keyword.0, keyword.1, &self.input, self.position) {
-                    Some(tok) => {
-                        self.lookahead = tok.size();
-                        return Ok(tok)
-                    },
-                    None => {},
-                }
-            }
-            let mut string_fsm = fsm::string_fsm::make_string_fsm();
-            match string_fsm.run(&self.input.get(self.position..self.input.len()).unwrap()) {
-                Some(string_len) => {
-                    self.lookahead = string_len.0;
-                    return make_token(TokenType::StringType, self.position, self.position + string_len.0, &self.input).ok_or(LexerError::NotFound);
+        for keyword in &self.keywords {
+            match run_keyword_fsm(keyword.0, keyword.1, &self.input, self.position) {
+                Some(tok) => {
+                    self.lookahead = tok.size();
+                    return Ok(tok)
                 },
                 None => {},
             }
-            if c =='$' {
-                let mut parameter_fsm = fsm::parameter_fsm::make_parameter_fsm();
-                match parameter_fsm.run(&self.input.get(self.position..self.input.len()).unwrap()) {
-                    Some(idlen) => {
-                        self.lookahead = idlen.0;
-                        return make_token(TokenType::Parameter, self.position, self.position + idlen.0, &self.input).ok_or(LexerError::NotFound)
-                    } ,
-    
-                    None => {},
+        }
+        let mut string_fsm = fsm::string_fsm::make_string_fsm();
+        match string_fsm.run(&self.input.get(self.position..self.input.len()).unwrap()) {
+            Some(string_len) => {
+                self.lookahead = string_len.0;
+                if self.input.get(self.position + 1..self.position + string_len.0 - 1).and_then(unescape_string).is_none() {
+                    return Err(LexerError::WrongStringFormat(self.position));
                 }
-            }
-            let mut identifier_fsm = fsm::identifier_fsm::make_identifier_fsm();
-            return match identifier_fsm.run(&self.input.get(self.position..self.input.len()).unwrap()) {
+                return make_token(TokenType::StringType, self.position, self.position + string_len.0, &self.input).ok_or(LexerError::NotFound);
+            },
+            None => {},
+        }
+        if c =='$' {
+            let mut parameter_fsm = fsm::parameter_fsm::make_parameter_fsm();
+            match parameter_fsm.run(&self.input.get(self.position..self.input.len()).unwrap()) {
                 Some(idlen) => {
                     self.lookahead = idlen.0;
-                    return make_token(TokenType::Identifier, self.position, self.position + idlen.0, &self.input).ok_or(LexerError::NotFound)
+                    return make_token(TokenType::Parameter, self.position, self.position + idlen.0, &self.input).ok_or(LexerError::NotFound)
                 } ,
 
-                None => Err(LexerError::WrongIdentifierFormat(self.position)),
-            };
+                None => {},
+            }
+        }
+        let mut identifier_fsm = fsm::identifier_fsm::make_identifier_fsm();
+        return match identifier_fsm.run(&self.input.get(self.position..self.input.len()).unwrap()) {
+            Some(idlen) => {
+                self.lookahead = idlen.0;
+                return make_token(TokenType::Identifier, self.position, self.position + idlen.0, &self.input).ok_or(LexerError::NotFound)
+            } ,
+
+            None => Err(LexerError::WrongIdentifierFormat(self.position)),
+        };
+    }
+
+    /// Position of the first character following `position` that is neither a whitespace nor in a comment.
+    fn skip_ignored(&self, mut position: usize) -> LexerResult<usize> {
+        loop {
+            let rest = self.input.get(position..).unwrap_or_default();
+            let trimmed = rest.trim_start();
+            position += rest.len() - trimmed.len();
+            if trimmed.starts_with("//") {
+                position += trimmed.find('\n').unwrap_or(trimmed.len());
+            } else if let Some(comment) = trimmed.strip_prefix("/*") {
+                position += comment.find("*/").map(|end| end + 4).ok_or(LexerError::UnterminatedComment(position))?;
+            } else {
+                return Ok(position);
+            }
         }
-        Err(LexerError::NotFound)
     }
 
+    /// Byte offset in the input of the token being read.
+    pub fn get_position(&self) -> usize {
+        self.position
+    }
+
+    /// Trailing whitespaces and comments are not tokens, an unterminated comment is reported by the next token.
     pub fn has_next(&self) -> bool {
-        self.position + self.lookahead < self.input.len()
+        self.skip_ignored(self.position + self.lookahead).map_or(true, |position| position < self.input.len())
     }
 
     pub fn get_tokens(&mut self) -> LexerResult<Vec<Token<'a>>> {
@@ -244,6 +321,80 @@ mod test_lexer {
         }
     }
 
+    #[test]
+    fn test_keyword_prefixed_identifier() {
+        let mut lexer = Lexer::new("optional match orders");
+        match lexer.next_token() {
+            Ok(tok) => assert_eq!(tok.token_type, TokenType::Optional),
+            Err(_msg) => assert!(false),
+        }
+        match lexer.next_token() {
+            Ok(tok) => assert_eq!(tok.token_type, TokenType::Match),
+            Err(_msg) => assert!(false),
+        }
+        match lexer.next_token() {
+            Ok(tok) => {
+                assert_eq!(tok.content, "orders");
+                assert_eq!(tok.token_type, TokenType::Identifier);
+            },
+            Err(_msg) => assert!(false),
+        }
+    }
+
+    #[test]
+    fn test_comparison_operators() {
+        let mut lexer = Lexer::new("n.age >= 12 and not n.age <> 20 and (m)<-[r]-(n)");
+        let tokens = lexer.get_tokens().expect("tokens");
+        let types: Vec<TokenType> = tokens.iter().map(|t| t.token_type).collect();
+        assert_eq!(types, vec![TokenType::Identifier, TokenType::Dot, TokenType::Identifier, TokenType::GreaterThanOrEqual, TokenType::Integer,
+            TokenType::And, TokenType::Not, TokenType::Identifier, TokenType::Dot, TokenType::Identifier, TokenType::NotEqual, TokenType::Integer,
+            TokenType::And, TokenType::OpenParenthesis, TokenType::Identifier, TokenType::CloseParenthesis, TokenType::LeftTargetRel,
+            TokenType::Identifier, TokenType::RightSourceRel, TokenType::OpenParenthesis, TokenType::Identifier, TokenType::CloseParenthesis]);
+    }
+
+    #[test]
+    fn test_arithmetic_and_alias() {
+        let mut lexer = Lexer::new("n.age % 2 * 3 AS rest, assets");
+        let tokens = lexer.get_tokens().expect("tokens");
+        let types: Vec<TokenType> = tokens.iter().map(|t| t.token_type).collect();
+        assert_eq!(types, vec![TokenType::Identifier, TokenType::Dot, TokenType::Identifier, TokenType::Modulo, TokenType::Integer,
+            TokenType::Mult, TokenType::Integer, TokenType::As, TokenType::Identifier, TokenType::Comma, TokenType::Identifier]);
+    }
+
+    #[test]
+    fn test_string_operators() {
+        let mut lexer = Lexer::new("n.name STARTS WITH 'A' or n.name ends with 'a' or n.name contains 'd' or n.endsAt");
+        let tokens = lexer.get_tokens().expect("tokens");
+        let types: Vec<TokenType> = tokens.iter().map(|t| t.token_type).filter(|t| ![TokenType::Identifier, TokenType::Dot, TokenType::StringType].contains(t)).collect();
+        assert_eq!(types, vec![TokenType::Starts, TokenType::With, TokenType::Or, TokenType::Ends, TokenType::With, TokenType::Or, TokenType::Contains, TokenType::Or]);
+    }
+
+    #[test]
+    fn test_update_keywords() {
+        let mut lexer = Lexer::new("SET n:Person remove n:Ada");
+        let tokens = lexer.get_tokens().expect("tokens");
+        let types: Vec<TokenType> = tokens.iter().map(|t| t.token_type).collect();
+        assert_eq!(types, vec![TokenType::Set, TokenType::Identifier, TokenType::Colon, TokenType::Identifier,
+            TokenType::Remove, TokenType::Identifier, TokenType::Colon, TokenType::Identifier]);
+    }
+
+    #[test]
+    fn test_union_keywords() {
+        let mut lexer = Lexer::new("UNION all union allowed");
+        let tokens = lexer.get_tokens().expect("tokens");
+        let types: Vec<TokenType> = tokens.iter().map(|t| t.token_type).collect();
+        assert_eq!(types, vec![TokenType::Union, TokenType::All, TokenType::Union, TokenType::Identifier]);
+    }
+
+    #[test]
+    fn test_foreach_keywords() {
+        let mut lexer = Lexer::new("FOREACH (x IN items | index)");
+        let tokens = lexer.get_tokens().expect("tokens");
+        let types: Vec<TokenType> = tokens.iter().map(|t| t.token_type).collect();
+        assert_eq!(types, vec![TokenType::Foreach, TokenType::OpenParenthesis, TokenType::Identifier, TokenType::In, TokenType::Identifier,
+            TokenType::Pipe, TokenType::Identifier, TokenType::CloseParenthesis]);
+    }
+
     #[test]
     fn test_run_string_fsm() {
         let mut lexer = Lexer::new("'this is a string' or 'this is another string'");
@@ -260,4 +411,24 @@ mod test_lexer {
             Err(_msg) => assert!(false),
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_comments() {
+        let mut lexer = Lexer::new("MATCH (n) // all the nodes\n/* of any\n label */ RETURN n /* done */ // end");
+        let tokens = lexer.get_tokens().expect("tokens");
+        let types: Vec<TokenType> = tokens.iter().map(|t| t.token_type).collect();
+        assert_eq!(types, vec![TokenType::Match, TokenType::OpenParenthesis, TokenType::Identifier, TokenType::CloseParenthesis, TokenType::Return, TokenType::Identifier]);
+        assert_eq!(5, Lexer::new("n.age / 2 // half").get_tokens().expect("tokens").len());
+        assert!(matches!(Lexer::new("RETURN n /* open").get_tokens(), Err(LexerError::UnterminatedComment(9))));
+    }
+
+    #[test]
+    fn test_escaped_identifiers_and_strings() {
+        let mut lexer = Lexer::new("MATCH (n:`first name`) WHERE n.`a``b` = 'l\\'été\\u00e0' OR n.c = \"say \\\"hi\\\"\\n\" RETURN n.ville");
+        let tokens = lexer.get_tokens().expect("tokens");
+        let values: Vec<String> = tokens.iter().filter(|t| t.token_type == TokenType::Identifier || t.token_type == TokenType::StringType).map(|t| t.get_value()).collect();
+        assert_eq!(values, vec!["n", "first name", "n", "a`b", "l'étéà", "n", "c", "say \"hi\"\n", "n", "ville"]);
+        assert!(matches!(Lexer::new("RETURN 'a\\qb'").get_tokens(), Err(LexerError::WrongStringFormat(7))));
+        assert!(matches!(Lexer::new("RETURN '\\u00e'").get_tokens(), Err(LexerError::WrongStringFormat(7))));
+    }
+}
@@ -18,11 +18,12 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-use zawgl_cypher_query_model::ast::{AstTagNode, AstTag, AstTokenNode};
+use zawgl_cypher_query_model::ast::{AstTagNode, AstTag, AstTokenNode, Ast};
 use zawgl_cypher_query_model::token::TokenType;
 
 use super::*;
 use super::{error::*};
+use super::properties_parser_delegate::{enter_prop_value, parse_collection_literal};
 
 pub fn parse_function_definition(parser: &mut Parser) -> ParserResult<Box<AstTagNode>> {
     let mut item_id = make_ast_token(&parser);
@@ -34,13 +35,116 @@ pub fn parse_function_definition(parser: &mut Parser) -> ParserResult<Box<AstTag
     Ok(func_node)
 }
 
+pub fn parse_item_property_identifier(parser: &mut Parser) -> ParserResult<Box<AstTagNode>> {
+    let mut item_prop = make_ast_tag(AstTag::ItemPropertyIdentifier);
+    item_prop.append(make_ast_token(parser));
+    parser.require(TokenType::Dot)?;
+    parser.require(TokenType::Identifier)?;
+    item_prop.append(make_ast_token(parser));
+    Ok(item_prop)
+}
+
+fn get_additive_tag(token_type: TokenType) -> Option<AstTag> {
+    match token_type {
+        TokenType::Plus => Some(AstTag::AddOperator),
+        TokenType::Minus => Some(AstTag::SubtractOperator),
+        _ => None,
+    }
+}
+
+fn get_multiplicative_tag(token_type: TokenType) -> Option<AstTag> {
+    match token_type {
+        TokenType::Mult => Some(AstTag::MultiplyOperator),
+        TokenType::Divide => Some(AstTag::DivideOperator),
+        TokenType::Modulo => Some(AstTag::ModuloOperator),
+        _ => None,
+    }
+}
+
+fn parse_binary_operations(parser: &mut Parser, get_tag: fn(TokenType) -> Option<AstTag>, parse_operand: fn(&mut Parser) -> ParserResult<Box<dyn Ast>>) -> ParserResult<Box<dyn Ast>> {
+    let mut expr = parse_operand(parser)?;
+    while parser.index < parser.get_tokens().len() {
+        match get_tag(parser.get_current_token_type()) {
+            Some(tag) => {
+                parser.advance();
+                let mut operator = make_ast_tag(tag);
+                operator.append(expr);
+                operator.append(parse_operand(parser)?);
+                expr = operator;
+            },
+            None => break,
+        }
+    }
+    Ok(expr)
+}
+
+pub fn parse_arithmetic_expression(parser: &mut Parser) -> ParserResult<Box<dyn Ast>> {
+    parse_binary_operations(parser, get_additive_tag, parse_multiplicative_expression)
+}
+
+fn parse_multiplicative_expression(parser: &mut Parser) -> ParserResult<Box<dyn Ast>> {
+    parse_binary_operations(parser, get_multiplicative_tag, parse_expression_terminal)
+}
+
+const EXPRESSION_TOKENS: [TokenType; 11] = [TokenType::Identifier, TokenType::Integer, TokenType::Float, TokenType::StringType, TokenType::Parameter,
+    TokenType::True, TokenType::False, TokenType::Null, TokenType::OpenParenthesis, TokenType::OpenBracket, TokenType::OpenBrace];
+
+fn parse_expression_terminal(parser: &mut Parser) -> ParserResult<Box<dyn Ast>> {
+    parser.expect(&EXPRESSION_TOKENS);
+    if parser.index >= parser.get_tokens().len() {
+        return Err(ParserError::EndOfFile)
+    }
+    match parser.get_current_token_type() {
+        TokenType::Integer | TokenType::Float | TokenType::True | TokenType::False | TokenType::StringType | TokenType::Parameter | TokenType::Null => {
+            parser.advance();
+            Ok(make_ast_token(parser))
+        },
+        TokenType::OpenBracket | TokenType::OpenBrace => {
+            parse_collection_literal(parser)
+        },
+        TokenType::OpenParenthesis => {
+            parser.advance();
+            let expr = parse_arithmetic_expression(parser)?;
+            parser.require(TokenType::CloseParenthesis)?;
+            Ok(expr)
+        },
+        TokenType::Identifier => {
+            parser.advance();
+            if parser.check(TokenType::OpenParenthesis) {
+                Ok(parse_function_definition(parser)?)
+            } else if parser.check(TokenType::Dot) {
+                Ok(parse_item_property_identifier(parser)?)
+            } else {
+                Ok(make_ast_token(parser))
+            }
+        },
+        _ => {
+            Err(ParserError::SyntaxError(parser.index))
+        }
+    }
+}
+
 fn parse_func_args(parser: &mut Parser, parent_node: &mut Box<AstTokenNode>) -> ParserResult<()> {
-    
-    while parser.check(TokenType::Identifier) {
-        parser.advance();
+    if parser.current_token_type_advance(TokenType::Mult) {
         let mut func_arg = Box::new(AstTagNode::new_tag(AstTag::FunctionArg));
         func_arg.append(make_ast_token(parser));
         parent_node.append(func_arg);
+        return Ok(())
+    }
+    while parser.index < parser.get_tokens().len() && !parser.check(TokenType::CloseParenthesis) {
+        let mut func_arg: Box<dyn Ast> = Box::new(AstTagNode::new_tag(AstTag::FunctionArg));
+        if parser.current_token_type_advance(TokenType::Identifier) {
+            if parser.check(TokenType::Dot) {
+                func_arg.append(parse_item_property_identifier(parser)?);
+            } else {
+                func_arg.append(make_ast_token(parser));
+            }
+        } else if parser.current_token_type_advance(TokenType::Parameter) {
+            func_arg.append(make_ast_token(parser));
+        } else {
+            enter_prop_value(parser, &mut func_arg)?;
+        }
+        parent_node.append(func_arg);
         if !parser.check(TokenType::Comma) {
             break;
         } else {
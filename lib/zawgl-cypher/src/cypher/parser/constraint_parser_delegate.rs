@@ -0,0 +1,52 @@
+// MIT License
+//
+// Copyright (c) 2022 Alexandre RICCIARDI
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use zawgl_cypher_query_model::ast::AstTag;
+use zawgl_cypher_query_model::token::TokenType;
+
+use super::*;
+use super::error::*;
+use super::common_parser_delegate::parse_item_property_identifier;
+
+pub fn parse_create_constraint(parser: &mut Parser, parent_node: &mut Box<AstTagNode>) -> ParserResult<()> {
+    let mut constraint_node = make_ast_tag(AstTag::CreateConstraint);
+    parser.require(TokenType::Constraint)?;
+    parser.require(TokenType::On)?;
+    parser.require(TokenType::OpenParenthesis)?;
+    parser.require(TokenType::Identifier)?;
+    let mut var_node = make_ast_tag(AstTag::Variable);
+    var_node.append(make_ast_token(parser));
+    constraint_node.append(var_node);
+    parser.require(TokenType::Colon)?;
+    parser.require(TokenType::Identifier)?;
+    let mut label_node = make_ast_tag(AstTag::Label);
+    label_node.append(make_ast_token(parser));
+    constraint_node.append(label_node);
+    parser.require(TokenType::CloseParenthesis)?;
+    parser.require(TokenType::Assert)?;
+    parser.require(TokenType::Identifier)?;
+    constraint_node.append(parse_item_property_identifier(parser)?);
+    parser.require(TokenType::Is)?;
+    parser.require(TokenType::Unique)?;
+    parent_node.append(constraint_node);
+    Ok(())
+}
@@ -19,56 +19,245 @@
 // SOFTWARE.
 
 use super::error::*;
-use zawgl_cypher_query_model::ast::{AstTagNode, AstTag, Ast};
+use zawgl_cypher_query_model::ast::{AstTagNode, AstTokenNode, AstTag, Ast};
 use zawgl_cypher_query_model::token::TokenType;
 use super::*;
 use super::pattern_parser_delegate::*;
 use super::return_clause_parser_delegate::*;
 use super::where_clause_parser_delegate::parse_where_clause;
+use super::constraint_parser_delegate::parse_create_constraint;
+use super::common_parser_delegate::{parse_arithmetic_expression, parse_item_property_identifier};
 
 fn parse_match(parser: &mut Parser, parent_node: &mut Box<AstTagNode>) -> ParserResult<()> {
-    let mut match_node = make_ast_tag(AstTag::Match);
+    parse_match_tag(parser, parent_node, AstTag::Match)
+}
+
+fn parse_match_tag(parser: &mut Parser, parent_node: &mut Box<AstTagNode>, tag: AstTag) -> ParserResult<()> {
+    let mut match_node = make_ast_tag(tag);
     parse_path(parser, &mut match_node)?;
     parent_node.append(match_node);
+    parse_where_clause(parser, parent_node)?;
     if parser.current_token_type_advance(TokenType::Match) {
-        parse_match(parser, parent_node)?;
+        parse_match_tag(parser, parent_node, AstTag::Match)?;
+    } else if parser.current_token_type_advance(TokenType::Optional) {
+        parser.require(TokenType::Match)?;
+        parse_match_tag(parser, parent_node, AstTag::OptionalMatch)?;
     }
     Ok(())
 }
 
 
-pub fn parse(parser: &mut Parser) -> ParserResult<Box<dyn Ast>> {
-    if parser.get_tokens().len() > 0  {
-        let mut query_node = make_ast_tag(AstTag::Query);
+fn parse_unwind(parser: &mut Parser, parent_node: &mut Box<AstTagNode>) -> ParserResult<()> {
+    let mut unwind_node = make_ast_tag(AstTag::Unwind);
+    unwind_node.append(parse_arithmetic_expression(parser)?);
+    parser.require(TokenType::As)?;
+    parser.require(TokenType::Identifier)?;
+    let mut alias_node = make_ast_tag(AstTag::Alias);
+    alias_node.append(make_ast_token(parser));
+    unwind_node.append(alias_node);
+    parse_clauses(parser, &mut unwind_node)?;
+    parent_node.append(unwind_node);
+    Ok(())
+}
 
-        let tok = &parser.get_tokens()[0];
-        match tok.token_type {
-            TokenType::Create =>  {
+/// The dotted procedure name is kept in a single identifier token.
+fn parse_call(parser: &mut Parser, parent_node: &mut Box<AstTagNode>) -> ParserResult<()> {
+    let mut call_node = make_ast_tag(AstTag::Call);
+    parser.require(TokenType::Identifier)?;
+    let token_id = parser.index - 1;
+    let mut name = parser.get_tokens()[token_id].get_value();
+    while parser.current_token_type_advance(TokenType::Dot) {
+        parser.require(TokenType::Identifier)?;
+        name.push('.');
+        name.push_str(&parser.get_tokens()[parser.index - 1].get_value());
+    }
+    call_node.append(Box::new(AstTokenNode::new_token(token_id, name, TokenType::Identifier)));
+    parser.require(TokenType::OpenParenthesis)?;
+    if !parser.current_token_type_advance(TokenType::CloseParenthesis) {
+        loop {
+            call_node.append(parse_arithmetic_expression(parser)?);
+            if !parser.current_token_type_advance(TokenType::Comma) {
+                break;
+            }
+        }
+        parser.require(TokenType::CloseParenthesis)?;
+    }
+    if parser.current_token_type_advance(TokenType::Yield) {
+        let mut yield_node = make_ast_tag(AstTag::Yield);
+        loop {
+            parser.require(TokenType::Identifier)?;
+            yield_node.append(make_ast_token(parser));
+            if !parser.current_token_type_advance(TokenType::Comma) {
+                break;
+            }
+        }
+        call_node.append(yield_node);
+    }
+    parent_node.append(call_node);
+    parse_return(parser, parent_node)
+}
+
+/// The list and the variable of a FOREACH come first in its node, as for an UNWIND, followed by its update clauses.
+fn parse_foreach(parser: &mut Parser, parent_node: &mut Box<AstTagNode>) -> ParserResult<()> {
+    let mut foreach_node = make_ast_tag(AstTag::Foreach);
+    parser.require(TokenType::OpenParenthesis)?;
+    parser.require(TokenType::Identifier)?;
+    let mut alias_node = make_ast_tag(AstTag::Alias);
+    alias_node.append(make_ast_token(parser));
+    parser.require(TokenType::In)?;
+    foreach_node.append(parse_arithmetic_expression(parser)?);
+    foreach_node.append(alias_node);
+    parser.require(TokenType::Pipe)?;
+    loop {
+        if parser.current_token_type_advance(TokenType::Create) {
+            let mut create_node = make_ast_tag(AstTag::Create);
+            parse_path(parser, &mut create_node)?;
+            foreach_node.append(create_node);
+        } else if parser.check(TokenType::Set) || parser.check(TokenType::Remove) || parser.check(TokenType::Foreach) {
+            parse_update_clauses(parser, &mut foreach_node)?;
+        } else {
+            break;
+        }
+    }
+    if foreach_node.childs.len() < 3 {
+        return Err(ParserError::SyntaxError(parser.index));
+    }
+    parser.require(TokenType::CloseParenthesis)?;
+    parent_node.append(foreach_node);
+    Ok(())
+}
+
+/// SET and REMOVE items are a variable followed by its labels, as in `n:Label1:Label2`,
+/// REMOVE items may also be a property of a variable.
+fn parse_update_clauses(parser: &mut Parser, parent_node: &mut Box<AstTagNode>) -> ParserResult<()> {
+    loop {
+        if parser.current_token_type_advance(TokenType::Foreach) {
+            parse_foreach(parser, parent_node)?;
+            continue;
+        }
+        let tag = if parser.current_token_type_advance(TokenType::Set) {
+            AstTag::Set
+        } else if parser.current_token_type_advance(TokenType::Remove) {
+            AstTag::Remove
+        } else {
+            return Ok(());
+        };
+        let remove = tag == AstTag::Remove;
+        let mut update_node = make_ast_tag(tag);
+        loop {
+            if remove && parser.check(TokenType::Identifier) && parser.next_token_type(TokenType::Dot) {
                 parser.advance();
+                update_node.append(parse_item_property_identifier(parser)?);
+            } else {
+                parse_labels_item(parser, &mut update_node)?;
+            }
+            if !parser.current_token_type_advance(TokenType::Comma) {
+                break;
+            }
+        }
+        parent_node.append(update_node);
+    }
+}
+
+fn parse_labels_item(parser: &mut Parser, parent_node: &mut Box<AstTagNode>) -> ParserResult<()> {
+    parser.require(TokenType::Identifier)?;
+    let mut var_node = make_ast_tag(AstTag::Variable);
+    var_node.append(make_ast_token(parser));
+    parent_node.append(var_node);
+    parser.require(TokenType::Colon)?;
+    loop {
+        parser.require(TokenType::Identifier)?;
+        let mut label_node = make_ast_tag(AstTag::Label);
+        label_node.append(make_ast_token(parser));
+        parent_node.append(label_node);
+        if !parser.current_token_type_advance(TokenType::Colon) {
+            return Ok(());
+        }
+    }
+}
+
+/// Clauses following an UNWIND are nested in its node as they run once per list element.
+fn parse_clauses(parser: &mut Parser, parent_node: &mut Box<AstTagNode>) -> ParserResult<()> {
+    parser.expect(&[TokenType::Match, TokenType::Optional, TokenType::Create, TokenType::Unwind, TokenType::Foreach]);
+    if parser.index >= parser.get_tokens().len() {
+        return Err(ParserError::SyntaxError(parser.index));
+    }
+    let tok = &parser.get_tokens()[parser.index];
+    match tok.token_type {
+        TokenType::Unwind => {
+            parser.advance();
+            parse_unwind(parser, parent_node)
+        },
+        TokenType::Foreach => {
+            parse_update_clauses(parser, parent_node)?;
+            parse_return(parser, parent_node)
+        },
+        TokenType::Create =>  {
+            parser.advance();
+            let mut create_node = make_ast_tag(AstTag::Create);
+            parse_path(parser, &mut create_node)?;
+            parent_node.append(create_node);
+            parse_where_clause(parser, parent_node)?;
+            parse_update_clauses(parser, parent_node)?;
+            parse_return(parser, parent_node)
+        },
+        TokenType::Match | TokenType::Optional => {
+            if parser.current_token_type_advance(TokenType::Optional) {
+                parser.require(TokenType::Match)?;
+                parse_match_tag(parser, parent_node, AstTag::OptionalMatch)?;
+            } else {
+                parser.advance();
+                parse_match(parser, parent_node)?;
+            }
+            if parser.current_token_type_advance(TokenType::Unwind) {
+                return parse_unwind(parser, parent_node);
+            }
+            if parser.current_token_type_advance(TokenType::Create) {
                 let mut create_node = make_ast_tag(AstTag::Create);
                 parse_path(parser, &mut create_node)?;
-                query_node.append(create_node);
-                parse_where_clause(parser, &mut query_node)?;
-                parse_return(parser, &mut query_node)?;
-                
-                Ok(query_node)
-                
-            },
-            TokenType::Match => {
-                parser.advance();
-                parse_match(parser, &mut query_node)?;
-                if parser.current_token_type_advance(TokenType::Create) {
-                    let mut create_node = make_ast_tag(AstTag::Create);
-                    parse_path(parser, &mut create_node)?;
-                    query_node.append(create_node);
-                }
-                parse_where_clause(parser, &mut query_node)?;
-                parse_return(parser, &mut query_node)?;
-                
-                Ok(query_node)
-            },
-            _ => Err(ParserError::SyntaxError(parser.index))
+                parent_node.append(create_node);
+            }
+            parse_where_clause(parser, parent_node)?;
+            parse_update_clauses(parser, parent_node)?;
+            parse_return(parser, parent_node)
+        },
+        _ => Err(ParserError::SyntaxError(parser.index))
+    }
+}
+
+/// Each query part following a UNION is nested in its node.
+fn parse_unions(parser: &mut Parser, parent_node: &mut Box<AstTagNode>) -> ParserResult<()> {
+    while parser.current_token_type_advance(TokenType::Union) {
+        let tag = if parser.current_token_type_advance(TokenType::All) { AstTag::UnionAll } else { AstTag::Union };
+        let mut union_node = make_ast_tag(tag);
+        parse_clauses(parser, &mut union_node)?;
+        parent_node.append(union_node);
+    }
+    Ok(())
+}
+
+pub fn parse(parser: &mut Parser) -> ParserResult<Box<dyn Ast>> {
+    if parser.get_tokens().len() > 0  {
+        let mut query_node = make_ast_tag(AstTag::Query);
+
+        if parser.current_token_type_advance(TokenType::Explain) {
+            query_node.append(make_ast_tag(AstTag::Explain));
+        } else if parser.current_token_type_advance(TokenType::Profile) {
+            query_node.append(make_ast_tag(AstTag::Profile));
+        }
+        if parser.check(TokenType::Create) && parser.next_token_type(TokenType::Constraint) {
+            parser.advance();
+            parse_create_constraint(parser, &mut query_node)?;
+        } else if parser.current_token_type_advance(TokenType::Call) {
+            parse_call(parser, &mut query_node)?;
+        } else {
+            parse_clauses(parser, &mut query_node)?;
+            parse_unions(parser, &mut query_node)?;
+        }
+        if parser.index < parser.get_tokens().len() {
+            return Err(ParserError::SyntaxError(parser.index));
         }
+        Ok(query_node)
     } else {
         Err(ParserError::SyntaxError(parser.index))
     }
@@ -18,22 +18,50 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-use std::fmt;
+use thiserror::Error;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
 pub enum ParserError {
+    #[error("syntax error around token {0}")]
     SyntaxError(usize),
-    EndOfFile
+    #[error("unexpected end of query")]
+    EndOfFile,
+    /// Error reported to the client: `token` is None when the query ends too early, `expected` lists the tokens that could follow.
+    #[error("unexpected {} at line {line}, column {column}{}", describe_token(token), describe_expected(expected))]
+    UnexpectedToken {
+        line: usize,
+        column: usize,
+        token: Option<String>,
+        expected: Vec<String>,
+    },
+    #[error("the query is syntactically valid but cannot be run")]
+    SemanticError,
 }
 
 pub type ParserResult<T> = std::result::Result<T, ParserError>;
 
+impl ParserError {
+    /// Locates the error found at the byte `offset` of the query, lines and columns start at 1.
+    pub fn unexpected(query: &str, offset: usize, token: Option<&str>, expected: Vec<String>) -> Self {
+        let before = query.get(..offset).unwrap_or(query);
+        let line = before.matches('\n').count() + 1;
+        let column = before.rsplit('\n').next().map_or(0, |last_line| last_line.chars().count()) + 1;
+        ParserError::UnexpectedToken{line, column, token: token.map(String::from), expected}
+    }
+}
+
+fn describe_token(token: &Option<String>) -> String {
+    match token {
+        Some(token) if token.starts_with(['\'', '"']) => token.clone(),
+        Some(token) => format!("'{}'", token),
+        None => String::from("end of query"),
+    }
+}
 
-impl fmt::Display for ParserError {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match *self {
-            ParserError::SyntaxError(index) => f.write_str(&format!("Syntax Error Around {}", index)),
-            ParserError::EndOfFile => f.write_str("InternalServerError"),
-        }
+fn describe_expected(expected: &[String]) -> String {
+    match expected {
+        [] => String::new(),
+        [token] => format!(", expected {}", token),
+        _ => format!(", expected one of {}", expected.join(", ")),
     }
 }
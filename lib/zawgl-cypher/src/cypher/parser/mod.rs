@@ -25,11 +25,15 @@ mod properties_parser_delegate;
 mod common_parser_delegate;
 mod return_clause_parser_delegate;
 mod where_clause_parser_delegate;
+mod constraint_parser_delegate;
 pub mod cypher_parser;
 
+use std::cell::RefCell;
+
 use zawgl_cypher_query_model::ast::{AstTagNode, AstTag, AstTokenNode, Ast, AstVisitorResult, AstVisitor};
 use zawgl_cypher_query_model::token::{TokenType, Token};
 use self::error::*;
+use super::lexer::get_token_type_name;
 
 
 pub fn walk_ast(visitor: &mut dyn AstVisitor, ast: &Box<dyn Ast>) -> AstVisitorResult<()>  {
@@ -46,11 +50,38 @@ pub fn walk_ast(visitor: &mut dyn AstVisitor, ast: &Box<dyn Ast>) -> AstVisitorR
 pub struct Parser<'a>  {
     tokens: Vec<Token<'a>>,
     pub index: usize,
+    /// Furthest token index checked and the token types expected there, reported when the parsing fails.
+    expected: RefCell<(usize, Vec<TokenType>)>,
 }
 
 impl <'a> Parser<'a> {
     pub fn new(tokens: Vec<Token>) -> Parser {
-        Parser {tokens : tokens, index: 0}
+        Parser {tokens : tokens, index: 0, expected: RefCell::new((0, Vec::new()))}
+    }
+
+    /// Records the token types accepted at the current index, alternatives parsed with a match on the token type are listed here.
+    pub fn expect(&self, token_types: &[TokenType]) {
+        let mut expected = self.expected.borrow_mut();
+        if self.index > expected.0 {
+            *expected = (self.index, Vec::new());
+        }
+        if self.index == expected.0 {
+            for token_type in token_types {
+                if !expected.1.contains(token_type) {
+                    expected.1.push(*token_type);
+                }
+            }
+        }
+    }
+
+    /// Syntax error at the furthest token reached, with the token types that would have been accepted there.
+    pub fn get_syntax_error(&self, query: &str) -> ParserError {
+        let expected = self.expected.borrow();
+        let expected_names = expected.1.iter().map(|token_type| get_token_type_name(*token_type)).collect();
+        match self.tokens.get(expected.0) {
+            Some(token) => ParserError::unexpected(query, token.begin, Some(token.content), expected_names),
+            None => ParserError::unexpected(query, query.trim_end().len(), None, expected_names),
+        }
     }
 
     pub fn get_tokens(&self) -> &Vec<Token> {
@@ -74,6 +105,7 @@ impl <'a> Parser<'a> {
     }
 
     pub fn current_token_type_advance(&mut self, token_type: TokenType) -> bool {
+        self.expect(&[token_type]);
         if self.tokens.len() > self.index && self.tokens[self.index].token_type == token_type {
             self.advance();
             true
@@ -87,6 +119,7 @@ impl <'a> Parser<'a> {
     }
 
     pub fn check(&self, token_type: TokenType) -> bool {
+        self.expect(&[token_type]);
         self.tokens.len() > self.index && self.tokens[self.index].token_type == token_type
     }
 
@@ -100,7 +133,7 @@ impl <'a> Parser<'a> {
 fn make_ast_token(parser: &Parser) -> Box<AstTokenNode> {
     let token_id = parser.index - 1;
     let token = &parser.get_tokens()[token_id];
-    Box::new(AstTokenNode::new_token(token_id, token.content.to_owned(), token.token_type ))
+    Box::new(AstTokenNode::new_token(token_id, token.get_value(), token.token_type ))
 }
 
 fn make_ast_tag(tag: AstTag) -> Box<AstTagNode> {
@@ -165,9 +198,34 @@ mod test_parser {
         run("match (p:Person), (m:Movie) match (m)<-[r:Played]-(p) return m, r, p");
     }
 
+    #[test]
+    fn test_optional_match() {
+        run("match (a:Person) optional match (a)-[r:OWNS]->(c:Car) return a, r, c");
+    }
+
+    #[test]
+    fn test_return_aggregation() {
+        run("match (p:Person) return p.city, count(p), sum(p.age), count(*)");
+    }
+
+    #[test]
+    fn test_where_comparisons() {
+        run("match (n:Person) where n.age >= 18 and not (n.age > 65 or n.name <> 'Bob') return n");
+    }
+
+    #[test]
+    fn test_where_then_match() {
+        run("match (n:Person) where n.age < 18 match (n)-[r:OWNS]->(c:Car) return n, r, c");
+    }
+
     #[test]
     fn test_where_id_parameter() {
         run("MATCH (m:Movie) WHERE id(m) = $mid RETURN m, a, r");
     }
+
+    #[test]
+    fn test_create_constraint() {
+        run("CREATE CONSTRAINT ON (n:Person) ASSERT n.email IS UNIQUE");
+    }
 }
 
@@ -134,9 +134,11 @@ fn enter_node_def(parser: &mut Parser, mut parent_node: &mut Box<AstTagNode>) ->
     let mut node = Box::new(AstTagNode::new_tag(AstTag::Node));
     
     parser.require(TokenType::OpenParenthesis)?;
-    let mut var_node = Box::new(AstTagNode::new_tag(AstTag::Variable));
-    enter_identifier(parser, &mut var_node)?;
-    node.append(var_node);
+    if parser.check(TokenType::Identifier) {
+        let mut var_node = Box::new(AstTagNode::new_tag(AstTag::Variable));
+        enter_identifier(parser, &mut var_node)?;
+        node.append(var_node);
+    }
 
     if parser.current_token_type_advance(TokenType::Colon) {
         enter_labels(parser, &mut node)?;
@@ -157,6 +159,8 @@ fn enter_rel_tags(parser: &mut Parser, parent_node: &mut Box<AstTagNode>) -> Par
         let mut label_tag = Box::new(AstTagNode::new_tag(AstTag::Label));
         enter_identifier(parser, &mut label_tag)?;
         if parser.current_token_type_advance(TokenType::Pipe) {
+            // `[:A|:B]` is the legacy form of `[:A|B]`
+            parser.current_token_type_advance(TokenType::Colon);
             parent_node.append(label_tag);
             return enter_rel_tags(parser, parent_node);
         } else {
@@ -188,6 +192,7 @@ fn enter_rel_id(parser: &mut Parser, parent_node: &mut Box<AstTagNode>) -> Parse
 
 fn enter_rel_def(parser: &mut Parser, parent_node: &mut Box<AstTagNode>) -> ParserResult<()> {
     let mut rel_fsm = RelationshipFsm::new();
+    parser.expect(&[TokenType::LeftSourceRel, TokenType::LeftTargetRel]);
     if parser.has_next() {
         match parser.get_current_token_type() {
             TokenType::LeftSourceRel |
@@ -214,6 +219,7 @@ fn enter_rel_def(parser: &mut Parser, parent_node: &mut Box<AstTagNode>) -> Pars
 }
 
 fn exit_rel_def(parser: &mut Parser, mut rel_node: Box<AstTagNode>, rel_fsm: &mut RelationshipFsm, parent_node: &mut Box<AstTagNode>) -> ParserResult<()> {
+    parser.expect(&[TokenType::RightSourceRel, TokenType::RightTargetRel]);
     if parser.has_next() {
         match parser.get_current_token_type() {
             TokenType::RightSourceRel |
@@ -240,8 +246,12 @@ fn exit_rel_def(parser: &mut Parser, mut rel_node: Box<AstTagNode>, rel_fsm: &mu
 
 pub fn parse_path(parser: &mut Parser, parent_node: &mut Box<AstTagNode>) -> ParserResult<()> {
     let mut path = Box::new(AstTagNode::new_tag(AstTag::Path));
-    
-    
+    if parser.check(TokenType::Identifier) && parser.next_token_type(TokenType::Equals) {
+        let mut var_node = Box::new(AstTagNode::new_tag(AstTag::Variable));
+        enter_identifier(parser, &mut var_node)?;
+        path.append(var_node);
+        parser.require(TokenType::Equals)?;
+    }
     enter_node_def(parser, &mut path)?;
     
     if parser.current_token_type_advance(TokenType::Comma) {
@@ -251,3 +261,10 @@ pub fn parse_path(parser: &mut Parser, parent_node: &mut Box<AstTagNode>) -> Par
     parent_node.append(path);
     Ok(())
 }
+
+/// Single path without a path variable, as in the pattern predicates of a where clause.
+pub fn parse_path_pattern(parser: &mut Parser) -> ParserResult<Box<AstTagNode>> {
+    let mut path = Box::new(AstTagNode::new_tag(AstTag::Path));
+    enter_node_def(parser, &mut path)?;
+    Ok(path)
+}
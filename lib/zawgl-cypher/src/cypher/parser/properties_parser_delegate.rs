@@ -21,6 +21,7 @@
 
 use super::*;
 use super::error::*;
+use super::common_parser_delegate::{parse_function_definition, parse_item_property_identifier};
 
 use zawgl_cypher_query_model::ast::{AstTagNode, AstTag, AstTokenNode, Ast, AstVisitorResult, AstVisitor};
 use zawgl_cypher_query_model::token::{TokenType, Token};
@@ -73,7 +74,14 @@ fn enter_bool_expr(parser: &mut Parser, parent_node: &mut Box<dyn Ast>) -> Parse
     }
 }
 
-fn enter_prop_value(parser: &mut Parser, parent_node: &mut Box<dyn Ast>) -> ParserResult<usize> {
+const VALUE_TOKENS: [TokenType; 10] = [TokenType::Identifier, TokenType::Integer, TokenType::Float, TokenType::StringType, TokenType::Parameter,
+    TokenType::True, TokenType::False, TokenType::Null, TokenType::OpenBracket, TokenType::OpenBrace];
+
+pub fn enter_prop_value(parser: &mut Parser, parent_node: &mut Box<dyn Ast>) -> ParserResult<usize> {
+    parser.expect(&VALUE_TOKENS);
+    if parser.index >= parser.get_tokens().len() {
+        return Err(ParserError::EndOfFile)
+    }
     match parser.get_current_token_type() {
         TokenType::StringType => {
             enter_string_expr(parser, parent_node)
@@ -88,12 +96,90 @@ fn enter_prop_value(parser: &mut Parser, parent_node: &mut Box<dyn Ast>) -> Pars
         TokenType::Integer => {
             enter_integer_expr(parser, parent_node)
         },
+        TokenType::Null => {
+            parser.advance();
+            parent_node.append(make_ast_token(parser));
+            Ok(parser.index)
+        },
+        TokenType::OpenBracket |
+        TokenType::OpenBrace => {
+            let literal_node = parse_collection_literal(parser)?;
+            parent_node.append(literal_node);
+            Ok(parser.index)
+        },
+        TokenType::Identifier if parser.next_token_type(TokenType::OpenParenthesis) => {
+            parser.advance();
+            parent_node.append(parse_function_definition(parser)?);
+            Ok(parser.index)
+        },
+        TokenType::Parameter => {
+            parser.advance();
+            parent_node.append(make_ast_token(parser));
+            Ok(parser.index)
+        },
+        TokenType::Identifier if parser.next_token_type(TokenType::Dot) => {
+            parser.advance();
+            parent_node.append(parse_item_property_identifier(parser)?);
+            Ok(parser.index)
+        },
+        TokenType::Identifier => {
+            // a variable is referenced as an item property identifier without property
+            parser.advance();
+            let mut variable = make_ast_tag(AstTag::ItemPropertyIdentifier);
+            variable.append(make_ast_token(parser));
+            parent_node.append(variable);
+            Ok(parser.index)
+        },
         _ => {
             Err(ParserError::SyntaxError(parser.index))
         }
     }
 }
 
+fn parse_list_literal(parser: &mut Parser) -> ParserResult<Box<dyn Ast>> {
+    parser.require(TokenType::OpenBracket)?;
+    let mut list_node: Box<dyn Ast> = Box::new(AstTagNode::new_tag(AstTag::List));
+    if !parser.current_token_type_advance(TokenType::CloseBracket) {
+        loop {
+            enter_prop_value(parser, &mut list_node)?;
+            if !parser.current_token_type_advance(TokenType::Comma) {
+                break;
+            }
+        }
+        parser.require(TokenType::CloseBracket)?;
+    }
+    Ok(list_node)
+}
+
+fn parse_map_literal(parser: &mut Parser) -> ParserResult<Box<dyn Ast>> {
+    parser.require(TokenType::OpenBrace)?;
+    let mut map_node: Box<dyn Ast> = Box::new(AstTagNode::new_tag(AstTag::Map));
+    if !parser.current_token_type_advance(TokenType::CloseBrace) {
+        loop {
+            if !parser.current_token_type_advance(TokenType::Identifier) {
+                return Err(ParserError::SyntaxError(parser.index));
+            }
+            enter_prop_name(parser, &mut map_node)?;
+            parser.require(TokenType::Colon)?;
+            enter_prop_value(parser, &mut map_node)?;
+            if !parser.current_token_type_advance(TokenType::Comma) {
+                break;
+            }
+        }
+        parser.require(TokenType::CloseBrace)?;
+    }
+    Ok(map_node)
+}
+
+/// Parses a `[1, 2]` list or a `{a: 1}` map literal.
+pub fn parse_collection_literal(parser: &mut Parser) -> ParserResult<Box<dyn Ast>> {
+    match parser.get_current_token_type() {
+        TokenType::OpenBracket => parse_list_literal(parser),
+        TokenType::OpenBrace => parse_map_literal(parser),
+        _ => Err(ParserError::SyntaxError(parser.index)),
+    }
+}
+
 fn enter_prop_name(parser: &mut Parser, parent_node: &mut Box<dyn Ast>) -> ParserResult<usize> {
     let id_node = make_ast_token(&parser);
     parent_node.append(id_node);
@@ -25,6 +25,7 @@ use super::error::*;
 use zawgl_cypher_query_model::ast::{AstTagNode, AstTag, AstTokenNode, Ast, AstVisitorResult, AstVisitor};
 use zawgl_cypher_query_model::token::{TokenType, Token};
 use super::common_parser_delegate::*;
+use super::pattern_parser_delegate::parse_path_pattern;
 
 pub fn parse_where_clause(parser: &mut Parser, parent_node: &mut Box<AstTagNode>) -> ParserResult<()> {
     if parser.check(TokenType::Where) {
@@ -36,138 +37,122 @@ pub fn parse_where_clause(parser: &mut Parser, parent_node: &mut Box<AstTagNode>
     Ok(())
 }
 
-fn parse_boolean_operator(parser: &mut Parser, prev_expr: Box<AstTagNode>) -> ParserResult<Box<AstTagNode>> {
-    if parser.check(TokenType::And) {
-        parser.advance();
-        let mut operator = make_ast_tag(AstTag::AndOperator);
-        let mut expr = parse_boolean_expression(parser)?;
-        if expr.ast_tag == Some(AstTag::OrOperator) {
-            operator.append(prev_expr);
-            operator.append(expr.childs.remove(0));
-            expr.append(operator);
-            Ok(expr)
-        } else {
-            operator.append(prev_expr);
-            operator.append(expr);
-            Ok(operator)
-        }
-        
-    } else if parser.check(TokenType::Or) {
-        parser.advance();
+fn parse_boolean_expression(parser: &mut Parser) -> ParserResult<Box<AstTagNode>> {
+    let mut expr = parse_and_expression(parser)?;
+    while parser.current_token_type_advance(TokenType::Or) {
         let mut operator = make_ast_tag(AstTag::OrOperator);
-        let expr = parse_boolean_expression(parser)?;
-        operator.append(prev_expr);
         operator.append(expr);
+        operator.append(parse_and_expression(parser)?);
+        expr = operator;
+    }
+    Ok(expr)
+}
+
+fn parse_and_expression(parser: &mut Parser) -> ParserResult<Box<AstTagNode>> {
+    let mut expr = parse_not_expression(parser)?;
+    while parser.current_token_type_advance(TokenType::And) {
+        let mut operator = make_ast_tag(AstTag::AndOperator);
+        operator.append(expr);
+        operator.append(parse_not_expression(parser)?);
+        expr = operator;
+    }
+    Ok(expr)
+}
+
+fn parse_not_expression(parser: &mut Parser) -> ParserResult<Box<AstTagNode>> {
+    if parser.current_token_type_advance(TokenType::Not) {
+        let mut operator = make_ast_tag(AstTag::NotOperator);
+        operator.append(parse_not_expression(parser)?);
         Ok(operator)
+    } else if parser.check(TokenType::OpenParenthesis) {
+        // the parenthesis may also open a pattern or an arithmetic operand, e.g. (n.age + 1) > 18
+        let begin = parser.index;
+        if let Ok(predicate) = parse_pattern_predicate(parser) {
+            return Ok(predicate)
+        }
+        parser.index = begin;
+        parser.advance();
+        match parse_boolean_expression(parser).and_then(|expr| parser.require(TokenType::CloseParenthesis).map(|_| expr)) {
+            Ok(expr) => Ok(expr),
+            Err(_) => {
+                parser.index = begin;
+                parse_comparison(parser)
+            },
+        }
+    } else if parser.check(TokenType::Identifier) && parser.get_tokens()[parser.index].content.eq_ignore_ascii_case("exists") && parser.next_token_type(TokenType::OpenParenthesis) {
+        parse_exists(parser)
     } else {
-        Ok(prev_expr)
+        parse_comparison(parser)
     }
 }
 
-fn parse_boolean_expression_terminal(parser: &mut Parser, parent_node: &mut Box<AstTagNode>) -> ParserResult<()> {
-    match parser.get_current_token_type() {
-        TokenType::Integer | TokenType::Float | TokenType::True | TokenType::False | TokenType::StringType | TokenType::Parameter => {
-            parser.advance();
-            parent_node.append(make_ast_token(parser));
-            Ok(())
-        },
-        TokenType::Identifier => {
-            parser.advance();
-            if parser.check(TokenType::OpenParenthesis) {
-                let func = parse_function_definition(parser)?;
-                parent_node.append(func);
-            } else if parser.check(TokenType::Dot) {
-                let mut item_prop = make_ast_tag(AstTag::ItemPropertyIdentifier);
-                item_prop.append(make_ast_token(parser));
-                parser.advance();
-                if parser.check(TokenType::Identifier) {
-                    parser.advance();
-                    item_prop.append(make_ast_token(parser));
-                } else {
-                    return Err(ParserError::SyntaxError(parser.index))
-                }
-            } else {
-                return Err(ParserError::SyntaxError(parser.index))
-            }
-            Ok(())
+/// `exists(n.prop)` is the same condition as `n.prop IS NOT NULL` and `exists((n)-[:R]->())` as the pattern predicate.
+fn parse_exists(parser: &mut Parser) -> ParserResult<Box<AstTagNode>> {
+    parser.advance();
+    parser.require(TokenType::OpenParenthesis)?;
+    let begin = parser.index;
+    let condition = match parse_pattern_predicate(parser) {
+        Ok(predicate) => predicate,
+        Err(_) => {
+            parser.index = begin;
+            let mut operator = make_ast_tag(AstTag::IsNotNullOperator);
+            operator.append(parse_arithmetic_expression(parser)?);
+            operator
         },
-        _ => {
-            Err(ParserError::SyntaxError(parser.index))
-        }
+    };
+    parser.require(TokenType::CloseParenthesis)?;
+    Ok(condition)
+}
+
+/// A pattern predicate has at least one relationship, `(n)` alone is a parenthesized expression.
+fn parse_pattern_predicate(parser: &mut Parser) -> ParserResult<Box<AstTagNode>> {
+    let path = parse_path_pattern(parser)?;
+    if path.childs.len() < 3 {
+        return Err(ParserError::SyntaxError(parser.index))
     }
+    let mut predicate = make_ast_tag(AstTag::PatternPredicate);
+    predicate.append(path);
+    Ok(predicate)
 }
 
-fn parse_boolean_expression(parser: &mut Parser) -> ParserResult<Box<AstTagNode>> {
-    match parser.get_current_token_type() {
-        TokenType::Integer => {
-            parser.advance();
-            parser.require(TokenType::Equals)?;
-            let mut eqop = make_ast_tag(AstTag::EqualityOperator);
-            parse_boolean_expression_terminal(parser, &mut eqop)?;
-            parse_boolean_operator(parser, eqop)
-        },
-        TokenType::Float => {
-            parser.advance();
-            parser.require(TokenType::Equals)?;
-            let mut eqop = make_ast_tag(AstTag::EqualityOperator);
-            parse_boolean_expression_terminal(parser, &mut eqop)?;
-            parse_boolean_operator(parser, eqop)
-        },
-        TokenType::Parameter => {
-            parser.advance();
-            parser.require(TokenType::Equals)?;
-            let mut eqop = make_ast_tag(AstTag::EqualityOperator);
-            parse_boolean_expression_terminal(parser, &mut eqop)?;
-            parse_boolean_operator(parser, eqop)
-        }
-        TokenType::True | TokenType::False => {
-            parser.advance();
-            parser.require(TokenType::Equals)?;
-            let mut eqop = make_ast_tag(AstTag::EqualityOperator);
-            parse_boolean_expression_terminal(parser, &mut eqop)?;
-            parse_boolean_operator(parser, eqop)
-        },
-        TokenType::Identifier => {
-            parser.advance();
-            
-            if parser.check(TokenType::OpenParenthesis) {
-                let func = parse_function_definition(parser)?;
-                if parser.check(TokenType::Equals) {
-                    parser.advance();
-                    let mut eqop = make_ast_tag(AstTag::EqualityOperator);
-                    eqop.append(func);
-                    parse_boolean_expression_terminal(parser, &mut eqop)?;
-                    return parse_boolean_operator(parser, eqop)
-                } else {
-                    return parse_boolean_operator(parser, func)
-                }
-            } else if parser.check(TokenType::Dot) {
-                let mut item_prop = make_ast_tag(AstTag::ItemPropertyIdentifier);
-                item_prop.append(make_ast_token(parser));
-                parser.advance();
-                if parser.check(TokenType::Identifier) {
-                    parser.advance();
-                    item_prop.append(make_ast_token(parser));
-                    parser.require(TokenType::Equals)?;
-                    let mut eqop = make_ast_tag(AstTag::EqualityOperator);
-                    eqop.append(item_prop);
-                    parse_boolean_expression_terminal(parser, &mut eqop)?;
-                    return parse_boolean_operator(parser, eqop)
-                } else {
-                    return Err(ParserError::SyntaxError(parser.index))
-                }
-            } else {
-                return Err(ParserError::SyntaxError(parser.index))
-            }
-        },
-        TokenType::OpenParenthesis => {
-            parser.advance();
-            let expr = parse_boolean_expression(parser)?;
-            parser.require(TokenType::CloseParenthesis)?;
-            return parse_boolean_operator(parser, expr)
-        },
-        _ => {
-            Err(ParserError::SyntaxError(parser.index))
-        }
+const COMPARISON_OPERATORS: [TokenType; 9] = [TokenType::Equals, TokenType::NotEqual, TokenType::LessThan, TokenType::LessThanOrEqual, TokenType::GreaterThan,
+    TokenType::GreaterThanOrEqual, TokenType::Contains, TokenType::Starts, TokenType::Ends];
+
+fn get_comparison_tag(token_type: TokenType) -> Option<AstTag> {
+    match token_type {
+        TokenType::Equals => Some(AstTag::EqualityOperator),
+        TokenType::NotEqual => Some(AstTag::InequalityOperator),
+        TokenType::LessThan => Some(AstTag::LessThanOperator),
+        TokenType::LessThanOrEqual => Some(AstTag::LessThanOrEqualOperator),
+        TokenType::GreaterThan => Some(AstTag::GreaterThanOperator),
+        TokenType::GreaterThanOrEqual => Some(AstTag::GreaterThanOrEqualOperator),
+        TokenType::Contains => Some(AstTag::ContainsOperator),
+        TokenType::Starts => Some(AstTag::StartsWithOperator),
+        TokenType::Ends => Some(AstTag::EndsWithOperator),
+        _ => None,
+    }
+}
+
+fn parse_comparison(parser: &mut Parser) -> ParserResult<Box<AstTagNode>> {
+    let first_member = parse_arithmetic_expression(parser)?;
+    if parser.current_token_type_advance(TokenType::Is) {
+        let tag = if parser.current_token_type_advance(TokenType::Not) { AstTag::IsNotNullOperator } else { AstTag::IsNullOperator };
+        parser.require(TokenType::Null)?;
+        let mut operator = make_ast_tag(tag);
+        operator.append(first_member);
+        return Ok(operator)
+    }
+    parser.expect(&COMPARISON_OPERATORS);
+    if parser.index >= parser.get_tokens().len() {
+        return Err(ParserError::EndOfFile)
+    }
+    let tag = get_comparison_tag(parser.get_current_token_type()).ok_or(ParserError::SyntaxError(parser.index))?;
+    parser.advance();
+    if tag == AstTag::StartsWithOperator || tag == AstTag::EndsWithOperator {
+        parser.require(TokenType::With)?;
     }
+    let mut operator = make_ast_tag(tag);
+    operator.append(first_member);
+    operator.append(parse_arithmetic_expression(parser)?);
+    Ok(operator)
 }
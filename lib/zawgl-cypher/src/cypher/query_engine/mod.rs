@@ -19,39 +19,59 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-use crate::parameters::Parameters;
+use crate::parameters::{Parameters, ParameterValue};
+
+use std::collections::{BTreeMap, HashMap};
 
 use super::*;
+use super::parser::error::{ParserError, ParserResult};
 use zawgl_core::model::*;
+use zawgl_core::model::constraints::UniqueConstraint;
 
 
 mod path_builder;
 mod states;
 mod pattern_builder;
 
-use zawgl_cypher_query_model::{QueryStep, StepType};
-use zawgl_cypher_query_model::ast::{AstTagNode, AstTag, AstTokenNode, Ast, AstVisitorResult, AstVisitor};
-use zawgl_cypher_query_model::model::{Request, ReturnClause, WhereClause, ReturnExpression, FunctionCall};
+use zawgl_cypher_query_model::{QueryStep, StepType, ExecutionMode};
+use zawgl_cypher_query_model::ast::{AstTagNode, AstTag, Ast, AstVisitorResult, AstVisitor, AstVisitorError};
+use zawgl_cypher_query_model::model::{Request, UnionPart, ReturnClause, WhereClause, ReturnExpression, FunctionCall, ItemPropertyName, Expression, Operator, ProcedureCall, UpdateItem};
 use zawgl_cypher_query_model::token::{TokenType, Token};
+use zawgl_cypher_query_planner::temporal::eval_temporal_function;
 
 use states::*;
 use path_builder::*;
 use pattern_builder::*;
 
-pub fn process_cypher_query(query: &str, params: Option<Parameters>) -> Option<Request> {
-    let mut lexer = lexer::Lexer::new(query);
-    match lexer.get_tokens() {
-        Ok(tokens) => {
-            let mut parser = parser::Parser::new(tokens);
-            let ast = parser::cypher_parser::parse(&mut parser).ok()?;
-            let mut visitor = CypherAstVisitor::new(params);
-            parser::walk_ast(&mut visitor, &ast).ok()?;
-            visitor.request
-        }
-        Err(value) => None
+/// Parsed query, its parameters are resolved each time a request is built from it.
+pub struct PreparedQuery {
+    ast: Box<dyn Ast>,
+}
+
+impl PreparedQuery {
+    pub fn build_request(&self, params: Option<Parameters>) -> ParserResult<Request> {
+        let mut visitor = CypherAstVisitor::new(params);
+        parser::walk_ast(&mut visitor, &self.ast).map_err(|_| ParserError::SemanticError)?;
+        visitor.request.ok_or(ParserError::SemanticError)
     }
 }
 
+/// Syntax errors are located in the query text, with the tokens expected at the offending position.
+pub fn prepare_cypher_query(query: &str) -> ParserResult<PreparedQuery> {
+    let mut lexer = lexer::Lexer::new(query);
+    let tokens = lexer.get_tokens().map_err(|err| {
+        let position = err.get_position().unwrap_or_else(|| lexer.get_position());
+        ParserError::unexpected(query, position, query.get(position..).and_then(|rest| rest.split_whitespace().next()), Vec::new())
+    })?;
+    let mut parser = parser::Parser::new(tokens);
+    let ast = parser::cypher_parser::parse(&mut parser).map_err(|_| parser.get_syntax_error(query))?;
+    Ok(PreparedQuery{ast: ast})
+}
+
+pub fn process_cypher_query(query: &str, params: Option<Parameters>) -> ParserResult<Request> {
+    prepare_cypher_query(query)?.build_request(params)
+}
+
 
 struct CypherAstVisitor {
     request: Option<Request>,
@@ -60,17 +80,46 @@ struct CypherAstVisitor {
     id_type: Option<IdentifierType>,    
     path_builders: Vec<PathBuilder>,
     params: Option<Parameters>,
+    item_property_identifiers: Option<Vec<String>>,
+    expressions: Vec<Vec<Expression>>,
+    return_alias: Option<String>,
+    literals: Vec<LiteralFrame>,
+    bindings: HashMap<String, PropertyValue>,
+    procedure: Option<ProcedureCall>,
+    updates: Vec<UpdateItem>,
+}
+
+/// Literal values being built, function calls in pattern properties are evaluated once their arguments are known.
+enum LiteralFrame {
+    List(Vec<PropertyValue>),
+    Map(BTreeMap<String, PropertyValue>, Option<String>),
+    Function(Option<String>, Vec<PropertyValue>),
 }
 
 impl CypherAstVisitor {
     fn new(params: Option<Parameters>) -> Self {
         CypherAstVisitor { request: None, state: VisitorState::Init,
-            curr_identifier: None, id_type: None, path_builders: Vec::new(), params: params}
+            curr_identifier: None, id_type: None, path_builders: Vec::new(), params: params, item_property_identifiers: None, expressions: Vec::new(), return_alias: None, literals: Vec::new(), bindings: HashMap::new(), procedure: None, updates: Vec::new()}
     }
 }
 
 impl CypherAstVisitor {
     
+    /// Items of a SET or REMOVE clause are variables followed by their labels.
+    fn push_update_step(&mut self, step_type: StepType, node: &AstTagNode) -> AstVisitorResult<bool> {
+        let previous_state = self.state;
+        self.state = VisitorState::UpdateItem;
+        for child in &node.childs {
+            parser::walk_ast(self, child)?;
+        }
+        self.state = previous_state;
+        let updates = std::mem::take(&mut self.updates);
+        if let Some(req) = &mut self.request {
+            req.steps.push(QueryStep::new_update(step_type, updates));
+        }
+        Ok(false)
+    }
+
     fn current_path_builder(&mut self) -> Option<&mut PathBuilder> {
         self.path_builders.last_mut()
     }
@@ -78,7 +127,228 @@ impl CypherAstVisitor {
     fn append_path(&mut self) {
         self.path_builders.push(PathBuilder::new(self.params.clone()));
     }
+
+    /// Where conditions, return items, unwound lists and procedure arguments are built on the expression stack.
+    fn is_in_expression(&self) -> bool {
+        matches!(self.state, VisitorState::WhereClause | VisitorState::ReturnItem | VisitorState::Unwind | VisitorState::ProcedureArg)
+    }
+
+    /// Value of an unwound variable or of one of its map entries.
+    fn get_variable_value(&self, name: &str, property_name: Option<&String>) -> Option<PropertyValue> {
+        let value = self.bindings.get(name)?;
+        match (property_name, value) {
+            (None, _) => Some(value.clone()),
+            (Some(key), PropertyValue::PMap(entries)) => Some(entries.get(key).cloned().unwrap_or(PropertyValue::PNull)),
+            (Some(_), PropertyValue::PNull) => Some(PropertyValue::PNull),
+            _ => None,
+        }
+    }
+
+    /// Unwound variables are replaced by their value in where conditions and nested unwound lists
+    /// as each list element has its own steps.
+    fn make_item_expression(&self, item_name: &str, property_name: Option<&String>) -> Expression {
+        match (self.state, self.get_variable_value(item_name, property_name)) {
+            (VisitorState::WhereClause | VisitorState::Unwind, Some(value)) => Expression::Value(value),
+            _ => match property_name {
+                Some(property_name) => Expression::ItemPropertyName(ItemPropertyName::new(item_name, property_name)),
+                None => Expression::Item(String::from(item_name)),
+            },
+        }
+    }
+
+    /// Variable and values of the list of an UNWIND or a FOREACH, a null list has no values.
+    fn eval_list_variable(&mut self, list: &[Box<dyn Ast>]) -> AstVisitorResult<(String, Vec<PropertyValue>)> {
+        let previous_state = self.state;
+        self.state = VisitorState::Unwind;
+        self.expressions = vec![Vec::new()];
+        for child in list {
+            parser::walk_ast(self, child)?;
+        }
+        self.state = previous_state;
+        let variable = self.return_alias.take().ok_or(AstVisitorError::SyntaxError)?;
+        let values = match self.expressions.pop().and_then(|mut exprs| exprs.pop()) {
+            Some(Expression::Value(PropertyValue::PList(values))) => values,
+            Some(Expression::Value(PropertyValue::PNull)) => Vec::new(),
+            Some(Expression::Value(value)) => vec![value],
+            _ => return Err(AstVisitorError::SyntaxError),
+        };
+        Ok((variable, values))
+    }
+
+    /// Builds the steps following an UNWIND for a list element bound to its variable.
+    fn make_unwind_batch(&self, variable: &str, value: PropertyValue, clauses: &[Box<dyn Ast>]) -> AstVisitorResult<Request> {
+        let mut visitor = CypherAstVisitor::new(self.params.clone());
+        visitor.bindings = self.bindings.clone();
+        visitor.bindings.insert(String::from(variable), value);
+        visitor.request = Some(Request::new());
+        for clause in clauses {
+            parser::walk_ast(&mut visitor, clause)?;
+        }
+        visitor.request.ok_or(AstVisitorError::SyntaxError)
+    }
+
+    fn push_expression(&mut self, expr: Expression) {
+        if let Some(frame) = self.expressions.last_mut() {
+            frame.push(expr);
+        }
+    }
+
+    /// Adds a literal value to the enclosing list or map literal, the where clause or the current pattern property.
+    fn push_value(&mut self, value: Option<PropertyValue>) -> AstVisitorResult<bool> {
+        match self.literals.last_mut() {
+            Some(LiteralFrame::List(values)) => values.push(value.ok_or(AstVisitorError::SyntaxError)?),
+            Some(LiteralFrame::Map(entries, key)) => {
+                let name = key.take().ok_or(AstVisitorError::SyntaxError)?;
+                entries.insert(name, value.ok_or(AstVisitorError::SyntaxError)?);
+            },
+            Some(LiteralFrame::Function(_, args)) => args.push(value.ok_or(AstVisitorError::SyntaxError)?),
+            None => {
+                if self.is_in_expression() {
+                    let v = value.ok_or(AstVisitorError::SyntaxError)?;
+                    self.push_expression(Expression::Value(v));
+                } else if let Some(pb) = self.current_path_builder() {
+                    // null pattern properties are not stored
+                    pb.set_property_value(value.filter(|v| *v != PropertyValue::PNull));
+                }
+            },
+        }
+        Ok(true)
+    }
+
+    fn get_parameter_value(&self, name: &str) -> Option<PropertyValue> {
+        let param_name = name.trim_start_matches('$');
+        match self.params.as_ref()?.get(param_name)? {
+            ParameterValue::Value(value) => Some(value.clone()),
+            _ => None,
+        }
+    }
+}
+
+fn make_return_expression(expr: Expression) -> ReturnExpression {
+    match expr {
+        Expression::Item(name) => ReturnExpression::Item(name),
+        Expression::ItemPropertyName(prop) => ReturnExpression::ItemPropertyName(prop),
+        Expression::FunctionCall(func) => ReturnExpression::FunctionCall(func),
+        Expression::Value(value) => ReturnExpression::Value(value),
+        _ => ReturnExpression::Expression(Box::new(expr)),
+    }
+}
+
+fn make_function_call(exprs: Vec<Expression>) -> Option<FunctionCall> {
+    let mut exprs_iter = exprs.into_iter();
+    if let Some(Expression::Item(name)) = exprs_iter.next() {
+        let mut func = FunctionCall::new(&name);
+        for arg in exprs_iter {
+            func.args.push(make_return_expression(arg));
+        }
+        Some(func)
+    } else {
+        None
+    }
+}
+
+fn make_operation(ast_tag: Option<AstTag>, exprs: Vec<Expression>) -> Option<Expression> {
+    let mut exprs_iter = exprs.into_iter();
+    let first = Box::new(exprs_iter.next()?);
+    let operator = match ast_tag? {
+        AstTag::NotOperator => return Some(Expression::Not(first)),
+        AstTag::IsNullOperator => return Some(Expression::IsNull(first)),
+        AstTag::IsNotNullOperator => return Some(Expression::IsNotNull(first)),
+        AstTag::AndOperator => Operator::And,
+        AstTag::OrOperator => Operator::Or,
+        AstTag::EqualityOperator => Operator::Equal,
+        AstTag::InequalityOperator => Operator::NotEqual,
+        AstTag::LessThanOperator => Operator::Inferior,
+        AstTag::LessThanOrEqualOperator => Operator::InferiorOrEqual,
+        AstTag::GreaterThanOperator => Operator::Superior,
+        AstTag::GreaterThanOrEqualOperator => Operator::SuperiorOrEqual,
+        AstTag::AddOperator => Operator::Add,
+        AstTag::SubtractOperator => Operator::Subtract,
+        AstTag::MultiplyOperator => Operator::Multiply,
+        AstTag::DivideOperator => Operator::Divide,
+        AstTag::ModuloOperator => Operator::Modulo,
+        AstTag::ContainsOperator => Operator::Contains,
+        AstTag::StartsWithOperator => Operator::StartsWith,
+        AstTag::EndsWithOperator => Operator::EndsWith,
+        _ => return None,
+    };
+    let second = Box::new(exprs_iter.next()?);
+    Some(Expression::BinaryOperation(first, operator, second))
+}
+enum MatchAnchor {
+    Id(u64),
+    Uuid(String),
+}
+
+fn get_anchor_variable(func: &FunctionCall) -> Option<String> {
+    match func.args.as_slice() {
+        [ReturnExpression::Item(name)] => Some(name.clone()),
+        _ => None,
+    }
+}
+
+fn get_match_anchor(call: &Expression, value: &Expression) -> Option<(String, MatchAnchor)> {
+    match (call, value) {
+        (Expression::FunctionCall(func), Expression::Value(PropertyValue::PInteger(id))) if func.name.eq_ignore_ascii_case("id") && *id >= 0 => {
+            get_anchor_variable(func).map(|name| (name, MatchAnchor::Id(*id as u64)))
+        },
+        (Expression::FunctionCall(func), Expression::Value(PropertyValue::PString(uuid))) if func.name.eq_ignore_ascii_case("elementId") => {
+            get_anchor_variable(func).map(|name| (name, MatchAnchor::Uuid(uuid.clone())))
+        },
+        _ => None,
+    }
+}
+
+/// Ids and uuids compared to id(variable) or elementId(variable) in the conjuncts of a where condition.
+fn collect_match_anchors(condition: &Expression, anchors: &mut Vec<(String, MatchAnchor)>) {
+    match condition {
+        Expression::BinaryOperation(first, Operator::And, second) => {
+            collect_match_anchors(first, anchors);
+            collect_match_anchors(second, anchors);
+        },
+        Expression::BinaryOperation(first, Operator::Equal, second) => {
+            anchors.extend(get_match_anchor(first, second).or_else(|| get_match_anchor(second, first)));
+        },
+        _ => {},
+    }
+}
+
+fn anchor_properties(properties: &mut Vec<Property>, uuid: &str) {
+    if get_element_uuid(properties).is_none() {
+        properties.push(Property::new(String::from(ELEMENT_UUID_PROPERTY), PropertyValue::PString(String::from(uuid))));
+    }
+}
+
+/// Sets the ids and uuids of the where condition on the nodes and relationships of the match step,
+/// so that the matcher starts from them instead of scanning the stores.
+fn anchor_match_ids(step: &mut QueryStep, condition: &Expression) {
+    if !matches!(step.step_type, StepType::MATCH) {
+        return;
+    }
+    let mut anchors = Vec::new();
+    collect_match_anchors(condition, &mut anchors);
+    for (name, anchor) in anchors {
+        for pattern in &mut step.patterns {
+            for node in pattern.get_nodes_mut() {
+                if node.get_var().as_deref() == Some(name.as_str()) {
+                    match &anchor {
+                        MatchAnchor::Id(id) => node.set_id(Some(*id)),
+                        MatchAnchor::Uuid(uuid) => anchor_properties(node.get_properties_mut(), uuid),
+                    }
+                }
+            }
+            for rel in pattern.get_relationships_mut() {
+                if rel.get_var().as_deref() == Some(name.as_str()) {
+                    match &anchor {
+                        MatchAnchor::Id(id) => rel.set_id(Some(*id)),
+                        MatchAnchor::Uuid(uuid) => anchor_properties(rel.get_properties_mut(), uuid),
+                    }
+                }
+            }
+        }
+    }
 }
+
 impl AstVisitor for CypherAstVisitor {
 
 
@@ -109,24 +379,24 @@ impl AstVisitor for CypherAstVisitor {
         if let Some(request) = &mut self.request {
             request.steps.push(QueryStep::new_where_clause(WhereClause::new(node.clone_ast())));
         }
-        Ok(false)
+        self.state = VisitorState::WhereClause;
+        self.expressions = vec![Vec::new()];
+        Ok(true)
     }
     fn enter_function(&mut self) -> AstVisitorResult<bool> {
-        if let Some(request) = &mut self.request {
-            if let Some(_) = &mut request.return_clause {
-                self.state = VisitorState::FunctionCall;
-            }
+        if self.literals.is_empty() && self.is_in_expression() {
+            self.expressions.push(Vec::new());
+            return Ok(true)
         }
+        self.literals.push(LiteralFrame::Function(None, Vec::new()));
         Ok(true)
     }
     fn enter_function_arg(&mut self) -> AstVisitorResult<bool> {
-        if self.state == VisitorState::FunctionCall {
-            self.state = VisitorState::FunctionArg;
-        }
         Ok(true)
     }
     fn enter_item(&mut self) -> AstVisitorResult<bool> {
         self.state = VisitorState::ReturnItem;
+        self.expressions = vec![Vec::new()];
         Ok(true)
     }
     fn enter_create(&mut self, node: &AstTagNode) -> AstVisitorResult<bool> {
@@ -143,6 +413,13 @@ impl AstVisitor for CypherAstVisitor {
         self.state = VisitorState::DirectiveMatch;
         Ok(true)
     }
+    fn enter_optional_match(&mut self, node: &AstTagNode) -> AstVisitorResult<bool> {
+        if let Some(rq) = &mut self.request {
+            rq.steps.push(QueryStep::new(StepType::OPTIONAL_MATCH));
+        }
+        self.state = VisitorState::DirectiveMatch;
+        Ok(true)
+    }
     fn enter_node(&mut self, node: &AstTagNode) -> AstVisitorResult<bool> {
         let state = self.state.clone();
         if let Some(pb) = self.current_path_builder() {
@@ -165,28 +442,19 @@ impl AstVisitor for CypherAstVisitor {
     }
 
     fn enter_integer_value(&mut self, value: Option<i64>) -> AstVisitorResult<bool> {
-        if let Some(pb) = self.current_path_builder() {
-            pb.enter_integer_value(value);
-        }
-        Ok(true)
+        self.push_value(value.map(PropertyValue::PInteger))
     }
     fn enter_float_value(&mut self, value: Option<f64>) -> AstVisitorResult<bool> {
-        if let Some(pb) = self.current_path_builder() {
-            pb.enter_float_value(value);
-        }
-        Ok(true)
+        self.push_value(value.map(PropertyValue::PFloat))
     }
     fn enter_string_value(&mut self, value: Option<&str>) -> AstVisitorResult<bool> {
-        if let Some(pb) = self.current_path_builder() {
-            pb.enter_string_value(value);
-        }
-        Ok(true)
+        self.push_value(value.map(|v| PropertyValue::PString(String::from(v))))
     }
     fn enter_bool_value(&mut self, value: Option<bool>) -> AstVisitorResult<bool> {
-        if let Some(pb) = self.current_path_builder() {
-            pb.enter_bool_value(value);
-        }
-        Ok(true)
+        self.push_value(value.map(PropertyValue::PBool))
+    }
+    fn enter_null_value(&mut self) -> AstVisitorResult<bool> {
+        self.push_value(Some(PropertyValue::PNull))
     }
 
     fn enter_label(&mut self) -> AstVisitorResult<bool> {
@@ -206,13 +474,29 @@ impl AstVisitor for CypherAstVisitor {
     }
 
     fn enter_parameter(&mut self, name: &str) -> AstVisitorResult<bool> { 
-        if let Some(pb) = self.current_path_builder() {
-            pb.enter_parameter(name);
-        }
-        Ok(true)
+        let value = self.get_parameter_value(name).ok_or(AstVisitorError::SyntaxError)?;
+        self.push_value(Some(value))
     }
 
     fn enter_identifier(&mut self, key: &str) -> AstVisitorResult<bool> {
+        if let Some(ids) = &mut self.item_property_identifiers {
+            ids.push(String::from(key));
+            return Ok(true)
+        }
+        match self.literals.last_mut() {
+            Some(LiteralFrame::Map(_, name)) |
+            Some(LiteralFrame::Function(name @ None, _)) => {
+                *name = Some(String::from(key));
+                return Ok(true)
+            },
+            _ => {}
+        }
+        if self.state == VisitorState::CreateConstraint {
+            if let Some(IdentifierType::Label) = self.id_type {
+                self.curr_identifier = Some(String::from(key));
+            }
+            return Ok(true)
+        }
         let state = self.state.clone();
         match self.state {
             VisitorState::MatchPattern |
@@ -221,31 +505,32 @@ impl AstVisitor for CypherAstVisitor {
                     pb.enter_identifier(state, key);
                 }
             }
-            VisitorState::FunctionCall => {
-                if let Some(req) = &mut self.request {
-                    if let Some(ret) = &mut req.return_clause {
-                        ret.expressions.push(ReturnExpression::FunctionCall(FunctionCall::new(key)));
-                    }
-                }
+            VisitorState::ReturnAlias => {
+                self.return_alias = Some(String::from(key));
             },
-            VisitorState::FunctionArg => {
-                if let Some(req) = &mut self.request {
-                    if let Some(ret) = &mut req.return_clause {
-                        if let Some(expr) = ret.expressions.last_mut() {
-                            if let ReturnExpression::FunctionCall(func_call) = expr {
-                                func_call.args.push(String::from(key));
-                            }
-                        }
-                    }
+            VisitorState::ReturnItem |
+            VisitorState::WhereClause |
+            VisitorState::Unwind |
+            VisitorState::ProcedureArg => {
+                let expr = self.make_item_expression(key, None);
+                self.push_expression(expr);
+            }
+            VisitorState::ProcedureName => {
+                self.procedure = Some(ProcedureCall::new(key));
+                self.state = VisitorState::ProcedureArg;
+            },
+            VisitorState::ProcedureYield => {
+                if let Some(procedure) = &mut self.procedure {
+                    procedure.yields.push(String::from(key));
                 }
             },
-            VisitorState::ReturnItem => {
-                if let Some(req) = &mut self.request {
-                    if let Some(ret) = &mut req.return_clause {
-                        ret.expressions.push(ReturnExpression::Item(String::from(key)));
-                    }
+            VisitorState::UpdateItem => {
+                match (&self.id_type, self.updates.last_mut()) {
+                    (Some(IdentifierType::Variable), _) => self.updates.push(UpdateItem::Labels(String::from(key), Vec::new())),
+                    (Some(IdentifierType::Label), Some(UpdateItem::Labels(_, labels))) => labels.push(String::from(key)),
+                    _ => return Err(AstVisitorError::SyntaxError),
                 }
-            }
+            },
             _ => {}
         }
         Ok(true)
@@ -254,7 +539,12 @@ impl AstVisitor for CypherAstVisitor {
         if let Some(rq) = &mut self.request {
             let current_step = rq.steps.last_mut();
             if let Some(step) = current_step {
+                rq.paths.extend(self.path_builders.iter_mut().filter_map(|pb| pb.build_named_path()));
                 let paths: Vec<PropertyGraph> = self.path_builders.iter().map(|pb| pb.get_path_graph().clone()).collect();
+                // a created relationship has a single type
+                if paths.iter().any(|path| path.get_relationships().iter().any(|rel| rel.get_labels_ref().len() > 1)) {
+                    return Err(AstVisitorError::SyntaxError);
+                }
                 step.patterns = merge_paths(&paths);
                 self.path_builders.clear();
             }
@@ -265,13 +555,17 @@ impl AstVisitor for CypherAstVisitor {
         if let Some(rq) = &mut self.request {
             let current_step = rq.steps.last_mut();
             if let Some(step) = current_step {
+                rq.paths.extend(self.path_builders.iter_mut().filter_map(|pb| pb.build_named_path()));
                 let paths: &Vec<PropertyGraph> = &self.path_builders.iter().map(|pb| pb.get_path_graph().clone()).collect();
                 step.patterns = merge_paths(paths);
                 self.path_builders.clear();
             }
         }
         Ok(true)
-    }   
+    }
+    fn exit_optional_match(&mut self) -> AstVisitorResult<bool> {
+        self.exit_match()
+    }
     fn exit_path(&mut self) -> AstVisitorResult<bool> {
         Ok(true)
     }
@@ -287,11 +581,277 @@ impl AstVisitor for CypherAstVisitor {
     fn exit_label(&mut self) -> AstVisitorResult<bool> { Ok(true)}
     fn exit_query(&mut self) -> AstVisitorResult<bool> { Ok(true)}
     fn exit_return(&mut self) -> AstVisitorResult<bool> { Ok(true)}
-    fn exit_function(&mut self) -> AstVisitorResult<bool> { Ok(true)}
+    fn exit_function(&mut self) -> AstVisitorResult<bool> {
+        if let Some(LiteralFrame::Function(..)) = self.literals.last() {
+            if let Some(LiteralFrame::Function(Some(name), args)) = self.literals.pop() {
+                let value = eval_temporal_function(&name, &args).ok_or(AstVisitorError::SyntaxError)?;
+                return self.push_value(Some(value));
+            }
+            return Err(AstVisitorError::SyntaxError)
+        }
+        if self.is_in_expression() {
+            let exprs = self.expressions.pop().ok_or(AstVisitorError::SyntaxError)?;
+            let func = make_function_call(exprs).ok_or(AstVisitorError::SyntaxError)?;
+            self.push_expression(Expression::FunctionCall(func));
+        }
+        Ok(true)
+    }
     fn exit_function_arg(&mut self) -> AstVisitorResult<bool> { Ok(true)}
-    fn exit_item(&mut self) -> AstVisitorResult<bool> { Ok(true)}
-    fn exit_where(&mut self) -> AstVisitorResult<bool> { Ok(true)}
+    fn exit_item(&mut self) -> AstVisitorResult<bool> {
+        let expr = self.expressions.pop().and_then(|mut exprs| exprs.pop()).ok_or(AstVisitorError::SyntaxError)?;
+        let mut ret_expr = make_return_expression(expr);
+        if let Some(alias) = self.return_alias.take() {
+            ret_expr = ReturnExpression::Alias(Box::new(ret_expr), alias);
+        }
+        if let Some(ret) = self.request.as_mut().and_then(|req| req.return_clause.as_mut()) {
+            ret.expressions.push(ret_expr);
+        }
+        self.state = VisitorState::Init;
+        Ok(true)
+    }
+    fn exit_where(&mut self) -> AstVisitorResult<bool> {
+        let condition = self.expressions.pop().and_then(|mut exprs| exprs.pop()).ok_or(AstVisitorError::SyntaxError)?;
+        if let Some(req) = &mut self.request {
+            let nb_steps = req.steps.len();
+            if let Some(match_step) = nb_steps.checked_sub(2).and_then(|index| req.steps.get_mut(index)) {
+                anchor_match_ids(match_step, &condition);
+            }
+            if let Some(where_clause) = req.steps.last_mut().and_then(|step| step.where_clause.as_mut()) {
+                where_clause.condition = Some(condition);
+            }
+        }
+        self.state = VisitorState::Init;
+        Ok(true)
+    }
     fn exit_parameter(&mut self) -> AstVisitorResult<bool> { Ok(true)}
+    fn enter_item_property_identifier(&mut self) -> AstVisitorResult<bool> {
+        self.item_property_identifiers = Some(Vec::new());
+        Ok(true)
+    }
+    fn exit_item_property_identifier(&mut self) -> AstVisitorResult<bool> {
+        if self.state == VisitorState::CreateConstraint {
+            let ids = self.item_property_identifiers.take().ok_or(AstVisitorError::SyntaxError)?;
+            let label = self.curr_identifier.take().ok_or(AstVisitorError::SyntaxError)?;
+            if let (Some(property_name), Some(req)) = (ids.get(1), &mut self.request) {
+                req.steps.push(QueryStep::new_create_constraint(UniqueConstraint::new(&label, property_name)));
+            }
+            return Ok(true)
+        }
+        let ids = self.item_property_identifiers.take().ok_or(AstVisitorError::SyntaxError)?;
+        let item_name = ids.get(0).ok_or(AstVisitorError::SyntaxError)?;
+        if self.state == VisitorState::UpdateItem {
+            let property_name = ids.get(1).ok_or(AstVisitorError::SyntaxError)?;
+            self.updates.push(UpdateItem::Property(item_name.clone(), property_name.clone()));
+            return Ok(true)
+        }
+        if self.is_in_expression() {
+            let expr = self.make_item_expression(item_name, ids.get(1));
+            self.push_expression(expr);
+            Ok(true)
+        } else {
+            // variable references in pattern properties
+            let value = self.get_variable_value(item_name, ids.get(1)).ok_or(AstVisitorError::SyntaxError)?;
+            self.push_value(Some(value))
+        }
+    }
+    fn enter_operator(&mut self, _node: &AstTagNode) -> AstVisitorResult<bool> {
+        if self.is_in_expression() {
+            self.expressions.push(Vec::new());
+        }
+        Ok(true)
+    }
+    fn exit_operator(&mut self, node: &AstTagNode) -> AstVisitorResult<bool> {
+        if self.is_in_expression() {
+            let exprs = self.expressions.pop().ok_or(AstVisitorError::SyntaxError)?;
+            let operation = make_operation(node.ast_tag, exprs).ok_or(AstVisitorError::SyntaxError)?;
+            self.push_expression(operation);
+        }
+        Ok(true)
+    }
+    fn enter_create_constraint(&mut self, _node: &AstTagNode) -> AstVisitorResult<bool> {
+        self.state = VisitorState::CreateConstraint;
+        Ok(true)
+    }
+    fn exit_create_constraint(&mut self) -> AstVisitorResult<bool> {
+        self.state = VisitorState::Init;
+        Ok(true)
+    }
+    fn enter_explain(&mut self) -> AstVisitorResult<bool> {
+        if let Some(request) = &mut self.request {
+            request.mode = ExecutionMode::Explain;
+        }
+        Ok(true)
+    }
+    fn exit_explain(&mut self) -> AstVisitorResult<bool> {
+        Ok(true)
+    }
+    fn enter_profile(&mut self) -> AstVisitorResult<bool> {
+        if let Some(request) = &mut self.request {
+            request.mode = ExecutionMode::Profile;
+        }
+        Ok(true)
+    }
+    fn exit_profile(&mut self) -> AstVisitorResult<bool> {
+        Ok(true)
+    }
+    fn enter_list(&mut self) -> AstVisitorResult<bool> {
+        self.literals.push(LiteralFrame::List(Vec::new()));
+        Ok(true)
+    }
+    fn enter_map(&mut self) -> AstVisitorResult<bool> {
+        self.literals.push(LiteralFrame::Map(BTreeMap::new(), None));
+        Ok(true)
+    }
+    fn exit_list(&mut self) -> AstVisitorResult<bool> {
+        match self.literals.pop() {
+            Some(LiteralFrame::List(values)) => self.push_value(Some(PropertyValue::PList(values))),
+            _ => Err(AstVisitorError::SyntaxError),
+        }
+    }
+    fn exit_map(&mut self) -> AstVisitorResult<bool> {
+        match self.literals.pop() {
+            Some(LiteralFrame::Map(entries, _)) => self.push_value(Some(PropertyValue::PMap(entries))),
+            _ => Err(AstVisitorError::SyntaxError),
+        }
+    }
+    fn enter_alias(&mut self) -> AstVisitorResult<bool> {
+        self.state = VisitorState::ReturnAlias;
+        Ok(true)
+    }
+    fn exit_alias(&mut self) -> AstVisitorResult<bool> {
+        self.state = VisitorState::ReturnItem;
+        Ok(true)
+    }
+    fn enter_distinct(&mut self) -> AstVisitorResult<bool> {
+        if let Some(ret) = self.request.as_mut().and_then(|req| req.return_clause.as_mut()) {
+            ret.distinct = true;
+        }
+        Ok(true)
+    }
+    fn exit_distinct(&mut self) -> AstVisitorResult<bool> {
+        Ok(true)
+    }
+    fn enter_unwind(&mut self, node: &AstTagNode) -> AstVisitorResult<bool> {
+        let (list, clauses) = node.childs.split_at(2.min(node.childs.len()));
+        let (variable, values) = self.eval_list_variable(list)?;
+        let mut batches = Vec::new();
+        let mut return_clause = None;
+        let mut paths = None;
+        for value in values {
+            let request = self.make_unwind_batch(&variable, value, clauses)?;
+            batches.push(request.steps);
+            return_clause = return_clause.or(request.return_clause);
+            paths = paths.or(Some(request.paths));
+        }
+        if batches.is_empty() {
+            // an empty list still has the columns of its return clause
+            return_clause = self.make_unwind_batch(&variable, PropertyValue::PNull, clauses)?.return_clause;
+        }
+        if let Some(req) = &mut self.request {
+            req.steps.push(QueryStep::new_unwind(batches));
+            req.return_clause = return_clause;
+            req.paths.extend(paths.unwrap_or_default());
+        }
+        Ok(false)
+    }
+    fn exit_unwind(&mut self) -> AstVisitorResult<bool> {
+        Ok(true)
+    }
+    fn enter_foreach(&mut self, node: &AstTagNode) -> AstVisitorResult<bool> {
+        let (list, clauses) = node.childs.split_at(2.min(node.childs.len()));
+        let (variable, values) = self.eval_list_variable(list)?;
+        let mut batches = Vec::new();
+        for value in values {
+            batches.push(self.make_unwind_batch(&variable, value, clauses)?.steps);
+        }
+        if let Some(req) = &mut self.request {
+            req.steps.push(QueryStep::new_foreach(batches));
+        }
+        Ok(false)
+    }
+    fn exit_foreach(&mut self) -> AstVisitorResult<bool> {
+        Ok(true)
+    }
+    /// The path of a pattern predicate is built as a match pattern and kept in the condition, it is matched for each row.
+    fn enter_pattern_predicate(&mut self, node: &AstTagNode) -> AstVisitorResult<bool> {
+        if !self.is_in_expression() {
+            return Err(AstVisitorError::SyntaxError);
+        }
+        let previous_state = self.state;
+        let path_builders = std::mem::take(&mut self.path_builders);
+        self.state = VisitorState::MatchPattern;
+        for child in &node.childs {
+            parser::walk_ast(self, child)?;
+        }
+        self.state = previous_state;
+        let paths = std::mem::replace(&mut self.path_builders, path_builders).iter().map(|pb| pb.get_path_graph().clone()).collect();
+        let pattern = merge_paths(&paths).pop().ok_or(AstVisitorError::SyntaxError)?;
+        self.push_expression(Expression::Pattern(pattern));
+        Ok(false)
+    }
+    fn exit_pattern_predicate(&mut self) -> AstVisitorResult<bool> {
+        Ok(true)
+    }
+    fn enter_call(&mut self, node: &AstTagNode) -> AstVisitorResult<bool> {
+        self.state = VisitorState::ProcedureName;
+        for child in &node.childs {
+            self.expressions = vec![Vec::new()];
+            parser::walk_ast(self, child)?;
+            if let Some(arg) = self.expressions.pop().and_then(|mut exprs| exprs.pop()) {
+                self.procedure.as_mut().ok_or(AstVisitorError::SyntaxError)?.args.push(arg);
+            }
+        }
+        self.state = VisitorState::Init;
+        let procedure = self.procedure.take().ok_or(AstVisitorError::SyntaxError)?;
+        if let Some(req) = &mut self.request {
+            req.steps.push(QueryStep::new_call(procedure));
+        }
+        Ok(false)
+    }
+    fn exit_call(&mut self) -> AstVisitorResult<bool> {
+        Ok(true)
+    }
+    fn enter_yield(&mut self) -> AstVisitorResult<bool> {
+        self.state = VisitorState::ProcedureYield;
+        Ok(true)
+    }
+    fn exit_yield(&mut self) -> AstVisitorResult<bool> {
+        Ok(true)
+    }
+    fn enter_set(&mut self, node: &AstTagNode) -> AstVisitorResult<bool> {
+        self.push_update_step(StepType::SET, node)
+    }
+    fn exit_set(&mut self) -> AstVisitorResult<bool> {
+        Ok(true)
+    }
+    fn enter_remove(&mut self, node: &AstTagNode) -> AstVisitorResult<bool> {
+        self.push_update_step(StepType::REMOVE, node)
+    }
+    fn exit_remove(&mut self) -> AstVisitorResult<bool> {
+        Ok(true)
+    }
+    /// Query parts of a union return the same columns and are all combined either with UNION or with UNION ALL.
+    fn enter_union(&mut self, node: &AstTagNode) -> AstVisitorResult<bool> {
+        let mut visitor = CypherAstVisitor::new(self.params.clone());
+        let request = self.request.as_mut().ok_or(AstVisitorError::SyntaxError)?;
+        let mut part = Request::new();
+        part.mode = request.mode;
+        visitor.request = Some(part);
+        for child in &node.childs {
+            parser::walk_ast(&mut visitor, child)?;
+        }
+        let part = visitor.request.ok_or(AstVisitorError::SyntaxError)?;
+        let all = node.ast_tag == Some(AstTag::UnionAll);
+        let columns = request.get_column_names();
+        if columns.is_none() || columns != part.get_column_names() || request.unions.iter().any(|union| union.all != all) {
+            return Err(AstVisitorError::SyntaxError);
+        }
+        request.unions.push(UnionPart{request: part, all});
+        Ok(false)
+    }
+    fn exit_union(&mut self) -> AstVisitorResult<bool> {
+        Ok(true)
+    }
 }
 
 #[cfg(test)]
@@ -303,7 +863,7 @@ mod test_query_engine {
     #[test]
     fn test_create_0() {
         let request = process_cypher_query("CREATE (n:Person)", None);
-        if let  Some(req) = request {
+        if let  Ok(req) = request {
             let node = req.steps[0].patterns[0].get_node_ref(&NodeIndex::new(0));
             assert_eq!(node.get_var(), &Some(String::from("n")));
             assert_eq!(node.get_labels_ref()[0], String::from("Person"));
@@ -317,7 +877,7 @@ mod test_query_engine {
     #[test]
     fn test_create_1() {
         let request = process_cypher_query("CREATE (n:Person:Parent {test: 'Hello', case: 4.99})", None);
-        if let  Some(req) = request {
+        if let  Ok(req) = request {
             let node = req.steps[0].patterns[0].get_node_ref(&NodeIndex::new(0));
             assert_eq!(node.get_var(), &Some(String::from("n")));
             assert_eq!(node.get_labels_ref()[0], String::from("Person"));
@@ -332,10 +892,20 @@ mod test_query_engine {
         
     }
 
+    #[test]
+    fn test_escaped_names_and_strings() {
+        let request = process_cypher_query("// new person\nCREATE (n:`Famous Person` {`full name`: 'Zoë \\'Z\\' Ada', city: \"Zürich\"}) /* no return */", None).expect("request");
+        let node = request.steps[0].patterns[0].get_node_ref(&NodeIndex::new(0));
+        assert_eq!(node.get_labels_ref()[0], String::from("Famous Person"));
+        assert_eq!(node.get_properties_ref()[0].get_name(), "full name");
+        assert_eq!(node.get_properties_ref()[0].get_value(), &PropertyValue::PString(String::from("Zoë 'Z' Ada")));
+        assert_eq!(node.get_properties_ref()[1].get_value(), &PropertyValue::PString(String::from("Zürich")));
+    }
+
     #[test]
     fn test_create_2() {
         let request = process_cypher_query("CREATE (n:Person:Parent)-[r:FRIEND_OF]->(p:Person)", None);
-        if let  Some(req) = request {
+        if let  Ok(req) = request {
             let node = req.steps[0].patterns[0].get_node_ref(&NodeIndex::new(0));
             assert_eq!(node.get_var(), &Some(String::from("n")));
             assert_eq!(node.get_labels_ref()[0], String::from("Person"));
@@ -352,7 +922,7 @@ mod test_query_engine {
     #[test]
     fn test_match_and_create() {
         let request = process_cypher_query("MATCH (m:Movie), (a:Actor) CREATE (a)-[r:PLAYED_IN]->(m) RETURN m, a, r", None);
-        if let  Some(req) = request {
+        if let  Ok(req) = request {
             let movie = req.steps[0].patterns[0].get_node_ref(&NodeIndex::new(0));
             assert_eq!(movie.get_var(), &Some(String::from("a")));
             assert_eq!(movie.get_labels_ref()[0], String::from("Actor"));
@@ -374,7 +944,7 @@ mod test_query_engine {
     #[test]
     fn test_match_match() {
         let request = process_cypher_query("MATCH (m:Movie), (a:Actor) MATCH (a)-[r:PLAYED_IN]->(m) RETURN m, a, r", None);
-        if let  Some(req) = request {
+        if let  Ok(req) = request {
             let movie = req.steps[0].patterns[0].get_node_ref(&NodeIndex::new(0));
             assert_eq!(movie.get_var(), &Some(String::from("a")));
             assert_eq!(movie.get_labels_ref()[0], String::from("Actor"));
@@ -393,19 +963,446 @@ mod test_query_engine {
     }
 
 
+    #[test]
+    fn test_optional_match() {
+        let request = process_cypher_query("MATCH (a:Person) OPTIONAL MATCH (a)-[r:OWNS]->(c:Car) RETURN a, r, c", None);
+        if let  Ok(req) = request {
+            assert_eq!(req.steps.len(), 2);
+            assert!(matches!(req.steps[0].step_type, StepType::MATCH));
+            assert!(matches!(req.steps[1].step_type, StepType::OPTIONAL_MATCH));
+            let rel = req.steps[1].patterns[0].get_relationship_ref(&EdgeIndex::new(0));
+            assert_eq!(rel.get_var(), &Some(String::from("r")));
+            assert_eq!(rel.get_labels_ref()[0], String::from("OWNS"));
+            assert_eq!(rel.get_status(), &Status::Match);
+        } else {
+            assert!(false, "no request found");
+        }
+    }
+
+    #[test]
+    fn test_return_aggregation() {
+        let request = process_cypher_query("MATCH (p:Person) RETURN p.city, count(p), sum(p.age)", None);
+        if let  Ok(req) = request {
+            let ret = req.return_clause.expect("return clause");
+            assert!(ret.has_aggregation());
+            let names: Vec<String> = ret.expressions.iter().map(|expr| expr.get_name()).collect();
+            assert_eq!(names, vec!["p.city", "count(p)", "sum(p.age)"]);
+        } else {
+            assert!(false, "no request found");
+        }
+    }
+
+    #[test]
+    fn test_return_expressions() {
+        let request = process_cypher_query("MATCH (p:Person) WHERE p.age * 2 > 40 RETURN p.name AS name, p.age + 1, (p.age - 1) * 2 AS twice, 7 % 3, count(p) AS total", None);
+        if let  Ok(req) = request {
+            let condition = req.steps[1].where_clause.as_ref().and_then(|w| w.condition.as_ref());
+            assert!(matches!(condition, Some(Expression::BinaryOperation(first, Operator::Superior, _)) if matches!(**first, Expression::BinaryOperation(_, Operator::Multiply, _))));
+            let ret = req.return_clause.expect("return clause");
+            assert!(ret.has_aggregation());
+            let names: Vec<String> = ret.expressions.iter().map(|expr| expr.get_name()).collect();
+            assert_eq!(names, vec!["name", "p.age + 1", "twice", "7 % 3", "total"]);
+            assert!(matches!(ret.expressions[0].get_expression(), ReturnExpression::ItemPropertyName(_)));
+            match ret.expressions[2].get_expression() {
+                ReturnExpression::Expression(expr) => assert_eq!("(p.age - 1) * 2", expr.get_name()),
+                _ => assert!(false, "wrong return expression"),
+            }
+        } else {
+            assert!(false, "no request found");
+        }
+    }
+
+    #[test]
+    fn test_string_predicates() {
+        let request = process_cypher_query("MATCH (p:Person) WHERE p.name STARTS WITH 'A' AND NOT p.name ENDS WITH 'n' OR p.city CONTAINS 'ar' RETURN toUpper(p.name), toLower(p.city)", None).expect("request");
+        let condition = request.steps[1].where_clause.as_ref().and_then(|w| w.condition.as_ref()).expect("condition");
+        assert_eq!("p.name STARTS WITH 'A' AND NOT p.name ENDS WITH 'n' OR p.city CONTAINS 'ar'", condition.get_name());
+        let names: Vec<String> = request.return_clause.expect("return clause").expressions.iter().map(|expr| expr.get_name()).collect();
+        assert_eq!(names, vec!["toUpper(p.name)", "toLower(p.city)"]);
+        assert!(process_cypher_query("MATCH (p:Person) WHERE p.name STARTS 'A' RETURN p", None).is_err());
+    }
+
+    #[test]
+    fn test_return_distinct() {
+        let request = process_cypher_query("MATCH (p:Person) RETURN DISTINCT p.city AS city", None);
+        let ret = request.ok().and_then(|req| req.return_clause).expect("return clause");
+        assert!(ret.distinct);
+        assert_eq!("city", ret.expressions[0].get_name());
+        let request = process_cypher_query("MATCH (p:Person) RETURN p", None);
+        assert!(!request.ok().and_then(|req| req.return_clause).expect("return clause").distinct);
+    }
+
+    #[test]
+    fn test_where_comparisons() {
+        let request = process_cypher_query("MATCH (n:Person) WHERE n.age >= 18 AND NOT n.name = 'Bob' RETURN n", None);
+        if let  Ok(req) = request {
+            assert!(matches!(req.steps[1].step_type, StepType::WHERE));
+            let condition = req.steps[1].where_clause.as_ref().and_then(|w| w.condition.as_ref());
+            match condition {
+                Some(Expression::BinaryOperation(first, Operator::And, second)) => {
+                    assert!(matches!(**first, Expression::BinaryOperation(_, Operator::SuperiorOrEqual, _)));
+                    assert!(matches!(**second, Expression::Not(_)));
+                },
+                _ => assert!(false, "wrong where condition"),
+            }
+        } else {
+            assert!(false, "no request found");
+        }
+    }
+
+    #[test]
+    fn test_where_pattern_predicates() {
+        let req = process_cypher_query("MATCH (a:Person), (b:Person) WHERE (a)-[:KNOWS]->(b) AND NOT (b)-[:KNOWS]->(:Person) RETURN a, b", None).expect("no request found");
+        assert_eq!(2, req.steps.len());
+        let condition = req.steps[1].where_clause.as_ref().and_then(|w| w.condition.as_ref()).expect("where condition");
+        match condition {
+            Expression::BinaryOperation(first, Operator::And, second) => {
+                match (first.as_ref(), second.as_ref()) {
+                    (Expression::Pattern(knows), Expression::Not(not_knows)) => {
+                        assert_eq!(2, knows.get_nodes().len());
+                        assert_eq!(1, knows.get_relationships().len());
+                        assert!(knows.get_nodes().iter().all(|node| node.get_status() == &Status::Match));
+                        assert!(matches!(not_knows.as_ref(), Expression::Pattern(_)));
+                    },
+                    _ => assert!(false, "wrong pattern predicates"),
+                }
+            },
+            _ => assert!(false, "wrong where condition"),
+        }
+        assert_eq!("(a)-[:KNOWS]->(b) AND NOT (b)-[:KNOWS]->(:Person)", condition.get_name());
+        let req = process_cypher_query("MATCH (n:Person) WHERE (n.age + 1) > 18 RETURN n", None).expect("no request found");
+        let condition = req.steps[1].where_clause.as_ref().and_then(|w| w.condition.as_ref());
+        assert!(matches!(condition, Some(Expression::BinaryOperation(_, Operator::Superior, _))));
+    }
+
+    #[test]
+    fn test_where_exists() {
+        let req = process_cypher_query("MATCH (n:Person) WHERE exists(n.email) AND NOT EXISTS((n)-[:KNOWS]->()) RETURN n, exists(n.city)", None).expect("no request found");
+        let condition = req.steps[1].where_clause.as_ref().and_then(|w| w.condition.as_ref()).expect("where condition");
+        match condition {
+            Expression::BinaryOperation(first, Operator::And, second) => {
+                assert!(matches!(first.as_ref(), Expression::IsNotNull(prop) if matches!(prop.as_ref(), Expression::ItemPropertyName(_))));
+                assert!(matches!(second.as_ref(), Expression::Not(pattern) if matches!(pattern.as_ref(), Expression::Pattern(_))));
+            },
+            _ => assert!(false, "wrong where condition"),
+        }
+        let ret = req.return_clause.expect("return clause");
+        assert_eq!("exists(n.city)", ret.expressions[1].get_name());
+    }
+
+    #[test]
+    fn test_unwind() {
+        let make_item = |name: &str| PropertyValue::PMap(BTreeMap::from([(String::from("name"), PropertyValue::PString(String::from(name)))]));
+        let mut params = Parameters::new();
+        params.insert("items".to_string(), ParameterValue::Value(PropertyValue::PList(vec![make_item("a"), make_item("b")])));
+        let request = process_cypher_query("UNWIND $items AS item CREATE (n:Thing {name: item.name}) RETURN n", Some(params));
+        let req = request.expect("no request found");
+        assert_eq!(1, req.steps.len());
+        assert_eq!(StepType::UNWIND, req.steps[0].step_type);
+        assert_eq!(2, req.steps[0].unwind_batches.len());
+        for (batch, name) in req.steps[0].unwind_batches.iter().zip(["a", "b"]) {
+            let node = batch[0].patterns[0].get_node_ref(&NodeIndex::new(0));
+            assert_eq!(node.get_status(), &Status::Create);
+            assert_eq!(node.get_properties_ref()[0].get_value(), &PropertyValue::PString(String::from(name)));
+        }
+        assert_eq!("n", req.return_clause.expect("return clause").expressions[0].get_name());
+
+        let request = process_cypher_query("MATCH (p:Person) UNWIND [1, 2] AS age MATCH (p)-[r:KNOWS]->(f:Person) WHERE f.age = age RETURN f", None);
+        let req = request.expect("no request found");
+        assert_eq!(StepType::MATCH, req.steps[0].step_type);
+        let batch = &req.steps[1].unwind_batches[1];
+        let condition = batch[1].where_clause.as_ref().and_then(|w| w.condition.as_ref());
+        assert!(matches!(condition, Some(Expression::BinaryOperation(_, Operator::Equal, second)) if matches!(**second, Expression::Value(PropertyValue::PInteger(2)))));
+
+        let request = process_cypher_query("UNWIND [] AS name CREATE (n:Thing {name: name}) RETURN n.name AS name", None);
+        let req = request.expect("no request found");
+        assert!(req.steps[0].unwind_batches.is_empty());
+        assert_eq!("name", req.return_clause.expect("return clause").expressions[0].get_name());
+    }
+
+    #[test]
+    fn test_call() {
+        let mut params = Parameters::new();
+        params.insert("times".to_string(), ParameterValue::Value(PropertyValue::PInteger(3)));
+        let request = process_cypher_query("CALL test.repeat('a', $times + 1) YIELD index, value RETURN index * 2 AS double", Some(params));
+        let req = request.expect("no request found");
+        assert_eq!(1, req.steps.len());
+        assert_eq!(StepType::CALL, req.steps[0].step_type);
+        let procedure = req.steps[0].procedure.as_ref().expect("procedure call");
+        assert_eq!("test.repeat", procedure.name);
+        assert_eq!(2, procedure.args.len());
+        assert!(matches!(&procedure.args[0], Expression::Value(PropertyValue::PString(value)) if value == "a"));
+        assert!(matches!(&procedure.args[1], Expression::BinaryOperation(_, Operator::Add, _)));
+        assert_eq!(vec!["index", "value"], procedure.yields);
+        assert_eq!("double", req.return_clause.expect("return clause").expressions[0].get_name());
+
+        let request = process_cypher_query("CALL db.constraints()", None);
+        let procedure = request.expect("no request found").steps[0].procedure.take().expect("procedure call");
+        assert_eq!("db.constraints", procedure.name);
+        assert!(procedure.args.is_empty() && procedure.yields.is_empty());
+    }
+
     #[test]
     fn test_node_id_parameter() {
         let mut params = Parameters::new();
         params.insert("mid".to_string(), ParameterValue::Value(PropertyValue::PInteger(12)));
         let request = process_cypher_query("MATCH (m:Movie) WHERE id(m) = $mid RETURN m, a, r", Some(params));
-        if let  Some(req) = request {
+        if let  Ok(req) = request {
             let movie = req.steps[0].patterns[0].get_node_ref(&NodeIndex::new(0));
             assert_eq!(movie.get_var(), &Some(String::from("m")));
             assert_eq!(movie.get_labels_ref()[0], String::from("Movie"));
             assert_eq!(movie.get_status(), &Status::Match);
-            //assert_eq!(movie.get_id(), Some(12u64));
+            let condition = req.steps[1].where_clause.as_ref().and_then(|w| w.condition.as_ref());
+            match condition {
+                Some(Expression::BinaryOperation(first, Operator::Equal, second)) => {
+                    assert!(matches!(**first, Expression::FunctionCall(_)));
+                    assert!(matches!(**second, Expression::Value(PropertyValue::PInteger(12))));
+                },
+                _ => assert!(false, "wrong where condition"),
+            }
+            assert_eq!(movie.get_id(), Some(12u64));
+        } else {
+            assert!(false, "no request found");
+        }
+    }
+
+    #[test]
+    fn test_relationship_id_parameter() {
+        let mut params = Parameters::new();
+        params.insert("rid".to_string(), ParameterValue::Value(PropertyValue::PInteger(7)));
+        let request = process_cypher_query("MATCH (a:Person)-[r:KNOWS]->(b) WHERE $rid = id(r) AND b.age > 18 RETURN b", Some(params)).expect("request");
+        let pattern = &request.steps[0].patterns[0];
+        assert_eq!(Some(7), pattern.get_relationships()[0].get_id());
+        assert!(pattern.get_nodes().iter().all(|node| node.get_id().is_none()));
+        let request = process_cypher_query("MATCH (a:Person)-[r:KNOWS]->(b) WHERE id(r) = 7 OR id(a) = 1 RETURN b", None).expect("request");
+        let pattern = &request.steps[0].patterns[0];
+        assert_eq!(None, pattern.get_relationships()[0].get_id());
+        assert!(pattern.get_nodes().iter().all(|node| node.get_id().is_none()));
+    }
+
+    #[test]
+    fn test_element_id_parameter() {
+        let uuid = "41d2e28a-20a4-4ab0-b379-d810dede3786";
+        let mut params = Parameters::new();
+        params.insert("uuid".to_string(), ParameterValue::Value(PropertyValue::PString(uuid.to_string())));
+        let request = process_cypher_query("MATCH (a)-[r:KNOWS]->(b) WHERE elementId(a) = $uuid AND elementId(r) = 'r1' RETURN b", Some(params)).expect("request");
+        let pattern = &request.steps[0].patterns[0];
+        let a = pattern.get_node_ref(&NodeIndex::new(0));
+        assert_eq!(Some(uuid), get_element_uuid(a.get_properties_ref()));
+        assert_eq!(Some("r1"), get_element_uuid(pattern.get_relationships()[0].get_properties_ref()));
+        assert!(pattern.get_node_ref(&NodeIndex::new(1)).get_properties_ref().is_empty());
+    }
+
+    #[test]
+    fn test_set_remove_labels() {
+        let request = process_cypher_query("MATCH (n:Ada), (m) WHERE id(m) = 1 SET n:Person:Admin, m:Person REMOVE n:Ada RETURN n", None).expect("request");
+        let steps: Vec<StepType> = request.steps.iter().map(|step| step.step_type).collect();
+        assert_eq!(steps, vec![StepType::MATCH, StepType::WHERE, StepType::SET, StepType::REMOVE]);
+        assert_eq!(request.steps[2].updates, vec![UpdateItem::Labels(String::from("n"), vec![String::from("Person"), String::from("Admin")]),
+            UpdateItem::Labels(String::from("m"), vec![String::from("Person")])]);
+        assert_eq!(request.steps[3].updates, vec![UpdateItem::Labels(String::from("n"), vec![String::from("Ada")])]);
+        assert!(request.return_clause.is_some());
+        assert!(process_cypher_query("MATCH (n) SET n RETURN n", None).is_err());
+        let request = process_cypher_query("MATCH (n)-[r:KNOWS]->(m) REMOVE n.age, r.since, m:Person", None).expect("request");
+        assert_eq!(request.steps[1].updates, vec![UpdateItem::Property(String::from("n"), String::from("age")),
+            UpdateItem::Property(String::from("r"), String::from("since")), UpdateItem::Labels(String::from("m"), vec![String::from("Person")])]);
+        assert!(process_cypher_query("MATCH (n) SET n.age RETURN n", None).is_err());
+    }
+
+    #[test]
+    fn test_union() {
+        let request = process_cypher_query("EXPLAIN MATCH (n:Person) RETURN n.name AS name UNION MATCH (c:City) RETURN c.name AS name UNION MATCH (n:Town) RETURN n.name AS name", None).expect("request");
+        assert_eq!(ExecutionMode::Explain, request.mode);
+        assert_eq!(Some(vec![String::from("name")]), request.get_column_names());
+        assert_eq!(2, request.unions.len());
+        assert!(request.unions.iter().all(|union| !union.all && union.request.mode == ExecutionMode::Explain));
+        assert_eq!(request.unions[0].request.steps[0].patterns[0].get_node_ref(&NodeIndex::new(0)).get_labels_ref(), &vec![String::from("City")]);
+        let request = process_cypher_query("MATCH (n:Person) RETURN n UNION ALL MATCH (n:City) RETURN n", None).expect("request");
+        assert!(request.unions[0].all);
+        assert!(process_cypher_query("MATCH (n:Person) RETURN n.name UNION MATCH (c:City) RETURN c.name", None).is_err());
+        assert!(process_cypher_query("MATCH (n:Person) RETURN n UNION MATCH (n:City) RETURN n UNION ALL MATCH (n:Town) RETURN n", None).is_err());
+        assert!(process_cypher_query("MATCH (n:Person) RETURN n UNION MATCH (n:City)", None).is_err());
+    }
+
+    #[test]
+    fn test_foreach() {
+        let mut params = Parameters::new();
+        params.insert("values".to_string(), ParameterValue::Value(PropertyValue::PList(vec![PropertyValue::PInteger(1), PropertyValue::PInteger(2)])));
+        let request = process_cypher_query("FOREACH (x IN $values | CREATE (i:Item {v: x}))", Some(params)).expect("request");
+        assert_eq!(1, request.steps.len());
+        assert_eq!(StepType::FOREACH, request.steps[0].step_type);
+        for (batch, value) in request.steps[0].unwind_batches.iter().zip([1, 2]) {
+            let node = batch[0].patterns[0].get_node_ref(&NodeIndex::new(0));
+            assert_eq!(node.get_status(), &Status::Create);
+            assert_eq!(node.get_properties_ref()[0].get_value(), &PropertyValue::PInteger(value));
+        }
+        assert!(request.return_clause.is_none());
+
+        let request = process_cypher_query("MATCH (p:Person) FOREACH (tag IN ['a', 'b'] | CREATE (p)-[r:TAGGED]->(t:Tag {name: tag}) SET p:Tagged FOREACH (n IN tag | CREATE (m:Name {n: n}))) RETURN p", None).expect("request");
+        let steps: Vec<StepType> = request.steps.iter().map(|step| step.step_type).collect();
+        assert_eq!(steps, vec![StepType::MATCH, StepType::FOREACH]);
+        let batch: Vec<StepType> = request.steps[1].unwind_batches[1].iter().map(|step| step.step_type).collect();
+        assert_eq!(batch, vec![StepType::CREATE, StepType::SET, StepType::FOREACH]);
+        let nested = &request.steps[1].unwind_batches[1][2].unwind_batches[0][0].patterns[0];
+        assert_eq!(nested.get_node_ref(&NodeIndex::new(0)).get_properties_ref()[0].get_value(), &PropertyValue::PString(String::from("b")));
+        assert!(request.return_clause.is_some());
+        assert!(process_cypher_query("FOREACH (x IN [1] | )", None).is_err());
+        assert!(process_cypher_query("FOREACH (x IN [1] | MATCH (n) CREATE (i:Item))", None).is_err());
+    }
+
+    #[test]
+    fn test_collection_literals() {
+        let request = process_cypher_query("CREATE (n:Person {tags: ['a', 'b'], address: {city: 'Santa Fe', zip: [87501]}}) RETURN n", None);
+        if let  Ok(req) = request {
+            let node = req.steps[0].patterns[0].get_node_ref(&NodeIndex::new(0));
+            assert_eq!(node.get_properties_ref()[0].get_value(), &PropertyValue::PList(vec![PropertyValue::PString(String::from("a")), PropertyValue::PString(String::from("b"))]));
+            let mut address = BTreeMap::new();
+            address.insert(String::from("city"), PropertyValue::PString(String::from("Santa Fe")));
+            address.insert(String::from("zip"), PropertyValue::PList(vec![PropertyValue::PInteger(87501)]));
+            assert_eq!(node.get_properties_ref()[1].get_name(), "address");
+            assert_eq!(node.get_properties_ref()[1].get_value(), &PropertyValue::PMap(address));
         } else {
             assert!(false, "no request found");
         }
+        let request = process_cypher_query("MATCH (n:Person) WHERE n.tags = [1, 2] RETURN n", None);
+        if let  Ok(req) = request {
+            let condition = req.steps[1].where_clause.as_ref().and_then(|w| w.condition.as_ref());
+            match condition {
+                Some(Expression::BinaryOperation(_, Operator::Equal, second)) => {
+                    assert!(matches!(&**second, Expression::Value(PropertyValue::PList(values)) if values.len() == 2));
+                },
+                _ => assert!(false, "wrong where condition"),
+            }
+        } else {
+            assert!(false, "no request found");
+        }
+        assert!(process_cypher_query("CREATE (n:Person {tags: [1, ", None).is_err());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_temporal_functions() {
+        let request = process_cypher_query("CREATE (e:Event {at: datetime('2015-07-21T21:40:32.142+01:00'), day: date({year: 2015, month: 7, day: 21})}) RETURN e", None);
+        if let  Ok(req) = request {
+            let node = req.steps[0].patterns[0].get_node_ref(&NodeIndex::new(0));
+            assert!(matches!(node.get_properties_ref()[0].get_value(), PropertyValue::PDateTime(dt) if dt.offset_seconds == 3600));
+            assert!(matches!(node.get_properties_ref()[1].get_value(), PropertyValue::PDate(date) if date.to_string() == "2015-07-21"));
+        } else {
+            assert!(false, "no request found");
+        }
+        let request = process_cypher_query("MATCH (e:Event) WHERE e.at < datetime('2016-01-01T00:00:00Z') RETURN e, duration('P1D')", None);
+        if let  Ok(req) = request {
+            let condition = req.steps[1].where_clause.as_ref().and_then(|w| w.condition.as_ref());
+            match condition {
+                Some(Expression::BinaryOperation(_, Operator::Inferior, second)) => {
+                    assert!(matches!(&**second, Expression::FunctionCall(func) if func.name == "datetime" && func.args.len() == 1));
+                },
+                _ => assert!(false, "wrong where condition"),
+            }
+            let ret = req.return_clause.expect("return clause");
+            assert_eq!("duration('P1D')", ret.expressions[1].get_name());
+        } else {
+            assert!(false, "no request found");
+        }
+        assert!(process_cypher_query("CREATE (e:Event {at: datetime('not a date')})", None).is_err());
+    }
+
+    #[test]
+    fn test_null_values() {
+        let request = process_cypher_query("CREATE (n:Person {name: 'Alice', city: null, tags: [1, null]}) RETURN n", None);
+        if let  Ok(req) = request {
+            let node = req.steps[0].patterns[0].get_node_ref(&NodeIndex::new(0));
+            assert_eq!(node.get_properties_ref().len(), 2);
+            assert_eq!(node.get_properties_ref()[1].get_value(), &PropertyValue::PList(vec![PropertyValue::PInteger(1), PropertyValue::PNull]));
+        } else {
+            assert!(false, "no request found");
+        }
+        let request = process_cypher_query("MATCH (n:Person) WHERE n.city IS NULL AND n.name IS NOT NULL OR n.age = null RETURN n", None);
+        if let  Ok(req) = request {
+            let condition = req.steps[1].where_clause.as_ref().and_then(|w| w.condition.as_ref());
+            match condition {
+                Some(Expression::BinaryOperation(first, Operator::Or, second)) => {
+                    assert!(matches!(&**first, Expression::BinaryOperation(is_null, Operator::And, is_not_null)
+                        if matches!(**is_null, Expression::IsNull(_)) && matches!(**is_not_null, Expression::IsNotNull(_))));
+                    assert!(matches!(&**second, Expression::BinaryOperation(_, Operator::Equal, null) if matches!(**null, Expression::Value(PropertyValue::PNull))));
+                },
+                _ => assert!(false, "wrong where condition"),
+            }
+        } else {
+            assert!(false, "no request found");
+        }
+        assert!(process_cypher_query("MATCH (n:Person) WHERE n.city IS NOT RETURN n", None).is_err());
+    }
+
+    #[test]
+    fn test_create_constraint() {
+        let request = process_cypher_query("create constraint on (p:Person) assert p.email is unique", None);
+        if let  Ok(req) = request {
+            assert_eq!(req.steps.len(), 1);
+            assert!(matches!(req.steps[0].step_type, StepType::CREATE_CONSTRAINT));
+            assert_eq!(req.steps[0].constraint, Some(UniqueConstraint::new("Person", "email")));
+        } else {
+            assert!(false, "no request found");
+        }
+    }
+
+    #[test]
+    fn test_explain_profile() {
+        let explain = process_cypher_query("explain match (n:Person) return n", None).expect("explain request");
+        assert_eq!(ExecutionMode::Explain, explain.mode);
+        assert_eq!(explain.steps.len(), 1);
+        let profile = process_cypher_query("profile match (n:Person) return n", None).expect("profile request");
+        assert_eq!(ExecutionMode::Profile, profile.mode);
+        let run = process_cypher_query("match (n:Person) return n", None).expect("request");
+        assert_eq!(ExecutionMode::Run, run.mode);
+        assert!(process_cypher_query("explain", None).is_err());
+    }
+
+    #[test]
+    fn test_named_path() {
+        let request = process_cypher_query("MATCH p = (a:Person)-[r:KNOWS]->(b)<-[:KNOWS]-(c) RETURN p", None).expect("request");
+        assert_eq!(1, request.paths.len());
+        assert_eq!("p", request.paths[0].name);
+        assert_eq!(vec!["a", "r", "b", "p#1", "c"], request.paths[0].elements);
+        let pattern = &request.steps[0].patterns[0];
+        assert_eq!(3, pattern.get_nodes().len());
+        assert!(pattern.get_relationships().iter().any(|rel| rel.get_var().as_deref() == Some("p#1")));
+        assert!(process_cypher_query("MATCH (a:Person)-[r:KNOWS]->(b) RETURN a", None).expect("request").paths.is_empty());
+    }
+
+    #[test]
+    fn test_relationship_type_union() {
+        let request = process_cypher_query("MATCH (a:Person)-[r:KNOWS|LIKES]->(b) RETURN r", None).expect("request");
+        let rel = &request.steps[0].patterns[0].get_relationships()[0];
+        assert_eq!(&vec![String::from("KNOWS"), String::from("LIKES")], rel.get_labels_ref());
+        let legacy = process_cypher_query("MATCH (a:Person)-[:KNOWS|:LIKES]->(b) RETURN b", None).expect("request");
+        assert_eq!(2, legacy.steps[0].patterns[0].get_relationships()[0].get_labels_ref().len());
+        assert!(process_cypher_query("CREATE (a:Person)-[r:KNOWS|LIKES]->(b:Person)", None).is_err());
+    }
+
+    fn get_syntax_error(query: &str) -> ParserError {
+        process_cypher_query(query, None).err().expect("syntax error")
+    }
+
+    fn get_expected(err: ParserError) -> Vec<String> {
+        match err {
+            ParserError::UnexpectedToken{expected, ..} => expected,
+            _ => Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_syntax_errors() {
+        let err = get_syntax_error("MATCH (p:Person)\nWHERE p.name STARTS 'A' RETURN p");
+        assert_eq!(ParserError::UnexpectedToken{line: 2, column: 21, token: Some(String::from("'A'")), expected: vec![String::from("'WITH'")]}, err);
+        assert_eq!("unexpected 'A' at line 2, column 21, expected 'WITH'", err.to_string());
+        let err = get_syntax_error("MATCH (n) RETRUN n");
+        assert!(get_expected(err).contains(&String::from("'RETURN'")));
+        let err = get_syntax_error("MATCH (n:Person) WHERE n.age > RETURN n");
+        assert!(err.to_string().starts_with("unexpected 'RETURN' at line 1, column 32, expected one of identifier, integer"));
+        let err = get_syntax_error("MATCH (n:Person)\nRETURN n,\n");
+        assert!(err.to_string().starts_with("unexpected end of query at line 2, column 10"));
+        assert_eq!(ParserError::UnexpectedToken{line: 1, column: 25, token: Some(String::from("@")), expected: Vec::new()}, get_syntax_error("MATCH (n) RETURN n.name @"));
+        assert_eq!(ParserError::SemanticError, get_syntax_error("MATCH (n:Person) RETURN n.name UNION MATCH (c:City) RETURN c.name"));
+        assert!(process_cypher_query("MATCH (n) RETURN n ", None).is_ok());
+    }
+}
@@ -22,6 +22,7 @@
 use zawgl_core::graph::*;
 use zawgl_core::model::*;
 use zawgl_cypher_query_model::ast::AstTag;
+use zawgl_cypher_query_model::model::NamedPath;
 use crate::parameters::Parameters;
 
 use super::states::*;
@@ -36,6 +37,8 @@ pub struct PathBuilder {
     curr_property_name: Option<String>,
     current_path: PropertyGraph,
     params: Option<Parameters>,
+    path_name: Option<String>,
+    path_relationships: Vec<(EdgeIndex, Option<EdgeIndex>)>,
 }
 
 fn make_relationship(visitor_state: &VisitorState) -> Relationship {
@@ -70,7 +73,29 @@ impl PathBuilder {
     pub fn new(params: Option<Parameters>) -> Self {
         PathBuilder {curr_node: None, curr_directed_relationship: None, curr_both_ways_relationship: None,
             pattern_state: VisitorPatternState::Init,
-            id_type: None, curr_property_name: None, current_path: PropertyGraph::new(), params: params }
+            id_type: None, curr_property_name: None, current_path: PropertyGraph::new(), params: params,
+            path_name: None, path_relationships: Vec::new() }
+    }
+
+    /// Variables of a named path, anonymous relationships get a variable that cannot be written in a query.
+    pub fn build_named_path(&mut self) -> Option<NamedPath> {
+        let name = self.path_name.clone()?;
+        let node_vars: Vec<Option<String>> = self.current_path.get_nodes().iter().map(|node| node.get_var().clone()).collect();
+        let mut elements = Vec::new();
+        for (index, node_var) in node_vars.into_iter().enumerate() {
+            if index > 0 {
+                let rel_ids = *self.path_relationships.get(index - 1)?;
+                if self.current_path.get_relationship_ref(&rel_ids.0).get_var().is_none() {
+                    let var = format!("{}#{}", name, index - 1);
+                    for rel_id in Some(rel_ids.0).iter().chain(rel_ids.1.iter()) {
+                        self.current_path.get_relationship_mut(rel_id).set_var(&var);
+                    }
+                }
+                elements.push(self.current_path.get_relationship_ref(&rel_ids.0).get_var().clone()?);
+            }
+            elements.push(node_var?);
+        }
+        Some(NamedPath{name, elements})
     }
 
     pub fn get_path_graph(&self) -> &PropertyGraph {
@@ -128,15 +153,18 @@ impl PathBuilder {
         match ast_tag {
             AstTag::RelDirectedLR => {
                 self.pattern_state = VisitorPatternState::RelationshipLR;
-                self.curr_directed_relationship = source_target.map(|st| self.current_path.add_relationship(make_relationship(&visitor_state), st.0, st.1))
+                self.curr_directed_relationship = source_target.map(|st| self.current_path.add_relationship(make_relationship(&visitor_state), st.0, st.1));
+                self.path_relationships.extend(self.curr_directed_relationship.map(|rel_id| (rel_id, None)));
             }
             AstTag::RelDirectedRL => {
                 self.pattern_state = VisitorPatternState::RelationshipRL;
-                self.curr_directed_relationship = source_target.map(|st| self.current_path.add_relationship(make_relationship(&visitor_state), st.1, st.0))
+                self.curr_directed_relationship = source_target.map(|st| self.current_path.add_relationship(make_relationship(&visitor_state), st.1, st.0));
+                self.path_relationships.extend(self.curr_directed_relationship.map(|rel_id| (rel_id, None)));
             }
             AstTag::RelUndirected => {
                 self.pattern_state = VisitorPatternState::UndirectedRelationship;
                 self.curr_both_ways_relationship = source_target.map(|st| (self.current_path.add_relationship(make_relationship(&visitor_state), st.0, st.1), self.current_path.add_relationship(Relationship::new(), st.1, st.0)));
+                self.path_relationships.extend(self.curr_both_ways_relationship.map(|rel_ids| (rel_ids.0, Some(rel_ids.1))));
             }
             _ => {}
         }
@@ -152,23 +180,6 @@ impl PathBuilder {
         }
     }
 
-    pub fn enter_integer_value(&mut self, value: Option<i64>) {
-        let pv = value.map(|v| PropertyValue::PInteger(v));
-        self.set_property_value(pv);
-    }
-    pub fn enter_float_value(&mut self, value: Option<f64>) {
-        let pv = value.map(|v| PropertyValue::PFloat(v));
-        self.set_property_value(pv);
-    }
-    pub fn enter_string_value(&mut self, value: Option<&str>) {
-        let pv = value.map(|sv|PropertyValue::PString(String::from(sv)));
-        self.set_property_value(pv);
-    }
-    pub fn enter_bool_value(&mut self, value: Option<bool>) {
-        let pv = value.map(|v| PropertyValue::PBool(v));
-        self.set_property_value(pv);
-    }
-    
     pub fn enter_label(&mut self) {
         self.id_type = Some(IdentifierType::Label);
     }
@@ -181,6 +192,11 @@ impl PathBuilder {
             VisitorState::MatchPattern |
             VisitorState::CreatePattern => {
                 match self.pattern_state {
+                    VisitorPatternState::Init => {
+                        if let Some(IdentifierType::Variable) = self.id_type {
+                            self.path_name = Some(String::from(key));
+                        }
+                    },
                     VisitorPatternState::Node => {
                         if let Some(node_id) = self.curr_node {
                             let node = self.current_path.get_node_mut(&node_id);
@@ -253,8 +269,4 @@ impl PathBuilder {
             _ => {}
         }
     }
-    
-    pub fn enter_parameter(&mut self, name: &str) {
-        
-    }
 }
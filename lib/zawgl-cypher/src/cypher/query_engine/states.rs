@@ -26,9 +26,15 @@ pub enum VisitorState {
     DirectiveMatch,
     MatchPattern,
     CreatePattern,
-    FunctionCall,
-    FunctionArg,
     ReturnItem,
+    ReturnAlias,
+    Unwind,
+    WhereClause,
+    CreateConstraint,
+    ProcedureName,
+    ProcedureArg,
+    ProcedureYield,
+    UpdateItem,
 }
 #[derive(PartialEq)]
 pub enum VisitorPatternState {
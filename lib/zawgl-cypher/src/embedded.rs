@@ -0,0 +1,368 @@
+// MIT License
+//
+// Copyright (c) 2022 Alexandre RICCIARDI
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use zawgl_core::graph_engine::query_context::QueryContext;
+use zawgl_core::model::{Node, Path, Property, PropertyGraph, PropertyValue, Relationship, Status};
+use zawgl_core::model::init::InitContext;
+use zawgl_cypher_query_planner::count::rewrite_count_request;
+use zawgl_cypher_query_planner::evaluator::EvalResult;
+use zawgl_cypher_query_model::{QueryStep, StepType, model::Request};
+use zawgl_tx_handler::handle_graph_request;
+use zawgl_tx_handler::databases::{self, DatabaseManager};
+use zawgl_tx_handler::tx_context::{IsolationLevel, TxContext};
+use zawgl_tx_handler::tx_handler::DEFAULT_TX_TIMEOUT_SECS;
+
+use super::{CypherError, run_query};
+use super::parameters::{Parameters, build_parameter};
+use super::session::CypherSession;
+
+const CREATED_VARIABLE: &str = "created";
+
+static TX_COUNT: AtomicU64 = AtomicU64::new(0);
+
+/// Database embedded in an application, its queries run in process through the request handlers of the server.
+pub struct Database {
+    handlers: databases::Database<'static>,
+    session: Mutex<CypherSession>,
+}
+
+impl Database {
+    /// Opens the database stored in the directory, it is created if it does not exist.
+    pub fn open(path: &str) -> Option<Self> {
+        Self::open_with(InitContext::new(path)?)
+    }
+
+    /// Opens the database with the settings of the context, as its page cache size or its durability.
+    pub fn open_with(conf: InitContext<'static>) -> Option<Self> {
+        let handlers = DatabaseManager::new(conf, Duration::from_secs(DEFAULT_TX_TIMEOUT_SECS)).get_database(None).ok()?;
+        Some(Database{handlers, session: Mutex::new(CypherSession::new())})
+    }
+
+    /// Runs the query in its own transaction.
+    pub fn execute_cypher(&self, query: &str, params: &[(&str, PropertyValue)]) -> Result<QueryResult, CypherError> {
+        self.execute(query, params, None)
+    }
+
+    /// Creates a node without Cypher once its labels and properties are set.
+    pub fn create_node(&self) -> NodeBuilder<'_> {
+        NodeBuilder::new(self, None)
+    }
+
+    /// Creates a relationship between the nodes of the ids without Cypher once its type and properties are set.
+    pub fn create_relationship(&self, source_id: u64, target_id: u64) -> RelationshipBuilder<'_> {
+        RelationshipBuilder::new(self, None, source_id, target_id)
+    }
+
+    /// Starts a transaction, it is rolled back if it is dropped before being committed.
+    pub fn begin(&self) -> Transaction<'_> {
+        let session_id = format!("embedded-tx-{}", TX_COUNT.fetch_add(1, Ordering::SeqCst) + 1);
+        Transaction{db: self, tx_context: TxContext{session_id, commit: false, rollback: false, read_only: false, isolation: IsolationLevel::default()}, ended: false}
+    }
+
+    fn execute(&self, query: &str, params: &[(&str, PropertyValue)], tx_context: Option<TxContext>) -> Result<QueryResult, CypherError> {
+        let params = params.iter().map(|(name, value)| (String::from(*name), build_parameter(value))).collect::<Parameters>();
+        let request = self.session.lock().unwrap().build_request(query, Some(params)).map_err(CypherError::QueryError)?;
+        self.run(&rewrite_count_request(request), tx_context)
+    }
+
+    fn run(&self, request: &Request, tx_context: Option<TxContext>) -> Result<QueryResult, CypherError> {
+        let (paged_result, table) = run_query(self.handlers.tx_handler.clone(), self.handlers.graph_request_handler.clone(), request, tx_context, None, &QueryContext::new())?;
+        let (columns, rows) = table.map(|table| (table.columns, table.rows)).unwrap_or_default();
+        Ok(QueryResult{columns: Arc::new(columns), rows: rows.into_iter(), graphs: paged_result.graphs})
+    }
+
+    fn end_tx(&self, tx_context: TxContext) -> Result<(), CypherError> {
+        handle_graph_request(self.handlers.tx_handler.clone(), self.handlers.graph_request_handler.clone(), &Vec::new(), Some(tx_context)).map(|_| ()).map_err(CypherError::TxError)
+    }
+}
+
+/// Transaction of an embedded database, its writes are seen by the other queries once it is committed.
+pub struct Transaction<'a> {
+    db: &'a Database,
+    tx_context: TxContext,
+    ended: bool,
+}
+
+impl <'a> Transaction<'a> {
+    pub fn execute_cypher(&self, query: &str, params: &[(&str, PropertyValue)]) -> Result<QueryResult, CypherError> {
+        self.db.execute(query, params, Some(self.tx_context.clone()))
+    }
+
+    pub fn create_node(&self) -> NodeBuilder<'_> {
+        NodeBuilder::new(self.db, Some(self.tx_context.clone()))
+    }
+
+    pub fn create_relationship(&self, source_id: u64, target_id: u64) -> RelationshipBuilder<'_> {
+        RelationshipBuilder::new(self.db, Some(self.tx_context.clone()), source_id, target_id)
+    }
+
+    pub fn commit(mut self) -> Result<(), CypherError> {
+        self.end(true)
+    }
+
+    pub fn rollback(mut self) -> Result<(), CypherError> {
+        self.end(false)
+    }
+
+    fn end(&mut self, commit: bool) -> Result<(), CypherError> {
+        self.ended = true;
+        self.db.end_tx(TxContext{commit, rollback: !commit, ..self.tx_context.clone()})
+    }
+}
+
+impl <'a> Drop for Transaction<'a> {
+    fn drop(&mut self) {
+        if !self.ended {
+            let _ = self.end(false);
+        }
+    }
+}
+
+fn build_create_request(pattern: PropertyGraph) -> Request {
+    let mut step = QueryStep::new(StepType::CREATE);
+    step.patterns.push(pattern);
+    let mut request = Request::new();
+    request.steps.push(step);
+    request
+}
+
+fn is_created(var: &Option<String>) -> bool {
+    var.as_deref() == Some(CREATED_VARIABLE)
+}
+
+/// Node to create, the request is the one of a Cypher CREATE so that it goes through the same constraints and locks.
+pub struct NodeBuilder<'a> {
+    db: &'a Database,
+    tx_context: Option<TxContext>,
+    node: Node,
+}
+
+impl <'a> NodeBuilder<'a> {
+    fn new(db: &'a Database, tx_context: Option<TxContext>) -> Self {
+        let mut node = Node::new();
+        node.set_var(CREATED_VARIABLE);
+        node.set_status(Status::Create);
+        NodeBuilder{db, tx_context, node}
+    }
+
+    pub fn label(mut self, label: &str) -> Self {
+        self.node.get_labels_mut().push(String::from(label));
+        self
+    }
+
+    pub fn prop<T: Into<PropertyValue>>(mut self, name: &str, value: T) -> Self {
+        self.node.get_properties_mut().push(Property::new(String::from(name), value.into()));
+        self
+    }
+
+    /// Creates the node and returns it with its id.
+    pub fn done(self) -> Result<Node, CypherError> {
+        let mut pattern = PropertyGraph::new();
+        pattern.add_node(self.node);
+        let result = self.db.run(&build_create_request(pattern), self.tx_context)?;
+        result.graphs.iter().flat_map(|graph| graph.get_nodes()).find(|node| is_created(node.get_var())).cloned().ok_or(CypherError::ResponseError)
+    }
+}
+
+/// Relationship to create between two existing nodes, it fails if one of them does not exist.
+pub struct RelationshipBuilder<'a> {
+    db: &'a Database,
+    tx_context: Option<TxContext>,
+    source_id: u64,
+    target_id: u64,
+    relationship: Relationship,
+}
+
+impl <'a> RelationshipBuilder<'a> {
+    fn new(db: &'a Database, tx_context: Option<TxContext>, source_id: u64, target_id: u64) -> Self {
+        let mut relationship = Relationship::new();
+        relationship.set_var(CREATED_VARIABLE);
+        relationship.set_status(Status::Create);
+        RelationshipBuilder{db, tx_context, source_id, target_id, relationship}
+    }
+
+    pub fn rel_type(mut self, rel_type: &str) -> Self {
+        self.relationship.set_labels(vec![String::from(rel_type)]);
+        self
+    }
+
+    pub fn prop<T: Into<PropertyValue>>(mut self, name: &str, value: T) -> Self {
+        self.relationship.get_properties_mut().push(Property::new(String::from(name), value.into()));
+        self
+    }
+
+    /// Creates the relationship and returns it with its id.
+    pub fn done(self) -> Result<Relationship, CypherError> {
+        let mut pattern = PropertyGraph::new();
+        let source = pattern.add_node(build_matched_node(self.source_id));
+        let target = if self.source_id == self.target_id { source } else { pattern.add_node(build_matched_node(self.target_id)) };
+        pattern.add_relationship(self.relationship, source, target);
+        let result = self.db.run(&build_create_request(pattern), self.tx_context)?;
+        result.graphs.iter().flat_map(|graph| graph.get_relationships()).find(|rel| is_created(rel.get_var())).cloned().ok_or(CypherError::ResponseError)
+    }
+}
+
+fn build_matched_node(id: u64) -> Node {
+    let mut node = Node::new();
+    node.set_id(Some(id));
+    node.set_status(Status::Match);
+    node
+}
+
+/// Rows of the return clause of a query, a query without one only has the graphs it matched or created.
+pub struct QueryResult {
+    columns: Arc<Vec<String>>,
+    rows: std::vec::IntoIter<Vec<EvalResult>>,
+    graphs: Vec<PropertyGraph>,
+}
+
+impl QueryResult {
+    pub fn get_columns(&self) -> &[String] {
+        &self.columns
+    }
+
+    pub fn get_graphs(&self) -> &[PropertyGraph] {
+        &self.graphs
+    }
+}
+
+impl Iterator for QueryResult {
+    type Item = Row;
+
+    fn next(&mut self) -> Option<Row> {
+        self.rows.next().map(|values| Row{columns: self.columns.clone(), values})
+    }
+}
+
+/// Values of a result row, looked up by column name.
+#[derive(Debug, Clone)]
+pub struct Row {
+    columns: Arc<Vec<String>>,
+    values: Vec<EvalResult>,
+}
+
+impl Row {
+    pub fn get(&self, column: &str) -> Option<&EvalResult> {
+        self.columns.iter().position(|name| name == column).and_then(|index| self.values.get(index))
+    }
+
+    /// Property value of the column, None if it is null or not a property value.
+    pub fn get_value(&self, column: &str) -> Option<&PropertyValue> {
+        match self.get(column)? {
+            EvalResult::Scalar(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn get_node(&self, column: &str) -> Option<&Node> {
+        match self.get(column)? {
+            EvalResult::Node(node) => Some(node),
+            _ => None,
+        }
+    }
+
+    pub fn get_relationship(&self, column: &str) -> Option<&Relationship> {
+        match self.get(column)? {
+            EvalResult::Relationship(rel) => Some(rel),
+            _ => None,
+        }
+    }
+
+    pub fn get_path(&self, column: &str) -> Option<&Path> {
+        match self.get(column)? {
+            EvalResult::Path(path) => Some(path),
+            _ => None,
+        }
+    }
+
+    pub fn get_values(&self) -> &[EvalResult] {
+        &self.values
+    }
+
+    pub fn into_values(self) -> Vec<EvalResult> {
+        self.values
+    }
+}
+
+#[cfg(test)]
+mod test_embedded {
+    use super::*;
+    use zawgl_core::test_utils::build_dir_path_and_rm_old;
+
+    fn count_persons(db: &Database) -> usize {
+        db.execute_cypher("MATCH (n:Person) RETURN n.name AS name", &[]).expect("match").count()
+    }
+
+    #[test]
+    fn test_embedded_database() {
+        let main_dir = build_dir_path_and_rm_old("test_embedded_database").expect("db path");
+        let db = Database::open(&main_dir).expect("database");
+        let created = db.execute_cypher("CREATE (n:Person {name: $name}) RETURN n", &[("name", PropertyValue::PString(String::from("ada")))]).expect("create");
+        assert_eq!(["n"], created.get_columns());
+        let row = created.into_iter().next().expect("row");
+        assert_eq!(Some(&vec![String::from("Person")]), row.get_node("n").map(|node| node.get_labels_ref()));
+
+        let result = db.execute_cypher("MATCH (n:Person) WHERE n.name = $name RETURN n.name AS name", &[("name", PropertyValue::PString(String::from("ada")))]).expect("match");
+        let names = result.map(|row| row.get_value("name").cloned()).collect::<Vec<Option<PropertyValue>>>();
+        assert_eq!(vec![Some(PropertyValue::PString(String::from("ada")))], names);
+        assert!(db.execute_cypher("MATCH (n:Person RETURN n", &[]).is_err());
+
+        let tx = db.begin();
+        tx.execute_cypher("CREATE (n:Person {name: 'alan'}) RETURN n", &[]).expect("create in tx");
+        assert_eq!(1, count_persons(&db));
+        tx.commit().expect("commit");
+        assert_eq!(2, count_persons(&db));
+
+        let tx = db.begin();
+        tx.execute_cypher("CREATE (n:Person {name: 'grace'}) RETURN n", &[]).expect("create in tx");
+        tx.rollback().expect("rollback");
+        {
+            let tx = db.begin();
+            tx.execute_cypher("CREATE (n:Person {name: 'linus'}) RETURN n", &[]).expect("create in tx");
+        }
+        assert_eq!(2, count_persons(&db));
+    }
+
+    #[test]
+    fn test_embedded_builders() {
+        let main_dir = build_dir_path_and_rm_old("test_embedded_builders").expect("db path");
+        let db = Database::open(&main_dir).expect("database");
+        let ada = db.create_node().label("Person").prop("name", "ada").prop("born", 1815i64).done().expect("node");
+        assert!(ada.get_id().is_some());
+        assert_eq!(&vec![String::from("Person")], ada.get_labels_ref());
+        let tx = db.begin();
+        let alan = tx.create_node().label("Person").prop("name", "alan").done().expect("node in tx");
+        let knows = tx.create_relationship(ada.get_id().unwrap(), alan.get_id().unwrap()).rel_type("KNOWS").prop("since", 1936i64).done().expect("relationship");
+        assert!(knows.get_id().is_some());
+        tx.commit().expect("commit");
+        assert!(db.create_relationship(ada.get_id().unwrap(), 1000).rel_type("KNOWS").done().is_err());
+
+        let result = db.execute_cypher("MATCH (a:Person)-[r:KNOWS]->(b:Person) RETURN a.name AS a, r.since AS since, b.name AS b", &[]).expect("match");
+        let rows = result.map(|row| row.into_values()).collect::<Vec<Vec<EvalResult>>>();
+        assert_eq!(1, rows.len());
+        assert!(matches!(&rows[0][..], [EvalResult::Scalar(PropertyValue::PString(a)), EvalResult::Scalar(PropertyValue::PInteger(1936)), EvalResult::Scalar(PropertyValue::PString(b))] if a == "ada" && b == "alan"));
+    }
+}
@@ -1,8 +1,22 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use thiserror::Error;
 use bson::{Bson, Document, doc};
-use cypher::query_engine::process_cypher_query;
+use session::CypherSession;
+use zawgl_cypher_query_planner::projection::{ResultTable, project, project_table, union_tables};
+use zawgl_cypher_query_planner::evaluator::{EvalResult, PathBindings};
+use zawgl_cypher_query_planner::{PagedResult, ResultPage};
+use zawgl_cypher_query_planner::count::rewrite_count_request;
+use zawgl_cypher_query_planner::explain::StepPlan;
+use zawgl_cypher_query_model::{StepType, ExecutionMode, model::Request};
 use parameters::*;
-use zawgl_core::model::{Property, PropertyValue};
-use zawgl_tx_handler::{DatabaseError, handle_graph_request, request_handler::RequestHandler, tx_handler::TxHandler};
+use cypher::parser::error::ParserError;
+use zawgl_core::model::{Node, Path, Property, PropertyGraph, PropertyValue, Relationship};
+use zawgl_core::graph_engine::query_context::QueryContext;
+use zawgl_core::model::changes::{ChangeEvent, ChangeSet};
+use zawgl_tx_handler::{DatabaseError, handle_graph_request, handle_graph_query, request_handler::RequestHandler, tx_context::{IsolationLevel, TxContext}, tx_handler::TxHandler};
+use zawgl_tx_handler::query_log::{QueryLogEntry, log_query};
 
 extern crate zawgl_core;
 
@@ -29,55 +43,333 @@ extern crate bson;
 // SOFTWARE.
 
 pub mod cypher;
+pub mod session;
+pub mod query_cache;
+pub mod embedded;
 mod parameters;
 
-#[derive(Debug)]
+#[derive(Debug, Error)]
 pub enum CypherError {
+    #[error("invalid cypher request")]
     RequestError,
+    #[error("invalid cypher response")]
     ResponseError,
+    #[error(transparent)]
+    QueryError(ParserError),
+    #[error(transparent)]
     TxError(DatabaseError)
 }
 
+fn build_tx_context(cypher_request: &Document) -> Option<TxContext> {
+    let session_id = cypher_request.get_str("session_id").ok()?;
+    let tx = cypher_request.get_str("tx").unwrap_or("");
+    let read_only = cypher_request.get_bool("read_only").unwrap_or(false);
+    let isolation = cypher_request.get_str("isolation").ok().and_then(IsolationLevel::from_name).unwrap_or_default();
+    Some(TxContext{session_id: String::from(session_id), commit: tx == "commit", rollback: tx == "rollback", read_only, isolation})
+}
+
+fn get_positive_integer(cypher_request: &Document, key: &str) -> Option<usize> {
+    match cypher_request.get(key)? {
+        Bson::Int32(v) if *v >= 0 => Some(*v as usize),
+        Bson::Int64(v) if *v >= 0 => Some(*v as usize),
+        _ => None,
+    }
+}
+
+/// Timeouts are sent in milliseconds, a zero timeout disables the one of the session.
+fn get_timeout(cypher_request: &Document, key: &str) -> Option<Duration> {
+    get_positive_integer(cypher_request, key).map(|millis| Duration::from_millis(millis as u64))
+}
+
+fn build_result_page(cypher_request: &Document, request: &Request) -> Option<ResultPage> {
+    let page_size = get_positive_integer(cypher_request, "page_size").filter(|size| *size > 0)?;
+    if request.mode != ExecutionMode::Run || !request.unions.is_empty() {
+        return None;
+    }
+    let is_read_only = request.steps.iter().all(|step| matches!(step.step_type, StepType::MATCH | StepType::OPTIONAL_MATCH | StepType::WHERE));
+    let has_aggregation = request.return_clause.as_ref().map_or(false, |ret| ret.has_aggregation());
+    if !is_read_only || has_aggregation {
+        return None;
+    }
+    let cursor = get_positive_integer(cypher_request, "cursor").unwrap_or(0);
+    Some(ResultPage::new(cursor, page_size))
+}
+
+fn build_response(request_id: &str, result_doc: Document) -> Document {
+    let mut response_doc = Document::new();
+    response_doc.insert("request_id", request_id);
+    response_doc.insert("result", result_doc);
+    response_doc
+}
+
 pub fn handle_open_cypher_request<'a>(tx_handler: TxHandler, graph_request_handler: RequestHandler<'a>, cypher_request: &Document) -> Result<Document, CypherError> {
-    let query = cypher_request.get_str("query").map_err(|err| CypherError::RequestError)?;
-    let request_id = cypher_request.get_str("request_id").map_err(|err| CypherError::RequestError)?;
+    handle_open_cypher_session_request(tx_handler, graph_request_handler, &mut CypherSession::new(), cypher_request)
+}
+
+fn count_results(response: &Document) -> usize {
+    let result = response.get_document("result").ok();
+    let items = result.and_then(|result| result.get_array("rows").or_else(|_| result.get_array("graphs")).ok());
+    items.map(|items| items.len()).unwrap_or(0)
+}
+
+/// Handles a request of a client session, session parameters may be sent alone or along with a query.
+/// Queries are logged with their duration once handled.
+pub fn handle_open_cypher_session_request<'a>(tx_handler: TxHandler, graph_request_handler: RequestHandler<'a>, session: &mut CypherSession, cypher_request: &Document) -> Result<Document, CypherError> {
+    handle_open_cypher_query_request(tx_handler, graph_request_handler, session, cypher_request, &QueryContext::new())
+}
+
+/// Handles a request of a client session in a query context, the query fails once the context is aborted.
+pub fn handle_open_cypher_query_request<'a>(tx_handler: TxHandler, graph_request_handler: RequestHandler<'a>, session: &mut CypherSession, cypher_request: &Document, query_context: &QueryContext) -> Result<Document, CypherError> {
+    let start = Instant::now();
+    let res = run_open_cypher_session_request(tx_handler, graph_request_handler.clone(), session, cypher_request, query_context);
+    if let Ok(query) = cypher_request.get_str("query") {
+        let query_log = graph_request_handler.read().unwrap().get_query_log();
+        let parameters = cypher_request.get_document("parameters").ok();
+        log_query(&query_log, &QueryLogEntry{language: "cypher", query, parameters: parameters.map(|p| p as &dyn std::fmt::Display),
+            tx_id: cypher_request.get_str("session_id").ok(), elapsed: start.elapsed(), results: res.as_ref().ok().map(count_results)});
+    }
+    res
+}
+
+fn run_open_cypher_session_request<'a>(tx_handler: TxHandler, graph_request_handler: RequestHandler<'a>, session: &mut CypherSession, cypher_request: &Document, query_context: &QueryContext) -> Result<Document, CypherError> {
+    let request_id = cypher_request.get_str("request_id").map_err(|_| CypherError::RequestError)?;
+    let tx_context = build_tx_context(cypher_request);
+    if let Some(ctx) = &tx_context {
+        if ctx.commit || ctx.rollback {
+            handle_graph_request(tx_handler.clone(), graph_request_handler.clone(), &Vec::new(), tx_context).map_err(|err| CypherError::TxError(err))?;
+            return Ok(build_response(request_id, doc!{"graphs": Bson::Array(Vec::new())}));
+        }
+    }
+    let session_parameters = cypher_request.get_document("session_parameters").ok();
+    if let Some(session_parameters) = session_parameters {
+        session.set_parameters(build_parameters(session_parameters));
+    }
+    let session_timeout = get_timeout(cypher_request, "session_timeout");
+    if let Some(timeout) = session_timeout {
+        session.set_timeout(Some(timeout).filter(|timeout| !timeout.is_zero()));
+    }
+    if (session_parameters.is_some() || session_timeout.is_some()) && !cypher_request.contains_key("query") {
+        return Ok(build_response(request_id, doc!{"graphs": Bson::Array(Vec::new())}));
+    }
+    let query_context = &match get_timeout(cypher_request, "timeout").or_else(|| session.get_timeout()).filter(|timeout| !timeout.is_zero()) {
+        Some(timeout) => query_context.clone().with_timeout(timeout),
+        None => query_context.clone(),
+    };
+    let query = cypher_request.get_str("query").map_err(|_| CypherError::RequestError)?;
     let parameters = cypher_request.get_document("parameters");
     let params = parameters.ok().map(|p| build_parameters(p));
-    let request = process_cypher_query(query, params).ok_or(CypherError::RequestError)?;
-    let matched_graphs = handle_graph_request(tx_handler.clone(), graph_request_handler.clone(), &request.steps, None).map_err(|err| CypherError::TxError(err))?;
+    let request = rewrite_count_request(session.build_request(query, params).map_err(CypherError::QueryError)?);
+    let page = build_result_page(cypher_request, &request);
+    let (paged_result, table) = run_query(tx_handler, graph_request_handler, &request, tx_context, page.as_ref(), query_context)?;
+    let matched_graphs = paged_result.graphs;
+    let plan = paged_result.plan;
     let mut result_doc = Document::new();
     let mut graph_list = Vec::new();
     for pattern in &matched_graphs {
         let mut graph_doc = Document::new();  
         let mut nodes_doc = Vec::new();
         for node in pattern.get_nodes() {
-            nodes_doc.push(doc!{
-                "id": node.get_id().ok_or(CypherError::ResponseError)?.to_string(),
-                "properties": build_properties(node.get_properties_ref()),
-                "labels": Bson::from(node.get_labels_ref()),
-            });
+            nodes_doc.push(build_node(node)?);
         }
         graph_doc.insert("nodes", nodes_doc);
 
         let mut rels_doc = Vec::new();
         for rel in pattern.get_relationships_and_edges() {
-            rels_doc.push(doc!{
-                "id": rel.relationship.get_id().ok_or(CypherError::ResponseError)?.to_string(),
-                "source_id": pattern.get_node_ref(&rel.get_source()).get_id().ok_or(CypherError::ResponseError)?.to_string(),
-                "target_id": pattern.get_node_ref(&rel.get_target()).get_id().ok_or(CypherError::ResponseError)?.to_string(),
-                "properties": build_properties(rel.relationship.get_properties_ref()),
-                "labels": Bson::from(rel.relationship.get_labels_ref()),
-            });
+            let source_id = pattern.get_node_ref(&rel.get_source()).get_id().ok_or(CypherError::ResponseError)?;
+            let target_id = pattern.get_node_ref(&rel.get_target()).get_id().ok_or(CypherError::ResponseError)?;
+            rels_doc.push(build_relationship(&rel.relationship, Some(source_id), Some(target_id))?);
         }
         graph_doc.insert("relationships", rels_doc);
         graph_list.push(graph_doc);
     }
     result_doc.insert("graphs", graph_list);
+    if let Some(p) = &page {
+        result_doc.insert("has_more", paged_result.has_more);
+        if paged_result.has_more {
+            result_doc.insert("cursor", (p.skip + p.limit) as i64);
+        }
+    }
+    if let Some(plan) = &plan {
+        result_doc.insert("plan", build_plan(plan));
+    }
 
-    let mut response_doc = Document::new();
-    response_doc.insert("request_id", request_id);
-    response_doc.insert("result", result_doc);
-    Ok(response_doc)
+    if let Some(table) = table {
+        let endpoints = collect_relationships_endpoints(&matched_graphs);
+        let mut rows = Vec::new();
+        for row in &table.rows {
+            let mut values = Vec::new();
+            for value in row {
+                values.push(build_eval_result(value, &endpoints)?);
+            }
+            rows.push(Bson::from(values));
+        }
+        result_doc.insert("columns", table.columns);
+        result_doc.insert("rows", rows);
+    }
+
+    Ok(build_response(request_id, result_doc))
+}
+
+/// Runs a request followed by the query parts of its unions, their graphs, plans and rows are combined.
+fn run_query<'a>(tx_handler: TxHandler, graph_request_handler: RequestHandler<'a>, request: &Request, tx_context: Option<TxContext>, page: Option<&ResultPage>, query_context: &QueryContext) -> Result<(PagedResult, Option<ResultTable>), CypherError> {
+    let (mut paged_result, mut table) = run_request(tx_handler.clone(), graph_request_handler.clone(), request, tx_context.clone(), page, query_context)?;
+    if request.mode == ExecutionMode::Run && request.steps.iter().any(|step| step.step_type == StepType::CREATE_CONSTRAINT) {
+        query_cache::invalidate_query_cache();
+    }
+    for union in &request.unions {
+        let (part_result, part_table) = run_request(tx_handler.clone(), graph_request_handler.clone(), &union.request, tx_context.clone(), None, query_context)?;
+        paged_result.graphs.extend(part_result.graphs);
+        if let (Some(plan), Some(part_plan)) = (&mut paged_result.plan, part_result.plan) {
+            plan.extend(part_plan);
+        }
+        table = table.zip(part_table).map(|(table, part_table)| union_tables(table, part_table, union.all));
+    }
+    Ok((paged_result, table))
+}
+
+/// Runs the steps of a request and builds its rows from its return clause, or takes the records of a procedure call.
+fn run_request<'a>(tx_handler: TxHandler, graph_request_handler: RequestHandler<'a>, request: &Request, tx_context: Option<TxContext>, page: Option<&ResultPage>, query_context: &QueryContext) -> Result<(PagedResult, Option<ResultTable>), CypherError> {
+    let mut paged_result = handle_graph_query(tx_handler, graph_request_handler, &request.steps, tx_context, page, request.mode, query_context).map_err(CypherError::TxError)?;
+    let records = paged_result.records.take();
+    let projected = request.return_clause.as_ref().map(|return_clause| match &records {
+        Some(records) => project_table(return_clause, records),
+        None => {
+            let bindings: Vec<PathBindings> = paged_result.graphs.iter().map(|graph| PathBindings::new(graph, &request.paths)).collect();
+            project(return_clause, &bindings)
+        },
+    });
+    Ok((paged_result, projected.or(records)))
+}
+
+fn build_plan(plan: &Vec<StepPlan>) -> Vec<Document> {
+    let mut steps = Vec::new();
+    for step in plan {
+        let mut patterns = Vec::new();
+        for pattern in &step.patterns {
+            let mut pattern_doc = doc!{
+                "start_labels": Bson::from(&pattern.start_labels),
+                "scan": pattern.scan_type.get_name(),
+                "estimated_rows": pattern.estimated_rows,
+            };
+            if let Some(var) = &pattern.start_variable {
+                pattern_doc.insert("start_variable", var);
+            }
+            patterns.push(pattern_doc);
+        }
+        let mut step_doc = doc!{
+            "step": step.step_name,
+            "patterns": patterns,
+        };
+        if let Some(rows) = step.rows {
+            step_doc.insert("rows", rows as i64);
+        }
+        if let Some(elapsed) = step.elapsed_micros {
+            step_doc.insert("time_us", elapsed as i64);
+        }
+        steps.push(step_doc);
+    }
+    steps
+}
+
+fn build_node(node: &Node) -> Result<Document, CypherError> {
+    Ok(doc!{
+        "id": node.get_id().ok_or(CypherError::ResponseError)?.to_string(),
+        "properties": build_properties(node.get_properties_ref()),
+        "labels": Bson::from(node.get_labels_ref()),
+    })
+}
+
+fn build_relationship(rel: &Relationship, source_id: Option<u64>, target_id: Option<u64>) -> Result<Document, CypherError> {
+    let mut rel_doc = doc!{
+        "id": rel.get_id().ok_or(CypherError::ResponseError)?.to_string(),
+    };
+    if let Some(sid) = source_id {
+        rel_doc.insert("source_id", sid.to_string());
+    }
+    if let Some(tid) = target_id {
+        rel_doc.insert("target_id", tid.to_string());
+    }
+    rel_doc.insert("properties", build_properties(rel.get_properties_ref()));
+    rel_doc.insert("labels", Bson::from(rel.get_labels_ref()));
+    Ok(rel_doc)
+}
+
+/// Path as its nodes and relationships in the order they are traversed.
+fn build_path(path: &Path) -> Result<Document, CypherError> {
+    let mut nodes = Vec::new();
+    for node in path.get_nodes_ref() {
+        nodes.push(build_node(node)?);
+    }
+    let mut relationships = Vec::new();
+    for (index, rel) in path.get_relationships_ref().iter().enumerate() {
+        let (source, target) = path.get_endpoints(index).ok_or(CypherError::ResponseError)?;
+        relationships.push(build_relationship(rel, source.get_id(), target.get_id())?);
+    }
+    Ok(doc!{"nodes": nodes, "relationships": relationships})
+}
+
+/// Change set of the change log, its nodes and relationships are built like the ones of the query results.
+pub fn build_change_set(change_set: &ChangeSet) -> Result<Document, CypherError> {
+    let mut events = Vec::new();
+    for event in &change_set.events {
+        events.push(match event {
+            ChangeEvent::Node{kind, node} => doc!{"change": kind.get_name(), "node": build_node(node)?},
+            ChangeEvent::Relationship{kind, relationship, source_id, target_id} => doc!{"change": kind.get_name(), "relationship": build_relationship(relationship, Some(*source_id), Some(*target_id))?},
+        });
+    }
+    Ok(doc!{
+        "offset": change_set.offset as i64,
+        "next_offset": change_set.next_offset as i64,
+        "timestamp": change_set.timestamp_millis,
+        "events": events,
+    })
+}
+
+fn build_property_value(value: &PropertyValue) -> Bson {
+    match value {
+        PropertyValue::PBool(v) => Bson::from(*v),
+        PropertyValue::PFloat(f) => Bson::from(*f),
+        PropertyValue::PInteger(i) => Bson::from(*i),
+        PropertyValue::PString(s) => Bson::from(s),
+        PropertyValue::PList(values) => Bson::Array(values.iter().map(build_property_value).collect()),
+        PropertyValue::PMap(entries) => Bson::Document(entries.iter().map(|(k, v)| (k.clone(), build_property_value(v))).collect()),
+        PropertyValue::PDateTime(dt) => Bson::from(dt.to_string()),
+        PropertyValue::PDate(date) => Bson::from(date.to_string()),
+        PropertyValue::PDuration(duration) => Bson::from(duration.to_string()),
+        PropertyValue::PNull => Bson::Null,
+    }
+}
+
+fn collect_relationships_endpoints(graphs: &Vec<PropertyGraph>) -> HashMap<u64, (Option<u64>, Option<u64>)> {
+    let mut endpoints = HashMap::new();
+    for graph in graphs {
+        for rel in graph.get_relationships_and_edges() {
+            if let Some(id) = rel.relationship.get_id() {
+                endpoints.insert(id, (graph.get_node_ref(&rel.get_source()).get_id(), graph.get_node_ref(&rel.get_target()).get_id()));
+            }
+        }
+    }
+    endpoints
+}
+
+fn build_eval_result(value: &EvalResult, endpoints: &HashMap<u64, (Option<u64>, Option<u64>)>) -> Result<Bson, CypherError> {
+    match value {
+        EvalResult::Null => Ok(Bson::Null),
+        EvalResult::Scalar(v) => Ok(build_property_value(v)),
+        EvalResult::Node(n) => Ok(Bson::from(build_node(n)?)),
+        EvalResult::Relationship(r) => {
+            let (source_id, target_id) = r.get_id().and_then(|id| endpoints.get(&id).copied()).unwrap_or((None, None));
+            Ok(Bson::from(build_relationship(r, source_id, target_id)?))
+        },
+        EvalResult::Path(p) => Ok(Bson::from(build_path(p)?)),
+        EvalResult::List(values) => {
+            let mut list = Vec::new();
+            for v in values {
+                list.push(build_eval_result(v, endpoints)?);
+            }
+            Ok(Bson::from(list))
+        },
+    }
 }
 
 fn build_properties(item_properties: &Vec<Property>) -> Vec<Document> {
@@ -87,12 +379,7 @@ fn build_properties(item_properties: &Vec<Property>) -> Vec<Document> {
         let name = p.get_name();
         let value = p.get_value();
         let mut bprop = Document::new();
-        match value {
-            PropertyValue::PBool(v) => bprop.insert(name, v),
-            PropertyValue::PFloat(f) => bprop.insert(name, f),
-            PropertyValue::PInteger(i) => bprop.insert(name, i),
-            PropertyValue::PString(s) => bprop.insert(name, s),
-        };
+        bprop.insert(name, build_property_value(value));
         props.push(bprop);
     }
     props
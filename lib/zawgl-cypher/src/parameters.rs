@@ -33,16 +33,38 @@ pub enum ParameterValue {
 
 pub type Parameters = HashMap<String, ParameterValue>;
 
+/// Values nested in list parameters, documents become maps and unsupported types null.
+fn build_property_value(value: &Bson) -> PropertyValue {
+    match value {
+        Bson::Double(v) => PropertyValue::PFloat(*v),
+        Bson::String(v) => PropertyValue::PString(v.to_string()),
+        Bson::Boolean(v) => PropertyValue::PBool(*v),
+        Bson::Int32(v) => PropertyValue::PInteger(*v as i64),
+        Bson::Int64(v) => PropertyValue::PInteger(*v),
+        Bson::Array(values) => PropertyValue::PList(values.iter().map(build_property_value).collect()),
+        Bson::Document(doc) => PropertyValue::PMap(doc.iter().map(|(k, v)| (k.to_string(), build_property_value(v))).collect()),
+        _ => PropertyValue::PNull,
+    }
+}
+
+/// Parameter of a property value, maps become nested parameters as the ones of documents.
+pub fn build_parameter(value: &PropertyValue) -> ParameterValue {
+    match value {
+        PropertyValue::PMap(entries) => ParameterValue::Parameters(entries.iter().map(|(k, v)| (k.to_string(), build_parameter(v))).collect()),
+        _ => ParameterValue::Value(value.clone()),
+    }
+}
+
 pub fn build_parameters(params: &Document) -> Parameters {
     let mut parameters = Parameters::new();
     for param in params {
         match param.1 {
             Bson::Double(v) => {parameters.insert(param.0.to_string(), ParameterValue::Value(PropertyValue::PFloat(*v)));}
             Bson::String(v) => {parameters.insert(param.0.to_string(), ParameterValue::Value(PropertyValue::PString(v.to_string())));}
-            Bson::Array(_) => todo!(),
+            Bson::Array(v) => {parameters.insert(param.0.to_string(), ParameterValue::Value(PropertyValue::PList(v.iter().map(build_property_value).collect())));}
             Bson::Document(v) => {parameters.insert(param.0.to_string(), ParameterValue::Parameters(build_parameters(v)));}
             Bson::Boolean(v) => {parameters.insert(param.0.to_string(), ParameterValue::Value(PropertyValue::PBool(*v)));}
-            Bson::Null => todo!(),
+            Bson::Null => {parameters.insert(param.0.to_string(), ParameterValue::Value(PropertyValue::PNull));}
             Bson::RegularExpression(_) => todo!(),
             Bson::JavaScriptCode(_) => todo!(),
             Bson::JavaScriptCodeWithScope(_) => todo!(),
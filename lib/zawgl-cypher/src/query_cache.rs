@@ -0,0 +1,253 @@
+// MIT License
+//
+// Copyright (c) 2022 Alexandre RICCIARDI
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+
+use zawgl_core::metrics::{self, Counter};
+use crate::cypher::parser::error::ParserResult;
+use crate::cypher::query_engine::{PreparedQuery, prepare_cypher_query};
+
+pub const DEFAULT_QUERY_CACHE_CAPACITY: usize = 1024;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QueryCacheStatistics {
+    pub entries: usize,
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    pub invalidations: u64,
+}
+
+struct CacheEntry {
+    statement: Arc<PreparedQuery>,
+    last_used: u64,
+}
+
+/// Parsed queries shared by all the sessions, keyed by their normalized text.
+/// The least recently used query is evicted once the capacity is reached and the whole cache
+/// is invalidated when the schema changes.
+pub struct QueryCache {
+    capacity: usize,
+    entries: HashMap<String, CacheEntry>,
+    lru: BTreeMap<u64, String>,
+    tick: u64,
+    schema_version: u64,
+    hits: u64,
+    misses: u64,
+    evictions: u64,
+}
+
+impl QueryCache {
+    pub fn new(capacity: usize) -> Self {
+        QueryCache{capacity: capacity, entries: HashMap::new(), lru: BTreeMap::new(), tick: 0, schema_version: 0, hits: 0, misses: 0, evictions: 0}
+    }
+
+    fn next_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+
+    pub fn get(&mut self, query: &str) -> Option<Arc<PreparedQuery>> {
+        let tick = self.next_tick();
+        match self.entries.get_mut(&normalize_query(query)) {
+            Some(entry) => {
+                self.hits += 1;
+                metrics::increment(Counter::QueryCacheHits);
+                let key = self.lru.remove(&entry.last_used)?;
+                self.lru.insert(tick, key);
+                entry.last_used = tick;
+                Some(entry.statement.clone())
+            },
+            None => {
+                self.misses += 1;
+                metrics::increment(Counter::QueryCacheMisses);
+                None
+            }
+        }
+    }
+
+    pub fn insert(&mut self, query: &str, statement: Arc<PreparedQuery>) {
+        if self.capacity == 0 {
+            return;
+        }
+        let key = normalize_query(query);
+        if let Some(entry) = self.entries.remove(&key) {
+            self.lru.remove(&entry.last_used);
+        }
+        while self.entries.len() >= self.capacity {
+            self.evict_lru();
+        }
+        let tick = self.next_tick();
+        self.lru.insert(tick, key.clone());
+        self.entries.insert(key, CacheEntry{statement: statement, last_used: tick});
+    }
+
+    fn evict_lru(&mut self) {
+        if let Some((_, key)) = self.lru.pop_first() {
+            self.entries.remove(&key);
+            self.evictions += 1;
+        }
+    }
+
+    /// Drops all the cached queries, to be called once indexes or constraints are created or dropped.
+    pub fn invalidate(&mut self) {
+        self.entries.clear();
+        self.lru.clear();
+        self.schema_version += 1;
+    }
+
+    pub fn get_schema_version(&self) -> u64 {
+        self.schema_version
+    }
+
+    pub fn get_statistics(&self) -> QueryCacheStatistics {
+        QueryCacheStatistics{entries: self.entries.len(), hits: self.hits, misses: self.misses, evictions: self.evictions, invalidations: self.schema_version}
+    }
+}
+
+/// Collapses the whitespaces and drops the comments outside of string literals and escaped identifiers
+/// so that queries differing only by their layout share the same cache entry.
+pub fn normalize_query(query: &str) -> String {
+    let mut normalized = String::with_capacity(query.len());
+    let mut quote = None;
+    let mut escaped = false;
+    let mut pending_space = false;
+    let trimmed = query.trim();
+    let mut chars = trimmed.char_indices().peekable();
+    while let Some((position, c)) = chars.next() {
+        match quote {
+            Some(q) => {
+                if escaped {
+                    escaped = false;
+                } else if c == '\\' && q != '`' {
+                    escaped = true;
+                } else if c == q {
+                    quote = None;
+                }
+            },
+            None if c.is_whitespace() => {
+                pending_space = true;
+                continue;
+            },
+            None if c == '/' && chars.peek().is_some_and(|(_, next)| *next == '/') => {
+                while chars.next_if(|(_, next)| *next != '\n').is_some() {}
+                pending_space = true;
+                continue;
+            },
+            None if c == '/' && chars.peek().is_some_and(|(_, next)| *next == '*') => {
+                // an unterminated comment is kept for the parser to report it
+                match trimmed[position + 2..].find("*/") {
+                    Some(end) => {
+                        while chars.next_if(|(next_position, _)| *next_position < position + end + 4).is_some() {}
+                        pending_space = true;
+                        continue;
+                    },
+                    None => {
+                        if pending_space {
+                            normalized.push(' ');
+                        }
+                        normalized.push_str(&trimmed[position..]);
+                        return normalized;
+                    },
+                }
+            },
+            None => {
+                if c == '\'' || c == '"' || c == '`' {
+                    quote = Some(c);
+                }
+            }
+        }
+        if pending_space {
+            normalized.push(' ');
+            pending_space = false;
+        }
+        normalized.push(c);
+    }
+    normalized
+}
+
+fn lock_query_cache() -> MutexGuard<'static, QueryCache> {
+    static QUERY_CACHE: OnceLock<Mutex<QueryCache>> = OnceLock::new();
+    let cache = QUERY_CACHE.get_or_init(|| Mutex::new(QueryCache::new(DEFAULT_QUERY_CACHE_CAPACITY)));
+    cache.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Returns the shared parsed query along with the schema version it is valid for.
+pub fn prepare_cached_query(query: &str) -> ParserResult<(Arc<PreparedQuery>, u64)> {
+    let mut cache = lock_query_cache();
+    if let Some(statement) = cache.get(query) {
+        return Ok((statement, cache.get_schema_version()));
+    }
+    drop(cache);
+    let statement = Arc::new(prepare_cypher_query(query)?);
+    let mut cache = lock_query_cache();
+    cache.insert(query, statement.clone());
+    Ok((statement, cache.get_schema_version()))
+}
+
+pub fn get_schema_version() -> u64 {
+    lock_query_cache().get_schema_version()
+}
+
+pub fn invalidate_query_cache() {
+    lock_query_cache().invalidate();
+}
+
+pub fn get_query_cache_statistics() -> QueryCacheStatistics {
+    lock_query_cache().get_statistics()
+}
+
+#[cfg(test)]
+mod test_query_cache {
+    use super::*;
+
+    fn prepare(query: &str) -> Arc<PreparedQuery> {
+        Arc::new(prepare_cypher_query(query).expect("query"))
+    }
+
+    #[test]
+    fn test_normalize_query() {
+        assert_eq!("MATCH (n:Person) RETURN n", normalize_query("  MATCH (n:Person)\n\t  RETURN n \n"));
+        assert_eq!("MATCH (n {name: 'a  b'}) RETURN \"c\\\"  d\"", normalize_query("MATCH  (n {name: 'a  b'})  RETURN \"c\\\"  d\""));
+        assert_eq!("MATCH (n:`a // b`) RETURN n", normalize_query("MATCH (n:`a // b`) // persons\n/* all of them */ RETURN n // done"));
+        assert_eq!("MATCH (n) RETURN n /* open", normalize_query("MATCH (n)\nRETURN n /* open"));
+    }
+
+    #[test]
+    fn test_lru_and_invalidation() {
+        let mut cache = QueryCache::new(2);
+        cache.insert("MATCH (n:A) RETURN n", prepare("MATCH (n:A) RETURN n"));
+        cache.insert("MATCH (n:B) RETURN n", prepare("MATCH (n:B) RETURN n"));
+        assert!(cache.get("MATCH  (n:A)\nRETURN n").is_some());
+        cache.insert("MATCH (n:C) RETURN n", prepare("MATCH (n:C) RETURN n"));
+        assert!(cache.get("MATCH (n:B) RETURN n").is_none());
+        assert!(cache.get("MATCH (n:A) RETURN n").is_some());
+        assert!(cache.get("MATCH (n:C) RETURN n").is_some());
+        assert_eq!(QueryCacheStatistics{entries: 2, hits: 3, misses: 1, evictions: 1, invalidations: 0}, cache.get_statistics());
+
+        cache.invalidate();
+        assert_eq!(1, cache.get_schema_version());
+        assert!(cache.get("MATCH (n:A) RETURN n").is_none());
+        assert_eq!(0, cache.get_statistics().entries);
+    }
+}
@@ -0,0 +1,152 @@
+// MIT License
+//
+// Copyright (c) 2022 Alexandre RICCIARDI
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use zawgl_cypher_query_model::model::Request;
+
+use crate::cypher::parser::error::ParserResult;
+use crate::cypher::query_engine::PreparedQuery;
+use crate::query_cache::{get_schema_version, prepare_cached_query};
+use crate::parameters::Parameters;
+
+pub const DEFAULT_MAX_PREPARED_STATEMENTS: usize = 256;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PreparedStatementStatistics {
+    pub statements: usize,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+/// State kept between the requests of a client connection: parameters sent once for the session
+/// and prepared statements keyed by their query text, taken from the shared query cache on their first execution.
+/// Prepared statements are dropped once the schema changes.
+pub struct CypherSession {
+    parameters: Parameters,
+    timeout: Option<Duration>,
+    statements: HashMap<String, Arc<PreparedQuery>>,
+    max_statements: usize,
+    schema_version: u64,
+    hits: u64,
+    misses: u64,
+}
+
+impl CypherSession {
+    pub fn new() -> Self {
+        Self::with_max_statements(DEFAULT_MAX_PREPARED_STATEMENTS)
+    }
+
+    /// Queries received once the session holds `max_statements` statements are parsed for each request.
+    pub fn with_max_statements(max_statements: usize) -> Self {
+        CypherSession{parameters: Parameters::new(), timeout: None, statements: HashMap::new(), max_statements: max_statements, schema_version: get_schema_version(), hits: 0, misses: 0}
+    }
+
+    pub fn set_parameters(&mut self, parameters: Parameters) {
+        self.parameters.extend(parameters);
+    }
+
+    pub fn clear_parameters(&mut self) {
+        self.parameters.clear();
+    }
+
+    /// Timeout of the session queries sent without their own one.
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.timeout = timeout;
+    }
+
+    pub fn get_timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    pub fn get_statistics(&self) -> PreparedStatementStatistics {
+        PreparedStatementStatistics{statements: self.statements.len(), hits: self.hits, misses: self.misses}
+    }
+
+    /// Builds the request of a query with the session parameters, overridden by the request ones.
+    pub fn build_request(&mut self, query: &str, params: Option<Parameters>) -> ParserResult<Request> {
+        let mut parameters = self.parameters.clone();
+        parameters.extend(params.unwrap_or_default());
+        let schema_version = get_schema_version();
+        if schema_version != self.schema_version {
+            self.statements.clear();
+            self.schema_version = schema_version;
+        }
+        if let Some(statement) = self.statements.get(query) {
+            self.hits += 1;
+            return statement.build_request(Some(parameters));
+        }
+        self.misses += 1;
+        let (statement, schema_version) = prepare_cached_query(query)?;
+        let request = statement.build_request(Some(parameters));
+        if self.statements.len() < self.max_statements && schema_version == self.schema_version {
+            self.statements.insert(String::from(query), statement);
+        }
+        request
+    }
+}
+
+#[cfg(test)]
+mod test_session {
+    use super::*;
+    use crate::parameters::ParameterValue;
+    use zawgl_core::model::PropertyValue;
+    use zawgl_cypher_query_model::model::Expression;
+
+    fn get_condition_value(request: &Request) -> Option<&PropertyValue> {
+        match request.steps[1].where_clause.as_ref()?.condition.as_ref()? {
+            Expression::BinaryOperation(_, _, second) => match second.as_ref() {
+                Expression::Value(value) => Some(value),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn make_parameters(name: &str, value: &str) -> Parameters {
+        let mut params = Parameters::new();
+        params.insert(String::from(name), ParameterValue::Value(PropertyValue::PString(String::from(value))));
+        params
+    }
+
+    #[test]
+    fn test_prepared_statements() {
+        let query = "MATCH (n:Person) WHERE n.name = $name RETURN n";
+        let mut session = CypherSession::with_max_statements(1);
+        session.set_parameters(make_parameters("name", "ada"));
+        let request = session.build_request(query, None).expect("request");
+        assert_eq!(Some(&PropertyValue::PString(String::from("ada"))), get_condition_value(&request));
+        let request = session.build_request(query, Some(make_parameters("name", "alan"))).expect("request");
+        assert_eq!(Some(&PropertyValue::PString(String::from("alan"))), get_condition_value(&request));
+        assert_eq!(PreparedStatementStatistics{statements: 1, hits: 1, misses: 1}, session.get_statistics());
+
+        assert!(session.build_request("MATCH (n:City) RETURN n", None).is_ok());
+        assert!(session.build_request("MATCH (n:City) RETURN n", None).is_ok());
+        assert!(session.build_request("MATCH (n:", None).is_err());
+        assert_eq!(PreparedStatementStatistics{statements: 1, hits: 1, misses: 4}, session.get_statistics());
+
+        session.clear_parameters();
+        assert!(session.build_request(query, None).is_err());
+    }
+}
@@ -0,0 +1,671 @@
+// MIT License
+//
+// Copyright (c) 2022 Alexandre RICCIARDI
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use std::convert::{TryFrom, TryInto};
+
+use serde_json::{json, Map, Value};
+
+use crate::handler::GremlinError;
+use crate::json_gremlin_request_builder::build_gremlin_request_from_json;
+use super::gremlin::*;
+
+pub const GRAPH_BINARY_MIME_TYPE: &str = "application/vnd.graphbinary-v1.0";
+
+const VERSION: u8 = 0x81;
+const VALUE_FLAG_NONE: u8 = 0x00;
+const VALUE_FLAG_NULL: u8 = 0x01;
+
+const INT: u8 = 0x01;
+const LONG: u8 = 0x02;
+const STRING: u8 = 0x03;
+const DATE: u8 = 0x04;
+const TIMESTAMP: u8 = 0x05;
+const CLASS: u8 = 0x06;
+const DOUBLE: u8 = 0x07;
+const FLOAT: u8 = 0x08;
+const LIST: u8 = 0x09;
+const MAP: u8 = 0x0a;
+const SET: u8 = 0x0b;
+const UUID: u8 = 0x0c;
+const EDGE: u8 = 0x0d;
+const PATH: u8 = 0x0e;
+const PROPERTY: u8 = 0x0f;
+const VERTEX: u8 = 0x11;
+const VERTEX_PROPERTY: u8 = 0x12;
+const BARRIER: u8 = 0x13;
+const BYTECODE: u8 = 0x15;
+const CARDINALITY: u8 = 0x16;
+const COLUMN: u8 = 0x17;
+const DIRECTION: u8 = 0x18;
+const OPERATOR: u8 = 0x19;
+const ORDER: u8 = 0x1a;
+const PICK: u8 = 0x1b;
+const POP: u8 = 0x1c;
+const P: u8 = 0x1e;
+const SCOPE: u8 = 0x1f;
+const T: u8 = 0x20;
+const TRAVERSER: u8 = 0x21;
+const BIG_DECIMAL: u8 = 0x22;
+const BIG_INTEGER: u8 = 0x23;
+const BYTE: u8 = 0x24;
+const SHORT: u8 = 0x26;
+const BOOLEAN: u8 = 0x27;
+const CHAR: u8 = 0x80;
+const UNSPECIFIED_NULL: u8 = 0xfe;
+/// Values nested deeper are rejected before they overflow the stack of the reader.
+const MAX_DEPTH: usize = 64;
+
+/// Decodes a GraphBinary request, the mime type prefix being already removed, into its GraphSON equivalent
+/// so that both serializations share the same request builder.
+pub fn build_gremlin_request_from_graph_binary(data: &[u8]) -> Result<GremlinRequest, GremlinError> {
+    let request = GraphBinaryReader::new(data).read_request().ok_or(GremlinError::RequestError)?;
+    build_gremlin_request_from_json(&request)
+}
+
+struct GraphBinaryReader<'a> {
+    data: &'a [u8],
+    position: usize,
+    depth: usize,
+}
+
+impl <'a> GraphBinaryReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        GraphBinaryReader{data: data, position: 0, depth: 0}
+    }
+
+    fn read_bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+        let end = self.position.checked_add(len)?;
+        let bytes = self.data.get(self.position..end)?;
+        self.position = end;
+        Some(bytes)
+    }
+
+    fn read_u8(&mut self) -> Option<u8> {
+        Some(self.read_bytes(1)?[0])
+    }
+
+    fn read_short(&mut self) -> Option<i16> {
+        Some(i16::from_be_bytes(self.read_bytes(2)?.try_into().ok()?))
+    }
+
+    fn read_int(&mut self) -> Option<i32> {
+        Some(i32::from_be_bytes(self.read_bytes(4)?.try_into().ok()?))
+    }
+
+    fn read_long(&mut self) -> Option<i64> {
+        Some(i64::from_be_bytes(self.read_bytes(8)?.try_into().ok()?))
+    }
+
+    fn read_length(&mut self) -> Option<usize> {
+        usize::try_from(self.read_int()?).ok()
+    }
+
+    fn read_string(&mut self) -> Option<String> {
+        let len = self.read_length()?;
+        String::from_utf8(self.read_bytes(len)?.to_vec()).ok()
+    }
+
+    fn read_uuid(&mut self) -> Option<String> {
+        Some(format_uuid(self.read_bytes(16)?))
+    }
+
+    fn read_big_integer(&mut self) -> Option<i128> {
+        let len = self.read_length()?;
+        let bytes = self.read_bytes(len)?;
+        if bytes.is_empty() || bytes.len() > 16 {
+            return None;
+        }
+        let sign = if bytes[0] & 0x80 != 0 { 0xff } else { 0x00 };
+        let mut be_bytes = [sign; 16];
+        be_bytes[16 - bytes.len()..].copy_from_slice(bytes);
+        Some(i128::from_be_bytes(be_bytes))
+    }
+
+    fn read_char(&mut self) -> Option<String> {
+        let first = self.read_u8()?;
+        let len = match first.leading_ones() {
+            0 => 1,
+            n @ 2..=4 => n as usize,
+            _ => return None,
+        };
+        let mut bytes = vec![first];
+        bytes.extend_from_slice(self.read_bytes(len - 1)?);
+        String::from_utf8(bytes).ok()
+    }
+
+    fn read_request(&mut self) -> Option<Value> {
+        if self.read_u8()? != VERSION {
+            return None;
+        }
+        let request_id = self.read_uuid()?;
+        let op = self.read_string()?;
+        let processor = self.read_string()?;
+        let args = self.read_map()?;
+        Some(json!({"requestId": request_id, "op": op, "processor": processor, "args": args}))
+    }
+
+    /// Reads a fully qualified value: its type code, its value flag then its value.
+    /// None once the value is nested in more than `MAX_DEPTH` collections, enums or bytecodes.
+    fn read_value(&mut self) -> Option<Value> {
+        if self.depth == MAX_DEPTH {
+            return None;
+        }
+        self.depth += 1;
+        let value = self.read_typed_value();
+        self.depth -= 1;
+        value
+    }
+
+    fn read_typed_value(&mut self) -> Option<Value> {
+        let type_code = self.read_u8()?;
+        let value_flag = self.read_u8()?;
+        if type_code == UNSPECIFIED_NULL || value_flag & VALUE_FLAG_NULL != 0 {
+            return Some(Value::Null);
+        }
+        match type_code {
+            INT => Some(json!({"@type": "g:Int32", "@value": self.read_int()?})),
+            LONG => Some(json!({"@type": "g:Int64", "@value": self.read_long()?})),
+            STRING => Some(json!(self.read_string()?)),
+            DATE => Some(json!({"@type": "g:Date", "@value": self.read_long()?})),
+            TIMESTAMP => Some(json!({"@type": "g:Timestamp", "@value": self.read_long()?})),
+            CLASS => Some(json!({"@type": "g:Class", "@value": self.read_string()?})),
+            DOUBLE => Some(GDouble(f64::from_bits(self.read_long()? as u64)).to_json()),
+            FLOAT => Some(GFloat(f32::from_bits(self.read_int()? as u32)).to_json()),
+            LIST => Some(json!({"@type": "g:List", "@value": self.read_values()?})),
+            SET => Some(json!({"@type": "g:Set", "@value": self.read_values()?})),
+            MAP => self.read_map(),
+            UUID => Some(json!({"@type": "g:UUID", "@value": self.read_uuid()?})),
+            VERTEX => {
+                let id = self.read_value()?;
+                let label = self.read_string()?;
+                self.read_value()?;
+                Some(json!({"@type": "g:Vertex", "@value": {"id": id, "label": label}}))
+            },
+            BYTECODE => self.read_bytecode(),
+            BARRIER => self.read_enum("g:Barrier"),
+            CARDINALITY => self.read_enum("g:Cardinality"),
+            COLUMN => self.read_enum("g:Column"),
+            DIRECTION => self.read_enum("g:Direction"),
+            OPERATOR => self.read_enum("g:Operator"),
+            ORDER => self.read_enum("g:Order"),
+            PICK => self.read_enum("g:Pick"),
+            POP => self.read_enum("g:Pop"),
+            SCOPE => self.read_enum("g:Scope"),
+            T => self.read_enum("g:T"),
+            P => self.read_predicate(),
+            BIG_DECIMAL => {
+                let scale = self.read_int()?;
+                let unscaled = self.read_big_integer()?;
+                Some(json!({"@type": "gx:BigDecimal", "@value": unscaled as f64 / 10f64.powi(scale)}))
+            },
+            BIG_INTEGER => Some(json!({"@type": "gx:BigInteger", "@value": self.read_big_integer()?.to_string()})),
+            BYTE => Some(json!({"@type": "gx:Byte", "@value": self.read_u8()? as i8})),
+            SHORT => Some(json!({"@type": "gx:Int16", "@value": self.read_short()?})),
+            BOOLEAN => Some(json!(self.read_u8()? != 0)),
+            CHAR => Some(json!({"@type": "gx:Char", "@value": self.read_char()?})),
+            _ => None,
+        }
+    }
+
+    fn read_values(&mut self) -> Option<Vec<Value>> {
+        let len = self.read_length()?;
+        let mut values = Vec::new();
+        for _ in 0..len {
+            values.push(self.read_value()?);
+        }
+        Some(values)
+    }
+
+    fn read_map(&mut self) -> Option<Value> {
+        let len = self.read_length()?;
+        let mut entries = Vec::new();
+        for _ in 0..len {
+            entries.push(self.read_value()?);
+            entries.push(self.read_value()?);
+        }
+        Some(json!({"@type": "g:Map", "@value": entries}))
+    }
+
+    fn read_enum(&mut self, gtype: &str) -> Option<Value> {
+        Some(json!({"@type": gtype, "@value": self.read_value()?}))
+    }
+
+    fn read_predicate(&mut self) -> Option<Value> {
+        let name = self.read_string()?;
+        let mut values = self.read_values()?;
+        let value = if values.len() == 1 && name != "within" && name != "without" {
+            values.remove(0)
+        } else {
+            json!({"@type": "g:List", "@value": values})
+        };
+        Some(json!({"@type": "g:P", "@value": {"predicate": name, "value": value}}))
+    }
+
+    fn read_instructions(&mut self) -> Option<Vec<Value>> {
+        let len = self.read_length()?;
+        let mut instructions = Vec::new();
+        for _ in 0..len {
+            let mut instruction = vec![json!(self.read_string()?)];
+            instruction.append(&mut self.read_values()?);
+            instructions.push(Value::Array(instruction));
+        }
+        Some(instructions)
+    }
+
+    fn read_bytecode(&mut self) -> Option<Value> {
+        let steps = self.read_instructions()?;
+        let sources = self.read_instructions()?;
+        let mut bytecode = Map::new();
+        if !steps.is_empty() {
+            bytecode.insert(String::from("step"), Value::Array(steps));
+        }
+        if !sources.is_empty() {
+            bytecode.insert(String::from("source"), Value::Array(sources));
+        }
+        Some(json!({"@type": "g:Bytecode", "@value": bytecode}))
+    }
+}
+
+fn format_uuid(bytes: &[u8]) -> String {
+    let hex = bytes.iter().map(|b| format!("{:02x}", b)).collect::<String>();
+    format!("{}-{}-{}-{}-{}", &hex[0..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..32])
+}
+
+fn parse_uuid(uuid: &str) -> Option<[u8; 16]> {
+    let hex = uuid.replace('-', "");
+    if hex.len() != 32 {
+        return None;
+    }
+    let mut bytes = [0u8; 16];
+    for (index, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(hex.get(index * 2..index * 2 + 2)?, 16).ok()?;
+    }
+    Some(bytes)
+}
+
+/// Serializes a response to GraphBinary, the result data being the list of traversers.
+pub fn build_graph_binary_gremlin_response(response: &GremlinResponse) -> Vec<u8> {
+    let mut writer = GraphBinaryWriter::new();
+    writer.buffer.push(VERSION);
+    match parse_uuid(&response.request_id) {
+        Some(uuid) => {
+            writer.buffer.push(VALUE_FLAG_NONE);
+            writer.buffer.extend_from_slice(&uuid);
+        },
+        None => writer.buffer.push(VALUE_FLAG_NULL),
+    }
+    writer.write_int(response.status.code);
+    writer.buffer.push(VALUE_FLAG_NONE);
+    writer.write_string(&response.status.message);
+    writer.write_string_map(&response.status.attributes);
+    writer.write_string_map(&response.result.meta);
+    writer.write_header(LIST);
+    writer.write_length(response.result.data.values.len());
+    for traverser in &response.result.data.values {
+        writer.write_header(TRAVERSER);
+        writer.write_long(traverser.bulk.0);
+        writer.write_item(&traverser.value);
+    }
+    writer.buffer
+}
+
+struct GraphBinaryWriter {
+    buffer: Vec<u8>,
+}
+
+impl GraphBinaryWriter {
+    fn new() -> Self {
+        GraphBinaryWriter{buffer: Vec::new()}
+    }
+
+    fn write_header(&mut self, type_code: u8) {
+        self.buffer.push(type_code);
+        self.buffer.push(VALUE_FLAG_NONE);
+    }
+
+    fn write_null(&mut self) {
+        self.buffer.push(UNSPECIFIED_NULL);
+        self.buffer.push(VALUE_FLAG_NULL);
+    }
+
+    fn write_int(&mut self, value: i32) {
+        self.buffer.extend_from_slice(&value.to_be_bytes());
+    }
+
+    fn write_long(&mut self, value: i64) {
+        self.buffer.extend_from_slice(&value.to_be_bytes());
+    }
+
+    fn write_length(&mut self, len: usize) {
+        self.write_int(len as i32);
+    }
+
+    fn write_string(&mut self, value: &str) {
+        self.write_length(value.len());
+        self.buffer.extend_from_slice(value.as_bytes());
+    }
+
+    fn write_string_map(&mut self, map: &GMap) {
+        self.write_length(map.map.len());
+        for (key, value) in &map.map {
+            self.write_header(STRING);
+            self.write_string(key);
+            self.write_header(STRING);
+            self.write_string(value);
+        }
+    }
+
+    fn write_item(&mut self, item: &GItem) {
+        match item {
+            GItem::Vertex(vertex) => self.write_vertex(vertex),
+            GItem::Edge(edge) => self.write_edge(edge),
+            GItem::Value(value) => self.write_value(value),
+            GItem::Map(map) => {
+                self.write_header(MAP);
+                self.write_length(map.map.len());
+                for (key, list) in &map.map {
+                    self.write_header(STRING);
+                    self.write_string(key);
+                    self.write_header(LIST);
+                    self.write_length(list.values.len());
+                    for value in &list.values {
+                        self.write_value(value);
+                    }
+                }
+            },
+            GItem::Path(path) => {
+                self.write_header(PATH);
+                self.write_header(LIST);
+                self.write_length(path.labels.len());
+                for labels in &path.labels {
+                    self.write_header(SET);
+                    self.write_length(labels.len());
+                    for label in labels {
+                        self.write_header(STRING);
+                        self.write_string(label);
+                    }
+                }
+                self.write_header(LIST);
+                self.write_length(path.objects.len());
+                for object in &path.objects {
+                    self.write_item(object);
+                }
+            },
+            GItem::List(items) => {
+                self.write_header(LIST);
+                self.write_length(items.len());
+                for item in items {
+                    self.write_item(item);
+                }
+            },
+            GItem::Bindings(bindings) => {
+                self.write_header(MAP);
+                self.write_length(bindings.map.len());
+                for (alias, item) in &bindings.map {
+                    self.write_header(STRING);
+                    self.write_string(alias);
+                    self.write_item(item);
+                }
+            },
+        }
+    }
+
+    fn write_vertex(&mut self, vertex: &GVertex) {
+        self.write_header(VERTEX);
+        self.write_value(&vertex.id);
+        self.write_string(&vertex.label);
+        self.write_header(LIST);
+        self.write_length(vertex.properties.properties.iter().map(|p| p.values.len()).sum());
+        for property in &vertex.properties.properties {
+            for (id, value) in &property.values {
+                self.write_header(VERTEX_PROPERTY);
+                self.write_header(LONG);
+                self.write_long(id.0);
+                self.write_string(&property.name);
+                self.write_value(value);
+                self.write_null();
+                self.write_null();
+            }
+        }
+    }
+
+    fn write_edge(&mut self, edge: &GEdge) {
+        self.write_header(EDGE);
+        self.write_header(LONG);
+        self.write_long(edge.id.0);
+        self.write_string(&edge.label);
+        self.write_header(LONG);
+        self.write_long(edge.in_v.0);
+        self.write_string(&edge.in_v_label);
+        self.write_header(LONG);
+        self.write_long(edge.out_v.0);
+        self.write_string(&edge.out_v_abel);
+        self.write_null();
+        self.write_header(LIST);
+        self.write_length(edge.properties.properties.iter().map(|p| p.values.len()).sum());
+        for property in &edge.properties.properties {
+            for (_, value) in &property.values {
+                self.write_header(PROPERTY);
+                self.write_string(&property.name);
+                self.write_value(value);
+                self.write_null();
+            }
+        }
+    }
+
+    fn write_values(&mut self, type_code: u8, values: &[GValue]) {
+        self.write_header(type_code);
+        self.write_length(values.len());
+        for value in values {
+            self.write_value(value);
+        }
+    }
+
+    fn write_value(&mut self, value: &GValue) {
+        match value {
+            GValue::Integer(GInteger::I32(v)) => {
+                self.write_header(INT);
+                self.write_int(v.0);
+            },
+            GValue::Integer(GInteger::I64(v)) => {
+                self.write_header(LONG);
+                self.write_long(v.0);
+            },
+            GValue::Double(v) => {
+                self.write_header(DOUBLE);
+                self.buffer.extend_from_slice(&v.0.to_be_bytes());
+            },
+            GValue::Float(v) => {
+                self.write_header(FLOAT);
+                self.buffer.extend_from_slice(&v.0.to_be_bytes());
+            },
+            GValue::String(v) => {
+                self.write_header(STRING);
+                self.write_string(v);
+            },
+            GValue::Bool(v) => {
+                self.write_header(BOOLEAN);
+                self.buffer.push(*v as u8);
+            },
+            GValue::Date(v) => {
+                self.write_header(DATE);
+                self.write_long(v.0);
+            },
+            GValue::Timestamp(v) => {
+                self.write_header(TIMESTAMP);
+                self.write_long(v.0);
+            },
+            GValue::Uuid(v) => match parse_uuid(&v.0) {
+                Some(uuid) => {
+                    self.write_header(UUID);
+                    self.buffer.extend_from_slice(&uuid);
+                },
+                None => {
+                    self.write_header(STRING);
+                    self.write_string(&v.0);
+                }
+            },
+            GValue::List(values) => self.write_values(LIST, values),
+            GValue::Set(values) => self.write_values(SET, values),
+            GValue::Map(entries) => {
+                self.write_header(MAP);
+                self.write_length(entries.len());
+                for (key, value) in entries {
+                    self.write_header(STRING);
+                    self.write_string(key);
+                    self.write_value(value);
+                }
+            },
+            GValue::Null => self.write_null(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_graph_binary {
+    use super::*;
+
+    const REQUEST_ID: &str = "4a4d1f3c-8b8e-4f38-a7e4-1b3c5f0a2e11";
+
+    fn write_instruction(writer: &mut GraphBinaryWriter, name: &str, values: &[GValue]) {
+        writer.write_string(name);
+        writer.write_length(values.len());
+        for value in values {
+            writer.write_value(value);
+        }
+    }
+
+    fn build_request(processor: &str, session: Option<&str>) -> Vec<u8> {
+        let mut writer = GraphBinaryWriter::new();
+        writer.buffer.push(VERSION);
+        writer.buffer.extend_from_slice(&parse_uuid(REQUEST_ID).unwrap());
+        writer.write_string("bytecode");
+        writer.write_string(processor);
+        writer.write_length(if session.is_some() { 4 } else { 1 });
+        writer.write_value(&GValue::String(String::from("gremlin")));
+        writer.write_header(BYTECODE);
+        writer.write_length(4);
+        write_instruction(&mut writer, "V", &[]);
+        writer.write_string("has");
+        writer.write_length(2);
+        writer.write_value(&GValue::String(String::from("age")));
+        writer.write_header(P);
+        write_instruction(&mut writer, "within", &[GValue::Integer(GInteger::I32(GInt32(29))), GValue::Integer(GInteger::I64(GInt64(30)))]);
+        write_instruction(&mut writer, "order", &[]);
+        writer.write_string("by");
+        writer.write_length(1);
+        writer.write_header(ORDER);
+        writer.write_value(&GValue::String(String::from("desc")));
+        writer.write_length(0);
+        if let Some(session) = session {
+            writer.write_value(&GValue::String(String::from("session")));
+            writer.write_value(&GValue::Uuid(GUuid(String::from(session))));
+            writer.write_value(&GValue::String(String::from("manageTransaction")));
+            writer.write_value(&GValue::Bool(false));
+            writer.write_value(&GValue::String(String::from("maintainStateAfterException")));
+            writer.write_value(&GValue::Bool(false));
+        }
+        writer.buffer
+    }
+
+    #[test]
+    fn test_read_request() {
+        let request = build_gremlin_request_from_graph_binary(&build_request("traversal", None)).expect("request");
+        assert_eq!(REQUEST_ID, request.request_id);
+        assert!(request.session.is_none());
+        let steps = request.data.expect("data").steps;
+        assert_eq!(3, steps.len());
+        match &steps[1] {
+            GStep::Has(name, GPredicate::Within(values)) => {
+                assert_eq!("age", name);
+                assert_eq!(vec![GValue::Integer(GInteger::I32(GInt32(29))), GValue::Integer(GInteger::I64(GInt64(30)))], values.values);
+            },
+            _ => panic!("has step expected"),
+        }
+        assert!(matches!(&steps[2], GStep::Order(order_by) if order_by[0].order == GOrder::Desc));
+
+        let session_id = "b6e1d5a0-3f8e-4c1b-9e7a-2d4f6a8c0e12";
+        let request = build_gremlin_request_from_graph_binary(&build_request("session", Some(session_id))).expect("request");
+        assert_eq!(session_id, request.session.expect("session").session_id);
+
+        let request = build_request("traversal", None);
+        assert!(build_gremlin_request_from_graph_binary(&request[..request.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_nesting_depth() {
+        let build_nested_request = |depth: usize| {
+            let mut writer = GraphBinaryWriter::new();
+            writer.buffer.push(VERSION);
+            writer.buffer.extend_from_slice(&parse_uuid(REQUEST_ID).unwrap());
+            writer.write_string("eval");
+            writer.write_string("");
+            writer.write_length(1);
+            writer.write_value(&GValue::String(String::from("bindings")));
+            for _ in 0..depth {
+                writer.write_header(LIST);
+                writer.write_length(1);
+            }
+            writer.write_null();
+            writer.buffer
+        };
+        assert!(GraphBinaryReader::new(&build_nested_request(MAX_DEPTH - 1)).read_request().is_some());
+        assert!(GraphBinaryReader::new(&build_nested_request(MAX_DEPTH)).read_request().is_none());
+        assert!(build_gremlin_request_from_graph_binary(&build_nested_request(1_000_000)).is_err());
+    }
+
+    #[test]
+    fn test_write_response() {
+        let properties = GProperties{properties: vec![GProperty{name: String::from("name"), values: vec![(GInt64(3), GValue::String(String::from("marko")))]}]};
+        let vertex = GVertex{id: GValue::Integer(GInteger::I64(GInt64(1))), label: String::from("person"), properties: properties};
+        let mut result = GResult::new();
+        result.data.values.push(GTraverser{bulk: GInt64(1), value: GItem::Vertex(vertex)});
+        let response = GremlinResponse{request_id: String::from(REQUEST_ID), status: GStatus{message: String::new(), code: 200, attributes: GMap::new()}, result: result};
+        let data = build_graph_binary_gremlin_response(&response);
+
+        let mut reader = GraphBinaryReader::new(&data);
+        assert_eq!(Some(VERSION), reader.read_u8());
+        assert_eq!(Some(VALUE_FLAG_NONE), reader.read_u8());
+        assert_eq!(Some(String::from(REQUEST_ID)), reader.read_uuid());
+        assert_eq!(Some(200), reader.read_int());
+        assert_eq!(Some(VALUE_FLAG_NONE), reader.read_u8());
+        assert_eq!(Some(String::new()), reader.read_string());
+        assert_eq!(Some(0), reader.read_length());
+        assert_eq!(Some(0), reader.read_length());
+        assert_eq!(Some((LIST, VALUE_FLAG_NONE)), reader.read_u8().zip(reader.read_u8()));
+        assert_eq!(Some(1), reader.read_length());
+        assert_eq!(Some((TRAVERSER, VALUE_FLAG_NONE)), reader.read_u8().zip(reader.read_u8()));
+        assert_eq!(Some(1), reader.read_long());
+        assert_eq!(Some((VERTEX, VALUE_FLAG_NONE)), reader.read_u8().zip(reader.read_u8()));
+        assert_eq!(Some(json!({"@type": "g:Int64", "@value": 1})), reader.read_value());
+        assert_eq!(Some(String::from("person")), reader.read_string());
+        assert_eq!(Some((LIST, VALUE_FLAG_NONE)), reader.read_u8().zip(reader.read_u8()));
+        assert_eq!(Some(1), reader.read_length());
+        assert_eq!(Some((VERTEX_PROPERTY, VALUE_FLAG_NONE)), reader.read_u8().zip(reader.read_u8()));
+        assert_eq!(Some(json!({"@type": "g:Int64", "@value": 3})), reader.read_value());
+        assert_eq!(Some(String::from("name")), reader.read_string());
+        assert_eq!(Some(json!("marko")), reader.read_value());
+        assert_eq!(Some(Value::Null), reader.read_value());
+        assert_eq!(Some(Value::Null), reader.read_value());
+        assert_eq!(data.len(), reader.position);
+    }
+}
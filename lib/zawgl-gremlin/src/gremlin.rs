@@ -21,34 +21,90 @@
 
 use std::collections::HashMap;
 use std::convert::TryFrom;
+use std::time::Duration;
 use serde_json::json;
+use zawgl_tx_handler::tx_context::IsolationLevel;
 
 pub trait ToJson {
     fn to_json(&self) -> serde_json::Value;
 }
 
+impl ToJson for String {
+    fn to_json(&self) -> serde_json::Value {
+        json!(self)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum GStep {
     AddV(String),
     V(Option<GValueOrVertex>),
     InV,
+    OutV,
+    OtherV,
     Has(String, GPredicate),
+    HasLabel(Vec<String>),
+    HasId(Vec<GValue>),
     AddE(String),
     E(Option<GValue>),
     OutE(Vec<String>),
+    InE(Vec<String>),
+    BothE(Vec<String>),
     As(String),
     From(GValueOrVertex),
     To(GValueOrVertex),
     Match(Vec<Vec<GStep>>),
-    SetProperty(String, GValue),
+    SetProperty(String, GValue, GCardinality),
     SetDynProperty(String, Vec<GStep>),
+    Values(Vec<String>),
+    ValueMap(Vec<String>),
+    Order(Vec<GOrderBy>),
+    Range(u64, Option<u64>),
+    Limit(u64),
+    Repeat(GRepeat),
+    Select(Vec<String>),
+    Where(Vec<GStep>),
+    Path,
+    Count,
+    Fold,
+    Unfold,
+    Drop,
     Commit,
     Empty,
 }
 
+/// Sub traversal repeated a number of times or until the until traversal is satisfied.
+#[derive(Debug, Clone)]
+pub struct GRepeat {
+    pub steps: Vec<GStep>,
+    pub times: Option<u64>,
+    pub until: Option<Vec<GStep>>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum GCardinality {
+    Single,
+    List,
+    Set,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum GOrder {
+    Asc,
+    Desc,
+    Shuffle,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct GOrderBy {
+    pub key: Option<String>,
+    pub order: GOrder,
+}
+
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum GSource {
     TxCommit,
+    TxRollback,
     Empty,
 }
 
@@ -61,8 +117,16 @@ pub enum GBytecode {
 pub enum GValue {
     Integer(GInteger),
     Double(GDouble),
+    Float(GFloat),
     String(String),
     Bool(bool),
+    Date(GDate),
+    Timestamp(GTimestamp),
+    Uuid(GUuid),
+    List(Vec<GValue>),
+    Set(Vec<GValue>),
+    Map(Vec<(String, GValue)>),
+    Null,
 }
 
 impl GValue {
@@ -89,6 +153,35 @@ impl ToJson for GValue {
             GValue::Double(v) => {
                 v.to_json()
             }
+            GValue::Float(v) => {
+                v.to_json()
+            }
+            GValue::Date(v) => {
+                v.to_json()
+            }
+            GValue::Timestamp(v) => {
+                v.to_json()
+            }
+            GValue::Uuid(v) => {
+                v.to_json()
+            }
+            GValue::List(values) => {
+                json!({"@type": "g:List", "@value": values.iter().map(|v| v.to_json()).collect::<Vec<serde_json::Value>>()})
+            }
+            GValue::Set(values) => {
+                json!({"@type": "g:Set", "@value": values.iter().map(|v| v.to_json()).collect::<Vec<serde_json::Value>>()})
+            }
+            GValue::Map(entries) => {
+                let mut items = Vec::new();
+                for (k, v) in entries {
+                    items.push(json!(k));
+                    items.push(v.to_json());
+                }
+                json!({"@type": "g:Map", "@value": items})
+            }
+            GValue::Null => {
+                serde_json::Value::Null
+            }
         }
     }
 }
@@ -195,6 +288,69 @@ impl ToJson for GDouble {
     fn to_json(&self) -> serde_json::Value {
         json!({
             "@type": "g:Double",
+            "@value": floating_point_to_json(self.0),
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct GFloat(pub f32);
+
+impl ToJson for GFloat {
+    fn to_json(&self) -> serde_json::Value {
+        json!({
+            "@type": "g:Float",
+            "@value": floating_point_to_json(self.0 as f64),
+        })
+    }
+}
+
+/// GraphSON writes the non finite floating point values as strings.
+fn floating_point_to_json(value: f64) -> serde_json::Value {
+    if value.is_nan() {
+        json!("NaN")
+    } else if value == f64::INFINITY {
+        json!("Infinity")
+    } else if value == f64::NEG_INFINITY {
+        json!("-Infinity")
+    } else {
+        json!(value)
+    }
+}
+
+/// Milliseconds since the unix epoch.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct GDate(pub i64);
+
+impl ToJson for GDate {
+    fn to_json(&self) -> serde_json::Value {
+        json!({
+            "@type": "g:Date",
+            "@value": self.0,
+        })
+    }
+}
+
+/// Milliseconds since the unix epoch.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct GTimestamp(pub i64);
+
+impl ToJson for GTimestamp {
+    fn to_json(&self) -> serde_json::Value {
+        json!({
+            "@type": "g:Timestamp",
+            "@value": self.0,
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct GUuid(pub String);
+
+impl ToJson for GUuid {
+    fn to_json(&self) -> serde_json::Value {
+        json!({
+            "@type": "g:UUID",
             "@value": self.0,
         })
     }
@@ -248,6 +404,7 @@ impl <T: ToJson> ToJson for GList<T> {
     }
 }
 
+#[derive(Clone)]
 pub struct GEdge {
     pub id: GInt64,
     pub label: String,
@@ -285,6 +442,7 @@ pub struct GremlinRequest {
     pub request_id: String,
     pub data: Option<GremlinRequestData>,
     pub session: Option<GremlinSession>,
+    pub evaluation_timeout: Option<Duration>,
 }
 
 pub struct GremlinRequestData {
@@ -296,6 +454,9 @@ pub struct GremlinSession {
     pub manage_transaction: bool,
     pub maintain_state_after_exception: bool,
     pub commit: bool, 
+    pub rollback: bool,
+    pub read_only: bool,
+    pub isolation: IsolationLevel,
 }
 
 pub struct GremlinResponse {
@@ -330,6 +491,7 @@ impl GStatus {
     }
 }
 
+#[derive(Clone)]
 pub struct GTraverser {
     pub bulk: GInt64,
     pub value: GItem,
@@ -354,9 +516,36 @@ pub struct GVertex {
     pub properties: GProperties,
 }
 
+#[derive(Clone)]
 pub enum GItem {
     Vertex(GVertex),
     Edge(GEdge),
+    Value(GValue),
+    Map(GMap<GList<GValue>>),
+    Path(GPath),
+    List(Vec<GItem>),
+    Bindings(GMap<GItem>),
+}
+
+/// Vertices and edges of a traversed path, each object having its own set of step labels.
+#[derive(Clone)]
+pub struct GPath {
+    pub labels: Vec<Vec<String>>,
+    pub objects: Vec<GItem>,
+}
+
+impl ToJson for GPath {
+    fn to_json(&self) -> serde_json::Value {
+        let labels: Vec<serde_json::Value> = self.labels.iter().map(|labels| json!({"@type": "g:Set", "@value": labels})).collect();
+        let objects: Vec<serde_json::Value> = self.objects.iter().map(|object| object.to_json()).collect();
+        json!({
+            "@type": "g:Path",
+            "@value": {
+                "labels": {"@type": "g:List", "@value": labels},
+                "objects": {"@type": "g:List", "@value": objects},
+            }
+        })
+    }
 }
 
 
@@ -388,6 +577,21 @@ impl ToJson for GItem {
             GItem::Edge(e) => {
                 e.to_json()
             }
+            GItem::Value(v) => {
+                v.to_json()
+            }
+            GItem::Map(m) => {
+                m.to_json()
+            }
+            GItem::Path(p) => {
+                p.to_json()
+            }
+            GItem::List(items) => {
+                json!({"@type": "g:List", "@value": items.iter().map(|item| item.to_json()).collect::<Vec<serde_json::Value>>()})
+            }
+            GItem::Bindings(bindings) => {
+                bindings.to_json()
+            }
         }
     }
 }
@@ -405,11 +609,12 @@ impl ToJson for GVertex {
     }
 }
 
-pub struct GMap {
-    pub map: HashMap<String, String>,
+#[derive(Clone)]
+pub struct GMap<V: ToJson = String> {
+    pub map: HashMap<String, V>,
 }
 
-impl GMap {
+impl <V: ToJson> GMap<V> {
     pub fn new() -> Self {
         GMap{map: HashMap::new()}
     }
@@ -417,8 +622,8 @@ impl GMap {
     fn to_json(&self) -> serde_json::Value {
         let mut res = Vec::new();
         for e in &self.map {
-            res.push(e.0);
-            res.push(e.1);
+            res.push(json!(e.0));
+            res.push(e.1.to_json());
         }
         json!({
             "@type": "g:Map",
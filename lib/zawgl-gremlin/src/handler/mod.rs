@@ -19,9 +19,15 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
+use std::time::Instant;
+use thiserror::Error;
 use super::gremlin::*;
+use zawgl_core::graph_engine::query_context::{QueryAbort, QueryContext};
+use zawgl_cypher_query_model::ExecutionMode;
 use zawgl_tx_handler::DatabaseError;
-use zawgl_tx_handler::handle_graph_request;
+use zawgl_tx_handler::handle_graph_query;
+use zawgl_tx_handler::query_log::{QueryLogEntry, log_query};
+use zawgl_tx_handler::request_handler::make_query_steps;
 use zawgl_tx_handler::request_handler::RequestHandler;
 use zawgl_tx_handler::tx_context::TxContext;
 use zawgl_tx_handler::tx_handler::TxHandler;
@@ -35,7 +41,7 @@ mod utils;
 
 fn skip_step(prev_step: &GStep, curr_step: &GStep) -> GStep {
     match curr_step {
-        GStep::Has(_, _) => prev_step.clone(),
+        GStep::Has(_, _) | GStep::HasLabel(_) | GStep::HasId(_) | GStep::Where(_) | GStep::SetProperty(_, _, _) => prev_step.clone(),
         _ => curr_step.clone(),
     }
 }
@@ -59,26 +65,259 @@ fn iterate_gremlin_steps(steps: &Vec<GStep>, mut gremlin_state: GremlinStateMach
     Ok(gremlin_state)
 }
 
+/// Number of repetitions tried by a repeat step with an until predicate and no times bound.
+pub const MAX_REPEAT_DEPTH: u64 = 10;
+
+/// Linear traversals equivalent to the steps. A repeat step bounded by times is unrolled in place, a repeat step
+/// with an until traversal gives one traversal per number of repetitions, up to its times bound, ending with the
+/// until steps. The vertices reached before the last repetition are not checked against the until steps.
+fn unroll_repeat_steps(steps: &[GStep]) -> Result<Vec<Vec<GStep>>, GremlinStateError> {
+    let mut traversals = vec![Vec::new()];
+    for step in steps {
+        match step {
+            GStep::Repeat(repeat) => {
+                if repeat.steps.iter().any(|s| matches!(s, GStep::Repeat(_))) {
+                    return Err(GremlinStateError::Invalid(step.clone()));
+                }
+                let depths = match (&repeat.until, repeat.times) {
+                    (None, Some(times)) => times..=times,
+                    (Some(_), times) => 1..=times.unwrap_or(MAX_REPEAT_DEPTH),
+                    (None, None) => return Err(GremlinStateError::Invalid(step.clone())),
+                };
+                let mut unrolled = Vec::new();
+                for traversal in &traversals {
+                    for depth in depths.clone() {
+                        let mut alternative = traversal.clone();
+                        for _ in 0..depth {
+                            alternative.extend(repeat.steps.iter().cloned());
+                        }
+                        if let Some(until) = &repeat.until {
+                            alternative.extend(until.iter().cloned());
+                        }
+                        unrolled.push(alternative);
+                    }
+                }
+                traversals = unrolled;
+            }
+            _ => {
+                for traversal in &mut traversals {
+                    traversal.push(step.clone());
+                }
+            }
+        }
+    }
+    Ok(traversals)
+}
+
+/// Runs the state machine over each unrolled traversal, their patterns being matched as alternatives.
+fn build_gremlin_state(steps: &[GStep]) -> Result<GremlinStateMachine, GremlinStateError> {
+    let mut traversals = unroll_repeat_steps(steps)?.into_iter();
+    let mut gremlin_state = iterate_gremlin_steps(&traversals.next().unwrap_or_default(), GremlinStateMachine::new())?;
+    for traversal in traversals {
+        let alternative = iterate_gremlin_steps(&traversal, GremlinStateMachine::new())?;
+        gremlin_state.context.patterns.extend(alternative.context.patterns);
+    }
+    Ok(gremlin_state)
+}
+
 fn make_tx_context(session: &GremlinSession) -> TxContext {
-    TxContext { session_id: session.session_id.clone(), commit: session.commit }
+    TxContext { session_id: session.session_id.clone(), commit: session.commit, rollback: session.rollback, read_only: session.read_only, isolation: session.isolation }
 }
 
+/// Gremlin server status of the requests aborted once their evaluation timeout elapsed.
+pub const SERVER_ERROR_STATUS: i32 = 500;
+pub const SERVER_TIMEOUT_STATUS: i32 = 598;
+
 pub fn handle_gremlin_request<'a>(tx_handler: TxHandler, graph_request_handler: RequestHandler<'a>, gremlin: &GremlinRequest) -> Result<GremlinResponse, GremlinError> {
+    handle_gremlin_query_request(tx_handler, graph_request_handler, gremlin, &QueryContext::new())
+}
+
+/// Handles the request in the query context, bounded by the evaluation timeout of the request. A timed out
+/// request gets a server timeout response.
+pub fn handle_gremlin_query_request<'a>(tx_handler: TxHandler, graph_request_handler: RequestHandler<'a>, gremlin: &GremlinRequest, query_context: &QueryContext) -> Result<GremlinResponse, GremlinError> {
+    let start = Instant::now();
+    let query_context = match gremlin.evaluation_timeout {
+        Some(timeout) => query_context.clone().with_timeout(timeout),
+        None => query_context.clone(),
+    };
+    let res = run_gremlin_request(tx_handler, graph_request_handler.clone(), gremlin, &query_context);
+    if let Some(data) = &gremlin.data {
+        let query_log = graph_request_handler.read().unwrap().get_query_log();
+        let query = format!("{:?}", data.steps);
+        log_query(&query_log, &QueryLogEntry{language: "gremlin", query: &query, parameters: None, tx_id: gremlin.session.as_ref().map(|s| s.session_id.as_str()),
+            elapsed: start.elapsed(), results: res.as_ref().ok().map(|(count, _)| *count)});
+    }
+    match res {
+        Err(GremlinError::TxError(DatabaseError::QueryAborted(QueryAbort::Timeout))) => Ok(build_error_response(&gremlin.request_id, SERVER_TIMEOUT_STATUS, "the evaluation exceeded its timeout")),
+        Err(GremlinError::TxError(err)) => Ok(build_error_response(&gremlin.request_id, SERVER_ERROR_STATUS, &err.to_string())),
+        res => res.map(|(_, response)| response),
+    }
+}
+
+fn build_error_response(request_id: &str, code: i32, message: &str) -> GremlinResponse {
+    let status = GStatus{message: String::from(message), code, attributes: GMap::new()};
+    GremlinResponse{request_id: String::from(request_id), status, result: GResult::new()}
+}
+
+fn run_gremlin_request<'a>(tx_handler: TxHandler, graph_request_handler: RequestHandler<'a>, gremlin: &GremlinRequest, query_context: &QueryContext) -> Result<(usize, GremlinResponse), GremlinError> {
     let mut gremlin_state = GremlinStateMachine::new();
     if let Some(data) = &gremlin.data {
-        gremlin_state = iterate_gremlin_steps(&data.steps, gremlin_state).or_else(|err| Err(GremlinError::StateError(err)))?;
+        gremlin_state = build_gremlin_state(&data.steps).map_err(GremlinError::StateError)?;
     }    
     let ctx = gremlin_state.context;
     let tx_context = gremlin.session.as_ref().map(|s| make_tx_context(s));
-    let matched_graphs = handle_graph_request(tx_handler.clone(), graph_request_handler.clone(), &vec![], tx_context).map_err(|err| GremlinError::TxError(err))?;
-    convert_graph_to_gremlin_response(&matched_graphs, &gremlin.request_id)
+    let matched_graphs = handle_graph_query(tx_handler.clone(), graph_request_handler.clone(), &make_query_steps(&ctx.patterns), tx_context, None, ExecutionMode::Run, query_context)
+        .map_err(|err| GremlinError::TxError(err))?.graphs;
+    convert_graph_to_gremlin_response(&matched_graphs, &gremlin.request_id, &ctx.traverser_steps).map(|response| (matched_graphs.len(), response))
 }
 
-#[derive(Debug)]
+#[derive(Debug, Error)]
 pub enum GremlinError {
+    #[error("invalid gremlin request")]
     RequestError,
+    #[error("cannot build the gremlin response")]
     ResponseError,
+    #[error(transparent)]
     StateError(GremlinStateError),
+    #[error(transparent)]
     TxError(DatabaseError)
 }
-  
\ No newline at end of file
+
+#[cfg(test)]
+mod test_gremlin_handler {
+    use super::*;
+    use zawgl_core::graph::NodeIndex;
+    use zawgl_core::model::{Status, get_element_uuid};
+
+    fn vertex(id: i64) -> GStep {
+        GStep::V(Some(GValueOrVertex::Value(GValue::Integer(GInteger::I64(GInt64(id))))))
+    }
+
+    #[test]
+    fn test_drop_vertex() {
+        let state = iterate_gremlin_steps(&vec![vertex(1), GStep::Drop], GremlinStateMachine::new()).expect("state");
+        let pattern = &state.context.patterns[0];
+        assert_eq!(Status::Delete, *pattern.get_nodes()[0].get_status());
+    }
+
+    #[test]
+    fn test_vertex_uuid() {
+        let uuid = "41d2e28a-20a4-4ab0-b379-d810dede3786";
+        for id in [GValue::String(String::from(uuid)), GValue::Uuid(GUuid(String::from(uuid)))] {
+            let state = iterate_gremlin_steps(&vec![GStep::V(Some(GValueOrVertex::Value(id)))], GremlinStateMachine::new()).expect("state");
+            let node = &state.context.patterns[0].get_nodes()[0];
+            assert_eq!(None, node.get_id());
+            assert_eq!(Some(uuid), get_element_uuid(node.get_properties_ref()));
+        }
+        let state = iterate_gremlin_steps(&vec![GStep::V(Some(GValueOrVertex::Value(GValue::String(String::from("12")))))], GremlinStateMachine::new()).expect("state");
+        let node = &state.context.patterns[0].get_nodes()[0];
+        assert_eq!(Some(12), node.get_id());
+        assert!(node.get_properties_ref().is_empty());
+    }
+
+    #[test]
+    fn test_drop_both_edges() {
+        let steps = vec![vertex(1), GStep::BothE(vec![String::from("knows")]), GStep::Drop];
+        let state = iterate_gremlin_steps(&steps, GremlinStateMachine::new()).expect("state");
+        assert_eq!(2, state.context.patterns.len());
+        for pattern in &state.context.patterns {
+            assert_eq!(Status::Match, *pattern.get_nodes()[0].get_status());
+            assert_eq!(Status::Delete, *pattern.get_relationships()[0].get_status());
+        }
+        let query_steps = make_query_steps(&state.context.patterns);
+        assert_eq!(1, query_steps.len());
+        assert_eq!(2, query_steps[0].patterns.len());
+    }
+
+    #[test]
+    fn test_has_label_and_property() {
+        let steps = vec![GStep::V(None), GStep::HasLabel(vec![String::from("Person")]), GStep::Has(String::from("age"), GPredicate::Value(GValue::Integer(GInteger::I32(GInt32(42)))))];
+        let state = iterate_gremlin_steps(&steps, GremlinStateMachine::new()).expect("state");
+        assert_eq!(1, state.context.patterns.len());
+        let node = &state.context.patterns[0].get_nodes()[0];
+        assert_eq!(&vec![String::from("Person")], node.get_labels_ref());
+        assert_eq!(1, node.get_predicates_ref().len());
+    }
+
+    #[test]
+    fn test_has_any_label_and_id() {
+        let labels = vec![String::from("Person"), String::from("Software")];
+        let ids = vec![GValue::Integer(GInteger::I64(GInt64(1))), GValue::Integer(GInteger::I64(GInt64(2)))];
+        let steps = vec![GStep::V(None), GStep::HasId(ids), GStep::HasLabel(labels), GStep::OutE(vec![]), GStep::InV, GStep::HasLabel(vec![String::from("City")])];
+        let state = iterate_gremlin_steps(&steps, GremlinStateMachine::new()).expect("state");
+        assert_eq!(4, state.context.patterns.len());
+        let mut alternatives = state.context.patterns.iter().map(|pattern| {
+            let source = &pattern.get_nodes()[0];
+            assert_eq!(&vec![String::from("City")], pattern.get_nodes()[1].get_labels_ref());
+            (source.get_id().expect("id"), source.get_labels_ref()[0].clone())
+        }).collect::<Vec<(u64, String)>>();
+        alternatives.sort();
+        assert_eq!(vec![(1, String::from("Person")), (1, String::from("Software")), (2, String::from("Person")), (2, String::from("Software"))], alternatives);
+    }
+
+    #[test]
+    fn test_aggregation_steps() {
+        let steps = vec![GStep::V(None), GStep::HasLabel(vec![String::from("Person")]), GStep::Fold, GStep::Unfold, GStep::Count];
+        let state = iterate_gremlin_steps(&steps, GremlinStateMachine::new()).expect("state");
+        assert!(matches!(&state.context.traverser_steps[..], [GStep::Fold, GStep::Unfold, GStep::Count]));
+        assert!(iterate_gremlin_steps(&vec![GStep::V(None), GStep::Count, GStep::Unfold], GremlinStateMachine::new()).is_err());
+    }
+
+    #[test]
+    fn test_repeat_times() {
+        let out = vec![GStep::OutE(vec![String::from("knows")]), GStep::InV];
+        let steps = vec![vertex(1), GStep::Repeat(GRepeat{steps: out, times: Some(3), until: None}), GStep::Values(vec![String::from("name")])];
+        let state = build_gremlin_state(&steps).expect("state");
+        assert_eq!(1, state.context.patterns.len());
+        let pattern = &state.context.patterns[0];
+        assert_eq!(4, pattern.get_nodes().len());
+        assert!(pattern.get_relationships().iter().all(|rel| rel.get_labels_ref() == &vec![String::from("knows")]));
+        assert!(matches!(&state.context.traverser_steps[..], [GStep::Values(_)]));
+    }
+
+    #[test]
+    fn test_repeat_until() {
+        let out = vec![GStep::OutE(Vec::new()), GStep::InV];
+        let until = vec![GStep::HasLabel(vec![String::from("City")])];
+        let steps = vec![vertex(1), GStep::Repeat(GRepeat{steps: out.clone(), times: Some(3), until: Some(until.clone())}), GStep::Count];
+        let state = build_gremlin_state(&steps).expect("state");
+        assert_eq!(vec![2, 3, 4], state.context.patterns.iter().map(|pattern| pattern.get_nodes().len()).collect::<Vec<usize>>());
+        for pattern in &state.context.patterns {
+            let nodes = pattern.get_nodes();
+            assert_eq!(&vec![String::from("City")], nodes[nodes.len() - 1].get_labels_ref());
+        }
+        let unbounded = build_gremlin_state(&vec![vertex(1), GStep::Repeat(GRepeat{steps: out.clone(), times: None, until: Some(until)})]).expect("state");
+        assert_eq!(MAX_REPEAT_DEPTH as usize, unbounded.context.patterns.len());
+        assert!(build_gremlin_state(&vec![vertex(1), GStep::Repeat(GRepeat{steps: out, times: None, until: None})]).is_err());
+    }
+
+    #[test]
+    fn test_select_aliases() {
+        let steps = vec![vertex(1), GStep::As(String::from("a")), GStep::OutE(Vec::new()), GStep::InV, GStep::As(String::from("b")), GStep::Select(vec![String::from("a"), String::from("b")])];
+        let state = build_gremlin_state(&steps).expect("state");
+        let nodes = state.context.patterns[0].get_nodes();
+        assert_eq!(vec![Some(String::from("a")), Some(String::from("b"))], nodes.iter().map(|node| node.get_var().clone()).collect::<Vec<Option<String>>>());
+        assert!(matches!(&state.context.traverser_steps[..], [GStep::Select(aliases)] if aliases.len() == 2));
+        assert!(build_gremlin_state(&vec![vertex(1), GStep::Select(vec![String::from("a")])]).is_err());
+        let path = build_gremlin_state(&vec![vertex(1), GStep::OutE(Vec::new()), GStep::InV, GStep::Path, GStep::Count]).expect("state");
+        assert!(matches!(&path.context.traverser_steps[..], [GStep::Path, GStep::Count]));
+    }
+
+    #[test]
+    fn test_where_traversal() {
+        let name = GStep::Has(String::from("name"), GPredicate::Value(GValue::String(String::from("marko"))));
+        let knows = vec![GStep::OutE(vec![String::from("knows")]), GStep::InV, name];
+        let steps = vec![GStep::V(None), GStep::HasLabel(vec![String::from("Person")]), GStep::Where(knows), GStep::As(String::from("a")), GStep::OutE(Vec::new()), GStep::InV];
+        let state = build_gremlin_state(&steps).expect("state");
+        assert_eq!(1, state.context.patterns.len());
+        let pattern = &state.context.patterns[0];
+        let nodes = pattern.get_nodes();
+        assert_eq!(3, nodes.len());
+        assert_eq!(&Some(String::from("a")), nodes[0].get_var());
+        assert_eq!(1, nodes[1].get_predicates_ref().len());
+        let relationships = pattern.get_relationships();
+        assert_eq!(&vec![String::from("knows")], relationships[0].get_labels_ref());
+        assert!(pattern.get_edges().iter().all(|edge| edge.source == NodeIndex::new(0)));
+        assert!(build_gremlin_state(&vec![GStep::V(None), GStep::Where(vec![GStep::OutE(Vec::new())])]).is_err());
+    }
+}
@@ -52,8 +52,8 @@ impl State for AddVertexState {
 
     fn create_state(&self, step: &GStep) -> Result<Box<dyn State>, GremlinStateError> {
         match step {
-            GStep::SetProperty(name, value) => {
-                Ok(Box::new(SetPropertyState::new(name, value)))
+            GStep::SetProperty(name, value, cardinality) => {
+                Ok(Box::new(SetPropertyState::new(name, value, *cardinality)))
             }
             _ => {
                 Err(GremlinStateError::Invalid(step.clone()))
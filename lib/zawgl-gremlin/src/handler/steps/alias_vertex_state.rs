@@ -21,9 +21,21 @@
 
 use super::gremlin_state::{State, StateContext};
 use super::match_out_edge_state::MatchOutEdgeState;
+use super::match_in_edge_state::MatchInEdgeState;
+use super::match_both_edge_state::MatchBothEdgeState;
 use super::match_vertex_state::MatchVertexState;
 use super::super::super::gremlin::*;
 use super::gremlin_state::*;
+use super::order_state::OrderState;
+use super::range_state::RangeState;
+use super::values_state::ValuesState;
+use super::value_map_state::ValueMapState;
+use super::drop_state::DropState;
+use super::count_state::CountState;
+use super::fold_state::FoldState;
+use super::select_state::SelectState;
+use super::path_state::PathState;
+use super::where_state::WhereState;
 
 pub struct AliasVertexState {
     name: String,
@@ -38,9 +50,12 @@ impl State for AliasVertexState {
 
     fn handle_step(&self, context: &mut StateContext) -> Result<(), GremlinStateError> {
         match &context.previous_step {
-            GStep::V(_vid) => {
+            GStep::V(_) | GStep::InV | GStep::OutV | GStep::OtherV => {
                 if let Some(nid) = context.node_index {
                     context.node_aliases.insert(self.name.clone(), nid);
+                    for pattern in context.current_patterns_mut()? {
+                        pattern.get_node_mut(&nid).set_var(&self.name);
+                    }
                 }
             }
             _ => {}
@@ -57,6 +72,45 @@ impl State for AliasVertexState {
             GStep::OutE(_labels) => {
                 Ok(Box::new(MatchOutEdgeState::new()))
             }
+            GStep::InE(_labels) => {
+                Ok(Box::new(MatchInEdgeState::new()))
+            }
+            GStep::BothE(_labels) => {
+                Ok(Box::new(MatchBothEdgeState::new()))
+            }
+            GStep::Values(names) => {
+                Ok(Box::new(ValuesState::new(names)))
+            }
+            GStep::ValueMap(names) => {
+                Ok(Box::new(ValueMapState::new(names)))
+            }
+            GStep::Order(order_by) => {
+                Ok(Box::new(OrderState::new(order_by)))
+            }
+            GStep::Where(steps) => {
+                Ok(Box::new(WhereState::new(steps)))
+            }
+            GStep::Select(aliases) => {
+                Ok(Box::new(SelectState::new(aliases)))
+            }
+            GStep::Path => {
+                Ok(Box::new(PathState::new()))
+            }
+            GStep::Count => {
+                Ok(Box::new(CountState::new()))
+            }
+            GStep::Fold => {
+                Ok(Box::new(FoldState::new()))
+            }
+            GStep::Range(low, high) => {
+                Ok(Box::new(RangeState::new(*low, *high)))
+            }
+            GStep::Limit(limit) => {
+                Ok(Box::new(RangeState::new(0, Some(*limit))))
+            }
+            GStep::Drop => {
+                Ok(Box::new(DropState::new()))
+            }
             _ => {
                 Err(GremlinStateError::Invalid(step.clone()))
             }
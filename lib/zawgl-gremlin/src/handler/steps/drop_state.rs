@@ -0,0 +1,79 @@
+// MIT License
+//
+// Copyright (c) 2022 Alexandre RICCIARDI
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use super::gremlin_state::{State, StateContext};
+use zawgl_core::model::*;
+use super::super::super::gremlin::*;
+use super::gremlin_state::*;
+use super::super::utils::*;
+
+pub struct DropState {
+}
+
+impl DropState {
+    pub fn new() -> Self {
+        DropState{}
+    }
+}
+
+fn drop_edges(context: &mut StateContext, labels: &Vec<String>, direction: ExpandDirection) -> Result<(), GremlinStateError> {
+    let mut n = Node::new();
+    n.set_status(Status::Match);
+    let mut rel = Relationship::new();
+    rel.set_labels(labels.clone());
+    rel.set_status(Status::Delete);
+    expand_patterns(context, &n, &rel, direction)
+}
+
+impl State for DropState {
+    fn handle_step(&self, context: &mut StateContext) -> Result<(), GremlinStateError> {
+        match context.previous_step.clone() {
+            GStep::OutE(labels) => {
+                drop_edges(context, &labels, ExpandDirection::Out)?;
+            }
+            GStep::InE(labels) => {
+                drop_edges(context, &labels, ExpandDirection::In)?;
+            }
+            GStep::BothE(labels) => {
+                drop_edges(context, &labels, ExpandDirection::Both)?;
+            }
+            _ => {
+                let nid = context.node_index.ok_or(GremlinStateError::WrongContext("missing vertex to drop"))?;
+                for pattern in context.current_patterns_mut()? {
+                    pattern.get_node_mut(&nid).set_status(Status::Delete);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn create_state(&self, step: &GStep) -> Result<Box<dyn State>, GremlinStateError> {
+        match step {
+            GStep::Empty => {
+                Ok(Box::new(EndState::new()))
+            }
+            _ => {
+                Err(GremlinStateError::Invalid(step.clone()))
+            }
+        }
+    }
+}
@@ -0,0 +1,67 @@
+// MIT License
+//
+// Copyright (c) 2022 Alexandre RICCIARDI
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use super::gremlin_state::{State, StateContext};
+use super::super::super::gremlin::*;
+use super::gremlin_state::*;
+use super::count_state::CountState;
+use super::range_state::RangeState;
+use super::unfold_state::UnfoldState;
+
+pub struct FoldState {
+}
+
+impl FoldState {
+    pub fn new() -> Self {
+        FoldState{}
+    }
+}
+
+impl State for FoldState {
+    fn handle_step(&self, context: &mut StateContext) -> Result<(), GremlinStateError> {
+        context.traverser_steps.push(GStep::Fold);
+        Ok(())
+    }
+
+    fn create_state(&self, step: &GStep) -> Result<Box<dyn State>, GremlinStateError> {
+        match step {
+            GStep::Unfold => {
+                Ok(Box::new(UnfoldState::new()))
+            }
+            GStep::Count => {
+                Ok(Box::new(CountState::new()))
+            }
+            GStep::Range(low, high) => {
+                Ok(Box::new(RangeState::new(*low, *high)))
+            }
+            GStep::Limit(limit) => {
+                Ok(Box::new(RangeState::new(0, Some(*limit))))
+            }
+            GStep::Empty => {
+                Ok(Box::new(EndState::new()))
+            }
+            _ => {
+                Err(GremlinStateError::Invalid(step.clone()))
+            }
+        }
+    }
+}
@@ -54,20 +54,26 @@ impl State for FromState {
                 let mut r = Relationship::new();
                 r.set_labels(vec![label.clone()]);
                 r.set_status(Status::Create);
-                let pattern = context.patterns.last_mut().ok_or(GremlinStateError::WrongContext("missing pattern"))?;
                 let target_id = context.node_index;
-                let source_id = match &self.source {
-                    Source::Alias(a) => {*context.node_aliases.get(a).ok_or(GremlinStateError::WrongContext("missing alias"))?}
-                    Source::VertexId(vid) => {
-                        let mut source = Node::new();
-                        source.set_id(Some(*vid));
-                        source.set_status(Status::Match);
-                        pattern.add_node(source)
-                    }
+                let alias_id = match &self.source {
+                    Source::Alias(a) => Some(*context.node_aliases.get(a).ok_or(GremlinStateError::WrongContext("missing alias"))?),
+                    Source::VertexId(_) => None,
                 };
                 if let Some(tid) = target_id {
-                    let rid = pattern.add_relationship(r, source_id, tid);
-                    context.relationship_index = Some(rid);
+                    let mut rid = None;
+                    for pattern in context.current_patterns_mut()? {
+                        let source_id = match &self.source {
+                            Source::Alias(_) => alias_id.ok_or(GremlinStateError::WrongContext("missing alias"))?,
+                            Source::VertexId(vid) => {
+                                let mut source = Node::new();
+                                source.set_id(Some(*vid));
+                                source.set_status(Status::Match);
+                                pattern.add_node(source)
+                            }
+                        };
+                        rid = Some(pattern.add_relationship(r.clone(), source_id, tid));
+                    }
+                    context.relationship_index = rid;
                 } else {
                     return Err(GremlinStateError::WrongContext("missing target id in in from state"))
                 }
@@ -82,8 +88,8 @@ impl State for FromState {
             GStep::V(vid) => {
                 Ok(Box::new(MatchVertexState::new(vid)))
             }
-            GStep::SetProperty(name, value) => {
-                Ok(Box::new(SetPropertyState::new(name, value)))
+            GStep::SetProperty(name, value, cardinality) => {
+                Ok(Box::new(SetPropertyState::new(name, value, *cardinality)))
             }
             GStep::Empty => {
                 Ok(Box::new(EndState::new()))
@@ -21,15 +21,19 @@
 
 use std::collections::HashMap;
 
+use thiserror::Error;
+
 use super::super::super::gremlin::*;
 use zawgl_core::model::*;
 use zawgl_core::graph::*;
 use super::add_vertex_state::AddVertexState;
 use super::match_vertex_state::MatchVertexState;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Error)]
 pub enum GremlinStateError {
+    #[error("invalid step {0:?}")]
     Invalid(GStep),
+    #[error("wrong context: {0}")]
     WrongContext(&'static str),
 }
 
@@ -97,17 +101,28 @@ impl State for EndState {
 
 pub struct StateContext {
     pub patterns: Vec<PropertyGraph>,
+    pub branch_start: usize,
     pub node_index: Option<NodeIndex>,
     pub relationship_index: Option<EdgeIndex>,
     pub previous_step: GStep,
     pub node_aliases: HashMap<String, NodeIndex>,
     pub add_edge_label: Option<String>,
+    pub traverser_steps: Vec<GStep>,
 }
 
 impl StateContext {
     pub fn new() -> Self {
-        StateContext{patterns: Vec::new(), node_index: None, relationship_index: None, previous_step: GStep::Empty,
-             node_aliases: HashMap::new(), add_edge_label: None}
+        StateContext{patterns: Vec::new(), branch_start: 0, node_index: None, relationship_index: None, previous_step: GStep::Empty,
+             node_aliases: HashMap::new(), add_edge_label: None, traverser_steps: Vec::new()}
+    }
+
+    /// Alternative patterns of the current traversal, one per combination of undirected expansions.
+    pub fn current_patterns_mut(&mut self) -> Result<&mut [PropertyGraph], GremlinStateError> {
+        if self.branch_start < self.patterns.len() {
+            Ok(&mut self.patterns[self.branch_start..])
+        } else {
+            Err(GremlinStateError::WrongContext("missing pattern"))
+        }
     }
 }
 
@@ -121,6 +136,11 @@ impl GremlinStateMachine {
         GremlinStateMachine{context: StateContext::new(), state: Box::new(InitState::new())}
     }
     
+    /// State machine resuming the traversal of the context from the state.
+    pub fn from_state(context: StateContext, state: Box<dyn State>) -> Self {
+        GremlinStateMachine{context, state}
+    }
+
     pub fn new_step_state(mut previous: GremlinStateMachine, previous_step: &GStep, current_step: &GStep) -> Result<Self, GremlinStateError> {
         previous.state.handle_step(&mut previous.context)?;
         let new_state = previous.state.create_state(current_step)?;
@@ -19,7 +19,6 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-use zawgl_core::graph::traits::GraphContainerTrait;
 
 use super::add_edge_state::AddEdgeState;
 use super::alias_vertex_state::AliasVertexState;
@@ -27,8 +26,22 @@ use super::gremlin_state::{State, StateContext};
 use super::super::super::gremlin::*;
 use super::gremlin_state::*;
 use super::match_out_edge_state::MatchOutEdgeState;
+use super::match_in_edge_state::MatchInEdgeState;
+use super::match_both_edge_state::MatchBothEdgeState;
 use super::match_state::MatchState;
 use super::super::utils::*;
+use super::order_state::OrderState;
+use super::range_state::RangeState;
+use super::values_state::ValuesState;
+use super::value_map_state::ValueMapState;
+use super::drop_state::DropState;
+use super::has_label_state::HasLabelState;
+use super::has_id_state::HasIdState;
+use super::count_state::CountState;
+use super::fold_state::FoldState;
+use super::select_state::SelectState;
+use super::path_state::PathState;
+use super::where_state::WhereState;
 
 pub struct HasPropertyState {
     name: String,
@@ -44,9 +57,10 @@ impl HasPropertyState {
 impl State for HasPropertyState {
     fn handle_step(&self, context: &mut StateContext) -> Result<(), GremlinStateError> {
         if let Some(nid) = context.node_index {
-            let pattern = context.patterns.last_mut().ok_or(GremlinStateError::WrongContext("missing pattern"))?;
-            let node = pattern.get_node_mut(&nid);
-            node.add_predicate(convert_gremlin_predicate_to_pattern_predicate(&self.name, &self.predicate))
+            for pattern in context.current_patterns_mut()? {
+                let node = pattern.get_node_mut(&nid);
+                node.add_predicate(convert_gremlin_predicate_to_pattern_predicate(&self.name, &self.predicate))
+            }
         }
         Ok(())
     }
@@ -56,6 +70,12 @@ impl State for HasPropertyState {
             GStep::OutE(_labels) => {
                 Ok(Box::new(MatchOutEdgeState::new()))
             }
+            GStep::InE(_labels) => {
+                Ok(Box::new(MatchInEdgeState::new()))
+            }
+            GStep::BothE(_labels) => {
+                Ok(Box::new(MatchBothEdgeState::new()))
+            }
             GStep::As(alias) => {
                 Ok(Box::new(AliasVertexState::new(alias)))
             }
@@ -65,6 +85,51 @@ impl State for HasPropertyState {
             GStep::AddE(_label) => {
                 Ok(Box::new(AddEdgeState::new()))
             }
+            GStep::Values(names) => {
+                Ok(Box::new(ValuesState::new(names)))
+            }
+            GStep::ValueMap(names) => {
+                Ok(Box::new(ValueMapState::new(names)))
+            }
+            GStep::Order(order_by) => {
+                Ok(Box::new(OrderState::new(order_by)))
+            }
+            GStep::Where(steps) => {
+                Ok(Box::new(WhereState::new(steps)))
+            }
+            GStep::Select(aliases) => {
+                Ok(Box::new(SelectState::new(aliases)))
+            }
+            GStep::Path => {
+                Ok(Box::new(PathState::new()))
+            }
+            GStep::Count => {
+                Ok(Box::new(CountState::new()))
+            }
+            GStep::Fold => {
+                Ok(Box::new(FoldState::new()))
+            }
+            GStep::Range(low, high) => {
+                Ok(Box::new(RangeState::new(*low, *high)))
+            }
+            GStep::Limit(limit) => {
+                Ok(Box::new(RangeState::new(0, Some(*limit))))
+            }
+            GStep::Has(name, predicate) => {
+                Ok(Box::new(HasPropertyState::new(name, predicate)))
+            }
+            GStep::HasLabel(labels) => {
+                Ok(Box::new(HasLabelState::new(labels)))
+            }
+            GStep::HasId(ids) => {
+                Ok(Box::new(HasIdState::new(ids)))
+            }
+            GStep::Drop => {
+                Ok(Box::new(DropState::new()))
+            }
+            GStep::Empty => {
+                Ok(Box::new(EndState::new()))
+            }
             _ => {
                 Err(GremlinStateError::Invalid(step.clone()))
             }
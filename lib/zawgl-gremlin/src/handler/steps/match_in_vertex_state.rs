@@ -25,8 +25,24 @@ use zawgl_core::model::*;
 use super::super::super::gremlin::*;
 use super::gremlin_state::*;
 use super::match_out_edge_state::MatchOutEdgeState;
+use super::match_in_edge_state::MatchInEdgeState;
+use super::match_both_edge_state::MatchBothEdgeState;
 use super::match_state::MatchState;
 use super::add_edge_state::AddEdgeState;
+use super::order_state::OrderState;
+use super::range_state::RangeState;
+use super::values_state::ValuesState;
+use super::value_map_state::ValueMapState;
+use super::drop_state::DropState;
+use super::has_property_state::HasPropertyState;
+use super::has_label_state::HasLabelState;
+use super::has_id_state::HasIdState;
+use super::super::utils::*;
+use super::count_state::CountState;
+use super::fold_state::FoldState;
+use super::select_state::SelectState;
+use super::path_state::PathState;
+use super::where_state::WhereState;
 
 pub struct MatchInVertexState {
 }
@@ -45,14 +61,11 @@ impl State for MatchInVertexState {
 
         match &context.previous_step {
             GStep::OutE(labels) => {
-                if let Some(node_index) = context.node_index {
+                if context.node_index.is_some() {
                     let mut rel = Relationship::new();
                     rel.set_labels(labels.clone());
                     rel.set_status(Status::Match);
-                    let pattern = context.patterns.last_mut().ok_or(GremlinStateError::WrongContext("missing pattern"))?;
-                    let nid = pattern.add_node(n);
-                    pattern.add_relationship(rel, node_index, nid);
-                    context.node_index = Some(nid);
+                    expand_patterns(context, &n, &rel, ExpandDirection::Out)?;
                 }
             }
             _ => {
@@ -67,6 +80,12 @@ impl State for MatchInVertexState {
             GStep::OutE(_labels) => {
                 Ok(Box::new(MatchOutEdgeState::new()))
             }
+            GStep::InE(_labels) => {
+                Ok(Box::new(MatchInEdgeState::new()))
+            }
+            GStep::BothE(_labels) => {
+                Ok(Box::new(MatchBothEdgeState::new()))
+            }
             GStep::As(alias) => {
                 Ok(Box::new(AliasVertexState::new(alias)))
             }
@@ -79,6 +98,48 @@ impl State for MatchInVertexState {
             GStep::Empty => {
                 Ok(Box::new(EndState::new()))
             }
+            GStep::Values(names) => {
+                Ok(Box::new(ValuesState::new(names)))
+            }
+            GStep::ValueMap(names) => {
+                Ok(Box::new(ValueMapState::new(names)))
+            }
+            GStep::Order(order_by) => {
+                Ok(Box::new(OrderState::new(order_by)))
+            }
+            GStep::Where(steps) => {
+                Ok(Box::new(WhereState::new(steps)))
+            }
+            GStep::Select(aliases) => {
+                Ok(Box::new(SelectState::new(aliases)))
+            }
+            GStep::Path => {
+                Ok(Box::new(PathState::new()))
+            }
+            GStep::Count => {
+                Ok(Box::new(CountState::new()))
+            }
+            GStep::Fold => {
+                Ok(Box::new(FoldState::new()))
+            }
+            GStep::Range(low, high) => {
+                Ok(Box::new(RangeState::new(*low, *high)))
+            }
+            GStep::Limit(limit) => {
+                Ok(Box::new(RangeState::new(0, Some(*limit))))
+            }
+            GStep::Drop => {
+                Ok(Box::new(DropState::new()))
+            }
+            GStep::Has(name, predicate) => {
+                Ok(Box::new(HasPropertyState::new(name, predicate)))
+            }
+            GStep::HasLabel(labels) => {
+                Ok(Box::new(HasLabelState::new(labels)))
+            }
+            GStep::HasId(ids) => {
+                Ok(Box::new(HasIdState::new(ids)))
+            }
             _ => {
                 Err(GremlinStateError::Invalid(step.clone()))
             }
@@ -0,0 +1,149 @@
+// MIT License
+//
+// Copyright (c) 2022 Alexandre RICCIARDI
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use super::gremlin_state::{State, StateContext};
+use super::alias_vertex_state::AliasVertexState;
+use zawgl_core::model::*;
+use super::super::super::gremlin::*;
+use super::gremlin_state::*;
+use super::match_out_edge_state::MatchOutEdgeState;
+use super::match_in_edge_state::MatchInEdgeState;
+use super::match_both_edge_state::MatchBothEdgeState;
+use super::match_state::MatchState;
+use super::add_edge_state::AddEdgeState;
+use super::order_state::OrderState;
+use super::range_state::RangeState;
+use super::values_state::ValuesState;
+use super::value_map_state::ValueMapState;
+use super::drop_state::DropState;
+use super::has_property_state::HasPropertyState;
+use super::has_label_state::HasLabelState;
+use super::has_id_state::HasIdState;
+use super::super::utils::*;
+use super::count_state::CountState;
+use super::fold_state::FoldState;
+use super::select_state::SelectState;
+use super::path_state::PathState;
+use super::where_state::WhereState;
+
+pub struct MatchOtherVertexState {
+}
+
+impl MatchOtherVertexState {
+    pub fn new() -> Self {
+        MatchOtherVertexState{}
+    }
+}
+
+impl State for MatchOtherVertexState {
+    
+    fn handle_step(&self, context: &mut StateContext) -> Result<(), GremlinStateError> {
+        let mut n = Node::new();
+        n.set_status(Status::Match);
+
+        match &context.previous_step {
+            GStep::BothE(labels) => {
+                if context.node_index.is_some() {
+                    let mut rel = Relationship::new();
+                    rel.set_labels(labels.clone());
+                    rel.set_status(Status::Match);
+                    expand_patterns(context, &n, &rel, ExpandDirection::Both)?;
+                }
+            }
+            _ => {
+
+            }
+        }
+        Ok(())
+    }
+
+    fn create_state(&self, step: &GStep) -> Result<Box<dyn State>, GremlinStateError> {
+        match step {
+            GStep::OutE(_labels) => {
+                Ok(Box::new(MatchOutEdgeState::new()))
+            }
+            GStep::InE(_labels) => {
+                Ok(Box::new(MatchInEdgeState::new()))
+            }
+            GStep::BothE(_labels) => {
+                Ok(Box::new(MatchBothEdgeState::new()))
+            }
+            GStep::As(alias) => {
+                Ok(Box::new(AliasVertexState::new(alias)))
+            }
+            GStep::Match(bytecodes) => {
+                Ok(Box::new(MatchState::new(bytecodes)))
+            }
+            GStep::AddE(_label) => {
+                Ok(Box::new(AddEdgeState::new()))
+            }
+            GStep::Empty => {
+                Ok(Box::new(EndState::new()))
+            }
+            GStep::Values(names) => {
+                Ok(Box::new(ValuesState::new(names)))
+            }
+            GStep::ValueMap(names) => {
+                Ok(Box::new(ValueMapState::new(names)))
+            }
+            GStep::Order(order_by) => {
+                Ok(Box::new(OrderState::new(order_by)))
+            }
+            GStep::Where(steps) => {
+                Ok(Box::new(WhereState::new(steps)))
+            }
+            GStep::Select(aliases) => {
+                Ok(Box::new(SelectState::new(aliases)))
+            }
+            GStep::Path => {
+                Ok(Box::new(PathState::new()))
+            }
+            GStep::Count => {
+                Ok(Box::new(CountState::new()))
+            }
+            GStep::Fold => {
+                Ok(Box::new(FoldState::new()))
+            }
+            GStep::Range(low, high) => {
+                Ok(Box::new(RangeState::new(*low, *high)))
+            }
+            GStep::Limit(limit) => {
+                Ok(Box::new(RangeState::new(0, Some(*limit))))
+            }
+            GStep::Drop => {
+                Ok(Box::new(DropState::new()))
+            }
+            GStep::Has(name, predicate) => {
+                Ok(Box::new(HasPropertyState::new(name, predicate)))
+            }
+            GStep::HasLabel(labels) => {
+                Ok(Box::new(HasLabelState::new(labels)))
+            }
+            GStep::HasId(ids) => {
+                Ok(Box::new(HasIdState::new(ids)))
+            }
+            _ => {
+                Err(GremlinStateError::Invalid(step.clone()))
+            }
+        }
+    }
+}
@@ -23,6 +23,7 @@ use super::gremlin_state::{State, StateContext};
 use super::match_vertex_state::MatchVertexState;
 use super::super::super::gremlin::*;
 use super::gremlin_state::*;
+use super::drop_state::DropState;
 use super::match_in_vertex_state::*;
 
 pub struct MatchOutEdgeState {
@@ -47,6 +48,9 @@ impl State for MatchOutEdgeState {
             GStep::InV => {
                 Ok(Box::new(MatchInVertexState::new()))
             }
+            GStep::Drop => {
+                Ok(Box::new(DropState::new()))
+            }
             _ => {
                 Err(GremlinStateError::Invalid(step.clone()))
             }
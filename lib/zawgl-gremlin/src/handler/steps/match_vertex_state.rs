@@ -26,22 +26,43 @@ use zawgl_core::model::*;
 use super::super::super::gremlin::*;
 use super::gremlin_state::*;
 use super::match_out_edge_state::MatchOutEdgeState;
+use super::match_in_edge_state::MatchInEdgeState;
+use super::match_both_edge_state::MatchBothEdgeState;
 use super::match_state::MatchState;
 use super::add_edge_state::AddEdgeState;
 use std::convert::TryFrom;
 use super::super::utils::*;
+use super::order_state::OrderState;
+use super::range_state::RangeState;
+use super::values_state::ValuesState;
+use super::value_map_state::ValueMapState;
+use super::drop_state::DropState;
+use super::has_label_state::HasLabelState;
+use super::has_id_state::HasIdState;
+use super::count_state::CountState;
+use super::fold_state::FoldState;
+use super::select_state::SelectState;
+use super::path_state::PathState;
+use super::where_state::WhereState;
 
 pub struct MatchVertexState {
     vid: Option<u64>,
+    uuid: Option<String>,
 }
 
 impl MatchVertexState {
     pub fn new(gid: &Option<GValueOrVertex>) -> Self {
-        let vid = gid.as_ref().and_then(|value| match value {
-            GValueOrVertex::Value(v) => {u64::try_from(v.clone()).ok()}
-            GValueOrVertex::Vertex(vertex) => {u64::try_from(vertex.id.clone()).ok()}
-        }).or(None);
-        MatchVertexState{vid: vid}
+        let value = gid.as_ref().map(|value| match value {
+            GValueOrVertex::Value(v) => {v.clone()}
+            GValueOrVertex::Vertex(vertex) => {vertex.id.clone()}
+        });
+        let vid = value.clone().and_then(|v| u64::try_from(v).ok());
+        // ids that are not integers are the uuids of the vertices
+        let uuid = match value {
+            Some(GValue::String(uuid)) | Some(GValue::Uuid(GUuid(uuid))) if vid.is_none() => Some(uuid),
+            _ => None,
+        };
+        MatchVertexState{vid, uuid}
     }
 }
 
@@ -52,25 +73,24 @@ impl State for MatchVertexState {
         let mut n = Node::new();
         n.set_id(self.vid);
         n.set_status(Status::Match);
+        if let Some(uuid) = &self.uuid {
+            n.get_properties_mut().push(Property::new(String::from(ELEMENT_UUID_PROPERTY), PropertyValue::PString(uuid.clone())));
+        }
 
         match &context.previous_step {
             GStep::As(_alias) => {
-                let pattern = context.patterns.last_mut().ok_or(GremlinStateError::WrongContext("missing pattern"))?;
-                let nid = pattern.add_node(n);
+                let nid = add_node_to_patterns(context, &n)?;
                 context.node_index = Some(nid);
             }
             GStep::Empty => {
                 init_pattern(context, n);
             }
             GStep::OutE(labels) => {
-                if let Some(node_index) = context.node_index {
+                if context.node_index.is_some() {
                     let mut rel = Relationship::new();
                     rel.set_labels(labels.clone());
                     rel.set_status(Status::Match);
-                    let pattern = context.patterns.last_mut().ok_or(GremlinStateError::WrongContext("missing pattern"))?;
-                    let nid = pattern.add_node(n);
-                    pattern.add_relationship(rel, node_index, nid);
-                    context.node_index = Some(nid);
+                    expand_patterns(context, &n, &rel, ExpandDirection::Out)?;
                 }
             }
             GStep::AddE(label) => {
@@ -78,10 +98,13 @@ impl State for MatchVertexState {
                     let mut rel = Relationship::new();
                     rel.set_labels(vec![label.clone()]);
                     rel.set_status(Status::Create);
-                    let pattern = context.patterns.last_mut().ok_or(GremlinStateError::WrongContext("missing pattern"))?;
-                    let nid = pattern.add_node(n);
-                    pattern.add_relationship(rel, node_index, nid);
-                    context.node_index = Some(nid);
+                    let mut nid = None;
+                    for pattern in context.current_patterns_mut()? {
+                        let target = pattern.add_node(n.clone());
+                        pattern.add_relationship(rel.clone(), node_index, target);
+                        nid = Some(target);
+                    }
+                    context.node_index = nid;
                 }
             }
             _ => {
@@ -97,6 +120,12 @@ impl State for MatchVertexState {
             GStep::OutE(_labels) => {
                 Ok(Box::new(MatchOutEdgeState::new()))
             }
+            GStep::InE(_labels) => {
+                Ok(Box::new(MatchInEdgeState::new()))
+            }
+            GStep::BothE(_labels) => {
+                Ok(Box::new(MatchBothEdgeState::new()))
+            }
             GStep::As(alias) => {
                 Ok(Box::new(AliasVertexState::new(alias)))
             }
@@ -109,6 +138,48 @@ impl State for MatchVertexState {
             GStep::Has(name, predicate) => {
                 Ok(Box::new(HasPropertyState::new(name, predicate)))
             }
+            GStep::HasLabel(labels) => {
+                Ok(Box::new(HasLabelState::new(labels)))
+            }
+            GStep::HasId(ids) => {
+                Ok(Box::new(HasIdState::new(ids)))
+            }
+            GStep::Values(names) => {
+                Ok(Box::new(ValuesState::new(names)))
+            }
+            GStep::ValueMap(names) => {
+                Ok(Box::new(ValueMapState::new(names)))
+            }
+            GStep::Order(order_by) => {
+                Ok(Box::new(OrderState::new(order_by)))
+            }
+            GStep::Where(steps) => {
+                Ok(Box::new(WhereState::new(steps)))
+            }
+            GStep::Select(aliases) => {
+                Ok(Box::new(SelectState::new(aliases)))
+            }
+            GStep::Path => {
+                Ok(Box::new(PathState::new()))
+            }
+            GStep::Count => {
+                Ok(Box::new(CountState::new()))
+            }
+            GStep::Fold => {
+                Ok(Box::new(FoldState::new()))
+            }
+            GStep::Range(low, high) => {
+                Ok(Box::new(RangeState::new(*low, *high)))
+            }
+            GStep::Limit(limit) => {
+                Ok(Box::new(RangeState::new(0, Some(*limit))))
+            }
+            GStep::Drop => {
+                Ok(Box::new(DropState::new()))
+            }
+            GStep::Empty => {
+                Ok(Box::new(EndState::new()))
+            }
             _ => {
                 Err(GremlinStateError::Invalid(step.clone()))
             }
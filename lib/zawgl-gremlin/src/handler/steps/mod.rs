@@ -30,4 +30,21 @@ pub mod add_vertex_state;
 pub mod set_property_state;
 pub mod gremlin_state;
 pub mod match_in_vertex_state;
-pub mod has_property_state;
\ No newline at end of file
+pub mod match_in_edge_state;
+pub mod match_out_vertex_state;
+pub mod match_both_edge_state;
+pub mod match_other_vertex_state;
+pub mod has_property_state;
+pub mod has_label_state;
+pub mod has_id_state;
+pub mod order_state;
+pub mod range_state;
+pub mod values_state;
+pub mod value_map_state;
+pub mod drop_state;
+pub mod count_state;
+pub mod fold_state;
+pub mod unfold_state;
+pub mod select_state;
+pub mod path_state;
+pub mod where_state;
\ No newline at end of file
@@ -21,39 +21,39 @@
 
 use super::gremlin_state::{State, StateContext};
 use super::match_vertex_state::MatchVertexState;
-use super::super::utils::prop_value_from_gremlin_value;
-use zawgl_core::{graph::traits::GraphContainerTrait, model::Property};
+use super::super::utils::{prop_value_from_gremlin_value, set_property_value};
 use super::super::super::gremlin::*;
 use super::gremlin_state::*;
 
 pub struct SetPropertyState {
     name: String,
     value: GValue,
+    cardinality: GCardinality,
 }
 
 impl SetPropertyState {
-    pub fn new(name: &str, value: &GValue) -> Self {
-        SetPropertyState{name: String::from(name), value: value.clone()}
+    pub fn new(name: &str, value: &GValue, cardinality: GCardinality) -> Self {
+        SetPropertyState{name: String::from(name), value: value.clone(), cardinality: cardinality}
     }
 }
 impl State for SetPropertyState {
     fn handle_step(&self, context: &mut StateContext) -> Result<(), GremlinStateError> {
         match &context.previous_step {
             GStep::AddV(_label) => {
-                let pattern = context.patterns.last_mut().ok_or(GremlinStateError::WrongContext("missing pattern"))?;
-                if let Some(nid) = &context.node_index {
-                    let n = pattern.get_node_mut(nid);
-                    let prop = Property::new(self.name.clone(), prop_value_from_gremlin_value(&self.value));
-                    n.get_properties_mut().push(prop);
+                if let Some(nid) = context.node_index {
+                    for pattern in context.current_patterns_mut()? {
+                        let n = pattern.get_node_mut(&nid);
+                        set_property_value(n.get_properties_mut(), &self.name, prop_value_from_gremlin_value(&self.value), self.cardinality);
+                    }
                 }
                 
             },
             GStep::From(_alias) => {
-                let pattern = context.patterns.last_mut().ok_or(GremlinStateError::WrongContext("missing pattern"))?;
-                if let Some(rid) = &context.relationship_index {
-                    let r = pattern.get_relationship_mut(rid);
-                    let prop = Property::new(self.name.clone(), prop_value_from_gremlin_value(&self.value));
-                    r.get_properties_mut().push(prop);
+                if let Some(rid) = context.relationship_index {
+                    for pattern in context.current_patterns_mut()? {
+                        let r = pattern.get_relationship_mut(&rid);
+                        set_property_value(r.get_properties_mut(), &self.name, prop_value_from_gremlin_value(&self.value), GCardinality::Single);
+                    }
                 }
             }
             _ => {}
@@ -66,6 +66,9 @@ impl State for SetPropertyState {
             GStep::V(vid) => {
                 Ok(Box::new(MatchVertexState::new(vid)))
             }
+            GStep::SetProperty(name, value, cardinality) => {
+                Ok(Box::new(SetPropertyState::new(name, value, *cardinality)))
+            }
             GStep::Empty => {
                 Ok(Box::new(EndState::new()))
             }
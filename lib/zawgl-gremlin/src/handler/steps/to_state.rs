@@ -53,20 +53,26 @@ impl State for ToState {
                 let mut r = Relationship::new();
                 r.set_labels(vec![label.clone()]);
                 r.set_status(Status::Create);
-                let pattern = context.patterns.last_mut().ok_or(GremlinStateError::WrongContext("missing pattern"))?;
                 let source_id = context.node_index;
-                let target_id = match &self.target {
-                    Target::Alias(a) => {*context.node_aliases.get(a).ok_or(GremlinStateError::WrongContext("missing pattern"))?}
-                    Target::VertexId(vid) => {
-                        let mut target = Node::new();
-                        target.set_id(Some(*vid));
-                        target.set_status(Status::Match);
-                        pattern.add_node(target)
-                    }
+                let alias_id = match &self.target {
+                    Target::Alias(a) => Some(*context.node_aliases.get(a).ok_or(GremlinStateError::WrongContext("missing pattern"))?),
+                    Target::VertexId(_) => None,
                 };
                 if let Some(sid) = source_id {
-                    let rid = pattern.add_relationship(r, sid, target_id);
-                    context.relationship_index = Some(rid);
+                    let mut rid = None;
+                    for pattern in context.current_patterns_mut()? {
+                        let target_id = match &self.target {
+                            Target::Alias(_) => alias_id.ok_or(GremlinStateError::WrongContext("missing pattern"))?,
+                            Target::VertexId(vid) => {
+                                let mut target = Node::new();
+                                target.set_id(Some(*vid));
+                                target.set_status(Status::Match);
+                                pattern.add_node(target)
+                            }
+                        };
+                        rid = Some(pattern.add_relationship(r.clone(), sid, target_id));
+                    }
+                    context.relationship_index = rid;
                 } else {
                     return Err(GremlinStateError::WrongContext("missing source"));
                 }
@@ -81,8 +87,8 @@ impl State for ToState {
             GStep::V(vid) => {
                 Ok(Box::new(MatchVertexState::new(vid)))
             }
-            GStep::SetProperty(name, value) => {
-                Ok(Box::new(SetPropertyState::new(name, value)))
+            GStep::SetProperty(name, value, cardinality) => {
+                Ok(Box::new(SetPropertyState::new(name, value, *cardinality)))
             }
             GStep::Empty => {
                 Ok(Box::new(EndState::new()))
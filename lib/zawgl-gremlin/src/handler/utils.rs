@@ -19,10 +19,12 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-use zawgl_core::{graph::{EdgeIndex, traits::{GraphContainerTrait, GraphTrait}}, model::{Node, Property, PropertyGraph, PropertyValue, Relationship, Status, predicates::{NamedPropertyPredicate, PropertyPredicate}}};
-use zawgl_tx_handler::{ResultGraph, tx_handler::Scenario};
+use zawgl_core::{graph::{EdgeIndex, NodeIndex}, model::{Node, Path, Property, PropertyGraph, PropertyValue, Relationship, Status, predicates::{NamedPropertyPredicate, PropertyPredicate}, temporal::DateTime}};
+use std::cmp::Ordering;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hash, Hasher};
 
-use super::{GremlinError, steps::gremlin_state::StateContext};
+use super::{GremlinError, steps::gremlin_state::{GremlinStateError, StateContext}};
 
 use super::super::gremlin::*;
 
@@ -30,10 +32,71 @@ use super::super::gremlin::*;
 pub fn init_pattern(context: &mut StateContext, n: Node) {
     let mut pattern = PropertyGraph::new();
     let nid = pattern.add_node(n);
+    context.branch_start = context.patterns.len();
     context.patterns.push(pattern);
     context.node_index = Some(nid);
 }
 
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ExpandDirection {
+    Out,
+    In,
+    Both,
+}
+
+pub fn add_node_to_patterns(context: &mut StateContext, n: &Node) -> Result<NodeIndex, GremlinStateError> {
+    let mut nid = None;
+    for pattern in context.current_patterns_mut()? {
+        nid = Some(pattern.add_node(n.clone()));
+    }
+    nid.ok_or(GremlinStateError::WrongContext("missing pattern"))
+}
+
+pub fn expand_patterns(context: &mut StateContext, n: &Node, rel: &Relationship, direction: ExpandDirection) -> Result<(), GremlinStateError> {
+    let source = context.node_index.ok_or(GremlinStateError::WrongContext("missing source"))?;
+    let mut expanded = Vec::new();
+    let mut nid = None;
+    for pattern in context.patterns.drain(context.branch_start..) {
+        let directions = match direction {
+            ExpandDirection::Both => vec![ExpandDirection::Out, ExpandDirection::In],
+            _ => vec![direction],
+        };
+        for dir in directions {
+            let mut alternative = pattern.clone();
+            let target = alternative.add_node(n.clone());
+            if dir == ExpandDirection::Out {
+                alternative.add_relationship(rel.clone(), source, target);
+            } else {
+                alternative.add_relationship(rel.clone(), target, source);
+            }
+            nid = Some(target);
+            expanded.push(alternative);
+        }
+    }
+    context.patterns.append(&mut expanded);
+    context.node_index = Some(nid.ok_or(GremlinStateError::WrongContext("missing pattern"))?);
+    Ok(())
+}
+
+/// Replaces each current pattern by one alternative per value, the current node of an alternative being
+/// updated with its value.
+pub fn branch_current_node<T, F: Fn(&mut Node, &T)>(context: &mut StateContext, values: &[T], update: F) -> Result<(), GremlinStateError> {
+    let nid = context.node_index.ok_or(GremlinStateError::WrongContext("missing node"))?;
+    let mut branches = Vec::new();
+    for pattern in context.patterns.drain(context.branch_start..) {
+        for value in values {
+            let mut alternative = pattern.clone();
+            update(alternative.get_node_mut(&nid), value);
+            branches.push(alternative);
+        }
+    }
+    if branches.is_empty() {
+        return Err(GremlinStateError::WrongContext("missing pattern"));
+    }
+    context.patterns.append(&mut branches);
+    Ok(())
+}
+
 pub fn prop_value_from_gremlin_value(gval: &GValue) -> PropertyValue {
     match gval {
         GValue::String(sval) => {
@@ -55,18 +118,77 @@ pub fn prop_value_from_gremlin_value(gval: &GValue) -> PropertyValue {
         GValue::Double(dval) => {
             PropertyValue::PFloat(dval.0)
         }
+        GValue::Float(fval) => {
+            PropertyValue::PFloat(fval.0 as f64)
+        }
+        GValue::Date(GDate(millis)) | GValue::Timestamp(GTimestamp(millis)) => {
+            PropertyValue::PDateTime(DateTime::new(millis * 1_000_000, 0))
+        }
+        GValue::Uuid(uuid) => {
+            PropertyValue::PString(uuid.0.clone())
+        }
+        GValue::List(values) | GValue::Set(values) => {
+            PropertyValue::PList(values.iter().map(prop_value_from_gremlin_value).collect())
+        }
+        GValue::Map(entries) => {
+            PropertyValue::PMap(entries.iter().map(|(k, v)| (k.clone(), prop_value_from_gremlin_value(v))).collect())
+        }
+        GValue::Null => {
+            PropertyValue::PNull
+        }
     }
 }
 
 
-pub fn gremlin_value_from_property_value(p: &Property) -> Option<(GInt64, GValue)> {
-    Some((GInt64(p.get_id()? as i64),
-        match p.get_value() {
-            PropertyValue::PString(v) => GValue::String(v.clone()),
-            PropertyValue::PInteger(v) => GValue::Integer(GInteger::I64(GInt64(*v))),
-            PropertyValue::PFloat(v) => GValue::Double(GDouble(*v)),
-            PropertyValue::PBool(v) => GValue::Bool(*v),
-        }))
+/// Sets a property following the gremlin cardinality, list and set cardinalities
+/// accumulate the values in a list property.
+pub fn set_property_value(properties: &mut Vec<Property>, name: &str, value: PropertyValue, cardinality: GCardinality) {
+    let index = properties.iter().position(|p| p.get_name() == name);
+    let new_value = match (cardinality, index) {
+        (GCardinality::Single, _) => value,
+        (_, None) => PropertyValue::PList(vec![value]),
+        (_, Some(i)) => {
+            let mut values = match properties[i].get_value() {
+                PropertyValue::PList(values) => values.clone(),
+                other => vec![other.clone()],
+            };
+            if cardinality == GCardinality::List || !values.contains(&value) {
+                values.push(value);
+            }
+            PropertyValue::PList(values)
+        }
+    };
+    let prop = Property::new(String::from(name), new_value);
+    match index {
+        Some(i) => properties[i] = prop,
+        None => properties.push(prop),
+    }
+}
+
+fn gremlin_value_from_value(value: &PropertyValue) -> GValue {
+    match value {
+        PropertyValue::PString(v) => GValue::String(v.clone()),
+        PropertyValue::PInteger(v) => GValue::Integer(GInteger::I64(GInt64(*v))),
+        PropertyValue::PFloat(v) => GValue::Double(GDouble(*v)),
+        PropertyValue::PBool(v) => GValue::Bool(*v),
+        PropertyValue::PList(list) => GValue::List(list.iter().map(gremlin_value_from_value).collect()),
+        PropertyValue::PMap(entries) => GValue::Map(entries.iter().map(|(k, v)| (k.clone(), gremlin_value_from_value(v))).collect()),
+        PropertyValue::PDateTime(dt) => GValue::Date(GDate(dt.epoch_nanos.div_euclid(1_000_000))),
+        PropertyValue::PDate(date) => GValue::String(date.to_string()),
+        PropertyValue::PDuration(duration) => GValue::String(duration.to_string()),
+        PropertyValue::PNull => GValue::Null,
+    }
+}
+
+/// Multi-valued properties are expanded into one gremlin value per list element,
+/// nested lists and maps are kept as collection values.
+pub fn gremlin_values_from_property_value(p: &Property) -> Option<Vec<(GInt64, GValue)>> {
+    let id = GInt64(p.get_id()? as i64);
+    let values = match p.get_value() {
+        PropertyValue::PList(list) => list.iter().map(gremlin_value_from_value).collect(),
+        other => vec![gremlin_value_from_value(other)],
+    };
+    Some(values.into_iter().map(|v| (id, v)).collect())
 }
 
 
@@ -116,12 +238,92 @@ fn build_edge_properties(r: &Relationship) -> Option<GProperties> {
 }
 
 fn build_property(p: &Property) -> Option<GProperty> {
-    Some(GProperty { name: String::from(p.get_name()), values: vec![gremlin_value_from_property_value(p)?]})
+    Some(GProperty { name: String::from(p.get_name()), values: gremlin_values_from_property_value(p)?})
+}
+
+fn build_edge(r: &Relationship, source: &Node, target: &Node) -> Result<GEdge, GremlinError> {
+    Ok(GEdge{id: GInt64(r.get_id().ok_or(GremlinError::ResponseError)? as i64), label: r.get_labels_ref().join(":"),
+        out_v_abel: target.get_labels_ref().join(":"),
+        in_v_label: source.get_labels_ref().join(":"),
+        in_v: GInt64(source.get_id().ok_or(GremlinError::ResponseError)? as i64),
+        out_v: GInt64(target.get_id().ok_or(GremlinError::ResponseError)? as i64),
+        properties: build_edge_properties(r).ok_or(GremlinError::ResponseError)?,
+    })
 }
 
-pub fn convert_graph_to_gremlin_response(graphs: &Vec<PropertyGraph>, request_id: &str) -> Result<GremlinResponse, GremlinError> {
+/// Path objects alternate vertices and edges, none of them being labelled.
+pub fn build_path(path: &Path) -> Result<GPath, GremlinError> {
+    let mut objects = Vec::new();
+    for (index, node) in path.get_nodes_ref().iter().enumerate() {
+        if index > 0 {
+            let (source, target) = path.get_endpoints(index - 1).ok_or(GremlinError::ResponseError)?;
+            objects.push(GItem::Edge(build_edge(&path.get_relationships_ref()[index - 1], source, target)?));
+        }
+        objects.push(GItem::Vertex(build_vertex_from_node(node).ok_or(GremlinError::ResponseError)?));
+    }
+    Ok(GPath{labels: objects.iter().map(|_| Vec::new()).collect(), objects})
+}
+
+/// Vertices bound to the aliases in the matched graph, a single alias gives its vertex. None if an alias is not bound.
+fn build_bindings(graph: &PropertyGraph, aliases: &[String]) -> Result<Option<GItem>, GremlinError> {
+    let mut bindings = GMap::new();
+    for alias in aliases {
+        let node = match graph.get_nodes().into_iter().find(|n| n.get_var().as_ref() == Some(alias)) {
+            Some(node) => node,
+            None => return Ok(None),
+        };
+        bindings.map.insert(alias.clone(), GItem::Vertex(build_vertex_from_node(node).ok_or(GremlinError::ResponseError)?));
+    }
+    if aliases.len() == 1 {
+        Ok(bindings.map.remove(&aliases[0]))
+    } else {
+        Ok(Some(GItem::Bindings(bindings)))
+    }
+}
+
+/// Path following the relationships of the matched graph in traversal order from its first vertex,
+/// the vertices being labelled with their aliases.
+fn build_graph_path(graph: &PropertyGraph) -> Result<GPath, GremlinError> {
+    let mut end = NodeIndex::new(0);
+    let first = *graph.get_nodes().first().ok_or(GremlinError::ResponseError)?;
+    let mut path = Path::new(first.clone());
+    for edge in graph.get_edges() {
+        let (forward, next) = if edge.get_source() == end {
+            (true, edge.get_target())
+        } else if edge.get_target() == end {
+            (false, edge.get_source())
+        } else {
+            continue;
+        };
+        path.append(edge.relationship.clone(), forward, graph.get_node_ref(&next).clone());
+        end = next;
+    }
+    let mut gpath = build_path(&path)?;
+    for (index, node) in path.get_nodes_ref().iter().enumerate() {
+        if let Some(alias) = node.get_var() {
+            gpath.labels[2 * index].push(alias.clone());
+        }
+    }
+    Ok(gpath)
+}
+
+pub fn convert_graph_to_gremlin_response(graphs: &Vec<PropertyGraph>, request_id: &str, traverser_steps: &Vec<GStep>) -> Result<GremlinResponse, GremlinError> {
     let mut res = GResult::new();
+    let projection = traverser_steps.iter().find(|step| matches!(step, GStep::Select(_) | GStep::Path));
     for graph in graphs {
+        match projection {
+            Some(GStep::Select(aliases)) => {
+                if let Some(item) = build_bindings(graph, aliases)? {
+                    res.data.values.push(GTraverser{bulk: GInt64(1), value: item});
+                }
+                continue;
+            }
+            Some(GStep::Path) => {
+                res.data.values.push(GTraverser{bulk: GInt64(1), value: GItem::Path(build_graph_path(graph)?)});
+                continue;
+            }
+            _ => {}
+        }
         for n in graph.get_nodes() {
             let vertex = build_vertex_from_node(n).ok_or_else(|| GremlinError::ResponseError)?;
             let traverser = GTraverser{bulk: GInt64(1), value: GItem::Vertex(vertex)};
@@ -131,25 +333,416 @@ pub fn convert_graph_to_gremlin_response(graphs: &Vec<PropertyGraph>, request_id
         let mut r_index = 0;
         for r in graph.get_relationships() {
             let edge_index = EdgeIndex::new(r_index);
-            let s_index = graph.get_source_index(&edge_index);
-            let t_index = graph.get_target_index(&edge_index);
-            let label = r.get_labels_ref().join(":");
-            let id = GInt64(r.get_id().ok_or_else(|| GremlinError::ResponseError)? as i64);
-            let source = graph.get_node_ref(&s_index);
-            let target = graph.get_node_ref(&t_index);
-            let edge = GEdge{id: id, label: label, 
-                out_v_abel: target.get_labels_ref().join(":"),
-                in_v_label: source.get_labels_ref().join(":"),
-                in_v: GInt64(source.get_id().ok_or_else(|| GremlinError::ResponseError)? as i64),
-                out_v: GInt64(target.get_id().ok_or_else(|| GremlinError::ResponseError)? as i64),
-                properties: build_edge_properties(r).ok_or_else(|| GremlinError::ResponseError)?,
-            };
-            let traverser = GTraverser{bulk: GInt64(1), value: GItem::Edge(edge)};
+            let source = graph.get_node_ref(&graph.get_source_index(&edge_index));
+            let target = graph.get_node_ref(&graph.get_target_index(&edge_index));
+            let traverser = GTraverser{bulk: GInt64(1), value: GItem::Edge(build_edge(r, source, target)?)};
             res.data.values.push(traverser);
             r_index += 1;
         }
     }
     
+    res.data.values = apply_traverser_steps(res.data.values, traverser_steps);
     let attrs = GMap::new();
     Ok(GremlinResponse{request_id: String::from(request_id), status: GStatus{message: String::from(""), code: 200, attributes: attrs}, result: res})
-}
\ No newline at end of file
+}
+
+fn get_item_id(item: &GItem) -> i64 {
+    match item {
+        GItem::Vertex(v) => match &v.id {
+            GValue::Integer(GInteger::I64(id)) => id.0,
+            GValue::Integer(GInteger::I32(id)) => id.0 as i64,
+            _ => 0,
+        },
+        GItem::Edge(e) => e.id.0,
+        _ => 0,
+    }
+}
+
+fn get_item_properties(item: &GItem) -> Option<&GProperties> {
+    match item {
+        GItem::Vertex(v) => Some(&v.properties),
+        GItem::Edge(e) => Some(&e.properties),
+        _ => None,
+    }
+}
+
+fn get_item_property<'a>(item: &'a GItem, name: &str) -> Option<&'a GValue> {
+    match item {
+        GItem::Map(m) => m.map.get(name).and_then(|values| values.values.first()),
+        _ => get_item_properties(item)?.properties.iter().find(|p| p.name == name).and_then(|p| p.values.first()).map(|v| &v.1),
+    }
+}
+
+fn get_projected_properties<'a>(item: &'a GItem, names: &Vec<String>) -> Vec<&'a GProperty> {
+    get_item_properties(item).map_or(Vec::new(), |properties| properties.properties.iter()
+        .filter(|p| names.is_empty() || names.contains(&p.name)).collect())
+}
+
+fn project_values(traversers: Vec<GTraverser>, names: &Vec<String>) -> Vec<GTraverser> {
+    let mut res = Vec::new();
+    for traverser in &traversers {
+        for property in get_projected_properties(&traverser.value, names) {
+            for value in &property.values {
+                res.push(GTraverser{bulk: traverser.bulk, value: GItem::Value(value.1.clone())});
+            }
+        }
+    }
+    res
+}
+
+fn project_value_map(traversers: Vec<GTraverser>, names: &Vec<String>) -> Vec<GTraverser> {
+    let mut res = Vec::new();
+    for traverser in &traversers {
+        let mut value_map = GMap::new();
+        for property in get_projected_properties(&traverser.value, names) {
+            let values = property.values.iter().map(|v| v.1.clone()).collect();
+            value_map.map.insert(property.name.clone(), GList{values: values});
+        }
+        res.push(GTraverser{bulk: traverser.bulk, value: GItem::Map(value_map)});
+    }
+    res
+}
+
+fn get_value_rank(value: &GValue) -> u8 {
+    match value {
+        GValue::Null => 0,
+        GValue::Bool(_) => 1,
+        GValue::Integer(_) | GValue::Double(_) | GValue::Float(_) => 2,
+        GValue::Date(_) | GValue::Timestamp(_) => 3,
+        GValue::String(_) | GValue::Uuid(_) => 4,
+        GValue::List(_) | GValue::Set(_) => 5,
+        GValue::Map(_) => 6,
+    }
+}
+
+fn get_number(value: &GValue) -> Option<f64> {
+    match value {
+        GValue::Integer(GInteger::I64(v)) => Some(v.0 as f64),
+        GValue::Integer(GInteger::I32(v)) => Some(v.0 as f64),
+        GValue::Double(v) => Some(v.0),
+        GValue::Float(v) => Some(v.0 as f64),
+        _ => None,
+    }
+}
+
+pub fn compare_gremlin_values(v0: &GValue, v1: &GValue) -> Ordering {
+    match (v0, v1) {
+        (GValue::Bool(b0), GValue::Bool(b1)) => b0.cmp(b1),
+        (GValue::String(s0), GValue::String(s1)) => s0.cmp(s1),
+        (GValue::Uuid(u0), GValue::Uuid(u1)) => u0.0.cmp(&u1.0),
+        (GValue::Date(GDate(d0)) | GValue::Timestamp(GTimestamp(d0)), GValue::Date(GDate(d1)) | GValue::Timestamp(GTimestamp(d1))) => d0.cmp(d1),
+        _ => match (get_number(v0), get_number(v1)) {
+            (Some(n0), Some(n1)) => n0.total_cmp(&n1),
+            _ => get_value_rank(v0).cmp(&get_value_rank(v1)),
+        }
+    }
+}
+
+fn compare_items(t0: &GTraverser, t1: &GTraverser, order_by: &Vec<GOrderBy>) -> Ordering {
+    for by in order_by {
+        let ordering = match &by.key {
+            Some(key) => match (get_item_property(&t0.value, key), get_item_property(&t1.value, key)) {
+                (Some(v0), Some(v1)) => compare_gremlin_values(v0, v1),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            },
+            None => match (&t0.value, &t1.value) {
+                (GItem::Value(v0), GItem::Value(v1)) => compare_gremlin_values(v0, v1),
+                _ => get_item_id(&t0.value).cmp(&get_item_id(&t1.value)),
+            },
+        };
+        let ordering = if by.order == GOrder::Desc { ordering.reverse() } else { ordering };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    Ordering::Equal
+}
+
+fn shuffle(traversers: &mut Vec<GTraverser>) {
+    let random_state = RandomState::new();
+    let mut keyed = traversers.drain(..).enumerate().map(|(index, t)| {
+        let mut hasher = random_state.build_hasher();
+        index.hash(&mut hasher);
+        (hasher.finish(), t)
+    }).collect::<Vec<(u64, GTraverser)>>();
+    keyed.sort_by_key(|(key, _)| *key);
+    traversers.extend(keyed.into_iter().map(|(_, t)| t));
+}
+
+/// Items of the traversers, each one being repeated as many times as its bulk.
+fn fold(traversers: Vec<GTraverser>) -> Vec<GItem> {
+    let mut items = Vec::new();
+    for traverser in traversers {
+        for _ in 1..traverser.bulk.0 {
+            items.push(traverser.value.clone());
+        }
+        if traverser.bulk.0 > 0 {
+            items.push(traverser.value);
+        }
+    }
+    items
+}
+
+/// Lists are unfolded into one traverser per element keeping the bulk of the list, other items are kept.
+fn unfold(traversers: Vec<GTraverser>) -> Vec<GTraverser> {
+    let mut res = Vec::new();
+    for traverser in traversers {
+        let bulk = traverser.bulk;
+        match traverser.value {
+            GItem::List(items) => {
+                res.extend(items.into_iter().map(|value| GTraverser{bulk, value}));
+            }
+            GItem::Value(GValue::List(values)) | GItem::Value(GValue::Set(values)) => {
+                res.extend(values.into_iter().map(|value| GTraverser{bulk, value: GItem::Value(value)}));
+            }
+            value => res.push(GTraverser{bulk, value}),
+        }
+    }
+    res
+}
+
+/// Applies the ordering, pagination, projection and aggregation steps to the response traversers.
+pub fn apply_traverser_steps(mut traversers: Vec<GTraverser>, steps: &Vec<GStep>) -> Vec<GTraverser> {
+    for step in steps {
+        match step {
+            GStep::Order(order_by) => {
+                if order_by.iter().any(|by| by.order == GOrder::Shuffle) {
+                    shuffle(&mut traversers);
+                } else if order_by.is_empty() {
+                    traversers.sort_by(|t0, t1| compare_items(t0, t1, &vec![GOrderBy{key: None, order: GOrder::Asc}]));
+                } else {
+                    traversers.sort_by(|t0, t1| compare_items(t0, t1, order_by));
+                }
+            }
+            GStep::Range(low, high) => {
+                let skip = *low as usize;
+                let take = high.map_or(usize::MAX, |h| (h as usize).saturating_sub(skip));
+                traversers = traversers.into_iter().skip(skip).take(take).collect();
+            }
+            GStep::Limit(limit) => {
+                traversers.truncate(*limit as usize);
+            }
+            GStep::Values(names) => {
+                traversers = project_values(traversers, names);
+            }
+            GStep::ValueMap(names) => {
+                traversers = project_value_map(traversers, names);
+            }
+            GStep::Count => {
+                let count = traversers.iter().map(|t| t.bulk.0).sum();
+                traversers = vec![GTraverser{bulk: GInt64(1), value: GItem::Value(GValue::Integer(GInteger::I64(GInt64(count))))}];
+            }
+            GStep::Fold => {
+                traversers = vec![GTraverser{bulk: GInt64(1), value: GItem::List(fold(traversers))}];
+            }
+            GStep::Unfold => {
+                traversers = unfold(traversers);
+            }
+            _ => {}
+        }
+    }
+    traversers
+}
+
+#[cfg(test)]
+mod test_utils {
+    use super::*;
+    use serde_json::json;
+
+    fn make_traverser(id: i64, age: i64) -> GTraverser {
+        let properties = GProperties{properties: vec![GProperty{name: String::from("age"), values: vec![(GInt64(id), GValue::Integer(GInteger::I64(GInt64(age))))]}]};
+        GTraverser{bulk: GInt64(1), value: GItem::Vertex(GVertex{id: GValue::Integer(GInteger::I64(GInt64(id))), label: String::from("person"), properties: properties})}
+    }
+
+    fn get_ids(traversers: &Vec<GTraverser>) -> Vec<i64> {
+        traversers.iter().map(|t| get_item_id(&t.value)).collect()
+    }
+
+    #[test]
+    fn test_order_and_range() {
+        let traversers = vec![make_traverser(1, 30), make_traverser(2, 25), make_traverser(3, 40), make_traverser(4, 35)];
+        let steps = vec![GStep::Order(vec![GOrderBy{key: Some(String::from("age")), order: GOrder::Desc}]), GStep::Range(1, Some(3))];
+        assert_eq!(vec![4, 1], get_ids(&apply_traverser_steps(traversers, &steps)));
+    }
+
+    #[test]
+    fn test_limit() {
+        let traversers = vec![make_traverser(3, 30), make_traverser(1, 25), make_traverser(2, 40)];
+        let steps = vec![GStep::Order(Vec::new()), GStep::Limit(2)];
+        assert_eq!(vec![1, 2], get_ids(&apply_traverser_steps(traversers, &steps)));
+    }
+
+    #[test]
+    fn test_values_and_value_map() {
+        let traversers = vec![make_traverser(1, 30), make_traverser(2, 25)];
+        let steps = vec![GStep::Order(vec![GOrderBy{key: Some(String::from("age")), order: GOrder::Asc}]), GStep::Values(vec![String::from("age")])];
+        let values = apply_traverser_steps(traversers, &steps).into_iter().map(|t| match t.value {
+            GItem::Value(v) => v,
+            _ => GValue::Bool(false),
+        }).collect::<Vec<GValue>>();
+        assert_eq!(vec![GValue::Integer(GInteger::I64(GInt64(25))), GValue::Integer(GInteger::I64(GInt64(30)))], values);
+
+        let maps = apply_traverser_steps(vec![make_traverser(1, 30)], &vec![GStep::ValueMap(Vec::new())]);
+        match &maps[0].value {
+            GItem::Map(m) => assert_eq!(vec![GValue::Integer(GInteger::I64(GInt64(30)))], m.map["age"].values),
+            _ => assert!(false),
+        }
+        assert!(apply_traverser_steps(vec![make_traverser(1, 30)], &vec![GStep::Values(vec![String::from("name")])]).is_empty());
+    }
+
+    #[test]
+    fn test_count_fold_and_unfold() {
+        let mut traversers = vec![make_traverser(1, 30), make_traverser(2, 25)];
+        traversers[1].bulk = GInt64(3);
+        let count = apply_traverser_steps(traversers.clone(), &vec![GStep::Count]);
+        assert_eq!(1, count.len());
+        assert_eq!(json!({"@type": "g:Int64", "@value": 4}), count[0].value.to_json());
+        let empty_count = apply_traverser_steps(Vec::new(), &vec![GStep::Count]);
+        assert_eq!(json!({"@type": "g:Int64", "@value": 0}), empty_count[0].value.to_json());
+
+        let folded = apply_traverser_steps(traversers.clone(), &vec![GStep::Fold]);
+        assert_eq!(1, folded.len());
+        let json = folded[0].value.to_json();
+        assert_eq!("g:List", json["@type"]);
+        assert_eq!(4, json["@value"].as_array().expect("list").len());
+        assert_eq!("g:Vertex", json["@value"][3]["@type"]);
+
+        let unfolded = apply_traverser_steps(traversers, &vec![GStep::Fold, GStep::Unfold, GStep::Count]);
+        assert_eq!(json!({"@type": "g:Int64", "@value": 4}), unfolded[0].value.to_json());
+        let values = apply_traverser_steps(vec![make_traverser(1, 30), make_traverser(2, 25)], &vec![GStep::Values(vec![String::from("age")]), GStep::Fold, GStep::Unfold]);
+        assert_eq!(vec![30, 25], values.iter().map(|t| t.value.to_json()["@value"].as_i64().expect("age")).collect::<Vec<i64>>());
+    }
+
+    #[test]
+    fn test_expand_patterns_both_directions() {
+        let mut context = StateContext::new();
+        init_pattern(&mut context, Node::new());
+        let source = context.node_index.expect("source");
+        let mut rel = Relationship::new();
+        rel.set_labels(vec![String::from("knows")]);
+        expand_patterns(&mut context, &Node::new(), &rel, ExpandDirection::Both).expect("both");
+        assert_eq!(2, context.patterns.len());
+        let target = context.node_index.expect("target");
+        let out_rel = &context.patterns[0].get_edges()[0];
+        assert_eq!((source, target), (out_rel.get_source(), out_rel.get_target()));
+        let in_rel = &context.patterns[1].get_edges()[0];
+        assert_eq!((target, source), (in_rel.get_source(), in_rel.get_target()));
+
+        expand_patterns(&mut context, &Node::new(), &rel, ExpandDirection::In).expect("in");
+        assert_eq!(2, context.patterns.len());
+        init_pattern(&mut context, Node::new());
+        expand_patterns(&mut context, &Node::new(), &rel, ExpandDirection::Both).expect("both");
+        assert_eq!(4, context.patterns.len());
+        assert_eq!(2, context.current_patterns_mut().expect("patterns").len());
+    }
+
+    #[test]
+    fn test_property_cardinality() {
+        let mut props = Vec::new();
+        let marko = || PropertyValue::PString(String::from("marko"));
+        set_property_value(&mut props, "name", marko(), GCardinality::Single);
+        set_property_value(&mut props, "name", PropertyValue::PString(String::from("mko")), GCardinality::Single);
+        assert_eq!(vec![Property::new(String::from("name"), PropertyValue::PString(String::from("mko")))], props);
+
+        set_property_value(&mut props, "alias", marko(), GCardinality::List);
+        set_property_value(&mut props, "alias", marko(), GCardinality::List);
+        set_property_value(&mut props, "alias", marko(), GCardinality::Set);
+        set_property_value(&mut props, "alias", PropertyValue::PInteger(1), GCardinality::Set);
+        assert_eq!(PropertyValue::PList(vec![marko(), marko(), PropertyValue::PInteger(1)]), *props[1].get_value());
+
+        let mut alias = props[1].clone();
+        alias.set_id(Some(3));
+        let gprop = build_property(&alias).expect("property");
+        assert_eq!(3, gprop.values.len());
+        assert!(gprop.values.iter().all(|v| v.0 == GInt64(3)));
+        assert!(PropertyPredicate::EqualTo(PropertyValue::PInteger(1)).eval(alias.get_value()));
+    }
+
+    #[test]
+    fn test_map_property_conversion() {
+        let gmap = GValue::Map(vec![(String::from("city"), GValue::String(String::from("Santa Fe"))),
+            (String::from("tags"), GValue::List(vec![GValue::Bool(true)]))]);
+        let value = prop_value_from_gremlin_value(&gmap);
+        assert!(matches!(&value, PropertyValue::PMap(entries) if entries.len() == 2));
+        let mut prop = Property::new(String::from("address"), value);
+        prop.set_id(Some(5));
+        let values = gremlin_values_from_property_value(&prop).expect("values");
+        assert_eq!(1, values.len());
+        assert_eq!("Santa Fe", values[0].1.to_json()["@value"][1].as_str().unwrap());
+    }
+
+    #[test]
+    fn test_temporal_property_conversion() {
+        let value = prop_value_from_gremlin_value(&GValue::Date(GDate(1481750076295)));
+        assert_eq!(PropertyValue::PDateTime(DateTime::new(1481750076295 * 1_000_000, 0)), value);
+        assert_eq!(GValue::Date(GDate(1481750076295)), gremlin_value_from_value(&value));
+        let set = prop_value_from_gremlin_value(&GValue::Set(vec![GValue::Float(GFloat(0.5)), GValue::Uuid(GUuid(String::from("41d2e28a-20a4-4ab0-b379-d810dede3786")))]));
+        assert!(matches!(&set, PropertyValue::PList(values) if matches!(values[..], [PropertyValue::PFloat(f), PropertyValue::PString(ref uuid)] if f == 0.5 && uuid == "41d2e28a-20a4-4ab0-b379-d810dede3786")));
+    }
+
+    #[test]
+    fn test_select_and_path_projection() {
+        let make_node = |id: u64, alias: Option<&str>| {
+            let mut node = Node::new();
+            node.set_id(Some(id));
+            if let Some(alias) = alias {
+                node.set_var(alias);
+            }
+            node
+        };
+        let make_rel = |id: u64| {
+            let mut rel = Relationship::new();
+            rel.set_id(Some(id));
+            rel
+        };
+        let mut graph = PropertyGraph::new();
+        let a = graph.add_node(make_node(1, Some("a")));
+        let b = graph.add_node(make_node(2, None));
+        let c = graph.add_node(make_node(3, Some("c")));
+        graph.add_relationship(make_rel(10), a, b);
+        graph.add_relationship(make_rel(11), c, b);
+        let graphs = vec![graph];
+
+        let select = |aliases: Vec<&str>| {
+            let steps = vec![GStep::Select(aliases.into_iter().map(String::from).collect())];
+            convert_graph_to_gremlin_response(&graphs, "1", &steps).expect("response").result.data.values
+        };
+        let single = select(vec!["c"]);
+        assert_eq!(1, single.len());
+        assert_eq!(3, get_item_id(&single[0].value));
+        let both = select(vec!["a", "c"]).remove(0).value.to_json();
+        assert_eq!("g:Map", both["@type"]);
+        assert_eq!(4, both["@value"].as_array().expect("map").len());
+        assert!(select(vec!["a", "b"]).is_empty());
+
+        let path = convert_graph_to_gremlin_response(&graphs, "1", &vec![GStep::Path]).expect("response").result.data.values.remove(0).value.to_json();
+        let objects = path["@value"]["objects"]["@value"].as_array().expect("objects").clone();
+        assert_eq!(vec![1, 10, 2, 11, 3], objects.iter().map(|object| object["@value"]["id"]["@value"].as_i64().expect("id")).collect::<Vec<i64>>());
+        assert_eq!(json!(["c"]), path["@value"]["labels"]["@value"][4]["@value"]);
+        assert_eq!(json!([]), path["@value"]["labels"]["@value"][2]["@value"]);
+    }
+
+    #[test]
+    fn test_build_path() {
+        let make_node = |id: u64| {
+            let mut node = Node::new();
+            node.set_id(Some(id));
+            node.set_labels(vec![String::from("person")]);
+            node
+        };
+        let mut rel = Relationship::new();
+        rel.set_id(Some(7));
+        rel.set_labels(vec![String::from("knows")]);
+        let mut path = Path::new(make_node(1));
+        path.append(rel, false, make_node(2));
+        let json = GItem::Path(build_path(&path).expect("path")).to_json();
+        assert_eq!("g:Path", json["@type"]);
+        let objects = &json["@value"]["objects"]["@value"];
+        assert_eq!(3, objects.as_array().expect("objects").len());
+        assert_eq!("g:Edge", objects[1]["@type"]);
+        assert_eq!(2, objects[1]["@value"]["inV"]["@value"]);
+        assert_eq!(3, json["@value"]["labels"]["@value"].as_array().expect("labels").len());
+    }
+}
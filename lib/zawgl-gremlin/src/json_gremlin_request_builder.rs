@@ -23,7 +23,10 @@ use crate::handler::GremlinError;
 
 use super::gremlin::*;
 use serde_json::Map;
+use std::convert::TryFrom;
+use std::time::Duration;
 use serde_json::Value;
+use zawgl_tx_handler::tx_context::IsolationLevel;
 
 
 pub fn build_gremlin_request_from_json(value: &Value) -> Result<GremlinRequest, GremlinError> {
@@ -41,6 +44,10 @@ pub fn build_gremlin_request_from_json(value: &Value) -> Result<GremlinRequest,
         let mut manage_transaction = None;
         let mut maintain_state_after_exception = None;
         let mut commit_tx = false;
+        let mut rollback_tx = false;
+        let mut read_only = false;
+        let mut isolation = IsolationLevel::default();
+        let mut evaluation_timeout = None;
         for index in 0..gmap_values.len()/2 {
           let key = gmap_values[index * 2].as_str().ok_or_else(|| GremlinError::RequestError)?;
           let value = &gmap_values[index * 2 + 1];
@@ -52,15 +59,22 @@ pub fn build_gremlin_request_from_json(value: &Value) -> Result<GremlinRequest,
                 },
                 GBytecode::Source(gremlin_source) => {
                   commit_tx = gremlin_source == GSource::TxCommit;
+                  rollback_tx = gremlin_source == GSource::TxRollback;
                 },
             }
            
           } else if key == "session" {
-            session = value.as_str().ok_or_else(|| GremlinError::RequestError)?;
+            session = get_session_id(value).ok_or_else(|| GremlinError::RequestError)?;
           } else if key == "manageTransaction" {
             manage_transaction = value.as_bool();
           } else if key == "maintainStateAfterException" {
             maintain_state_after_exception = value.as_bool();
+          } else if key == "readOnly" {
+            read_only = value.as_bool().ok_or_else(|| GremlinError::RequestError)?;
+          } else if key == "isolation" {
+            isolation = value.as_str().and_then(IsolationLevel::from_name).ok_or_else(|| GremlinError::RequestError)?;
+          } else if key == "evaluationTimeout" {
+            evaluation_timeout = get_evaluation_timeout(value).ok_or_else(|| GremlinError::RequestError)?;
           }
         }
         
@@ -69,6 +83,7 @@ pub fn build_gremlin_request_from_json(value: &Value) -> Result<GremlinRequest,
             request_id: String::from(req_id), 
             data: req_data,
             session: None,
+            evaluation_timeout,
           })
         } else if processor == "session" {
           return Ok(GremlinRequest{
@@ -79,14 +94,63 @@ pub fn build_gremlin_request_from_json(value: &Value) -> Result<GremlinRequest,
               manage_transaction: manage_transaction.ok_or_else(|| GremlinError::RequestError)?,
               maintain_state_after_exception: maintain_state_after_exception.ok_or_else(|| GremlinError::RequestError)?,
               commit: commit_tx,
-            })
+              rollback: rollback_tx,
+              read_only,
+              isolation,
+            }),
+            evaluation_timeout,
           });
         }
       }
+    } else if op == "close" && processor == "session" {
+      let session = get_argument(args, "session").and_then(get_session_id).ok_or_else(|| GremlinError::RequestError)?;
+      // closing a session discards its uncommitted changes
+      return Ok(GremlinRequest{
+        request_id: String::from(req_id),
+        data: None,
+        session: Some(GremlinSession {
+          session_id: String::from(session),
+          manage_transaction: false,
+          maintain_state_after_exception: false,
+          commit: false,
+          rollback: true,
+          read_only: false,
+          isolation: IsolationLevel::default(),
+        }),
+        evaluation_timeout: None,
+      });
     }
     Err(GremlinError::RequestError)
 }
 
+/// Session ids are sent either as strings or as typed UUIDs.
+fn get_session_id(value: &Value) -> Option<&str> {
+  match build_gremlin_value(value)? {
+    GValue::String(_) => value.as_str(),
+    GValue::Uuid(_) => value["@value"].as_str(),
+    _ => None,
+  }
+}
+
+/// Timeouts are sent in milliseconds, a zero timeout disables the one of the server.
+fn get_evaluation_timeout(value: &Value) -> Option<Option<Duration>> {
+  let millis = match build_gremlin_value(value)? {
+    GValue::Integer(GInteger::I32(GInt32(millis))) => u64::try_from(millis).ok()?,
+    GValue::Integer(GInteger::I64(GInt64(millis))) => u64::try_from(millis).ok()?,
+    _ => return None,
+  };
+  Some(Some(Duration::from_millis(millis)).filter(|timeout| !timeout.is_zero()))
+}
+
+fn get_argument<'a>(args: &'a Value, key: &str) -> Option<&'a Value> {
+  if args["@type"].as_str() == Some("g:Map") {
+    let values = args["@value"].as_array()?;
+    values.chunks(2).find(|entry| entry[0].as_str() == Some(key)).and_then(|entry| entry.get(1))
+  } else {
+    args.get(key)
+  }
+}
+
 fn build_gremlin_bytecode(bytecode: &Value) -> Option<GBytecode> {
   let bytecode_type = bytecode["@type"].as_str()?;
   if bytecode_type == "g:Bytecode" {
@@ -101,6 +165,18 @@ fn build_gremlin_bytecode(bytecode: &Value) -> Option<GBytecode> {
       let gsteps = steps.as_array()?;
       let mut gremlin_steps = Vec::new();
       for step in gsteps {
+          if let Some(GStep::Order(order_by)) = gremlin_steps.last_mut() {
+            if let Some(by) = order_by_modulator(step) {
+              order_by.push(by?);
+              continue;
+            }
+          }
+          if let Some(GStep::Repeat(repeat)) = gremlin_steps.last_mut() {
+            if let Some(modulated) = repeat_modulator(step, repeat) {
+              modulated?;
+              continue;
+            }
+          }
           let mut gremlin_step = build_gremlin_step(step)?;
           gremlin_steps.append(&mut gremlin_step);
       }
@@ -124,7 +200,13 @@ fn build_gremlin_step(step: &Value) -> Option<Vec<GStep>> {
         vec![add_v(elts)?]
       },
       "has" => {
-        vec![has_property(elts)?]
+        has_steps(elts)?
+      },
+      "hasLabel" => {
+        vec![has_label(&elts[1..])?]
+      },
+      "hasId" => {
+        vec![has_id(&elts[1..])?]
       },
       "addE" => {
         vec![add_e(elts)?]
@@ -138,6 +220,27 @@ fn build_gremlin_step(step: &Value) -> Option<Vec<GStep>> {
       "out" => {
           match_out(elts)?
       },
+      "inE" => {
+        vec![match_in_e(elts)?]
+      },
+      "in" => {
+          match_in(elts)?
+      },
+      "outV" => {
+        vec![match_out_v()?]
+      },
+      "bothE" => {
+        vec![match_both_e(elts)?]
+      },
+      "both" => {
+          match_both(elts)?
+      },
+      "otherV" => {
+        vec![match_other_v()?]
+      },
+      "drop" => {
+        vec![GStep::Drop]
+      },
       "as" => {
         vec![as_step(elts)?]
       },
@@ -153,6 +256,42 @@ fn build_gremlin_step(step: &Value) -> Option<Vec<GStep>> {
       "property" => {
         vec![set_property_step(elts)?]
       }
+      "values" => {
+        vec![GStep::Values(property_names(elts))]
+      }
+      "valueMap" => {
+        vec![GStep::ValueMap(property_names(elts))]
+      }
+      "order" => {
+        vec![GStep::Order(Vec::new())]
+      }
+      "range" => {
+        vec![range_step(elts)?]
+      }
+      "limit" => {
+        vec![limit_step(elts)?]
+      }
+      "repeat" => {
+        vec![repeat_step(elts)?]
+      }
+      "where" => {
+        vec![GStep::Where(anonymous_traversal_steps(elts.get(1)?)?)]
+      }
+      "select" => {
+        vec![select_step(elts)?]
+      }
+      "path" => {
+        vec![GStep::Path]
+      }
+      "count" => {
+        vec![GStep::Count]
+      }
+      "fold" => {
+        vec![GStep::Fold]
+      }
+      "unfold" => {
+        vec![GStep::Unfold]
+      }
       _ => {
         vec![GStep::Empty]
       }
@@ -178,8 +317,12 @@ fn build_gremlin_source(step: &Value) -> Option<GSource> {
 
 
 fn set_property_step(json_step: &Vec<Value>) -> Option<GStep> {
-  let name = json_step.get(1)?.as_str()?;
-  let value = &json_step[2];
+  let (cardinality, skip) = match json_step.get(1).and_then(build_cardinality) {
+    Some(cardinality) => (cardinality, 1),
+    None => (GCardinality::Single, 0),
+  };
+  let name = json_step.get(1 + skip)?.as_str()?;
+  let value = json_step.get(2 + skip)?;
   if value.is_object() && value["@type"] == "g:Bytecode" {
     let gbytecode = build_gremlin_bytecode(value)?;
     match gbytecode {
@@ -187,10 +330,114 @@ fn set_property_step(json_step: &Vec<Value>) -> Option<GStep> {
         GBytecode::Source(_) => None,
     }
   } else {
-    Some(GStep::SetProperty(String::from(name), build_gremlin_value(value)?))
+    Some(GStep::SetProperty(String::from(name), build_gremlin_value(value)?, cardinality))
   }  
 }
 
+fn property_names(json_step: &Vec<Value>) -> Vec<String> {
+  json_step[1..].iter().filter_map(|name| name.as_str()).map(|name| String::from(name)).collect()
+}
+
+fn select_step(json_step: &Vec<Value>) -> Option<GStep> {
+  let aliases = property_names(json_step);
+  if aliases.is_empty() {
+    None
+  } else {
+    Some(GStep::Select(aliases))
+  }
+}
+
+fn build_cardinality(json: &Value) -> Option<GCardinality> {
+  let cardinality = match json {
+    Value::Object(obj) if obj.get("@type")?.as_str()? == "g:Cardinality" => obj.get("@value")?.as_str()?,
+    _ => return None,
+  };
+  match cardinality {
+    "single" => Some(GCardinality::Single),
+    "list" => Some(GCardinality::List),
+    "set" => Some(GCardinality::Set),
+    _ => None,
+  }
+}
+
+fn build_order(json: &Value) -> Option<GOrder> {
+  let order = match json {
+    Value::Object(obj) if obj.get("@type")?.as_str()? == "g:Order" => obj.get("@value")?.as_str()?,
+    _ => return None,
+  };
+  match order {
+    "asc" | "incr" => Some(GOrder::Asc),
+    "desc" | "decr" => Some(GOrder::Desc),
+    "shuffle" => Some(GOrder::Shuffle),
+    _ => None,
+  }
+}
+
+fn anonymous_traversal_steps(json: &Value) -> Option<Vec<GStep>> {
+  match build_gremlin_bytecode(json)? {
+    GBytecode::Steps(steps) => Some(steps),
+    GBytecode::Source(_) => None,
+  }
+}
+
+fn repeat_step(json_step: &Vec<Value>) -> Option<GStep> {
+  let steps = anonymous_traversal_steps(json_step.get(1)?)?;
+  Some(GStep::Repeat(GRepeat{steps, times: None, until: None}))
+}
+
+/// Applies a times() or until() modulator to the repeat step, returns None if the step is not a repeat modulator.
+fn repeat_modulator(json: &Value, repeat: &mut GRepeat) -> Option<Option<()>> {
+  let elts = json.as_array()?;
+  match elts.first()?.as_str()? {
+    "times" => {
+      repeat.times = elts.get(1).and_then(build_positive_integer);
+      Some(repeat.times.map(|_| ()))
+    }
+    "until" => {
+      repeat.until = elts.get(1).and_then(anonymous_traversal_steps);
+      Some(repeat.until.as_ref().map(|_| ()))
+    }
+    _ => None,
+  }
+}
+
+/// Parses a by() modulator, returns None if the step is not a by() step.
+fn order_by_modulator(json: &Value) -> Option<Option<GOrderBy>> {
+  let elts = json.as_array()?;
+  if elts.first()?.as_str()? != "by" {
+    return None;
+  }
+  let mut key = None;
+  let mut order = GOrder::Asc;
+  for arg in &elts[1..] {
+    match arg {
+      Value::String(name) => key = Some(name.clone()),
+      _ => match build_order(arg) {
+        Some(o) => order = o,
+        None => return Some(None),
+      }
+    }
+  }
+  Some(Some(GOrderBy{key: key, order: order}))
+}
+
+fn build_positive_integer(json: &Value) -> Option<u64> {
+  u64::try_from(build_gremlin_value(json)?).ok()
+}
+
+fn range_step(json_step: &Vec<Value>) -> Option<GStep> {
+  if json_step.len() < 3 {
+    return None;
+  }
+  let low = build_positive_integer(&json_step[json_step.len() - 2])?;
+  let high = &json_step[json_step.len() - 1];
+  Some(GStep::Range(low, build_positive_integer(high)))
+}
+
+fn limit_step(json_step: &Vec<Value>) -> Option<GStep> {
+  Some(GStep::Limit(build_positive_integer(json_step.last()?)?))
+}
+
 fn match_step(json_step: &Vec<Value>) -> Option<GStep> {
   let mut bytecodes = Vec::new();
   for bc in &json_step[1..] {
@@ -214,6 +461,8 @@ fn tx_source(json: &Vec<Value>) -> Option<GSource> {
   let tx_value = var.as_str()?;
   if tx_value == "commit" {
     Some(GSource::TxCommit)
+  } else if tx_value == "rollback" {
+    Some(GSource::TxRollback)
   } else {
     None
   }
@@ -273,37 +522,112 @@ fn match_e(json_step: &Vec<Value>) -> Option<GStep> {
 }
 
 
-fn match_out_e(json_step: &Vec<Value>) -> Option<GStep> {
+fn edge_labels(json_step: &Vec<Value>) -> Option<Vec<String>> {
   let mut labels = Vec::new();
   for value in &json_step[1..] {
     labels.push(String::from(value.as_str()?));
   }
-  Some(GStep::OutE(labels))
+  Some(labels)
+}
+
+fn match_out_e(json_step: &Vec<Value>) -> Option<GStep> {
+  Some(GStep::OutE(edge_labels(json_step)?))
+}
+
+fn match_in_e(json_step: &Vec<Value>) -> Option<GStep> {
+  Some(GStep::InE(edge_labels(json_step)?))
+}
+
+fn match_both_e(json_step: &Vec<Value>) -> Option<GStep> {
+  Some(GStep::BothE(edge_labels(json_step)?))
 }
 
 fn match_out(json_step: &Vec<Value>) -> Option<Vec<GStep>> {
   Some(vec![match_out_e(json_step)?, match_in_v()?])
 }
 
+fn match_in(json_step: &Vec<Value>) -> Option<Vec<GStep>> {
+  Some(vec![match_in_e(json_step)?, match_out_v()?])
+}
+
+fn match_both(json_step: &Vec<Value>) -> Option<Vec<GStep>> {
+  Some(vec![match_both_e(json_step)?, match_other_v()?])
+}
+
 fn match_in_v() -> Option<GStep> {
   Some(GStep::InV)
 }
 
+fn match_out_v() -> Option<GStep> {
+  Some(GStep::OutV)
+}
+
+fn match_other_v() -> Option<GStep> {
+  Some(GStep::OtherV)
+}
+
 fn add_v(json_step: &Vec<Value>) -> Option<GStep> {
     let label = json_step.get(1)?.as_str()?;
     Some(GStep::AddV(String::from(label)))
 }
 
+/// has(key, predicate), has(T.label | T.id, predicate) or has(label, key, predicate).
+fn has_steps(json_step: &Vec<Value>) -> Option<Vec<GStep>> {
+    match json_step.len() {
+        3 => match json_step[1].get("@type").and_then(|t| t.as_str()) {
+            Some("g:T") => match json_step[1].get("@value")?.as_str()? {
+                "label" => Some(vec![has_label(&json_step[2..])?]),
+                "id" => Some(vec![has_id(&json_step[2..])?]),
+                _ => None,
+            },
+            _ => Some(vec![has_property(json_step)?]),
+        },
+        4 => {
+            let has = has_property(&json_step[1..].to_vec())?;
+            Some(vec![has_label(&json_step[1..2])?, has])
+        },
+        _ => None,
+    }
+}
+
 fn has_property(json_step: &Vec<Value>) -> Option<GStep> {
     let name = json_step.get(1)?.as_str()?;
     Some(GStep::Has(String::from(name), build_predicate(json_step.get(2)?)?))
 }
 
+/// Values of the arguments, within predicates being flattened.
+fn predicate_values(args: &[Value]) -> Option<Vec<GValue>> {
+    let mut values = Vec::new();
+    for arg in args {
+        match build_predicate(arg)? {
+            GPredicate::Value(value) => values.push(value),
+            GPredicate::Within(list) => values.extend(list.values),
+        }
+    }
+    if values.is_empty() {
+        None
+    } else {
+        Some(values)
+    }
+}
+
+fn has_label(args: &[Value]) -> Option<GStep> {
+    let labels = predicate_values(args)?.iter().map(|value| value.as_str().map(String::from)).collect::<Option<Vec<String>>>()?;
+    Some(GStep::HasLabel(labels))
+}
+
+fn has_id(args: &[Value]) -> Option<GStep> {
+    Some(GStep::HasId(predicate_values(args)?))
+}
+
 fn build_predicate(json_predicate: &Value) -> Option<GPredicate> {
     match json_predicate {
         Value::String(sval) => {
             Some(GPredicate::Value(GValue::String(String::from(sval))))
         },
+        Value::Object(pobj) if pobj.get("@type")?.as_str()? != "g:P" => {
+            Some(GPredicate::Value(build_gremlin_value(json_predicate)?))
+        },
         Value::Object(pobj) => {
             let p = pobj.get("@value")?.as_object()?;
             match p.get("predicate")?.as_str()? {
@@ -314,7 +638,7 @@ fn build_predicate(json_predicate: &Value) -> Option<GPredicate> {
             }
         },
         _ => {
-            None
+            Some(GPredicate::Value(build_gremlin_value(json_predicate)?))
         }
     }
 }
@@ -342,19 +666,88 @@ fn build_gremlin_value(elt: &Value) -> Option<GValue> {
       Value::Object(obj) => {
         let val = obj.get("@value")?;
         match obj.get("@type")?.as_str()? {
-          "g:Int32" => Some(GValue::Integer(GInteger::I32(GInt32(val.as_i64()? as i32)))),
+          "g:Int32" | "gx:Int16" | "gx:Byte" => Some(GValue::Integer(GInteger::I32(GInt32(i32::try_from(val.as_i64()?).ok()?)))),
           "g:Int64" => Some(GValue::Integer(GInteger::I64(GInt64(val.as_i64()?)))),
-          "g:Double" => Some(GValue::Double(GDouble(val.as_f64()?))),
+          "gx:BigInteger" => build_big_integer(val),
+          "g:Double" | "gx:BigDecimal" => Some(GValue::Double(GDouble(build_floating_point(val)?))),
+          "g:Float" => Some(GValue::Float(GFloat(build_floating_point(val)? as f32))),
+          "g:Date" => Some(GValue::Date(GDate(val.as_i64()?))),
+          "g:Timestamp" => Some(GValue::Timestamp(GTimestamp(val.as_i64()?))),
+          "g:UUID" => Some(GValue::Uuid(GUuid(String::from(val.as_str()?)))),
+          "g:Class" | "gx:Char" | "gx:ByteBuffer" | "gx:Duration" | "gx:Instant" | "gx:LocalDate" | "gx:LocalDateTime" | "gx:LocalTime" | "gx:OffsetDateTime" => Some(GValue::String(String::from(val.as_str()?))),
+          "g:List" => Some(GValue::List(build_gremlin_values(val)?)),
+          "g:Set" => Some(GValue::Set(build_gremlin_values(val)?)),
+          "g:Map" => {
+            let items = val.as_array()?;
+            let mut entries = Vec::new();
+            for pair in items.chunks(2) {
+              entries.push((build_map_key(pair.first()?)?, build_gremlin_value(pair.get(1)?)?));
+            }
+            Some(GValue::Map(entries))
+          },
           _ => None
         }
       },
       Value::String(sval) => {
           Some(GValue::String(String::from(sval)))
       }
+      Value::Bool(bval) => {
+          Some(GValue::Bool(*bval))
+      }
+      Value::Number(nval) => {
+          nval.as_i64().map(|ival| GValue::Integer(GInteger::I64(GInt64(ival)))).or_else(|| Some(GValue::Double(GDouble(nval.as_f64()?))))
+      }
+      Value::Null => {
+          Some(GValue::Null)
+      }
       _ => None
     }
 }
 
+fn build_gremlin_values(val: &Value) -> Option<Vec<GValue>> {
+    val.as_array()?.iter().map(build_gremlin_value).collect()
+}
+
+/// Non finite values are written as strings.
+fn build_floating_point(val: &Value) -> Option<f64> {
+    match val {
+      Value::String(sval) => match sval.as_str() {
+        "NaN" => Some(f64::NAN),
+        "Infinity" => Some(f64::INFINITY),
+        "-Infinity" => Some(f64::NEG_INFINITY),
+        _ => sval.parse().ok(),
+      },
+      _ => val.as_f64(),
+    }
+}
+
+/// Big integers are written either as numbers or strings, the ones out of the 64 bits range are kept as doubles.
+fn build_big_integer(val: &Value) -> Option<GValue> {
+    let digits = match val {
+      Value::String(sval) => sval.clone(),
+      Value::Number(nval) => nval.to_string(),
+      _ => return None,
+    };
+    match digits.parse::<i64>() {
+      Ok(ival) => Some(GValue::Integer(GInteger::I64(GInt64(ival)))),
+      Err(_) => Some(GValue::Double(GDouble(digits.parse().ok()?))),
+    }
+}
+
+/// Property maps are keyed by strings, other scalar keys are converted to their string representation.
+fn build_map_key(key: &Value) -> Option<String> {
+    match build_gremlin_value(key)? {
+      GValue::String(sval) | GValue::Uuid(GUuid(sval)) => Some(sval),
+      GValue::Integer(GInteger::I32(ival)) => Some(ival.0.to_string()),
+      GValue::Integer(GInteger::I64(ival)) => Some(ival.0.to_string()),
+      GValue::Double(dval) => Some(dval.0.to_string()),
+      GValue::Float(fval) => Some(fval.0.to_string()),
+      GValue::Bool(bval) => Some(bval.to_string()),
+      GValue::Date(GDate(millis)) | GValue::Timestamp(GTimestamp(millis)) => Some(millis.to_string()),
+      _ => None,
+    }
+}
+
 
 #[cfg(test)]
 mod test_gremlin_json {
@@ -726,4 +1119,217 @@ mod test_gremlin_json {
       let g = build_gremlin_request_from_json(&value).expect("gremlin request");
       assert_eq!("b3a2c6a8-0982-4414-b07f-41ec49009861", g.request_id);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_tx_rollback() {
+      let json = r#"{"requestId":"4a4d1f3c-8b8e-4f38-a7e4-1b3c5f0a2e11","op":"bytecode","processor":"session","args":{"@type":"g:Map","@value":["gremlin",{"@type":"g:Bytecode","@value":{"source":[["tx","rollback"]]}},"aliases",{"@type":"g:Map","@value":["g","g"]},"session","b6e1d5a0-3f8e-4c1b-9e7a-2d4f6a8c0e12","manageTransaction",false,"maintainStateAfterException",false]}}"#;
+      let value: Value = serde_json::from_str(json).expect("json gremlin request");
+      let g = build_gremlin_request_from_json(&value).expect("gremlin request");
+      let session = g.session.expect("session");
+      assert!(session.rollback);
+      assert!(!session.commit);
+      assert!(!session.read_only);
+    }
+
+    #[test]
+    fn test_read_only_session() {
+      let json = r#"{"requestId":"5e2b7c1d-4f3a-4b8e-9c6d-1a0f2e3b4c5d","op":"bytecode","processor":"session","args":{"@type":"g:Map","@value":["gremlin",{"@type":"g:Bytecode","@value":{"step":[["V"]]}},"aliases",{"@type":"g:Map","@value":["g","g"]},"session","b6e1d5a0-3f8e-4c1b-9e7a-2d4f6a8c0e12","manageTransaction",false,"maintainStateAfterException",false,"readOnly",true]}}"#;
+      let value: Value = serde_json::from_str(json).expect("json gremlin request");
+      let g = build_gremlin_request_from_json(&value).expect("gremlin request");
+      assert!(g.session.expect("session").read_only);
+    }
+
+    #[test]
+    fn test_snapshot_session() {
+      let json = r#"{"requestId":"6f3c8d2e-5a4b-4c9f-8d7e-2b1a0f3c4d5e","op":"bytecode","processor":"session","args":{"@type":"g:Map","@value":["gremlin",{"@type":"g:Bytecode","@value":{"step":[["V"]]}},"aliases",{"@type":"g:Map","@value":["g","g"]},"session","b6e1d5a0-3f8e-4c1b-9e7a-2d4f6a8c0e12","manageTransaction",false,"maintainStateAfterException",false,"isolation","snapshot"]}}"#;
+      let value: Value = serde_json::from_str(json).expect("json gremlin request");
+      let g = build_gremlin_request_from_json(&value).expect("gremlin request");
+      assert_eq!(IsolationLevel::Snapshot, g.session.expect("session").isolation);
+      let invalid: Value = serde_json::from_str(&json.replace("\"snapshot\"", "\"serializable\"")).expect("json gremlin request");
+      assert!(build_gremlin_request_from_json(&invalid).is_err());
+    }
+
+    #[test]
+    fn test_evaluation_timeout() {
+      let json = r#"{"requestId":"7c1e9a4b-2d3f-4e5a-8b6c-0d1e2f3a4b5c","op":"bytecode","processor":"traversal","args":{"@type":"g:Map","@value":["gremlin",{"@type":"g:Bytecode","@value":{"step":[["V"]]}},"aliases",{"@type":"g:Map","@value":["g","g"]},"evaluationTimeout",{"@type":"g:Int64","@value":250}]}}"#;
+      let value: Value = serde_json::from_str(json).expect("json gremlin request");
+      let g = build_gremlin_request_from_json(&value).expect("gremlin request");
+      assert_eq!(Some(Duration::from_millis(250)), g.evaluation_timeout);
+      let json = json.replace(r#"{"@type":"g:Int64","@value":250}"#, "0");
+      let value: Value = serde_json::from_str(&json).expect("json gremlin request");
+      assert_eq!(None, build_gremlin_request_from_json(&value).expect("gremlin request").evaluation_timeout);
+      let json = json.replace(r#""evaluationTimeout",0"#, r#""evaluationTimeout","soon""#);
+      let value: Value = serde_json::from_str(&json).expect("json gremlin request");
+      assert!(build_gremlin_request_from_json(&value).is_err());
+    }
+
+    #[test]
+    fn test_session_close() {
+      let json = r#"{"requestId":"9d3f6b2a-5c1e-4a7d-8b0f-3e2c1a9d7f64","op":"close","processor":"session","args":{"@type":"g:Map","@value":["session","b6e1d5a0-3f8e-4c1b-9e7a-2d4f6a8c0e12"]}}"#;
+      let value: Value = serde_json::from_str(json).expect("json gremlin request");
+      let g = build_gremlin_request_from_json(&value).expect("gremlin request");
+      assert!(g.data.is_none());
+      let session = g.session.expect("session");
+      assert_eq!("b6e1d5a0-3f8e-4c1b-9e7a-2d4f6a8c0e12", session.session_id);
+      assert!(session.rollback);
+
+      let json = r#"{"requestId":"9d3f6b2a-5c1e-4a7d-8b0f-3e2c1a9d7f65","op":"close","processor":"session","args":{"session":"b6e1d5a0-3f8e-4c1b-9e7a-2d4f6a8c0e12"}}"#;
+      let value: Value = serde_json::from_str(json).expect("json gremlin request");
+      assert!(build_gremlin_request_from_json(&value).expect("gremlin request").session.expect("session").rollback);
+    }
+
+    #[test]
+    fn test_order_range_limit() {
+      let json = r#"{"requestId":"0f1c5a2e-7d3b-4e8a-9c6f-5b2d1e0a3c47","op":"bytecode","processor":"traversal","args":{"@type":"g:Map","@value":["gremlin",{"@type":"g:Bytecode","@value":{"step":[["V"],["order"],["by","age",{"@type":"g:Order","@value":"desc"}],["by","name"],["range",{"@type":"g:Int64","@value":1},{"@type":"g:Int64","@value":-1}],["limit",{"@type":"g:Int64","@value":2}]]}},"aliases",{"@type":"g:Map","@value":["g","g"]}]}}"#;
+      let value: Value = serde_json::from_str(json).expect("json gremlin request");
+      let g = build_gremlin_request_from_json(&value).expect("gremlin request");
+      let steps = g.data.expect("data").steps;
+      assert_eq!(4, steps.len());
+      match &steps[1] {
+        GStep::Order(order_by) => {
+          assert_eq!(vec![GOrderBy{key: Some(String::from("age")), order: GOrder::Desc}, GOrderBy{key: Some(String::from("name")), order: GOrder::Asc}], *order_by);
+        },
+        _ => assert!(false),
+      }
+      assert!(matches!(steps[2], GStep::Range(1, None)));
+      assert!(matches!(steps[3], GStep::Limit(2)));
+    }
+
+    #[test]
+    fn test_values_value_map() {
+      let json = r#"{"requestId":"7a2e4c1b-3d5f-4a6b-8c9d-0e1f2a3b4c5d","op":"bytecode","processor":"traversal","args":{"@type":"g:Map","@value":["gremlin",{"@type":"g:Bytecode","@value":{"step":[["V"],["valueMap","name","age"],["V"],["values","name"]]}},"aliases",{"@type":"g:Map","@value":["g","g"]}]}}"#;
+      let value: Value = serde_json::from_str(json).expect("json gremlin request");
+      let steps = build_gremlin_request_from_json(&value).expect("gremlin request").data.expect("data").steps;
+      match (&steps[1], &steps[3]) {
+        (GStep::ValueMap(map_names), GStep::Values(names)) => {
+          assert_eq!(vec![String::from("name"), String::from("age")], *map_names);
+          assert_eq!(vec![String::from("name")], *names);
+        },
+        _ => assert!(false),
+      }
+    }
+
+    #[test]
+    fn test_in_both_steps() {
+      let json = r#"{"requestId":"5c8e2a7d-1b4f-4e3a-9d6c-2f0a8b1e7c35","op":"bytecode","processor":"traversal","args":{"@type":"g:Map","@value":["gremlin",{"@type":"g:Bytecode","@value":{"step":[["V"],["in","knows"],["both"],["bothE","created"],["otherV"],["inE"],["outV"]]}},"aliases",{"@type":"g:Map","@value":["g","g"]}]}}"#;
+      let value: Value = serde_json::from_str(json).expect("json gremlin request");
+      let steps = build_gremlin_request_from_json(&value).expect("gremlin request").data.expect("data").steps;
+      assert_eq!(9, steps.len());
+      match (&steps[1], &steps[3], &steps[5]) {
+        (GStep::InE(in_labels), GStep::BothE(both_labels), GStep::BothE(created_labels)) => {
+          assert_eq!(vec![String::from("knows")], *in_labels);
+          assert!(both_labels.is_empty());
+          assert_eq!(vec![String::from("created")], *created_labels);
+        },
+        _ => assert!(false),
+      }
+      assert!(matches!(steps[2], GStep::OutV));
+      assert!(matches!(steps[4], GStep::OtherV));
+      assert!(matches!(steps[6], GStep::OtherV));
+      assert!(matches!(steps[7], GStep::InE(_)));
+      assert!(matches!(steps[8], GStep::OutV));
+    }
+
+    #[test]
+    fn test_drop_step() {
+      let json = r#"{"requestId":"9e3b7c1a-4d2f-4b8e-a6c5-1f0d2e3a4b5c","op":"bytecode","processor":"traversal","args":{"@type":"g:Map","@value":["gremlin",{"@type":"g:Bytecode","@value":{"step":[["V",{"@type":"g:Int64","@value":1}],["outE","knows"],["drop"]]}},"aliases",{"@type":"g:Map","@value":["g","g"]}]}}"#;
+      let value: Value = serde_json::from_str(json).expect("json gremlin request");
+      let steps = build_gremlin_request_from_json(&value).expect("gremlin request").data.expect("data").steps;
+      assert_eq!(3, steps.len());
+      assert!(matches!(steps[1], GStep::OutE(_)));
+      assert!(matches!(steps[2], GStep::Drop));
+    }
+
+    #[test]
+    fn test_property_cardinality() {
+      let json = r#"{"requestId":"2d4f6a8c-1e3b-4c5d-9f7a-8b6c4d2e0f1a","op":"bytecode","processor":"traversal","args":{"@type":"g:Map","@value":["gremlin",{"@type":"g:Bytecode","@value":{"step":[["addV","person"],["property","name","marko"],["property",{"@type":"g:Cardinality","@value":"list"},"alias","mar"],["property",{"@type":"g:Cardinality","@value":"set"},"alias","mko"]]}},"aliases",{"@type":"g:Map","@value":["g","g"]}]}}"#;
+      let value: Value = serde_json::from_str(json).expect("json gremlin request");
+      let steps = build_gremlin_request_from_json(&value).expect("gremlin request").data.expect("data").steps;
+      assert!(matches!(&steps[1], GStep::SetProperty(name, _, GCardinality::Single) if name == "name"));
+      assert!(matches!(&steps[2], GStep::SetProperty(name, GValue::String(v), GCardinality::List) if name == "alias" && v == "mar"));
+      assert!(matches!(&steps[3], GStep::SetProperty(_, _, GCardinality::Set)));
+    }
+
+    #[test]
+    fn test_graphson_types() {
+      let json = r#"{"@type":"g:List","@value":[
+        {"@type":"g:Float","@value":1.5},
+        {"@type":"g:Double","@value":"NaN"},
+        {"@type":"g:Date","@value":1481750076295},
+        {"@type":"g:Timestamp","@value":1481750076295},
+        {"@type":"g:UUID","@value":"41d2e28a-20a4-4ab0-b379-d810dede3786"},
+        {"@type":"gx:Int16","@value":100},
+        {"@type":"gx:BigInteger","@value":"123456789012345678901234567890"},
+        {"@type":"g:Set","@value":[{"@type":"g:Int32","@value":1},"a"]},
+        {"@type":"g:Map","@value":[{"@type":"g:Int32","@value":1},{"@type":"g:Map","@value":["nested",{"@type":"g:List","@value":[true]}]}]}
+      ]}"#;
+      let value: Value = serde_json::from_str(json).expect("json graphson");
+      let values = match build_gremlin_value(&value).expect("list") {
+        GValue::List(values) => values,
+        _ => panic!("list expected"),
+      };
+      assert_eq!(GValue::Float(GFloat(1.5)), values[0]);
+      assert!(matches!(values[1], GValue::Double(GDouble(v)) if v.is_nan()));
+      assert_eq!(GValue::Date(GDate(1481750076295)), values[2]);
+      assert_eq!(GValue::Timestamp(GTimestamp(1481750076295)), values[3]);
+      assert_eq!(GValue::Uuid(GUuid(String::from("41d2e28a-20a4-4ab0-b379-d810dede3786"))), values[4]);
+      assert_eq!(GValue::Integer(GInteger::I32(GInt32(100))), values[5]);
+      assert!(matches!(values[6], GValue::Double(GDouble(v)) if v > 1e29));
+      assert_eq!(GValue::Set(vec![GValue::Integer(GInteger::I32(GInt32(1))), GValue::String(String::from("a"))]), values[7]);
+      let nested = GValue::Map(vec![(String::from("nested"), GValue::List(vec![GValue::Bool(true)]))]);
+      assert_eq!(GValue::Map(vec![(String::from("1"), nested)]), values[8]);
+
+      for value in &values[2..] {
+        assert_eq!(Some(value.clone()), build_gremlin_value(&value.to_json()));
+      }
+      assert_eq!("NaN", values[1].to_json()["@value"]);
+    }
+
+    #[test]
+    fn test_has_typed_value() {
+      let json = r#"{"requestId":"1d7c3e5a-9b2f-4c8e-a1d6-7f3b5e9c2a40","op":"bytecode","processor":"traversal","args":{"@type":"g:Map","@value":["gremlin",{"@type":"g:Bytecode","@value":{"step":[["V"],["has","age",{"@type":"g:Int32","@value":29}],["has","born",{"@type":"g:Date","@value":0}]]}},"aliases",{"@type":"g:Map","@value":["g","g"]}]}}"#;
+      let value: Value = serde_json::from_str(json).expect("json gremlin request");
+      let steps = build_gremlin_request_from_json(&value).expect("gremlin request").data.expect("data").steps;
+      assert!(matches!(&steps[1], GStep::Has(name, GPredicate::Value(GValue::Integer(GInteger::I32(GInt32(29))))) if name == "age"));
+      assert!(matches!(&steps[2], GStep::Has(name, GPredicate::Value(GValue::Date(GDate(0)))) if name == "born"));
+    }
+
+    #[test]
+    fn test_has_label_steps() {
+      let json = r#"{"requestId":"1d7c3e5a-9b2f-4c8e-a1d6-7f3b5e9c2a40","op":"bytecode","processor":"traversal","args":{"@type":"g:Map","@value":["gremlin",{"@type":"g:Bytecode","@value":{"step":[["V"],["hasLabel","person","software"],["has",{"@type":"g:T","@value":"label"},"person"],["has",{"@type":"g:T","@value":"id"},{"@type":"g:Int64","@value":1}],["has","person","age",{"@type":"g:Int32","@value":29}],["hasId",{"@type":"g:P","@value":{"predicate":"within","value":{"@type":"g:List","@value":[{"@type":"g:Int64","@value":1},{"@type":"g:Int64","@value":2}]}}}]]}},"aliases",{"@type":"g:Map","@value":["g","g"]}]}}"#;
+      let value: Value = serde_json::from_str(json).expect("json gremlin request");
+      let steps = build_gremlin_request_from_json(&value).expect("gremlin request").data.expect("data").steps;
+      assert_eq!(7, steps.len());
+      assert!(matches!(&steps[1], GStep::HasLabel(labels) if labels == &vec![String::from("person"), String::from("software")]));
+      assert!(matches!(&steps[2], GStep::HasLabel(labels) if labels == &vec![String::from("person")]));
+      assert!(matches!(&steps[3], GStep::HasId(ids) if ids == &vec![GValue::Integer(GInteger::I64(GInt64(1)))]));
+      assert!(matches!(&steps[4], GStep::HasLabel(labels) if labels == &vec![String::from("person")]));
+      assert!(matches!(&steps[5], GStep::Has(name, GPredicate::Value(GValue::Integer(GInteger::I32(GInt32(29))))) if name == "age"));
+      assert!(matches!(&steps[6], GStep::HasId(ids) if ids.len() == 2));
+    }
+
+    #[test]
+    fn test_repeat_modulators() {
+      let json = r#"{"requestId":"1d7c3e5a-9b2f-4c8e-a1d6-7f3b5e9c2a40","op":"bytecode","processor":"traversal","args":{"@type":"g:Map","@value":["gremlin",{"@type":"g:Bytecode","@value":{"step":[["V",{"@type":"g:Int64","@value":1}],["repeat",{"@type":"g:Bytecode","@value":{"step":[["out","knows"]]}}],["times",{"@type":"g:Int32","@value":2}],["until",{"@type":"g:Bytecode","@value":{"step":[["hasLabel","city"]]}}],["values","name"]]}},"aliases",{"@type":"g:Map","@value":["g","g"]}]}}"#;
+      let value: Value = serde_json::from_str(json).expect("json gremlin request");
+      let steps = build_gremlin_request_from_json(&value).expect("gremlin request").data.expect("data").steps;
+      assert_eq!(3, steps.len());
+      match &steps[1] {
+        GStep::Repeat(repeat) => {
+          assert!(matches!(&repeat.steps[..], [GStep::OutE(labels), GStep::InV] if labels == &vec![String::from("knows")]));
+          assert_eq!(Some(2), repeat.times);
+          assert!(matches!(repeat.until.as_deref(), Some([GStep::HasLabel(_)])));
+        },
+        _ => panic!("repeat step expected"),
+      }
+    }
+
+    #[test]
+    fn test_where_traversal() {
+      let json = r#"{"requestId":"1d7c3e5a-9b2f-4c8e-a1d6-7f3b5e9c2a40","op":"bytecode","processor":"traversal","args":{"@type":"g:Map","@value":["gremlin",{"@type":"g:Bytecode","@value":{"step":[["V"],["where",{"@type":"g:Bytecode","@value":{"step":[["out","knows"],["has","name","marko"]]}}],["values","name"]]}},"aliases",{"@type":"g:Map","@value":["g","g"]}]}}"#;
+      let value: Value = serde_json::from_str(json).expect("json gremlin request");
+      let steps = build_gremlin_request_from_json(&value).expect("gremlin request").data.expect("data").steps;
+      assert_eq!(3, steps.len());
+      assert!(matches!(&steps[1], GStep::Where(nested) if matches!(&nested[..], [GStep::OutE(_), GStep::InV, GStep::Has(name, _)] if name == "name")));
+    }
+}
@@ -20,6 +20,7 @@
 // SOFTWARE.
 
 pub mod gremlin;
+pub mod graph_binary;
 pub mod handler;
 pub mod json_gremlin_request_builder;
 pub mod json_gremlin_response_builder;
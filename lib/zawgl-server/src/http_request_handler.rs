@@ -0,0 +1,869 @@
+// MIT License
+//
+// Copyright (c) 2022 Alexandre RICCIARDI
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use bson::{Bson, Document, doc};
+use hyper::header::{CONTENT_TYPE, LOCATION};
+use hyper::server::conn::Http;
+use hyper::service::service_fn;
+use hyper::{Body, Method, Request, Response, StatusCode};
+use log::*;
+use serde_json::{Map, Value, json};
+use tokio::io::{AsyncRead, AsyncWrite};
+use zawgl_core::error::GraphError;
+use zawgl_core::graph_engine::query_context::QueryAbort;
+use zawgl_core::metrics;
+use zawgl_core::model::check::INDEXES_NAMES;
+use zawgl_cypher::CypherError;
+use zawgl_cypher::cypher::parser::error::ParserError;
+use zawgl_tx_handler::DatabaseError;
+use zawgl_tx_handler::databases::Databases;
+
+use crate::open_cypher_request_handler::handle_open_cypher_request;
+use crate::run_blocking;
+use crate::result::ServerError;
+use crate::server::{SettingsLoader, reload_settings};
+
+const DB_PATH: &str = "/db";
+const QUERY_SEGMENT: &str = "query";
+const TX_SEGMENT: &str = "tx";
+const CHANGES_SEGMENT: &str = "changes";
+const WARMUP_SEGMENT: &str = "warmup";
+const DEFAULT_CHANGES_LIMIT: usize = 100;
+const METRICS_PATH: &str = "/metrics";
+const RELOAD_PATH: &str = "/admin/reload";
+const QUERIES_PATH: &str = "/admin/queries";
+
+static REQUEST_COUNT: AtomicU64 = AtomicU64::new(0);
+static TX_COUNT: AtomicU64 = AtomicU64::new(0);
+/// Open transactions with the name of their database, none for the default one.
+static OPEN_TRANSACTIONS: Mutex<BTreeMap<u64, Option<String>>> = Mutex::new(BTreeMap::new());
+
+struct HttpFailure {
+    status: StatusCode,
+    code: &'static str,
+    message: String,
+}
+
+impl HttpFailure {
+    fn new(status: StatusCode, code: &'static str, message: &str) -> Self {
+        HttpFailure{status: status, code: code, message: String::from(message)}
+    }
+
+    fn invalid_request(message: &str) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, "Neo.ClientError.Request.InvalidFormat", message)
+    }
+
+    fn not_found(message: &str) -> Self {
+        Self::new(StatusCode::NOT_FOUND, "Neo.ClientError.Request.Invalid", message)
+    }
+}
+
+/// Routes of the default database are under `/db`, the ones of a named database under `/db/{name}`.
+#[derive(Debug, PartialEq)]
+enum Route {
+    Query(Option<String>),
+    Begin(Option<String>),
+    Run(Option<String>, u64),
+    Commit(Option<String>, u64),
+    Rollback(Option<String>, u64),
+    ListDatabases,
+    CreateDatabase(String),
+    DropDatabase(String),
+    Changes(Option<String>),
+    WarmUp(Option<String>),
+    Metrics,
+    ReloadSettings,
+    ListQueries,
+    CancelQuery(String),
+}
+
+struct Statement {
+    query: String,
+    parameters: Option<Document>,
+    timeout: Option<u64>,
+}
+
+struct HttpContext {
+    databases: Databases<'static>,
+    settings_loader: Option<SettingsLoader>,
+}
+
+fn route(method: &Method, path: &str) -> Result<Route, HttpFailure> {
+    let path = path.trim_end_matches('/');
+    let method_not_allowed = || HttpFailure::new(StatusCode::METHOD_NOT_ALLOWED, "Neo.ClientError.Request.Invalid", &format!("{} not allowed on {}", method, path));
+    if path == METRICS_PATH {
+        return if method == Method::GET { Ok(Route::Metrics) } else { Err(method_not_allowed()) };
+    }
+    if path == RELOAD_PATH {
+        return if method == Method::POST { Ok(Route::ReloadSettings) } else { Err(method_not_allowed()) };
+    }
+    if path == QUERIES_PATH {
+        return if method == Method::GET { Ok(Route::ListQueries) } else { Err(method_not_allowed()) };
+    }
+    if let Some(request_id) = path.strip_prefix(QUERIES_PATH).and_then(|rest| rest.strip_prefix('/')) {
+        return if method == Method::DELETE { Ok(Route::CancelQuery(String::from(request_id))) } else { Err(method_not_allowed()) };
+    }
+    if path == DB_PATH {
+        return if method == Method::GET { Ok(Route::ListDatabases) } else { Err(method_not_allowed()) };
+    }
+    let not_found = || HttpFailure::not_found(&format!("unknown path {}", path));
+    let segments = path.strip_prefix(DB_PATH).and_then(|rest| rest.strip_prefix('/')).ok_or_else(not_found)?.split('/').collect::<Vec<&str>>();
+    let (database, segments) = match segments[0] {
+        QUERY_SEGMENT | TX_SEGMENT | CHANGES_SEGMENT | WARMUP_SEGMENT => (None, &segments[..]),
+        name => (Some(String::from(name)), &segments[1..]),
+    };
+    let parse_tx_id = |tx_id: &str| tx_id.parse::<u64>().map_err(|_| not_found());
+    match segments {
+        [] => match database {
+            Some(name) if method == Method::PUT => Ok(Route::CreateDatabase(name)),
+            Some(name) if method == Method::DELETE => Ok(Route::DropDatabase(name)),
+            _ => Err(method_not_allowed()),
+        },
+        [QUERY_SEGMENT] if method == Method::POST => Ok(Route::Query(database)),
+        [TX_SEGMENT] if method == Method::POST => Ok(Route::Begin(database)),
+        [TX_SEGMENT, tx_id] if method == Method::POST => Ok(Route::Run(database, parse_tx_id(tx_id)?)),
+        [TX_SEGMENT, tx_id] if method == Method::DELETE => Ok(Route::Rollback(database, parse_tx_id(tx_id)?)),
+        [TX_SEGMENT, tx_id, "commit"] if method == Method::POST => Ok(Route::Commit(database, parse_tx_id(tx_id)?)),
+        [CHANGES_SEGMENT] if method == Method::GET => Ok(Route::Changes(database)),
+        [WARMUP_SEGMENT] if method == Method::POST => Ok(Route::WarmUp(database)),
+        [QUERY_SEGMENT] | [TX_SEGMENT] | [TX_SEGMENT, _] | [TX_SEGMENT, _, "commit"] | [CHANGES_SEGMENT] | [WARMUP_SEGMENT] => Err(method_not_allowed()),
+        _ => Err(not_found()),
+    }
+}
+
+fn build_parameters(params: &Map<String, Value>) -> Result<Document, HttpFailure> {
+    let mut parameters = Document::new();
+    for (name, value) in params {
+        let param = match value {
+            Value::Bool(b) => Bson::Boolean(*b),
+            Value::Number(n) => match n.as_i64() {
+                Some(i) => Bson::Int64(i),
+                None => Bson::Double(n.as_f64().unwrap_or_default()),
+            },
+            Value::String(s) => Bson::String(s.clone()),
+            Value::Object(nested) => Bson::Document(build_parameters(nested)?),
+            _ => return Err(HttpFailure::invalid_request(&format!("unsupported parameter type for {}", name))),
+        };
+        parameters.insert(name, param);
+    }
+    Ok(parameters)
+}
+
+fn parse_statement(body: &[u8]) -> Result<Option<Statement>, HttpFailure> {
+    if body.iter().all(|b| b.is_ascii_whitespace()) {
+        return Ok(None);
+    }
+    let value: Value = serde_json::from_slice(body).map_err(|err| HttpFailure::invalid_request(&err.to_string()))?;
+    let query = match value.get("statement") {
+        Some(Value::String(query)) => query.clone(),
+        Some(_) => return Err(HttpFailure::invalid_request("statement must be a string")),
+        None => return Ok(None),
+    };
+    let parameters = match value.get("parameters") {
+        Some(Value::Object(params)) => Some(build_parameters(params)?),
+        Some(Value::Null) | None => None,
+        Some(_) => return Err(HttpFailure::invalid_request("parameters must be an object")),
+    };
+    let timeout = match value.get("timeout") {
+        Some(Value::Number(millis)) => Some(millis.as_u64().ok_or_else(|| HttpFailure::invalid_request("timeout must be a number of milliseconds"))?),
+        Some(Value::Null) | None => None,
+        Some(_) => return Err(HttpFailure::invalid_request("timeout must be a number of milliseconds")),
+    };
+    Ok(Some(Statement{query: query, parameters: parameters, timeout}))
+}
+
+fn make_session_id(tx_id: u64) -> String {
+    format!("http-tx-{}", tx_id)
+}
+
+fn next_request() -> Document {
+    doc!{"request_id": format!("http-{}", REQUEST_COUNT.fetch_add(1, Ordering::SeqCst))}
+}
+
+fn build_database_failure(err: DatabaseError) -> HttpFailure {
+    match err {
+        DatabaseError::ProcedureError(err) => HttpFailure::new(StatusCode::BAD_REQUEST, "Neo.ClientError.Procedure.ProcedureCallFailed", &err.to_string()),
+        DatabaseError::WriteInReadOnlyTx => HttpFailure::new(StatusCode::BAD_REQUEST, "Neo.ClientError.Statement.AccessMode", "write in a read only transaction"),
+        err @ DatabaseError::TxRollbackOnly(_) => HttpFailure::new(StatusCode::BAD_REQUEST, "Neo.ClientError.Transaction.TransactionMarkedAsFailed", &err.to_string()),
+        err @ DatabaseError::TxOutdated(_) => HttpFailure::new(StatusCode::CONFLICT, "Neo.TransientError.Transaction.Outdated", &err.to_string()),
+        DatabaseError::Deadlock => HttpFailure::new(StatusCode::CONFLICT, "Neo.TransientError.Transaction.DeadlockDetected", "the transaction was rolled back to break a deadlock"),
+        DatabaseError::LockTimeout => HttpFailure::new(StatusCode::CONFLICT, "Neo.TransientError.Transaction.LockAcquisitionTimeout", "timed out waiting for a lock"),
+        DatabaseError::UnknownDatabase(name) => HttpFailure::new(StatusCode::NOT_FOUND, "Neo.ClientError.Database.DatabaseNotFound", &format!("unknown database {}", name)),
+        DatabaseError::DatabaseAlreadyExists(name) => HttpFailure::new(StatusCode::CONFLICT, "Neo.ClientError.Database.ExistingDatabaseFound", &format!("database {} already exists", name)),
+        DatabaseError::InvalidDatabaseName(name) => HttpFailure::new(StatusCode::BAD_REQUEST, "Neo.ClientError.Statement.ArgumentError", &format!("invalid database name {}", name)),
+        DatabaseError::TriggerVeto(trigger, message) => HttpFailure::new(StatusCode::BAD_REQUEST, "Neo.ClientError.Transaction.TransactionHookFailed", &format!("trigger {} rejected the transaction: {}", trigger, message)),
+        DatabaseError::QueryAborted(QueryAbort::Cancelled) => HttpFailure::new(StatusCode::CONFLICT, "Neo.TransientError.Transaction.Terminated", "the query was cancelled"),
+        DatabaseError::QueryAborted(QueryAbort::MemoryLimitExceeded) => HttpFailure::new(StatusCode::SERVICE_UNAVAILABLE, "Neo.TransientError.General.MemoryPoolOutOfMemoryError", "the query exceeded its memory limit"),
+        DatabaseError::QueryAborted(QueryAbort::Timeout) => HttpFailure::new(StatusCode::REQUEST_TIMEOUT, "Neo.ClientError.Transaction.TransactionTimedOut", "the query timed out"),
+        DatabaseError::Busy => HttpFailure::new(StatusCode::SERVICE_UNAVAILABLE, "Neo.TransientError.Request.NoThreadsAvailable", "too many concurrent queries, retry later"),
+        DatabaseError::EngineError(err @ GraphError::UniqueConstraintViolation(_)) => HttpFailure::new(StatusCode::BAD_REQUEST, "Neo.ClientError.Schema.ConstraintValidationFailed", &err.to_string()),
+        DatabaseError::EngineError(err @ GraphError::NodeHasRelationships(_)) => HttpFailure::new(StatusCode::BAD_REQUEST, "Neo.ClientError.Schema.ConstraintValidationFailed", &err.to_string()),
+        err => HttpFailure::new(StatusCode::INTERNAL_SERVER_ERROR, "Neo.DatabaseError.General.UnknownError", &err.to_string()),
+    }
+}
+
+fn send_request(ctx: &HttpContext, database: &Option<String>, request: &Document) -> Result<Document, HttpFailure> {
+    let db = ctx.databases.get_database(database.as_deref()).map_err(build_database_failure)?;
+    let query = ctx.databases.start_query(request.get_str("request_id").unwrap_or_default());
+    let reply = handle_open_cypher_request(db.tx_handler, db.graph_request_handler, request, query.get_query_context()).map_err(|err| match err {
+        CypherError::RequestError => HttpFailure::new(StatusCode::BAD_REQUEST, "Neo.ClientError.Statement.SyntaxError", "invalid cypher request"),
+        CypherError::ResponseError => HttpFailure::new(StatusCode::INTERNAL_SERVER_ERROR, "Neo.DatabaseError.General.UnknownError", "invalid cypher response"),
+        CypherError::QueryError(err @ ParserError::SemanticError) => HttpFailure::new(StatusCode::BAD_REQUEST, "Neo.ClientError.Statement.SemanticError", &err.to_string()),
+        CypherError::QueryError(err) => HttpFailure::new(StatusCode::BAD_REQUEST, "Neo.ClientError.Statement.SyntaxError", &err.to_string()),
+        CypherError::TxError(err) => build_database_failure(err),
+    })?;
+    reply.get_document("result").map(|result| result.clone())
+        .map_err(|err| HttpFailure::new(StatusCode::INTERNAL_SERVER_ERROR, "Neo.DatabaseError.General.UnknownError", &err.to_string()))
+}
+
+fn run_statement(ctx: &HttpContext, database: &Option<String>, statement: Statement, tx_id: Option<u64>) -> Result<Value, HttpFailure> {
+    let _permit = ctx.databases.admit().map_err(build_database_failure)?;
+    let mut request = next_request();
+    request.insert("query", statement.query);
+    if let Some(parameters) = statement.parameters {
+        request.insert("parameters", parameters);
+    }
+    if let Some(timeout) = statement.timeout {
+        request.insert("timeout", timeout as i64);
+    }
+    if let Some(id) = tx_id {
+        request.insert("session_id", make_session_id(id));
+    }
+    Ok(build_result(&send_request(ctx, database, &request)?))
+}
+
+fn end_tx(ctx: &HttpContext, database: &Option<String>, tx_id: u64, tx: &str) -> Result<(), HttpFailure> {
+    OPEN_TRANSACTIONS.lock().unwrap().remove(&tx_id);
+    let mut request = next_request();
+    request.insert("session_id", make_session_id(tx_id));
+    request.insert("tx", tx);
+    send_request(ctx, database, &request).map(|_| ())
+}
+
+fn check_open_tx(database: &Option<String>, tx_id: u64) -> Result<(), HttpFailure> {
+    if OPEN_TRANSACTIONS.lock().unwrap().get(&tx_id) == Some(database) {
+        Ok(())
+    } else {
+        Err(HttpFailure::not_found(&format!("unknown transaction {}", tx_id)))
+    }
+}
+
+/// Runs the statement of an open transaction, a failing statement rolls the transaction back.
+fn run_tx_statement(ctx: &HttpContext, database: &Option<String>, statement: Option<Statement>, tx_id: u64) -> Result<Vec<Value>, HttpFailure> {
+    match statement {
+        Some(stmt) => match run_statement(ctx, database, stmt, Some(tx_id)) {
+            Ok(result) => Ok(vec![result]),
+            Err(failure) => {
+                let _ = end_tx(ctx, database, tx_id, "rollback");
+                Err(failure)
+            }
+        },
+        None => Ok(Vec::new()),
+    }
+}
+
+fn to_json(value: &Bson) -> Value {
+    value.clone().into_relaxed_extjson()
+}
+
+fn get_id(item: &Document, key: &str) -> Value {
+    match item.get_str(key).ok().and_then(|id| id.parse::<u64>().ok()) {
+        Some(id) => json!(id),
+        None => Value::Null,
+    }
+}
+
+fn get_properties(item: &Document) -> Value {
+    let mut properties = Map::new();
+    if let Ok(props) = item.get_array("properties") {
+        for prop in props {
+            if let Bson::Document(p) = prop {
+                properties.extend(p.iter().map(|(k, v)| (k.clone(), to_json(v))));
+            }
+        }
+    }
+    Value::Object(properties)
+}
+
+fn get_items<'a>(graph: &'a Document, key: &str) -> Vec<&'a Document> {
+    graph.get_array(key).map(|items| items.iter().filter_map(|item| item.as_document()).collect()).unwrap_or_default()
+}
+
+fn build_node(node: &Document) -> Value {
+    json!({
+        "id": get_id(node, "id"),
+        "labels": node.get_array("labels").map(|labels| to_json(&Bson::Array(labels.clone()))).unwrap_or_else(|_| json!([])),
+        "properties": get_properties(node),
+    })
+}
+
+fn build_relationship(rel: &Document) -> Value {
+    json!({
+        "id": get_id(rel, "id"),
+        "type": rel.get_array("labels").ok().and_then(|labels| labels.first()).map(to_json).unwrap_or(Value::Null),
+        "start": get_id(rel, "source_id"),
+        "end": get_id(rel, "target_id"),
+        "properties": get_properties(rel),
+    })
+}
+
+fn build_path(path: &Document) -> Value {
+    json!({
+        "nodes": get_items(path, "nodes").into_iter().map(build_node).collect::<Vec<Value>>(),
+        "relationships": get_items(path, "relationships").into_iter().map(build_relationship).collect::<Vec<Value>>(),
+    })
+}
+
+fn is_path(item: &Document) -> bool {
+    item.len() == 2 && item.get_array("nodes").is_ok() && item.get_array("relationships").is_ok()
+}
+
+fn is_entity(item: &Document, keys: &[&str]) -> bool {
+    item.len() == keys.len() && keys.iter().all(|key| item.contains_key(key)) && item.get_str("id").is_ok() && item.get_array("properties").is_ok()
+}
+
+fn build_row_value(value: &Bson) -> Value {
+    match value {
+        Bson::Document(d) if is_entity(d, &["id", "labels", "properties"]) => build_node(d),
+        Bson::Document(d) if is_entity(d, &["id", "source_id", "target_id", "labels", "properties"]) => build_relationship(d),
+        Bson::Document(d) if is_path(d) => build_path(d),
+        Bson::Array(values) => Value::Array(values.iter().map(build_row_value).collect()),
+        _ => to_json(value),
+    }
+}
+
+/// Results of queries with a RETURN clause are returned as rows, otherwise each matched graph
+/// is a row made of its nodes and relationships.
+fn build_result(result: &Document) -> Value {
+    if let (Ok(columns), Ok(rows)) = (result.get_array("columns"), result.get_array("rows")) {
+        return json!({"columns": to_json(&Bson::Array(columns.clone())), "rows": rows.iter().map(build_row_value).collect::<Vec<Value>>()});
+    }
+    let mut rows = Vec::new();
+    for graph in result.get_array("graphs").map(|graphs| graphs.iter().filter_map(|g| g.as_document()).collect()).unwrap_or_else(|_| Vec::new()) {
+        let nodes: Vec<Value> = get_items(graph, "nodes").into_iter().map(build_node).collect();
+        let relationships: Vec<Value> = get_items(graph, "relationships").into_iter().map(build_relationship).collect();
+        rows.push(json!([nodes, relationships]));
+    }
+    json!({"columns": ["nodes", "relationships"], "rows": rows})
+}
+
+fn json_response(status: StatusCode, body: Value) -> Response<Body> {
+    let mut response = Response::new(Body::from(body.to_string()));
+    *response.status_mut() = status;
+    if let Ok(content_type) = "application/json".parse() {
+        response.headers_mut().insert(CONTENT_TYPE, content_type);
+    }
+    response
+}
+
+fn build_tx_path(database: &Option<String>, tx_id: u64) -> String {
+    match database {
+        Some(name) => format!("{}/{}/{}/{}", DB_PATH, name, TX_SEGMENT, tx_id),
+        None => format!("{}/{}/{}", DB_PATH, TX_SEGMENT, tx_id),
+    }
+}
+
+fn tx_response(status: StatusCode, database: &Option<String>, tx_id: u64, results: Vec<Value>) -> Response<Body> {
+    let tx_path = build_tx_path(database, tx_id);
+    let mut response = json_response(status, json!({
+        "commit": format!("{}/commit", tx_path),
+        "results": results,
+        "errors": [],
+    }));
+    if let Ok(location) = tx_path.parse() {
+        response.headers_mut().insert(LOCATION, location);
+    }
+    response
+}
+
+/// Reads the `from` offset and the `limit` of the changes query string.
+fn parse_changes_query(query: Option<&str>) -> Result<(u64, usize), HttpFailure> {
+    let mut offset = 0;
+    let mut limit = DEFAULT_CHANGES_LIMIT;
+    for (name, value) in query.unwrap_or_default().split('&').filter_map(|param| param.split_once('=')) {
+        let invalid_value = || HttpFailure::invalid_request(&format!("invalid {} value {}", name, value));
+        match name {
+            "from" => offset = value.parse().map_err(|_| invalid_value())?,
+            "limit" => limit = value.parse().map_err(|_| invalid_value())?,
+            _ => {},
+        }
+    }
+    Ok((offset, limit))
+}
+
+fn build_change_set(change_set: &Document) -> Value {
+    let events = get_items(change_set, "events").into_iter().map(|event| {
+        let mut value = json!({"change": event.get_str("change").unwrap_or_default()});
+        if let Ok(node) = event.get_document("node") {
+            value["node"] = build_node(node);
+        }
+        if let Ok(rel) = event.get_document("relationship") {
+            value["relationship"] = build_relationship(rel);
+        }
+        value
+    }).collect::<Vec<Value>>();
+    json!({
+        "offset": change_set.get_i64("offset").unwrap_or_default(),
+        "next_offset": change_set.get_i64("next_offset").unwrap_or_default(),
+        "timestamp": change_set.get_i64("timestamp").unwrap_or_default(),
+        "events": events,
+    })
+}
+
+fn build_io_failure(err: io::Error) -> HttpFailure {
+    match err.kind() {
+        io::ErrorKind::InvalidData => HttpFailure::invalid_request(&err.to_string()),
+        _ => HttpFailure::new(StatusCode::INTERNAL_SERVER_ERROR, "Neo.DatabaseError.General.UnknownError", &err.to_string()),
+    }
+}
+
+/// Change sets committed from the requested offset, `next` is the offset to read the following ones from.
+fn read_changes(ctx: &HttpContext, database: &Option<String>, query: Option<&str>) -> Result<Value, HttpFailure> {
+    let (offset, limit) = parse_changes_query(query)?;
+    let db = ctx.databases.get_database(database.as_deref()).map_err(build_database_failure)?;
+    let request_handler = db.graph_request_handler.read().unwrap();
+    if !request_handler.get_change_data_capture() {
+        return Err(HttpFailure::invalid_request("change data capture is not enabled"));
+    }
+    let mut cursor = request_handler.read_changes(offset).map_err(build_io_failure)?;
+    let mut changes = Vec::new();
+    for change_set in cursor.by_ref().take(limit) {
+        let change_set = zawgl_cypher::build_change_set(&change_set.map_err(build_io_failure)?)
+            .map_err(|_| HttpFailure::new(StatusCode::INTERNAL_SERVER_ERROR, "Neo.DatabaseError.General.UnknownError", "invalid change set"))?;
+        changes.push(build_change_set(&change_set));
+    }
+    Ok(json!({"changes": changes, "next": cursor.get_offset(), "errors": []}))
+}
+
+/// Indexes to warm up, all of them unless an `indexes` list is given, as in `indexes=nodes+labels,relationships+types`.
+fn parse_warm_up_query(query: Option<&str>) -> Vec<String> {
+    let indexes = query.unwrap_or_default().split('&').filter_map(|param| param.split_once('=')).find(|(name, _)| *name == "indexes");
+    match indexes {
+        Some((_, value)) => value.split(',').filter(|name| !name.is_empty()).map(|name| name.replace('+', " ").replace("%20", " ")).collect(),
+        None => INDEXES_NAMES.iter().map(|name| String::from(*name)).collect(),
+    }
+}
+
+/// Preloads the stores of a database, the pages read are reported per store.
+fn warm_up(ctx: &HttpContext, database: &Option<String>, query: Option<&str>) -> Result<Value, HttpFailure> {
+    let indexes = parse_warm_up_query(query);
+    let db = ctx.databases.get_database(database.as_deref()).map_err(build_database_failure)?;
+    let report = db.graph_request_handler.read().unwrap().warm_up(&indexes.iter().map(String::as_str).collect::<Vec<&str>>())
+        .ok_or_else(|| HttpFailure::invalid_request(&format!("unknown index in {}, expected one of {}", indexes.join(", "), INDEXES_NAMES.join(", "))))?;
+    let stores = report.stores.iter().map(|(name, pages)| json!({"store": name, "pages": pages})).collect::<Vec<Value>>();
+    Ok(json!({"stores": stores, "pages": report.get_pages_count(), "cached_pages": report.page_cache.cached_pages, "capacity": report.page_cache.capacity, "errors": []}))
+}
+
+/// Counters in the Prometheus text exposition format.
+fn build_metrics_text() -> String {
+    let mut text = String::new();
+    for counter in &metrics::COUNTERS {
+        text.push_str(&format!("# HELP {} {}\n# TYPE {} counter\n{} {}\n", counter.get_name(), counter.get_help(), counter.get_name(), counter.get_name(), metrics::get(*counter)));
+    }
+    text
+}
+
+fn metrics_response() -> Response<Body> {
+    let mut response = Response::new(Body::from(build_metrics_text()));
+    if let Ok(content_type) = "text/plain; version=0.0.4; charset=utf-8".parse() {
+        response.headers_mut().insert(CONTENT_TYPE, content_type);
+    }
+    response
+}
+
+fn handle_route(ctx: &HttpContext, route: Route, query: Option<&str>, body: &[u8]) -> Result<Response<Body>, HttpFailure> {
+    let statement = parse_statement(body)?;
+    match route {
+        Route::Query(database) => {
+            let stmt = statement.ok_or_else(|| HttpFailure::invalid_request("missing statement"))?;
+            let result = run_statement(ctx, &database, stmt, None)?;
+            Ok(json_response(StatusCode::OK, json!({"results": [result], "errors": []})))
+        }
+        Route::Begin(database) => {
+            ctx.databases.get_database(database.as_deref()).map_err(build_database_failure)?;
+            let tx_id = TX_COUNT.fetch_add(1, Ordering::SeqCst) + 1;
+            OPEN_TRANSACTIONS.lock().unwrap().insert(tx_id, database.clone());
+            let results = run_tx_statement(ctx, &database, statement, tx_id)?;
+            Ok(tx_response(StatusCode::CREATED, &database, tx_id, results))
+        }
+        Route::Run(database, tx_id) => {
+            check_open_tx(&database, tx_id)?;
+            let results = run_tx_statement(ctx, &database, statement, tx_id)?;
+            Ok(tx_response(StatusCode::OK, &database, tx_id, results))
+        }
+        Route::Commit(database, tx_id) => {
+            check_open_tx(&database, tx_id)?;
+            let results = run_tx_statement(ctx, &database, statement, tx_id)?;
+            end_tx(ctx, &database, tx_id, "commit")?;
+            Ok(json_response(StatusCode::OK, json!({"results": results, "errors": []})))
+        }
+        Route::Rollback(database, tx_id) => {
+            check_open_tx(&database, tx_id)?;
+            end_tx(ctx, &database, tx_id, "rollback")?;
+            Ok(json_response(StatusCode::OK, json!({"results": [], "errors": []})))
+        }
+        Route::ListDatabases => Ok(json_response(StatusCode::OK, json!({"databases": ctx.databases.list_databases(), "errors": []}))),
+        Route::CreateDatabase(name) => {
+            ctx.databases.create_database(&name).map_err(build_database_failure)?;
+            Ok(json_response(StatusCode::CREATED, json!({"database": name, "errors": []})))
+        }
+        Route::DropDatabase(name) => {
+            ctx.databases.drop_database(&name).map_err(build_database_failure)?;
+            let database = Some(name);
+            OPEN_TRANSACTIONS.lock().unwrap().retain(|_, tx_database| *tx_database != database);
+            Ok(json_response(StatusCode::OK, json!({"results": [], "errors": []})))
+        }
+        Route::Changes(database) => Ok(json_response(StatusCode::OK, read_changes(ctx, &database, query)?)),
+        Route::WarmUp(database) => Ok(json_response(StatusCode::OK, warm_up(ctx, &database, query)?)),
+        Route::Metrics => Ok(metrics_response()),
+        Route::ListQueries => {
+            let queries = ctx.databases.list_running_queries().into_iter()
+                .map(|query| json!({"request_id": query.request_id, "elapsed_ms": query.elapsed.as_millis() as u64, "memory_used": query.memory_used})).collect::<Vec<Value>>();
+            Ok(json_response(StatusCode::OK, json!({"queries": queries, "errors": []})))
+        }
+        Route::CancelQuery(request_id) => match ctx.databases.cancel_query(&request_id) {
+            0 => Err(HttpFailure::not_found(&format!("unknown query {}", request_id))),
+            cancelled => Ok(json_response(StatusCode::OK, json!({"cancelled": cancelled, "errors": []}))),
+        },
+        Route::ReloadSettings => {
+            let loader = ctx.settings_loader.as_ref().ok_or_else(|| HttpFailure::invalid_request("settings reload is not enabled"))?;
+            let settings = reload_settings(&ctx.databases, loader)
+                .map_err(|err| HttpFailure::new(StatusCode::INTERNAL_SERVER_ERROR, "Neo.DatabaseError.General.UnknownError", &err))?;
+            let slow_query_threshold_ms = settings.query_log.slow_query_threshold.map(|threshold| threshold.as_millis() as u64);
+            Ok(json_response(StatusCode::OK, json!({
+                "settings": {"page_cache_size": settings.page_cache_size, "slow_query_threshold_ms": slow_query_threshold_ms, "redact_query_parameters": settings.query_log.redact_parameters},
+                "errors": [],
+            })))
+        }
+    }
+}
+
+async fn handle_http_request(ctx: Arc<HttpContext>, request: Request<Body>) -> Result<Response<Body>, Infallible> {
+    let method = request.method().clone();
+    let path = String::from(request.uri().path());
+    let query = request.uri().query().map(String::from);
+    debug!("http request: {} {}", method, path);
+    let res = match hyper::body::to_bytes(request.into_body()).await {
+        Ok(body) => run_blocking(move || route(&method, &path).and_then(|r| handle_route(&ctx, r, query.as_deref(), &body))).await
+            .unwrap_or_else(|err| Err(HttpFailure::new(StatusCode::INTERNAL_SERVER_ERROR, "Neo.DatabaseError.General.UnknownError", &format!("{:?}", err)))),
+        Err(err) => Err(HttpFailure::invalid_request(&err.to_string())),
+    };
+    Ok(res.unwrap_or_else(|failure| json_response(failure.status, json!({
+        "results": [],
+        "errors": [{"code": failure.code, "message": failure.message}],
+    }))))
+}
+
+pub async fn handle_http_connection<S>(peer: SocketAddr, databases: Databases<'static>, settings_loader: Option<SettingsLoader>, stream: S) -> Result<(), ServerError> where S: AsyncRead + AsyncWrite + Unpin + Send + 'static {
+    info!("New HTTP connection: {}", peer);
+    let ctx = Arc::new(HttpContext{databases, settings_loader});
+    let service = service_fn(move |request| handle_http_request(ctx.clone(), request));
+    Http::new().http1_only(true).serve_connection(stream, service).await.map_err(ServerError::HttpError)
+}
+
+#[cfg(test)]
+mod test_http {
+    use super::*;
+    use std::time::Duration;
+    use zawgl_core::model::init::{InitContext, QueryLog, ReloadableSettings};
+    use zawgl_core::test_utils::build_dir_path_and_rm_old;
+    use zawgl_tx_handler::admission::AdmissionControl;
+    use zawgl_tx_handler::databases::{DEFAULT_DATABASE_NAME, DatabaseManager};
+
+    async fn call(ctx: &HttpContext, method: Method, path: &str, body: Value) -> (StatusCode, Value) {
+        let body = if body.is_null() { String::new() } else { body.to_string() };
+        let (path, query) = match path.split_once('?') {
+            Some((path, query)) => (path, Some(query)),
+            None => (path, None),
+        };
+        let response = route(&method, path).and_then(|r| handle_route(ctx, r, query, body.as_bytes()));
+        match response {
+            Ok(response) => {
+                let status = response.status();
+                let bytes = hyper::body::to_bytes(response.into_body()).await.expect("body");
+                (status, serde_json::from_slice(&bytes).expect("json"))
+            }
+            Err(failure) => (failure.status, json!({"errors": [{"code": failure.code}]})),
+        }
+    }
+
+    #[test]
+    fn test_route() {
+        let analytics = Some(String::from("analytics"));
+        assert_eq!(Some(Route::Query(None)), route(&Method::POST, "/db/query").ok());
+        assert_eq!(Some(Route::Begin(None)), route(&Method::POST, "/db/tx/").ok());
+        assert_eq!(Some(Route::Run(None, 3)), route(&Method::POST, "/db/tx/3").ok());
+        assert_eq!(Some(Route::Commit(None, 3)), route(&Method::POST, "/db/tx/3/commit").ok());
+        assert_eq!(Some(Route::Rollback(None, 3)), route(&Method::DELETE, "/db/tx/3").ok());
+        assert_eq!(Some(Route::Query(analytics.clone())), route(&Method::POST, "/db/analytics/query").ok());
+        assert_eq!(Some(Route::Commit(analytics.clone(), 3)), route(&Method::POST, "/db/analytics/tx/3/commit").ok());
+        assert_eq!(Some(Route::ListDatabases), route(&Method::GET, "/db").ok());
+        assert_eq!(Some(Route::CreateDatabase(String::from("analytics"))), route(&Method::PUT, "/db/analytics").ok());
+        assert_eq!(Some(Route::DropDatabase(String::from("analytics"))), route(&Method::DELETE, "/db/analytics").ok());
+        assert_eq!(Some(StatusCode::NOT_FOUND), route(&Method::POST, "/db/analytics/nodes").err().map(|f| f.status));
+        assert_eq!(Some(StatusCode::METHOD_NOT_ALLOWED), route(&Method::GET, "/db/query").err().map(|f| f.status));
+        assert_eq!(Some(StatusCode::NOT_FOUND), route(&Method::POST, "/db/tx/abc").err().map(|f| f.status));
+        assert_eq!(Some(Route::Changes(None)), route(&Method::GET, "/db/changes").ok());
+        assert_eq!(Some(Route::Changes(analytics.clone())), route(&Method::GET, "/db/analytics/changes").ok());
+        assert_eq!(Some(StatusCode::METHOD_NOT_ALLOWED), route(&Method::POST, "/db/changes").err().map(|f| f.status));
+        assert_eq!(Some(Route::WarmUp(None)), route(&Method::POST, "/db/warmup").ok());
+        assert_eq!(Some(Route::WarmUp(analytics.clone())), route(&Method::POST, "/db/analytics/warmup").ok());
+        assert_eq!(Some(StatusCode::METHOD_NOT_ALLOWED), route(&Method::GET, "/db/warmup").err().map(|f| f.status));
+        assert_eq!(Some(Route::Metrics), route(&Method::GET, "/metrics").ok());
+        assert_eq!(Some(StatusCode::METHOD_NOT_ALLOWED), route(&Method::POST, "/metrics").err().map(|f| f.status));
+        assert_eq!(Some(Route::ReloadSettings), route(&Method::POST, "/admin/reload").ok());
+        assert_eq!(Some(Route::ListQueries), route(&Method::GET, "/admin/queries").ok());
+        assert_eq!(Some(Route::CancelQuery(String::from("request-1"))), route(&Method::DELETE, "/admin/queries/request-1").ok());
+        assert_eq!(Some(StatusCode::METHOD_NOT_ALLOWED), route(&Method::GET, "/admin/reload").err().map(|f| f.status));
+    }
+
+    #[tokio::test]
+    async fn test_http_queries() {
+        let main_dir = build_dir_path_and_rm_old("test_http_queries").expect("db path");
+        let conf = InitContext::new(&main_dir).expect("can't create context");
+        let ctx = HttpContext{databases: Arc::new(DatabaseManager::new(conf, Duration::from_secs(10))), settings_loader: None};
+
+        let (status, _) = call(&ctx, Method::POST, "/db/query", json!({"statement": "CREATE (n:Person {name: 'ada'}) RETURN n"})).await;
+        assert_eq!(StatusCode::OK, status);
+        let (status, body) = call(&ctx, Method::POST, "/db/query", json!({"statement": "MATCH (n:Person) WHERE n.name = $name RETURN n", "parameters": {"name": "ada"}})).await;
+        assert_eq!(StatusCode::OK, status);
+        assert_eq!(json!(["n"]), body["results"][0]["columns"]);
+        assert_eq!(json!("ada"), body["results"][0]["rows"][0][0]["properties"]["name"]);
+
+        let (status, body) = call(&ctx, Method::POST, "/db/tx", json!({"statement": "CREATE (n:Person {name: 'alan'}) RETURN n"})).await;
+        assert_eq!(StatusCode::CREATED, status);
+        let commit = String::from(body["commit"].as_str().expect("commit uri"));
+        let (status, _) = call(&ctx, Method::POST, &commit, Value::Null).await;
+        assert_eq!(StatusCode::OK, status);
+        let (status, _) = call(&ctx, Method::POST, &commit, Value::Null).await;
+        assert_eq!(StatusCode::NOT_FOUND, status);
+
+        let (_, body) = call(&ctx, Method::POST, "/db/tx", Value::Null).await;
+        let tx = body["commit"].as_str().expect("commit uri").trim_end_matches("/commit").to_string();
+        call(&ctx, Method::POST, &tx, json!({"statement": "CREATE (n:Person {name: 'grace'}) RETURN n"})).await;
+        let (status, _) = call(&ctx, Method::DELETE, &tx, Value::Null).await;
+        assert_eq!(StatusCode::OK, status);
+
+        let (_, body) = call(&ctx, Method::POST, "/db/query", json!({"statement": "MATCH (n:Person) RETURN n"})).await;
+        assert_eq!(2, body["results"][0]["rows"].as_array().expect("rows").len());
+
+        let (status, body) = call(&ctx, Method::POST, "/db/query", json!({"statement": "MATCH (n:Person RETURN n"})).await;
+        assert_eq!(StatusCode::BAD_REQUEST, status);
+        assert_eq!(json!("Neo.ClientError.Statement.SyntaxError"), body["errors"][0]["code"]);
+
+        let body = match handle_route(&ctx, Route::Metrics, None, &[]) {
+            Ok(response) => hyper::body::to_bytes(response.into_body()).await.expect("body"),
+            Err(failure) => panic!("metrics: {}", failure.message),
+        };
+        let text = String::from_utf8(body.to_vec()).expect("text");
+        assert!(text.contains("# TYPE zawgl_queries_total counter"));
+        assert!(metrics::get(metrics::Counter::TxCommits) >= 1);
+        assert!(metrics::get(metrics::Counter::PageWrites) >= 1);
+    }
+
+    #[tokio::test]
+    async fn test_http_databases() {
+        let main_dir = build_dir_path_and_rm_old("test_http_databases").expect("db path");
+        let conf = InitContext::new(&main_dir).expect("can't create context");
+        let ctx = HttpContext{databases: Arc::new(DatabaseManager::new(conf, Duration::from_secs(10))), settings_loader: None};
+
+        let (status, body) = call(&ctx, Method::POST, "/db/analytics/query", json!({"statement": "MATCH (n:Person) RETURN n"})).await;
+        assert_eq!(StatusCode::NOT_FOUND, status);
+        assert_eq!(json!("Neo.ClientError.Database.DatabaseNotFound"), body["errors"][0]["code"]);
+        let (status, _) = call(&ctx, Method::PUT, "/db/analytics", Value::Null).await;
+        assert_eq!(StatusCode::CREATED, status);
+        let (status, _) = call(&ctx, Method::PUT, "/db/analytics", Value::Null).await;
+        assert_eq!(StatusCode::CONFLICT, status);
+
+        let (status, body) = call(&ctx, Method::POST, "/db/analytics/tx", json!({"statement": "CREATE (n:Person {name: 'ada'}) RETURN n"})).await;
+        assert_eq!(StatusCode::CREATED, status);
+        let commit = String::from(body["commit"].as_str().expect("commit uri"));
+        assert!(commit.starts_with("/db/analytics/tx/"));
+        let default_commit = commit.replace("/db/analytics/", "/db/");
+        let (status, _) = call(&ctx, Method::POST, &default_commit, Value::Null).await;
+        assert_eq!(StatusCode::NOT_FOUND, status);
+        let (status, _) = call(&ctx, Method::POST, &commit, Value::Null).await;
+        assert_eq!(StatusCode::OK, status);
+
+        let (_, body) = call(&ctx, Method::POST, "/db/analytics/query", json!({"statement": "MATCH (n:Person) RETURN n"})).await;
+        assert_eq!(1, body["results"][0]["rows"].as_array().expect("rows").len());
+        let (_, body) = call(&ctx, Method::POST, "/db/query", json!({"statement": "MATCH (n:Person) RETURN n"})).await;
+        assert_eq!(0, body["results"][0]["rows"].as_array().expect("rows").len());
+        let (_, body) = call(&ctx, Method::GET, "/db", Value::Null).await;
+        assert_eq!(json!(["analytics", DEFAULT_DATABASE_NAME]), body["databases"]);
+
+        let (status, _) = call(&ctx, Method::DELETE, "/db/analytics", Value::Null).await;
+        assert_eq!(StatusCode::OK, status);
+        let (_, body) = call(&ctx, Method::GET, "/db", Value::Null).await;
+        assert_eq!(json!([DEFAULT_DATABASE_NAME]), body["databases"]);
+    }
+
+    #[tokio::test]
+    async fn test_http_changes() {
+        let main_dir = build_dir_path_and_rm_old("test_http_changes").expect("db path");
+        let conf = InitContext::new(&main_dir).expect("can't create context").with_change_data_capture(true);
+        let ctx = HttpContext{databases: Arc::new(DatabaseManager::new(conf, Duration::from_secs(10))), settings_loader: None};
+
+        let (status, body) = call(&ctx, Method::GET, "/db/changes", Value::Null).await;
+        assert_eq!(StatusCode::OK, status);
+        assert_eq!(json!([]), body["changes"]);
+        assert_eq!(json!(0), body["next"]);
+
+        call(&ctx, Method::POST, "/db/query", json!({"statement": "CREATE (n:Person {name: 'ada'}) RETURN n"})).await;
+        call(&ctx, Method::POST, "/db/query", json!({"statement": "CREATE (n:Person {name: 'alan'}) RETURN n"})).await;
+        let (_, body) = call(&ctx, Method::POST, "/db/tx", json!({"statement": "CREATE (n:Person {name: 'grace'}) RETURN n"})).await;
+        let tx = body["commit"].as_str().expect("commit uri").trim_end_matches("/commit").to_string();
+        call(&ctx, Method::DELETE, &tx, Value::Null).await;
+
+        let (status, body) = call(&ctx, Method::GET, "/db/changes?limit=1", Value::Null).await;
+        assert_eq!(StatusCode::OK, status);
+        let changes = body["changes"].as_array().expect("changes");
+        assert_eq!(1, changes.len());
+        assert_eq!(json!("created"), changes[0]["events"][0]["change"]);
+        assert_eq!(json!(["Person"]), changes[0]["events"][0]["node"]["labels"]);
+        assert_eq!(json!("ada"), changes[0]["events"][0]["node"]["properties"]["name"]);
+        assert_eq!(changes[0]["next_offset"], body["next"]);
+
+        let (_, body) = call(&ctx, Method::GET, &format!("/db/changes?from={}", body["next"]), Value::Null).await;
+        let changes = body["changes"].as_array().expect("changes");
+        assert_eq!(1, changes.len());
+        assert_eq!(json!("alan"), changes[0]["events"][0]["node"]["properties"]["name"]);
+
+        let (status, _) = call(&ctx, Method::GET, "/db/changes?from=1", Value::Null).await;
+        assert_eq!(StatusCode::BAD_REQUEST, status);
+        let (status, _) = call(&ctx, Method::GET, "/db/changes?limit=all", Value::Null).await;
+        assert_eq!(StatusCode::BAD_REQUEST, status);
+    }
+
+    #[tokio::test]
+    async fn test_http_warm_up() {
+        let main_dir = build_dir_path_and_rm_old("test_http_warm_up").expect("db path");
+        let conf = InitContext::new(&main_dir).expect("can't create context");
+        let ctx = HttpContext{databases: Arc::new(DatabaseManager::new(conf, Duration::from_secs(10))), settings_loader: None};
+        call(&ctx, Method::POST, "/db/query", json!({"statement": "CREATE (n:Person {name: 'ada'}) RETURN n"})).await;
+
+        let (status, body) = call(&ctx, Method::POST, "/db/warmup", Value::Null).await;
+        assert_eq!(StatusCode::OK, status);
+        let stores = body["stores"].as_array().expect("stores").iter().map(|store| store["store"].clone()).collect::<Vec<Value>>();
+        assert_eq!(json!(["nodes", "relationships", "nodes labels", "nodes property keys", "nodes properties", "relationships types", "relationships properties"]), json!(stores));
+        assert_eq!(json!(1), body["stores"][0]["pages"]);
+
+        let (status, body) = call(&ctx, Method::POST, "/db/warmup?indexes=nodes+labels", Value::Null).await;
+        assert_eq!(StatusCode::OK, status);
+        assert_eq!(3, body["stores"].as_array().expect("stores").len());
+        assert_eq!(json!("nodes labels"), body["stores"][2]["store"]);
+        let (status, _) = call(&ctx, Method::POST, "/db/warmup?indexes=nodes+ids", Value::Null).await;
+        assert_eq!(StatusCode::BAD_REQUEST, status);
+        let (status, _) = call(&ctx, Method::POST, "/db/missing/warmup", Value::Null).await;
+        assert_eq!(StatusCode::NOT_FOUND, status);
+    }
+
+    #[tokio::test]
+    async fn test_http_reload_settings() {
+        let main_dir = build_dir_path_and_rm_old("test_http_reload_settings").expect("db path");
+        let conf = InitContext::new(&main_dir).expect("can't create context");
+        let databases = Arc::new(DatabaseManager::new(conf, Duration::from_secs(10)));
+        let ctx = HttpContext{databases: databases.clone(), settings_loader: None};
+        let (status, _) = call(&ctx, Method::POST, "/admin/reload", Value::Null).await;
+        assert_eq!(StatusCode::BAD_REQUEST, status);
+
+        let query_log = QueryLog{slow_query_threshold: Some(Duration::from_millis(250)), redact_parameters: true};
+        let settings_loader: SettingsLoader = Arc::new(move || Ok(ReloadableSettings{page_cache_size: 8 * 4096, query_log}));
+        let ctx = HttpContext{databases: databases.clone(), settings_loader: Some(settings_loader)};
+        call(&ctx, Method::PUT, "/db/analytics", Value::Null).await;
+        let (status, body) = call(&ctx, Method::POST, "/admin/reload", Value::Null).await;
+        assert_eq!(StatusCode::OK, status);
+        assert_eq!(json!(250), body["settings"]["slow_query_threshold_ms"]);
+        assert_eq!(8 * 4096, databases.get_reloadable_settings().page_cache_size);
+        let analytics = databases.get_database(Some("analytics")).expect("analytics");
+        assert_eq!(query_log, analytics.graph_request_handler.read().unwrap().get_query_log());
+        let (status, _) = call(&ctx, Method::POST, "/db/analytics/query", json!({"statement": "CREATE (n:Person {name: 'ada'}) RETURN n"})).await;
+        assert_eq!(StatusCode::OK, status);
+
+        let failing_loader: SettingsLoader = Arc::new(|| Err(String::from("invalid settings")));
+        let ctx = HttpContext{databases: databases.clone(), settings_loader: Some(failing_loader)};
+        let (status, _) = call(&ctx, Method::POST, "/admin/reload", Value::Null).await;
+        assert_eq!(StatusCode::INTERNAL_SERVER_ERROR, status);
+        assert_eq!(query_log, databases.get_database(None).expect("default").graph_request_handler.read().unwrap().get_query_log());
+    }
+
+    #[tokio::test]
+    async fn test_http_busy() {
+        let main_dir = build_dir_path_and_rm_old("test_http_busy").expect("db path");
+        let conf = InitContext::new(&main_dir).expect("can't create context");
+        let databases = DatabaseManager::new(conf, Duration::from_secs(10)).with_admission_control(AdmissionControl::new(1, 0));
+        let ctx = HttpContext{databases: Arc::new(databases), settings_loader: None};
+        let permit = ctx.databases.admit().expect("running query");
+        let (status, body) = call(&ctx, Method::POST, "/db/query", json!({"statement": "MATCH (n:Person) RETURN n"})).await;
+        assert_eq!(StatusCode::SERVICE_UNAVAILABLE, status);
+        assert_eq!(json!("Neo.TransientError.Request.NoThreadsAvailable"), body["errors"][0]["code"]);
+        drop(permit);
+        let (status, _) = call(&ctx, Method::POST, "/db/query", json!({"statement": "MATCH (n:Person) RETURN n"})).await;
+        assert_eq!(StatusCode::OK, status);
+    }
+
+    #[tokio::test]
+    async fn test_http_query_limits() {
+        let main_dir = build_dir_path_and_rm_old("test_http_query_limits").expect("db path");
+        let conf = InitContext::new(&main_dir).expect("can't create context");
+        let ctx = HttpContext{databases: Arc::new(DatabaseManager::new(conf, Duration::from_secs(10)).with_query_memory_limit(1)), settings_loader: None};
+        let (status, _) = call(&ctx, Method::POST, "/db/query", json!({"statement": "CREATE (n:Person {name: 'ada'}) RETURN n"})).await;
+        assert_eq!(StatusCode::OK, status);
+        let (status, body) = call(&ctx, Method::POST, "/db/query", json!({"statement": "MATCH (n:Person) RETURN n"})).await;
+        assert_eq!(StatusCode::SERVICE_UNAVAILABLE, status);
+        assert_eq!(json!("Neo.TransientError.General.MemoryPoolOutOfMemoryError"), body["errors"][0]["code"]);
+
+        let (status, body) = call(&ctx, Method::GET, "/admin/queries", Value::Null).await;
+        assert_eq!(StatusCode::OK, status);
+        assert_eq!(json!([]), body["queries"]);
+        let running = ctx.databases.start_query("slow-query");
+        let (_, body) = call(&ctx, Method::GET, "/admin/queries", Value::Null).await;
+        assert_eq!(json!("slow-query"), body["queries"][0]["request_id"]);
+        let (status, _) = call(&ctx, Method::DELETE, "/admin/queries/slow-query", Value::Null).await;
+        assert_eq!(StatusCode::OK, status);
+        assert!(running.get_query_context().check().is_err());
+        drop(running);
+        let (status, _) = call(&ctx, Method::DELETE, "/admin/queries/slow-query", Value::Null).await;
+        assert_eq!(StatusCode::NOT_FOUND, status);
+    }
+
+    #[tokio::test]
+    async fn test_http_query_timeout() {
+        let main_dir = build_dir_path_and_rm_old("test_http_query_timeout").expect("db path");
+        let conf = InitContext::new(&main_dir).expect("can't create context");
+        let ctx = HttpContext{databases: Arc::new(DatabaseManager::new(conf, Duration::from_secs(10))), settings_loader: None};
+        let (status, _) = call(&ctx, Method::POST, "/db/tx", json!({"statement": "CREATE (n:Person {name: 'ada'}) RETURN n"})).await;
+        assert_eq!(StatusCode::CREATED, status);
+        let (status, body) = call(&ctx, Method::POST, "/db/query", json!({"statement": "CREATE (n:Person {name: 'alan'}) RETURN n", "timeout": 50})).await;
+        assert_eq!(StatusCode::REQUEST_TIMEOUT, status);
+        assert_eq!(json!("Neo.ClientError.Transaction.TransactionTimedOut"), body["errors"][0]["code"]);
+        let (status, _) = call(&ctx, Method::POST, "/db/query", json!({"statement": "MATCH (n:Person) RETURN n", "timeout": "soon"})).await;
+        assert_eq!(StatusCode::BAD_REQUEST, status);
+    }
+}
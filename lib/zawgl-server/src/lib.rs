@@ -31,49 +31,71 @@ use bson::Document;
 use futures_util::{
     SinkExt, StreamExt,
 };
-use zawgl_tx_handler::request_handler::GraphRequestHandler;
-use zawgl_tx_handler::request_handler::RequestHandler;
-use zawgl_tx_handler::tx_handler::GraphTxHandler;
-use zawgl_tx_handler::tx_handler::TxHandler;
-use parking_lot::ReentrantMutex;
+use zawgl_tx_handler::databases::Databases;
+use zawgl_tx_handler::tx_handler::DEFAULT_TX_TIMEOUT_SECS;
 use tokio_tungstenite::tungstenite::Message;
-use std::cell::RefCell;
-use std::sync::RwLock;
-use std::sync::Arc;
+use std::time::Duration;
 use log::*;
 use std::net::SocketAddr;
-use tokio::net::{TcpListener, TcpStream};
+use tokio::io::{AsyncRead, AsyncWrite};
 use tokio_tungstenite::{accept_async, tungstenite::Error};
 use serde_json::Value;
 use std::result::Result;
-use crate::open_cypher_request_handler::handle_open_cypher_request;
+use crate::open_cypher_request_handler::{build_error_response, handle_open_cypher_session_request};
+use zawgl_cypher::session::CypherSession;
+use zawgl_cypher::CypherError;
+use zawgl_gremlin::gremlin::{GremlinRequest, GremlinResponse};
+use zawgl_gremlin::handler::{GremlinError, handle_gremlin_query_request};
 
-//use self::json_gremlin_request_handler::*;
+use self::json_gremlin_request_handler::*;
+use self::graph_binary_gremlin_request_handler::*;
+use zawgl_gremlin::graph_binary::GRAPH_BINARY_MIME_TYPE;
 mod result;
-//mod json_gremlin_request_handler;
+mod json_gremlin_request_handler;
+mod graph_binary_gremlin_request_handler;
 mod open_cypher_request_handler;
-use self::result::ServerError;
+mod http_request_handler;
+pub mod server;
+pub use self::result::ServerError;
+pub use self::server::{Server, ServerBuilder};
 use zawgl_core::model::init::InitContext;
 
-async fn accept_connection<'a>(peer: SocketAddr, tx_handler: TxHandler, graph_request_handler: RequestHandler<'a>, stream: TcpStream) {
-    if let Err(e) = handle_connection(peer, tx_handler, graph_request_handler, stream).await {
+async fn accept_connection<S>(peer: SocketAddr, databases: Databases<'static>, stream: S) where S: AsyncRead + AsyncWrite + Unpin {
+    if let Err(e) = handle_connection(peer, databases, stream).await {
         match e {
-            ServerError::WebsocketError(te) => match te {
-                Error::ConnectionClosed | Error::Protocol(_) | Error::Utf8 => (),
-                err => error!("Error processing connection: {}", err),
-            },
-            ServerError::ParsingError(err_msg) => error!("Parsing error: {}", err_msg),
-            ServerError::HeaderError => error!("wrong header"),
-            ServerError::CypherTxError(_) => todo!(),
+            ServerError::WebsocketError(Error::ConnectionClosed | Error::Protocol(_) | Error::Utf8) => (),
+            err => error!("Error processing connection with {}: {}", peer, err),
         }
     }
 }
 
 
-async fn handle_connection<'a, 'b>(peer: SocketAddr, tx_handler: TxHandler, graph_request_handler: RequestHandler<'a>, stream: TcpStream) -> Result<(), ServerError> {
+/// Runs the requests on the blocking threads pool as they wait for the transaction locks and the store IO,
+/// the tasks of the other connections keep running on the async workers.
+pub(crate) async fn run_blocking<F, T>(request: F) -> Result<T, ServerError> where F: FnOnce() -> T + Send + 'static, T: Send + 'static {
+    tokio::task::spawn_blocking(request).await.map_err(ServerError::TaskError)
+}
+
+fn handle_cypher_request(databases: &Databases<'static>, session: &mut CypherSession, doc: &Document) -> Result<Document, CypherError> {
+    let _permit = databases.admit().map_err(CypherError::TxError)?;
+    let db = databases.get_database(doc.get_str("database").ok()).map_err(CypherError::TxError)?;
+    let query = databases.start_query(doc.get_str("request_id").unwrap_or_default());
+    handle_open_cypher_session_request(db.tx_handler, db.graph_request_handler, session, doc, query.get_query_context())
+}
+
+fn handle_gremlin_request(databases: &Databases<'static>, request: &GremlinRequest) -> Result<GremlinResponse, GremlinError> {
+    let _permit = databases.admit().map_err(GremlinError::TxError)?;
+    let db = databases.get_database(None).map_err(GremlinError::TxError)?;
+    let query = databases.start_query(&request.request_id);
+    handle_gremlin_query_request(db.tx_handler, db.graph_request_handler, request, query.get_query_context())
+}
+
+/// Gremlin requests are sent to the default database and cypher requests to the one named by their `database` field.
+async fn handle_connection<S>(peer: SocketAddr, databases: Databases<'static>, stream: S) -> Result<(), ServerError> where S: AsyncRead + AsyncWrite + Unpin {
     let ws_stream = accept_async(stream).await.expect("Failed to accept");
     info!("New WebSocket connection: {}", peer);
     let (mut ws_sender, mut ws_receiver) = ws_stream.split();
+    let mut cypher_session = CypherSession::new();
 
     let mut msg_fut = ws_receiver.next();
     loop {
@@ -83,17 +105,33 @@ async fn handle_connection<'a, 'b>(peer: SocketAddr, tx_handler: TxHandler, grap
                 if msg.is_binary() {
                     let json_gremlin_prefix = "!application/vnd.gremlin-v3.0+json".as_bytes();
                     let open_cypher_prefix = "!application/openCypher".as_bytes();
+                    let graph_binary_prefix = [&[GRAPH_BINARY_MIME_TYPE.len() as u8], GRAPH_BINARY_MIME_TYPE.as_bytes()].concat();
                     let data = msg.into_data();
                     if data.len() > json_gremlin_prefix.len() && &data[..json_gremlin_prefix.len()] == json_gremlin_prefix {
-                        //let v: Value = serde_json::from_reader(&data[json_gremlin_prefix.len()..]).map_err(|err| ServerError::ParsingError(err.to_string()))?;
-                        //let gremlin_reply = handle_gremlin_json_request(tx_handler.clone(), graph_request_handler.clone(), &v).map_err(|err| ServerError::GremlinTxError(err))?;
-                        //let res_msg = serde_json::to_string(&gremlin_reply).map_err(|err| ServerError::ParsingError(err.to_string()))?;
-                        //debug!("gremlin response msg: {}", res_msg);
-                        //let response = Message::Text(res_msg);
-                        //ws_sender.send(response).await.map_err(ServerError::WebsocketError)?;
+                        let v: Value = serde_json::from_reader(&data[json_gremlin_prefix.len()..]).map_err(|err| ServerError::ParsingError(err.to_string()))?;
+                        let databases = databases.clone();
+                        let gremlin_reply = run_blocking(move || handle_gremlin_json_request(&databases, &v)).await?.map_err(ServerError::GremlinTxError)?;
+                        let res_msg = serde_json::to_string(&gremlin_reply).map_err(|err| ServerError::ParsingError(err.to_string()))?;
+                        debug!("gremlin response msg: {}", res_msg);
+                        let response = Message::Text(res_msg);
+                        ws_sender.send(response).await.map_err(ServerError::WebsocketError)?;
+                    } else if data.len() > graph_binary_prefix.len() && data[..graph_binary_prefix.len()] == graph_binary_prefix[..] {
+                        let databases = databases.clone();
+                        let prefix_len = graph_binary_prefix.len();
+                        let gremlin_reply = run_blocking(move || handle_graph_binary_gremlin_request(&databases, &data[prefix_len..])).await?.map_err(ServerError::GremlinTxError)?;
+                        let response = Message::Binary(gremlin_reply);
+                        ws_sender.send(response).await.map_err(ServerError::WebsocketError)?;
                     } else if data.len() > open_cypher_prefix.len() &&  &data[..open_cypher_prefix.len()] == open_cypher_prefix {
                         let doc = Document::from_reader(&data[open_cypher_prefix.len()..]).map_err(|err| ServerError::ParsingError(err.to_string()))?;
-                        let cypher_reply = handle_open_cypher_request(tx_handler.clone(), graph_request_handler.clone(), &doc).map_err(|err| ServerError::CypherTxError(err))?;
+                        let request_id = doc.get_str("request_id").map(String::from).unwrap_or_default();
+                        let databases = databases.clone();
+                        let mut session = cypher_session;
+                        let (cypher_reply, session) = run_blocking(move || {
+                            let reply = handle_cypher_request(&databases, &mut session, &doc);
+                            (reply, session)
+                        }).await?;
+                        cypher_session = session;
+                        let cypher_reply = cypher_reply.unwrap_or_else(|err| build_error_response(&request_id, &err));
                         let mut response_data = Vec::new();
                         cypher_reply.to_writer(&mut response_data).map_err(|err| ServerError::ParsingError(err.to_string()))?;
                         let response = Message::Binary(response_data);
@@ -110,6 +148,7 @@ async fn handle_connection<'a, 'b>(peer: SocketAddr, tx_handler: TxHandler, grap
             None => break, // WebSocket stream terminated.
         }
     }
+    debug!("prepared statements of {}: {:?}", peer, cypher_session.get_statistics());
 
     Ok(())
 }
@@ -117,14 +156,10 @@ async fn handle_connection<'a, 'b>(peer: SocketAddr, tx_handler: TxHandler, grap
 
 
 pub async fn run_server<F>(addr: &str, conf: InitContext<'static>, callback: F) where F : FnOnce() -> () {
-    let tx_handler = Arc::new(ReentrantMutex::new(RefCell::new(GraphTxHandler::new())));
-    let graph_request_handler = Arc::new(RwLock::new(GraphRequestHandler::new(conf)));
-    let listener = TcpListener::bind(&addr).await.expect("Can't listen");
-    info!("Listening on: {}", addr);
-    callback();
-    while let Ok((stream, _)) = listener.accept().await {
-        let peer = stream.peer_addr().expect("connected streams should have a peer address");
-        info!("Peer address: {}", peer);
-        tokio::spawn(accept_connection(peer, tx_handler.clone(), graph_request_handler.clone(), stream));
-    }
+    run_server_with_tx_timeout(addr, conf, Duration::from_secs(DEFAULT_TX_TIMEOUT_SECS), callback).await
+}
+
+pub async fn run_server_with_tx_timeout<F>(addr: &str, conf: InitContext<'static>, tx_timeout: Duration, callback: F) where F : FnOnce() -> () {
+    let server = ServerBuilder::new().address(addr).tx_timeout(tx_timeout).build(conf).expect("Can't build server");
+    server.run_until(callback, std::future::pending()).await.expect("Can't listen");
 }
@@ -19,10 +19,51 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-use bson::Document;
+use bson::{Document, doc};
 use zawgl_cypher::CypherError;
+use zawgl_cypher::cypher::parser::error::ParserError;
+use zawgl_cypher::session::CypherSession;
+use zawgl_core::error::GraphError;
+use zawgl_core::graph_engine::query_context::{QueryAbort, QueryContext};
+use zawgl_tx_handler::DatabaseError;
 use zawgl_tx_handler::{request_handler::RequestHandler, tx_handler::TxHandler};
 
-pub fn handle_open_cypher_request<'a>(tx_handler: TxHandler, graph_request_handler: RequestHandler<'a>, cypher_request: &Document) -> Result<Document, CypherError> {
-    zawgl_cypher::handle_open_cypher_request(tx_handler, graph_request_handler, cypher_request)
-}
\ No newline at end of file
+pub fn handle_open_cypher_request<'a>(tx_handler: TxHandler, graph_request_handler: RequestHandler<'a>, cypher_request: &Document, query_context: &QueryContext) -> Result<Document, CypherError> {
+    zawgl_cypher::handle_open_cypher_query_request(tx_handler, graph_request_handler, &mut CypherSession::new(), cypher_request, query_context)
+}
+
+pub fn handle_open_cypher_session_request<'a>(tx_handler: TxHandler, graph_request_handler: RequestHandler<'a>, session: &mut CypherSession, cypher_request: &Document, query_context: &QueryContext) -> Result<Document, CypherError> {
+    zawgl_cypher::handle_open_cypher_query_request(tx_handler, graph_request_handler, session, cypher_request, query_context)
+}
+
+/// Response of a failed query, the connection stays open for the next requests.
+pub fn build_error_response(request_id: &str, err: &CypherError) -> Document {
+    doc!{"request_id": request_id, "error": {"code": get_error_code(err), "message": err.to_string()}}
+}
+
+fn get_error_code(err: &CypherError) -> &'static str {
+    match err {
+        CypherError::RequestError => "Neo.ClientError.Statement.SyntaxError",
+        CypherError::ResponseError => "Neo.DatabaseError.General.UnknownError",
+        CypherError::QueryError(ParserError::SemanticError) => "Neo.ClientError.Statement.SemanticError",
+        CypherError::QueryError(_) => "Neo.ClientError.Statement.SyntaxError",
+        CypherError::TxError(err) => match err {
+            DatabaseError::EngineError(GraphError::UniqueConstraintViolation(_)) => "Neo.ClientError.Schema.ConstraintValidationFailed",
+            DatabaseError::EngineError(GraphError::NodeHasRelationships(_)) => "Neo.ClientError.Schema.ConstraintValidationFailed",
+            DatabaseError::TxNotFound(_) => "Neo.ClientError.Transaction.TransactionNotFound",
+            DatabaseError::TxRollbackOnly(_) => "Neo.ClientError.Transaction.TransactionMarkedAsFailed",
+            DatabaseError::TxOutdated(_) => "Neo.TransientError.Transaction.Outdated",
+            DatabaseError::ProcedureError(_) => "Neo.ClientError.Procedure.ProcedureCallFailed",
+            DatabaseError::WriteInReadOnlyTx => "Neo.ClientError.Statement.AccessMode",
+            DatabaseError::Deadlock => "Neo.TransientError.Transaction.DeadlockDetected",
+            DatabaseError::LockTimeout => "Neo.TransientError.Transaction.LockAcquisitionTimeout",
+            DatabaseError::UnknownDatabase(_) => "Neo.ClientError.Database.DatabaseNotFound",
+            DatabaseError::TriggerVeto(_, _) => "Neo.ClientError.Transaction.TransactionHookFailed",
+            DatabaseError::QueryAborted(QueryAbort::Cancelled) => "Neo.TransientError.Transaction.Terminated",
+            DatabaseError::QueryAborted(QueryAbort::MemoryLimitExceeded) => "Neo.TransientError.General.MemoryPoolOutOfMemoryError",
+            DatabaseError::QueryAborted(QueryAbort::Timeout) => "Neo.ClientError.Transaction.TransactionTimedOut",
+            DatabaseError::Busy => "Neo.TransientError.Request.NoThreadsAvailable",
+            _ => "Neo.DatabaseError.General.UnknownError",
+        },
+    }
+}
@@ -19,13 +19,29 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
+use thiserror::Error;
 use zawgl_cypher::CypherError;
-use tokio_tungstenite::tungstenite::Error;
+use zawgl_gremlin::handler::GremlinError;
+use tokio_tungstenite::tungstenite;
 
-#[derive(Debug)]
+#[derive(Debug, Error)]
 pub enum ServerError {
+    #[error("wrong header")]
     HeaderError,
+    #[error("parsing error: {0}")]
     ParsingError(String),
-    WebsocketError(Error),
-    CypherTxError(CypherError),
+    #[error("websocket error: {0}")]
+    WebsocketError(#[source] tungstenite::Error),
+    #[error("cypher error: {0}")]
+    CypherTxError(#[source] CypherError),
+    #[error("gremlin error: {0}")]
+    GremlinTxError(#[source] GremlinError),
+    #[error("IO error: {0}")]
+    IoError(#[source] std::io::Error),
+    #[error("TLS error: {0}")]
+    TlsError(String),
+    #[error("HTTP error: {0}")]
+    HttpError(#[source] hyper::Error),
+    #[error("request task error: {0}")]
+    TaskError(#[source] tokio::task::JoinError),
 }
\ No newline at end of file
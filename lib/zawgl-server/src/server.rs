@@ -0,0 +1,507 @@
+// MIT License
+//
+// Copyright (c) 2022 Alexandre RICCIARDI
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use std::fs::File;
+use std::future::Future;
+use std::io::BufReader;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use log::*;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Semaphore;
+use tokio_rustls::TlsAcceptor;
+use tokio_rustls::rustls::{Certificate, PrivateKey, ServerConfig};
+use zawgl_core::model::init::{InitContext, ReloadableSettings};
+use zawgl_bolt::handle_bolt_connection;
+use zawgl_tx_handler::admission::AdmissionControl;
+use zawgl_tx_handler::databases::{DatabaseManager, Databases};
+use zawgl_tx_handler::tx_handler::DEFAULT_TX_TIMEOUT_SECS;
+
+use crate::accept_connection;
+use crate::http_request_handler::handle_http_connection;
+use crate::result::ServerError;
+
+pub const DEFAULT_BIND_ADDRESS: &str = "127.0.0.1";
+pub const DEFAULT_PORT: u16 = 8182;
+pub const DEFAULT_MAX_CONNECTIONS: usize = 1024;
+pub const DEFAULT_SHUTDOWN_TIMEOUT_SECS: u64 = 30;
+pub const DEFAULT_MAX_QUEUED_QUERIES: usize = 128;
+
+/// Reads the settings applied on a reload, an error leaves the running settings unchanged.
+pub type SettingsLoader = Arc<dyn Fn() -> Result<ReloadableSettings, String> + Send + Sync>;
+
+#[derive(Debug, Clone)]
+struct TlsSettings {
+    cert_path: String,
+    key_path: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Protocol {
+    WebSocket,
+    Bolt,
+    Http,
+}
+
+pub struct ServerBuilder {
+    bind_address: String,
+    port: u16,
+    bolt_port: Option<u16>,
+    http_port: Option<u16>,
+    tls: Option<TlsSettings>,
+    max_connections: usize,
+    tx_timeout: Duration,
+    shutdown_timeout: Duration,
+    settings_loader: Option<SettingsLoader>,
+    max_concurrent_queries: Option<usize>,
+    max_queued_queries: usize,
+    query_memory_limit: Option<usize>,
+}
+
+impl ServerBuilder {
+    pub fn new() -> Self {
+        ServerBuilder {
+            bind_address: String::from(DEFAULT_BIND_ADDRESS),
+            port: DEFAULT_PORT,
+            bolt_port: None,
+            http_port: None,
+            tls: None,
+            max_connections: DEFAULT_MAX_CONNECTIONS,
+            tx_timeout: Duration::from_secs(DEFAULT_TX_TIMEOUT_SECS),
+            shutdown_timeout: Duration::from_secs(DEFAULT_SHUTDOWN_TIMEOUT_SECS),
+            settings_loader: None,
+            max_concurrent_queries: None,
+            max_queued_queries: DEFAULT_MAX_QUEUED_QUERIES,
+            query_memory_limit: None,
+        }
+    }
+
+    pub fn bind_address(mut self, bind_address: &str) -> Self {
+        self.bind_address = String::from(bind_address);
+        self
+    }
+
+    pub fn port(mut self, port: u16) -> Self {
+        self.port = port;
+        self
+    }
+
+    /// Also serves the Bolt protocol on this port, with the same bind address.
+    pub fn bolt_port(mut self, bolt_port: u16) -> Self {
+        self.bolt_port = Some(bolt_port);
+        self
+    }
+
+    /// Also serves the HTTP/JSON query API on this port, with the same bind address.
+    pub fn http_port(mut self, http_port: u16) -> Self {
+        self.http_port = Some(http_port);
+        self
+    }
+
+    /// Sets both the bind address and the port from a `host:port` address.
+    pub fn address(self, address: &str) -> Self {
+        match address.rsplit_once(':').and_then(|(host, port)| Some((host, port.parse::<u16>().ok()?))) {
+            Some((host, port)) => self.bind_address(host).port(port),
+            None => self.bind_address(address),
+        }
+    }
+
+    /// Serves the connections over TLS with a PEM encoded certificate chain and private key.
+    pub fn tls(mut self, cert_path: &str, key_path: &str) -> Self {
+        self.tls = Some(TlsSettings{cert_path: String::from(cert_path), key_path: String::from(key_path)});
+        self
+    }
+
+    pub fn max_connections(mut self, max_connections: usize) -> Self {
+        self.max_connections = max_connections;
+        self
+    }
+
+    pub fn tx_timeout(mut self, tx_timeout: Duration) -> Self {
+        self.tx_timeout = tx_timeout;
+        self
+    }
+
+    pub fn shutdown_timeout(mut self, shutdown_timeout: Duration) -> Self {
+        self.shutdown_timeout = shutdown_timeout;
+        self
+    }
+
+    /// Bounds the queries running at once, the ones over the limit wait in a queue and are rejected
+    /// with a busy error when the queue is full.
+    pub fn max_concurrent_queries(mut self, max_concurrent_queries: usize) -> Self {
+        self.max_concurrent_queries = Some(max_concurrent_queries);
+        self
+    }
+
+    pub fn max_queued_queries(mut self, max_queued_queries: usize) -> Self {
+        self.max_queued_queries = max_queued_queries;
+        self
+    }
+
+    /// Aborts the queries once the estimated size in bytes of the graphs they match exceeds the limit.
+    pub fn query_memory_limit(mut self, query_memory_limit: usize) -> Self {
+        self.query_memory_limit = Some(query_memory_limit);
+        self
+    }
+
+    /// Reloads the settings on SIGHUP and on `POST /admin/reload` requests of the HTTP API.
+    pub fn settings_loader<F>(mut self, loader: F) -> Self where F: Fn() -> Result<ReloadableSettings, String> + Send + Sync + 'static {
+        self.settings_loader = Some(Arc::new(loader));
+        self
+    }
+
+    pub fn build(self, conf: InitContext<'static>) -> Result<Server, ServerError> {
+        let tls_acceptor = match &self.tls {
+            Some(tls) => Some(load_tls_acceptor(tls)?),
+            None => None,
+        };
+        Ok(Server {
+            address: format!("{}:{}", self.bind_address, self.port),
+            bolt_address: self.bolt_port.map(|port| format!("{}:{}", self.bind_address, port)),
+            http_address: self.http_port.map(|port| format!("{}:{}", self.bind_address, port)),
+            conf: conf,
+            tls_acceptor: tls_acceptor,
+            max_connections: self.max_connections,
+            tx_timeout: self.tx_timeout,
+            shutdown_timeout: self.shutdown_timeout,
+            settings_loader: self.settings_loader,
+            max_concurrent_queries: self.max_concurrent_queries,
+            max_queued_queries: self.max_queued_queries,
+            query_memory_limit: self.query_memory_limit,
+        })
+    }
+}
+
+pub struct Server {
+    address: String,
+    bolt_address: Option<String>,
+    http_address: Option<String>,
+    conf: InitContext<'static>,
+    tls_acceptor: Option<TlsAcceptor>,
+    max_connections: usize,
+    tx_timeout: Duration,
+    shutdown_timeout: Duration,
+    settings_loader: Option<SettingsLoader>,
+    max_concurrent_queries: Option<usize>,
+    max_queued_queries: usize,
+    query_memory_limit: Option<usize>,
+}
+
+impl Server {
+    pub fn get_address(&self) -> &str {
+        &self.address
+    }
+
+    pub fn get_bolt_address(&self) -> Option<&str> {
+        self.bolt_address.as_deref()
+    }
+
+    pub fn get_http_address(&self) -> Option<&str> {
+        self.http_address.as_deref()
+    }
+
+    pub fn is_tls(&self) -> bool {
+        self.tls_acceptor.is_some()
+    }
+
+    /// Serves the requests until SIGTERM or ctrl-c is received.
+    pub async fn run<F>(self, callback: F) -> Result<(), ServerError> where F : FnOnce() -> () {
+        self.run_until(callback, shutdown_signal()).await
+    }
+
+    /// Serves the requests until the shutdown future completes, then stops accepting connections
+    /// and waits for the open ones to be closed, at most for the shutdown timeout.
+    pub async fn run_until<F, S>(self, callback: F, shutdown: S) -> Result<(), ServerError> where F : FnOnce() -> (), S: Future<Output = ()> {
+        let admission = match self.max_concurrent_queries {
+            Some(max_concurrent_queries) => AdmissionControl::new(max_concurrent_queries, self.max_queued_queries),
+            None => AdmissionControl::unbounded(),
+        };
+        let mut databases = DatabaseManager::new(self.conf, self.tx_timeout).with_admission_control(admission);
+        if let Some(query_memory_limit) = self.query_memory_limit {
+            databases = databases.with_query_memory_limit(query_memory_limit);
+        }
+        let databases = Arc::new(databases);
+        let listener = TcpListener::bind(&self.address).await.map_err(ServerError::IoError)?;
+        info!("Listening on: {}", self.address);
+        let bolt_listener = bind_optional(&self.bolt_address, "Bolt").await?;
+        let http_listener = bind_optional(&self.http_address, "HTTP").await?;
+        callback();
+        let connections = Arc::new(Semaphore::new(self.max_connections));
+        let mut reload_signal = ReloadSignal::new(self.settings_loader.is_some());
+        tokio::pin!(shutdown);
+        loop {
+            let (protocol, accepted) = tokio::select! {
+                accepted = listener.accept() => (Protocol::WebSocket, accepted),
+                accepted = accept_optional(&bolt_listener) => (Protocol::Bolt, accepted),
+                accepted = accept_optional(&http_listener) => (Protocol::Http, accepted),
+                _ = reload_signal.recv() => {
+                    if let Some(loader) = &self.settings_loader {
+                        let _ = reload_settings(&databases, loader);
+                    }
+                    continue;
+                }
+                _ = &mut shutdown => break,
+            };
+            let (stream, peer) = match accepted {
+                Ok(accepted) => accepted,
+                Err(err) => {
+                    error!("Error accepting connection: {}", err);
+                    continue;
+                }
+            };
+            let permit = match connections.clone().try_acquire_owned() {
+                Ok(permit) => permit,
+                Err(_) => {
+                    warn!("Max connections reached, rejecting peer {}", peer);
+                    continue;
+                }
+            };
+            info!("Peer address: {}", peer);
+            let databases = databases.clone();
+            let tls_acceptor = self.tls_acceptor.clone();
+            let settings_loader = self.settings_loader.clone();
+            tokio::spawn(async move {
+                match tls_acceptor {
+                    Some(acceptor) => match acceptor.accept(stream).await {
+                        Ok(tls_stream) => serve_connection(protocol, peer, databases, settings_loader, tls_stream).await,
+                        Err(err) => error!("TLS handshake error with {}: {}", peer, err),
+                    },
+                    None => serve_connection(protocol, peer, databases, settings_loader, stream).await,
+                }
+                drop(permit);
+            });
+        }
+        drop(http_listener);
+        drop(bolt_listener);
+        drop(listener);
+        info!("Shutting down, waiting for open connections");
+        let closed = tokio::time::timeout(self.shutdown_timeout, connections.acquire_many(self.max_connections as u32)).await;
+        if closed.is_err() {
+            warn!("Shutdown timeout reached with open connections");
+        }
+        Ok(())
+    }
+}
+
+async fn bind_optional(address: &Option<String>, protocol_name: &str) -> Result<Option<TcpListener>, ServerError> {
+    match address {
+        Some(address) => {
+            let listener = TcpListener::bind(address).await.map_err(ServerError::IoError)?;
+            info!("Listening to {} connections on: {}", protocol_name, address);
+            Ok(Some(listener))
+        }
+        None => Ok(None),
+    }
+}
+
+async fn accept_optional(listener: &Option<TcpListener>) -> std::io::Result<(TcpStream, SocketAddr)> {
+    match listener {
+        Some(listener) => listener.accept().await,
+        None => std::future::pending().await,
+    }
+}
+
+async fn serve_connection<S>(protocol: Protocol, peer: SocketAddr, databases: Databases<'static>, settings_loader: Option<SettingsLoader>, stream: S) where S: AsyncRead + AsyncWrite + Unpin + Send + 'static {
+    match protocol {
+        Protocol::WebSocket => accept_connection(peer, databases, stream).await,
+        Protocol::Bolt => {
+            if let Err(err) = handle_bolt_connection(peer, databases, stream).await {
+                error!("Bolt connection error with {}: {}", peer, err);
+            }
+        }
+        Protocol::Http => {
+            if let Err(err) = handle_http_connection(peer, databases, settings_loader, stream).await {
+                error!("HTTP connection error with {}: {}", peer, err);
+            }
+        }
+    }
+}
+
+/// Loads the settings and applies them to the databases.
+pub(crate) fn reload_settings(databases: &Databases<'static>, loader: &SettingsLoader) -> Result<ReloadableSettings, String> {
+    match loader() {
+        Ok(settings) => {
+            databases.reload_settings(settings);
+            Ok(settings)
+        }
+        Err(err) => {
+            error!("Can't reload settings: {}", err);
+            Err(err)
+        }
+    }
+}
+
+/// SIGHUP stream, pending forever when reloads are disabled or on other platforms.
+struct ReloadSignal {
+    #[cfg(unix)]
+    hangup: Option<tokio::signal::unix::Signal>,
+}
+
+impl ReloadSignal {
+    fn new(enabled: bool) -> Self {
+        #[cfg(unix)]
+        {
+            use tokio::signal::unix::{signal, SignalKind};
+            let hangup = if enabled {
+                signal(SignalKind::hangup()).map_err(|err| error!("Can't listen to SIGHUP: {}", err)).ok()
+            } else {
+                None
+            };
+            ReloadSignal{hangup}
+        }
+        #[cfg(not(unix))]
+        {
+            let _ = enabled;
+            ReloadSignal{}
+        }
+    }
+
+    async fn recv(&mut self) {
+        #[cfg(unix)]
+        {
+            if let Some(hangup) = &mut self.hangup {
+                if hangup.recv().await.is_some() {
+                    info!("SIGHUP received, reloading settings");
+                    return;
+                }
+            }
+        }
+        std::future::pending().await
+    }
+}
+
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = terminate.recv() => info!("SIGTERM received"),
+                    _ = tokio::signal::ctrl_c() => info!("ctrl-c received"),
+                }
+            }
+            Err(err) => {
+                error!("Can't listen to SIGTERM: {}", err);
+                let _ = tokio::signal::ctrl_c().await;
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+    }
+}
+
+fn load_tls_acceptor(tls: &TlsSettings) -> Result<TlsAcceptor, ServerError> {
+    let cert_file = File::open(&tls.cert_path).map_err(ServerError::IoError)?;
+    let certs = rustls_pemfile::certs(&mut BufReader::new(cert_file)).map_err(ServerError::IoError)?
+        .into_iter().map(Certificate).collect();
+    let key = load_private_key(&tls.key_path)?;
+    let config = ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .map_err(|err| ServerError::TlsError(err.to_string()))?;
+    Ok(TlsAcceptor::from(Arc::new(config)))
+}
+
+fn load_private_key(key_path: &str) -> Result<PrivateKey, ServerError> {
+    let key_file = File::open(key_path).map_err(ServerError::IoError)?;
+    let mut reader = BufReader::new(key_file);
+    loop {
+        match rustls_pemfile::read_one(&mut reader).map_err(ServerError::IoError)? {
+            Some(rustls_pemfile::Item::RSAKey(key)) | Some(rustls_pemfile::Item::PKCS8Key(key)) | Some(rustls_pemfile::Item::ECKey(key)) => return Ok(PrivateKey(key)),
+            Some(_) => continue,
+            None => return Err(ServerError::TlsError(format!("no private key found in {}", key_path))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_server {
+    use super::*;
+    use zawgl_core::test_utils::build_dir_path_and_rm_old;
+
+    #[test]
+    fn test_builder_address() {
+        let main_dir = build_dir_path_and_rm_old("test_server_builder").expect("db path");
+        let conf = InitContext::new(&main_dir).expect("can't create context");
+        let server = ServerBuilder::new().address("0.0.0.0:9182").build(conf).expect("server");
+        assert_eq!("0.0.0.0:9182", server.get_address());
+        assert_eq!(None, server.get_bolt_address());
+        assert!(!server.is_tls());
+
+        let conf = InitContext::new(&main_dir).expect("can't create context");
+        let server = ServerBuilder::new().bind_address("localhost").bolt_port(7688).http_port(7474).build(conf).expect("server");
+        assert_eq!(format!("localhost:{}", DEFAULT_PORT), server.get_address());
+        assert_eq!(Some("localhost:7688"), server.get_bolt_address());
+        assert_eq!(Some("localhost:7474"), server.get_http_address());
+    }
+
+    #[test]
+    fn test_missing_tls_files() {
+        let main_dir = build_dir_path_and_rm_old("test_server_tls").expect("db path");
+        let conf = InitContext::new(&main_dir).expect("can't create context");
+        let res = ServerBuilder::new().tls("missing-cert.pem", "missing-key.pem").build(conf);
+        assert!(matches!(res, Err(ServerError::IoError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_graceful_shutdown() {
+        let main_dir = build_dir_path_and_rm_old("test_server_shutdown").expect("db path");
+        let conf = InitContext::new(&main_dir).expect("can't create context");
+        let server = ServerBuilder::new().port(9183).bolt_port(9184).http_port(9185).max_connections(2).build(conf).expect("server");
+        let (tx, rx) = tokio::sync::oneshot::channel::<()>();
+        let shutdown = async move {
+            let _ = rx.await;
+        };
+        let res = server.run_until(move || {
+            let _ = tx.send(());
+        }, shutdown).await;
+        assert!(res.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_blocking_request_does_not_block_connections() {
+        let runtime_thread = std::thread::current().id();
+        let start = std::time::Instant::now();
+        let request = tokio::spawn(crate::run_blocking(move || {
+            std::thread::sleep(Duration::from_millis(300));
+            std::thread::current().id()
+        }));
+        tokio::task::yield_now().await;
+        let other_connection = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            start.elapsed()
+        });
+        let other_elapsed = other_connection.await.expect("task");
+        assert!(other_elapsed < Duration::from_millis(200), "a concurrent task waited {:?} for the blocking request", other_elapsed);
+        let request_thread = request.await.expect("task").expect("blocking request");
+        assert_ne!(runtime_thread, request_thread);
+        assert!(start.elapsed() >= Duration::from_millis(300));
+    }
+}
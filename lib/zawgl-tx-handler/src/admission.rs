@@ -0,0 +1,111 @@
+// MIT License
+//
+// Copyright (c) 2022 Alexandre RICCIARDI
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use parking_lot::{Condvar, Mutex};
+use zawgl_core::metrics::{self, Counter};
+
+use crate::DatabaseError;
+
+struct AdmissionState {
+    running: usize,
+    queued: usize,
+}
+
+/// Bounds the queries running at once, the queries over the limit wait in a queue of bounded depth
+/// and the ones that don't fit in the queue are rejected with a busy error.
+pub struct AdmissionControl {
+    max_running_queries: usize,
+    max_queued_queries: usize,
+    state: Mutex<AdmissionState>,
+    released: Condvar,
+}
+
+/// Slot of a running query, released when dropped.
+pub struct AdmissionPermit<'a> {
+    control: &'a AdmissionControl,
+}
+
+impl AdmissionControl {
+    pub fn new(max_running_queries: usize, max_queued_queries: usize) -> Self {
+        AdmissionControl{max_running_queries: max_running_queries.max(1), max_queued_queries, state: Mutex::new(AdmissionState{running: 0, queued: 0}), released: Condvar::new()}
+    }
+
+    pub fn unbounded() -> Self {
+        Self::new(usize::MAX, 0)
+    }
+
+    /// Waits for a slot if the queue is not full.
+    pub fn admit(&self) -> Result<AdmissionPermit<'_>, DatabaseError> {
+        let mut state = self.state.lock();
+        if state.running >= self.max_running_queries {
+            if state.queued >= self.max_queued_queries {
+                metrics::increment(Counter::RejectedQueries);
+                return Err(DatabaseError::Busy);
+            }
+            state.queued += 1;
+            while state.running >= self.max_running_queries {
+                self.released.wait(&mut state);
+            }
+            state.queued -= 1;
+        }
+        state.running += 1;
+        Ok(AdmissionPermit{control: self})
+    }
+
+    pub fn get_running_queries(&self) -> usize {
+        self.state.lock().running
+    }
+
+    pub fn get_queued_queries(&self) -> usize {
+        self.state.lock().queued
+    }
+}
+
+impl Drop for AdmissionPermit<'_> {
+    fn drop(&mut self) {
+        self.control.state.lock().running -= 1;
+        self.control.released.notify_one();
+    }
+}
+
+#[cfg(test)]
+mod test_admission {
+    use super::*;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[test]
+    fn test_admission_queue() {
+        let control = Arc::new(AdmissionControl::new(1, 1));
+        let permit = control.admit().expect("first query");
+        let queued_control = control.clone();
+        let queued = std::thread::spawn(move || queued_control.admit().map(|_| ()).is_ok());
+        while control.get_queued_queries() == 0 {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert!(matches!(control.admit(), Err(DatabaseError::Busy)));
+        drop(permit);
+        assert!(queued.join().expect("queued query"));
+        assert_eq!(0, control.get_running_queries());
+        assert_eq!(0, control.get_queued_queries());
+    }
+}
@@ -0,0 +1,252 @@
+// MIT License
+//
+// Copyright (c) 2022 Alexandre RICCIARDI
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use std::cell::RefCell;
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use log::info;
+use parking_lot::ReentrantMutex;
+use zawgl_core::graph_engine::query_context::QueryContext;
+use zawgl_core::model::init::{InitContext, ReloadableSettings};
+
+use crate::DatabaseError;
+use crate::admission::{AdmissionControl, AdmissionPermit};
+use crate::running_queries::{QueryRegistry, RunningQuery, RunningQueryInfo};
+use crate::request_handler::{GraphRequestHandler, RequestHandler};
+use crate::tx_handler::{GraphTxHandler, TxHandler};
+
+pub const DEFAULT_DATABASE_NAME: &str = "zawgl";
+const MAX_DATABASE_NAME_LEN: usize = 63;
+/// Names used by the HTTP routes.
+const RESERVED_DATABASE_NAMES: [&str; 3] = ["query", "tx", "changes"];
+
+/// Handlers of a database, each database has its own stores and transactions.
+#[derive(Clone)]
+pub struct Database<'a> {
+    pub tx_handler: TxHandler,
+    pub graph_request_handler: RequestHandler<'a>,
+}
+
+impl <'a> Database<'a> {
+    fn open(conf: InitContext<'a>, tx_timeout: Duration) -> Self {
+        Database{
+            tx_handler: Arc::new(ReentrantMutex::new(RefCell::new(GraphTxHandler::with_timeout(tx_timeout)))),
+            graph_request_handler: Arc::new(RwLock::new(GraphRequestHandler::new(conf))),
+        }
+    }
+}
+
+pub type Databases<'a> = Arc<DatabaseManager<'a>>;
+
+/// Databases under one root directory: the default database is stored in the root directory and the
+/// named ones in sub directories of its databases directory, they are opened on first use.
+pub struct DatabaseManager<'a> {
+    conf: RwLock<InitContext<'a>>,
+    tx_timeout: Duration,
+    databases: RwLock<HashMap<String, Database<'a>>>,
+    admission: AdmissionControl,
+    queries: QueryRegistry,
+    query_memory_limit: Option<usize>,
+}
+
+fn check_database_name(name: &str) -> Result<(), DatabaseError> {
+    let is_valid = !name.is_empty() && name.len() <= MAX_DATABASE_NAME_LEN && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        && name != DEFAULT_DATABASE_NAME && !RESERVED_DATABASE_NAMES.contains(&name);
+    if is_valid { Ok(()) } else { Err(DatabaseError::InvalidDatabaseName(String::from(name))) }
+}
+
+impl <'a> DatabaseManager<'a> {
+    pub fn new(conf: InitContext<'a>, tx_timeout: Duration) -> Self {
+        let mut databases = HashMap::new();
+        databases.insert(String::from(DEFAULT_DATABASE_NAME), Database::open(conf.clone(), tx_timeout));
+        DatabaseManager{conf: RwLock::new(conf), tx_timeout, databases: RwLock::new(databases), admission: AdmissionControl::unbounded(), queries: QueryRegistry::new(), query_memory_limit: None}
+    }
+
+    /// Bounds the queries running at once on all the databases.
+    pub fn with_admission_control(mut self, admission: AdmissionControl) -> Self {
+        self.admission = admission;
+        self
+    }
+
+    /// Slot held by a query while it runs, see [`AdmissionControl::admit`].
+    pub fn admit(&self) -> Result<AdmissionPermit<'_>, DatabaseError> {
+        self.admission.admit()
+    }
+
+    /// Aborts the queries once the estimated size of the graphs they match exceeds the limit in bytes.
+    pub fn with_query_memory_limit(mut self, query_memory_limit: usize) -> Self {
+        self.query_memory_limit = Some(query_memory_limit);
+        self
+    }
+
+    /// Registers a query so that it can be cancelled by its request id while it runs.
+    pub fn start_query(&self, request_id: &str) -> RunningQuery<'_> {
+        let query_context = match self.query_memory_limit {
+            Some(query_memory_limit) => QueryContext::new().with_memory_limit(query_memory_limit),
+            None => QueryContext::new(),
+        };
+        self.queries.register(request_id, query_context)
+    }
+
+    /// Cancels the running queries of the request id, returns their count.
+    pub fn cancel_query(&self, request_id: &str) -> usize {
+        self.queries.cancel(request_id)
+    }
+
+    pub fn list_running_queries(&self) -> Vec<RunningQueryInfo> {
+        self.queries.list()
+    }
+
+    fn get_database_dir(&self, name: &str) -> Option<String> {
+        Path::new(&self.conf.read().unwrap().get_databases_dir()?).join(name).to_str().map(String::from)
+    }
+
+    fn exists(&self, name: &str) -> bool {
+        self.get_database_dir(name).is_some_and(|dir| Path::new(&dir).is_dir())
+    }
+
+    /// Returns the database named `name` or the default one.
+    pub fn get_database(&self, name: Option<&str>) -> Result<Database<'a>, DatabaseError> {
+        let name = name.unwrap_or(DEFAULT_DATABASE_NAME);
+        if let Some(database) = self.databases.read().unwrap().get(name) {
+            return Ok(database.clone());
+        }
+        check_database_name(name).map_err(|_| DatabaseError::UnknownDatabase(String::from(name)))?;
+        let mut databases = self.databases.write().unwrap();
+        if let Some(database) = databases.get(name) {
+            return Ok(database.clone());
+        }
+        if !self.exists(name) {
+            return Err(DatabaseError::UnknownDatabase(String::from(name)));
+        }
+        let conf = self.conf.read().unwrap().for_database(name).ok_or_else(|| DatabaseError::DatabaseDirectory(String::from(name), String::from("cannot create the directory")))?;
+        let database = Database::open(conf, self.tx_timeout);
+        databases.insert(String::from(name), database.clone());
+        Ok(database)
+    }
+
+    pub fn create_database(&self, name: &str) -> Result<Database<'a>, DatabaseError> {
+        check_database_name(name)?;
+        let mut databases = self.databases.write().unwrap();
+        if databases.contains_key(name) || self.exists(name) {
+            return Err(DatabaseError::DatabaseAlreadyExists(String::from(name)));
+        }
+        let conf = self.conf.read().unwrap().for_database(name).ok_or_else(|| DatabaseError::DatabaseDirectory(String::from(name), String::from("cannot create the directory")))?;
+        let database = Database::open(conf, self.tx_timeout);
+        databases.insert(String::from(name), database.clone());
+        info!("created database {}", name);
+        Ok(database)
+    }
+
+    /// Removes the database and its stores, requests in progress on the database complete on the removed stores.
+    pub fn drop_database(&self, name: &str) -> Result<(), DatabaseError> {
+        check_database_name(name)?;
+        let mut databases = self.databases.write().unwrap();
+        if !databases.contains_key(name) && !self.exists(name) {
+            return Err(DatabaseError::UnknownDatabase(String::from(name)));
+        }
+        databases.remove(name);
+        let dir = self.get_database_dir(name).ok_or_else(|| DatabaseError::DatabaseDirectory(String::from(name), String::from("invalid path")))?;
+        std::fs::remove_dir_all(dir).map_err(|err| DatabaseError::DatabaseDirectory(String::from(name), err.to_string()))?;
+        info!("dropped database {}", name);
+        Ok(())
+    }
+
+    /// Names of the default database and of the named databases, sorted.
+    pub fn list_databases(&self) -> Vec<String> {
+        let mut names = BTreeSet::new();
+        names.insert(String::from(DEFAULT_DATABASE_NAME));
+        names.extend(self.databases.read().unwrap().keys().cloned());
+        if let Some(entries) = self.conf.read().unwrap().get_databases_dir().and_then(|dir| std::fs::read_dir(dir).ok()) {
+            let dirs = entries.filter_map(|entry| entry.ok()).filter(|entry| entry.path().is_dir());
+            names.extend(dirs.filter_map(|entry| entry.file_name().to_str().map(String::from)).filter(|name| check_database_name(name).is_ok()));
+        }
+        names.into_iter().collect()
+    }
+
+    /// Applies the settings to the open databases and to the ones opened afterwards.
+    pub fn reload_settings(&self, settings: ReloadableSettings) {
+        let databases = self.databases.write().unwrap();
+        self.conf.write().unwrap().set_reloadable_settings(settings);
+        for database in databases.values() {
+            database.graph_request_handler.write().unwrap().reload_settings(settings);
+        }
+        info!("reloaded settings: {:?}", settings);
+    }
+
+    pub fn get_reloadable_settings(&self) -> ReloadableSettings {
+        self.conf.read().unwrap().get_reloadable_settings()
+    }
+}
+
+#[cfg(test)]
+mod test_databases {
+    use super::*;
+    use zawgl_core::model::{Node, PropertyGraph, Status};
+    use zawgl_core::test_utils::build_dir_path_and_rm_old;
+    use zawgl_cypher_query_model::{QueryStep, StepType};
+    use crate::handle_graph_request;
+
+    fn make_step(step_type: StepType, status: Status) -> Vec<QueryStep> {
+        let mut node = Node::new();
+        node.set_labels(vec!["Person".to_string()]);
+        node.set_status(status);
+        let mut pattern = PropertyGraph::new();
+        pattern.add_node(node);
+        let mut step = QueryStep::new(step_type);
+        step.patterns.push(pattern);
+        vec![step]
+    }
+
+    fn count_persons(database: &Database) -> usize {
+        handle_graph_request(database.tx_handler.clone(), database.graph_request_handler.clone(), &make_step(StepType::MATCH, Status::Match), None).expect("match").len()
+    }
+
+    #[test]
+    fn test_named_databases() {
+        let main_dir = build_dir_path_and_rm_old("test_named_databases").expect("db path");
+        let conf = InitContext::new(&main_dir).expect("can't create context");
+        let manager = DatabaseManager::new(conf.clone(), Duration::from_secs(10));
+        assert!(matches!(manager.get_database(Some("analytics")), Err(DatabaseError::UnknownDatabase(_))));
+        assert!(matches!(manager.create_database("tx"), Err(DatabaseError::InvalidDatabaseName(_))));
+        assert!(matches!(manager.create_database("../escape"), Err(DatabaseError::InvalidDatabaseName(_))));
+
+        let analytics = manager.create_database("analytics").expect("create");
+        assert!(matches!(manager.create_database("analytics"), Err(DatabaseError::DatabaseAlreadyExists(_))));
+        handle_graph_request(analytics.tx_handler.clone(), analytics.graph_request_handler.clone(), &make_step(StepType::CREATE, Status::Create), None).expect("create");
+        assert_eq!(1, count_persons(&analytics));
+        assert_eq!(0, count_persons(&manager.get_database(None).expect("default")));
+        assert_eq!(vec!["analytics".to_string(), DEFAULT_DATABASE_NAME.to_string()], manager.list_databases());
+        drop(analytics);
+        drop(manager);
+
+        let manager = DatabaseManager::new(conf, Duration::from_secs(10));
+        assert_eq!(1, count_persons(&manager.get_database(Some("analytics")).expect("reopen")));
+        manager.drop_database("analytics").expect("drop");
+        assert!(matches!(manager.get_database(Some("analytics")), Err(DatabaseError::UnknownDatabase(_))));
+        assert!(matches!(manager.drop_database(DEFAULT_DATABASE_NAME), Err(DatabaseError::InvalidDatabaseName(_))));
+        assert_eq!(vec![DEFAULT_DATABASE_NAME.to_string()], manager.list_databases());
+    }
+}
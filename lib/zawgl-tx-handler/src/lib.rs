@@ -22,50 +22,796 @@
 pub mod tx_context;
 pub mod tx_handler;
 pub mod request_handler;
-use request_handler::RequestHandler;
-use tx_handler::{Scenario, TxHandler, TxStatus, needs_write_lock};
+pub mod lock_manager;
+pub mod query_log;
+pub mod databases;
+pub mod triggers;
+pub mod admission;
+pub mod running_queries;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
+use thiserror::Error;
+use lock_manager::{LockError, LockMode, LockResource};
+use request_handler::{RequestHandler, make_query_steps};
+use tx_handler::{Scenario, TxHandler, TxStatus, get_request_locks, get_tx_request_locks, needs_write_lock};
 
+use zawgl_core::graph_engine::query_context::{QueryAbort, QueryContext};
+use zawgl_core::error::GraphError;
+use zawgl_core::metrics::{self, Counter};
 use zawgl_core::model::PropertyGraph;
-use zawgl_cypher_query_model::QueryStep;
-use self::tx_context::TxContext;
+use zawgl_cypher_query_model::{QueryStep, ExecutionMode};
+use zawgl_cypher_query_planner::{PagedResult, ResultPage};
+use zawgl_cypher_query_planner::procedures::ProcedureError;
+use self::tx_context::{IsolationLevel, TxContext};
+
+static AUTOCOMMIT_COUNT: AtomicU64 = AtomicU64::new(0);
 
 pub struct ResultGraph {
     pub scenario: Scenario,
     pub patterns: Vec<PropertyGraph>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Error)]
 pub enum DatabaseError {
-    EngineError,
-    TxError,
+    #[error(transparent)]
+    EngineError(#[from] GraphError),
+    #[error("no open transaction for session {0}")]
+    TxNotFound(String),
+    /// A statement of the transaction of the session failed, the transaction can only be rolled back.
+    #[error("the transaction of session {0} failed and can only be rolled back")]
+    TxRollbackOnly(String),
+    /// Another transaction committed after the snapshot read by the transaction of the session, which is rolled back.
+    #[error("the transaction of session {0} was rolled back as its snapshot is outdated by another commit")]
+    TxOutdated(String),
+    /// A batch only creates its patterns, they can't match or delete.
+    #[error("the patterns of a batch can only create nodes and relationships")]
+    InvalidBatch,
+    #[error(transparent)]
+    ProcedureError(#[from] ProcedureError),
+    /// The transaction was rolled back as it waited for a lock held by a transaction waiting for it.
+    #[error("the transaction was rolled back to break a deadlock")]
+    Deadlock,
+    #[error("timed out waiting for a lock")]
+    LockTimeout,
+    #[error("write in a read only transaction")]
+    WriteInReadOnlyTx,
+    #[error("unknown database {0}")]
+    UnknownDatabase(String),
+    #[error("database {0} already exists")]
+    DatabaseAlreadyExists(String),
+    #[error("invalid database name {0}")]
+    InvalidDatabaseName(String),
+    /// The directory of the database, named first, can't be created or removed.
+    #[error("cannot access the directory of database {0}: {1}")]
+    DatabaseDirectory(String, String),
+    /// A trigger, named first, rejected the changes of the transaction with the message.
+    #[error("trigger {0} rejected the transaction: {1}")]
+    TriggerVeto(String, String),
+    #[error(transparent)]
+    QueryAborted(QueryAbort),
+    /// The maximum of concurrent queries is reached and the queue of the waiting ones is full.
+    #[error("too many concurrent queries, retry later")]
+    Busy,
 }
 
 pub fn handle_graph_request<'a>(tx_handler: TxHandler, graph_request_handler: RequestHandler<'a>, steps: &Vec<QueryStep>, tx_context: Option<TxContext>) -> Result<Vec<PropertyGraph>, DatabaseError> {
-    
-    let tx_lock = tx_handler.lock();
-    let tx_status = tx_lock.borrow_mut().get_session_status(&tx_context);
+    handle_graph_request_page(tx_handler, graph_request_handler, steps, tx_context, None, ExecutionMode::Run).map(|res| res.graphs)
+}
+
+pub fn handle_graph_request_page<'a>(tx_handler: TxHandler, graph_request_handler: RequestHandler<'a>, steps: &Vec<QueryStep>, tx_context: Option<TxContext>, page: Option<&ResultPage>, mode: ExecutionMode) -> Result<PagedResult, DatabaseError> {
+    handle_graph_query(tx_handler, graph_request_handler, steps, tx_context, page, mode, &QueryContext::new())
+}
+
+/// Runs the request in the query context, the query fails once the context is aborted.
+pub fn handle_graph_query<'a>(tx_handler: TxHandler, graph_request_handler: RequestHandler<'a>, steps: &Vec<QueryStep>, tx_context: Option<TxContext>, page: Option<&ResultPage>, mode: ExecutionMode, query_context: &QueryContext) -> Result<PagedResult, DatabaseError> {
+    if !steps.is_empty() {
+        metrics::increment(Counter::Queries);
+    }
+    if tx_context.is_none() && !needs_write_lock(steps) {
+        return graph_request_handler.read().unwrap().handle_graph_read_request(steps, page, mode, query_context);
+    }
+    let complete = |graphs| PagedResult {graphs: graphs, has_more: false, plan: None, records: None};
+    if let Some(ctx) = tx_context.as_ref().filter(|ctx| ctx.read_only) {
+        if ctx.isolation == IsolationLevel::ReadCommitted && (ctx.commit || ctx.rollback) {
+            return Ok(complete(Vec::new()));
+        }
+        if needs_write_lock(steps) {
+            return Err(DatabaseError::WriteInReadOnlyTx);
+        }
+        if ctx.isolation == IsolationLevel::ReadCommitted {
+            return graph_request_handler.read().unwrap().handle_graph_read_request(steps, page, mode, query_context);
+        }
+    }
+    rollback_expired_sessions(&tx_handler, &graph_request_handler);
+    let tx_status = tx_handler.lock().borrow_mut().get_session_status(&tx_context);
     match tx_status {
-        TxStatus::OpenNewTx(ctx) => {
-            tx_lock.borrow_mut().acquire_session_lock();
-            graph_request_handler.write().unwrap().open_graph_tx(ctx);
-            graph_request_handler.write().unwrap().handle_graph_request_tx(steps, ctx)
-        },
-        TxStatus::ContinueCurrentTx(ctx) => graph_request_handler.write().unwrap().handle_graph_request_tx(steps, ctx),
-        TxStatus::CommitCurrentTx(ctx) => { 
-            let res = graph_request_handler.write().unwrap().commit_tx(ctx);
-            tx_lock.borrow_mut().release_session_lock();
+        TxStatus::OpenNewTx(ctx) | TxStatus::ContinueCurrentTx(ctx) => {
+            if tx_handler.lock().borrow().is_rollback_only(&ctx.session_id) {
+                return Err(DatabaseError::TxRollbackOnly(ctx.session_id.clone()));
+            }
+            let res = handle_graph_request_tx(&tx_handler, &graph_request_handler, steps, ctx, page, mode, query_context);
+            if res.is_err() {
+                tx_handler.lock().borrow_mut().set_rollback_only(&ctx.session_id);
+            }
             res
         },
-        TxStatus::WaitForCurrentTx => {
-            tx_lock.borrow_mut().acquire_session_lock();
-            handle_graph_request(tx_handler.clone(), graph_request_handler, steps, tx_context)
+        TxStatus::CommitCurrentTx(ctx) if tx_handler.lock().borrow().is_rollback_only(&ctx.session_id) => {
+            let _ = graph_request_handler.read().unwrap().rollback_tx(ctx);
+            tx_handler.lock().borrow_mut().end_session(&ctx.session_id);
+            metrics::increment(Counter::TxRollbacks);
+            Err(DatabaseError::TxRollbackOnly(ctx.session_id.clone()))
+        },
+        TxStatus::CommitCurrentTx(ctx) => {
+            let res = graph_request_handler.read().unwrap().commit_tx(ctx);
+            tx_handler.lock().borrow_mut().end_session(&ctx.session_id);
+            metrics::increment(if res.is_ok() { Counter::TxCommits } else { Counter::TxRollbacks });
+            res.map(complete)
+        },
+        TxStatus::RollbackCurrentTx(ctx) => {
+            let res = graph_request_handler.read().unwrap().rollback_tx(ctx);
+            tx_handler.lock().borrow_mut().end_session(&ctx.session_id);
+            metrics::increment(Counter::TxRollbacks);
+            res.map(complete)
         },
         TxStatus::NoTx => {
             if needs_write_lock(steps) {
-                graph_request_handler.write().unwrap().handle_graph_request(steps)
+                with_autocommit_locks(&tx_handler, &graph_request_handler, steps, query_context, || graph_request_handler.read().unwrap().handle_graph_request(steps, page, mode, query_context))
             } else {
-                graph_request_handler.read().unwrap().handle_graph_request(steps)
+                graph_request_handler.read().unwrap().handle_graph_read_request(steps, page, mode, query_context)
             }
         },
     }
-}
\ No newline at end of file
+}
+
+/// Runs a request of an open transaction once it holds the request locks. The transaction gets its own graph
+/// engine on its first write, or on its first request at the snapshot isolation level, until then its requests
+/// read the last committed state. The pages written by the engine stay in its page cache until commit, so the
+/// following requests of the transaction read its own creates, updates and deletes while the other sessions don't.
+/// The requests of the sessions run concurrently, each one through the graph engine of its session, only their
+/// commits are run one at a time. A failed request may have written part of its changes, the caller then marks
+/// the transaction rollback only.
+fn handle_graph_request_tx(tx_handler: &TxHandler, graph_request_handler: &RequestHandler, steps: &Vec<QueryStep>, ctx: &TxContext, page: Option<&ResultPage>, mode: ExecutionMode, query_context: &QueryContext) -> Result<PagedResult, DatabaseError> {
+    tx_handler.lock().borrow_mut().touch_session(&ctx.session_id);
+    if let Err(err) = acquire_request_locks(tx_handler, graph_request_handler, &ctx.session_id, &get_tx_request_locks(steps), query_context) {
+        if let DatabaseError::Deadlock = err {
+            let _ = graph_request_handler.read().unwrap().rollback_tx(ctx);
+            tx_handler.lock().borrow_mut().end_session(&ctx.session_id);
+            metrics::increment(Counter::TxRollbacks);
+        }
+        return Err(err);
+    }
+    let request_handler = graph_request_handler.read().unwrap();
+    if (needs_write_lock(steps) || ctx.isolation == IsolationLevel::Snapshot) && !request_handler.has_graph_tx(&ctx.session_id) {
+        request_handler.open_graph_tx(ctx);
+    }
+    if request_handler.has_graph_tx(&ctx.session_id) {
+        request_handler.handle_graph_request_tx(steps, ctx, page, mode, query_context)
+    } else {
+        request_handler.handle_graph_read_request(steps, page, mode, query_context)
+    }
+}
+
+/// Waits for the locks of the request, a wait timing out ends the expired transactions before waiting again.
+/// The waits end at the deadline of the query context, the query then fails with a timeout.
+fn acquire_request_locks(tx_handler: &TxHandler, graph_request_handler: &RequestHandler, owner: &str, locks: &[(LockResource, LockMode)], query_context: &QueryContext) -> Result<(), DatabaseError> {
+    let (lock_manager, tx_timeout) = {
+        let tx_lock = tx_handler.lock();
+        let handler = tx_lock.borrow();
+        (handler.get_lock_manager(), handler.get_tx_timeout())
+    };
+    for (resource, mode) in locks {
+        loop {
+            query_context.check().map_err(DatabaseError::QueryAborted)?;
+            let timeout = match query_context.get_deadline() {
+                Some(deadline) => tx_timeout.min(deadline.saturating_duration_since(Instant::now())),
+                None => tx_timeout,
+            };
+            match lock_manager.acquire(owner, resource, *mode, timeout) {
+                Ok(()) => break,
+                Err(LockError::Deadlock) => return Err(DatabaseError::Deadlock),
+                Err(LockError::Timeout) => {
+                    query_context.check().map_err(DatabaseError::QueryAborted)?;
+                    if rollback_expired_sessions(tx_handler, graph_request_handler) == 0 {
+                        return Err(DatabaseError::LockTimeout);
+                    }
+                },
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Runs a request outside of any transaction while holding its locks.
+fn with_autocommit_locks<T, F: FnOnce() -> Result<T, DatabaseError>>(tx_handler: &TxHandler, graph_request_handler: &RequestHandler, steps: &[QueryStep], query_context: &QueryContext, request: F) -> Result<T, DatabaseError> {
+    let owner = format!("autocommit-{}", AUTOCOMMIT_COUNT.fetch_add(1, Ordering::SeqCst));
+    let res = acquire_request_locks(tx_handler, graph_request_handler, &owner, &get_request_locks(steps), query_context).and_then(|_| request());
+    tx_handler.lock().borrow().get_lock_manager().release_all(&owner);
+    res
+}
+
+fn rollback_expired_sessions(tx_handler: &TxHandler, graph_request_handler: &RequestHandler) -> usize {
+    let expired_session_ids = tx_handler.lock().borrow_mut().evict_expired_sessions();
+    for session_id in &expired_session_ids {
+        let expired_ctx = TxContext{session_id: session_id.clone(), commit: false, rollback: true, read_only: false, isolation: IsolationLevel::default()};
+        let _ = graph_request_handler.read().unwrap().rollback_tx(&expired_ctx);
+    }
+    metrics::add(Counter::TxRollbacks, expired_session_ids.len() as u64);
+    expired_session_ids.len()
+}
+
+/// Creates the patterns atomically with a single store sync, for bulk loads. The batch waits for the
+/// locks of the transactions in progress on the same labels.
+pub fn handle_graph_batch_create<'a>(tx_handler: TxHandler, graph_request_handler: RequestHandler<'a>, patterns: &[PropertyGraph]) -> Result<Vec<PropertyGraph>, DatabaseError> {
+    metrics::increment(Counter::Queries);
+    rollback_expired_sessions(&tx_handler, &graph_request_handler);
+    let steps = make_query_steps(&patterns.to_vec());
+    with_autocommit_locks(&tx_handler, &graph_request_handler, &steps, &QueryContext::new(), || graph_request_handler.read().unwrap().handle_graph_batch_create(patterns))
+}
+
+#[cfg(test)]
+mod test_tx {
+    use std::cell::RefCell;
+    use std::sync::{Arc, RwLock};
+    use std::sync::atomic::AtomicUsize;
+    use std::time::Duration;
+    use parking_lot::ReentrantMutex;
+    use zawgl_core::model::{Node, Property, PropertyGraph, PropertyValue, Status, init::InitContext};
+    use zawgl_core::model::constraints::UniqueConstraint;
+    use zawgl_core::test_utils::build_dir_path_and_rm_old;
+    use zawgl_cypher_query_model::{QueryStep, StepType};
+    use zawgl_cypher_query_model::model::UpdateItem;
+    use crate::request_handler::{GraphRequestHandler, get_request_scenario, make_query_steps};
+    use crate::tx_handler::{DEFAULT_TX_TIMEOUT_SECS, GraphTxHandler};
+    use zawgl_cypher_query_planner::{ResultComparator, ResultOrder};
+    use super::*;
+
+    fn make_step(step_type: StepType, status: Status) -> QueryStep {
+        make_label_step(step_type, status, "Person")
+    }
+
+    fn make_label_step(step_type: StepType, status: Status, label: &str) -> QueryStep {
+        let mut node = Node::new();
+        node.set_labels(vec![label.to_string()]);
+        node.set_status(status);
+        let mut pattern = PropertyGraph::new();
+        pattern.add_node(node);
+        let mut step = QueryStep::new(step_type);
+        step.patterns.push(pattern);
+        step
+    }
+
+    fn make_tx_context(commit: bool, rollback: bool) -> Option<TxContext> {
+        Some(TxContext{session_id: "session".to_string(), commit: commit, rollback: rollback, read_only: false, isolation: IsolationLevel::default()})
+    }
+
+    #[test]
+    fn test_rollback() {
+        let main_dir = build_dir_path_and_rm_old("test_tx_rollback").expect("db path");
+        let conf = InitContext::new(&main_dir).expect("can't create context");
+        let tx_handler = Arc::new(ReentrantMutex::new(RefCell::new(GraphTxHandler::new())));
+        let request_handler = Arc::new(RwLock::new(GraphRequestHandler::new(conf)));
+
+        let create = vec![make_step(StepType::CREATE, Status::Create)];
+        let created = handle_graph_request(tx_handler.clone(), request_handler.clone(), &create, make_tx_context(false, false)).expect("create");
+        assert_eq!(1, created.len());
+        handle_graph_request(tx_handler.clone(), request_handler.clone(), &Vec::new(), make_tx_context(false, true)).expect("rollback");
+
+        let matching = vec![make_step(StepType::MATCH, Status::Match)];
+        let matched = handle_graph_request(tx_handler.clone(), request_handler.clone(), &matching, None).expect("match");
+        assert_eq!(0, matched.len());
+
+        handle_graph_request(tx_handler.clone(), request_handler.clone(), &create, make_tx_context(false, false)).expect("create");
+        handle_graph_request(tx_handler.clone(), request_handler.clone(), &Vec::new(), make_tx_context(true, false)).expect("commit");
+        let matched = handle_graph_request(tx_handler.clone(), request_handler.clone(), &matching, None).expect("match");
+        assert_eq!(1, matched.len());
+    }
+
+    #[test]
+    fn test_read_own_writes() {
+        let main_dir = build_dir_path_and_rm_old("test_tx_read_own_writes").expect("db path");
+        let conf = InitContext::new(&main_dir).expect("can't create context");
+        let tx_handler = Arc::new(ReentrantMutex::new(RefCell::new(GraphTxHandler::new())));
+        let request_handler = Arc::new(RwLock::new(GraphRequestHandler::new(conf)));
+        let match_label = |label: &str, status: Status| {
+            let mut step = make_step(StepType::MATCH, status);
+            let node = &mut step.patterns[0].get_nodes_mut()[0];
+            node.set_labels(vec![label.to_string()]);
+            node.set_var("n");
+            step
+        };
+        let count = |label: &str, tx_context: Option<TxContext>| handle_graph_request(tx_handler.clone(), request_handler.clone(), &vec![match_label(label, Status::Match)], tx_context).expect("match").len();
+
+        let create = vec![make_step(StepType::CREATE, Status::Create)];
+        for _ in 0..2 {
+            handle_graph_request(tx_handler.clone(), request_handler.clone(), &create, make_tx_context(false, false)).expect("create");
+        }
+        assert_eq!(2, count("Person", make_tx_context(false, false)));
+        assert_eq!(0, count("Person", None));
+
+        let set = vec![match_label("Person", Status::Match), QueryStep::new_update(StepType::SET, vec![UpdateItem::Labels("n".to_string(), vec!["Admin".to_string()])])];
+        handle_graph_request(tx_handler.clone(), request_handler.clone(), &set, make_tx_context(false, false)).expect("set");
+        assert_eq!(2, count("Admin", make_tx_context(false, false)));
+        let remove = vec![match_label("Admin", Status::Match), QueryStep::new_update(StepType::REMOVE, vec![UpdateItem::Labels("n".to_string(), vec!["Person".to_string()])])];
+        handle_graph_request(tx_handler.clone(), request_handler.clone(), &remove, make_tx_context(false, false)).expect("remove");
+        assert_eq!(0, count("Person", make_tx_context(false, false)));
+
+        let delete = make_query_steps(&vec![match_label("Admin", Status::Delete).patterns.remove(0)]);
+        handle_graph_request(tx_handler.clone(), request_handler.clone(), &delete, make_tx_context(false, false)).expect("delete");
+        assert_eq!(0, count("Admin", make_tx_context(false, false)));
+        handle_graph_request(tx_handler.clone(), request_handler.clone(), &create, make_tx_context(false, false)).expect("create");
+        assert_eq!(0, count("Person", None));
+        handle_graph_request(tx_handler.clone(), request_handler.clone(), &Vec::new(), make_tx_context(true, false)).expect("commit");
+        assert_eq!(1, count("Person", None));
+        assert_eq!(0, count("Admin", None));
+    }
+
+    #[test]
+    fn test_aborted_query() {
+        let main_dir = build_dir_path_and_rm_old("test_tx_aborted_query").expect("db path");
+        let conf = InitContext::new(&main_dir).expect("can't create context");
+        let tx_handler = Arc::new(ReentrantMutex::new(RefCell::new(GraphTxHandler::new())));
+        let request_handler = Arc::new(RwLock::new(GraphRequestHandler::new(conf)));
+        let create = vec![make_step(StepType::CREATE, Status::Create)];
+        for _ in 0..3 {
+            handle_graph_request(tx_handler.clone(), request_handler.clone(), &create, None).expect("create");
+        }
+
+        let cancelled = QueryContext::new();
+        cancelled.cancel();
+        let res = handle_graph_query(tx_handler.clone(), request_handler.clone(), &create, None, None, ExecutionMode::Run, &cancelled);
+        assert!(matches!(res, Err(DatabaseError::QueryAborted(QueryAbort::Cancelled))));
+
+        let matching = vec![make_step(StepType::MATCH, Status::Match)];
+        let limited = QueryContext::new().with_memory_limit(1);
+        let res = handle_graph_query(tx_handler.clone(), request_handler.clone(), &matching, None, None, ExecutionMode::Run, &limited);
+        assert!(matches!(res, Err(DatabaseError::QueryAborted(QueryAbort::MemoryLimitExceeded))));
+        assert_eq!(3, handle_graph_request(tx_handler.clone(), request_handler.clone(), &matching, None).expect("match").len());
+    }
+
+    #[test]
+    fn test_query_timeout_on_lock_wait() {
+        let main_dir = build_dir_path_and_rm_old("test_tx_query_timeout_on_lock_wait").expect("db path");
+        let conf = InitContext::new(&main_dir).expect("can't create context");
+        let tx_handler = Arc::new(ReentrantMutex::new(RefCell::new(GraphTxHandler::new())));
+        let request_handler = Arc::new(RwLock::new(GraphRequestHandler::new(conf)));
+        let create = vec![make_step(StepType::CREATE, Status::Create)];
+        handle_graph_request(tx_handler.clone(), request_handler.clone(), &create, make_tx_context(false, false)).expect("create");
+
+        let start = Instant::now();
+        let timeout = QueryContext::new().with_timeout(Duration::from_millis(50));
+        let res = handle_graph_query(tx_handler.clone(), request_handler.clone(), &create, None, None, ExecutionMode::Run, &timeout);
+        assert!(matches!(res, Err(DatabaseError::QueryAborted(QueryAbort::Timeout))));
+        assert!(start.elapsed() < Duration::from_secs(DEFAULT_TX_TIMEOUT_SECS));
+
+        handle_graph_request(tx_handler.clone(), request_handler.clone(), &Vec::new(), make_tx_context(true, false)).expect("commit");
+        let matching = vec![make_step(StepType::MATCH, Status::Match)];
+        assert_eq!(1, handle_graph_request(tx_handler.clone(), request_handler.clone(), &matching, None).expect("match").len());
+    }
+
+    #[test]
+    fn test_commit_without_open_tx() {
+        let main_dir = build_dir_path_and_rm_old("test_tx_commit_without_open_tx").expect("db path");
+        let conf = InitContext::new(&main_dir).expect("can't create context");
+        let tx_handler = Arc::new(ReentrantMutex::new(RefCell::new(GraphTxHandler::new())));
+        let request_handler = Arc::new(RwLock::new(GraphRequestHandler::new(conf)));
+
+        handle_graph_request(tx_handler.clone(), request_handler.clone(), &Vec::new(), make_tx_context(true, false)).expect("commit");
+        handle_graph_request(tx_handler.clone(), request_handler.clone(), &Vec::new(), make_tx_context(false, true)).expect("rollback");
+
+        let create = vec![make_step(StepType::CREATE, Status::Create)];
+        let created = handle_graph_request(tx_handler.clone(), request_handler.clone(), &create, None).expect("create");
+        assert_eq!(1, created.len());
+    }
+
+    #[test]
+    fn test_rollback_of_other_session() {
+        let main_dir = build_dir_path_and_rm_old("test_tx_rollback_of_other_session").expect("db path");
+        let conf = InitContext::new(&main_dir).expect("can't create context");
+        let tx_handler = Arc::new(ReentrantMutex::new(RefCell::new(GraphTxHandler::new())));
+        let request_handler = Arc::new(RwLock::new(GraphRequestHandler::new(conf)));
+
+        let create = vec![make_step(StepType::CREATE, Status::Create)];
+        handle_graph_request(tx_handler.clone(), request_handler.clone(), &create, make_tx_context(false, false)).expect("create");
+        let other_session = Some(TxContext{session_id: "other_session".to_string(), commit: false, rollback: true, read_only: false, isolation: IsolationLevel::default()});
+        handle_graph_request(tx_handler.clone(), request_handler.clone(), &Vec::new(), other_session).expect("rollback");
+
+        let matching = vec![make_step(StepType::MATCH, Status::Match)];
+        let matched = handle_graph_request(tx_handler.clone(), request_handler.clone(), &matching, make_tx_context(false, false)).expect("match");
+        assert_eq!(1, matched.len());
+        handle_graph_request(tx_handler.clone(), request_handler.clone(), &Vec::new(), make_tx_context(false, true)).expect("rollback");
+        let matched = handle_graph_request(tx_handler.clone(), request_handler.clone(), &matching, None).expect("match");
+        assert_eq!(0, matched.len());
+    }
+
+    #[test]
+    fn test_batch_create() {
+        let main_dir = build_dir_path_and_rm_old("test_tx_batch_create").expect("db path");
+        let conf = InitContext::new(&main_dir).expect("can't create context");
+        let tx_handler = Arc::new(ReentrantMutex::new(RefCell::new(GraphTxHandler::new())));
+        let request_handler = Arc::new(RwLock::new(GraphRequestHandler::new(conf)));
+
+        let patterns = (0..50).map(|_| make_step(StepType::CREATE, Status::Create).patterns.remove(0)).collect::<Vec<PropertyGraph>>();
+        let created = handle_graph_batch_create(tx_handler.clone(), request_handler.clone(), &patterns).expect("batch");
+        assert_eq!(50, created.len());
+        assert!(created.iter().all(|pattern| pattern.get_nodes()[0].get_id().is_some()));
+
+        let mut invalid = patterns.clone();
+        invalid.push(make_step(StepType::MATCH, Status::Match).patterns.remove(0));
+        assert!(handle_graph_batch_create(tx_handler.clone(), request_handler.clone(), &invalid).is_err());
+
+        let constraint = vec![QueryStep::new_create_constraint(UniqueConstraint::new("Person", "email"))];
+        handle_graph_request(tx_handler.clone(), request_handler.clone(), &constraint, None).expect("constraint");
+        let mut duplicates = Vec::new();
+        for email in &["ada@example.com", "alan@example.com", "ada@example.com"] {
+            let mut pattern = make_step(StepType::CREATE, Status::Create).patterns.remove(0);
+            pattern.get_nodes_mut()[0].get_properties_mut().push(Property::new("email".to_string(), PropertyValue::PString(email.to_string())));
+            duplicates.push(pattern);
+        }
+        assert!(handle_graph_batch_create(tx_handler.clone(), request_handler.clone(), &duplicates).is_err());
+
+        let matching = vec![make_step(StepType::MATCH, Status::Match)];
+        let matched = handle_graph_request(tx_handler.clone(), request_handler.clone(), &matching, None).expect("match");
+        assert_eq!(50, matched.len());
+    }
+
+    #[test]
+    fn test_indexes_rebuilt_at_open() {
+        let main_dir = build_dir_path_and_rm_old("test_tx_indexes_rebuilt_at_open").expect("db path");
+        let conf = InitContext::new(&main_dir).expect("can't create context");
+        let tx_handler = Arc::new(ReentrantMutex::new(RefCell::new(GraphTxHandler::new())));
+        let create = vec![make_step(StepType::CREATE, Status::Create)];
+        let matching = vec![make_step(StepType::MATCH, Status::Match)];
+        {
+            let request_handler = Arc::new(RwLock::new(GraphRequestHandler::new(conf.clone())));
+            for _ in 0..3 {
+                handle_graph_request(tx_handler.clone(), request_handler.clone(), &create, None).expect("create");
+            }
+        }
+        let indexes_paths = conf.get_indexes_paths().expect("indexes paths");
+        for index_path in &indexes_paths {
+            let _ = std::fs::remove_file(index_path);
+        }
+        let request_handler = Arc::new(RwLock::new(GraphRequestHandler::new(conf)));
+        assert!(indexes_paths.iter().all(|index_path| std::path::Path::new(index_path).exists()));
+        for _ in 0..2 {
+            assert_eq!(3, handle_graph_request(tx_handler.clone(), request_handler.clone(), &matching, None).expect("match").len());
+        }
+        handle_graph_request(tx_handler.clone(), request_handler.clone(), &create, None).expect("create");
+        assert_eq!(4, handle_graph_request(tx_handler.clone(), request_handler.clone(), &matching, None).expect("match").len());
+    }
+
+    #[test]
+    fn test_failed_statement_marks_tx_rollback_only() {
+        let main_dir = build_dir_path_and_rm_old("test_tx_rollback_only").expect("db path");
+        let conf = InitContext::new(&main_dir).expect("can't create context");
+        let tx_handler = Arc::new(ReentrantMutex::new(RefCell::new(GraphTxHandler::new())));
+        let request_handler = Arc::new(RwLock::new(GraphRequestHandler::new(conf)));
+        let constraint = vec![QueryStep::new_create_constraint(UniqueConstraint::new("Person", "email"))];
+        handle_graph_request(tx_handler.clone(), request_handler.clone(), &constraint, None).expect("constraint");
+        let create_persons = |emails: &[&str]| {
+            let mut step = QueryStep::new(StepType::CREATE);
+            for email in emails {
+                let mut pattern = make_step(StepType::CREATE, Status::Create).patterns.remove(0);
+                pattern.get_nodes_mut()[0].get_properties_mut().push(Property::new("email".to_string(), PropertyValue::PString(email.to_string())));
+                step.patterns.push(pattern);
+            }
+            vec![step]
+        };
+
+        handle_graph_request(tx_handler.clone(), request_handler.clone(), &create_persons(&["ada@example.com"]), make_tx_context(false, false)).expect("create");
+        let res = handle_graph_request(tx_handler.clone(), request_handler.clone(), &create_persons(&["alan@example.com", "ada@example.com"]), make_tx_context(false, false));
+        assert!(matches!(res, Err(DatabaseError::EngineError(GraphError::UniqueConstraintViolation(_)))));
+        let res = handle_graph_request(tx_handler.clone(), request_handler.clone(), &create_persons(&["grace@example.com"]), make_tx_context(false, false));
+        assert!(matches!(res, Err(DatabaseError::TxRollbackOnly(_))));
+        let res = handle_graph_request(tx_handler.clone(), request_handler.clone(), &Vec::new(), make_tx_context(true, false));
+        assert!(matches!(res, Err(DatabaseError::TxRollbackOnly(_))));
+
+        let matching = vec![make_step(StepType::MATCH, Status::Match)];
+        assert_eq!(0, handle_graph_request(tx_handler.clone(), request_handler.clone(), &matching, None).expect("match").len());
+        handle_graph_request(tx_handler.clone(), request_handler.clone(), &create_persons(&["alan@example.com"]), make_tx_context(false, false)).expect("new tx");
+        handle_graph_request(tx_handler.clone(), request_handler.clone(), &Vec::new(), make_tx_context(true, false)).expect("commit");
+        assert_eq!(1, handle_graph_request(tx_handler.clone(), request_handler.clone(), &matching, None).expect("match").len());
+    }
+
+    #[test]
+    fn test_tx_timeout() {
+        let main_dir = build_dir_path_and_rm_old("test_tx_timeout").expect("db path");
+        let conf = InitContext::new(&main_dir).expect("can't create context");
+        let tx_handler = Arc::new(ReentrantMutex::new(RefCell::new(GraphTxHandler::with_timeout(Duration::from_millis(10)))));
+        let request_handler = Arc::new(RwLock::new(GraphRequestHandler::new(conf)));
+
+        let create = vec![make_step(StepType::CREATE, Status::Create)];
+        handle_graph_request(tx_handler.clone(), request_handler.clone(), &create, make_tx_context(false, false)).expect("create");
+
+        handle_graph_request(tx_handler.clone(), request_handler.clone(), &create, None).expect("create");
+        assert!(tx_handler.lock().borrow_mut().evict_expired_sessions().is_empty());
+
+        let matching = vec![make_step(StepType::MATCH, Status::Match)];
+        let matched = handle_graph_request(tx_handler.clone(), request_handler.clone(), &matching, None).expect("match");
+        assert_eq!(1, matched.len());
+    }
+
+    #[test]
+    fn test_snapshot_read_during_tx() {
+        let main_dir = build_dir_path_and_rm_old("test_tx_snapshot_read").expect("db path");
+        let conf = InitContext::new(&main_dir).expect("can't create context");
+        let tx_handler = Arc::new(ReentrantMutex::new(RefCell::new(GraphTxHandler::new())));
+        let request_handler = Arc::new(RwLock::new(GraphRequestHandler::new(conf)));
+
+        let create = vec![make_step(StepType::CREATE, Status::Create)];
+        let matching = vec![make_step(StepType::MATCH, Status::Match)];
+        assert!(needs_write_lock(&create));
+        assert!(!needs_write_lock(&matching));
+        assert!(needs_write_lock(&[QueryStep::new_unwind(vec![vec![make_step(StepType::MATCH, Status::Match)], vec![make_step(StepType::CREATE, Status::Create)]])]));
+        assert!(!needs_write_lock(&[QueryStep::new_unwind(vec![vec![make_step(StepType::MATCH, Status::Match)]])]));
+        handle_graph_request(tx_handler.clone(), request_handler.clone(), &create, None).expect("create");
+
+        handle_graph_request(tx_handler.clone(), request_handler.clone(), &create, make_tx_context(false, false)).expect("create");
+        let reader = {
+            let tx_handler = tx_handler.clone();
+            let request_handler = request_handler.clone();
+            std::thread::spawn(move || {
+                let matching = vec![make_step(StepType::MATCH, Status::Match)];
+                handle_graph_request(tx_handler, request_handler, &matching, None).expect("match").len()
+            })
+        };
+        assert_eq!(1, reader.join().expect("reader"));
+        let matched = handle_graph_request(tx_handler.clone(), request_handler.clone(), &matching, make_tx_context(false, false)).expect("match");
+        assert_eq!(2, matched.len());
+
+        handle_graph_request(tx_handler.clone(), request_handler.clone(), &Vec::new(), make_tx_context(true, false)).expect("commit");
+        let matched = handle_graph_request(tx_handler.clone(), request_handler.clone(), &matching, None).expect("match");
+        assert_eq!(2, matched.len());
+    }
+
+    #[test]
+    fn test_concurrent_sessions() {
+        let main_dir = build_dir_path_and_rm_old("test_tx_concurrent_sessions").expect("db path");
+        let conf = InitContext::new(&main_dir).expect("can't create context");
+        let tx_handler = Arc::new(ReentrantMutex::new(RefCell::new(GraphTxHandler::new())));
+        let request_handler = Arc::new(RwLock::new(GraphRequestHandler::new(conf)));
+        let session = |session_id: &str, commit: bool| Some(TxContext{session_id: session_id.to_string(), commit: commit, rollback: false, read_only: false, isolation: IsolationLevel::Snapshot});
+
+        let create = vec![make_step(StepType::CREATE, Status::Create)];
+        let matching = vec![make_step(StepType::MATCH, Status::Match)];
+        handle_graph_request(tx_handler.clone(), request_handler.clone(), &create, None).expect("create");
+        for session_id in &["first", "second"] {
+            let matched = handle_graph_request(tx_handler.clone(), request_handler.clone(), &matching, session(session_id, false)).expect("match");
+            assert_eq!(1, matched.len());
+        }
+
+        assert_eq!(1, handle_graph_request(tx_handler.clone(), request_handler.clone(), &create, session("first", false)).expect("create").len());
+        let second_write = {
+            let tx_handler = tx_handler.clone();
+            let request_handler = request_handler.clone();
+            std::thread::spawn(move || {
+                let create = vec![make_step(StepType::CREATE, Status::Create)];
+                handle_graph_request(tx_handler, request_handler, &create, Some(TxContext{session_id: "second".to_string(), commit: false, rollback: false, read_only: false, isolation: IsolationLevel::Snapshot})).map(|created| created.len())
+            })
+        };
+        std::thread::sleep(Duration::from_millis(100));
+        assert!(!second_write.is_finished());
+        handle_graph_request(tx_handler.clone(), request_handler.clone(), &Vec::new(), session("first", true)).expect("commit");
+        assert_eq!(1, second_write.join().expect("second write").expect("create"));
+        let res = handle_graph_request(tx_handler.clone(), request_handler.clone(), &Vec::new(), session("second", true));
+        assert!(matches!(res, Err(DatabaseError::TxOutdated(_))));
+
+        let matched = handle_graph_request(tx_handler.clone(), request_handler.clone(), &matching, None).expect("match");
+        assert_eq!(2, matched.len());
+        assert!(tx_handler.lock().borrow().get_lock_manager().get_held_locks("second").is_empty());
+    }
+
+    #[test]
+    fn test_writes_of_different_labels() {
+        let main_dir = build_dir_path_and_rm_old("test_tx_writes_of_different_labels").expect("db path");
+        let conf = InitContext::new(&main_dir).expect("can't create context");
+        let tx_handler = Arc::new(ReentrantMutex::new(RefCell::new(GraphTxHandler::new())));
+        let request_handler = Arc::new(RwLock::new(GraphRequestHandler::new(conf)));
+        let session = |session_id: &str, commit: bool| Some(TxContext{session_id: session_id.to_string(), commit: commit, rollback: false, read_only: false, isolation: IsolationLevel::ReadCommitted});
+        let create = |label: &str| vec![make_label_step(StepType::CREATE, Status::Create, label)];
+        let matching = |label: &str| vec![make_label_step(StepType::MATCH, Status::Match, label)];
+        let timeout = || QueryContext::new().with_timeout(Duration::from_millis(200));
+
+        assert!(!get_request_locks(&create("City")).contains(&(LockResource::Store, LockMode::Exclusive)));
+        handle_graph_query(tx_handler.clone(), request_handler.clone(), &create("City"), session("first", false), None, ExecutionMode::Run, &timeout()).expect("create");
+        handle_graph_query(tx_handler.clone(), request_handler.clone(), &create("Country"), session("second", false), None, ExecutionMode::Run, &timeout()).expect("create");
+        handle_graph_query(tx_handler.clone(), request_handler.clone(), &create("Street"), None, None, ExecutionMode::Run, &timeout()).expect("create");
+        handle_graph_request(tx_handler.clone(), request_handler.clone(), &Vec::new(), session("first", true)).expect("commit");
+        handle_graph_request(tx_handler.clone(), request_handler.clone(), &Vec::new(), session("second", true)).expect("commit");
+
+        assert_eq!(1, handle_graph_request(tx_handler.clone(), request_handler.clone(), &matching("City"), None).expect("match").len());
+        assert_eq!(1, handle_graph_request(tx_handler.clone(), request_handler.clone(), &matching("Country"), None).expect("match").len());
+        assert!(tx_handler.lock().borrow().get_lock_manager().get_held_locks("second").is_empty());
+
+        handle_graph_request(tx_handler.clone(), request_handler.clone(), &create("City"), session("first", false)).expect("create");
+        handle_graph_request(tx_handler.clone(), request_handler.clone(), &create("Country"), session("second", false)).expect("create");
+        let first_write = {
+            let tx_handler = tx_handler.clone();
+            let request_handler = request_handler.clone();
+            std::thread::spawn(move || {
+                let create = vec![make_label_step(StepType::CREATE, Status::Create, "Country")];
+                handle_graph_request(tx_handler, request_handler, &create, Some(TxContext{session_id: "first".to_string(), commit: false, rollback: false, read_only: false, isolation: IsolationLevel::ReadCommitted})).map(|created| created.len())
+            })
+        };
+        std::thread::sleep(Duration::from_millis(100));
+        assert!(matches!(handle_graph_request(tx_handler.clone(), request_handler.clone(), &create("City"), session("second", false)), Err(DatabaseError::Deadlock)));
+        assert_eq!(1, first_write.join().expect("first write").expect("create"));
+        handle_graph_request(tx_handler.clone(), request_handler.clone(), &Vec::new(), session("first", true)).expect("commit");
+        assert_eq!(2, handle_graph_request(tx_handler.clone(), request_handler.clone(), &matching("City"), None).expect("match").len());
+        assert_eq!(2, handle_graph_request(tx_handler.clone(), request_handler.clone(), &matching("Country"), None).expect("match").len());
+    }
+
+    #[test]
+    fn test_concurrent_statements() {
+        let main_dir = build_dir_path_and_rm_old("test_tx_concurrent_statements").expect("db path");
+        let conf = InitContext::new(&main_dir).expect("can't create context");
+        let tx_handler = Arc::new(ReentrantMutex::new(RefCell::new(GraphTxHandler::new())));
+        let request_handler = Arc::new(RwLock::new(GraphRequestHandler::new(conf)));
+        for label in ["City", "City", "Country", "Country"] {
+            handle_graph_request(tx_handler.clone(), request_handler.clone(), &vec![make_label_step(StepType::CREATE, Status::Create, label)], None).expect("create");
+        }
+
+        // the results of both statements are sorted once each, the sort waits for the other statement to sort its results
+        let sorting = Arc::new(AtomicUsize::new(0));
+        let overlapping = Arc::new(AtomicUsize::new(0));
+        let wait_other_statement: ResultComparator = {
+            let sorting = sorting.clone();
+            let overlapping = overlapping.clone();
+            Arc::new(move |graph: &PropertyGraph, other: &PropertyGraph| {
+                sorting.fetch_add(1, Ordering::SeqCst);
+                let start = std::time::Instant::now();
+                while sorting.load(Ordering::SeqCst) < 2 && start.elapsed() < Duration::from_secs(2) {
+                    std::thread::sleep(Duration::from_millis(1));
+                }
+                if sorting.load(Ordering::SeqCst) >= 2 {
+                    overlapping.fetch_add(1, Ordering::SeqCst);
+                }
+                graph.get_nodes()[0].get_id().cmp(&other.get_nodes()[0].get_id())
+            })
+        };
+        request_handler.write().unwrap().set_result_order(ResultOrder::Key(wait_other_statement));
+
+        let statements = ["City", "Country"].iter().map(|label| {
+            let tx_handler = tx_handler.clone();
+            let request_handler = request_handler.clone();
+            let steps = vec![make_label_step(StepType::MATCH, Status::Match, label), QueryStep::new(StepType::SET)];
+            let ctx = Some(TxContext{session_id: label.to_string(), commit: false, rollback: false, read_only: false, isolation: IsolationLevel::ReadCommitted});
+            std::thread::spawn(move || handle_graph_request(tx_handler, request_handler, &steps, ctx).map(|updated| updated.len()))
+        }).collect::<Vec<_>>();
+        for statement in statements {
+            assert_eq!(2, statement.join().expect("statement").expect("set"));
+        }
+        assert_eq!(2, overlapping.load(Ordering::SeqCst));
+        for label in ["City", "Country"] {
+            let ctx = Some(TxContext{session_id: label.to_string(), commit: true, rollback: false, read_only: false, isolation: IsolationLevel::ReadCommitted});
+            handle_graph_request(tx_handler.clone(), request_handler.clone(), &Vec::new(), ctx).expect("commit");
+        }
+    }
+
+    #[test]
+    fn test_read_committed_isolation() {
+        let main_dir = build_dir_path_and_rm_old("test_tx_read_committed_isolation").expect("db path");
+        let conf = InitContext::new(&main_dir).expect("can't create context");
+        let tx_handler = Arc::new(ReentrantMutex::new(RefCell::new(GraphTxHandler::new())));
+        let request_handler = Arc::new(RwLock::new(GraphRequestHandler::new(conf)));
+        let session = |commit: bool| Some(TxContext{session_id: "reader".to_string(), commit, rollback: false, read_only: false, isolation: IsolationLevel::ReadCommitted});
+
+        let create = vec![make_step(StepType::CREATE, Status::Create)];
+        let matching = vec![make_step(StepType::MATCH, Status::Match)];
+        assert!(get_tx_request_locks(&matching).is_empty());
+        assert_eq!(get_request_locks(&create), get_tx_request_locks(&create));
+        handle_graph_request(tx_handler.clone(), request_handler.clone(), &create, None).expect("create");
+
+        assert_eq!(1, handle_graph_request(tx_handler.clone(), request_handler.clone(), &matching, session(false)).expect("match").len());
+        assert!(tx_handler.lock().borrow().get_lock_manager().get_held_locks("reader").is_empty());
+        let timeout = QueryContext::new().with_timeout(Duration::from_millis(50));
+        handle_graph_query(tx_handler.clone(), request_handler.clone(), &create, None, None, ExecutionMode::Run, &timeout).expect("create");
+        assert_eq!(2, handle_graph_request(tx_handler.clone(), request_handler.clone(), &matching, session(false)).expect("match").len());
+        handle_graph_request(tx_handler.clone(), request_handler.clone(), &Vec::new(), session(true)).expect("commit");
+    }
+
+    #[test]
+    fn test_snapshot_isolation() {
+        let main_dir = build_dir_path_and_rm_old("test_tx_snapshot_isolation").expect("db path");
+        let conf = InitContext::new(&main_dir).expect("can't create context");
+        let tx_handler = Arc::new(ReentrantMutex::new(RefCell::new(GraphTxHandler::new())));
+        let request_handler = Arc::new(RwLock::new(GraphRequestHandler::new(conf)));
+        let session = |commit: bool| Some(TxContext{session_id: "snapshot".to_string(), commit, rollback: false, read_only: false, isolation: IsolationLevel::Snapshot});
+
+        let create = vec![make_step(StepType::CREATE, Status::Create)];
+        let matching = vec![make_step(StepType::MATCH, Status::Match)];
+        assert!(get_tx_request_locks(&matching).is_empty());
+        handle_graph_request(tx_handler.clone(), request_handler.clone(), &create, None).expect("create");
+
+        assert_eq!(1, handle_graph_request(tx_handler.clone(), request_handler.clone(), &matching, session(false)).expect("match").len());
+        handle_graph_request(tx_handler.clone(), request_handler.clone(), &create, None).expect("create");
+        assert_eq!(1, handle_graph_request(tx_handler.clone(), request_handler.clone(), &matching, session(false)).expect("match").len());
+        handle_graph_request(tx_handler.clone(), request_handler.clone(), &Vec::new(), session(true)).expect("commit");
+        assert!(tx_handler.lock().borrow().get_lock_manager().get_held_locks("snapshot").is_empty());
+
+        assert_eq!(2, handle_graph_request(tx_handler.clone(), request_handler.clone(), &matching, session(false)).expect("match").len());
+        handle_graph_request(tx_handler.clone(), request_handler.clone(), &create, None).expect("create");
+        handle_graph_request(tx_handler.clone(), request_handler.clone(), &create, session(false)).expect("create");
+        let res = handle_graph_request(tx_handler.clone(), request_handler.clone(), &Vec::new(), session(true));
+        assert!(matches!(res, Err(DatabaseError::TxOutdated(_))));
+        assert!(tx_handler.lock().borrow().get_lock_manager().get_held_locks("snapshot").is_empty());
+        assert_eq!(3, handle_graph_request(tx_handler.clone(), request_handler.clone(), &matching, None).expect("match").len());
+    }
+
+    #[test]
+    fn test_read_only_session() {
+        let main_dir = build_dir_path_and_rm_old("test_tx_read_only_session").expect("db path");
+        let conf = InitContext::new(&main_dir).expect("can't create context");
+        let tx_handler = Arc::new(ReentrantMutex::new(RefCell::new(GraphTxHandler::new())));
+        let request_handler = Arc::new(RwLock::new(GraphRequestHandler::new(conf)));
+        let read_only = |commit: bool| Some(TxContext{session_id: "analytics".to_string(), commit: commit, rollback: false, read_only: true, isolation: IsolationLevel::default()});
+
+        let create = vec![make_step(StepType::CREATE, Status::Create)];
+        let matching = vec![make_step(StepType::MATCH, Status::Match)];
+        handle_graph_request(tx_handler.clone(), request_handler.clone(), &create, None).expect("create");
+        handle_graph_request(tx_handler.clone(), request_handler.clone(), &create, make_tx_context(false, false)).expect("create");
+
+        let matched = handle_graph_request(tx_handler.clone(), request_handler.clone(), &matching, read_only(false)).expect("match");
+        assert_eq!(1, matched.len());
+        assert!(matches!(handle_graph_request(tx_handler.clone(), request_handler.clone(), &create, read_only(false)), Err(DatabaseError::WriteInReadOnlyTx)));
+        handle_graph_request(tx_handler.clone(), request_handler.clone(), &Vec::new(), read_only(true)).expect("commit");
+        assert!(tx_handler.lock().borrow().get_lock_manager().get_held_locks("analytics").is_empty());
+
+        handle_graph_request(tx_handler.clone(), request_handler.clone(), &Vec::new(), make_tx_context(true, false)).expect("commit");
+        let matched = handle_graph_request(tx_handler.clone(), request_handler.clone(), &matching, read_only(false)).expect("match");
+        assert_eq!(2, matched.len());
+    }
+
+    #[test]
+    fn test_result_order() {
+        let main_dir = build_dir_path_and_rm_old("test_tx_result_order").expect("db path");
+        let conf = InitContext::new(&main_dir).expect("can't create context");
+        let tx_handler = Arc::new(ReentrantMutex::new(RefCell::new(GraphTxHandler::new())));
+        let request_handler = Arc::new(RwLock::new(GraphRequestHandler::new(conf)));
+        let create = vec![make_step(StepType::CREATE, Status::Create)];
+        for _ in 0..5 {
+            handle_graph_request(tx_handler.clone(), request_handler.clone(), &create, None).expect("create");
+        }
+        let matching = vec![make_step(StepType::MATCH, Status::Match)];
+        let get_ids = |graphs: Vec<PropertyGraph>| graphs.iter().map(|graph| graph.get_nodes()[0].get_id().expect("id")).collect::<Vec<u64>>();
+        let get_page = |skip: usize| {
+            let page = ResultPage::new(skip, 2);
+            get_ids(handle_graph_request_page(tx_handler.clone(), request_handler.clone(), &matching, None, Some(&page), ExecutionMode::Run).expect("page").graphs)
+        };
+
+        request_handler.write().unwrap().set_result_order(ResultOrder::Id);
+        let ids = get_ids(handle_graph_request(tx_handler.clone(), request_handler.clone(), &matching, None).expect("match"));
+        let mut sorted_ids = ids.clone();
+        sorted_ids.sort();
+        assert_eq!(sorted_ids, ids);
+        assert_eq!(ids, [get_page(0), get_page(2), get_page(4)].concat());
+
+        let by_descending_id: ResultComparator = Arc::new(|graph: &PropertyGraph, other: &PropertyGraph| other.get_nodes()[0].get_id().cmp(&graph.get_nodes()[0].get_id()));
+        request_handler.write().unwrap().set_result_order(ResultOrder::Key(by_descending_id));
+        sorted_ids.reverse();
+        assert_eq!(sorted_ids, [get_page(0), get_page(2), get_page(4)].concat());
+    }
+
+    #[test]
+    fn test_delete_scenario() {
+        let main_dir = build_dir_path_and_rm_old("test_tx_delete").expect("db path");
+        let conf = InitContext::new(&main_dir).expect("can't create context");
+        let tx_handler = Arc::new(ReentrantMutex::new(RefCell::new(GraphTxHandler::new())));
+        let request_handler = Arc::new(RwLock::new(GraphRequestHandler::new(conf)));
+
+        let create = vec![make_step(StepType::CREATE, Status::Create)];
+        handle_graph_request(tx_handler.clone(), request_handler.clone(), &create, None).expect("create");
+
+        let pattern = make_step(StepType::MATCH, Status::Delete).patterns.remove(0);
+        assert_eq!(Scenario::MatchAndDelete, get_request_scenario(&pattern));
+        let delete = make_query_steps(&vec![pattern]);
+        assert_eq!(1, delete.len());
+        assert_eq!(StepType::DELETE, delete[0].step_type);
+        let deleted = handle_graph_request(tx_handler.clone(), request_handler.clone(), &delete, None).expect("delete");
+        assert!(deleted.is_empty());
+
+        let matching = vec![make_step(StepType::MATCH, Status::Match)];
+        let matched = handle_graph_request(tx_handler.clone(), request_handler.clone(), &matching, None).expect("match");
+        assert_eq!(0, matched.len());
+    }
+}
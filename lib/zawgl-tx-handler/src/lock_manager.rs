@@ -0,0 +1,190 @@
+// MIT License
+//
+// Copyright (c) 2022 Alexandre RICCIARDI
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+use parking_lot::{Condvar, Mutex};
+
+/// Data locked by a session: all the nodes with a label, or the whole store.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum LockResource {
+    Store,
+    Label(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LockMode {
+    Shared,
+    Exclusive,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockError {
+    Deadlock,
+    Timeout,
+}
+
+struct LockEntry {
+    mode: LockMode,
+    holders: HashSet<String>,
+}
+
+#[derive(Default)]
+struct LockTable {
+    locks: HashMap<LockResource, LockEntry>,
+    waits_for: HashMap<String, HashSet<String>>,
+}
+
+impl LockTable {
+    /// Sessions preventing the owner from holding the lock in the given mode.
+    fn get_blockers(&self, owner: &str, resource: &LockResource, mode: LockMode) -> HashSet<String> {
+        match self.locks.get(resource) {
+            Some(entry) if mode == LockMode::Exclusive || entry.mode == LockMode::Exclusive => {
+                entry.holders.iter().filter(|holder| *holder != owner).cloned().collect()
+            },
+            _ => HashSet::new(),
+        }
+    }
+
+    fn grant(&mut self, owner: &str, resource: &LockResource, mode: LockMode) {
+        let entry = self.locks.entry(resource.clone()).or_insert_with(|| LockEntry{mode, holders: HashSet::new()});
+        entry.mode = entry.mode.max(mode);
+        entry.holders.insert(String::from(owner));
+    }
+
+    /// Whether one of the blockers waits, directly or not, for the owner.
+    fn leads_to(&self, blockers: &HashSet<String>, owner: &str) -> bool {
+        let mut visited = HashSet::new();
+        let mut to_visit: Vec<&String> = blockers.iter().collect();
+        while let Some(session) = to_visit.pop() {
+            if session == owner {
+                return true;
+            }
+            if visited.insert(session) {
+                if let Some(waited) = self.waits_for.get(session) {
+                    to_visit.extend(waited.iter());
+                }
+            }
+        }
+        false
+    }
+}
+
+/// Shared and exclusive locks held by sessions until they release all of them.
+/// A session waiting for a lock is recorded in a wait-for graph, a request that would close a cycle
+/// in that graph fails with a deadlock instead of waiting.
+#[derive(Default)]
+pub struct LockManager {
+    table: Mutex<LockTable>,
+    released: Condvar,
+}
+
+impl LockManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Acquires or upgrades the lock of the owner, waiting at most the timeout for its other holders to release it.
+    pub fn acquire(&self, owner: &str, resource: &LockResource, mode: LockMode, timeout: Duration) -> Result<(), LockError> {
+        let deadline = Instant::now() + timeout;
+        let mut table = self.table.lock();
+        loop {
+            let blockers = table.get_blockers(owner, resource, mode);
+            if blockers.is_empty() {
+                table.waits_for.remove(owner);
+                table.grant(owner, resource, mode);
+                return Ok(());
+            }
+            if table.leads_to(&blockers, owner) {
+                table.waits_for.remove(owner);
+                return Err(LockError::Deadlock);
+            }
+            table.waits_for.insert(String::from(owner), blockers);
+            if self.released.wait_until(&mut table, deadline).timed_out() {
+                table.waits_for.remove(owner);
+                return Err(LockError::Timeout);
+            }
+        }
+    }
+
+    pub fn release_all(&self, owner: &str) {
+        let mut table = self.table.lock();
+        table.waits_for.remove(owner);
+        table.locks.retain(|_, entry| {
+            entry.holders.remove(owner);
+            !entry.holders.is_empty()
+        });
+        self.released.notify_all();
+    }
+
+    pub fn get_held_locks(&self, owner: &str) -> Vec<(LockResource, LockMode)> {
+        let table = self.table.lock();
+        let mut locks: Vec<(LockResource, LockMode)> = table.locks.iter().filter(|(_, entry)| entry.holders.contains(owner)).map(|(resource, entry)| (resource.clone(), entry.mode)).collect();
+        locks.sort();
+        locks
+    }
+}
+
+#[cfg(test)]
+mod test_lock_manager {
+    use std::sync::Arc;
+    use std::thread;
+    use super::*;
+
+    fn label(name: &str) -> LockResource {
+        LockResource::Label(String::from(name))
+    }
+
+    #[test]
+    fn test_shared_and_exclusive_locks() {
+        let lock_manager = LockManager::new();
+        let timeout = Duration::from_millis(10);
+        lock_manager.acquire("a", &label("Person"), LockMode::Shared, timeout).expect("shared");
+        lock_manager.acquire("b", &label("Person"), LockMode::Shared, timeout).expect("shared");
+        lock_manager.acquire("b", &label("City"), LockMode::Exclusive, timeout).expect("disjoint");
+        assert_eq!(Err(LockError::Timeout), lock_manager.acquire("a", &label("Person"), LockMode::Exclusive, timeout));
+        assert_eq!(Err(LockError::Timeout), lock_manager.acquire("a", &label("City"), LockMode::Shared, timeout));
+        lock_manager.release_all("b");
+        lock_manager.acquire("a", &label("Person"), LockMode::Exclusive, timeout).expect("upgrade");
+        assert_eq!(vec![(label("Person"), LockMode::Exclusive)], lock_manager.get_held_locks("a"));
+        assert!(lock_manager.get_held_locks("b").is_empty());
+    }
+
+    #[test]
+    fn test_deadlock_detection() {
+        let lock_manager = Arc::new(LockManager::new());
+        let timeout = Duration::from_secs(10);
+        lock_manager.acquire("a", &label("Person"), LockMode::Exclusive, timeout).expect("lock");
+        lock_manager.acquire("b", &label("City"), LockMode::Exclusive, timeout).expect("lock");
+        let waiter = {
+            let lock_manager = lock_manager.clone();
+            thread::spawn(move || lock_manager.acquire("b", &label("Person"), LockMode::Exclusive, timeout))
+        };
+        while lock_manager.table.lock().waits_for.get("b").is_none() {
+            thread::yield_now();
+        }
+        assert_eq!(Err(LockError::Deadlock), lock_manager.acquire("a", &label("City"), LockMode::Exclusive, timeout));
+        lock_manager.release_all("a");
+        assert_eq!(Ok(()), waiter.join().expect("waiter"));
+        assert_eq!(vec![(label("City"), LockMode::Exclusive), (label("Person"), LockMode::Exclusive)], lock_manager.get_held_locks("b"));
+    }
+}
@@ -0,0 +1,89 @@
+// MIT License
+//
+// Copyright (c) 2022 Alexandre RICCIARDI
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use std::fmt::Display;
+use std::time::Duration;
+use log::{Level, log};
+use zawgl_core::model::init::QueryLog;
+
+pub const QUERY_LOG_TARGET: &str = "zawgl::query";
+const REDACTED_PARAMETERS: &str = "<redacted>";
+
+pub struct QueryLogEntry<'a> {
+    pub language: &'a str,
+    pub query: &'a str,
+    pub parameters: Option<&'a dyn Display>,
+    pub tx_id: Option<&'a str>,
+    pub elapsed: Duration,
+    /// Number of results, none if the request failed.
+    pub results: Option<usize>,
+}
+
+fn build_log_record(query_log: &QueryLog, entry: &QueryLogEntry) -> (Level, String) {
+    let results = entry.results.map(|count| count.to_string()).unwrap_or_else(|| String::from("failed"));
+    let mut line = format!("language={} tx={} duration_ms={:.3} results={} query={:?}", entry.language, entry.tx_id.unwrap_or("none"),
+        entry.elapsed.as_secs_f64() * 1000.0, results, entry.query);
+    let is_slow = query_log.slow_query_threshold.is_some_and(|threshold| entry.elapsed > threshold);
+    if !is_slow {
+        return (Level::Info, line);
+    }
+    if let Some(parameters) = entry.parameters {
+        let parameters = if query_log.redact_parameters { REDACTED_PARAMETERS.to_string() } else { parameters.to_string() };
+        line.push_str(&format!(" parameters={}", parameters));
+    }
+    (Level::Warn, format!("slow query {}", line))
+}
+
+pub fn log_query(query_log: &QueryLog, entry: &QueryLogEntry) {
+    let (level, line) = build_log_record(query_log, entry);
+    log!(target: QUERY_LOG_TARGET, level, "{}", line);
+}
+
+#[cfg(test)]
+mod test_query_log {
+    use super::*;
+
+    #[test]
+    fn test_slow_query_log() {
+        let parameters = String::from("{\"name\": \"ada\"}");
+        let entry = QueryLogEntry{language: "cypher", query: "MATCH (n:Person) WHERE n.name = $name RETURN n", parameters: Some(&parameters),
+            tx_id: Some("session"), elapsed: Duration::from_millis(20), results: Some(3)};
+        let (level, line) = build_log_record(&QueryLog::default(), &entry);
+        assert_eq!(Level::Info, level);
+        assert!(line.contains("tx=session") && line.contains("results=3"));
+        assert!(!line.contains("parameters="));
+
+        let query_log = QueryLog{slow_query_threshold: Some(Duration::from_millis(10)), redact_parameters: false};
+        let (level, line) = build_log_record(&query_log, &entry);
+        assert_eq!(Level::Warn, level);
+        assert!(line.contains("parameters={\"name\": \"ada\"}"));
+
+        let redacted = QueryLog{redact_parameters: true, ..query_log};
+        let (_, line) = build_log_record(&redacted, &entry);
+        assert!(line.contains("parameters=<redacted>") && !line.contains("ada"));
+
+        let failed = QueryLogEntry{results: None, elapsed: Duration::from_millis(5), ..entry};
+        let (level, line) = build_log_record(&query_log, &failed);
+        assert_eq!(Level::Info, level);
+        assert!(line.contains("results=failed"));
+    }
+}
@@ -20,66 +20,251 @@
 // SOFTWARE.
 
 use std::collections::HashMap;
+use std::io;
 
-use std::sync::{Arc, RwLock};
+use std::sync::{Arc, Mutex, RwLock};
 
-use zawgl_core::graph_engine::GraphEngine;
+use zawgl_core::graph_engine::{BufferPool, GraphEngine, SharedBufferPool};
+use zawgl_core::graph_engine::cdc::{self, ChangeCursor};
+use zawgl_core::graph_engine::query_context::QueryContext;
 use zawgl_core::model::{PropertyGraph, Status};
-use zawgl_core::model::init::InitContext;
-use zawgl_cypher_query_model::QueryStep;
-use zawgl_cypher_query_planner::handle_query_steps;
+use zawgl_core::model::init::{InitContext, QueryLog, ReloadableSettings};
+use zawgl_core::model::statistics::WarmUpReport;
+use zawgl_cypher_query_model::{QueryStep, StepType, ExecutionMode};
+use zawgl_cypher_query_planner::{handle_query, PagedResult, QueryError, ResultOrder, ResultPage};
+use zawgl_cypher_query_planner::procedures::{Procedure, ProcedureRegistry};
 
-use crate::tx_context::TxContext;
-use crate::tx_handler::Scenario;
+use crate::tx_context::{IsolationLevel, TxContext};
+use crate::triggers::{Trigger, TriggerRegistry};
+use crate::tx_handler::{Scenario, needs_write_lock};
 use crate::{DatabaseError};
 
 
 pub type RequestHandler<'a> = Arc<RwLock<GraphRequestHandler<'a>>>;
 
+/// Graph engine of an open transaction, created at the version of the stores the transaction reads,
+/// and the requests that wrote through it.
+struct GraphTx {
+    graph_engine: GraphEngine,
+    writes: Vec<Vec<QueryStep>>,
+}
+
 pub struct GraphRequestHandler <'a> {
     conf: InitContext<'a>,
-    map_session_graph_engine: HashMap<String, GraphEngine>,
+    pool: SharedBufferPool,
+    read_engines: Mutex<Vec<GraphEngine>>,
+    map_session_graph_engine: Mutex<HashMap<String, Arc<Mutex<GraphTx>>>>,
+    commit_lock: Mutex<()>,
+    procedures: ProcedureRegistry,
+    triggers: TriggerRegistry,
+    result_order: ResultOrder,
 }
 
 impl <'a> GraphRequestHandler<'a> {
+    /// Opens the database: its missing indexes are rebuilt before any request reads them.
     pub fn new(ctx: InitContext<'a>) -> Self {
-        GraphRequestHandler{conf: ctx, map_session_graph_engine: HashMap::new()}
+        let pool = BufferPool::from_context(&ctx);
+        let graph_engine = GraphEngine::open(&ctx, &pool);
+        GraphRequestHandler{pool, read_engines: Mutex::new(vec![graph_engine]), conf: ctx, map_session_graph_engine: Mutex::new(HashMap::new()), commit_lock: Mutex::new(()), procedures: ProcedureRegistry::with_builtins(), triggers: TriggerRegistry::new(), result_order: ResultOrder::Matcher}
+    }
+
+    pub fn get_query_log(&self) -> QueryLog {
+        self.conf.get_query_log()
+    }
+
+    /// Applies to the graph engines of the next requests, the running ones keep their settings.
+    /// The page cache of the database is resized at once.
+    pub fn reload_settings(&mut self, settings: ReloadableSettings) {
+        self.conf.set_reloadable_settings(settings);
+        self.pool.lock().unwrap().set_capacity(self.conf.get_page_cache_capacity());
+        self.read_engines.lock().unwrap().clear();
+    }
+
+    pub fn get_change_data_capture(&self) -> bool {
+        self.conf.get_change_data_capture()
     }
 
-    pub fn handle_graph_request(&self, steps: &Vec<QueryStep>) -> Result<Vec<PropertyGraph>, DatabaseError> {
-        let mut graph_engine = GraphEngine::new(&self.conf);
-        let matched_graphs = handle_query_steps(steps, &mut graph_engine);
+    /// Reads the stores into the page cache of the database, shared by the graph engines of the following requests.
+    /// None if an index is unknown.
+    pub fn warm_up(&self, indexes: &[&str]) -> Option<WarmUpReport> {
+        GraphEngine::with_pool(&self.conf, &self.pool).warm_up(indexes)
+    }
+
+    /// Reads the changes committed from the offset of a change set of the change log.
+    pub fn read_changes(&self, offset: u64) -> io::Result<ChangeCursor> {
+        cdc::read_changes(&self.conf, offset)
+    }
+
+    /// Sorts the matched graphs of the queries, so that repeated queries and their pages return them in the same order.
+    pub fn set_result_order(&mut self, result_order: ResultOrder) {
+        self.result_order = result_order;
+    }
+
+    /// Makes a procedure callable with CALL, replacing the one registered under the same name.
+    pub fn register_procedure(&mut self, procedure: Procedure) -> Option<Procedure> {
+        self.procedures.register(procedure)
+    }
+
+    /// Registers a trigger fired before each commit, replacing the one registered under the same name.
+    pub fn register_trigger(&mut self, trigger: Trigger) -> Option<Trigger> {
+        self.triggers.register(trigger)
+    }
+
+    pub fn unregister_trigger(&mut self, name: &str) -> Option<Trigger> {
+        self.triggers.unregister(name)
+    }
+
+    /// Graph engine for writes, recording its changes for the triggers.
+    fn new_graph_engine(&self) -> GraphEngine {
+        let mut graph_engine = GraphEngine::with_pool(&self.conf, &self.pool);
+        if !self.triggers.is_empty() {
+            graph_engine.enable_change_capture();
+        }
+        graph_engine
+    }
+
+    /// Syncs the writes of the graph engine unless a trigger vetoes them, one commit at a time. The requests
+    /// run meanwhile through their own graph engines: once another commit outdated the pages written by the
+    /// graph engine, it is replaced by the one of `replay`, which writes again against the last committed state.
+    /// The idle read engines are dropped as they were opened on the previous state of the stores.
+    fn commit_graph_engine<F: FnOnce() -> Result<GraphEngine, DatabaseError>>(&self, graph_engine: &mut GraphEngine, replay: F) -> Result<(), DatabaseError> {
+        let _commit = self.commit_lock.lock().unwrap();
+        if graph_engine.is_outdated() {
+            *graph_engine = replay()?;
+        }
+        self.triggers.fire(graph_engine)?;
+        self.read_engines.lock().unwrap().clear();
         graph_engine.sync();
-        Ok(matched_graphs)
+        Ok(())
     }
 
-    
-    pub fn handle_graph_request_tx(&mut self, steps: &Vec<QueryStep>, tx_context: &TxContext) -> Result<Vec<PropertyGraph>, DatabaseError> {
-        let mut graph_engine = self.map_session_graph_engine.get_mut(&tx_context.session_id).ok_or_else(|| DatabaseError::TxError)?;
-        let matched_graphs = handle_query_steps(steps, &mut graph_engine);
+    /// Runs the request through its own graph engine and commits it, the request is run again at commit if
+    /// another commit happened meanwhile, it then returns the graphs of its last run.
+    pub fn handle_graph_request(&self, steps: &Vec<QueryStep>, page: Option<&ResultPage>, mode: ExecutionMode, query_context: &QueryContext) -> Result<PagedResult, DatabaseError> {
+        let mut graph_engine = self.new_graph_engine();
+        let mut matched_graphs = run_query(steps, &mut graph_engine, &self.procedures, page, mode, &self.result_order, query_context)?;
+        self.commit_graph_engine(&mut graph_engine, || {
+            let mut graph_engine = self.new_graph_engine();
+            matched_graphs = run_query(steps, &mut graph_engine, &self.procedures, page, mode, &self.result_order, &QueryContext::new())?;
+            Ok(graph_engine)
+        })?;
         Ok(matched_graphs)
     }
 
-    pub fn commit_tx(&mut self, tx_context: & TxContext) -> Result<Vec<PropertyGraph>, DatabaseError> {
-        let graph_engine = self.map_session_graph_engine.get_mut(&tx_context.session_id).ok_or_else(|| DatabaseError::TxError)?;
-        graph_engine.sync();
-        self.map_session_graph_engine.remove(&tx_context.session_id);
+    /// Creates all the patterns through a single graph engine synced once, nothing is written
+    /// if one of the patterns is not a creation or can't be created.
+    pub fn handle_graph_batch_create(&self, patterns: &[PropertyGraph]) -> Result<Vec<PropertyGraph>, DatabaseError> {
+        if patterns.iter().any(|pattern| get_request_scenario(pattern) != Scenario::CreateOnly) {
+            return Err(DatabaseError::InvalidBatch);
+        }
+        let create = |graph_engine: &mut GraphEngine| patterns.iter().map(|pattern| graph_engine.create_graph(pattern)).collect::<Result<Vec<PropertyGraph>, _>>();
+        let mut graph_engine = self.new_graph_engine();
+        let mut created = create(&mut graph_engine)?;
+        self.commit_graph_engine(&mut graph_engine, || {
+            let mut graph_engine = self.new_graph_engine();
+            created = create(&mut graph_engine)?;
+            Ok(graph_engine)
+        })?;
+        Ok(created)
+    }
+
+    /// Runs a read only query against the last committed state: open transactions keep
+    /// their writes in their own page cache until commit.
+    /// The read engines are reused by the following reads until a commit.
+    pub fn handle_graph_read_request(&self, steps: &Vec<QueryStep>, page: Option<&ResultPage>, mode: ExecutionMode, query_context: &QueryContext) -> Result<PagedResult, DatabaseError> {
+        let idle_engine = self.read_engines.lock().unwrap().pop().filter(|graph_engine| !graph_engine.is_outdated());
+        let mut graph_engine = idle_engine.unwrap_or_else(|| GraphEngine::with_pool(&self.conf, &self.pool));
+        let res = run_query(steps, &mut graph_engine, &self.procedures, page, mode, &self.result_order, query_context);
+        if res.is_ok() && !graph_engine.is_outdated() {
+            self.read_engines.lock().unwrap().push(graph_engine);
+        }
+        res
+    }
+
+    /// Runs a request of the transaction through its graph engine. Only the graph engine of the session
+    /// is locked, the requests of the other sessions run meanwhile.
+    pub fn handle_graph_request_tx(&self, steps: &Vec<QueryStep>, tx_context: &TxContext, page: Option<&ResultPage>, mode: ExecutionMode, query_context: &QueryContext) -> Result<PagedResult, DatabaseError> {
+        let graph_tx = self.get_graph_tx(&tx_context.session_id)?;
+        let mut graph_tx = graph_tx.lock().unwrap();
+        let res = run_query(steps, &mut graph_tx.graph_engine, &self.procedures, page, mode, &self.result_order, query_context)?;
+        if needs_write_lock(steps) && mode != ExecutionMode::Explain {
+            graph_tx.writes.push(steps.clone());
+        }
+        Ok(res)
+    }
+
+    fn get_graph_tx(&self, session_id: &str) -> Result<Arc<Mutex<GraphTx>>, DatabaseError> {
+        self.map_session_graph_engine.lock().unwrap().get(session_id).cloned().ok_or_else(|| DatabaseError::TxNotFound(session_id.to_string()))
+    }
+
+    /// Syncs the writes of the transaction, a transaction that did not write has nothing to sync.
+    /// Once another transaction committed, the pages written by the graph engine of the transaction are
+    /// outdated: at the read committed level its writes are run again against the last committed state,
+    /// the created nodes and relationships then get new ids, while at the snapshot level the transaction
+    /// is rolled back. The labels locked by the transaction keep the writes of the others away from the
+    /// nodes it matched or wrote. A transaction vetoed by a trigger is rolled back.
+    pub fn commit_tx(&self, tx_context: & TxContext) -> Result<Vec<PropertyGraph>, DatabaseError> {
+        let graph_tx = self.map_session_graph_engine.lock().unwrap().remove(&tx_context.session_id);
+        if let Some(graph_tx) = graph_tx {
+            let mut graph_tx = graph_tx.lock().unwrap();
+            if !graph_tx.writes.is_empty() {
+                let GraphTx{graph_engine, writes} = &mut *graph_tx;
+                self.commit_graph_engine(graph_engine, || match tx_context.isolation {
+                    IsolationLevel::Snapshot => Err(DatabaseError::TxOutdated(tx_context.session_id.clone())),
+                    IsolationLevel::ReadCommitted => self.replay_writes(writes),
+                })?;
+            }
+        }
+        Ok(Vec::new())
+    }
+
+    /// Runs the writes of a transaction again through a graph engine reading the last committed state.
+    fn replay_writes(&self, writes: &[Vec<QueryStep>]) -> Result<GraphEngine, DatabaseError> {
+        let mut graph_engine = self.new_graph_engine();
+        for steps in writes {
+            run_query(steps, &mut graph_engine, &self.procedures, None, ExecutionMode::Run, &self.result_order, &QueryContext::new())?;
+        }
+        Ok(graph_engine)
+    }
+
+    pub fn rollback_tx(&self, tx_context: & TxContext) -> Result<Vec<PropertyGraph>, DatabaseError> {
+        self.map_session_graph_engine.lock().unwrap().remove(&tx_context.session_id);
         Ok(Vec::new())
     }
 
-    pub fn open_graph_tx(&mut self, tx_context: &TxContext) {
-        self.map_session_graph_engine.insert(tx_context.session_id.clone(), GraphEngine::new(&self.conf));
+    pub fn has_graph_tx(&self, session_id: &str) -> bool {
+        self.map_session_graph_engine.lock().unwrap().contains_key(session_id)
+    }
+
+    /// Creates the graph engine of the transaction, it reads the stores as they are committed at this point.
+    pub fn open_graph_tx(&self, tx_context: &TxContext) {
+        let graph_engine = self.new_graph_engine();
+        self.map_session_graph_engine.lock().unwrap().insert(tx_context.session_id.clone(), Arc::new(Mutex::new(GraphTx{graph_engine, writes: Vec::new()})));
     }
 }
 
 
+/// Runs the query in its context, an aborted query fails so that its writes are not synced.
+fn run_query(steps: &Vec<QueryStep>, graph_engine: &mut GraphEngine, procedures: &ProcedureRegistry, page: Option<&ResultPage>, mode: ExecutionMode, order: &ResultOrder, query_context: &QueryContext) -> Result<PagedResult, DatabaseError> {
+    query_context.check().map_err(DatabaseError::QueryAborted)?;
+    graph_engine.set_query_context(query_context.clone());
+    let res = handle_query(steps, graph_engine, procedures, page, mode, order).map_err(|err| match err {
+        QueryError::Procedure(err) => DatabaseError::ProcedureError(err),
+        QueryError::Graph(err) => DatabaseError::EngineError(err),
+    });
+    query_context.check().map_err(DatabaseError::QueryAborted)?;
+    res
+}
+
 pub fn get_request_scenario(pattern: &PropertyGraph) -> Scenario {
     let mut contains_match = false;
     let mut contains_create = false;
+    let mut contains_delete = false;
     for n in pattern.get_nodes() {
         match n.get_status() {
             Status::Create => {contains_create = true;}
             Status::Match => {contains_match = true;}
+            Status::Delete => {contains_delete = true;}
             _ => {}
         } 
     }
@@ -87,10 +272,17 @@ pub fn get_request_scenario(pattern: &PropertyGraph) -> Scenario {
         match r.get_status() {
             Status::Create => {contains_create = true;}
             Status::Match => {contains_match = true;}
+            Status::Delete => {contains_delete = true;}
             _ => {}
         } 
     }
-    if contains_match {
+    if contains_delete {
+        if contains_create {
+            Scenario::Unknown
+        } else {
+            Scenario::MatchAndDelete
+        }
+    } else if contains_match {
         if contains_create {
             Scenario::MatchAndCreate
         } else {
@@ -103,4 +295,27 @@ pub fn get_request_scenario(pattern: &PropertyGraph) -> Scenario {
             Scenario::Unknown
         }
     }
-}
\ No newline at end of file
+}
+
+/// Builds the query steps of patterns coming from a traversal, consecutive patterns
+/// of the same step type are grouped in a single step.
+pub fn make_query_steps(patterns: &Vec<PropertyGraph>) -> Vec<QueryStep> {
+    let mut steps: Vec<QueryStep> = Vec::new();
+    for pattern in patterns {
+        let step_type = match get_request_scenario(pattern) {
+            Scenario::CreateOnly | Scenario::MatchAndCreate => StepType::CREATE,
+            Scenario::MatchOnly => StepType::MATCH,
+            Scenario::MatchAndDelete => StepType::DELETE,
+            Scenario::Unknown => continue,
+        };
+        match steps.last_mut() {
+            Some(step) if step.step_type == step_type => step.patterns.push(pattern.clone()),
+            _ => {
+                let mut step = QueryStep::new(step_type);
+                step.patterns.push(pattern.clone());
+                steps.push(step);
+            }
+        }
+    }
+    steps
+}
@@ -0,0 +1,118 @@
+// MIT License
+//
+// Copyright (c) 2022 Alexandre RICCIARDI
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+use zawgl_core::graph_engine::query_context::QueryContext;
+
+struct QueryEntry {
+    request_id: String,
+    start: Instant,
+    query_context: QueryContext,
+}
+
+/// Queries being run, so that a client can cancel them by request id.
+pub struct QueryRegistry {
+    next_key: Mutex<u64>,
+    queries: Mutex<BTreeMap<u64, QueryEntry>>,
+}
+
+/// Registration of a running query, removed when dropped.
+pub struct RunningQuery<'a> {
+    registry: &'a QueryRegistry,
+    key: u64,
+    query_context: QueryContext,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunningQueryInfo {
+    pub request_id: String,
+    pub elapsed: Duration,
+    pub memory_used: usize,
+}
+
+impl QueryRegistry {
+    pub fn new() -> Self {
+        QueryRegistry{next_key: Mutex::new(0), queries: Mutex::new(BTreeMap::new())}
+    }
+
+    pub fn register(&self, request_id: &str, query_context: QueryContext) -> RunningQuery<'_> {
+        let key = {
+            let mut next_key = self.next_key.lock();
+            *next_key += 1;
+            *next_key
+        };
+        self.queries.lock().insert(key, QueryEntry{request_id: String::from(request_id), start: Instant::now(), query_context: query_context.clone()});
+        RunningQuery{registry: self, key, query_context}
+    }
+
+    /// Cancels the running queries of the request id, returns their count.
+    pub fn cancel(&self, request_id: &str) -> usize {
+        let queries = self.queries.lock();
+        let cancelled = queries.values().filter(|entry| entry.request_id == request_id).map(|entry| entry.query_context.cancel()).count();
+        cancelled
+    }
+
+    pub fn list(&self) -> Vec<RunningQueryInfo> {
+        self.queries.lock().values().map(|entry| RunningQueryInfo{request_id: entry.request_id.clone(), elapsed: entry.start.elapsed(), memory_used: entry.query_context.get_memory_used()}).collect()
+    }
+}
+
+impl Default for QueryRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RunningQuery<'_> {
+    pub fn get_query_context(&self) -> &QueryContext {
+        &self.query_context
+    }
+}
+
+impl Drop for RunningQuery<'_> {
+    fn drop(&mut self) {
+        self.registry.queries.lock().remove(&self.key);
+    }
+}
+
+#[cfg(test)]
+mod test_running_queries {
+    use super::*;
+    use zawgl_core::graph_engine::query_context::QueryAbort;
+
+    #[test]
+    fn test_cancel_query() {
+        let registry = QueryRegistry::new();
+        let query = registry.register("request-1", QueryContext::new());
+        let other = registry.register("request-2", QueryContext::new());
+        assert_eq!(2, registry.list().len());
+        assert_eq!(1, registry.cancel("request-1"));
+        assert_eq!(0, registry.cancel("unknown"));
+        assert_eq!(Err(QueryAbort::Cancelled), query.get_query_context().check());
+        assert!(other.get_query_context().check().is_ok());
+        drop(query);
+        assert_eq!(vec![String::from("request-2")], registry.list().into_iter().map(|info| info.request_id).collect::<Vec<String>>());
+    }
+}
@@ -0,0 +1,176 @@
+// MIT License
+//
+// Copyright (c) 2022 Alexandre RICCIARDI
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use std::collections::BTreeMap;
+
+use zawgl_core::graph_engine::GraphEngine;
+use zawgl_core::model::changes::{ChangeEvent, ChangeKind};
+
+use crate::DatabaseError;
+
+pub type TriggerResult = Result<(), String>;
+
+type TriggerHook = Box<dyn Fn(&[ChangeEvent], &mut GraphEngine) -> TriggerResult + Send + Sync>;
+
+pub struct Trigger {
+    name: String,
+    kinds: Vec<ChangeKind>,
+    hook: TriggerHook,
+}
+
+impl Trigger {
+    /// The hook receives the changes of the committing transaction having one of the kinds, it runs on the
+    /// graph engine of the transaction before its sync. An error vetoes the commit, its message is reported
+    /// to the client. Changes made by the hook are committed with the transaction without firing the triggers.
+    pub fn new<F>(name: &str, kinds: &[ChangeKind], hook: F) -> Self
+        where F: Fn(&[ChangeEvent], &mut GraphEngine) -> TriggerResult + Send + Sync + 'static {
+        Trigger{name: String::from(name), kinds: kinds.to_vec(), hook: Box::new(hook)}
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Triggers fired at commit, in the order of their names.
+pub struct TriggerRegistry {
+    triggers: BTreeMap<String, Trigger>,
+}
+
+impl TriggerRegistry {
+    pub fn new() -> Self {
+        TriggerRegistry{triggers: BTreeMap::new()}
+    }
+
+    /// Registers a trigger, returning the one previously registered under the same name.
+    pub fn register(&mut self, trigger: Trigger) -> Option<Trigger> {
+        self.triggers.insert(trigger.name.clone(), trigger)
+    }
+
+    pub fn unregister(&mut self, name: &str) -> Option<Trigger> {
+        self.triggers.remove(name)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.triggers.is_empty()
+    }
+
+    /// Runs the triggers on the changes of the graph engine, stopping at the first veto.
+    pub fn fire(&self, graph_engine: &mut GraphEngine) -> Result<(), DatabaseError> {
+        if self.triggers.is_empty() {
+            return Ok(());
+        }
+        let changes = graph_engine.get_changes();
+        for trigger in self.triggers.values() {
+            let events = changes.iter().filter(|event| trigger.kinds.contains(&event.get_kind())).cloned().collect::<Vec<ChangeEvent>>();
+            if !events.is_empty() {
+                (trigger.hook)(&events, graph_engine).map_err(|message| DatabaseError::TriggerVeto(trigger.name.clone(), message))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test_triggers {
+    use std::cell::RefCell;
+    use std::sync::{Arc, RwLock};
+    use parking_lot::ReentrantMutex;
+    use zawgl_core::model::{Node, Property, PropertyGraph, PropertyValue, Status, init::InitContext};
+    use zawgl_core::test_utils::build_dir_path_and_rm_old;
+    use zawgl_cypher_query_model::{QueryStep, StepType};
+    use crate::handle_graph_request;
+    use crate::request_handler::GraphRequestHandler;
+    use crate::tx_context::{IsolationLevel, TxContext};
+    use crate::tx_handler::GraphTxHandler;
+    use super::*;
+
+    fn make_step(label: &str, status: Status, name: Option<&str>) -> QueryStep {
+        let mut node = Node::new();
+        node.set_labels(vec![String::from(label)]);
+        node.set_status(status);
+        if let Some(name) = name {
+            node.set_properties(vec![Property::new(String::from("name"), PropertyValue::PString(String::from(name)))]);
+        }
+        let mut pattern = PropertyGraph::new();
+        pattern.add_node(node);
+        let step_type = if status == Status::Create { StepType::CREATE } else { StepType::MATCH };
+        let mut step = QueryStep::new(step_type);
+        step.patterns.push(pattern);
+        step
+    }
+
+    fn require_name(events: &[ChangeEvent], _graph_engine: &mut GraphEngine) -> TriggerResult {
+        for event in events {
+            if let ChangeEvent::Node{node, ..} = event {
+                if !node.get_properties_ref().iter().any(|p| p.get_name() == "name") {
+                    return Err(String::from("nodes must have a name"));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn audit_deletes(events: &[ChangeEvent], graph_engine: &mut GraphEngine) -> TriggerResult {
+        for _ in events {
+            let mut audit = Node::new();
+            audit.set_labels(vec![String::from("Audit")]);
+            audit.set_properties(vec![Property::new(String::from("name"), PropertyValue::PString(String::from("deleted")))]);
+            graph_engine.create_node(&audit).map_err(|err| err.to_string())?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_triggers() {
+        let main_dir = build_dir_path_and_rm_old("test_triggers").expect("db path");
+        let conf = InitContext::new(&main_dir).expect("can't create context");
+        let tx_handler = Arc::new(ReentrantMutex::new(RefCell::new(GraphTxHandler::new())));
+        let request_handler = Arc::new(RwLock::new(GraphRequestHandler::new(conf)));
+        request_handler.write().unwrap().register_trigger(Trigger::new("require_name", &[ChangeKind::Created], require_name));
+        request_handler.write().unwrap().register_trigger(Trigger::new("audit_deletes", &[ChangeKind::Deleted], audit_deletes));
+        let count = |label: &str| handle_graph_request(tx_handler.clone(), request_handler.clone(), &vec![make_step(label, Status::Match, None)], None).expect("match").len();
+
+        let res = handle_graph_request(tx_handler.clone(), request_handler.clone(), &vec![make_step("Person", Status::Create, None)], None);
+        assert!(matches!(res, Err(DatabaseError::TriggerVeto(name, _)) if name == "require_name"));
+        assert_eq!(0, count("Person"));
+        handle_graph_request(tx_handler.clone(), request_handler.clone(), &vec![make_step("Person", Status::Create, Some("ada"))], None).expect("create");
+        assert_eq!(1, count("Person"));
+
+        let tx = |commit: bool| Some(TxContext{session_id: String::from("session"), commit, rollback: false, read_only: false, isolation: IsolationLevel::default()});
+        handle_graph_request(tx_handler.clone(), request_handler.clone(), &vec![make_step("Person", Status::Create, Some("alan"))], tx(false)).expect("create");
+        handle_graph_request(tx_handler.clone(), request_handler.clone(), &vec![make_step("Person", Status::Create, None)], tx(false)).expect("create");
+        assert!(matches!(handle_graph_request(tx_handler.clone(), request_handler.clone(), &Vec::new(), tx(true)), Err(DatabaseError::TriggerVeto(..))));
+        assert_eq!(1, count("Person"));
+        assert!(tx_handler.lock().borrow().get_lock_manager().get_held_locks("session").is_empty());
+
+        let mut delete = make_step("Person", Status::Delete, None);
+        delete.step_type = StepType::DELETE;
+        handle_graph_request(tx_handler.clone(), request_handler.clone(), &vec![delete], None).expect("delete");
+        assert_eq!(0, count("Person"));
+        assert_eq!(1, count("Audit"));
+
+        assert!(request_handler.write().unwrap().unregister_trigger("require_name").is_some());
+        handle_graph_request(tx_handler.clone(), request_handler.clone(), &vec![make_step("Person", Status::Create, None)], None).expect("create");
+        assert_eq!(1, count("Person"));
+    }
+}
@@ -19,7 +19,39 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
+/// Isolation of a transaction from the changes committed by the other sessions while it runs.
+/// At both levels the requests which don't write take no lock, the writes lock the labels they write
+/// until the transaction ends and the transaction reads its own writes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IsolationLevel {
+    /// Each request reads the last committed state until the first write, the following requests read
+    /// the state committed before that write. The writes are run again at commit if another transaction
+    /// committed meanwhile.
+    #[default]
+    ReadCommitted,
+    /// All the requests read the state committed before the first one, through a graph engine created by that
+    /// request: the other sessions keep committing meanwhile. The commit fails and rolls back the transaction
+    /// if it wrote and another transaction committed after its first request.
+    Snapshot,
+}
+
+impl IsolationLevel {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "read_committed" => Some(IsolationLevel::ReadCommitted),
+            "snapshot" => Some(IsolationLevel::Snapshot),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct TxContext {
     pub session_id: String,
     pub commit: bool,
+    pub rollback: bool,
+    /// Read only sessions don't take any lock, at the read committed level they read the last committed state
+    /// without opening a transaction.
+    pub read_only: bool,
+    pub isolation: IsolationLevel,
 }
\ No newline at end of file
@@ -19,11 +19,15 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::sync::Arc;
-use std::{cell::RefCell, mem};
-use std::time::Instant;
-use parking_lot::{Mutex, ReentrantMutex};
-use zawgl_cypher_query_model::QueryStep;
+use std::cell::RefCell;
+use std::time::{Duration, Instant};
+use parking_lot::ReentrantMutex;
+use zawgl_core::graph::traits::MemGraphId;
+use zawgl_core::model::{PropertyGraph, Status};
+use zawgl_cypher_query_model::{QueryStep, StepType};
+use crate::lock_manager::{LockManager, LockMode, LockResource};
 use crate::tx_context::TxContext;
 
 pub type TxHandler = Arc<ReentrantMutex<RefCell<GraphTxHandler>>>;
@@ -32,79 +36,183 @@ pub enum TxStatus<'a> {
     OpenNewTx(&'a TxContext),
     ContinueCurrentTx(&'a TxContext),
     CommitCurrentTx(&'a TxContext),
-    WaitForCurrentTx,
+    RollbackCurrentTx(&'a TxContext),
     NoTx,
 }
 
+pub const DEFAULT_TX_TIMEOUT_SECS: u64 = 10;
+
+/// Open transactions by session, they hold their locks until they commit, roll back or expire.
 pub struct GraphTxHandler {
-    current_session_id: Option<String>,
-    session_lock: Mutex<()>,
-    is_session_locked: bool,
-    tx_start_date: Option<Instant>,
+    sessions: HashMap<String, Instant>,
+    rollback_only_sessions: HashSet<String>,
+    lock_manager: Arc<LockManager>,
+    tx_timeout: Duration,
 }
 
 impl GraphTxHandler {
     pub fn new() -> Self {
-        GraphTxHandler{current_session_id: None, session_lock: Mutex::new(()), is_session_locked: false, tx_start_date: None}
+        Self::with_timeout(Duration::from_secs(DEFAULT_TX_TIMEOUT_SECS))
+    }
+
+    pub fn with_timeout(tx_timeout: Duration) -> Self {
+        GraphTxHandler{sessions: HashMap::new(), rollback_only_sessions: HashSet::new(), lock_manager: Arc::new(LockManager::new()), tx_timeout}
     }
 
     pub fn get_session_status<'a>(&mut self, tx_context: &'a Option<TxContext>) -> TxStatus<'a> {
-        if let Some(ctx) = tx_context {
-            if let Some(s_id) = &self.current_session_id {
-                if &ctx.session_id == s_id {
-                    if ctx.commit {
-                        TxStatus::CommitCurrentTx(ctx)
-                    } else {
-                        TxStatus::ContinueCurrentTx(ctx)
-                    }
+        match tx_context {
+            Some(ctx) if self.sessions.contains_key(&ctx.session_id) => {
+                if ctx.commit {
+                    TxStatus::CommitCurrentTx(ctx)
+                } else if ctx.rollback {
+                    TxStatus::RollbackCurrentTx(ctx)
                 } else {
-                    TxStatus::WaitForCurrentTx
+                    TxStatus::ContinueCurrentTx(ctx)
                 }
-            } else {
-                self.current_session_id = Some(ctx.session_id.clone());
+            },
+            // the session has no open transaction, there is nothing to commit or discard
+            Some(ctx) if ctx.commit || ctx.rollback => TxStatus::NoTx,
+            Some(ctx) => {
+                self.sessions.insert(ctx.session_id.clone(), Instant::now());
                 TxStatus::OpenNewTx(ctx)
-            }
-        } else if let Some(_sid) = &self.current_session_id {
-            TxStatus::WaitForCurrentTx
-        } else {
-            TxStatus::NoTx
+            },
+            None => TxStatus::NoTx,
         }
     }
 
-    pub fn acquire_session_lock(&mut self) {
-        if let Some(tx_start_date) = self.tx_start_date {
-            let duration = Instant::now().duration_since(tx_start_date);
-            if duration.as_secs() > 10 {
-                self.release_session_lock();
-            }
+    pub fn get_lock_manager(&self) -> Arc<LockManager> {
+        self.lock_manager.clone()
+    }
+
+    pub fn get_tx_timeout(&self) -> Duration {
+        self.tx_timeout
+    }
+
+    /// Ends the transactions inactive for longer than the timeout and releases their locks.
+    pub fn evict_expired_sessions(&mut self) -> Vec<String> {
+        let tx_timeout = self.tx_timeout;
+        let expired = self.sessions.iter().filter(|(_, last_activity_date)| last_activity_date.elapsed() > tx_timeout).map(|(session_id, _)| session_id.clone()).collect::<Vec<String>>();
+        for session_id in &expired {
+            self.end_session(session_id);
         }
-        mem::forget(self.session_lock.lock());
-        self.is_session_locked = true;
-        self.tx_start_date = Some(Instant::now());
-    }
-
-    pub fn release_session_lock(&mut self) {
-        self.current_session_id = None;
-        if self.is_session_locked {
-            unsafe {
-                self.session_lock.force_unlock();
-            }
-            self.is_session_locked = false;
-            self.tx_start_date = None;
+        expired
+    }
+
+    pub fn touch_session(&mut self, session_id: &str) {
+        if let Some(last_activity_date) = self.sessions.get_mut(session_id) {
+            *last_activity_date = Instant::now();
         }
     }
-}
 
+    /// Marks the transaction of a session as failed, it can then only be rolled back.
+    pub fn set_rollback_only(&mut self, session_id: &str) {
+        if self.sessions.contains_key(session_id) {
+            self.rollback_only_sessions.insert(String::from(session_id));
+        }
+    }
+
+    pub fn is_rollback_only(&self, session_id: &str) -> bool {
+        self.rollback_only_sessions.contains(session_id)
+    }
+
+    pub fn end_session(&mut self, session_id: &str) {
+        self.sessions.remove(session_id);
+        self.rollback_only_sessions.remove(session_id);
+        self.lock_manager.release_all(session_id);
+    }
+}
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Scenario {
     CreateOnly,
     MatchAndCreate,
     MatchOnly,
+    MatchAndDelete,
     Unknown,
 }
 
 
-pub fn needs_write_lock<'a>(_steps: &Vec<QueryStep>) -> bool {
-    return true;
+pub fn needs_write_lock(steps: &[QueryStep]) -> bool {
+    steps.iter().any(|step| {
+        match step.step_type {
+            StepType::MATCH | StepType::OPTIONAL_MATCH | StepType::WHERE => {
+                step.patterns.iter().any(|pattern| {
+                    pattern.get_nodes().iter().map(|n| n.get_status()).chain(pattern.get_relationships().iter().map(|r| r.get_status()))
+                        .any(|status| !matches!(status, Status::Match | Status::Empty))
+                })
+            },
+            StepType::UNWIND | StepType::FOREACH => step.unwind_batches.iter().any(|batch| needs_write_lock(batch)),
+            _ => true,
+        }
+    })
+}
+
+fn is_write_status(status: &Status) -> bool {
+    !matches!(status, Status::Match | Status::Empty)
+}
+
+fn get_label_resources(labels: &[String]) -> Vec<LockResource> {
+    if labels.is_empty() {
+        vec![LockResource::Store]
+    } else {
+        labels.iter().map(|label| LockResource::Label(label.clone())).collect()
+    }
+}
+
+fn add_lock(locks: &mut BTreeMap<LockResource, LockMode>, resource: LockResource, mode: LockMode) {
+    let held = locks.entry(resource).or_insert(mode);
+    *held = (*held).max(mode);
+}
+
+fn add_pattern_locks(locks: &mut BTreeMap<LockResource, LockMode>, pattern: &PropertyGraph) {
+    let mut written_nodes = pattern.get_nodes().iter().map(|node| is_write_status(node.get_status())).collect::<Vec<bool>>();
+    for rel in pattern.get_relationships_and_edges() {
+        if is_write_status(rel.relationship.get_status()) {
+            written_nodes[rel.get_source().get_index()] = true;
+            written_nodes[rel.get_target().get_index()] = true;
+        }
+    }
+    for (node, written) in pattern.get_nodes().iter().zip(written_nodes) {
+        let mode = if written { LockMode::Exclusive } else { LockMode::Shared };
+        for resource in get_label_resources(node.get_labels_ref()) {
+            add_lock(locks, resource, mode);
+        }
+    }
+}
+
+fn add_steps_locks(locks: &mut BTreeMap<LockResource, LockMode>, steps: &[QueryStep]) {
+    for step in steps {
+        match step.step_type {
+            StepType::CREATE_CONSTRAINT | StepType::CALL => add_lock(locks, LockResource::Store, LockMode::Exclusive),
+            StepType::UNWIND | StepType::FOREACH => step.unwind_batches.iter().for_each(|batch| add_steps_locks(locks, batch)),
+            _ => step.patterns.iter().for_each(|pattern| add_pattern_locks(locks, pattern)),
+        }
+    }
+}
+
+fn make_request_locks(steps: &[QueryStep]) -> BTreeMap<LockResource, LockMode> {
+    let mut locks = BTreeMap::new();
+    add_steps_locks(&mut locks, steps);
+    if needs_write_lock(steps) {
+        add_lock(&mut locks, LockResource::Store, LockMode::Shared);
+    }
+    locks
+}
+
+/// Locks needed by a request: nodes are locked by label, shared when they are only matched and exclusive
+/// when they are written or when relationships from or to them are.
+/// Writes also lock the whole store shared, so that they wait for the constraints, procedures and writes
+/// of nodes without label, which lock it exclusively, while writes of other labels proceed.
+pub fn get_request_locks(steps: &[QueryStep]) -> Vec<(LockResource, LockMode)> {
+    make_request_locks(steps).into_iter().collect()
+}
+
+/// Locks needed by a request of a transaction at both isolation levels: the requests which don't write take no
+/// lock, at the snapshot isolation level they read the graph engine of the transaction.
+pub fn get_tx_request_locks(steps: &[QueryStep]) -> Vec<(LockResource, LockMode)> {
+    if needs_write_lock(steps) {
+        get_request_locks(steps)
+    } else {
+        Vec::new()
+    }
 }
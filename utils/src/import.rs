@@ -0,0 +1,432 @@
+// MIT License
+//
+// Copyright (c) 2022 Alexandre RICCIARDI
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+
+use zawgl_core::error::GraphError;
+use zawgl_core::graph_engine::GraphEngine;
+use zawgl_core::model::temporal::{Date, DateTime, Duration};
+use zawgl_core::model::{Node, Property, PropertyValue, Relationship};
+
+const LIST_SEPARATOR: char = ';';
+
+#[derive(Debug)]
+pub enum ImportError {
+    Io(io::Error),
+    Header(String),
+    Value { line: usize, column: String, value: String },
+    UnknownNode { line: usize, id: String },
+    Store { line: usize, source: GraphError },
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportError::Io(err) => write!(f, "io error: {}", err),
+            ImportError::Header(msg) => write!(f, "invalid header: {}", msg),
+            ImportError::Value { line, column, value } => write!(f, "line {}: invalid value '{}' for column '{}'", line, value, column),
+            ImportError::UnknownNode { line, id } => write!(f, "line {}: unknown node id '{}'", line, id),
+            ImportError::Store { line, source } => write!(f, "line {}: failed to write record: {}", line, source),
+        }
+    }
+}
+
+impl From<io::Error> for ImportError {
+    fn from(err: io::Error) -> Self {
+        ImportError::Io(err)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ValueType {
+    String,
+    Integer,
+    Float,
+    Boolean,
+    DateTime,
+    Date,
+    Duration,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Column {
+    Id { property: Option<String>, group: String },
+    StartId { group: String },
+    EndId { group: String },
+    Label,
+    Type,
+    Property { name: String, value_type: ValueType, list: bool },
+    Ignore,
+}
+
+fn parse_value_type(name: &str) -> Option<ValueType> {
+    match name.to_lowercase().as_str() {
+        "string" => Some(ValueType::String),
+        "int" | "integer" | "long" | "short" | "byte" => Some(ValueType::Integer),
+        "float" | "double" => Some(ValueType::Float),
+        "bool" | "boolean" => Some(ValueType::Boolean),
+        "datetime" => Some(ValueType::DateTime),
+        "date" => Some(ValueType::Date),
+        "duration" => Some(ValueType::Duration),
+        _ => None,
+    }
+}
+
+fn split_group(field_type: &str) -> (&str, String) {
+    match (field_type.find('('), field_type.ends_with(')')) {
+        (Some(open), true) => (&field_type[..open], field_type[open + 1..field_type.len() - 1].to_string()),
+        _ => (field_type, String::new()),
+    }
+}
+
+/// Parses a header field such as `name:string`, `id:ID(Person)`, `:LABEL` or `scores:int[]`.
+/// A field without type declaration is a string property.
+pub fn parse_column(field: &str) -> Result<Column, ImportError> {
+    let field = field.trim();
+    let (name, field_type) = match field.rfind(':') {
+        Some(pos) => (&field[..pos], &field[pos + 1..]),
+        None => (field, "string"),
+    };
+    let (field_type, group) = split_group(field_type);
+    match field_type {
+        "ID" => Ok(Column::Id { property: if name.is_empty() { None } else { Some(name.to_string()) }, group }),
+        "START_ID" => Ok(Column::StartId { group }),
+        "END_ID" => Ok(Column::EndId { group }),
+        "LABEL" => Ok(Column::Label),
+        "TYPE" => Ok(Column::Type),
+        "IGNORE" => Ok(Column::Ignore),
+        _ => {
+            if name.is_empty() {
+                return Err(ImportError::Header(format!("missing property name in '{}'", field)));
+            }
+            let (type_name, list) = match field_type.strip_suffix("[]") {
+                Some(type_name) => (type_name, true),
+                None => (field_type, false),
+            };
+            let value_type = parse_value_type(type_name).ok_or_else(|| ImportError::Header(format!("unknown type '{}' in '{}'", field_type, field)))?;
+            Ok(Column::Property { name: name.to_string(), value_type, list })
+        }
+    }
+}
+
+pub fn parse_header(fields: &[String]) -> Result<Vec<Column>, ImportError> {
+    fields.iter().map(|field| parse_column(field)).collect()
+}
+
+pub fn parse_value(value_type: ValueType, value: &str) -> Option<PropertyValue> {
+    match value_type {
+        ValueType::String => Some(PropertyValue::PString(value.to_string())),
+        ValueType::Integer => value.trim().parse().ok().map(PropertyValue::PInteger),
+        ValueType::Float => value.trim().parse().ok().map(PropertyValue::PFloat),
+        ValueType::Boolean => match value.trim().to_lowercase().as_str() {
+            "true" => Some(PropertyValue::PBool(true)),
+            "false" => Some(PropertyValue::PBool(false)),
+            _ => None,
+        },
+        ValueType::DateTime => DateTime::parse(value.trim()).map(PropertyValue::PDateTime),
+        ValueType::Date => Date::parse(value.trim()).map(PropertyValue::PDate),
+        ValueType::Duration => Duration::parse(value.trim()).map(PropertyValue::PDuration),
+    }
+}
+
+fn parse_property(value_type: ValueType, list: bool, value: &str) -> Option<PropertyValue> {
+    if list {
+        value.split(LIST_SEPARATOR).map(|item| parse_value(value_type, item)).collect::<Option<Vec<PropertyValue>>>().map(PropertyValue::PList)
+    } else {
+        parse_value(value_type, value)
+    }
+}
+
+/// Reads CSV records, quoted fields may contain delimiters, escaped quotes (`""`) and line breaks.
+pub struct CsvReader<R: BufRead> {
+    reader: R,
+    delimiter: char,
+    line: usize,
+    record_line: usize,
+}
+
+impl <R: BufRead> CsvReader<R> {
+    pub fn new(reader: R, delimiter: char) -> Self {
+        CsvReader { reader, delimiter, line: 0, record_line: 0 }
+    }
+
+    /// Line number of the last record start.
+    pub fn get_line(&self) -> usize {
+        self.record_line
+    }
+
+    pub fn read_record(&mut self) -> io::Result<Option<Vec<String>>> {
+        let mut fields = Vec::new();
+        let mut field = String::new();
+        let mut in_quotes = false;
+        let mut buf = String::new();
+        let mut started = false;
+        loop {
+            buf.clear();
+            if self.reader.read_line(&mut buf)? == 0 {
+                if in_quotes {
+                    return Err(io::Error::new(io::ErrorKind::UnexpectedEof, format!("unterminated quoted field at line {}", self.line)));
+                }
+                if !started {
+                    return Ok(None);
+                }
+                fields.push(field);
+                return Ok(Some(fields));
+            }
+            self.line += 1;
+            if !started {
+                if buf.trim().is_empty() {
+                    continue;
+                }
+                self.record_line = self.line;
+                started = true;
+            }
+            let line = buf.trim_end_matches(['\n', '\r']);
+            let mut chars = line.chars().peekable();
+            while let Some(c) = chars.next() {
+                if in_quotes {
+                    if c == '"' {
+                        if chars.peek() == Some(&'"') {
+                            field.push('"');
+                            chars.next();
+                        } else {
+                            in_quotes = false;
+                        }
+                    } else {
+                        field.push(c);
+                    }
+                } else if c == '"' {
+                    in_quotes = true;
+                } else if c == self.delimiter {
+                    fields.push(std::mem::take(&mut field));
+                } else {
+                    field.push(c);
+                }
+            }
+            if in_quotes {
+                field.push('\n');
+            } else {
+                fields.push(field);
+                return Ok(Some(fields));
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct ImportStats {
+    pub nodes: usize,
+    pub relationships: usize,
+}
+
+/// Writes nodes and relationships straight into the graph engine stores,
+/// syncing them every `batch_size` records.
+pub struct Importer<'a> {
+    graph_engine: &'a mut GraphEngine,
+    batch_size: usize,
+    delimiter: char,
+    pending: usize,
+    ids: HashMap<String, HashMap<String, u64>>,
+    stats: ImportStats,
+}
+
+impl <'a> Importer<'a> {
+    pub fn new(graph_engine: &'a mut GraphEngine, batch_size: usize, delimiter: char) -> Self {
+        Importer { graph_engine, batch_size: batch_size.max(1), delimiter, pending: 0, ids: HashMap::new(), stats: ImportStats::default() }
+    }
+
+    pub fn import_nodes_file(&mut self, path: &str) -> Result<usize, ImportError> {
+        self.import_nodes(BufReader::new(File::open(path)?))
+    }
+
+    pub fn import_relationships_file(&mut self, path: &str) -> Result<usize, ImportError> {
+        self.import_relationships(BufReader::new(File::open(path)?))
+    }
+
+    pub fn import_nodes<R: BufRead>(&mut self, reader: R) -> Result<usize, ImportError> {
+        let mut csv = CsvReader::new(reader, self.delimiter);
+        let columns = match csv.read_record()? {
+            Some(header) => parse_header(&header)?,
+            None => return Ok(0),
+        };
+        if columns.iter().any(|c| matches!(c, Column::StartId{..} | Column::EndId{..} | Column::Type)) {
+            return Err(ImportError::Header("relationship columns in nodes file".to_string()));
+        }
+        let mut count = 0;
+        while let Some(record) = csv.read_record()? {
+            let line = csv.get_line();
+            let mut node = Node::new();
+            let mut properties = Vec::new();
+            let mut node_id = None;
+            for (column, value) in columns.iter().zip(record.iter()) {
+                match column {
+                    Column::Id { property, group } => {
+                        if let Some(name) = property {
+                            properties.push(Property::new(name.clone(), PropertyValue::PString(value.clone())));
+                        }
+                        node_id = Some((group, value));
+                    },
+                    Column::Label => node.get_labels_mut().extend(value.split(LIST_SEPARATOR).filter(|l| !l.is_empty()).map(String::from)),
+                    Column::Property { .. } => properties.extend(make_property(column, value, line)?),
+                    _ => {},
+                }
+            }
+            node.set_properties(properties);
+            let created = self.graph_engine.create_node(&node).map_err(|source| ImportError::Store { line, source })?;
+            if let (Some((group, id)), Some(store_id)) = (node_id, created.get_id()) {
+                self.ids.entry(group.clone()).or_default().insert(id.clone(), store_id);
+            }
+            self.stats.nodes += 1;
+            count += 1;
+            self.record_written();
+        }
+        Ok(count)
+    }
+
+    pub fn import_relationships<R: BufRead>(&mut self, reader: R) -> Result<usize, ImportError> {
+        let mut csv = CsvReader::new(reader, self.delimiter);
+        let columns = match csv.read_record()? {
+            Some(header) => parse_header(&header)?,
+            None => return Ok(0),
+        };
+        if !columns.iter().any(|c| matches!(c, Column::StartId{..})) || !columns.iter().any(|c| matches!(c, Column::EndId{..})) {
+            return Err(ImportError::Header("relationships file requires :START_ID and :END_ID columns".to_string()));
+        }
+        let mut count = 0;
+        while let Some(record) = csv.read_record()? {
+            let line = csv.get_line();
+            let mut rel = Relationship::new();
+            let mut properties = Vec::new();
+            let mut source = None;
+            let mut target = None;
+            for (column, value) in columns.iter().zip(record.iter()) {
+                match column {
+                    Column::StartId { group } => source = Some(self.lookup_node(group, value, line)?),
+                    Column::EndId { group } => target = Some(self.lookup_node(group, value, line)?),
+                    Column::Type => rel.get_labels_mut().extend(value.split(LIST_SEPARATOR).filter(|l| !l.is_empty()).map(String::from)),
+                    Column::Property { .. } => properties.extend(make_property(column, value, line)?),
+                    _ => {},
+                }
+            }
+            let (source, target) = match (source, target) {
+                (Some(source), Some(target)) => (source, target),
+                _ => return Err(ImportError::Value { line, column: ":START_ID/:END_ID".to_string(), value: String::new() }),
+            };
+            rel.set_properties(properties);
+            self.graph_engine.create_relationship(&rel, source, target).map_err(|source| ImportError::Store { line, source })?;
+            self.stats.relationships += 1;
+            count += 1;
+            self.record_written();
+        }
+        Ok(count)
+    }
+
+    fn lookup_node(&self, group: &str, id: &str, line: usize) -> Result<u64, ImportError> {
+        self.ids.get(group).and_then(|ids| ids.get(id)).copied().ok_or_else(|| ImportError::UnknownNode { line, id: id.to_string() })
+    }
+
+    fn record_written(&mut self) {
+        self.pending += 1;
+        if self.pending >= self.batch_size {
+            self.graph_engine.sync();
+            self.pending = 0;
+        }
+    }
+
+    pub fn finish(self) -> ImportStats {
+        self.graph_engine.sync();
+        self.stats
+    }
+}
+
+fn make_property(column: &Column, value: &str, line: usize) -> Result<Option<Property>, ImportError> {
+    if let Column::Property { name, value_type, list } = column {
+        if value.is_empty() {
+            return Ok(None);
+        }
+        let pvalue = parse_property(*value_type, *list, value).ok_or_else(|| ImportError::Value { line, column: name.clone(), value: value.to_string() })?;
+        Ok(Some(Property::new(name.clone(), pvalue)))
+    } else {
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod test_import {
+    use super::*;
+    use zawgl_core::model::init::InitContext;
+    use zawgl_core::test_utils::build_dir_path_and_rm_old;
+    use zawgl_core::graph::traits::*;
+
+    #[test]
+    fn test_parse_header() {
+        let header = ["id:ID(Person)", "name", "age:int", "scores:float[]", ":LABEL", "skip:IGNORE"].iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        let columns = parse_header(&header).unwrap();
+        assert_eq!(columns[0], Column::Id { property: Some("id".to_string()), group: "Person".to_string() });
+        assert_eq!(columns[1], Column::Property { name: "name".to_string(), value_type: ValueType::String, list: false });
+        assert_eq!(columns[2], Column::Property { name: "age".to_string(), value_type: ValueType::Integer, list: false });
+        assert_eq!(columns[3], Column::Property { name: "scores".to_string(), value_type: ValueType::Float, list: true });
+        assert_eq!(columns[4], Column::Label);
+        assert_eq!(columns[5], Column::Ignore);
+        assert!(parse_column("age:bigdecimal").is_err());
+        assert!(parse_column(":int").is_err());
+    }
+
+    #[test]
+    fn test_read_quoted_records() {
+        let data = "a,b,c\n\"x, y\",\"say \"\"hi\"\"\",\"multi\nline\"\n\n1,,3\n";
+        let mut csv = CsvReader::new(data.as_bytes(), ',');
+        assert_eq!(csv.read_record().unwrap(), Some(vec!["a".to_string(), "b".to_string(), "c".to_string()]));
+        assert_eq!(csv.read_record().unwrap(), Some(vec!["x, y".to_string(), "say \"hi\"".to_string(), "multi\nline".to_string()]));
+        assert_eq!(csv.read_record().unwrap(), Some(vec!["1".to_string(), "".to_string(), "3".to_string()]));
+        assert_eq!(csv.get_line(), 5);
+        assert_eq!(csv.read_record().unwrap(), None);
+    }
+
+    #[test]
+    fn test_import_graph() {
+        let main_dir = build_dir_path_and_rm_old("test_csv_import").unwrap();
+        let ctx = InitContext::new(&main_dir).unwrap();
+        let mut graph_engine = GraphEngine::new(&ctx);
+        let nodes = "id:ID,name,age:int,born:date,:LABEL\n1,Alice,32,1990-04-02,Person;Admin\n2,Bob,,1985-01-20,Person\n";
+        let rels = ":START_ID,:END_ID,:TYPE,since:int\n1,2,KNOWS,2010\n2,1,KNOWS,\n";
+        let mut importer = Importer::new(&mut graph_engine, 2, ',');
+        assert_eq!(importer.import_nodes(nodes.as_bytes()).unwrap(), 2);
+        assert_eq!(importer.import_relationships(rels.as_bytes()).unwrap(), 2);
+        assert!(matches!(importer.import_relationships(":START_ID,:END_ID,:TYPE\n1,3,KNOWS\n".as_bytes()), Err(ImportError::UnknownNode { line: 2, .. })));
+        let stats = importer.finish();
+        assert_eq!(stats, ImportStats { nodes: 2, relationships: 2 });
+
+        let mut graph = graph_engine.retrieve_graph().unwrap();
+        assert_eq!(graph.get_nodes_ids().len(), 2);
+        let nodes = graph.get_nodes_ids().into_iter().filter_map(|id| graph.get_node_ref(&id).cloned()).collect::<Vec<Node>>();
+        let out_edges = graph.get_nodes_ids().iter().map(|id| graph.out_edges(id).count()).sum::<usize>();
+        assert_eq!(out_edges, 2);
+        let alice = nodes.iter().find(|n| n.get_labels_ref().len() == 2).unwrap();
+        assert_eq!(alice.get_labels_ref(), &vec!["Person".to_string(), "Admin".to_string()]);
+        assert_eq!(alice.get_properties_ref().len(), 4);
+        assert_eq!(alice.get_properties_ref()[2].get_value(), &PropertyValue::PInteger(32));
+    }
+}
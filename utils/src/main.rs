@@ -19,19 +19,307 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
+mod import;
+
 use std::collections::HashSet;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::process;
 
 use zawgl_core::graph::traits::GrowableGraphTrait;
 use zawgl_core::graph_engine::model::{GraphProxy, ProxyNodeId};
 use zawgl_core::model::init::InitContext;
-use zawgl_core::graph_engine::GraphEngine;
+use zawgl_core::graph_engine::{GraphEngine, rebuild_indexes};
+use zawgl_core::graph_engine::dump::{dump_database, restore_database};
+use zawgl_core::graph_engine::export::{export_cypher, export_graphml};
+use zawgl_core::graph_engine::compact::{compact_database, CompactionReport};
 use zawgl_core::graph::traits::*;
 
+use import::Importer;
+
+const DEFAULT_DB_DIR: &str = "zawgl-db";
+const DEFAULT_BATCH_SIZE: usize = 10000;
+
+const USAGE: &str = "usage:
+    zawgl-utils [print] [<db-dir>] [--page-size <n>]
+    zawgl-utils dump <db-dir> <dump-file>
+    zawgl-utils restore <db-dir> <dump-file>
+    zawgl-utils export <db-dir> (graphml|cypher) <output-file>
+    zawgl-utils reindex <db-dir>
+    zawgl-utils check-index <db-dir> [--rebuild]
+    zawgl-utils dbcheck <db-dir>
+    zawgl-utils compact <db-dir> [--relocations <output-file>]
+    zawgl-utils import [--db <db-dir>] [--batch-size <n>] [--delimiter <c>] (--nodes <file.csv>)... (--relationships <file.csv>)...";
+
 fn main() {
-    //let main_dir = get_tmp_dir_path("zawgl-db");
-    let main_dir = "zawgl-db";
+    let args = std::env::args().skip(1).collect::<Vec<String>>();
+    match args.first().map(|arg| arg.as_str()) {
+        Some("import") => import(&args[1..]),
+        Some("dump") => dump(&args[1..]),
+        Some("restore") => restore(&args[1..]),
+        Some("export") => export(&args[1..]),
+        Some("reindex") => reindex(&args[1..]),
+        Some("check-index") => check_index(&args[1..]),
+        Some("dbcheck") => dbcheck(&args[1..]),
+        Some("compact") => compact(&args[1..]),
+        Some("print") => print_args(&args[1..]),
+        Some("-h") | Some("--help") => println!("{}", USAGE),
+        Some(dir) => print(dir),
+        None => print(DEFAULT_DB_DIR),
+    }
+}
+
+fn exit_with_usage(msg: &str) -> ! {
+    eprintln!("{}\n{}", msg, USAGE);
+    process::exit(2);
+}
+
+fn import(args: &[String]) {
+    let mut main_dir = DEFAULT_DB_DIR.to_string();
+    let mut batch_size = DEFAULT_BATCH_SIZE;
+    let mut delimiter = ',';
+    let mut nodes_files = Vec::new();
+    let mut relationships_files = Vec::new();
+    let mut it = args.iter();
+    while let Some(arg) = it.next() {
+        let value = it.next().unwrap_or_else(|| exit_with_usage(&format!("missing value for {}", arg)));
+        match arg.as_str() {
+            "--db" => main_dir = value.clone(),
+            "--batch-size" => batch_size = value.parse().unwrap_or_else(|_| exit_with_usage(&format!("invalid batch size {}", value))),
+            "--delimiter" => {
+                let mut chars = value.chars();
+                delimiter = match (chars.next(), chars.next()) {
+                    (Some(c), None) => c,
+                    _ => exit_with_usage(&format!("invalid delimiter {}", value)),
+                };
+            },
+            "--nodes" => nodes_files.push(value.clone()),
+            "--relationships" => relationships_files.push(value.clone()),
+            _ => exit_with_usage(&format!("unknown option {}", arg)),
+        }
+    }
+    if nodes_files.is_empty() {
+        exit_with_usage("at least one nodes file is required");
+    }
     let conf = InitContext::new(&main_dir).expect("can't create context");
     let mut graph_engine = GraphEngine::new(&conf);
+    let mut importer = Importer::new(&mut graph_engine, batch_size, delimiter);
+    for file in &nodes_files {
+        match importer.import_nodes_file(file) {
+            Ok(count) => println!("{}: {} nodes", file, count),
+            Err(err) => {
+                eprintln!("{}: {}", file, err);
+                importer.finish();
+                process::exit(1);
+            }
+        }
+    }
+    for file in &relationships_files {
+        match importer.import_relationships_file(file) {
+            Ok(count) => println!("{}: {} relationships", file, count),
+            Err(err) => {
+                eprintln!("{}: {}", file, err);
+                importer.finish();
+                process::exit(1);
+            }
+        }
+    }
+    let stats = importer.finish();
+    println!("imported {} nodes and {} relationships into {}", stats.nodes, stats.relationships, main_dir);
+}
+
+fn dump_args(args: &[String]) -> (&str, &str) {
+    match args {
+        [main_dir, file] => (main_dir, file),
+        _ => exit_with_usage("expected a database directory and a dump file"),
+    }
+}
+
+fn dump(args: &[String]) {
+    let (main_dir, file) = dump_args(args);
+    let conf = InitContext::new(main_dir).expect("can't create context");
+    match dump_database(&conf, file) {
+        Ok(stats) => println!("dumped {} constraints, {} nodes and {} relationships to {}", stats.constraints, stats.nodes, stats.relationships, file),
+        Err(err) => {
+            eprintln!("{}: {}", file, err);
+            process::exit(1);
+        }
+    }
+}
+
+fn restore(args: &[String]) {
+    let (main_dir, file) = dump_args(args);
+    let conf = InitContext::new(main_dir).expect("can't create context");
+    match restore_database(&conf, file) {
+        Ok(stats) => println!("restored {} constraints, {} nodes and {} relationships into {}", stats.constraints, stats.nodes, stats.relationships, main_dir),
+        Err(err) => {
+            eprintln!("{}: {}", file, err);
+            process::exit(1);
+        }
+    }
+}
+
+fn export(args: &[String]) {
+    let (main_dir, format, file) = match args {
+        [main_dir, format, file] => (main_dir, format.as_str(), file),
+        _ => exit_with_usage("expected a database directory, an export format and an output file"),
+    };
+    let conf = InitContext::new(main_dir).expect("can't create context");
+    let mut graph_engine = GraphEngine::new(&conf);
+    let res = File::create(file).and_then(|f| {
+        let mut writer = BufWriter::new(f);
+        match format {
+            "graphml" => export_graphml(&mut graph_engine, &mut writer),
+            "cypher" => export_cypher(&mut graph_engine, &mut writer),
+            _ => exit_with_usage(&format!("unknown export format {}", format)),
+        }
+    });
+    if let Err(err) = res {
+        eprintln!("{}: {}", file, err);
+        process::exit(1);
+    }
+}
+
+fn reindex(args: &[String]) {
+    let main_dir = match args {
+        [main_dir] => main_dir,
+        _ => exit_with_usage("expected a database directory"),
+    };
+    let conf = InitContext::new(main_dir).expect("can't create context");
+    match rebuild_indexes(&conf) {
+        Ok(graph_engine) => {
+            let statistics = graph_engine.get_statistics();
+            println!("reindexed {} nodes and {} relationships in {}", statistics.get_nodes_count(), statistics.get_relationships_count(), main_dir);
+        },
+        Err(err) => {
+            eprintln!("{}: {}", main_dir, err);
+            process::exit(1);
+        }
+    }
+}
+
+fn check_index(args: &[String]) {
+    let (main_dir, rebuild) = match args {
+        [main_dir] => (main_dir, false),
+        [main_dir, flag] if flag == "--rebuild" => (main_dir, true),
+        _ => exit_with_usage("expected a database directory and an optional --rebuild flag"),
+    };
+    let conf = InitContext::new(main_dir).expect("can't create context");
+    let reports = GraphEngine::new(&conf).check_indexes();
+    for report in &reports {
+        println!("{}: {} nodes, {} keys, {} data pointers, {} errors", report.name, report.nodes, report.keys, report.data_ptrs, report.errors.len());
+        for err in &report.errors {
+            println!("    {}", err);
+        }
+    }
+    if reports.iter().all(|report| report.is_consistent()) {
+        return;
+    }
+    if !rebuild {
+        eprintln!("{}: corrupted indexes, run with --rebuild to rebuild them from the stores", main_dir);
+        process::exit(1);
+    }
+    reindex(&args[..1]);
+}
+
+fn dbcheck(args: &[String]) {
+    let main_dir = match args {
+        [main_dir] => main_dir,
+        _ => exit_with_usage("expected a database directory"),
+    };
+    let conf = InitContext::new(main_dir).expect("can't create context");
+    let report = GraphEngine::new(&conf).check_stores();
+    println!("nodes: {} records", report.nodes);
+    println!("relationships: {} records", report.relationships);
+    println!("properties: {} records, {} unreferenced", report.properties, report.unreferenced_properties);
+    println!("dynamic records: {} referenced by properties", report.dynamic_records);
+    for err in &report.errors {
+        println!("    {}", err);
+    }
+    if !report.is_consistent() {
+        eprintln!("{}: {} errors found", main_dir, report.errors.len());
+        process::exit(1);
+    }
+}
+
+fn write_relocations(report: &CompactionReport, file: &str) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(file)?);
+    writeln!(writer, "kind,old_id,new_id")?;
+    for (kind, relocations) in [("node", &report.nodes_relocations), ("relationship", &report.relationships_relocations)] {
+        let mut ids = relocations.iter().collect::<Vec<(&u64, &u64)>>();
+        ids.sort();
+        for (old_id, new_id) in ids {
+            writeln!(writer, "{},{},{}", kind, old_id, new_id)?;
+        }
+    }
+    writer.flush()
+}
+
+fn compact(args: &[String]) {
+    let (main_dir, relocations_file) = match args {
+        [main_dir] => (main_dir, None),
+        [main_dir, flag, file] if flag == "--relocations" => (main_dir, Some(file)),
+        _ => exit_with_usage("expected a database directory and an optional --relocations output file"),
+    };
+    let conf = InitContext::new(main_dir).expect("can't create context");
+    let report = compact_database(&conf).unwrap_or_else(|err| {
+        eprintln!("{}: {}", main_dir, err);
+        process::exit(1);
+    });
+    println!("compacted {} nodes and {} relationships in {}: {} bytes before, {} bytes after", report.nodes, report.relationships, main_dir, report.bytes_before, report.bytes_after);
+    if let Some(file) = relocations_file {
+        if let Err(err) = write_relocations(&report, file) {
+            eprintln!("{}: {}", file, err);
+            process::exit(1);
+        }
+    }
+}
+
+fn print_args(args: &[String]) {
+    let mut main_dir = DEFAULT_DB_DIR;
+    let mut page_size = None;
+    let mut it = args.iter();
+    while let Some(arg) = it.next() {
+        match arg.as_str() {
+            "--page-size" => {
+                let value = it.next().unwrap_or_else(|| exit_with_usage("missing value for --page-size"));
+                match value.parse::<usize>() {
+                    Ok(size) if size > 0 => page_size = Some(size),
+                    _ => exit_with_usage(&format!("invalid page size {}", value)),
+                }
+            },
+            dir => main_dir = dir,
+        }
+    }
+    match page_size {
+        Some(size) => print_pages(main_dir, size),
+        None => print(main_dir),
+    }
+}
+
+fn print_pages(main_dir: &str, page_size: usize) {
+    let conf = InitContext::new(main_dir).expect("can't create context");
+    let mut graph_engine = GraphEngine::new(&conf);
+    let mut cursor = None;
+    loop {
+        let page = graph_engine.retrieve_graph_page(cursor, page_size).expect("can't retrieve graph page");
+        for node in page.get_nodes() {
+            println!("{:?}", node);
+        }
+        for rel in page.get_relationships() {
+            println!("{:?}-[{:?}:{:?}]->{:?}", rel.get_source_id(), rel.get_relationship().get_id().unwrap(),
+                rel.get_relationship().get_labels_ref().join(":"), rel.get_target_id());
+        }
+        cursor = page.get_next_cursor();
+        if cursor.is_none() {
+            break;
+        }
+    }
+}
+
+fn print(main_dir: &str) {
+    let conf = InitContext::new(main_dir).expect("can't create context");
+    let mut graph_engine = GraphEngine::new(&conf);
     let mut full_graph = graph_engine.retrieve_graph().unwrap();
     println!("{:?}", full_graph.get_nodes_ids());
     depth_first_search(&mut full_graph);
@@ -23,21 +23,77 @@ extern crate zawgl_server;
 extern crate tokio;
 extern crate serde;
 mod settings;
-use log::info;
-use zawgl_core::model::init::InitContext;
+use log::{info, LevelFilter};
+use zawgl_core::model::init::{InitContext, PropertyCompression, ReloadableSettings};
 use settings::Settings;
+use zawgl_server::ServerBuilder;
 use simple_logger::SimpleLogger;
 
-#[tokio::main]
-async fn main() {
+/// Reads the settings file again, the log level is applied here and the other settings by the server.
+fn reload_settings() -> Result<ReloadableSettings, String> {
+    let settings = Settings::new().map_err(|err| err.to_string())?;
+    log::set_max_level(settings.get_log_level());
+    Ok(settings.get_reloadable_settings())
+}
+
+fn main() {
     let settings = Settings::new().expect("config can't be loaded");
-    let log_level = settings.get_log_level();
-    SimpleLogger::new().with_level(log_level).init().unwrap();
-    let ctx = InitContext::new(&settings.server.database_dir).expect("can't create database context");
-    tokio::select! {
-        _ = zawgl_server::run_server(&settings.server.address, ctx, || {
-            info!("database started");
-        }) => 0,
-        _ = tokio::signal::ctrl_c() => 0
-    };
+    let mut runtime = tokio::runtime::Builder::new_multi_thread();
+    if let Some(blocking_threads) = settings.server.blocking_threads {
+        runtime.max_blocking_threads(blocking_threads);
+    }
+    runtime.enable_all().build().expect("can't start runtime").block_on(run(settings));
+}
+
+async fn run(settings: Settings) {
+    // the max level filters the records so that the log level can be changed on reload
+    SimpleLogger::new().with_level(LevelFilter::Trace).init().unwrap();
+    log::set_max_level(settings.get_log_level());
+    let mut ctx = InitContext::new(&settings.server.database_dir).expect("can't create database context");
+    if let Some(page_cache_size) = settings.server.page_cache_size {
+        ctx = ctx.with_page_cache_size(page_cache_size);
+    }
+    if let Some(segment_size) = settings.server.segment_size {
+        ctx = ctx.with_segment_size(segment_size);
+    }
+    if let Some(threshold) = settings.server.property_compression_threshold {
+        ctx = ctx.with_property_compression(PropertyCompression::Zstd{threshold});
+    }
+    if let Some(io_mode) = settings.get_io_mode() {
+        ctx = ctx.with_io_mode(io_mode);
+    }
+    if let Some(durability) = settings.get_durability() {
+        ctx = ctx.with_durability(durability);
+    }
+    ctx = ctx.with_query_log(settings.get_query_log());
+    ctx = ctx.with_change_data_capture(settings.server.change_data_capture.unwrap_or(false));
+    ctx = ctx.with_element_uuids(settings.server.element_uuids.unwrap_or(false));
+    let mut builder = ServerBuilder::new().address(&settings.server.address).tx_timeout(settings.get_tx_timeout())
+        .settings_loader(reload_settings);
+    if let Some(max_connections) = settings.server.max_connections {
+        builder = builder.max_connections(max_connections);
+    }
+    if let Some(max_concurrent_queries) = settings.server.max_concurrent_queries {
+        builder = builder.max_concurrent_queries(max_concurrent_queries);
+    }
+    if let Some(max_queued_queries) = settings.server.max_queued_queries {
+        builder = builder.max_queued_queries(max_queued_queries);
+    }
+    if let Some(query_memory_limit) = settings.server.query_memory_limit {
+        builder = builder.query_memory_limit(query_memory_limit);
+    }
+    if let Some(bolt_port) = settings.server.bolt_port {
+        builder = builder.bolt_port(bolt_port);
+    }
+    if let Some(http_port) = settings.server.http_port {
+        builder = builder.http_port(http_port);
+    }
+    if let Some(tls) = &settings.server.tls {
+        builder = builder.tls(&tls.cert_file, &tls.key_file);
+    }
+    let server = builder.build(ctx).expect("can't configure server");
+    server.run(|| {
+        info!("database started");
+    }).await.expect("server error");
+    info!("database stopped");
 }
@@ -21,22 +21,51 @@
 
 use std::path::Path;
 use std::env;
+use std::time::Duration;
 
 use config::{Config, ConfigError};
 use serde::Deserialize;
 use log::*;
+use zawgl_core::model::init::{Durability, IoMode, QueryLog, ReloadableSettings};
+use zawgl_tx_handler::tx_handler::DEFAULT_TX_TIMEOUT_SECS;
 
 const CONFIG_FILE_PATH: &str = ".config/Settings";
+const DEFAULT_DURABILITY_INTERVAL_MS: u64 = 1000;
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct Log {
     pub level: String,
 }
 
+#[derive(Debug, Deserialize, Clone)]
+pub struct Tls {
+    pub cert_file: String,
+    pub key_file: String,
+}
+
 #[derive(Debug, Deserialize, Clone)]
 pub struct Server {
     pub address: String,
     pub database_dir: String,
+    pub tx_timeout_secs: Option<u64>,
+    pub max_connections: Option<usize>,
+    pub blocking_threads: Option<usize>,
+    pub max_concurrent_queries: Option<usize>,
+    pub max_queued_queries: Option<usize>,
+    pub query_memory_limit: Option<usize>,
+    pub bolt_port: Option<u16>,
+    pub http_port: Option<u16>,
+    pub page_cache_size: Option<usize>,
+    pub segment_size: Option<u64>,
+    pub io_mode: Option<String>,
+    pub property_compression_threshold: Option<usize>,
+    pub durability: Option<String>,
+    pub durability_interval_ms: Option<u64>,
+    pub slow_query_threshold_ms: Option<u64>,
+    pub redact_query_parameters: Option<bool>,
+    pub change_data_capture: Option<bool>,
+    pub element_uuids: Option<bool>,
+    pub tls: Option<Tls>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -52,9 +81,50 @@ impl Settings {
         let config_file_path = Path::new(CONFIG_FILE_PATH);
         current_dir.push(config_file_path);
         s.add_source(config::File::with_name(current_dir.as_path().to_str().expect("config file path")))
-        .build().expect("settings").try_deserialize()
+        .build()?.try_deserialize()
+    }
+
+
+    pub fn get_tx_timeout(&self) -> Duration {
+        Duration::from_secs(self.server.tx_timeout_secs.unwrap_or(DEFAULT_TX_TIMEOUT_SECS))
     }
 
+    pub fn get_durability(&self) -> Option<Durability> {
+        let durability = match self.server.durability.as_deref()? {
+            "always" => Durability::Always,
+            "on-commit" => Durability::OnCommit,
+            "periodic" => Durability::Periodic(Duration::from_millis(self.server.durability_interval_ms.unwrap_or(DEFAULT_DURABILITY_INTERVAL_MS))),
+            other => {
+                warn!("unknown durability setting {}, defaulting to always", other);
+                Durability::Always
+            }
+        };
+        Some(durability)
+    }
+
+    pub fn get_io_mode(&self) -> Option<IoMode> {
+        let io_mode = match self.server.io_mode.as_deref()? {
+            "read-write" => IoMode::ReadWrite,
+            "mmap" => IoMode::Mmap,
+            other => {
+                warn!("unknown io mode setting {}, defaulting to read-write", other);
+                IoMode::ReadWrite
+            }
+        };
+        Some(io_mode)
+    }
+
+    pub fn get_query_log(&self) -> QueryLog {
+        QueryLog{
+            slow_query_threshold: self.server.slow_query_threshold_ms.map(Duration::from_millis),
+            redact_parameters: self.server.redact_query_parameters.unwrap_or(false),
+        }
+    }
+
+    /// Settings applied to the running server on SIGHUP or on a reload request.
+    pub fn get_reloadable_settings(&self) -> ReloadableSettings {
+        ReloadableSettings{page_cache_size: self.server.page_cache_size.unwrap_or(ReloadableSettings::default().page_cache_size), query_log: self.get_query_log()}
+    }
 
     pub fn get_log_level(&self) -> LevelFilter {
         let log_level = match self.log.level.as_str() {